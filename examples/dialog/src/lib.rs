@@ -0,0 +1,95 @@
+use seed::{prelude::*, *};
+
+// Model
+
+#[derive(Default)]
+struct Model {
+    confirm_open: bool,
+    menu_open: bool,
+    last_action: Option<String>,
+}
+
+// Update
+
+#[derive(Clone)]
+enum Msg {
+    Delete,
+    ConfirmClosed(Option<String>),
+    ToggleMenu,
+    MenuToggled(bool),
+    MenuItemClicked(&'static str),
+}
+
+fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
+    match msg {
+        Msg::Delete => model.confirm_open = true,
+        // `Esc` closes the dialog without `returnValue`.
+        Msg::ConfirmClosed(return_value) => {
+            model.confirm_open = false;
+            model.last_action = Some(match return_value.as_ref().map(String::as_str) {
+                Some("delete") => "Deleted.".to_owned(),
+                _ => "Cancelled.".to_owned(),
+            });
+        }
+        Msg::ToggleMenu => model.menu_open = !model.menu_open,
+        Msg::MenuToggled(open) => model.menu_open = open,
+        Msg::MenuItemClicked(item) => {
+            model.menu_open = false;
+            model.last_action = Some(format!("{} clicked.", item));
+        }
+    }
+}
+
+// View
+
+fn view(model: &Model) -> impl View<Msg> {
+    div![
+        h1!["Dialog example"],
+        button![ev(Ev::Click, |_| Msg::Delete), "Delete"],
+        button![ev(Ev::Click, |_| Msg::ToggleMenu), "Menu"],
+        // Buttons with `popovertarget` work without messages.
+        button![
+            attrs! {At::from("popovertarget") => "menu"},
+            "Menu (popovertarget)"
+        ],
+        model
+            .last_action
+            .as_ref()
+            .map_or(empty![], |action| p![action]),
+        view_confirm_dialog(model.confirm_open),
+        view_menu(model.menu_open),
+    ]
+}
+
+fn view_confirm_dialog(open: bool) -> Node<Msg> {
+    dialog![
+        dialog_open(open),
+        close_ev(Msg::ConfirmClosed),
+        p!["Do you really want to delete it?"],
+        form![
+            attrs! {At::Method => "dialog"},
+            button![attrs! {At::Value => "delete"}, "Delete"],
+            button![attrs! {At::Value => "cancel"}, "Cancel"],
+        ],
+    ]
+}
+
+fn view_menu(open: bool) -> Node<Msg> {
+    ul![
+        attrs! {At::Id => "menu", At::from("popover") => "auto"},
+        popover_open(open),
+        popover_toggle_ev(Msg::MenuToggled),
+        ["Rename", "Duplicate", "Archive"].iter().map(|item| {
+            let item = *item;
+            li![button![
+                ev(Ev::Click, move |_| Msg::MenuItemClicked(item)),
+                item
+            ]]
+        }),
+    ]
+}
+
+#[wasm_bindgen(start)]
+pub fn render() {
+    App::builder(update, view).build_and_start();
+}
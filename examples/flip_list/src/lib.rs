@@ -0,0 +1,115 @@
+use seed::{prelude::*, *};
+
+const ITEM_COUNT: u32 = 100;
+const COLUMNS: u32 = 10;
+
+// Model
+
+struct Model {
+    items: Vec<u32>,
+    next_item: u32,
+    // Seed of the pseudo-random shuffling.
+    seed: u32,
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Self {
+            items: (0..ITEM_COUNT).collect(),
+            next_item: ITEM_COUNT,
+            seed: 42,
+        }
+    }
+}
+
+impl Model {
+    /// Linear congruential generator - good enough for shuffling.
+    fn random(&mut self, max: u32) -> u32 {
+        self.seed = self.seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        (self.seed >> 16) % max
+    }
+}
+
+// Update
+
+#[derive(Clone, Copy)]
+enum Msg {
+    Shuffle,
+    Sort,
+    Add,
+    Remove(u32),
+}
+
+fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
+    match msg {
+        Msg::Shuffle => {
+            // Fisher-Yates
+            for index in (1..model.items.len()).rev() {
+                #[allow(clippy::cast_possible_truncation)]
+                let other = model.random(index as u32 + 1) as usize;
+                model.items.swap(index, other);
+            }
+        }
+        Msg::Sort => model.items.sort(),
+        Msg::Add => {
+            #[allow(clippy::cast_possible_truncation)]
+            let index = model.random(model.items.len() as u32 + 1) as usize;
+            model.items.insert(index, model.next_item);
+            model.next_item += 1;
+        }
+        Msg::Remove(item) => model.items.retain(|other| *other != item),
+    }
+}
+
+// View
+
+fn view(model: &Model) -> impl View<Msg> {
+    div![
+        style! {St::MaxWidth => px(600); St::Margin => "0 auto"},
+        div![
+            button![ev(Ev::Click, |_| Msg::Shuffle), "Shuffle"],
+            button![ev(Ev::Click, |_| Msg::Sort), "Sort"],
+            button![ev(Ev::Click, |_| Msg::Add), "Add"],
+            " Click an item to remove it.",
+        ],
+        ul![
+            style! {
+                St::Display => "grid";
+                St::GridTemplateColumns => format!("repeat({}, 1fr)", COLUMNS);
+                St::Gap => px(6);
+                St::Padding => px(0);
+                St::ListStyle => "none";
+            },
+            keyed_nodes(&model.items, |item| *item, view_item),
+        ],
+    ]
+}
+
+fn view_item(item: &u32) -> Node<Msg> {
+    let item = *item;
+    li![
+        // Only `transform` and `opacity` are animated - the browser composites them
+        // without layouts, so even 100 moving items run at 60fps.
+        flip_group("items")
+            .duration(400)
+            .easing("cubic-bezier(0.2, 0, 0, 1)")
+            .enter(FlipEffect::Scale)
+            .exit(FlipEffect::Scale),
+        style! {
+            St::Height => px(48);
+            St::LineHeight => px(48);
+            St::TextAlign => "center";
+            St::BorderRadius => px(6);
+            St::Cursor => "pointer";
+            St::Color => "white";
+            St::BackgroundColor => format!("hsl({}, 60%, 50%)", item * 360 / ITEM_COUNT % 360);
+        },
+        ev(Ev::Click, move |_| Msg::Remove(item)),
+        item.to_string(),
+    ]
+}
+
+#[wasm_bindgen(start)]
+pub fn render() {
+    App::builder(update, view).build_and_start();
+}
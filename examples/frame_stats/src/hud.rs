@@ -0,0 +1,29 @@
+//! Dev HUD overlay with frame-rate statistics. Render it anywhere in your view
+//! and refresh the snapshot periodically by `orders.frame_stats()`.
+
+use seed::{prelude::*, *};
+
+pub fn view<Ms: 'static>(stats: &FrameStatsSnapshot) -> Node<Ms> {
+    let color = if stats.long_frames > 0 {
+        "orangered"
+    } else {
+        "limegreen"
+    };
+    div![
+        style! {
+            St::Position => "fixed",
+            St::Top => px(8),
+            St::Right => px(8),
+            St::Padding => "6px 10px",
+            St::BackgroundColor => "rgba(0, 0, 0, 0.75)",
+            St::Color => color,
+            St::FontFamily => "monospace",
+            St::FontSize => px(12),
+            St::PointerEvents => "none",
+        },
+        div![format!("{:.0} FPS", stats.avg_fps)],
+        div![format!("p95 {:.1} ms", stats.p95_ms)],
+        div![format!("worst {:.1} ms", stats.worst_ms)],
+        div![format!("long {} / {}", stats.long_frames, stats.frames)],
+    ]
+}
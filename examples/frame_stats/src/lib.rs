@@ -0,0 +1,90 @@
+use seed::{prelude::*, *};
+
+mod hud;
+
+// Model
+
+#[derive(Default)]
+struct Model {
+    angle: f64,
+    // Artificial work per frame in milliseconds.
+    workload_ms: u32,
+    frame_stats: FrameStatsSnapshot,
+    long_frames: Vec<f64>,
+    // Long frames are reported while the handle is alive.
+    long_frames_sub: Option<SubHandle>,
+}
+
+// AfterMount
+
+fn after_mount(_: Url, orders: &mut impl Orders<Msg>) -> AfterMount<Model> {
+    let long_frames_sub = orders.subscribe_long_frames(Msg::LongFrame);
+    orders
+        .stream(streams::interval(500, || Msg::RefreshHud))
+        .after_next_render(Msg::Rendered);
+    AfterMount::new(Model {
+        long_frames_sub: Some(long_frames_sub),
+        ..Model::default()
+    })
+}
+
+// Update
+
+enum Msg {
+    Rendered(RenderInfo),
+    RefreshHud,
+    LongFrame(f64),
+    SetWorkload(u32),
+}
+
+fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+    match msg {
+        Msg::Rendered(info) => {
+            let delta = f64::from(info.timestamp_delta.unwrap_or_default());
+            model.angle = (model.angle + delta * 0.18) % 360.;
+            busy_wait(model.workload_ms);
+            orders.after_next_render(Msg::Rendered);
+        }
+        Msg::RefreshHud => model.frame_stats = orders.frame_stats(),
+        Msg::LongFrame(frame_ms) => {
+            log!("Long frame", frame_ms);
+            model.long_frames.push(frame_ms);
+        }
+        Msg::SetWorkload(workload_ms) => model.workload_ms = workload_ms,
+    }
+}
+
+fn busy_wait(ms: u32) {
+    let start = js_sys::Date::now();
+    while js_sys::Date::now() - start < f64::from(ms) {}
+}
+
+// View
+
+fn view(model: &Model) -> impl View<Msg> {
+    div![
+        hud::view(&model.frame_stats),
+        div![style! {
+            St::Width => px(80),
+            St::Height => px(80),
+            St::Margin => px(60),
+            St::BackgroundColor => "royalblue",
+            St::Transform => format!("rotate({}deg)", model.angle),
+        }],
+        div![[0, 20, 80].iter().map(|&workload_ms| {
+            button![
+                attrs! {At::Disabled => (model.workload_ms == workload_ms).as_at_value()},
+                ev(Ev::Click, move |_| Msg::SetWorkload(workload_ms)),
+                format!("{} ms work per frame", workload_ms)
+            ]
+        })],
+        p![format!("Reported long frames: {}", model.long_frames.len())],
+    ]
+}
+
+#[wasm_bindgen(start)]
+pub fn render() {
+    App::builder(update, view)
+        .after_mount(after_mount)
+        .build_and_start();
+}
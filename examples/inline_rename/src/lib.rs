@@ -0,0 +1,126 @@
+use seed::{prelude::*, *};
+
+// Model
+
+type FileId = u32;
+
+struct File {
+    id: FileId,
+    name: String,
+    // The name before editing - `Esc` restores it.
+    original_name: String,
+}
+
+struct Model {
+    files: Vec<File>,
+    note: EditableContent,
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        let files = ["notes.txt", "budget.ods", "holiday.jpg"]
+            .iter()
+            .zip(0..)
+            .map(|(&name, id)| File {
+                id,
+                name: name.to_owned(),
+                original_name: name.to_owned(),
+            })
+            .collect();
+        Self {
+            files,
+            note: EditableContent::default(),
+        }
+    }
+}
+
+// Update
+
+enum Msg {
+    Renamed(FileId, String),
+    KeyPressed(FileId, String),
+    RenameConfirmed(FileId),
+    NoteEdited(EditableContent),
+}
+
+fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+    match msg {
+        Msg::Renamed(id, name) => {
+            if let Some(file) = file_mut(model, id) {
+                file.name = name;
+            }
+        }
+        Msg::KeyPressed(id, key) => match key.as_str() {
+            "Enter" => update(Msg::RenameConfirmed(id), model, orders),
+            "Escape" => {
+                if let Some(file) = file_mut(model, id) {
+                    file.name = file.original_name.clone();
+                }
+            }
+            _ => (),
+        },
+        Msg::RenameConfirmed(id) => {
+            if let Some(file) = file_mut(model, id) {
+                file.name = file.name.trim().to_owned();
+                file.original_name = file.name.clone();
+            }
+        }
+        Msg::NoteEdited(note) => model.note = note,
+    }
+}
+
+fn file_mut(model: &mut Model, id: FileId) -> Option<&mut File> {
+    model.files.iter_mut().find(|file| file.id == id)
+}
+
+// View
+
+fn view(model: &Model) -> impl View<Msg> {
+    div![
+        h1!["Files"],
+        ul![keyed_nodes(&model.files, |file| file.id, view_file)],
+        h2!["Note"],
+        div![
+            attrs! {At::ContentEditable => true.as_at_value()},
+            style! {
+                St::Border => "1px solid gray",
+                St::MinHeight => "3em",
+                St::Padding => "0.5em",
+                St::WhiteSpace => "pre-wrap",
+            },
+            content_editable_ev(Msg::NoteEdited),
+            model.note.text,
+        ],
+        h3!["Sanitized HTML"],
+        pre![model.note.sanitized_html],
+    ]
+}
+
+fn view_file(file: &File) -> Node<Msg> {
+    let id = file.id;
+    li![
+        span![
+            attrs! {At::ContentEditable => true.as_at_value()},
+            content_editable_ev(move |content| Msg::Renamed(id, content.text)),
+            keyboard_ev(Ev::KeyDown, move |event| {
+                if event.key() == "Enter" {
+                    // Don't insert a line break.
+                    event.prevent_default();
+                }
+                Msg::KeyPressed(id, event.key())
+            }),
+            ev(Ev::Blur, move |_| Msg::RenameConfirmed(id)),
+            file.name,
+        ],
+        if file.name == file.original_name {
+            empty![]
+        } else {
+            span![" (unsaved)"]
+        },
+    ]
+}
+
+#[wasm_bindgen(start)]
+pub fn render() {
+    App::builder(update, view).build_and_start();
+}
@@ -0,0 +1,89 @@
+use seed::{prelude::*, *};
+
+const LOCALES: &[(&str, &str)] = &[("en", "English"), ("de", "Deutsch"), ("cs", "Čeština")];
+
+// Model
+
+struct Model {
+    clicks: u32,
+    // The locale subscription is active while the handle is stored in the model.
+    _locale_sub: SubHandle,
+    last_change: Option<Locale>,
+}
+
+// AfterMount
+
+fn after_mount(_: Url, orders: &mut impl Orders<Msg>) -> AfterMount<Model> {
+    AfterMount::new(Model {
+        clicks: 0,
+        _locale_sub: orders.subscribe(|LocaleChanged(locale)| Msg::LocaleChanged(locale)),
+        last_change: None,
+    })
+}
+
+// Update
+
+enum Msg {
+    Click,
+    LocaleSelected(&'static str),
+    LocaleChanged(Locale),
+}
+
+fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+    match msg {
+        Msg::Click => model.clicks += 1,
+        // The model doesn't change, but the page is rerendered with the new locale.
+        Msg::LocaleSelected(tag) => {
+            orders.set_locale(tag).skip();
+        }
+        Msg::LocaleChanged(locale) => model.last_change = Some(locale),
+    }
+}
+
+// View
+
+fn t(key: &str) -> &'static str {
+    match (locale::current().as_str(), key) {
+        ("de", "title") => "Sprachbeispiel",
+        ("de", "clicks") => "Klicks",
+        ("de", "click") => "Klick mich",
+        ("de", "changed") => "Sprache geändert",
+        ("cs", "title") => "Ukázka jazyků",
+        ("cs", "clicks") => "Kliknutí",
+        ("cs", "click") => "Klikni na mě",
+        ("cs", "changed") => "Jazyk změněn",
+        (_, "title") => "Locale example",
+        (_, "clicks") => "Clicks",
+        (_, "click") => "Click me",
+        (_, _) => "Locale changed",
+    }
+}
+
+fn view(model: &Model) -> impl View<Msg> {
+    div![
+        h1![t("title")],
+        div![LOCALES.iter().map(|&(tag, name)| {
+            button![
+                attrs! {At::Disabled => (locale::current().as_str() == tag).as_at_value()},
+                ev(Ev::Click, move |_| Msg::LocaleSelected(tag)),
+                name
+            ]
+        })],
+        p![format!("{}: {}", t("clicks"), model.clicks)],
+        button![ev(Ev::Click, |_| Msg::Click), t("click")],
+        model.last_change.as_ref().map_or(empty![], |locale| {
+            p![format!("{}: {}", t("changed"), locale)]
+        }),
+    ]
+}
+
+#[wasm_bindgen(start)]
+pub fn render() {
+    App::builder(update, view)
+        .after_mount(after_mount)
+        .locale_opts(LocaleOpts {
+            supported: LOCALES.iter().map(|(tag, _)| (*tag).to_owned()).collect(),
+            ..LocaleOpts::default()
+        })
+        .build_and_start();
+}
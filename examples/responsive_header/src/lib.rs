@@ -0,0 +1,146 @@
+use seed::{prelude::*, *};
+
+const BREAKPOINTS: &[(u32, &str)] = &[(640, "sm"), (1024, "md")];
+const SECTION_COUNT: usize = 20;
+
+// Model
+
+#[derive(Default)]
+struct Model {
+    viewport: Viewport,
+    menu_open: bool,
+    // The subscription is active as long as its handle lives.
+    viewport_handle: Option<ViewportHandle>,
+}
+
+impl Model {
+    fn compact(&self) -> bool {
+        self.viewport.breakpoint.is_none()
+    }
+
+    fn shrunk(&self) -> bool {
+        self.viewport.scroll_y > 40.
+    }
+}
+
+// AfterMount
+
+fn after_mount(_: Url, orders: &mut impl Orders<Msg>) -> AfterMount<Model> {
+    let viewport_handle = orders.subscribe_viewport(
+        ViewportOpts {
+            breakpoints: BREAKPOINTS,
+            track_scroll: true,
+        },
+        Msg::Viewport,
+    );
+    AfterMount::new(Model {
+        viewport_handle: Some(viewport_handle),
+        ..Model::default()
+    })
+}
+
+// Update
+
+enum Msg {
+    Viewport(Viewport),
+    ToggleMenu,
+}
+
+fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
+    match msg {
+        Msg::Viewport(viewport) => {
+            model.viewport = viewport;
+            if !model.compact() {
+                model.menu_open = false;
+            }
+        }
+        Msg::ToggleMenu => model.menu_open = !model.menu_open,
+    }
+}
+
+// View
+
+fn view_nav(model: &Model) -> Node<Msg> {
+    let links = vec!["Home", "Docs", "Blog", "About"]
+        .into_iter()
+        .map(|title| a![style! {St::Padding => "0 12px"}, title]);
+
+    if model.compact() {
+        div![
+            button!["☰", ev(Ev::Click, |_| Msg::ToggleMenu)],
+            if model.menu_open {
+                div![
+                    style! {
+                        St::Position => "absolute";
+                        St::Right => px(0);
+                        St::Display => "flex";
+                        St::FlexDirection => "column";
+                        St::BackgroundColor => "white";
+                    },
+                    links,
+                ]
+            } else {
+                empty![]
+            },
+        ]
+    } else {
+        nav![links]
+    }
+}
+
+fn view(model: &Model) -> impl View<Msg> {
+    let insets = model.viewport.safe_area;
+    let height = if model.shrunk() { 48 } else { 96 };
+    vec![
+        header![
+            style! {
+                St::Position => "fixed";
+                St::Top => px(0);
+                St::Left => px(0);
+                St::Right => px(0);
+                St::Display => "flex";
+                St::AlignItems => "center";
+                St::JustifyContent => "space-between";
+                St::Height => px(height);
+                St::PaddingTop => format!("{}px", insets.top);
+                St::PaddingLeft => format!("{}px", insets.left + 16.);
+                St::PaddingRight => format!("{}px", insets.right + 16.);
+                St::BackgroundColor => "rebeccapurple";
+                St::Color => "white";
+                St::Transition => "height 200ms";
+            },
+            h1![
+                style! {St::FontSize => if model.shrunk() { "1.2em" } else { "2em" }},
+                "Responsive header",
+            ],
+            view_nav(model),
+        ],
+        main![
+            style! {
+                St::MaxWidth => px(600);
+                St::Margin => "0 auto";
+                St::PaddingTop => px(120);
+            },
+            p![format!(
+                "{} × {} @ {}x, breakpoint: {}",
+                model.viewport.width,
+                model.viewport.height,
+                model.viewport.dpr,
+                model.viewport.breakpoint.unwrap_or("none"),
+            )],
+            (0..SECTION_COUNT).map(|index| {
+                section![
+                    style! {St::Height => px(200); St::BackgroundColor => "lavender"; St::Margin => "20px 0"},
+                    h2![format!("Section {}", index + 1)],
+                ]
+            }),
+        ],
+    ]
+}
+
+#[wasm_bindgen(start)]
+pub fn render() {
+    App::builder(update, view)
+        .after_mount(after_mount)
+        .build_and_start();
+}
@@ -0,0 +1,74 @@
+use seed::{prelude::*, *};
+use serde::Deserialize;
+
+const CONFIG_ELEMENT_ID: &str = "seed-config";
+
+// Config
+
+#[derive(Deserialize)]
+struct Config {
+    environment: String,
+    api_url: String,
+    new_checkout: bool,
+}
+
+// Model
+
+struct Model {
+    environment: String,
+    api_url: String,
+    new_checkout: bool,
+}
+
+// AfterMount
+
+fn after_mount(_: Url, orders: &mut impl Orders<Msg>) -> AfterMount<Model> {
+    let config = orders
+        .context::<Config>()
+        .expect("config is loaded by `config_from_element`");
+    AfterMount::new(Model {
+        environment: config.environment.clone(),
+        api_url: config.api_url.clone(),
+        new_checkout: config.new_checkout,
+    })
+}
+
+// Update
+
+enum Msg {}
+
+fn update(_: Msg, _: &mut Model, _: &mut impl Orders<Msg>) {}
+
+// View
+
+fn view(model: &Model) -> impl View<Msg> {
+    div![
+        h1![format!("Environment: {}", model.environment)],
+        p![format!("API: {}", model.api_url)],
+        p![if model.new_checkout {
+            "New checkout is enabled."
+        } else {
+            "Old checkout."
+        }],
+    ]
+}
+
+fn error_view(error: &ConfigError) {
+    let message = format!("The app can't be started: {}", error);
+    if let Some(app) = document().get_element_by_id("app") {
+        app.set_text_content(Some(&message));
+    }
+    seed::error(message);
+}
+
+#[wasm_bindgen(start)]
+pub fn render() {
+    let result = App::builder(update, view)
+        .config_from_element::<Config>(CONFIG_ELEMENT_ID)
+        .after_mount(after_mount)
+        .try_build_and_start();
+
+    if let Err(error) = result {
+        error_view(&error);
+    }
+}
@@ -0,0 +1,130 @@
+use seed::{prelude::*, *};
+
+const ITEM_COUNT: usize = 12;
+
+// Model
+
+#[derive(Default)]
+struct Model {
+    article: ElRef<web_sys::Element>,
+    reading_progress: f64,
+    revealed: Vec<bool>,
+    // Subscriptions are active as long as their handles live.
+    progress_handles: Vec<ScrollProgressHandle>,
+}
+
+// AfterMount
+
+fn after_mount(_: Url, orders: &mut impl Orders<Msg>) -> AfterMount<Model> {
+    let article = ElRef::new();
+    // Measure only the part when the article covers the viewport.
+    let viewport_height = window()
+        .inner_height()
+        .ok()
+        .and_then(|height| height.as_f64())
+        .unwrap_or_default();
+    // The article is resolved after the first render.
+    // Progress isn't shown in browsers without `IntersectionObserver`.
+    let progress_handles = orders
+        .subscribe_scroll_progress_el_ref(
+            &article,
+            ScrollProgressOpts {
+                axis: Axis::Vertical,
+                offset_start: viewport_height,
+                offset_end: viewport_height,
+            },
+            Msg::ReadingProgress,
+        )
+        .into_iter()
+        .collect();
+
+    // Items exist after the first render.
+    orders.after_next_render(|_| Msg::Rendered);
+    AfterMount::new(Model {
+        article,
+        revealed: vec![false; ITEM_COUNT],
+        progress_handles,
+        ..Model::default()
+    })
+}
+
+// Update
+
+#[derive(Clone, Copy)]
+enum Msg {
+    Rendered,
+    ReadingProgress(f64),
+    ItemProgress(usize, f64),
+}
+
+fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+    match msg {
+        Msg::Rendered => {
+            for index in 0..ITEM_COUNT {
+                let item = document()
+                    .get_element_by_id(&item_id(index))
+                    .expect("item element");
+                model
+                    .progress_handles
+                    .extend(orders.subscribe_scroll_progress(
+                        &item,
+                        ScrollProgressOpts {
+                            offset_start: 100.,
+                            ..ScrollProgressOpts::default()
+                        },
+                        move |progress| Msg::ItemProgress(index, progress),
+                    ));
+            }
+        }
+        Msg::ReadingProgress(progress) => model.reading_progress = progress,
+        Msg::ItemProgress(index, progress) => {
+            if progress > 0. {
+                model.revealed[index] = true;
+            }
+        }
+    }
+}
+
+// View
+
+fn item_id(index: usize) -> String {
+    format!("item-{}", index)
+}
+
+fn view(model: &Model) -> impl View<Msg> {
+    vec![
+        div![style! {
+            St::Position => "fixed";
+            St::Top => px(0);
+            St::Left => px(0);
+            St::Height => px(6);
+            St::Width => format!("{}%", model.reading_progress * 100.);
+            St::BackgroundColor => "rebeccapurple";
+        }],
+        div![
+            el_ref(&model.article),
+            style! {St::MaxWidth => px(600); St::Margin => "0 auto"},
+            h1!["Reading progress"],
+            model.revealed.iter().enumerate().map(|(index, revealed)| {
+                section![
+                    attrs! {At::Id => item_id(index)},
+                    style! {
+                        St::Height => px(300);
+                        St::Margin => "40px 0";
+                        St::BackgroundColor => "lavender";
+                        St::Opacity => if *revealed { "1" } else { "0" };
+                        St::Transition => "opacity 600ms";
+                    },
+                    h2![format!("Section {}", index + 1)],
+                ]
+            })
+        ],
+    ]
+}
+
+#[wasm_bindgen(start)]
+pub fn render() {
+    App::builder(update, view)
+        .after_mount(after_mount)
+        .build_and_start();
+}
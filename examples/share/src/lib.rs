@@ -0,0 +1,75 @@
+use futures::FutureExt;
+use seed::browser::{share, Capabilities};
+use seed::{prelude::*, *};
+
+// Model
+
+struct Model {
+    capabilities: Capabilities,
+    share_data: ShareData,
+    status: Option<String>,
+}
+
+// AfterMount
+
+fn after_mount(_: Url, orders: &mut impl Orders<Msg>) -> AfterMount<Model> {
+    AfterMount::new(Model {
+        capabilities: orders.capabilities().clone(),
+        share_data: ShareData {
+            title: Some("Seed".to_owned()),
+            text: Some("A Rust framework for creating web apps".to_owned()),
+            url: window().location().href().ok(),
+            ..ShareData::default()
+        },
+        status: None,
+    })
+}
+
+// Update
+
+enum Msg {
+    Share,
+    Shared(Result<(), ShareError>),
+    CopyLink,
+    LinkCopied(Result<(), ShareError>),
+}
+
+fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+    match msg {
+        // `Share` is sent by a click handler, so the share sheet is opened during the user gesture.
+        Msg::Share => {
+            orders.share(model.share_data.clone(), Msg::Shared);
+        }
+        Msg::Shared(Ok(())) => model.status = Some("Shared!".to_owned()),
+        Msg::Shared(Err(ShareError::Cancelled)) => model.status = None,
+        Msg::Shared(Err(error)) => model.status = Some(error.to_string()),
+        Msg::CopyLink => {
+            let url = model.share_data.url.clone().unwrap_or_default();
+            let copied = share::copy_to_clipboard(orders.capabilities(), &url);
+            orders.perform_cmd(copied.map(|result| Ok::<_, Msg>(Msg::LinkCopied(result))));
+        }
+        Msg::LinkCopied(Ok(())) => model.status = Some("Link copied to the clipboard.".to_owned()),
+        Msg::LinkCopied(Err(error)) => model.status = Some(error.to_string()),
+    }
+}
+
+// View
+
+fn view(model: &Model) -> impl View<Msg> {
+    div![
+        h1!["Share example"],
+        if seed::browser::can_share(&model.capabilities, &model.share_data) {
+            button![ev(Ev::Click, |_| Msg::Share), "Share"]
+        } else {
+            button![ev(Ev::Click, |_| Msg::CopyLink), "Copy link"]
+        },
+        model.status.as_ref().map_or(empty![], |status| p![status]),
+    ]
+}
+
+#[wasm_bindgen(start)]
+pub fn render() {
+    App::builder(update, view)
+        .after_mount(after_mount)
+        .build_and_start();
+}
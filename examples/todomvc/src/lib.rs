@@ -31,6 +31,7 @@ impl ToString for Visible {
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
 struct Todo {
+    id: u32,
     title: String,
     completed: bool,
     editing: bool,
@@ -51,6 +52,7 @@ struct Model {
     visible: Visible,
     entry_text: String,
     edit_text: String,
+    next_todo_id: u32,
     local_storage: Storage,
 }
 
@@ -95,6 +97,7 @@ impl Default for Model {
             visible: Visible::All,
             entry_text: String::new(),
             edit_text: String::new(),
+            next_todo_id: 0,
             local_storage,
         }
     }
@@ -171,11 +174,13 @@ fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
 
                 if !title.is_empty() {
                     model.todos.push(Todo {
+                        id: model.next_todo_id,
                         title,
                         completed: false,
                         editing: false,
                     });
                     input_el.set_value("");
+                    model.next_todo_id += 1;
                 }
             }
         }
@@ -292,22 +297,19 @@ fn footer(model: &Model) -> Node<Msg> {
 
 // Top-level component we pass to the virtual dom. Must accept the model as its only argument.
 fn view(model: &Model) -> impl View<Msg> {
-    // We use the item's position in model.todos to identify it, because this allows
-    // simple in-place modification through indexing. This is different from its
-    // position in visible todos, hence the two-step process.
-    let todo_els: Vec<Node<Msg>> = model
-        .todos
-        .clone()
-        .into_iter()
-        .enumerate()
-        .filter_map(|(posit, todo)| {
-            if todo.visible(model.visible) {
-                Some(todo_item(&todo, posit, &model.edit_text))
-            } else {
-                None
-            }
-        })
-        .collect();
+    // We use the item's position in model.todos to identify it in messages, because this
+    // allows simple in-place modification through indexing. This is different from its
+    // position in visible todos, hence the two-step process. Elements are keyed by todo ids,
+    // so the edited todo keeps its DOM nodes when other todos are destroyed.
+    let todo_els = keyed_nodes(
+        model
+            .todos
+            .iter()
+            .enumerate()
+            .filter(|(_, todo)| todo.visible(model.visible)),
+        |(_, todo)| todo.id,
+        |(posit, todo)| todo_item(todo, posit, &model.edit_text),
+    );
 
     let main = if model.todos.is_empty() {
         seed::empty()
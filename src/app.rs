@@ -1,46 +1,132 @@
-use crate::browser::dom::virtual_dom_bridge;
+use crate::browser::dom::{
+    csp, dialog,
+    flip::FlipAnimations,
+    scheduler::{self, SharedQueue},
+};
 use crate::browser::{
-    service::routing,
+    input_modality::{ModalityListeners, ModalityTracker},
+    service::routing::{self, HistoryOp, NavigationOutcome, NavigationSource, RoutingCfg},
+    time::{self, Clock},
     url,
-    util::{self, window, ClosureNew},
-    NextTick, Url,
+    util::{self, ClosureNew},
+    visibility, Capabilities, NextTick, Url, VisibilityResumed,
+};
+use crate::virtual_dom::{
+    active_link, el_ref, hydration, keep_alive,
+    lazy_hydration::{self, LazyHydration},
+    patch,
+    renderer::Renderers,
+    text_normalization, vdom_eq, El, Mailbox, Node, Tag, TextNormalization, View,
 };
-use crate::virtual_dom::{patch, El, Mailbox, Node, Tag, View};
 use builder::{
     init::{Init, InitFn},
     IntoAfterMount, MountPointInitInitAPI, UndefinedInitAPI, UndefinedMountPoint,
 };
+use cmd_lanes::CmdLanes;
+use context::Contexts;
+use debounced_cmds::DebouncedCmds;
+use deferred_navigation::DeferredNavigation;
 use enclose::enclose;
-use futures::future::LocalFutureObj;
+use frame_stats::FrameStats;
+use futures::channel::oneshot;
+use futures::future::{self, LocalFutureObj, Shared};
+use futures::stream::{LocalBoxStream, StreamExt};
 use futures::FutureExt;
+use memory_growth::MemoryCounts;
+#[cfg(all(feature = "debug-tools", debug_assertions))]
+use memory_growth::MemoryGrowth;
+use msg_listeners::MsgListeners;
+use msg_subscriptions::MsgSubscriptions;
+use offline_retry::OfflineRetries;
+use page_meta::PageMetaState;
+#[cfg(feature = "perf-budget")]
+use perf_budget::{Measurement, PerfViolations};
+use preload::Preloads;
+use scroll_behavior::ScrollPositions;
 use std::{
     cell::{Cell, RefCell},
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
+    future::Future,
+    mem,
+    panic::{self, AssertUnwindSafe},
     rc::Rc,
+    time::Duration,
 };
+use subs::Subscriptions;
 use types::*;
-use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
 use wasm_bindgen_futures::spawn_local;
 use web_sys::Element;
 
 pub mod builder;
 pub mod cfg;
+pub mod cmd_handle;
+pub mod cmd_lanes;
+pub mod cmds;
+pub mod context;
 pub mod data;
+pub mod debounced_cmds;
+pub mod deferred_navigation;
 pub mod effects;
+pub mod frame_stats;
+pub mod framework_error;
+pub mod locale;
+pub mod memory_growth;
 pub mod message_mapper;
+pub mod msg_listeners;
+pub mod msg_subscriptions;
+pub mod offline_retry;
 pub mod orders;
+pub mod page_meta;
+pub mod perf_budget;
+pub mod ports;
+pub mod preload;
+pub mod queue_budget;
+pub mod render_info;
 pub mod render_timestamp_delta;
+pub mod route_focus;
+pub mod scroll_behavior;
+pub mod stream_handle;
+pub mod streams;
+pub mod subs;
+pub mod toast;
 pub mod types;
+pub mod view_slices;
 
 pub use builder::{
     AfterMount, BeforeMount, Builder as AppBuilder, MountPoint, MountType, UrlHandling,
 };
 pub use cfg::{AppCfg, AppInitCfg};
+pub use cmd_handle::CmdHandle;
+pub use cmd_lanes::{Lane, LaneStats, QueueOverflow};
+pub use context::ConfigError;
 pub use data::AppData;
 pub use effects::Effect;
-pub use message_mapper::MessageMapper;
-pub use orders::{Orders, OrdersContainer, OrdersProxy};
+pub use frame_stats::{FrameStatsOpts, FrameStatsSnapshot};
+pub use framework_error::FrameworkError;
+pub use locale::{ActiveLocale, Locale, LocaleChanged, LocaleError, LocaleOpts};
+pub use memory_growth::MemoryGrowthCheck;
+pub use message_mapper::{GMessageMapper, MessageMapper};
+pub use msg_listeners::MsgListenerHandle;
+pub use msg_subscriptions::SubscriptionHandle;
+pub use offline_retry::CmdFailure;
+pub use orders::{MockedOrders, MsgReturn, Orders, OrdersContainer, OrdersProxy};
+pub use page_meta::PageMeta;
+pub use perf_budget::{PerfBudget, PerfPhase, PerfViolation, WarnMode};
+pub use ports::{Port, PortError, PortEvent, PortPayload};
+pub use preload::{
+    prefetch_on_hover, AsKind, CrossOrigin, HintRel, PreloadHandle, PreloadHint, PreloadStatus,
+    RoutePrefetch,
+};
+pub use queue_budget::{OnQueueBudgetExceeded, QueueBudget};
+pub use render_info::RenderInfo;
 pub use render_timestamp_delta::RenderTimestampDelta;
+pub use route_focus::{skip_link, RouteFocus};
+pub use scroll_behavior::ScrollBehavior;
+pub use stream_handle::StreamHandle;
+pub use subs::SubHandle;
+pub use toast::{toast_region_view, Toast, ToastKind, ToastManager, ToastMsg, ToastOpts};
+pub use view_slices::ViewSlices;
 
 pub struct UndefinedGMsg;
 
@@ -48,12 +134,27 @@ type OptDynInitCfg<Ms, Mdl, ElC, GMs> =
     Option<AppInitCfg<Ms, Mdl, ElC, GMs, dyn IntoAfterMount<Ms, Mdl, ElC, GMs>>>;
 
 /// Determines if an update should cause the `VDom` to rerender or not.
+/// See `orders.render`, `orders.force_render_now` and `orders.skip`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ShouldRender {
     Render,
     ForceRenderNow,
     Skip,
 }
 
+impl ShouldRender {
+    /// `ForceRenderNow` wins over `Render`, `Render` wins over `Skip`.
+    fn strongest(self, other: Self) -> Self {
+        match (self, other) {
+            (ShouldRender::ForceRenderNow, _) | (_, ShouldRender::ForceRenderNow) => {
+                ShouldRender::ForceRenderNow
+            }
+            (ShouldRender::Render, _) | (_, ShouldRender::Render) => ShouldRender::Render,
+            (ShouldRender::Skip, ShouldRender::Skip) => ShouldRender::Skip,
+        }
+    }
+}
+
 pub struct App<Ms, Mdl, ElC, GMs = UndefinedGMsg>
 where
     Ms: 'static,
@@ -65,7 +166,7 @@ where
     /// App configuration available for the entire application lifetime.
     pub cfg: Rc<AppCfg<Ms, Mdl, ElC, GMs>>,
     /// Mutable app state
-    pub data: Rc<AppData<Ms, Mdl>>,
+    pub data: Rc<AppData<Ms, Mdl, GMs>>,
 }
 
 impl<Ms: 'static, Mdl: 'static, ElC: View<Ms>, GMs> ::std::fmt::Debug for App<Ms, Mdl, ElC, GMs> {
@@ -141,32 +242,197 @@ impl<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static> App<Ms, Mdl, ElC, GMs> {
     /// the actual DOM, via web_sys, when we need.
     /// The model stored in inner is the old model; updated_model is a newly-calculated one.
     pub fn update(&self, message: Ms) {
+        if self.warn_unmounted() {
+            return;
+        }
         let mut queue: VecDeque<Effect<Ms, GMs>> = VecDeque::new();
         queue.push_front(message.into());
         self.process_cmd_and_msg_queue(queue);
     }
 
     pub fn sink(&self, g_msg: GMs) {
+        if self.warn_unmounted() {
+            return;
+        }
         let mut queue: VecDeque<Effect<Ms, GMs>> = VecDeque::new();
         queue.push_front(Effect::GMsg(g_msg));
         self.process_cmd_and_msg_queue(queue);
     }
 
+    /// Returns `true` and warns when the app has been unmounted - see `App::unmount`.
+    fn warn_unmounted(&self) -> bool {
+        if !self.data.unmounted.get() {
+            return false;
+        }
+        web_sys::console::warn_1(
+            &"The app has been unmounted (`App::unmount`) - the message is ignored.".into(),
+        );
+        true
+    }
+
+    /// Append `queue` to the app's effect queue and process all queued effects.
+    ///
+    /// Delivery guarantees:
+    /// - Effects are processed in FIFO order. Effects returned by `update` are appended
+    ///   to the end of the queue - behind effects queued before.
+    /// - Renders requested by all processed messages are combined and performed once,
+    ///   after the queue is drained - `ForceRenderNow` renders synchronously at that point,
+    ///   `Render` schedules a render (see `orders.render_throttle`).
+    /// - Effects produced by the render (e.g. `after_next_render` messages) are processed
+    ///   right after the render.
+    /// - The queue is stored in `AppData`, so the remaining effects survive a panic unwinding
+    ///   out of a forced render (e.g. a bug in `view`) - they are processed by the next call
+    ///   (the next `app.update`, `app.sink` or render). The old vdom and `after_next_render`
+    ///   callbacks are kept as well, so the next render patches the DOM as usual.
     pub fn process_cmd_and_msg_queue(&self, mut queue: VecDeque<Effect<Ms, GMs>>) {
-        while let Some(effect) = queue.pop_front() {
-            match effect {
-                Effect::Msg(msg) => {
-                    let mut new_effects = self.process_queue_message(msg);
-                    queue.append(&mut new_effects);
+        if self.data.unmounted.get() {
+            return;
+        }
+        self.data.effect_queue.borrow_mut().append(&mut queue);
+
+        let budget = self.cfg.queue_budget;
+        let mut should_render = ShouldRender::Skip;
+        let mut processed_messages = 0;
+        let mut recent_messages = VecDeque::new();
+        let mut overflow_reported = false;
+
+        loop {
+            // Don't hold the borrow - processing may queue new effects.
+            let effect = match self.data.effect_queue.borrow_mut().pop_front() {
+                Some(effect) => effect,
+                None => break,
+            };
+            if let Effect::Msg(_) | Effect::GMsg(_) | Effect::Batch(_) = effect {
+                if processed_messages >= budget.max_messages {
+                    if !overflow_reported {
+                        overflow_reported = true;
+                        let report = queue_budget::overflow_report(
+                            &budget,
+                            &recent_messages.iter().collect::<Vec<_>>(),
+                            self.data.effect_queue.borrow().len() + 1,
+                        );
+                        if let Some(msg) =
+                            self.framework_error(FrameworkError::QueueBudgetExceeded(report))
+                        {
+                            self.data.effect_queue.borrow_mut().push_front(msg.into());
+                        }
+                    }
+                    if budget.on_exceeded == OnQueueBudgetExceeded::Defer {
+                        self.data.effect_queue.borrow_mut().push_front(effect);
+                        self.render_as(should_render);
+                        // Next task (not microtask) - the browser can render and handle events.
+                        let app = self.clone();
+                        self.cfg
+                            .clock
+                            .set_timeout(
+                                0,
+                                Box::new(move || app.process_cmd_and_msg_queue(VecDeque::new())),
+                            )
+                            .forget();
+                        return;
+                    }
                 }
-                Effect::GMsg(g_msg) => {
-                    let mut new_effects = self.process_queue_global_message(g_msg);
-                    queue.append(&mut new_effects);
+                processed_messages += match &effect {
+                    Effect::Batch(msgs) => msgs.len(),
+                    _ => 1,
+                };
+            }
+            if let Effect::Msg(msg) = &effect {
+                if recent_messages.len() == queue_budget::RECENT_MESSAGES_LIMIT {
+                    recent_messages.pop_front();
                 }
-                Effect::Cmd(cmd) => self.process_queue_cmd(cmd),
-                Effect::GCmd(g_cmd) => self.process_queue_global_cmd(g_cmd),
+                recent_messages.push_back(mem::discriminant(msg));
             }
+
+            let (effect_should_render, mut new_effects) = match effect {
+                Effect::Msg(msg) => self.update_model(msg),
+                Effect::Batch(msgs) => self.process_queue_batch(msgs),
+                Effect::GMsg(g_msg) => self.process_queue_global_message(g_msg),
+                Effect::Cmd(cmd) => {
+                    self.process_queue_cmd(cmd);
+                    continue;
+                }
+                Effect::Stream(stream) => {
+                    self.process_queue_stream(stream);
+                    continue;
+                }
+                Effect::GCmd(g_cmd) => {
+                    self.process_queue_global_cmd(g_cmd);
+                    continue;
+                }
+            };
+            should_render = should_render.strongest(effect_should_render);
+            self.data.effect_queue.borrow_mut().append(&mut new_effects);
         }
+        self.render_as(should_render);
+    }
+
+    /// Map `url` to a message by `routes`. When `routes` don't match, `not_found` is used.
+    fn route(&self, url: Url) -> Option<Ms> {
+        // Unmounted apps don't route - e.g. late `navigate` calls from commands are ignored.
+        if self.data.unmounted.get() {
+            return None;
+        }
+        self.set_current_url(url.clone());
+
+        let routes = (*self.data.routes.borrow())?;
+        routes(url.clone()).or_else(|| self.cfg.not_found.map(|not_found| not_found(url)))
+    }
+
+    fn set_current_url(&self, url: Url) {
+        // Links are updated now, even if `update` skips the render.
+        if let Some(vdom) = self.data.main_el_vdom.borrow_mut().as_mut() {
+            active_link::refresh_all(vdom, &url);
+        }
+        self.data.current_url.replace(url);
+    }
+
+    /// Resolve `source` by `routing::resolve_navigation` and apply the outcome -
+    /// push the history entry, remember the new URL and return the message for `update`.
+    /// Unmounted apps only push the history entry.
+    fn resolve_navigation(&self, source: NavigationSource) -> NavigationOutcome<Ms> {
+        let routes = (*self.data.routes.borrow()).unwrap_or(|_| None);
+        let mut outcome = routing::resolve_navigation(
+            &self.data.current_url.borrow(),
+            source,
+            routes,
+            &RoutingCfg {
+                base_path: &self.cfg.base_path,
+                not_found: self.cfg.not_found,
+            },
+        );
+        if let HistoryOp::Push(url) = &outcome.history {
+            routing::push_route_with_base_path(&self.cfg.base_path, url.clone());
+        }
+        if self.data.unmounted.get() {
+            outcome.msg = None;
+            return outcome;
+        }
+        if let Some(url) = outcome.url.clone() {
+            if self.cfg.route_focus.is_some() {
+                self.data.route_focus_pending.set(true);
+            }
+            self.set_current_url(url);
+        }
+        outcome
+    }
+
+    /// `resolve_navigation` for browser events - the message is sent to `update`.
+    /// Returns `true` when the browser's default action has to be prevented.
+    fn dispatch_navigation(&self, source: NavigationSource) -> bool {
+        let outcome = self.resolve_navigation(source);
+        if let Some(msg) = outcome.msg {
+            self.update(msg);
+        }
+        outcome.intercept
+    }
+
+    /// `route` for navigations - the focus is moved after the next render (see `route_focus`).
+    fn navigate(&self, url: Url) -> Option<Ms> {
+        if self.cfg.route_focus.is_some() && !self.data.unmounted.get() {
+            self.data.route_focus_pending.set(true);
+        }
+        self.route(url)
     }
 
     pub fn setup_window_listeners(&self) {
@@ -182,28 +448,173 @@ impl<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static> App<Ms, Mdl, ElC, GMs> {
         }
     }
 
-    pub fn add_message_listener<F>(&self, listener: F)
+    /// Install document listeners which track user's input modality.
+    /// Registered modality handlers are invoked when the modality changes.
+    pub fn setup_input_modality_listeners(&self) {
+        let listeners = ModalityListeners::new(enclose!((self => s) move |event| {
+            let modality = s.data.input_modality_tracker.borrow_mut().handle(&event);
+            if let Some(modality) = modality {
+                let msgs = {
+                    let mut handlers = s.data.input_modality_handlers.borrow_mut();
+                    handlers.retain(|(alive, _)| alive.upgrade().is_some());
+                    handlers
+                        .iter()
+                        .map(|(_, handler)| handler(modality))
+                        .collect::<Vec<_>>()
+                };
+                for msg in msgs {
+                    s.update(msg);
+                }
+            }
+        }));
+        self.data.input_modality_listeners.replace(Some(listeners));
+    }
+
+    /// Send `VisibilityResumed` to subscribers when the document becomes visible again.
+    fn setup_visibility_listener(&self) {
+        let clock = Rc::clone(&self.cfg.clock);
+        let listener = visibility::on_resume(
+            clock,
+            enclose!((self => s) move |hidden_for| {
+                let msgs = s
+                    .data
+                    .subscriptions
+                    .borrow_mut()
+                    .notify(&VisibilityResumed { hidden_for });
+                for msg in msgs {
+                    s.update(msg);
+                }
+            }),
+        );
+        self.data.visibility_listener.replace(Some(listener));
+    }
+
+    /// Invoke `listener` with each message before it's passed to `update`.
+    /// The listener is removed when the returned handle is dropped - call `forget` on the handle
+    /// to keep the listener until the app is unmounted.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///let handle = app.add_message_listener(|msg| log!("message", msg));
+    ///// ...
+    ///drop(handle);
+    /// ```
+    pub fn add_message_listener<F>(&self, listener: F) -> MsgListenerHandle
     where
         F: Fn(&Ms) + 'static,
     {
-        self.data
-            .msg_listeners
-            .borrow_mut()
-            .push(Box::new(listener));
+        let id = self.data.msg_listeners.borrow_mut().add(listener);
+        let data = Rc::downgrade(&self.data);
+        MsgListenerHandle::new(move || {
+            if let Some(data) = data.upgrade() {
+                data.msg_listeners.borrow_mut().remove(id);
+            }
+        })
+    }
+
+    /// Create a port for another Seed app on the page - pass `Port::to_js` to the other app's
+    /// `connect_port` through the host page. Events of the port are mapped by `handler`
+    /// and passed to `update`. See `seed::app::ports`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///let port = app.create_port::<CartEvent>("cart", Msg::FromWidget);
+    ///js_sys::Reflect::set(&window(), &"cartPort".into(), &port.to_js()).unwrap();
+    /// ```
+    pub fn create_port<P: PortPayload>(
+        &self,
+        name: &str,
+        handler: impl Fn(PortEvent<P>) -> Ms + 'static,
+    ) -> Port<P> {
+        ports::create(self, name, handler)
+    }
+
+    /// Connect a port created by another Seed app's `create_port`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PortError` when `js_handle` isn't a port handle, when the port's name or
+    /// `PortPayload::SCHEMA` doesn't match, or when the port has been already connected.
+    pub fn connect_port<P: PortPayload>(
+        &self,
+        name: &str,
+        js_handle: &JsValue,
+        handler: impl Fn(PortEvent<P>) -> Ms + 'static,
+    ) -> Result<Port<P>, PortError> {
+        ports::connect(self, name, js_handle, handler)
+    }
+
+    /// Invoke `handler` with each message before it's passed to `update`.
+    /// Messages returned by `handler` are appended to the message queue.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///let handle = app.subscribe(|msg| match msg {
+    ///    Msg::Saved => Some(Msg::ShowToast("Saved".to_owned())),
+    ///    _ => None,
+    ///});
+    ///app.unsubscribe(handle);
+    /// ```
+    pub fn subscribe(&self, handler: impl Fn(&Ms) -> Option<Ms> + 'static) -> SubscriptionHandle {
+        self.data.msg_subscriptions.borrow_mut().subscribe(handler)
+    }
+
+    /// Remove the subscription created by `App::subscribe`.
+    /// Returns `false` when it has been removed already.
+    pub fn unsubscribe(&self, handle: SubscriptionHandle) -> bool {
+        self.data.msg_subscriptions.borrow_mut().unsubscribe(handle)
     }
 
     #[allow(clippy::too_many_arguments)]
     pub(super) fn new(
         update: UpdateFn<Ms, Mdl, ElC, GMs>,
-        sink: Option<SinkFn<Ms, Mdl, ElC, GMs>>,
+        update_middlewares: Vec<UpdateMiddlewareFn<Ms, Mdl, ElC, GMs>>,
+        sinks: Vec<SinkFn<Ms, Mdl, ElC, GMs>>,
+        clone_g_msg: Option<fn(&GMs) -> GMs>,
         view: ViewFn<Mdl, ElC>,
         mount_point: Element,
         routes: Option<RoutesFn<Ms>>,
+        not_found: Option<NotFoundFn<Ms>>,
         window_events: Option<WindowEventsFn<Ms, Mdl>>,
+        queue_budget: QueueBudget,
+        renderers: Renderers<Ms>,
+        clock: Rc<dyn Clock>,
+        mut contexts: Contexts,
+        perf_budget: Option<PerfBudget>,
+        perf_msg_name: Option<PerfMsgNameFn<Ms>>,
+        base_meta: PageMeta,
+        text_normalization: TextNormalization,
+        locale_opts: LocaleOpts,
+        before_unmount: Option<BeforeUnmountFn<Mdl>>,
+        intercept_forms: Option<InterceptFormsFn<Ms>>,
+        frame_stats_opts: FrameStatsOpts,
+        base_path: Vec<String>,
+        csp_nonce: Option<String>,
+        view_slices: ViewSlices<Ms, Mdl>,
+        route_focus: Option<RouteFocus>,
+        scroll_behavior: ScrollBehavior,
+        view_error_handler: Option<ViewErrorHandlerFn<Ms>>,
+        memory_growth_check: Option<MemoryGrowthCheck>,
+        preload_ttl: Duration,
+        devtools_codec: Option<MsgCodec<Ms>>,
+        capabilities: Capabilities,
+        capabilities_msg: Option<CapabilitiesMsgFn<Ms>>,
+        framework_errors: Option<FrameworkErrorsFn<Ms>>,
         init_cfg: OptDynInitCfg<Ms, Mdl, ElC, GMs>,
     ) -> Self {
         let window = util::window();
         let document = window.document().expect("Can't find the window's document");
+        // Detect it before `init`, so it's available in `init` and in the first view.
+        locale::insert_detected(&mut contexts, &locale_opts);
+        let csp_nonce = csp_nonce.or_else(csp::detect_nonce);
+        let current_url = url::current_without_base_path(&base_path);
+        let (unmount_sender, unmount_receiver) = oneshot::channel();
+        // Budgets are ignored without the feature - see `AppBuilder::perf_budget`.
+        #[cfg(not(feature = "perf-budget"))]
+        let _ = (perf_budget, perf_msg_name);
 
         Self {
             init_cfg,
@@ -211,9 +622,36 @@ impl<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static> App<Ms, Mdl, ElC, GMs> {
                 document,
                 mount_point,
                 update,
-                sink,
+                update_middlewares,
+                sinks,
+                clone_g_msg,
                 view,
                 window_events,
+                not_found,
+                intercept_forms,
+                capabilities,
+                capabilities_msg,
+                framework_errors,
+                queue_budget,
+                renderers,
+                clock,
+                contexts,
+                #[cfg(feature = "perf-budget")]
+                perf_budget,
+                #[cfg(feature = "perf-budget")]
+                perf_msg_name,
+                base_meta,
+                text_normalization,
+                locale_opts,
+                base_path,
+                csp_nonce,
+                view_slices,
+                route_focus,
+                scroll_behavior,
+                view_error_handler,
+                memory_growth_check,
+                preload_ttl,
+                devtools_codec,
             }),
             data: Rc::new(AppData {
                 model: RefCell::new(None),
@@ -221,18 +659,59 @@ impl<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static> App<Ms, Mdl, ElC, GMs> {
                 main_el_vdom: RefCell::new(None),
                 popstate_closure: RefCell::new(None),
                 hashchange_closure: RefCell::new(None),
+                link_closure: RefCell::new(None),
+                form_closure: RefCell::new(None),
                 routes: RefCell::new(routes),
                 window_listeners: RefCell::new(Vec::new()),
-                msg_listeners: RefCell::new(Vec::new()),
+                msg_listeners: RefCell::new(MsgListeners::default()),
+                msg_subscriptions: RefCell::new(MsgSubscriptions::default()),
+                effect_queue: RefCell::new(VecDeque::new()),
+                subscriptions: RefCell::new(Subscriptions::default()),
                 scheduled_render_handle: RefCell::new(None),
+                render_scheduled: Cell::new(false),
+                render_throttle_ms: Cell::new(0),
+                throttled_render: RefCell::new(None),
+                animation_frame_senders: RefCell::new(Vec::new()),
                 after_next_render_callbacks: RefCell::new(Vec::new()),
                 render_timestamp: Cell::new(None),
+                frame_stats: RefCell::new(FrameStats::new(frame_stats_opts)),
+                input_modality_tracker: RefCell::new(ModalityTracker::default()),
+                input_modality_handlers: RefCell::new(Vec::new()),
+                input_modality_listeners: RefCell::new(None),
+                visibility_listener: RefCell::new(None),
+                kept_alive: RefCell::new(HashMap::new()),
+                flip: FlipAnimations::default(),
+                offline_retries: RefCell::new(OfflineRetries::default()),
+                deferred_navigation: RefCell::new(DeferredNavigation::default()),
+                debounced_cmds: RefCell::new(DebouncedCmds::new()),
+                cmd_lanes: RefCell::new(CmdLanes::new()),
+                lazy_hydration: RefCell::new(LazyHydration::default()),
+                page_meta: RefCell::new(PageMetaState::default()),
+                render_waiters: RefCell::new(Vec::new()),
+                preloads: RefCell::new(Preloads::default()),
+                ports: RefCell::new(Vec::new()),
+                current_url: RefCell::new(current_url),
+                before_unmount: RefCell::new(before_unmount),
+                unmounted: Cell::new(false),
+                dom_ops: SharedQueue::default(),
+                unmount_sender: RefCell::new(Some(unmount_sender)),
+                unmount_signal: unmount_receiver.shared(),
+                adopted_by: Cell::new(None),
+                route_focus_pending: Cell::new(false),
+                scroll_positions: RefCell::new(ScrollPositions::default()),
+                #[cfg(feature = "perf-budget")]
+                perf_violations: RefCell::new(PerfViolations::default()),
+                memory_counts: MemoryCounts::default(),
+                #[cfg(all(feature = "debug-tools", debug_assertions))]
+                memory_growth: RefCell::new(MemoryGrowth::default()),
+                #[cfg(feature = "devtools")]
+                devtools: RefCell::new(crate::devtools::DevtoolsState::default()),
             }),
         }
     }
 
-    /// Bootstrap the dom with the vdom by taking over all children of the mount point and
-    /// replacing them with the vdom if requested. Will otherwise ignore the original children of
+    /// Bootstrap the vdom by adopting all children of the mount point if requested
+    /// (see `virtual_dom::hydration`). Will otherwise ignore the original children of
     /// the mount point.
     fn bootstrap_vdom(&self, mount_type: MountType) -> El<Ms> {
         // "new" name is for consistency with `update` function.
@@ -240,70 +719,130 @@ impl<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static> App<Ms, Mdl, ElC, GMs> {
         // in a way consistent with patching code.
         let mut new = El::empty(Tag::Placeholder);
 
-        // Map the DOM's elements onto the virtual DOM if requested to takeover.
-        if mount_type == MountType::Takeover {
-            // Construct a vdom from the root element. Subsequently strip the workspace so that we
-            // can recreate it later - this is a kind of simple way to avoid missing nodes (but
-            // not entirely correct).
-            // TODO: 1) Please refer to [issue #277](https://github.com/seed-rs/seed/issues/277)
-            let mut dom_nodes: El<Ms> = (&self.cfg.mount_point).into();
-            dom_nodes.strip_ws_nodes_from_self_and_children();
-
-            // Replace the root dom with a placeholder tag and move the children from the root element
-            // to the newly created root. Uses `Placeholder` to mimic update logic.
-            new.children = dom_nodes.children;
-        }
-
-        // Recreate the needed nodes. Only do this if requested to takeover the mount point since
-        // it should only be needed here.
-        if mount_type == MountType::Takeover {
-            // TODO: Please refer to [issue #277](https://github.com/seed-rs/seed/issues/277)
-            virtual_dom_bridge::assign_ws_nodes_to_el(&util::document(), &mut new);
-
-            // Remove all old elements. We'll swap them out with the newly created elements later.
-            // This maneuver will effectively allow us to remove everything in the mount and thus
-            // takeover the mount point.
-            while let Some(child) = self.cfg.mount_point.first_child() {
-                self.cfg
-                    .mount_point
-                    .remove_child(&child)
-                    .expect("No problem removing node from parent.");
-            }
-
-            // Attach all top-level elements to the mount point if present. This means that we have
-            // effectively taken full control of everything within the mounting element.
-            for child in &mut new.children {
-                match child {
-                    Node::Element(child_el) => {
-                        virtual_dom_bridge::attach_el_and_children(child_el, &self.cfg.mount_point);
-                        patch::attach_listeners(child_el, &self.mailbox());
-                    }
-                    Node::Text(top_child_text) => {
-                        virtual_dom_bridge::attach_text_node(top_child_text, &self.cfg.mount_point);
-                    }
-                    Node::Empty => (),
-                }
-            }
+        // Adopt the DOM's nodes if requested to takeover or hydrate - the first render only patches
+        // the differences between the server-rendered HTML and `view`.
+        if mount_type == MountType::Takeover || mount_type == MountType::Hydrate {
+            new = hydration::adopt_mount_point(&self.cfg.mount_point);
+            // Normalize the server-rendered text the same way as the text from `view`,
+            // so the first render doesn't patch it.
+            text_normalization::normalize_el(&mut new, self.cfg.text_normalization);
+            hydration::sync_normalized_text(&new.children, &self.cfg.mount_point);
+            self.data.adopted_by.set(Some(mount_type));
         }
 
         new
     }
 
-    fn process_queue_message(&self, message: Ms) -> VecDeque<Effect<Ms, GMs>> {
-        for l in self.data.msg_listeners.borrow().iter() {
-            (l)(&message)
+    /// Pass `messages` to `update` one after another. Returns the strongest requested render
+    /// and the new effects of all messages.
+    fn process_queue_batch(&self, messages: Vec<Ms>) -> (ShouldRender, VecDeque<Effect<Ms, GMs>>) {
+        let mut should_render = ShouldRender::Skip;
+        let mut effects = VecDeque::new();
+        for message in messages {
+            let (message_should_render, mut message_effects) = self.update_model(message);
+            should_render = should_render.strongest(message_should_render);
+            effects.append(&mut message_effects);
+        }
+        (should_render, effects)
+    }
+
+    /// Pass `message` to the update middleware with `index` (see `AppBuilder::update_middleware`)
+    /// or to `update` when there are no more middlewares.
+    fn call_update(
+        &self,
+        index: usize,
+        message: Ms,
+        model: &mut Mdl,
+        orders: &mut OrdersContainer<Ms, Mdl, ElC, GMs>,
+    ) {
+        match self.cfg.update_middlewares.get(index) {
+            Some(middleware) => middleware(message, model, orders, &|message, model, orders| {
+                self.call_update(index + 1, message, model, orders)
+            }),
+            None => (self.cfg.update)(message, model, orders),
+        }
+    }
+
+    /// Pass `message` to `update`. Returns the requested render and the new effects.
+    fn update_model(&self, message: Ms) -> (ShouldRender, VecDeque<Effect<Ms, GMs>>) {
+        // Listeners may remove listeners - e.g. by dropping their own handles.
+        let listeners = self.data.msg_listeners.borrow().snapshot();
+        for (id, listener) in listeners {
+            if self.data.msg_listeners.borrow().contains(id) {
+                listener(&message)
+            }
         }
+        // Subscriptions may subscribe or unsubscribe, as listeners above.
+        let subscriptions = self.data.msg_subscriptions.borrow().snapshot();
+        let follow_ups = subscriptions
+            .into_iter()
+            .filter(|(id, _)| self.data.msg_subscriptions.borrow().contains(*id))
+            .filter_map(|(_, handler)| handler(&message))
+            .collect::<Vec<_>>();
+
+        #[cfg(all(feature = "devtools", feature = "debug-tools", debug_assertions))]
+        crate::devtools::log_message(self, &message);
+
+        #[cfg(feature = "perf-budget")]
+        let measurement = self
+            .cfg
+            .perf_budget
+            .filter(|_| !self.data.perf_violations.borrow().reporting)
+            .map(|budget| {
+                let measurement = Measurement::update(&message, self.cfg.perf_msg_name);
+                (budget, measurement)
+            });
 
         let mut orders = OrdersContainer::new(self.clone());
-        (self.cfg.update)(
-            message,
-            &mut self.data.model.borrow_mut().as_mut().unwrap(),
-            &mut orders,
-        );
+        // `cmds::timeout`, `streams::interval`, .. created in `update` use the app's clock
+        // and `seed::dom_write`s belong to the app.
+        scheduler::with_queue(&self.data.dom_ops, || {
+            time::with_clock(&self.cfg.clock, || {
+                locale::with_locale(&self.cfg.contexts, || {
+                    self.call_update(
+                        0,
+                        message,
+                        &mut self.data.model.borrow_mut().as_mut().unwrap(),
+                        &mut orders,
+                    )
+                })
+            })
+        });
+
+        #[cfg(feature = "perf-budget")]
+        {
+            if let Some((budget, measurement)) = measurement {
+                let msgs = measurement.finish(&budget, &self.data.perf_violations);
+                let effects = self.send_perf_violation_msgs(msgs);
+                orders.effects.extend(effects);
+            }
+        }
 
         self.setup_window_listeners();
 
-        match orders.should_render {
+        // Appended behind the `update`'s effects instead of calling `update` recursively.
+        orders
+            .effects
+            .extend(follow_ups.into_iter().map(Effect::Msg));
+        (orders.should_render, orders.effects)
+    }
+
+    /// Send messages of perf violation handlers to `update` and return their effects.
+    /// Their renders are skipped and they aren't measured - see `seed::app::perf_budget`.
+    #[cfg(feature = "perf-budget")]
+    fn send_perf_violation_msgs(&self, msgs: Vec<Ms>) -> VecDeque<Effect<Ms, GMs>> {
+        let mut effects = VecDeque::new();
+        self.data.perf_violations.borrow_mut().reporting = true;
+        for msg in msgs {
+            let (_, msg_effects) = self.update_model(msg);
+            effects.extend(msg_effects);
+        }
+        self.data.perf_violations.borrow_mut().reporting = false;
+        effects
+    }
+
+    fn render_as(&self, should_render: ShouldRender) {
+        match should_render {
             ShouldRender::Render => self.schedule_render(),
             ShouldRender::ForceRenderNow => {
                 self.cancel_scheduled_render();
@@ -311,109 +850,484 @@ impl<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static> App<Ms, Mdl, ElC, GMs> {
             }
             ShouldRender::Skip => (),
         };
-        orders.effects
     }
 
-    fn process_queue_global_message(&self, g_message: GMs) -> VecDeque<Effect<Ms, GMs>> {
-        let mut orders = OrdersContainer::new(self.clone());
+    fn process_queue_global_message(
+        &self,
+        g_message: GMs,
+    ) -> (ShouldRender, VecDeque<Effect<Ms, GMs>>) {
+        // Without sinks, global messages still trigger a render.
+        let mut should_render = if self.cfg.sinks.is_empty() {
+            ShouldRender::Render
+        } else {
+            ShouldRender::Skip
+        };
+        let mut effects = VecDeque::new();
+        let mut g_message = Some(g_message);
 
-        if let Some(sink) = self.cfg.sink {
+        for (index, sink) in self.cfg.sinks.iter().enumerate() {
+            let g_message = if index + 1 == self.cfg.sinks.len() {
+                g_message.take().unwrap()
+            } else {
+                // More sinks can be registered only by `add_sink` and it sets `clone_g_msg`.
+                let clone_g_msg = self
+                    .cfg
+                    .clone_g_msg
+                    .expect("clone_g_msg should be set for multiple sinks");
+                clone_g_msg(g_message.as_ref().unwrap())
+            };
+            let mut orders = OrdersContainer::new(self.clone());
             sink(
                 g_message,
                 &mut self.data.model.borrow_mut().as_mut().unwrap(),
                 &mut orders,
             );
+            should_render = should_render.strongest(orders.should_render);
+            effects.append(&mut orders.effects);
         }
 
         self.setup_window_listeners();
-
-        match orders.should_render {
-            ShouldRender::Render => self.schedule_render(),
-            ShouldRender::ForceRenderNow => {
-                self.cancel_scheduled_render();
-                self.rerender_vdom();
-            }
-            ShouldRender::Skip => (),
-        };
-        orders.effects
+        (should_render, effects)
     }
 
-    fn process_queue_cmd(&self, cmd: LocalFutureObj<'static, Result<Ms, Ms>>) {
+    /// Perform `cmd`. It resolves with `None` when it has been aborted (see `CmdHandle`).
+    fn process_queue_cmd(&self, cmd: impl Future<Output = Option<Ms>> + 'static) {
+        let cmd = time::with_clock_polled(Rc::clone(&self.cfg.clock), cmd);
         let lazy_schedule_cmd = enclose!((self => s) move |_| {
+            let unmount_signal = s.data.unmount_signal.clone();
             // schedule future (cmd) to be executed
-            spawn_local(async move {
-                let msg_returned_from_effect = cmd.await.unwrap_or_else(|err_msg| err_msg);
-                // recursive call which can blow the call stack
-                s.update(msg_returned_from_effect);
-            })
+            spawn_local(until_unmounted(unmount_signal, async move {
+                if let Some(msg_returned_from_effect) = cmd.await {
+                    // Results of commands finished after `unmount` are dropped silently.
+                    if !s.data.unmounted.get() {
+                        // recursive call which can blow the call stack
+                        s.update(msg_returned_from_effect);
+                    }
+                }
+            }))
         });
         // we need to clear the call stack by NextTick so we don't exceed it's capacity
         spawn_local(NextTick::new().map(lazy_schedule_cmd));
     }
 
-    fn process_queue_global_cmd(&self, g_cmd: LocalFutureObj<'static, Result<GMs, GMs>>) {
+    /// Send each item of `stream` to `update`.
+    fn process_queue_stream(&self, stream: LocalBoxStream<'static, Ms>) {
+        let lazy_schedule_stream = enclose!((self => s) move |_| {
+            let unmount_signal = s.data.unmount_signal.clone();
+            spawn_local(until_unmounted(unmount_signal, stream.for_each(move |msg| {
+                if !s.data.unmounted.get() {
+                    s.update(msg);
+                }
+                future::ready(())
+            })))
+        });
+        // we need to clear the call stack by NextTick so we don't exceed it's capacity
+        spawn_local(NextTick::new().map(lazy_schedule_stream));
+    }
+
+    fn process_queue_global_cmd(&self, g_cmd: LocalFutureObj<'static, Option<GMs>>) {
+        let g_cmd = time::with_clock_polled(Rc::clone(&self.cfg.clock), g_cmd);
         let lazy_schedule_cmd = enclose!((self => s) move |_| {
+            let unmount_signal = s.data.unmount_signal.clone();
             // schedule future (g_cmd) to be executed
-            spawn_local(async move {
-                let msg_returned_from_effect = g_cmd.await.unwrap_or_else(|err_msg| err_msg);
-                // recursive call which can blow the call stack
-                s.sink(msg_returned_from_effect);
-            })
+            spawn_local(until_unmounted(unmount_signal, async move {
+                if let Some(msg_returned_from_effect) = g_cmd.await {
+                    if !s.data.unmounted.get() {
+                        // recursive call which can blow the call stack
+                        s.sink(msg_returned_from_effect);
+                    }
+                }
+            }))
         });
         // we need to clear the call stack by NextTick so we don't exceed it's capacity
         spawn_local(NextTick::new().map(lazy_schedule_cmd));
     }
 
-    fn schedule_render(&self) {
+    pub(crate) fn schedule_render(&self) {
+        if self.data.render_scheduled.get() || self.data.throttled_render.borrow().is_some() {
+            return;
+        }
+        // Wait until the throttle window (see `orders.render_throttle`) since the last render ends.
+        let throttle_ms = self.data.render_throttle_ms.get();
+        if let Some(render_timestamp) = self.data.render_timestamp.get() {
+            let remaining_ms = render_timestamp + f64::from(throttle_ms) - self.cfg.clock.now_ms();
+            if throttle_ms > 0 && remaining_ms > 0. {
+                // `remaining_ms` is positive and at most `throttle_ms`, so it fits into `u32`.
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let remaining_ms = remaining_ms.ceil() as u32;
+                let app = self.clone();
+                let timeout = self.cfg.clock.set_timeout(
+                    remaining_ms,
+                    // The handle stays in `throttled_render` until the render - see `rerender_vdom`.
+                    Box::new(move || {
+                        app.data.render_scheduled.set(true);
+                        app.request_frame();
+                    }),
+                );
+                self.data.throttled_render.replace(Some(timeout));
+                return;
+            }
+        }
+        self.data.render_scheduled.set(true);
+        self.request_frame();
+    }
+
+    /// Request the next animation frame, unless it's already requested.
+    /// One frame serves both the scheduled render and animation frame streams
+    /// (see `orders.request_animation_frame_stream`).
+    pub(crate) fn request_frame(&self) {
         let mut scheduled_render_handle = self.data.scheduled_render_handle.borrow_mut();
 
         if scheduled_render_handle.is_none() {
             let cb = Closure::new(enclose!((self => s) move |_| {
                 s.data.scheduled_render_handle.borrow_mut().take();
-                s.rerender_vdom();
+                s.animation_frame();
             }));
 
             *scheduled_render_handle = Some(util::request_animation_frame(cb));
         }
     }
 
+    fn animation_frame(&self) {
+        let streams_alive = {
+            let mut senders = self.data.animation_frame_senders.borrow_mut();
+            if !senders.is_empty() {
+                let timestamp = self.cfg.clock.now_ms();
+                // Sending fails when the stream has been dropped together with its handle.
+                senders.retain(|sender| sender.unbounded_send(timestamp).is_ok());
+            }
+            !senders.is_empty()
+        };
+        if streams_alive {
+            self.request_frame();
+        }
+        if self.data.render_scheduled.replace(false) {
+            self.rerender_vdom();
+        }
+    }
+
     fn cancel_scheduled_render(&self) {
-        // Cancel animation frame request by dropping it.
-        self.data.scheduled_render_handle.borrow_mut().take();
+        self.data.render_scheduled.set(false);
+        self.data.throttled_render.borrow_mut().take();
+        if self.data.animation_frame_senders.borrow().is_empty() {
+            // Cancel animation frame request by dropping it.
+            self.data.scheduled_render_handle.borrow_mut().take();
+        }
+    }
+
+    /// Tear the app down - e.g. when it's embedded in a page which replaces it.
+    ///
+    /// It invokes the `before_unmount` hook (see `AppBuilder::before_unmount`), detaches
+    /// window, routing and input modality listeners, cancels the scheduled render,
+    /// drops queued effects and scheduled DOM reads and writes (see `seed::dom_read`)
+    /// and removes the app's nodes from the mount point.
+    /// Then `update` and `sink` are ignored with a warning and results of running commands
+    /// and queued lane commands (see `Orders::perform_cmd_in_lane`) are dropped.
+    /// Calling it again does nothing.
+    pub fn unmount(&self) {
+        if self.data.unmounted.replace(true) {
+            return;
+        }
+        // The teardown below mutates the mount point - it isn't an external mutation.
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        crate::debug::ownership_guard::uninstall(&self.cfg.mount_point);
+
+        if let Some(before_unmount) = self.data.before_unmount.borrow_mut().take() {
+            if let Some(model) = self.data.model.borrow().as_ref() {
+                before_unmount(model);
+            }
+        }
+
+        self.data.animation_frame_senders.borrow_mut().clear();
+        self.cancel_scheduled_render();
+        scheduler::clear(&self.data.dom_ops);
+        self.data.effect_queue.borrow_mut().clear();
+        self.data.after_next_render_callbacks.borrow_mut().clear();
+        // Dropped senders resolve waiting futures.
+        self.data.render_waiters.borrow_mut().clear();
+        self.data.flip.stop();
+        self.data.offline_retries.borrow_mut().stop();
+        self.data.lazy_hydration.borrow_mut().stop();
+        preload::clear(self);
+        ports::close_all(self);
+        #[cfg(feature = "devtools")]
+        crate::devtools::unregister(self);
+
+        let window = util::window();
+        for listener in self.data.window_listeners.borrow_mut().iter_mut() {
+            listener.detach(&window);
+        }
+        self.data.window_listeners.borrow_mut().clear();
+        let routing_listeners = vec![
+            ("popstate", self.data.popstate_closure.replace(None)),
+            ("hashchange", self.data.hashchange_closure.replace(None)),
+        ];
+        for (event, closure) in routing_listeners {
+            if let Some(closure) = closure {
+                window
+                    .remove_event_listener_with_callback(event, closure.as_ref().unchecked_ref())
+                    .expect("Problem removing routing listener");
+            }
+        }
+        if let Some(closure) = self.data.link_closure.replace(None) {
+            routing::remove_link_listener(&self.cfg.mount_point, &closure);
+        }
+        if let Some(closure) = self.data.form_closure.replace(None) {
+            routing::form_interceptor::remove_form_listener(&closure);
+        }
+        self.data.input_modality_listeners.replace(None);
+        self.data.visibility_listener.replace(None);
+        self.data.msg_listeners.borrow_mut().clear();
+        self.data.msg_subscriptions.borrow_mut().clear();
+        self.data.frame_stats.borrow_mut().clear();
+        // Dropping timers cancels pending debounced commands.
+        self.data.debounced_cmds.borrow_mut().clear();
+        cmd_lanes::clear_queues(&self.data.cmd_lanes.borrow());
+        // Running commands and streams are dropped with their timers.
+        self.data.unmount_sender.borrow_mut().take();
+        self.data.deferred_navigation.borrow_mut().clear();
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        self.data.memory_growth.replace(MemoryGrowth::default());
+        if self.cfg.scroll_behavior == ScrollBehavior::RestoreOrTop {
+            scroll_behavior::restore_browser_restoration();
+        }
+        let kept_alive = mem::replace(&mut *self.data.kept_alive.borrow_mut(), HashMap::new());
+        for parked in kept_alive.into_iter().map(|(_, parked)| parked) {
+            keep_alive::drop_parked(parked, &self.cfg.renderers);
+        }
+
+        let main_el_vdom = self.data.main_el_vdom.borrow_mut().take();
+        if let Some(mut main_el_vdom) = main_el_vdom {
+            patch::detach_listeners(&mut main_el_vdom);
+            el_ref::clear_all(&main_el_vdom);
+            for child in main_el_vdom.children {
+                patch::remove_old_child(child, &self.cfg.mount_point, self);
+            }
+        }
+        self.data.memory_counts.reset::<Ms>(None);
+    }
+
+    /// Stop the app - an alias of [`unmount`](#method.unmount).
+    pub fn stop(&self) {
+        self.unmount();
+    }
+
+    /// Rerender immediately if a render is scheduled and execute all scheduled DOM reads and writes
+    /// (see `seed::dom_read` and `seed::dom_write`).
+    ///
+    /// It's useful in tests, when you need a stable DOM right after `update`.
+    pub fn flush_render(&self) {
+        if self.data.render_scheduled.get() || self.data.throttled_render.borrow().is_some() {
+            self.cancel_scheduled_render();
+            self.rerender_vdom();
+        }
+        scheduler::flush_queue(&self.data.dom_ops);
+    }
+
+    /// Returns a future which resolves after the next render - e.g. to wait in tests
+    /// until the DOM is stable. It resolves immediately when no render is scheduled
+    /// (e.g. after `force_render_now` or `flush_render`) and when the app is unmounted.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///simulate::click(&model.save_button);
+    ///app.next_render().await;
+    /// ```
+    pub fn next_render(&self) -> impl Future<Output = ()> {
+        let render_pending =
+            self.data.render_scheduled.get() || self.data.throttled_render.borrow().is_some();
+        let receiver = if render_pending && !self.data.unmounted.get() {
+            let (sender, receiver) = oneshot::channel();
+            self.data.render_waiters.borrow_mut().push(sender);
+            Some(receiver)
+        } else {
+            None
+        };
+        async move {
+            if let Some(receiver) = receiver {
+                // It fails when the app has been unmounted.
+                let _ = receiver.await;
+            }
+        }
+    }
+
+    /// Log `error` and map it by the `framework_errors` handler.
+    /// The caller sends the returned message - it knows whether `update` is running.
+    pub(crate) fn framework_error(&self, error: FrameworkError) -> Option<Ms> {
+        crate::error(error.to_string());
+        self.cfg.framework_errors.and_then(|handler| handler(error))
+    }
+
+    /// Call `view`. Its panic is replaced by the fallback of `view_error_handler` when it's set.
+    fn view(&self, model: &Mdl) -> Vec<Node<Ms>> {
+        let view_error_handler = match &self.cfg.view_error_handler {
+            Some(view_error_handler) => view_error_handler,
+            None => return (self.cfg.view)(model).els(),
+        };
+        let view = self.cfg.view;
+        match panic::catch_unwind(AssertUnwindSafe(|| view(model).els())) {
+            Ok(nodes) => nodes,
+            Err(payload) => vec![view_error_handler(panic_message(&*payload))],
+        }
     }
 
     fn rerender_vdom(&self) {
-        let new_render_timestamp = window().performance().expect("get `Performance`").now();
+        // DOM reads and writes scheduled by `view` or while patching belong to the app.
+        scheduler::with_queue(&self.data.dom_ops, || self.render_now())
+    }
+
+    fn render_now(&self) {
+        if self.data.unmounted.get() {
+            return;
+        }
+        // The throttle window (if any) has ended - drop its fired timer.
+        self.data.throttled_render.borrow_mut().take();
+        let new_render_timestamp = self.cfg.clock.now_ms();
+
+        #[cfg(feature = "perf-budget")]
+        let measurement = self
+            .cfg
+            .perf_budget
+            .map(|budget| (budget, Measurement::render()));
+
+        // Read phase - all scheduled DOM reads run before the DOM is modified.
+        scheduler::flush_reads(&self.data.dom_ops);
 
         // Create a new vdom: The top element, and all its children. Does not yet
-        // have associated web_sys elements.
+        // have associated web_sys elements (except nodes of unchanged view regions).
         let mut new = El::empty(Tag::Placeholder);
-        new.children = (self.cfg.view)(self.data.model.borrow().as_ref().unwrap()).els();
+        // Views read the app's locale by `locale::current`.
+        let (mut old, flip_first) = locale::with_locale(&self.cfg.contexts, || {
+            lazy_hydration::with_hydrated_keys(&self.data.lazy_hydration, || {
+                let model = self.data.model.borrow();
+                let model = model.as_ref().unwrap();
+                new.children = self.view(model);
+                text_normalization::normalize_el(&mut new, self.cfg.text_normalization);
 
-        let mut old = self
-            .data
-            .main_el_vdom
-            .borrow_mut()
-            .take()
-            .expect("missing main_el_vdom");
+                // Taken after `view` has returned - the old vdom stays in place when `view` panics,
+                // so the next render patches it as usual.
+                let mut old = self
+                    .data
+                    .main_el_vdom
+                    .borrow_mut()
+                    .take()
+                    .expect("missing main_el_vdom");
+                // FLIP - positions of `flip_group` members before patching.
+                let flip_first = self.data.flip.first(&old);
+                let root_len = new.children.len();
+                new.children.extend(self.cfg.view_slices.render(
+                    model,
+                    &mut old,
+                    root_len,
+                    self.cfg.text_normalization,
+                ));
+                (old, flip_first)
+            })
+        });
+        // The URL may have been changed without `routes` (e.g. by `seed::push_route`).
+        self.data
+            .current_url
+            .replace(url::current_without_base_path(&self.cfg.base_path));
+        active_link::resolve_all(&mut new, &self.data.current_url.borrow());
+
+        if self.cfg.scroll_behavior == ScrollBehavior::RestoreOrTop {
+            // The old page is still rendered - its position is recorded now
+            // and the new one is applied after patching.
+            if let Some((x, y)) = self.data.scroll_positions.borrow_mut().before_render() {
+                self.data
+                    .after_next_render_callbacks
+                    .borrow_mut()
+                    .push(Box::new(move |_| {
+                        util::window().scroll_to_with_x_and_y(x, y);
+                        None
+                    }));
+            }
+        }
 
-        // Detach all old listeners before patching. We'll re-add them as required during patching.
-        // We'll get a runtime panic if any are left un-removed.
-        patch::detach_listeners(&mut old);
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        let guard_pause = crate::debug::ownership_guard::pause();
 
-        patch::patch_els(
-            &self.cfg.document,
-            &self.mailbox(),
-            &self.clone(),
-            &self.cfg.mount_point,
-            old.children.into_iter(),
-            new.children.iter_mut(),
-        );
+        // The old vdom (with its DOM and listeners) is kept when nothing has changed.
+        if self.data.adopted_by.get().is_none() && vdom_eq::same_nodes(&old.children, &new.children)
+        {
+            // Nodes of unchanged view regions have been moved to `new` - move them back.
+            for (old_child, new_child) in old.children.iter_mut().zip(new.children) {
+                let is_reused = match &new_child {
+                    Node::Element(el) => el.node_ws.is_some(),
+                    Node::Text(text) => text.node_ws.is_some(),
+                    Node::Empty => false,
+                };
+                if is_reused {
+                    *old_child = new_child;
+                }
+            }
+            new = old;
+        } else {
+            // Detach all old listeners before patching. We'll re-add them as required during patching.
+            // We'll get a runtime panic if any are left un-removed.
+            patch::detach_listeners(&mut old);
+            let old_refs = el_ref::collect_all(&old);
+
+            match self.data.adopted_by.replace(None) {
+                Some(MountType::Takeover) => {
+                    if let Some(mismatch) = hydration::first_mismatch(&old.children, &new.children)
+                    {
+                        web_sys::console::warn_1(
+                            &format!(
+                                "The server-rendered HTML doesn't match the view ({}) - it will be patched.",
+                                mismatch
+                            )
+                            .into(),
+                        );
+                    }
+                }
+                Some(MountType::Hydrate) => {
+                    hydration::hydrate(&mut old, &mut new, &self.cfg.mount_point);
+                    self.data.memory_counts.reset(Some(&old));
+                }
+                _ => (),
+            }
+
+            patch::patch_els(
+                &self.cfg.document,
+                &self.mailbox(),
+                &self.clone(),
+                &self.cfg.mount_point,
+                old.children.into_iter(),
+                new.children.iter_mut(),
+            );
+            dialog::sync_all(&new);
+            el_ref::resolve_all(&new, old_refs);
+        }
+        self.data.flip.play(&new, flip_first, &self.cfg.clock);
 
         // Now that we've re-rendered, replace our stored El with the new one;
         // it will be used as the old El next time.
         self.data.main_el_vdom.borrow_mut().replace(new);
 
+        // Write phase - scheduled out-of-tree DOM writes.
+        scheduler::flush_writes(&self.data.dom_ops);
+
+        // After the writes, so the new page's title is announced.
+        if self.data.route_focus_pending.replace(false) {
+            if let Some(route_focus) = self.cfg.route_focus {
+                route_focus::apply(route_focus, &self.data.current_url.borrow());
+            }
+        }
+
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        drop(guard_pause);
+
+        #[cfg(feature = "perf-budget")]
+        let mut effects = match measurement {
+            Some((budget, measurement)) => self
+                .send_perf_violation_msgs(measurement.finish(&budget, &self.data.perf_violations)),
+            None => VecDeque::new(),
+        };
+        #[cfg(not(feature = "perf-budget"))]
+        let mut effects: VecDeque<Effect<Ms, GMs>> = VecDeque::new();
+
         // Execute `after_next_render_callbacks`.
 
         let old_render_timestamp = self
@@ -425,14 +1339,48 @@ impl<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static> App<Ms, Mdl, ElC, GMs> {
             RenderTimestampDelta::new(new_render_timestamp - old_render_timestamp)
         });
 
-        self.process_cmd_and_msg_queue(
-            self.data
-                .after_next_render_callbacks
-                .replace(Vec::new())
+        if let Some(timestamp_delta) = timestamp_delta {
+            let long_frame_msgs = self
+                .data
+                .frame_stats
+                .borrow_mut()
+                .record(timestamp_delta.into(), new_render_timestamp);
+            effects.extend(long_frame_msgs.into_iter().map(Effect::Msg));
+        }
+
+        let render_info = RenderInfo {
+            timestamp: new_render_timestamp,
+            timestamp_delta,
+        };
+        // Taken before they are executed, so callbacks registered in the meantime
+        // wait for the next render.
+        let callbacks = self.data.after_next_render_callbacks.replace(Vec::new());
+
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        {
+            if let Some(check) = self.cfg.memory_growth_check {
+                let warnings = self.data.memory_growth.borrow_mut().record(
+                    &check,
+                    &self.data.current_url.borrow(),
+                    &crate::debug::memory_report(self),
+                );
+                for warning in warnings {
+                    web_sys::console::warn_1(&warning.into());
+                }
+            }
+        }
+        effects.extend(
+            callbacks
                 .into_iter()
-                .map(|callback| Effect::Msg(callback(timestamp_delta)))
-                .collect(),
+                .filter_map(|callback| callback(render_info).map(Effect::Msg)),
         );
+        self.process_cmd_and_msg_queue(effects);
+
+        let waiters = self.data.render_waiters.replace(Vec::new());
+        for waiter in waiters {
+            // It fails when the future has been dropped.
+            let _ = waiter.send(());
+        }
     }
 
     fn mailbox(&self) -> Mailbox<Ms> {
@@ -472,58 +1420,111 @@ impl<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static> App<Ms, Mdl, ElC, GMs> {
         self.data
             .main_el_vdom
             .replace(Some(self.bootstrap_vdom(mount_type)));
+        self.data
+            .memory_counts
+            .reset(self.data.main_el_vdom.borrow().as_ref());
 
         let mut orders = OrdersContainer::new(self.clone());
         let AfterMount {
             model,
             url_handling,
-        } = into_after_mount.into_after_mount(url::current(), &mut orders);
+        } = time::with_clock(&self.cfg.clock, || {
+            into_after_mount.into_after_mount(
+                url::current_without_base_path(&self.cfg.base_path),
+                &mut orders,
+            )
+        });
 
         self.data.model.replace(Some(model));
 
         match url_handling {
             UrlHandling::PassToRoutes => {
-                let url = url::current();
-                let routing_msg = self
-                    .data
-                    .routes
-                    .borrow()
-                    .as_ref()
-                    .and_then(|routes| routes(url));
-                if let Some(routing_msg) = routing_msg {
+                let url = url::current_without_base_path(&self.cfg.base_path);
+                if let Some(routing_msg) = self.route(url) {
                     orders.effects.push_back(routing_msg.into());
                 }
             }
             UrlHandling::None => (),
         };
+        if let Some(capabilities_msg) = self.cfg.capabilities_msg {
+            // Before other initial messages, so `update` can switch to a fallback first.
+            orders
+                .effects
+                .push_front(capabilities_msg(&self.cfg.capabilities).into());
+        }
 
         self.setup_window_listeners();
+        self.setup_input_modality_listeners();
+        self.setup_visibility_listener();
         patch::setup_input_listeners(&mut self.data.main_el_vdom.borrow_mut().as_mut().unwrap());
         patch::attach_listeners(
             self.data.main_el_vdom.borrow_mut().as_mut().unwrap(),
             &self.mailbox(),
         );
 
+        if self.cfg.scroll_behavior == ScrollBehavior::RestoreOrTop {
+            scroll_behavior::disable_browser_restoration();
+        }
+
         // Update the state on page load, based
         // on the starting URL. Must be set up on the server as well.
-        if let Some(routes) = *self.data.routes.borrow() {
+        if self.data.routes.borrow().is_some() {
             routing::setup_popstate_listener(
-                enclose!((self => s) move |msg| s.update(msg)),
+                self.cfg.base_path.clone(),
+                enclose!((self => s) move |source| {
+                    s.data.scroll_positions.borrow_mut().traversed();
+                    s.dispatch_navigation(source)
+                }),
                 enclose!((self => s) move |closure| {
                     s.data.popstate_closure.replace(Some(closure));
                 }),
-                routes,
             );
             routing::setup_hashchange_listener(
-                enclose!((self => s) move |msg| s.update(msg)),
+                enclose!((self => s) move |source| s.dispatch_navigation(source)),
                 enclose!((self => s) move |closure| {
                     s.data.hashchange_closure.replace(Some(closure));
                 }),
-                routes,
             );
-            routing::setup_link_listener(enclose!((self => s) move |msg| s.update(msg)), routes);
+            routing::setup_link_listener(
+                self.cfg.mount_point.clone(),
+                enclose!((self => s) move |source| s.dispatch_navigation(source)),
+                enclose!((self => s) move |closure| {
+                    s.data.link_closure.replace(Some(closure));
+                }),
+            );
+        }
+        if let Some(intercept_forms) = self.cfg.intercept_forms {
+            routing::setup_form_listener(
+                self.cfg.base_path.clone(),
+                enclose!((self => s) move |msg| s.update(msg)),
+                enclose!((self => s) move |url| {
+                    if s.data.routes.borrow().is_some() {
+                        s.navigate(url)
+                    } else {
+                        None
+                    }
+                }),
+                intercept_forms,
+                enclose!((self => s) move |closure| {
+                    s.data.form_closure.replace(Some(closure));
+                }),
+            );
+        }
+
+        #[cfg(feature = "devtools")]
+        crate::devtools::register(&self);
+
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        {
+            crate::debug::register_console_helper();
+            crate::debug::ownership_guard::install(&self.cfg.mount_point);
         }
 
+        #[cfg(all(feature = "dev-reload-ux", debug_assertions))]
+        crate::dev_reload::install();
+
+        page_meta::init(&self);
+
         self.process_cmd_and_msg_queue(orders.effects);
         // TODO: In the future, only run the following line if the above statement:
         //  - didn't force-rerender vdom
@@ -531,10 +1532,31 @@ impl<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static> App<Ms, Mdl, ElC, GMs> {
         //  - doesn't want to skip render
         self.rerender_vdom();
 
+        #[cfg(all(feature = "dev-reload-ux", debug_assertions))]
+        crate::dev_reload::restore();
+
         self
     }
 }
 
+/// Drop `future` when the app is unmounted - together with its timers and listeners.
+/// See `AppData::unmount_signal`.
+pub(crate) fn until_unmounted(
+    unmount_signal: Shared<oneshot::Receiver<()>>,
+    future: impl Future<Output = ()> + 'static,
+) -> impl Future<Output = ()> {
+    future::select(Box::pin(future), unmount_signal).map(|_| ())
+}
+
+/// Message of a panic caught by `catch_unwind` - see `AppBuilder::view_error_handler`.
+pub(crate) fn panic_message(payload: &(dyn std::any::Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("view panicked")
+}
+
 #[deprecated(since = "0.5.0", note = "Part of the old Init API.")]
 type InitAppBuilder<Ms, Mdl, ElC, GMs> = AppBuilder<
     Ms,
@@ -1,7 +1,17 @@
-use super::{types::*, App, AppInitCfg, OrdersContainer};
-use crate::browser::{url, Url};
-use crate::virtual_dom::View;
-use std::marker::PhantomData;
+use super::{
+    context::{self, ConfigError, ConfigLoader, Contexts},
+    preload,
+    types::*,
+    App, AppInitCfg, FrameStatsOpts, LocaleOpts, MemoryGrowthCheck, OrdersContainer, PageMeta,
+    PerfBudget, QueueBudget, RouteFocus, ScrollBehavior, ViewSlices,
+};
+use crate::browser::{
+    time::{BrowserClock, Clock},
+    url, Capabilities, Url,
+};
+use crate::virtual_dom::{renderer::Renderers, Node, Renderer, TextNormalization, View};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{marker::PhantomData, rc::Rc, time::Duration};
 
 pub mod after_mount;
 pub mod before_mount;
@@ -102,7 +112,8 @@ impl<
     > InitAPI<Ms, Mdl, ElC, GMs> for MountPointInitInitAPI<MP, II>
 {
     type Builder = Builder<Ms, Mdl, ElC, GMs, Self>;
-    fn build(builder: Self::Builder) -> App<Ms, Mdl, ElC, GMs> {
+    fn build(mut builder: Self::Builder) -> App<Ms, Mdl, ElC, GMs> {
+        builder.load_configs().expect("Problem loading app config");
         let MountPointInitInitAPI {
             into_init,
             mount_point,
@@ -110,16 +121,46 @@ impl<
 
         let mut app = App::new(
             builder.update,
-            builder.sink,
+            builder.update_middlewares,
+            builder.sinks,
+            builder.clone_g_msg,
             builder.view,
             mount_point.element_getter()(),
             builder.routes,
+            builder.not_found,
             builder.window_events,
+            builder.queue_budget,
+            builder.renderers,
+            builder.clock,
+            builder.contexts,
+            builder.perf_budget,
+            builder.perf_msg_name,
+            builder.base_meta,
+            builder.text_normalization,
+            builder.locale_opts,
+            builder.before_unmount,
+            builder.intercept_forms,
+            builder.frame_stats_opts,
+            builder.base_path,
+            builder.csp_nonce,
+            builder.view_slices,
+            builder.route_focus,
+            builder.scroll_behavior,
+            builder.view_error_handler,
+            builder.memory_growth_check,
+            builder.preload_ttl,
+            builder.devtools_codec,
+            builder.capabilities,
+            builder.capabilities_msg,
+            builder.framework_errors,
             None,
         );
 
         let mut initial_orders = OrdersContainer::new(app.clone());
-        let init = into_init.into_init(url::current(), &mut initial_orders);
+        let init = into_init.into_init(
+            url::current_without_base_path(&app.cfg.base_path),
+            &mut initial_orders,
+        );
 
         app.init_cfg.replace(AppInitCfg {
             mount_type: init.mount_type,
@@ -140,7 +181,8 @@ impl<
     > InitAPI<Ms, Mdl, ElC, GMs> for BeforeAfterInitAPI<IAM>
 {
     type Builder = Builder<Ms, Mdl, ElC, GMs, Self>;
-    fn build(builder: Self::Builder) -> App<Ms, Mdl, ElC, GMs> {
+    fn build(mut builder: Self::Builder) -> App<Ms, Mdl, ElC, GMs> {
+        builder.load_configs().expect("Problem loading app config");
         let BeforeAfterInitAPI {
             before_mount_handler,
             into_after_mount,
@@ -149,15 +191,42 @@ impl<
         let BeforeMount {
             mount_point_getter,
             mount_type,
-        } = before_mount_handler(url::current());
+        } = before_mount_handler(url::current_without_base_path(&builder.base_path));
 
         App::new(
             builder.update,
-            builder.sink,
+            builder.update_middlewares,
+            builder.sinks,
+            builder.clone_g_msg,
             builder.view,
             mount_point_getter(),
             builder.routes,
+            builder.not_found,
             builder.window_events,
+            builder.queue_budget,
+            builder.renderers,
+            builder.clock,
+            builder.contexts,
+            builder.perf_budget,
+            builder.perf_msg_name,
+            builder.base_meta,
+            builder.text_normalization,
+            builder.locale_opts,
+            builder.before_unmount,
+            builder.intercept_forms,
+            builder.frame_stats_opts,
+            builder.base_path,
+            builder.csp_nonce,
+            builder.view_slices,
+            builder.route_focus,
+            builder.scroll_behavior,
+            builder.view_error_handler,
+            builder.memory_growth_check,
+            builder.preload_ttl,
+            builder.devtools_codec,
+            builder.capabilities,
+            builder.capabilities_msg,
+            builder.framework_errors,
             Some(AppInitCfg {
                 mount_type,
                 into_after_mount: Box::new(into_after_mount),
@@ -172,16 +241,7 @@ impl<Ms: 'static, Mdl: 'static + Default, ElC: 'static + View<Ms>, GMs: 'static>
 {
     type Builder = Builder<Ms, Mdl, ElC, GMs, Self>;
     fn build(builder: Self::Builder) -> App<Ms, Mdl, ElC, GMs> {
-        BeforeAfterInitAPI::build(Builder {
-            update: builder.update,
-            view: builder.view,
-
-            routes: builder.routes,
-            window_events: builder.window_events,
-            sink: builder.sink,
-
-            init_api: BeforeAfterInitAPI::default(),
-        })
+        BeforeAfterInitAPI::build(builder.map_init_api(|_| BeforeAfterInitAPI::default()))
     }
 }
 
@@ -345,13 +405,97 @@ pub struct Builder<Ms: 'static, Mdl: 'static, ElC: View<Ms>, GMs, InitAPIType> {
     view: ViewFn<Mdl, ElC>,
 
     routes: Option<RoutesFn<Ms>>,
+    not_found: Option<NotFoundFn<Ms>>,
     window_events: Option<WindowEventsFn<Ms, Mdl>>,
-    sink: Option<SinkFn<Ms, Mdl, ElC, GMs>>,
+    sinks: Vec<SinkFn<Ms, Mdl, ElC, GMs>>,
+    clone_g_msg: Option<fn(&GMs) -> GMs>,
+    update_middlewares: Vec<UpdateMiddlewareFn<Ms, Mdl, ElC, GMs>>,
+    queue_budget: QueueBudget,
+    renderers: Renderers<Ms>,
+    clock: Rc<dyn Clock>,
+    config_loaders: Vec<ConfigLoader>,
+    contexts: Contexts,
+    perf_budget: Option<PerfBudget>,
+    perf_msg_name: Option<PerfMsgNameFn<Ms>>,
+    base_meta: PageMeta,
+    text_normalization: TextNormalization,
+    locale_opts: LocaleOpts,
+    before_unmount: Option<BeforeUnmountFn<Mdl>>,
+    intercept_forms: Option<InterceptFormsFn<Ms>>,
+    frame_stats_opts: FrameStatsOpts,
+    base_path: Vec<String>,
+    csp_nonce: Option<String>,
+    view_slices: ViewSlices<Ms, Mdl>,
+    route_focus: Option<RouteFocus>,
+    scroll_behavior: ScrollBehavior,
+    view_error_handler: Option<ViewErrorHandlerFn<Ms>>,
+    memory_growth_check: Option<MemoryGrowthCheck>,
+    preload_ttl: Duration,
+    devtools_codec: Option<MsgCodec<Ms>>,
+    capabilities: Capabilities,
+    capabilities_msg: Option<CapabilitiesMsgFn<Ms>>,
+    framework_errors: Option<FrameworkErrorsFn<Ms>>,
 
     // TODO: Remove when removing legacy init fields.
     init_api: InitAPIType,
 }
 
+impl<Ms, Mdl, ElC: View<Ms>, GMs, InitAPIType> Builder<Ms, Mdl, ElC, GMs, InitAPIType> {
+    /// Replace `init_api` and keep all other fields.
+    fn map_init_api<NewInitAPIType>(
+        self,
+        f: impl FnOnce(InitAPIType) -> NewInitAPIType,
+    ) -> Builder<Ms, Mdl, ElC, GMs, NewInitAPIType> {
+        Builder {
+            update: self.update,
+            view: self.view,
+
+            routes: self.routes,
+            not_found: self.not_found,
+            window_events: self.window_events,
+            sinks: self.sinks,
+            clone_g_msg: self.clone_g_msg,
+            update_middlewares: self.update_middlewares,
+            queue_budget: self.queue_budget,
+            renderers: self.renderers,
+            clock: self.clock,
+            config_loaders: self.config_loaders,
+            contexts: self.contexts,
+            perf_budget: self.perf_budget,
+            perf_msg_name: self.perf_msg_name,
+            base_meta: self.base_meta,
+            text_normalization: self.text_normalization,
+            locale_opts: self.locale_opts,
+            before_unmount: self.before_unmount,
+            intercept_forms: self.intercept_forms,
+            frame_stats_opts: self.frame_stats_opts,
+            base_path: self.base_path,
+            csp_nonce: self.csp_nonce,
+            view_slices: self.view_slices,
+            route_focus: self.route_focus,
+            scroll_behavior: self.scroll_behavior,
+            view_error_handler: self.view_error_handler,
+            memory_growth_check: self.memory_growth_check,
+            preload_ttl: self.preload_ttl,
+            devtools_codec: self.devtools_codec,
+            capabilities: self.capabilities,
+            capabilities_msg: self.capabilities_msg,
+            framework_errors: self.framework_errors,
+
+            init_api: f(self.init_api),
+        }
+    }
+
+    /// Execute config loaders and store loaded configs in `contexts`.
+    fn load_configs(&mut self) -> Result<(), ConfigError> {
+        for loader in self.config_loaders.drain(..) {
+            let (type_id, config) = loader()?;
+            self.contexts.insert(type_id, config);
+        }
+        Ok(())
+    }
+}
+
 impl<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static> Builder<Ms, Mdl, ElC, GMs, UndefinedInitAPI> {
     /// Constructs the Builder.
     pub(super) fn new(update: UpdateFn<Ms, Mdl, ElC, GMs>, view: ViewFn<Mdl, ElC>) -> Self {
@@ -360,8 +504,36 @@ impl<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static> Builder<Ms, Mdl, ElC, GMs,
             view,
 
             routes: None,
+            not_found: None,
             window_events: None,
-            sink: None,
+            sinks: Vec::new(),
+            clone_g_msg: None,
+            update_middlewares: Vec::new(),
+            queue_budget: QueueBudget::default(),
+            renderers: Renderers::new(),
+            clock: Rc::new(BrowserClock),
+            config_loaders: Vec::new(),
+            contexts: Contexts::new(),
+            perf_budget: None,
+            perf_msg_name: None,
+            base_meta: PageMeta::default(),
+            text_normalization: TextNormalization::default(),
+            locale_opts: LocaleOpts::default(),
+            before_unmount: None,
+            intercept_forms: None,
+            frame_stats_opts: FrameStatsOpts::default(),
+            base_path: Vec::new(),
+            csp_nonce: None,
+            view_slices: ViewSlices::new(),
+            route_focus: None,
+            scroll_behavior: ScrollBehavior::default(),
+            view_error_handler: None,
+            memory_growth_check: None,
+            preload_ttl: preload::DEFAULT_PRELOAD_TTL,
+            devtools_codec: None,
+            capabilities: Capabilities::new(),
+            capabilities_msg: None,
+            framework_errors: None,
 
             init_api: UndefinedInitAPI,
         }
@@ -387,16 +559,7 @@ impl<
         self,
         new_init: NewII,
     ) -> Builder<Ms, Mdl, ElC, GMs, MountPointInitInitAPI<MP, NewII>> {
-        Builder {
-            update: self.update,
-            view: self.view,
-
-            routes: self.routes,
-            window_events: self.window_events,
-            sink: self.sink,
-
-            init_api: self.init_api.init(new_init),
-        }
+        self.map_init_api(|init_api| init_api.init(new_init))
     }
 
     /// Choose the element where the application will be mounted.
@@ -424,16 +587,7 @@ impl<
         self,
         new_mount_point: NewMP,
     ) -> Builder<Ms, Mdl, ElC, GMs, MountPointInitInitAPI<NewMP, II>> {
-        Builder {
-            update: self.update,
-            view: self.view,
-
-            routes: self.routes,
-            window_events: self.window_events,
-            sink: self.sink,
-
-            init_api: self.init_api.mount(new_mount_point),
-        }
+        self.map_init_api(|init_api| init_api.mount(new_mount_point))
     }
 
     /// Select HTML element where the app will be mounted and how it'll be mounted.
@@ -453,16 +607,7 @@ impl<
         self,
         before_mount: impl FnOnce(Url) -> BeforeMount + 'static,
     ) -> Builder<Ms, Mdl, ElC, GMs, BeforeAfterInitAPI<IAM>> {
-        Builder {
-            update: self.update,
-            view: self.view,
-
-            routes: self.routes,
-            window_events: self.window_events,
-            sink: self.sink,
-
-            init_api: self.init_api.before_mount(Box::new(before_mount)),
-        }
+        self.map_init_api(|init_api| init_api.before_mount(Box::new(before_mount)))
     }
 
     /// You can create your `Model` and handle initial URL in this method.
@@ -481,16 +626,21 @@ impl<
         self,
         after_mount: AM,
     ) -> Builder<Ms, Mdl, ElC, GMs, BeforeAfterInitAPI<AM>> {
-        Builder {
-            update: self.update,
-            view: self.view,
-
-            routes: self.routes,
-            window_events: self.window_events,
-            sink: self.sink,
+        self.map_init_api(|init_api| init_api.after_mount(after_mount))
+    }
 
-            init_api: self.init_api.after_mount(after_mount),
-        }
+    /// Registers a function which is invoked with the model by `App::unmount`,
+    /// before the app's nodes are removed.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .before_unmount(|model: &Model| save_draft(&model.draft))
+    /// ```
+    pub fn before_unmount(mut self, before_unmount: impl FnOnce(&Mdl) + 'static) -> Self {
+        self.before_unmount = Some(Box::new(before_unmount));
+        self
     }
 
     /// Registers a function which maps URLs to messages.
@@ -500,6 +650,11 @@ impl<
     /// It's useful if the user clicked on a link and Seed shouldn't intercept it,
     /// because it's e.g. a download link.
     ///
+    /// Routing listeners are registered only for apps with `routes` - e.g. a widget embedded
+    /// in a page with another Seed app can leave routing to the other app.
+    /// Links are routed by the app with the innermost mount point containing them
+    /// (see `routing::setup_link_listener`).
+    ///
     /// # Example
     ///
     /// ```rust,no_run
@@ -512,8 +667,52 @@ impl<
         self
     }
 
+    /// Registers a function which creates a message for URLs which `routes` don't match
+    /// (i.e. `routes` return `None`). It's used for the initial URL (with `UrlHandling::PassToRoutes`),
+    /// history navigation and clicked links.
+    ///
+    /// It's ignored when `routes` aren't registered.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .routes(routes)
+    ///    .not_found(|url| Msg::ShowNotFound(url))
+    /// ```
+    pub fn not_found(mut self, not_found: NotFoundFn<Ms>) -> Self {
+        self.not_found = Some(not_found);
+        self
+    }
+
+    /// Intercept submissions of native forms - progressive enhancement of forms which work
+    /// also without JS. See `seed::browser::service::routing::form_interceptor`.
+    ///
+    /// `GET` submissions are routed by `routes` like clicked links. Other submissions
+    /// (and `GET` submissions not matched by `routes`) are passed to `intercept_forms` -
+    /// when it returns `None`, the form is submitted natively.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .routes(routes)
+    ///    .intercept_forms(|submission| match submission.method {
+    ///        Method::Post => Some(Msg::Submit(submission)),
+    ///        _ => None,
+    ///    })
+    /// ```
+    pub fn intercept_forms(mut self, intercept_forms: InterceptFormsFn<Ms>) -> Self {
+        self.intercept_forms = Some(intercept_forms);
+        self
+    }
+
     /// Registers a function which decides how window events will be handled.
     ///
+    /// The function is called and its listeners are diffed after each `update` call.
+    /// Prefer `Orders::subscribe_window_event` - it doesn't depend on the model
+    /// and its listener is added only once.
+    ///
     /// # Example
     ///
     /// ```rust,no_run
@@ -532,6 +731,8 @@ impl<
     /// on global messages. Consider to use a sink function when a
     /// submodule needs to trigger changes in other modules.
     ///
+    /// It replaces all previously registered sinks - use `add_sink` to register more of them.
+    ///
     /// # Example
     ///
     /// ```rust,no_run
@@ -542,7 +743,471 @@ impl<
     ///}
     /// ```
     pub fn sink(mut self, sink: SinkFn<Ms, Mdl, ElC, GMs>) -> Self {
-        self.sink = Some(sink);
+        self.sinks = vec![sink];
+        self
+    }
+
+    /// Registers an additional sink function.
+    ///
+    /// Every global message is passed to all sinks in registration order. Effects of their
+    /// orders are queued in the same order and the strongest requested render wins.
+    /// Global messages sent by a sink are queued like any other effect - i.e. each sink
+    /// receives every emitted message exactly once.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .add_sink(logging_sink)
+    ///    .add_sink(sink)
+    ///    .build_and_start();
+    /// ```
+    pub fn add_sink(mut self, sink: SinkFn<Ms, Mdl, ElC, GMs>) -> Self
+    where
+        GMs: Clone,
+    {
+        self.sinks.push(sink);
+        self.clone_g_msg = Some(GMs::clone);
+        self
+    }
+
+    /// Registers a middleware which wraps every `update` call - e.g. for logging, timing
+    /// or tracing. The middleware receives the message, the model, orders and `next` -
+    /// call `next` to pass the message further (or don't, to drop it).
+    ///
+    /// Middlewares compose in registration order - the first one is the outermost one
+    /// and `next` of the last one calls `update`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .update_middleware(|msg, model, orders, next| {
+    ///        log!("message", msg);
+    ///        next(msg, model, orders);
+    ///    })
+    ///    .build_and_start();
+    /// ```
+    pub fn update_middleware(
+        mut self,
+        middleware: impl Fn(
+                Ms,
+                &mut Mdl,
+                &mut OrdersContainer<Ms, Mdl, ElC, GMs>,
+                UpdateNext<'_, Ms, Mdl, ElC, GMs>,
+            ) + 'static,
+    ) -> Self {
+        self.update_middlewares.push(Box::new(middleware));
+        self
+    }
+
+    /// Limits how many messages are processed in one queue drain.
+    /// It protects the browser against freezing when messages invoke each other in a loop.
+    ///
+    /// Default: 10 000 messages, then the remaining ones are deferred to the next task.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .queue_budget(QueueBudget {
+    ///        max_messages: 1_000,
+    ///        on_exceeded: OnQueueBudgetExceeded::Warn,
+    ///    })
+    /// ```
+    pub fn queue_budget(mut self, queue_budget: QueueBudget) -> Self {
+        self.queue_budget = queue_budget;
+        self
+    }
+
+    /// Registers a custom renderer for children of elements marked by `rendered_by(name)`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .renderer("text_layout", TextLayoutRenderer::new())
+    /// ```
+    pub fn renderer(mut self, name: &'static str, renderer: impl Renderer<Ms> + 'static) -> Self {
+        self.renderers.insert(name, Rc::new(renderer));
+        self
+    }
+
+    /// Replaces the clock used by all time-dependent framework features.
+    /// Default: `BrowserClock`. Use `MockClock` in tests.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///let clock = MockClock::new();
+    ///App::builder(update, view)
+    ///    .clock(clock.clone())
+    /// ```
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Rc::new(clock);
+        self
+    }
+
+    /// Measures every `update` call and render and warns when they exceed `budget`.
+    /// See `seed::app::perf_budget` and `Orders::subscribe_perf_violations`.
+    ///
+    /// _Note:_ It requires the feature `perf-budget` - the budget is ignored without it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .perf_budget(PerfBudget {
+    ///        update_ms: 4.0,
+    ///        render_ms: 8.0,
+    ///        warn: WarnMode::ConsoleGrouped,
+    ///    })
+    /// ```
+    pub fn perf_budget(mut self, budget: PerfBudget) -> Self {
+        self.perf_budget = Some(budget);
+        self
+    }
+
+    /// Name messages in `AppBuilder::perf_budget` measurements and violations.
+    /// Messages are named only by their type without it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .perf_msg_name(|msg| match msg {
+    ///        Msg::Search(_) => "Search".to_owned(),
+    ///        Msg::ResultsFetched(_) => "ResultsFetched".to_owned(),
+    ///    })
+    /// ```
+    pub fn perf_msg_name(mut self, name: PerfMsgNameFn<Ms>) -> Self {
+        self.perf_msg_name = Some(name);
+        self
+    }
+
+    /// Take a `seed::debug::memory_report` after every render and warn when a count keeps
+    /// growing on an unchanged URL. See `seed::app::memory_growth`.
+    ///
+    /// _Note:_ It requires the feature `debug-tools` in debug builds - the check is ignored
+    /// without it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .memory_growth_check(MemoryGrowthCheck {
+    ///        idle_renders: 5,
+    ///        renders: 20,
+    ///    })
+    /// ```
+    pub fn memory_growth_check(mut self, check: MemoryGrowthCheck) -> Self {
+        self.memory_growth_check = Some(check);
+        self
+    }
+
+    /// How long links of `Orders::preload` stay in `<head>` when their handles are kept
+    /// (or forgotten). It's 5 minutes by default. See `seed::app::preload`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .preload_ttl(Duration::from_secs(60))
+    /// ```
+    pub fn preload_ttl(mut self, ttl: Duration) -> Self {
+        self.preload_ttl = ttl;
+        self
+    }
+
+    /// Replace the report about available browser APIs - e.g. by `Capabilities::mock`
+    /// to simulate a degraded environment in tests. See `seed::browser::capabilities`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .capabilities(Capabilities::mock(&[(Capability::IntersectionObserver, false)]))
+    /// ```
+    pub fn capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Send the message created from the report about available browser APIs to `update`
+    /// before all other initial messages - e.g. to switch to a "browser not supported" page.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .capabilities_msg(|capabilities| {
+    ///        Msg::CapabilitiesDetected(capabilities.assert_required(&[Capability::Fetch]))
+    ///    })
+    /// ```
+    pub fn capabilities_msg(mut self, handler: CapabilitiesMsgFn<Ms>) -> Self {
+        self.capabilities_msg = Some(handler);
+        self
+    }
+
+    /// Send `FrameworkError`s - e.g. an exceeded `queue_budget` - to `update`.
+    /// The errors are logged to the console also without the handler.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .framework_errors(|error| Some(Msg::FrameworkError(error)))
+    /// ```
+    pub fn framework_errors(mut self, handler: FrameworkErrorsFn<Ms>) -> Self {
+        self.framework_errors = Some(handler);
+        self
+    }
+
+    /// Serialize messages to JSON in the devtools message log and accept messages
+    /// dispatched by devtools (`seed::devtools::Request::Dispatch`).
+    ///
+    /// _Note:_ It requires the feature `devtools` - the codec is ignored without it.
+    /// The message log also requires the feature `debug-tools` in debug builds.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///#[derive(Serialize, Deserialize)]
+    ///enum Msg {
+    ///    Increment,
+    ///}
+    ///
+    ///App::builder(update, view)
+    ///    .devtools_codec()
+    /// ```
+    pub fn devtools_codec(mut self) -> Self
+    where
+        Ms: Serialize + DeserializeOwned,
+    {
+        self.devtools_codec = Some(MsgCodec {
+            serialize: |msg| serde_json::to_string(msg).ok(),
+            deserialize: |payload| serde_json::from_str(payload).map_err(|error| error.to_string()),
+        });
+        self
+    }
+
+    /// Document metadata inherited by all routes - route meta set by `Orders::set_page_meta`
+    /// overrides it field by field. See `seed::app::page_meta`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .base_meta(PageMeta {
+    ///        title: Some("Seed".to_owned()),
+    ///        description: Some("A Rust framework for creating web apps".to_owned()),
+    ///        ..PageMeta::default()
+    ///    })
+    /// ```
+    pub fn base_meta(mut self, meta: PageMeta) -> Self {
+        self.base_meta = meta;
+        self
+    }
+
+    /// Whitespace handling of text nodes in views and in the taken over mount point.
+    /// Defaults to `TextNormalization::Preserve`. See `seed::virtual_dom::text_normalization`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .text_normalization(TextNormalization::Collapse)
+    /// ```
+    pub fn text_normalization(mut self, mode: TextNormalization) -> Self {
+        self.text_normalization = mode;
+        self
+    }
+
+    /// Supported locales, the fallback locale and the storage key of the user's choice.
+    /// The locale is detected when the app is created. See `seed::app::locale`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .locale_opts(LocaleOpts {
+    ///        supported: vec!["en".to_owned(), "de".to_owned(), "cs".to_owned()],
+    ///        ..LocaleOpts::default()
+    ///    })
+    /// ```
+    pub fn locale_opts(mut self, opts: LocaleOpts) -> Self {
+        self.locale_opts = opts;
+        self
+    }
+
+    /// Buffer size and thresholds of the frame statistics. See `seed::app::frame_stats`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .frame_stats_opts(FrameStatsOpts {
+    ///        long_frame_ms: 100.,
+    ///        ..FrameStatsOpts::default()
+    ///    })
+    /// ```
+    pub fn frame_stats_opts(mut self, opts: FrameStatsOpts) -> Self {
+        self.frame_stats_opts = opts;
+        self
+    }
+
+    /// Path prefix of the app served from a sub-directory - e.g. `&["admin"]`
+    /// for `https://example.com/admin/`. It's removed from `Url`s passed to `routes`
+    /// (and to `init`) and `orders.push_route` prepends it. Links outside of the base path
+    /// aren't intercepted.
+    ///
+    /// _Note:_ The app-less `seed::push_route` doesn't know the base path.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .base_path(&["admin"])
+    /// ```
+    pub fn base_path(mut self, base_path: &[&str]) -> Self {
+        self.base_path = base_path.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    /// Content Security Policy nonce set on `<script>` and `<style>` elements created by Seed.
+    /// Defaults to the nonce of the first `<script nonce="..">` in the document.
+    /// See `seed::browser::dom::csp`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .csp_nonce(config.nonce.clone())
+    /// ```
+    pub fn csp_nonce(mut self, nonce: String) -> Self {
+        self.csp_nonce = Some(nonce);
+        self
+    }
+
+    /// Render parts of the page by region views which receive only their slice of the model.
+    /// Region nodes are rendered behind the nodes of `view` and a region is skipped
+    /// when its slice is equal to the slice of the previous render. See `seed::app::view_slices`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, header_view)
+    ///    .view_with_slices(|slices| {
+    ///        slices
+    ///            .region(|model| &model.cart, cart::view)
+    ///            .region(|model| &model.footer, footer::view)
+    ///    })
+    /// ```
+    pub fn view_with_slices(
+        mut self,
+        slices: impl FnOnce(ViewSlices<Ms, Mdl>) -> ViewSlices<Ms, Mdl>,
+    ) -> Self {
+        self.view_slices = slices(self.view_slices);
+        self
+    }
+
+    /// Move focus to `target` after the render which follows a navigation and announce
+    /// the new document title to screen readers. See `seed::app::route_focus`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .routes(routes)
+    ///    .route_focus(RouteFocus::Element("h1"))
+    /// ```
+    pub fn route_focus(mut self, target: RouteFocus) -> Self {
+        self.route_focus = Some(target);
+        self
+    }
+
+    /// Restore scroll positions on back / forward navigations and scroll to the top
+    /// on the other ones with `ScrollBehavior::RestoreOrTop`. Back / forward navigations
+    /// are detected only when `routes` are set. See `seed::app::scroll_behavior`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .routes(routes)
+    ///    .scroll_behavior(ScrollBehavior::RestoreOrTop)
+    /// ```
+    pub fn scroll_behavior(mut self, scroll_behavior: ScrollBehavior) -> Self {
+        self.scroll_behavior = scroll_behavior;
+        self
+    }
+
+    /// Shorthand for `scroll_behavior` - `true` is `ScrollBehavior::RestoreOrTop`
+    /// and `false` is `ScrollBehavior::Browser`.
+    pub fn manage_scroll_restoration(self, manage: bool) -> Self {
+        self.scroll_behavior(if manage {
+            ScrollBehavior::RestoreOrTop
+        } else {
+            ScrollBehavior::Browser
+        })
+    }
+
+    /// Render `view_error_handler`'s fallback instead of the view when `view` panics.
+    /// The handler receives the panic message. Panics in `update` aren't caught.
+    ///
+    /// _Note:_ Panics are caught only when they unwind - the default `wasm32-unknown-unknown`
+    /// profile aborts on panic, so the handler is invoked only with `panic = "unwind"`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .view_error_handler(|message| div![class!["error"], "Something went wrong: ", message])
+    /// ```
+    pub fn view_error_handler(
+        mut self,
+        view_error_handler: impl Fn(&str) -> Node<Ms> + 'static,
+    ) -> Self {
+        self.view_error_handler = Some(Box::new(view_error_handler));
+        self
+    }
+
+    /// Loads config of type `C` from JSON content of the element with `element_id`
+    /// (e.g. `<script type="application/json" id="seed-config">`, rendered by the server).
+    /// The config is available through `orders.context::<C>()`.
+    ///
+    /// Use `try_build_and_start` to handle a missing element or invalid JSON.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///#[derive(Deserialize)]
+    ///struct Config {
+    ///    api_url: String,
+    ///}
+    ///
+    ///App::builder(update, view)
+    ///    .config_from_element::<Config>("seed-config")
+    ///    .try_build_and_start()
+    /// ```
+    pub fn config_from_element<C: DeserializeOwned + 'static>(
+        mut self,
+        element_id: impl Into<String>,
+    ) -> Self {
+        self.config_loaders
+            .push(context::config_loader::<C>(element_id.into(), None));
+        self
+    }
+
+    /// Similar to `config_from_element`, but `default` is used when the element doesn't exist.
+    /// Invalid JSON is still an error.
+    pub fn config_from_element_or<C: DeserializeOwned + 'static>(
+        mut self,
+        element_id: impl Into<String>,
+        default: C,
+    ) -> Self {
+        self.config_loaders
+            .push(context::config_loader(element_id.into(), Some(default)));
         self
     }
 }
@@ -556,9 +1221,31 @@ impl<
     > Builder<Ms, Mdl, ElC, GMs, InitAPIType>
 {
     /// Build, mount and start the app.
+    ///
+    /// # Panics
+    ///
+    /// Panics when a config can't be loaded - see `try_build_and_start`.
     pub fn build_and_start(self) -> App<Ms, Mdl, ElC, GMs> {
         InitAPIType::build(self).run()
     }
+
+    /// Similar to `build_and_start`, but configs are loaded first and loading errors are
+    /// returned instead of panicking. The app isn't created when an error occurs.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///let result = App::builder(update, view)
+    ///    .config_from_element::<Config>("seed-config")
+    ///    .try_build_and_start();
+    ///if let Err(error) = result {
+    ///    seed::error(error.to_string());
+    ///}
+    /// ```
+    pub fn try_build_and_start(mut self) -> Result<App<Ms, Mdl, ElC, GMs>, ConfigError> {
+        self.load_configs()?;
+        Ok(self.build_and_start())
+    }
 }
 
 impl<
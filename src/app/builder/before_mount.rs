@@ -44,9 +44,15 @@ pub enum MountType {
     /// Take control of previously existing elements in the mount. This does not make guarantees of
     /// elements added after the [`App`] has been mounted.
     ///
-    /// Note that existing elements in the DOM will be recreated. This can be dangerous for script
-    /// tags and other, similar tags.
+    /// Existing DOM nodes are reused - the first render only patches the differences
+    /// between them and the `view` (a warning is logged when the structure differs).
+    /// Formatting whitespace between elements is removed.
     Takeover,
+    /// Like `Takeover`, but made for server-rendered HTML - existing DOM nodes are matched
+    /// against the first render and only their attributes, texts and listeners are fixed.
+    /// When the structure (tags or numbers of children) of an element's children differs,
+    /// a warning is logged and the children are recreated.
+    Hydrate,
     /// Leave the previously existing elements in the mount alone. This does not make guarantees of
     /// elements added after the [`App`] has been mounted.
     Append,
@@ -1,6 +1,14 @@
-use super::{builder::IntoAfterMount, types::*, MountType};
-use crate::virtual_dom::View;
-use std::marker::PhantomData;
+#[cfg(feature = "perf-budget")]
+use super::PerfBudget;
+use super::{
+    builder::IntoAfterMount, context::Contexts, types::*, LocaleOpts, MemoryGrowthCheck, MountType,
+    PageMeta, QueueBudget, RouteFocus, ScrollBehavior, ViewSlices,
+};
+use crate::{
+    browser::{time::Clock, Capabilities},
+    virtual_dom::{renderer::Renderers, TextNormalization, View},
+};
+use std::{marker::PhantomData, rc::Rc, time::Duration};
 
 #[allow(clippy::module_name_repetitions)]
 pub struct AppInitCfg<Ms, Mdl, ElC, GMs, IAM: ?Sized>
@@ -25,7 +33,43 @@ where
     pub document: web_sys::Document,
     pub mount_point: web_sys::Element,
     pub update: UpdateFn<Ms, Mdl, ElC, GMs>,
-    pub sink: Option<SinkFn<Ms, Mdl, ElC, GMs>>,
+    /// See `AppBuilder::update_middleware`.
+    pub update_middlewares: Vec<UpdateMiddlewareFn<Ms, Mdl, ElC, GMs>>,
+    pub sinks: Vec<SinkFn<Ms, Mdl, ElC, GMs>>,
+    /// Set by `AppBuilder::add_sink` - global messages are cloned for all sinks but the last one.
+    pub clone_g_msg: Option<fn(&GMs) -> GMs>,
     pub view: ViewFn<Mdl, ElC>,
     pub window_events: Option<WindowEventsFn<Ms, Mdl>>,
+    pub not_found: Option<NotFoundFn<Ms>>,
+    pub intercept_forms: Option<InterceptFormsFn<Ms>>,
+    /// See `AppBuilder::capabilities`. `Orders` methods pass it to services
+    /// - see `seed::browser::capabilities`.
+    pub capabilities: Capabilities,
+    /// See `AppBuilder::capabilities_msg`.
+    pub capabilities_msg: Option<CapabilitiesMsgFn<Ms>>,
+    /// See `AppBuilder::framework_errors`.
+    pub framework_errors: Option<FrameworkErrorsFn<Ms>>,
+    pub queue_budget: QueueBudget,
+    pub(crate) renderers: Renderers<Ms>,
+    pub clock: Rc<dyn Clock>,
+    pub(crate) contexts: Contexts,
+    #[cfg(feature = "perf-budget")]
+    pub perf_budget: Option<PerfBudget>,
+    /// See `AppBuilder::perf_msg_name`.
+    #[cfg(feature = "perf-budget")]
+    pub perf_msg_name: Option<PerfMsgNameFn<Ms>>,
+    pub(crate) base_meta: PageMeta,
+    pub text_normalization: TextNormalization,
+    pub locale_opts: LocaleOpts,
+    pub base_path: Vec<String>,
+    pub csp_nonce: Option<String>,
+    pub(crate) view_slices: ViewSlices<Ms, Mdl>,
+    pub route_focus: Option<RouteFocus>,
+    pub scroll_behavior: ScrollBehavior,
+    pub view_error_handler: Option<ViewErrorHandlerFn<Ms>>,
+    pub memory_growth_check: Option<MemoryGrowthCheck>,
+    /// See `AppBuilder::preload_ttl`.
+    pub preload_ttl: Duration,
+    /// See `AppBuilder::devtools_codec`.
+    pub devtools_codec: Option<MsgCodec<Ms>>,
 }
@@ -0,0 +1,53 @@
+use futures::future::{self, AbortHandle, FutureExt};
+use std::{convert::identity, fmt, future::Future};
+
+/// Handle of a command performed by `orders.perform_cmd_with_handle`.
+/// Dropping the handle (or calling `cancel`) aborts the command - its message isn't sent.
+///
+/// Store it in your model, so the command is aborted when it isn't needed anymore
+/// (e.g. a fetch for a page the user has left).
+#[must_use = "the command is aborted when the handle is dropped"]
+pub struct CmdHandle {
+    abort_handle: AbortHandle,
+    /// Removes the command from a lane's queue - see `Orders::perform_cmd_in_lane`.
+    on_cancel: Option<Box<dyn FnOnce()>>,
+}
+
+impl CmdHandle {
+    /// Abort the command.
+    pub fn cancel(self) {}
+
+    /// Call `on_cancel` when the handle is dropped.
+    pub(crate) fn on_cancel(mut self, on_cancel: impl FnOnce() + 'static) -> Self {
+        self.on_cancel = Some(Box::new(on_cancel));
+        self
+    }
+}
+
+impl fmt::Debug for CmdHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CmdHandle({:?})", self.abort_handle)
+    }
+}
+
+impl Drop for CmdHandle {
+    fn drop(&mut self) {
+        self.abort_handle.abort();
+        if let Some(on_cancel) = self.on_cancel.take() {
+            on_cancel();
+        }
+    }
+}
+
+/// Make `cmd` abortable by the returned handle. The future resolves with `None` when aborted.
+pub(crate) fn abortable<Ms>(
+    cmd: impl Future<Output = Option<Ms>> + 'static,
+) -> (impl Future<Output = Option<Ms>>, CmdHandle) {
+    let (cmd, abort_handle) = future::abortable(cmd);
+    let cmd = cmd.map(|result| result.ok().and_then(identity));
+    let handle = CmdHandle {
+        abort_handle,
+        on_cancel: None,
+    };
+    (cmd, handle)
+}
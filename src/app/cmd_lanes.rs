@@ -0,0 +1,257 @@
+//! Commands with bounded concurrency. See `Orders::perform_cmd_in_lane`.
+//!
+//! Semantics:
+//! - A lane is identified by its name. Its config is registered by the first command
+//!   performed in it - later commands with another config for the same name
+//!   use the registered one.
+//! - At most `max_concurrent` commands of the lane run at the same time. The other ones wait
+//!   in the lane's queue and they are started in the submission order as running commands finish.
+//! - When the queue is full (see `Lane::queue_cap`), `QueueOverflow::DropOldest` drops
+//!   the oldest waiting command and `QueueOverflow::Reject` drops the new one.
+//!   Dropped commands are never started.
+//! - Dropping a `CmdHandle` of a waiting command removes it from the queue - it's never started.
+
+use super::App;
+use crate::virtual_dom::View;
+use futures::future::{FutureExt, LocalFutureObj};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    rc::{Rc, Weak},
+};
+
+// ------ Lane ------
+
+/// Config of a command lane - see `Orders::perform_cmd_in_lane`.
+///
+/// # Example
+///
+/// ```rust,no_run
+///const MAX_THUMBNAIL_FETCHES: usize = 4;
+///
+///fn thumbnails_lane() -> Lane {
+///    Lane::named("thumbnails", MAX_THUMBNAIL_FETCHES).queue_cap(100, QueueOverflow::DropOldest)
+///}
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lane {
+    name: String,
+    max_concurrent: usize,
+    queue_cap: Option<(usize, QueueOverflow)>,
+}
+
+impl Lane {
+    /// A lane running at most `max_concurrent` commands at the same time.
+    /// `max_concurrent` is at least 1.
+    pub fn named(name: impl Into<String>, max_concurrent: usize) -> Self {
+        Self {
+            name: name.into(),
+            max_concurrent: max_concurrent.max(1),
+            queue_cap: None,
+        }
+    }
+
+    /// Limit the number of waiting commands. The queue is unbounded by default.
+    pub fn queue_cap(mut self, cap: usize, overflow: QueueOverflow) -> Self {
+        self.queue_cap = Some((cap, overflow));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// What happens with a new command when the lane's queue is full - see `Lane::queue_cap`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueueOverflow {
+    /// Drop the oldest waiting command and enqueue the new one.
+    DropOldest,
+    /// Drop the new command.
+    Reject,
+}
+
+/// Numbers of commands in a lane - see `Orders::lane_stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LaneStats {
+    pub running: usize,
+    pub queued: usize,
+}
+
+// ------ LaneState ------
+
+type QueuedCmd<Ms> = (u64, LocalFutureObj<'static, Option<Ms>>);
+
+pub(crate) struct LaneState<Ms> {
+    lane: Lane,
+    running: usize,
+    queue: VecDeque<QueuedCmd<Ms>>,
+    next_id: u64,
+}
+
+impl<Ms> LaneState<Ms> {
+    fn new(lane: Lane) -> Self {
+        Self {
+            lane,
+            running: 0,
+            queue: VecDeque::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Enqueue `cmd`. Returns its id or `None` when it has been rejected.
+    fn enqueue(&mut self, cmd: LocalFutureObj<'static, Option<Ms>>) -> Option<u64> {
+        if let Some((cap, overflow)) = self.lane.queue_cap {
+            if self.queue.len() >= cap {
+                match overflow {
+                    QueueOverflow::DropOldest => {
+                        if self.queue.pop_front().is_none() {
+                            // Zero cap.
+                            return None;
+                        }
+                    }
+                    QueueOverflow::Reject => return None,
+                }
+            }
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.queue.push_back((id, cmd));
+        Some(id)
+    }
+
+    /// Take the next command when the lane has a free slot.
+    fn start_next(&mut self) -> Option<LocalFutureObj<'static, Option<Ms>>> {
+        if self.running >= self.lane.max_concurrent {
+            return None;
+        }
+        let (_, cmd) = self.queue.pop_front()?;
+        self.running += 1;
+        Some(cmd)
+    }
+
+    fn stats(&self) -> LaneStats {
+        LaneStats {
+            running: self.running,
+            queued: self.queue.len(),
+        }
+    }
+}
+
+/// Lanes by their names.
+pub(crate) type CmdLanes<Ms> = HashMap<String, Rc<RefCell<LaneState<Ms>>>>;
+
+/// Enqueue `cmd` in `lane` and start the lane's commands while it has free slots.
+/// Returns the function which removes `cmd` from the queue (if it's still waiting)
+/// or `None` when `cmd` has been rejected.
+pub(crate) fn perform<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    lane: &Lane,
+    cmd: LocalFutureObj<'static, Option<Ms>>,
+) -> Option<impl FnOnce() + 'static> {
+    let state = Rc::clone(
+        app.data
+            .cmd_lanes
+            .borrow_mut()
+            .entry(lane.name.clone())
+            .or_insert_with(|| Rc::new(RefCell::new(LaneState::new(lane.clone())))),
+    );
+    let id = state.borrow_mut().enqueue(cmd);
+    let weak_state = Rc::downgrade(&state);
+    start_ready(app, &state);
+    id.map(|id| remove_queued(weak_state, id))
+}
+
+/// Numbers of running and waiting commands in `lane`.
+pub(crate) fn stats<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    lane: &Lane,
+) -> LaneStats {
+    app.data
+        .cmd_lanes
+        .borrow()
+        .get(&lane.name)
+        .map(|state| state.borrow().stats())
+        .unwrap_or_default()
+}
+
+/// Drop waiting commands of all lanes - they are never started.
+pub(crate) fn clear_queues<Ms>(lanes: &CmdLanes<Ms>) {
+    for state in lanes.values() {
+        state.borrow_mut().queue.clear();
+    }
+}
+
+/// Create the function which removes the command with `id` from the queue.
+fn remove_queued<Ms: 'static>(
+    state: Weak<RefCell<LaneState<Ms>>>,
+    id: u64,
+) -> impl FnOnce() + 'static {
+    move || {
+        if let Some(state) = state.upgrade() {
+            state
+                .borrow_mut()
+                .queue
+                .retain(|(queued_id, _)| *queued_id != id);
+        }
+    }
+}
+
+fn start_ready<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    state: &Rc<RefCell<LaneState<Ms>>>,
+) {
+    loop {
+        let cmd = match state.borrow_mut().start_next() {
+            Some(cmd) => cmd,
+            None => return,
+        };
+        let finished_app = app.clone();
+        let finished_state = Rc::clone(state);
+        let cmd = cmd.map(move |msg| {
+            finished_state.borrow_mut().running -= 1;
+            start_ready(&finished_app, &finished_state);
+            msg
+        });
+        app.process_queue_cmd(cmd);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn cmd(value: u32) -> LocalFutureObj<'static, Option<u32>> {
+        LocalFutureObj::new(Box::new(future::ready(Some(value))))
+    }
+
+    #[wasm_bindgen_test]
+    fn queue_overflow() {
+        let mut state = LaneState::new(Lane::named("a", 1).queue_cap(2, QueueOverflow::Reject));
+        assert!(state.enqueue(cmd(1)).is_some());
+        assert!(state.enqueue(cmd(2)).is_some());
+        assert_eq!(state.enqueue(cmd(3)), None);
+
+        let mut state = LaneState::new(Lane::named("b", 1).queue_cap(2, QueueOverflow::DropOldest));
+        let first = state.enqueue(cmd(1));
+        state.enqueue(cmd(2));
+        state.enqueue(cmd(3));
+        let ids = state
+            .queue
+            .iter()
+            .map(|(id, _)| Some(*id))
+            .collect::<Vec<_>>();
+        assert!(!ids.contains(&first));
+        assert_eq!(
+            state.stats(),
+            LaneStats {
+                running: 0,
+                queued: 2
+            }
+        );
+    }
+}
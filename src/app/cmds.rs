@@ -0,0 +1,35 @@
+//! Commands for `orders.perform_cmd` and `orders.perform_cmd_with_handle`.
+
+use crate::browser::time;
+use futures::channel::oneshot;
+use std::future::Future;
+
+/// Command which resolves to `handler`'s message after `ms` milliseconds.
+///
+/// The timer is started immediately - e.g. timeouts created in `after_mount`
+/// fire as soon as their delay elapses. It's scheduled by the app's clock
+/// (see `seed::browser::time::current_clock`). Dropping the command (e.g. by dropping the handle
+/// of `orders.perform_cmd_with_handle`) clears the timer.
+///
+/// # Example
+///
+/// ```rust,no_run
+///orders.perform_cmd(cmds::timeout(2_000, || Msg::HideNotification));
+///model.autosave = Some(orders.perform_cmd_with_handle(cmds::timeout(500, || Msg::Save)));
+/// ```
+pub fn timeout<MsU>(ms: u32, handler: impl FnOnce() -> MsU + 'static) -> impl Future<Output = MsU> {
+    let (sender, receiver) = oneshot::channel();
+    let timeout = time::current_clock().set_timeout(
+        ms,
+        Box::new(move || {
+            // The receiver is dropped only together with `timeout`.
+            let _ = sender.send(());
+        }),
+    );
+    async move {
+        // `timeout` is moved into the future, so the timer is cleared when the future is dropped.
+        let _timeout = timeout;
+        let _ = receiver.await;
+        handler()
+    }
+}
@@ -0,0 +1,153 @@
+//! Typed values shared by the whole app - e.g. runtime configuration injected by the server.
+//! See `AppBuilder::config_from_element` and `Orders::context`.
+
+use crate::browser::util;
+use serde::de::DeserializeOwned;
+use std::{any::Any, any::TypeId, collections::HashMap, error::Error, fmt, rc::Rc};
+
+pub(crate) type Contexts = HashMap<TypeId, Rc<dyn Any>>;
+
+pub(crate) type ConfigLoader = Box<dyn FnOnce() -> Result<(TypeId, Rc<dyn Any>), ConfigError>>;
+
+/// Error of configuration loading. See `AppBuilder::try_build_and_start`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// Element with the given id doesn't exist.
+    MissingElement { element_id: String },
+    /// Element's content isn't valid JSON or it doesn't match the config type.
+    InvalidJson { element_id: String, error: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::MissingElement { element_id } => {
+                write!(f, "Config element with id \"{}\" is missing", element_id)
+            }
+            ConfigError::InvalidJson { element_id, error } => write!(
+                f,
+                "Config element with id \"{}\" contains invalid JSON: {}",
+                element_id, error
+            ),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+/// Create a loader which deserializes `C` from the text content of the element with `element_id`.
+/// `default` is used when the element doesn't exist.
+pub(crate) fn config_loader<C: DeserializeOwned + 'static>(
+    element_id: String,
+    default: Option<C>,
+) -> ConfigLoader {
+    Box::new(move || {
+        let config = match util::document().get_element_by_id(&element_id) {
+            Some(element) => {
+                parse_config::<C>(&element_id, &element.text_content().unwrap_or_default())?
+            }
+            None => default.ok_or_else(|| ConfigError::MissingElement {
+                element_id: element_id.clone(),
+            })?,
+        };
+        Ok((TypeId::of::<C>(), Rc::new(config) as Rc<dyn Any>))
+    })
+}
+
+fn parse_config<C: DeserializeOwned>(element_id: &str, json: &str) -> Result<C, ConfigError> {
+    serde_json::from_str(json).map_err(|error| ConfigError::InvalidJson {
+        element_id: element_id.to_owned(),
+        error: error.to_string(),
+    })
+}
+
+/// Get the context value of type `C`.
+pub(crate) fn get<C: 'static>(contexts: &Contexts) -> Option<Rc<C>> {
+    contexts
+        .get(&TypeId::of::<C>())
+        .cloned()
+        .and_then(|context| context.downcast::<C>().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Config {
+        api_url: String,
+        new_checkout: bool,
+    }
+
+    fn load(element_id: &str, default: Option<Config>) -> Result<Option<Rc<Config>>, ConfigError> {
+        let (type_id, config) = config_loader::<Config>(element_id.to_owned(), default)()?;
+        let mut contexts = Contexts::new();
+        contexts.insert(type_id, config);
+        Ok(get::<Config>(&contexts))
+    }
+
+    fn add_config_element(id: &str, json: &str) -> web_sys::Element {
+        let element = util::document().create_element("script").unwrap();
+        element.set_attribute("type", "application/json").unwrap();
+        element.set_id(id);
+        element.set_text_content(Some(json));
+        util::body().append_child(&element).unwrap();
+        element
+    }
+
+    #[wasm_bindgen_test]
+    fn config_from_element() {
+        let element = add_config_element(
+            "seed-config-valid",
+            r#"{"api_url": "https://staging.example.com", "new_checkout": true}"#,
+        );
+
+        assert_eq!(
+            load("seed-config-valid", None),
+            Ok(Some(Rc::new(Config {
+                api_url: "https://staging.example.com".to_owned(),
+                new_checkout: true,
+            })))
+        );
+        element.remove();
+    }
+
+    #[wasm_bindgen_test]
+    fn missing_config_element() {
+        assert_eq!(
+            load("seed-config-missing", None),
+            Err(ConfigError::MissingElement {
+                element_id: "seed-config-missing".to_owned()
+            })
+        );
+
+        let default = Config {
+            api_url: "/api".to_owned(),
+            new_checkout: false,
+        };
+        assert_eq!(
+            load("seed-config-missing", Some(default))
+                .unwrap()
+                .unwrap()
+                .api_url,
+            "/api"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn malformed_config() {
+        let element = add_config_element("seed-config-malformed", r#"{"api_url": "/api""#);
+
+        match load("seed-config-malformed", None) {
+            Err(ConfigError::InvalidJson { element_id, .. }) => {
+                assert_eq!(element_id, "seed-config-malformed")
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+        element.remove();
+    }
+}
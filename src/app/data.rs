@@ -1,7 +1,31 @@
-use super::{render_timestamp_delta::RenderTimestamp, types::*, RenderTimestampDelta};
-use crate::browser::util;
-use crate::virtual_dom::{El, Listener};
-use std::cell::{Cell, RefCell};
+#[cfg(all(feature = "debug-tools", debug_assertions))]
+use super::memory_growth::MemoryGrowth;
+#[cfg(feature = "perf-budget")]
+use super::perf_budget::PerfViolations;
+use super::{
+    cmd_lanes::CmdLanes, debounced_cmds::DebouncedCmds, deferred_navigation::DeferredNavigation,
+    effects::Effect, frame_stats::FrameStats, memory_growth::MemoryCounts,
+    msg_listeners::MsgListeners, msg_subscriptions::MsgSubscriptions,
+    offline_retry::OfflineRetries, page_meta::PageMetaState, ports::Ports, preload::Preloads,
+    render_timestamp_delta::RenderTimestamp, scroll_behavior::ScrollPositions, subs::Subscriptions,
+    types::*, MountType, UndefinedGMsg,
+};
+use crate::browser::{
+    dom::{flip::FlipAnimations, scheduler::SharedQueue},
+    input_modality::{ModalityListeners, ModalityTracker},
+    time::TimeoutHandle,
+    util,
+    visibility::ResumeListener,
+    Modality, Url,
+};
+use crate::virtual_dom::{keep_alive::ParkedEls, lazy_hydration::LazyHydration, El, Listener};
+use futures::channel::{mpsc::UnboundedSender, oneshot};
+use futures::future::Shared;
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    rc::Weak,
+};
 use wasm_bindgen::closure::Closure;
 
 // TODO: Examine what needs to be ref cells, rcs etc
@@ -10,17 +34,88 @@ type StoredPopstate = RefCell<Option<Closure<dyn FnMut(web_sys::Event)>>>;
 
 /// Used as part of an interior-mutability pattern, ie Rc<RefCell<>>
 #[allow(clippy::type_complexity, clippy::module_name_repetitions)]
-pub struct AppData<Ms: 'static, Mdl> {
+pub struct AppData<Ms: 'static, Mdl, GMs = UndefinedGMsg> {
     // Model is in a RefCell here so we can modify it in self.update().
     pub model: RefCell<Option<Mdl>>,
     pub main_el_vdom: RefCell<Option<El<Ms>>>,
     pub popstate_closure: StoredPopstate,
     pub hashchange_closure: StoredPopstate,
+    pub(crate) link_closure: StoredPopstate,
+    /// See `AppBuilder::intercept_forms`.
+    pub(crate) form_closure: StoredPopstate,
     pub routes: RefCell<Option<RoutesFn<Ms>>>,
     pub window_listeners: RefCell<Vec<Listener<Ms>>>,
-    pub msg_listeners: RefCell<MsgListeners<Ms>>,
+    /// See `App::add_message_listener`.
+    pub(crate) msg_listeners: RefCell<MsgListeners<Ms>>,
+    /// See `App::subscribe`.
+    pub(crate) msg_subscriptions: RefCell<MsgSubscriptions<Ms>>,
+    pub(crate) subscriptions: RefCell<Subscriptions<Ms>>,
+    /// Effects waiting for processing - see `App::process_cmd_and_msg_queue`.
+    pub(crate) effect_queue: RefCell<VecDeque<Effect<Ms, GMs>>>,
+    /// Animation frame shared by the scheduled render and animation frame streams.
     pub scheduled_render_handle: RefCell<Option<util::RequestAnimationFrameHandle>>,
-    pub after_next_render_callbacks:
-        RefCell<Vec<Box<dyn FnOnce(Option<RenderTimestampDelta>) -> Ms>>>,
+    /// Is `rerender_vdom` waiting for the next animation frame?
+    pub(crate) render_scheduled: Cell<bool>,
+    /// See `orders.render_throttle`.
+    pub(crate) render_throttle_ms: Cell<u32>,
+    /// Timer of the render postponed by `render_throttle_ms`.
+    pub(crate) throttled_render: RefCell<Option<TimeoutHandle>>,
+    /// See `orders.request_animation_frame_stream`.
+    pub(crate) animation_frame_senders: RefCell<Vec<UnboundedSender<RenderTimestamp>>>,
+    pub after_next_render_callbacks: RefCell<Vec<AfterNextRenderCallback<Ms>>>,
     pub render_timestamp: Cell<Option<RenderTimestamp>>,
+    /// See `orders.frame_stats`.
+    pub(crate) frame_stats: RefCell<FrameStats<Ms>>,
+    pub input_modality_tracker: RefCell<ModalityTracker>,
+    /// Handlers are removed when their `SubHandle`s are dropped.
+    pub input_modality_handlers: RefCell<Vec<(Weak<()>, Box<dyn Fn(Modality) -> Ms>)>>,
+    pub input_modality_listeners: RefCell<Option<ModalityListeners>>,
+    /// Sends `VisibilityResumed` to subscribers.
+    pub(crate) visibility_listener: RefCell<Option<ResumeListener>>,
+    pub(crate) kept_alive: RefCell<ParkedEls<Ms>>,
+    /// See `flip_group`.
+    pub(crate) flip: FlipAnimations,
+    pub(crate) offline_retries: RefCell<OfflineRetries>,
+    pub(crate) deferred_navigation: RefCell<DeferredNavigation<Ms>>,
+    /// See `orders.perform_cmd_debounced`.
+    pub(crate) debounced_cmds: RefCell<DebouncedCmds>,
+    /// See `orders.perform_cmd_in_lane`.
+    pub(crate) cmd_lanes: RefCell<CmdLanes<Ms>>,
+    pub(crate) lazy_hydration: RefCell<LazyHydration<Ms>>,
+    pub(crate) page_meta: RefCell<PageMetaState>,
+    /// Resolve futures of `App::next_render`.
+    pub(crate) render_waiters: RefCell<Vec<oneshot::Sender<()>>>,
+    /// See `Orders::preload`.
+    pub(crate) preloads: RefCell<Preloads<Ms>>,
+    /// See `App::create_port`.
+    pub(crate) ports: RefCell<Ports>,
+    /// URL for `active_link`s - see `seed::virtual_dom::active_link`.
+    pub(crate) current_url: RefCell<Url>,
+    /// Hook invoked by `App::unmount` - see `AppBuilder::before_unmount`.
+    pub(crate) before_unmount: RefCell<Option<BeforeUnmountFn<Mdl>>>,
+    /// `App::unmount` has been called - the app doesn't process effects or render anymore.
+    pub(crate) unmounted: Cell<bool>,
+    /// Closures of `seed::dom_read` and `seed::dom_write` scheduled by the app - see `scheduler`.
+    pub(crate) dom_ops: SharedQueue,
+    /// Dropped by `App::unmount` - it resolves `unmount_signal`.
+    pub(crate) unmount_sender: RefCell<Option<oneshot::Sender<()>>>,
+    /// Resolved on unmount - running commands and streams are dropped together with their timers.
+    pub(crate) unmount_signal: Shared<oneshot::Receiver<()>>,
+    /// The vdom has been adopted (see `MountType::Takeover` and `MountType::Hydrate`)
+    /// and it'll be matched against the first render.
+    pub(crate) adopted_by: Cell<Option<MountType>>,
+    /// A navigation happened - move focus after the next render. See `AppBuilder::route_focus`.
+    pub(crate) route_focus_pending: Cell<bool>,
+    /// See `AppBuilder::scroll_behavior`.
+    pub(crate) scroll_positions: RefCell<ScrollPositions>,
+    #[cfg(feature = "perf-budget")]
+    pub(crate) perf_violations: RefCell<PerfViolations<Ms>>,
+    /// Counts of `main_el_vdom` for `seed::debug::memory_report`.
+    pub(crate) memory_counts: MemoryCounts,
+    /// See `AppBuilder::memory_growth_check`.
+    #[cfg(all(feature = "debug-tools", debug_assertions))]
+    pub(crate) memory_growth: RefCell<MemoryGrowth>,
+    /// The registration in `window.__SEED_APPS__` and the message log - see `seed::devtools`.
+    #[cfg(feature = "devtools")]
+    pub(crate) devtools: RefCell<crate::devtools::DevtoolsState>,
 }
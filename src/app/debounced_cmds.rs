@@ -0,0 +1,39 @@
+//! Debounced commands. See `Orders::perform_cmd_debounced`.
+
+use super::App;
+use crate::browser::time::TimeoutHandle;
+use crate::virtual_dom::View;
+use futures::future::LocalFutureObj;
+use std::collections::HashMap;
+
+/// Timers of the pending debounced commands by their keys.
+pub(crate) type DebouncedCmds = HashMap<String, TimeoutHandle>;
+
+/// Start `cmd` after `delay_ms` unless another command with the same `key` is debounced
+/// in the meantime. The previous pending command with the same `key` is dropped.
+pub(crate) fn debounce<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    key: String,
+    delay_ms: u32,
+    cmd: LocalFutureObj<'static, Option<Ms>>,
+) {
+    let timeout_app = app.clone();
+    let timeout_key = key.clone();
+    let timeout = app.cfg.clock.set_timeout(
+        delay_ms,
+        Box::new(move || {
+            let timeout = timeout_app
+                .data
+                .debounced_cmds
+                .borrow_mut()
+                .remove(&timeout_key);
+            if let Some(timeout) = timeout {
+                // We are inside of its callback - don't drop it.
+                timeout.forget();
+            }
+            timeout_app.process_queue_cmd(cmd);
+        }),
+    );
+    // Dropping the previous timeout cancels it.
+    app.data.debounced_cmds.borrow_mut().insert(key, timeout);
+}
@@ -0,0 +1,154 @@
+//! Paint-holding route transitions. See `Orders::defer_navigation`.
+//!
+//! Semantics:
+//! - The URL is changed immediately (as usual); only the view lags behind it.
+//! - Seed doesn't hold renders or snapshot the model - `update` keeps the previous route state
+//!   and switches it when the command's message (or `on_timeout`) arrives.
+//!   The old view stays rendered and interactive meanwhile.
+//! - A new deferred navigation cancels the previous one - its timeout is dropped and its command's
+//!   message is ignored. `Orders::cancel_deferred_navigation` does the same for navigations
+//!   which don't need to wait.
+//! - When the timeout fires first, `on_timeout` is sent and the command's message is still
+//!   delivered later (unless cancelled) so the new view can fill in the rest.
+//! - Navigation-pending handlers are called with `true` when the first navigation starts waiting
+//!   and with `false` after the swap message has been processed, so both changes are rendered
+//!   in the same frame.
+
+use super::App;
+use crate::browser::time::{self, TimeoutHandle};
+use crate::virtual_dom::View;
+use futures::future::LocalFutureObj;
+use std::{convert::identity, time::Duration};
+use wasm_bindgen_futures::spawn_local;
+
+pub(crate) struct DeferredNavigation<Ms> {
+    /// Id of the latest deferred navigation. Commands of older navigations are stale.
+    latest_id: u32,
+    /// Timeout of the latest navigation while it's waiting for its command.
+    pending: Option<TimeoutHandle>,
+    pub(crate) pending_handlers: Vec<Box<dyn Fn(bool) -> Ms>>,
+}
+
+impl<Ms> Default for DeferredNavigation<Ms> {
+    fn default() -> Self {
+        Self {
+            latest_id: 0,
+            pending: None,
+            pending_handlers: Vec::new(),
+        }
+    }
+}
+
+impl<Ms> DeferredNavigation<Ms> {
+    /// Drop the pending timeout and handlers; commands of started navigations become stale.
+    /// See `App::unmount`.
+    pub(crate) fn clear(&mut self) {
+        self.latest_id = self.latest_id.wrapping_add(1);
+        self.pending = None;
+        self.pending_handlers.clear();
+    }
+}
+
+/// Start a deferred navigation - send `cmd`'s message or `on_timeout` after `timeout`,
+/// whichever comes first.
+///
+/// Returns messages from navigation-pending handlers - they have to be sent by the caller
+/// because `update` is running.
+pub(crate) fn defer<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    cmd: LocalFutureObj<'static, Result<Ms, Ms>>,
+    timeout: Duration,
+    on_timeout: Ms,
+) -> Vec<Ms> {
+    let (id, was_pending) = {
+        let mut navigation = app.data.deferred_navigation.borrow_mut();
+        navigation.latest_id = navigation.latest_id.wrapping_add(1);
+        let id = navigation.latest_id;
+
+        let timeout_app = app.clone();
+        let timeout = app.cfg.clock.set_timeout(
+            time::duration_to_ms(timeout),
+            Box::new(move || {
+                let timeout = timeout_app
+                    .data
+                    .deferred_navigation
+                    .borrow_mut()
+                    .pending
+                    .take();
+                if let Some(timeout) = timeout {
+                    // We are inside of its callback - don't drop it.
+                    timeout.forget();
+                }
+                timeout_app.update(on_timeout);
+                notify(&timeout_app, false);
+            }),
+        );
+        // Dropping the previous timeout cancels it.
+        (id, navigation.pending.replace(timeout).is_some())
+    };
+
+    let app_for_cmd = app.clone();
+    let unmount_signal = app.data.unmount_signal.clone();
+    spawn_local(super::until_unmounted(unmount_signal, async move {
+        let app = app_for_cmd;
+        let msg = cmd.await.unwrap_or_else(identity);
+        let was_pending = {
+            let mut navigation = app.data.deferred_navigation.borrow_mut();
+            if navigation.latest_id != id {
+                // Cancelled by another navigation.
+                return;
+            }
+            navigation.pending.take().is_some()
+        };
+        app.update(msg);
+        if was_pending {
+            notify(&app, false);
+        }
+    }));
+
+    if was_pending {
+        Vec::new()
+    } else {
+        pending_msgs(app, true)
+    }
+}
+
+/// Cancel the latest deferred navigation, if any.
+///
+/// Returns messages from navigation-pending handlers - see `defer`.
+pub(crate) fn cancel<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+) -> Vec<Ms> {
+    let was_pending = {
+        let mut navigation = app.data.deferred_navigation.borrow_mut();
+        navigation.latest_id = navigation.latest_id.wrapping_add(1);
+        navigation.pending.take().is_some()
+    };
+    if was_pending {
+        pending_msgs(app, false)
+    } else {
+        Vec::new()
+    }
+}
+
+fn pending_msgs<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    pending: bool,
+) -> Vec<Ms> {
+    app.data
+        .deferred_navigation
+        .borrow()
+        .pending_handlers
+        .iter()
+        .map(|handler| handler(pending))
+        .collect()
+}
+
+fn notify<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    pending: bool,
+) {
+    for msg in pending_msgs(app, pending) {
+        app.update(msg);
+    }
+}
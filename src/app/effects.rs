@@ -1,11 +1,18 @@
-use super::MessageMapper;
+use super::{GMessageMapper, MessageMapper};
 use futures::future::LocalFutureObj;
+use futures::stream::{LocalBoxStream, StreamExt};
 
 pub enum Effect<Ms, GMs> {
     Msg(Ms),
-    Cmd(LocalFutureObj<'static, Result<Ms, Ms>>),
+    /// Messages sent to `update` one after another and rendered at most once - see `orders.batch`.
+    Batch(Vec<Ms>),
+    /// Resolves to `None` when the command doesn't send a message (e.g. it's been aborted).
+    Cmd(LocalFutureObj<'static, Option<Ms>>),
+    /// Each item is sent to `update` - see `orders.stream`.
+    Stream(LocalBoxStream<'static, Ms>),
     GMsg(GMs),
-    GCmd(LocalFutureObj<'static, Result<GMs, GMs>>),
+    /// Resolves to `None` when it's been aborted - see `orders.perform_g_cmd_with_handle`.
+    GCmd(LocalFutureObj<'static, Option<GMs>>),
 }
 
 impl<Ms, GMs> From<Ms> for Effect<Ms, GMs> {
@@ -19,11 +26,33 @@ impl<Ms: 'static, OtherMs: 'static, GMs> MessageMapper<Ms, OtherMs> for Effect<M
     fn map_msg(self, f: impl FnOnce(Ms) -> OtherMs + 'static + Clone) -> Effect<OtherMs, GMs> {
         match self {
             Effect::Msg(msg) => Effect::Msg(f(msg)),
-            Effect::Cmd(cmd) => Effect::Cmd(LocalFutureObj::new(Box::new(async {
-                cmd.await.map(f.clone()).map_err(f)
-            }))),
+            Effect::Batch(msgs) => {
+                Effect::Batch(msgs.into_iter().map(|msg| f.clone()(msg)).collect())
+            }
+            Effect::Cmd(cmd) => {
+                Effect::Cmd(LocalFutureObj::new(Box::new(async { cmd.await.map(f) })))
+            }
+            Effect::Stream(stream) => {
+                Effect::Stream(stream.map(move |msg| f.clone()(msg)).boxed_local())
+            }
             Effect::GMsg(g_msg) => Effect::GMsg(g_msg),
             Effect::GCmd(g_cmd) => Effect::GCmd(g_cmd),
         }
     }
 }
+
+impl<Ms, GMs: 'static, OtherGMs: 'static> GMessageMapper<GMs, OtherGMs> for Effect<Ms, GMs> {
+    type SelfWithOtherGMs = Effect<Ms, OtherGMs>;
+    fn map_g_msg(self, f: impl FnOnce(GMs) -> OtherGMs + 'static + Clone) -> Effect<Ms, OtherGMs> {
+        match self {
+            Effect::Msg(msg) => Effect::Msg(msg),
+            Effect::Batch(msgs) => Effect::Batch(msgs),
+            Effect::Cmd(cmd) => Effect::Cmd(cmd),
+            Effect::Stream(stream) => Effect::Stream(stream),
+            Effect::GMsg(g_msg) => Effect::GMsg(f(g_msg)),
+            Effect::GCmd(g_cmd) => {
+                Effect::GCmd(LocalFutureObj::new(Box::new(async { g_cmd.await.map(f) })))
+            }
+        }
+    }
+}
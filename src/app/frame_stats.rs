@@ -0,0 +1,214 @@
+//! Frame-rate statistics of the app's renders. See `orders.frame_stats`
+//! and `orders.subscribe_long_frames`.
+//!
+//! Each render records the time since the previous render (see `RenderTimestampDelta`)
+//! into a ring buffer of recent frames. Recording is cheap - the statistics are computed
+//! only when `orders.frame_stats` is called.
+//!
+//! Deltas longer than `FrameStatsOpts::idle_ms` aren't frames - the app didn't need to render
+//! (e.g. it waited for user's input), so they aren't recorded.
+
+use std::rc::Weak;
+
+/// Statistics of the recent frames returned by `orders.frame_stats`.
+/// All values are `0` when there are no recorded frames.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct FrameStatsSnapshot {
+    /// Number of the recorded frames.
+    pub frames: usize,
+    /// Frames per second computed from the average frame time.
+    pub avg_fps: f64,
+    /// 95th percentile of frame times in milliseconds.
+    pub p95_ms: f64,
+    /// The longest frame time in milliseconds.
+    pub worst_ms: f64,
+    /// Number of frames longer than `FrameStatsOpts::long_frame_ms`.
+    pub long_frames: usize,
+}
+
+/// Options of the frame statistics. See `AppBuilder::frame_stats_opts`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FrameStatsOpts {
+    /// How many recent frames are kept. Default is `120` (2 seconds at 60 FPS).
+    pub capacity: usize,
+    /// Frames longer than this are long frames (jank). Default is `50` ms.
+    pub long_frame_ms: f64,
+    /// Minimal time between two long frame notifications - see `orders.subscribe_long_frames`.
+    /// Default is `1000` ms.
+    pub min_report_interval_ms: f64,
+    /// Deltas between renders longer than this are idle periods and they aren't recorded.
+    /// Default is `1000` ms.
+    pub idle_ms: f64,
+}
+
+impl Default for FrameStatsOpts {
+    fn default() -> Self {
+        Self {
+            capacity: 120,
+            long_frame_ms: 50.,
+            min_report_interval_ms: 1000.,
+            idle_ms: 1000.,
+        }
+    }
+}
+
+pub(crate) struct FrameStats<Ms> {
+    opts: FrameStatsOpts,
+    /// Ring buffer of frame times - `next` is the index of the oldest one when it's full.
+    frames: Vec<f64>,
+    next: usize,
+    last_report: Option<f64>,
+    /// Handlers are removed when their `SubHandle`s are dropped.
+    pub(crate) long_frame_handlers: Vec<(Weak<()>, Box<dyn Fn(f64) -> Ms>)>,
+}
+
+impl<Ms> FrameStats<Ms> {
+    pub(crate) fn new(opts: FrameStatsOpts) -> Self {
+        Self {
+            opts,
+            frames: Vec::with_capacity(opts.capacity),
+            next: 0,
+            last_report: None,
+            long_frame_handlers: Vec::new(),
+        }
+    }
+
+    /// Record the frame time `frame_ms` of the render at `now`.
+    /// Returns messages of long frame handlers when the frame is long and it's reported.
+    pub(crate) fn record(&mut self, frame_ms: f64, now: f64) -> Vec<Ms> {
+        if frame_ms > self.opts.idle_ms || self.opts.capacity == 0 {
+            return Vec::new();
+        }
+        if self.frames.len() < self.opts.capacity {
+            self.frames.push(frame_ms);
+        } else {
+            self.frames[self.next] = frame_ms;
+        }
+        self.next = (self.next + 1) % self.opts.capacity;
+
+        self.long_frame_handlers
+            .retain(|(alive, _)| alive.upgrade().is_some());
+        let rate_limited = self.last_report.map_or(false, |last_report| {
+            now - last_report < self.opts.min_report_interval_ms
+        });
+        if frame_ms <= self.opts.long_frame_ms
+            || rate_limited
+            || self.long_frame_handlers.is_empty()
+        {
+            return Vec::new();
+        }
+        self.last_report = Some(now);
+        self.long_frame_handlers
+            .iter()
+            .map(|(_, handler)| handler(frame_ms))
+            .collect()
+    }
+
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub(crate) fn snapshot(&self) -> FrameStatsSnapshot {
+        if self.frames.is_empty() {
+            return FrameStatsSnapshot::default();
+        }
+        let mut sorted = self.frames.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        let count = sorted.len();
+        let avg_ms = sorted.iter().sum::<f64>() / count as f64;
+        // Nearest-rank percentile.
+        let p95_index = ((count as f64 * 0.95).ceil() as usize).max(1) - 1;
+        FrameStatsSnapshot {
+            frames: count,
+            avg_fps: if avg_ms > 0. { 1000. / avg_ms } else { 0. },
+            p95_ms: sorted[p95_index],
+            worst_ms: sorted[count - 1],
+            long_frames: sorted
+                .iter()
+                .filter(|frame_ms| **frame_ms > self.opts.long_frame_ms)
+                .count(),
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.frames.clear();
+        self.next = 0;
+        self.last_report = None;
+        self.long_frame_handlers.clear();
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+    use crate::app::SubHandle;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn opts(capacity: usize) -> FrameStatsOpts {
+        FrameStatsOpts {
+            capacity,
+            ..FrameStatsOpts::default()
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn snapshot() {
+        let mut stats = FrameStats::<()>::new(opts(20));
+        assert_eq!(stats.snapshot(), FrameStatsSnapshot::default());
+
+        for _ in 0..18 {
+            stats.record(10., 0.);
+        }
+        stats.record(60., 0.);
+        stats.record(90., 0.);
+        // Idle period.
+        stats.record(5000., 0.);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.frames, 20);
+        // (18 * 10 + 60 + 90) / 20 = 16.5 ms
+        assert!((snapshot.avg_fps - 1000. / 16.5).abs() < 1e-9);
+        assert_eq!(snapshot.p95_ms, 60.);
+        assert_eq!(snapshot.worst_ms, 90.);
+        assert_eq!(snapshot.long_frames, 2);
+    }
+
+    #[wasm_bindgen_test]
+    fn ring_buffer_keeps_recent_frames() {
+        let mut stats = FrameStats::<()>::new(opts(3));
+        for frame_ms in &[100., 10., 20., 30.] {
+            stats.record(*frame_ms, 0.);
+        }
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.frames, 3);
+        assert_eq!(snapshot.worst_ms, 30.);
+        assert_eq!(snapshot.long_frames, 0);
+        assert!((snapshot.avg_fps - 50.).abs() < 1e-9);
+    }
+
+    #[wasm_bindgen_test]
+    fn long_frames_are_rate_limited() {
+        let mut stats = FrameStats::new(opts(10));
+        // Long frames aren't reported without handlers.
+        assert!(stats.record(80., 0.).is_empty());
+
+        let (handle, alive) = SubHandle::new();
+        stats
+            .long_frame_handlers
+            .push((alive, Box::new(|frame_ms| frame_ms)));
+        assert!(stats.record(16., 100.).is_empty());
+        assert_eq!(stats.record(80., 200.), vec![80.]);
+        assert!(stats.record(90., 1100.).is_empty());
+        assert_eq!(stats.record(70., 1200.), vec![70.]);
+
+        // Dropping the handle unsubscribes.
+        drop(handle);
+        assert!(stats.record(80., 3000.).is_empty());
+        assert!(stats.long_frame_handlers.is_empty());
+    }
+}
@@ -0,0 +1,31 @@
+use super::LocaleError;
+use std::{error::Error, fmt};
+
+/// Problems detected by Seed while the app is running.
+/// They are always logged to the console and sent to the handler
+/// registered by `AppBuilder::framework_errors`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrameworkError {
+    /// See `QueueBudget`. Contains the diagnostic with the most frequent messages.
+    QueueBudgetExceeded(String),
+    /// A failed command hasn't been parked - see `offline_retry::MAX_OFFLINE_RETRIES`.
+    OfflineRetryLimitReached,
+    /// `orders.set_locale` has been called with an invalid tag.
+    InvalidLocale(LocaleError),
+}
+
+impl fmt::Display for FrameworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrameworkError::QueueBudgetExceeded(report) => write!(f, "{}", report),
+            FrameworkError::OfflineRetryLimitReached => write!(
+                f,
+                "Offline retry limit ({}) reached - the failed command won't be retried",
+                super::offline_retry::MAX_OFFLINE_RETRIES
+            ),
+            FrameworkError::InvalidLocale(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for FrameworkError {}
@@ -0,0 +1,369 @@
+//! The app's active locale. See `AppBuilder::locale_opts`, `orders.set_locale` and `current`.
+//!
+//! The locale is detected when the app is created - the first of these candidates
+//! which is valid and supported (see `LocaleOpts::supported`) wins:
+//! 1. The locale stored by the previous `orders.set_locale` (see `LocaleOpts::storage_key`).
+//! 2. User's preferred languages (`navigator.languages`, `navigator.language`).
+//! 3. `LocaleOpts::fallback`.
+//!
+//! A candidate is supported when it's one of the supported locales, or when a supported locale
+//! has the same language - e.g. `de-AT` is resolved to `de`, `pt` to `pt-BR`.
+//!
+//! Each app has its own locale - `ActiveLocale` in the app's context.
+//! Views and `update` read it by `current`.
+
+use super::context::{self, Contexts};
+use crate::browser::{service::storage, util};
+use std::{any::TypeId, cell::RefCell, error::Error, fmt, rc::Rc};
+use wasm_bindgen::JsValue;
+
+thread_local! {
+    /// Locale of the app whose `view` or `update` is running.
+    static RUNNING: RefCell<Option<Rc<ActiveLocale>>> = RefCell::new(None);
+}
+
+// ------ LocaleError ------
+
+/// Error of `Locale::parse`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LocaleError {
+    /// The tag isn't a well-formed BCP 47 language tag.
+    InvalidTag(String),
+}
+
+impl fmt::Display for LocaleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LocaleError::InvalidTag(tag) => write!(f, "\"{}\" isn't a valid language tag", tag),
+        }
+    }
+}
+
+impl Error for LocaleError {}
+
+// ------ Locale ------
+
+/// BCP 47 language tag like `en`, `en-US` or `zh-Hant-TW`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Locale(String);
+
+impl Locale {
+    /// Parse and canonicalize `tag` - the language is lowercased, the script is titlecased
+    /// and the region is uppercased (`EN_us` => `en-US`). Underscores are accepted as separators.
+    ///
+    /// # Errors
+    ///
+    /// Returns error when `tag` isn't well-formed - the language has to have 2-3 or 5-8 letters
+    /// and other subtags 1-8 letters or digits.
+    pub fn parse(tag: &str) -> Result<Self, LocaleError> {
+        let invalid = || LocaleError::InvalidTag(tag.to_owned());
+        let mut subtags = Vec::new();
+        for (index, subtag) in tag.split(|c| c == '-' || c == '_').enumerate() {
+            let is_alphabetic = subtag.chars().all(|c| c.is_ascii_alphabetic());
+            let is_alphanumeric = subtag.chars().all(|c| c.is_ascii_alphanumeric());
+            let subtag = match (index, subtag.len()) {
+                (0, 2..=3) | (0, 5..=8) if is_alphabetic => subtag.to_ascii_lowercase(),
+                (0, _) => return Err(invalid()),
+                // script
+                (1, 4) if is_alphabetic => {
+                    let mut script = subtag.to_ascii_lowercase();
+                    script[..1].make_ascii_uppercase();
+                    script
+                }
+                // region
+                (_, 2) if is_alphabetic => subtag.to_ascii_uppercase(),
+                (_, 1..=8) if is_alphanumeric => subtag.to_ascii_lowercase(),
+                _ => return Err(invalid()),
+            };
+            subtags.push(subtag);
+        }
+        Ok(Self(subtags.join("-")))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The primary language subtag - e.g. `en` for `en-US`.
+    pub fn language(&self) -> &str {
+        self.0.split('-').next().unwrap_or_default()
+    }
+
+    /// Get the best supported locale for this locale. Returns `self` when `supported` is empty.
+    fn negotiate(&self, supported: &[Locale]) -> Option<Locale> {
+        if supported.is_empty() || supported.contains(self) {
+            return Some(self.clone());
+        }
+        supported
+            .iter()
+            .find(|locale| locale.as_str() == self.language())
+            .or_else(|| {
+                supported
+                    .iter()
+                    .find(|locale| locale.language() == self.language())
+            })
+            .cloned()
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// ------ ActiveLocale ------
+
+/// The app's locale stored in the app's context.
+///
+/// # Example
+///
+/// ```rust,no_run
+///let locale = orders.context::<ActiveLocale>().map(|locale| locale.get());
+/// ```
+#[derive(Debug)]
+pub struct ActiveLocale(RefCell<Locale>);
+
+impl ActiveLocale {
+    pub fn get(&self) -> Locale {
+        self.0.borrow().clone()
+    }
+}
+
+// ------ LocaleChanged ------
+
+/// Notification sent by `orders.set_locale` when the locale has changed.
+/// Subscribe it by `orders.subscribe` - e.g. to rebuild locale-dependent formatters.
+///
+/// # Example
+///
+/// ```rust,no_run
+///model.locale_sub = orders.subscribe(|LocaleChanged(locale)| Msg::LocaleChanged(locale));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocaleChanged(pub Locale);
+
+// ------ LocaleOpts ------
+
+/// Locale detection and persistence options. See `AppBuilder::locale_opts`.
+#[derive(Debug, Clone)]
+pub struct LocaleOpts {
+    /// Locales the app has translations for. All valid locales are accepted when it's empty.
+    pub supported: Vec<String>,
+    /// Locale used when no other candidate is supported. Default is `en`.
+    pub fallback: String,
+    /// Local storage key of the locale set by `orders.set_locale`. `None` disables persistence.
+    /// Default is `Some("seed-locale")`.
+    pub storage_key: Option<String>,
+}
+
+impl Default for LocaleOpts {
+    fn default() -> Self {
+        Self {
+            supported: Vec::new(),
+            fallback: "en".to_owned(),
+            storage_key: Some("seed-locale".to_owned()),
+        }
+    }
+}
+
+impl LocaleOpts {
+    fn supported(&self) -> Vec<Locale> {
+        self.supported
+            .iter()
+            .filter_map(|tag| match Locale::parse(tag) {
+                Ok(locale) => Some(locale),
+                Err(error) => {
+                    crate::error(format!("Unsupported locale ignored: {}", error));
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn fallback(&self) -> Locale {
+        Locale::parse(&self.fallback).unwrap_or_else(|error| {
+            crate::error(format!("Fallback locale replaced by `en`: {}", error));
+            Locale("en".to_owned())
+        })
+    }
+}
+
+/// The active locale of the app whose `view` or `update` is running.
+/// It's `en` outside of them - use `ActiveLocale` there.
+pub fn current() -> Locale {
+    RUNNING
+        .with(|running| running.borrow().as_ref().map(|active| active.get()))
+        .unwrap_or_else(|| Locale("en".to_owned()))
+}
+
+/// Call `f` (the app's `view` or `update`) with the app's locale - `current` reads it.
+pub(crate) fn with_locale<R>(contexts: &Contexts, f: impl FnOnce() -> R) -> R {
+    let previous = RUNNING.with(|running| running.replace(Some(active(contexts))));
+    let output = f();
+    RUNNING.with(|running| running.replace(previous));
+    output
+}
+
+/// The app's `ActiveLocale` inserted by `insert_detected`.
+pub(crate) fn active(contexts: &Contexts) -> Rc<ActiveLocale> {
+    context::get::<ActiveLocale>(contexts).expect("missing ActiveLocale context")
+}
+
+/// The first supported candidate (invalid ones are skipped) or the fallback.
+fn resolve(candidates: impl IntoIterator<Item = String>, opts: &LocaleOpts) -> Locale {
+    let supported = opts.supported();
+    candidates
+        .into_iter()
+        .filter_map(|tag| Locale::parse(&tag).ok())
+        .filter_map(|locale| locale.negotiate(&supported))
+        .next()
+        .unwrap_or_else(|| opts.fallback())
+}
+
+/// User's preferred languages - `navigator.languages` or `navigator.language`.
+fn preferred_languages() -> Vec<String> {
+    let navigator = js_sys::Reflect::get(&util::window(), &JsValue::from_str("navigator"))
+        .unwrap_or(JsValue::UNDEFINED);
+    let get = |name: &str| {
+        js_sys::Reflect::get(&navigator, &JsValue::from_str(name)).unwrap_or(JsValue::UNDEFINED)
+    };
+    let languages = get("languages");
+    if js_sys::Array::is_array(&languages) {
+        js_sys::Array::from(&languages)
+            .iter()
+            .filter_map(|language| language.as_string())
+            .collect()
+    } else {
+        get("language").as_string().into_iter().collect()
+    }
+}
+
+/// Detect the locale. See the module docs.
+fn detect(opts: &LocaleOpts) -> Locale {
+    let stored = opts.storage_key.as_ref().and_then(|key| {
+        storage::get_storage().and_then(|storage| storage.get_item(key).unwrap_or(None))
+    });
+    resolve(stored.into_iter().chain(preferred_languages()), opts)
+}
+
+/// Detect the locale and store it into the app's `contexts` as `ActiveLocale`.
+pub(crate) fn insert_detected(contexts: &mut Contexts, opts: &LocaleOpts) {
+    let active = ActiveLocale(RefCell::new(detect(opts)));
+    contexts.insert(TypeId::of::<ActiveLocale>(), Rc::new(active));
+}
+
+/// Make the best supported locale for `tag` active and store it.
+/// Returns the new locale when it differs from the previous one.
+///
+/// Unsupported tags are resolved to the fallback locale.
+pub(crate) fn change(
+    active: &ActiveLocale,
+    opts: &LocaleOpts,
+    tag: &str,
+) -> Result<Option<Locale>, LocaleError> {
+    let locale = resolve(Some(Locale::parse(tag)?.0), opts);
+    if let Some(key) = &opts.storage_key {
+        if let Some(storage) = storage::get_storage() {
+            if storage.set_item(key, locale.as_str()).is_err() {
+                web_sys::console::warn_1(&format!("Locale \"{}\" can't be stored", locale).into());
+            }
+        }
+    }
+    let previous = active.0.replace(locale.clone());
+    if previous == locale {
+        Ok(None)
+    } else {
+        Ok(Some(locale))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn opts(supported: &[&str], fallback: &str, storage_key: Option<&str>) -> LocaleOpts {
+        LocaleOpts {
+            supported: supported.iter().map(|tag| (*tag).to_owned()).collect(),
+            fallback: fallback.to_owned(),
+            storage_key: storage_key.map(ToOwned::to_owned),
+        }
+    }
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|tag| (*tag).to_owned()).collect()
+    }
+
+    #[wasm_bindgen_test]
+    fn parse_tags() {
+        let parsed = |tag| Locale::parse(tag).map(|locale| locale.0);
+        assert_eq!(parsed("en"), Ok("en".to_owned()));
+        assert_eq!(parsed("EN_us"), Ok("en-US".to_owned()));
+        assert_eq!(parsed("zh-hant-tw"), Ok("zh-Hant-TW".to_owned()));
+        assert_eq!(parsed("es-419"), Ok("es-419".to_owned()));
+        assert_eq!(parsed("de-CH-1996"), Ok("de-CH-1996".to_owned()));
+        for invalid in &["", "e", "englishlanguage", "en-", "en-US!", "1a", "en--US"] {
+            assert_eq!(
+                Locale::parse(invalid),
+                Err(LocaleError::InvalidTag((*invalid).to_owned()))
+            );
+        }
+        assert_eq!(Locale::parse("en-US").unwrap().language(), "en");
+    }
+
+    #[wasm_bindgen_test]
+    fn fallback_chain() {
+        let opts = opts(&["en", "de", "pt-BR"], "en", None);
+        let resolved = |candidates: &[&str]| resolve(tags(candidates), &opts).0;
+
+        assert_eq!(resolved(&["de"]), "de");
+        // Region fallback.
+        assert_eq!(resolved(&["de-AT"]), "de");
+        // Any supported locale with the same language.
+        assert_eq!(resolved(&["pt"]), "pt-BR");
+        // Unsupported and invalid candidates are skipped.
+        assert_eq!(resolved(&["fr-FR", "not a tag", "de-DE"]), "de");
+        assert_eq!(resolved(&["fr", "cs"]), "en");
+        assert_eq!(resolved(&[]), "en");
+
+        // Everything valid is supported by default.
+        assert_eq!(resolve(tags(&["fr-FR"]), &LocaleOpts::default()).0, "fr-FR");
+    }
+
+    #[wasm_bindgen_test]
+    fn persistence_round_trip() {
+        let key = "seed-test-locale";
+        let opts = opts(&["en", "cs"], "en", Some(key));
+        let storage = storage::get_storage().unwrap();
+        storage.remove_item(key).unwrap();
+        let mut contexts = Contexts::new();
+        insert_detected(&mut contexts, &opts);
+        let active = active(&contexts);
+
+        assert_eq!(
+            change(&active, &opts, "cs-CZ"),
+            Ok(Some(Locale("cs".to_owned())))
+        );
+        assert_eq!(storage.get_item(key).unwrap(), Some("cs".to_owned()));
+        assert_eq!(active.get(), Locale("cs".to_owned()));
+        // No change.
+        assert_eq!(change(&active, &opts, "cs"), Ok(None));
+        assert_eq!(
+            change(&active, &opts, "c$"),
+            Err(LocaleError::InvalidTag("c$".to_owned()))
+        );
+        assert_eq!(with_locale(&contexts, current), Locale("cs".to_owned()));
+        assert_eq!(current(), Locale("en".to_owned()));
+
+        // The stored locale wins over the preferred languages after reload.
+        assert_eq!(detect(&opts), Locale("cs".to_owned()));
+
+        // An unsupported stored locale is skipped.
+        storage.set_item(key, "xx").unwrap();
+        assert_ne!(detect(&opts), Locale("xx".to_owned()));
+        storage.remove_item(key).unwrap();
+    }
+}
@@ -0,0 +1,269 @@
+//! Detection of growing memory usage. See `AppBuilder::memory_growth_check`.
+//!
+//! Enabled by the feature `debug-tools` in debug builds. Otherwise nothing is counted
+//! and the check is ignored.
+//!
+//! A `seed::debug::memory_report` snapshot is taken after each render. The check starts
+//! once the app has been idle - rendered `MemoryGrowthCheck::idle_renders` times
+//! on the same URL - and warns when a count has grown in each of the following
+//! `MemoryGrowthCheck::renders` renders. Each count is reported once per URL.
+
+//!
+//! Numbers of vdom nodes and element listeners are counted by `MemoryCounts` where subtrees
+//! are inserted into or removed from the vdom, so snapshots don't walk the vdom.
+
+use crate::virtual_dom::{El, Node};
+#[cfg(all(feature = "debug-tools", debug_assertions))]
+use crate::{browser::Url, debug::memory::MemoryReport};
+#[cfg(all(feature = "debug-tools", debug_assertions))]
+use std::cell::Cell;
+
+/// Set it by `AppBuilder::memory_growth_check`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MemoryGrowthCheck {
+    /// Number of renders after a URL change (or the app start) which aren't checked -
+    /// the page is still being set up (e.g. data are being loaded).
+    pub idle_renders: usize,
+    /// Number of consecutive renders with a growing count which lead to a warning.
+    pub renders: usize,
+}
+
+impl Default for MemoryGrowthCheck {
+    fn default() -> Self {
+        Self {
+            idle_renders: 5,
+            renders: 20,
+        }
+    }
+}
+
+// ------ MemoryGrowth ------
+
+#[cfg(all(feature = "debug-tools", debug_assertions))]
+#[derive(Default)]
+pub(crate) struct MemoryGrowth {
+    url: Option<Url>,
+    renders_on_url: usize,
+    /// The previous snapshot's counts and numbers of renders in which they have been growing.
+    last_counts: Option<Vec<(&'static str, usize, usize)>>,
+    reported: Vec<&'static str>,
+}
+
+#[cfg(all(feature = "debug-tools", debug_assertions))]
+impl MemoryGrowth {
+    /// Record the snapshot `report` taken after a render on `url`.
+    /// Returns warnings about counts that have been growing for `check.renders` renders.
+    pub(crate) fn record(
+        &mut self,
+        check: &MemoryGrowthCheck,
+        url: &Url,
+        report: &MemoryReport,
+    ) -> Vec<String> {
+        if self.url.as_ref() != Some(url) {
+            self.url = Some(url.clone());
+            self.renders_on_url = 0;
+            self.last_counts = None;
+            self.reported.clear();
+        }
+        self.renders_on_url += 1;
+        if self.renders_on_url <= check.idle_renders {
+            return Vec::new();
+        }
+
+        let counts = report
+            .counts()
+            .iter()
+            .enumerate()
+            .map(|(index, (name, count))| {
+                let growing_for = match &self.last_counts {
+                    Some(last_counts) if *count > last_counts[index].1 => last_counts[index].2 + 1,
+                    _ => 0,
+                };
+                (*name, *count, growing_for)
+            })
+            .collect::<Vec<_>>();
+
+        let mut warnings = Vec::new();
+        for (name, count, growing_for) in &counts {
+            if *growing_for >= check.renders && !self.reported.contains(name) {
+                self.reported.push(*name);
+                warnings.push(format!(
+                    "Possible memory leak: {} have grown in each of the last {} renders on the same URL (now {}).\n{}",
+                    name, growing_for, count, report
+                ));
+            }
+        }
+        self.last_counts = Some(counts);
+        warnings
+    }
+}
+
+// ------ MemoryCounts ------
+
+/// Element and text nodes and their listeners.
+#[cfg(all(feature = "debug-tools", debug_assertions))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub(crate) struct NodeCounts {
+    pub(crate) nodes: usize,
+    pub(crate) listeners: usize,
+}
+
+#[cfg(all(feature = "debug-tools", debug_assertions))]
+impl NodeCounts {
+    /// Count `el` and its descendants.
+    pub(crate) fn of_el<Ms>(el: &El<Ms>) -> Self {
+        let mut counts = Self {
+            nodes: 1,
+            listeners: el.listeners.len(),
+        };
+        counts.add(Self::of_nodes(&el.children));
+        counts
+    }
+
+    fn of_nodes<Ms>(nodes: &[Node<Ms>]) -> Self {
+        let mut counts = Self::default();
+        for node in nodes {
+            match node {
+                Node::Element(el) => counts.add(Self::of_el(el)),
+                Node::Text(_) => counts.nodes += 1,
+                Node::Empty => (),
+            }
+        }
+        counts
+    }
+
+    fn add(&mut self, other: Self) {
+        self.nodes += other.nodes;
+        self.listeners += other.listeners;
+    }
+
+    fn sub(&mut self, other: Self) {
+        self.nodes = self.nodes.saturating_sub(other.nodes);
+        self.listeners = self.listeners.saturating_sub(other.listeners);
+    }
+}
+
+/// Counts of the app's rendered vdom. Nothing is counted without the feature `debug-tools`.
+#[derive(Default)]
+pub(crate) struct MemoryCounts {
+    #[cfg(all(feature = "debug-tools", debug_assertions))]
+    counts: Cell<NodeCounts>,
+}
+
+#[cfg_attr(
+    not(all(feature = "debug-tools", debug_assertions)),
+    allow(unused_variables, clippy::unused_self)
+)]
+impl MemoryCounts {
+    #[cfg(all(feature = "debug-tools", debug_assertions))]
+    pub(crate) fn get(&self) -> NodeCounts {
+        self.counts.get()
+    }
+
+    /// Count the whole vdom - when it's been created or adopted from the DOM.
+    pub(crate) fn reset<Ms>(&self, root: Option<&El<Ms>>) {
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        self.counts
+            .set(root.map(NodeCounts::of_el).unwrap_or_default());
+    }
+
+    /// `el` and its descendants have been inserted into the vdom.
+    pub(crate) fn inserted<Ms>(&self, el: &El<Ms>) {
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        self.update(|counts| counts.add(NodeCounts::of_el(el)));
+    }
+
+    /// `el` and its descendants have been removed from the vdom.
+    pub(crate) fn removed<Ms>(&self, el: &El<Ms>) {
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        self.update(|counts| counts.sub(NodeCounts::of_el(el)));
+    }
+
+    pub(crate) fn text_inserted(&self) {
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        self.update(|counts| counts.nodes += 1);
+    }
+
+    pub(crate) fn text_removed(&self) {
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        self.update(|counts| counts.nodes = counts.nodes.saturating_sub(1));
+    }
+
+    /// Children have been replaced without patching - e.g. by a custom renderer.
+    pub(crate) fn children_replaced<Ms>(&self, old: &[Node<Ms>], new: &[Node<Ms>]) {
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        self.update(|counts| {
+            counts.sub(NodeCounts::of_nodes(old));
+            counts.add(NodeCounts::of_nodes(new));
+        });
+    }
+
+    /// A patched element's listeners have been replaced.
+    pub(crate) fn listeners_replaced(&self, old: usize, new: usize) {
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        self.update(|counts| counts.listeners = (counts.listeners + new).saturating_sub(old));
+    }
+
+    #[cfg(all(feature = "debug-tools", debug_assertions))]
+    fn update(&self, f: impl FnOnce(&mut NodeCounts)) {
+        let mut counts = self.counts.get();
+        f(&mut counts);
+        self.counts.set(counts);
+    }
+}
+
+#[cfg(all(test, feature = "debug-tools", debug_assertions))]
+mod tests {
+    use super::*;
+    use crate::debug::memory::Usage;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn report(subscriptions: usize) -> MemoryReport {
+        MemoryReport {
+            subscriptions: Usage {
+                count: subscriptions,
+                approx_bytes: None,
+            },
+            ..MemoryReport::default()
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn growth_is_reported_after_idle_renders() {
+        let check = MemoryGrowthCheck {
+            idle_renders: 2,
+            renders: 3,
+        };
+        let url = Url::new(vec!["todos"]);
+        let mut growth = MemoryGrowth::default();
+
+        // Idle renders aren't checked, the first checked render is the baseline.
+        for subscriptions in 0..3 {
+            assert!(growth
+                .record(&check, &url, &report(subscriptions))
+                .is_empty());
+        }
+        // A stable count interrupts the trend.
+        assert!(growth.record(&check, &url, &report(3)).is_empty());
+        assert!(growth.record(&check, &url, &report(3)).is_empty());
+        assert!(growth.record(&check, &url, &report(4)).is_empty());
+        assert!(growth.record(&check, &url, &report(5)).is_empty());
+        let warnings = growth.record(&check, &url, &report(6));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("subscriptions have grown in each of the last 3 renders"));
+
+        // Reported once per URL.
+        assert!(growth.record(&check, &url, &report(7)).is_empty());
+
+        // Another URL starts with idle renders again.
+        let other_url = Url::new(vec!["settings"]);
+        for subscriptions in 8..13 {
+            assert!(growth
+                .record(&check, &other_url, &report(subscriptions))
+                .is_empty());
+        }
+        assert_eq!(growth.record(&check, &other_url, &report(13)).len(), 1);
+    }
+}
@@ -1,4 +1,23 @@
+use super::types::AfterNextRenderCallback;
+
 pub trait MessageMapper<Ms, OtherMs> {
     type SelfWithOtherMs;
     fn map_msg(self, f: impl FnOnce(Ms) -> OtherMs + 'static + Clone) -> Self::SelfWithOtherMs;
 }
+
+/// Similar to `MessageMapper`, but it maps global messages - see `Orders::proxy_global`.
+pub trait GMessageMapper<GMs, OtherGMs> {
+    type SelfWithOtherGMs;
+    fn map_g_msg(self, f: impl FnOnce(GMs) -> OtherGMs + 'static + Clone)
+        -> Self::SelfWithOtherGMs;
+}
+
+impl<Ms: 'static, OtherMs: 'static> MessageMapper<Ms, OtherMs> for AfterNextRenderCallback<Ms> {
+    type SelfWithOtherMs = AfterNextRenderCallback<OtherMs>;
+    fn map_msg(
+        self,
+        f: impl FnOnce(Ms) -> OtherMs + 'static + Clone,
+    ) -> AfterNextRenderCallback<OtherMs> {
+        Box::new(move |render_info| self(render_info).map(f))
+    }
+}
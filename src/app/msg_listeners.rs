@@ -0,0 +1,81 @@
+//! Message listeners. See `App::add_message_listener`.
+
+use std::rc::Rc;
+
+/// Listener added by `App::add_message_listener`. The listener is removed when the handle is dropped.
+#[must_use = "the listener is removed when the handle is dropped"]
+pub struct MsgListenerHandle {
+    remove: Option<Box<dyn FnOnce()>>,
+}
+
+impl MsgListenerHandle {
+    pub(crate) fn new(remove: impl FnOnce() + 'static) -> Self {
+        Self {
+            remove: Some(Box::new(remove)),
+        }
+    }
+
+    /// Keep the listener until the app is unmounted, even when the handle is dropped.
+    pub fn forget(mut self) {
+        self.remove = None;
+    }
+}
+
+impl Drop for MsgListenerHandle {
+    fn drop(&mut self) {
+        if let Some(remove) = self.remove.take() {
+            remove();
+        }
+    }
+}
+
+#[allow(clippy::module_name_repetitions)]
+pub(crate) struct MsgListeners<Ms> {
+    next_id: usize,
+    listeners: Vec<(usize, Rc<dyn Fn(&Ms)>)>,
+}
+
+impl<Ms> Default for MsgListeners<Ms> {
+    fn default() -> Self {
+        Self {
+            next_id: 0,
+            listeners: Vec::new(),
+        }
+    }
+}
+
+impl<Ms> MsgListeners<Ms> {
+    /// Returns the listener's id.
+    pub(crate) fn add(&mut self, listener: impl Fn(&Ms) + 'static) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.listeners.push((id, Rc::new(listener)));
+        id
+    }
+
+    pub(crate) fn remove(&mut self, id: usize) {
+        self.listeners.retain(|(listener_id, _)| *listener_id != id);
+    }
+
+    pub(crate) fn contains(&self, id: usize) -> bool {
+        self.listeners
+            .iter()
+            .any(|(listener_id, _)| *listener_id == id)
+    }
+
+    /// Listeners with their ids, in the registration order.
+    ///
+    /// Call listeners from the snapshot, not while `MsgListeners` are borrowed -
+    /// a listener may add or remove listeners (e.g. drop its own handle).
+    pub(crate) fn snapshot(&self) -> Vec<(usize, Rc<dyn Fn(&Ms)>)> {
+        self.listeners.clone()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Rc<dyn Fn(&Ms)>> {
+        self.listeners.iter().map(|(_, listener)| listener)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.listeners.clear();
+    }
+}
@@ -0,0 +1,66 @@
+//! Message subscriptions. See `App::subscribe` and `App::unsubscribe`.
+//!
+//! Unlike message listeners (`App::add_message_listener`), subscriptions can re-inject
+//! follow-up messages.
+
+use std::rc::Rc;
+
+/// Subscription created by `App::subscribe`. Pass it to `App::unsubscribe` to remove it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SubscriptionHandle(usize);
+
+#[allow(clippy::module_name_repetitions)]
+pub(crate) struct MsgSubscriptions<Ms> {
+    next_id: usize,
+    subscriptions: Vec<(usize, Rc<dyn Fn(&Ms) -> Option<Ms>>)>,
+}
+
+impl<Ms> Default for MsgSubscriptions<Ms> {
+    fn default() -> Self {
+        Self {
+            next_id: 0,
+            subscriptions: Vec::new(),
+        }
+    }
+}
+
+impl<Ms> MsgSubscriptions<Ms> {
+    pub(crate) fn subscribe(
+        &mut self,
+        handler: impl Fn(&Ms) -> Option<Ms> + 'static,
+    ) -> SubscriptionHandle {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.subscriptions.push((id, Rc::new(handler)));
+        SubscriptionHandle(id)
+    }
+
+    /// Returns `false` when the subscription has been removed already.
+    pub(crate) fn unsubscribe(&mut self, handle: SubscriptionHandle) -> bool {
+        let count = self.subscriptions.len();
+        self.subscriptions.retain(|(id, _)| *id != handle.0);
+        self.subscriptions.len() != count
+    }
+
+    pub(crate) fn contains(&self, id: usize) -> bool {
+        self.subscriptions
+            .iter()
+            .any(|(subscription_id, _)| *subscription_id == id)
+    }
+
+    /// Handlers with their ids, in the subscription order.
+    ///
+    /// Call handlers from the snapshot, not while `MsgSubscriptions` are borrowed -
+    /// a handler may subscribe or unsubscribe.
+    pub(crate) fn snapshot(&self) -> Vec<(usize, Rc<dyn Fn(&Ms) -> Option<Ms>>)> {
+        self.subscriptions.clone()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.subscriptions.clear();
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.subscriptions.len()
+    }
+}
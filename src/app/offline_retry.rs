@@ -0,0 +1,180 @@
+//! Commands which are retried automatically when the browser comes back online.
+//! See `Orders::retry_when_online`.
+//!
+//! Only network failures (`CmdFailure::Network`) which happen while the browser is offline
+//! are retried. The failure message is sent to `update` as usual and the command is parked
+//! until the window's `online` event. Parked commands are dropped by
+//! `Orders::cancel_offline_retries`, by their `CmdHandle`s and by `App::unmount`.
+
+use super::{App, FrameworkError};
+use crate::browser::{service::fetch::FetchError, util};
+use crate::virtual_dom::View;
+use enclose::enclose;
+use futures::channel::oneshot;
+use futures::future::LocalFutureObj;
+use std::{future::Future, mem, rc::Rc};
+use wasm_bindgen::{closure::Closure, JsCast};
+
+/// Max number of commands waiting for the browser to come back online.
+/// Commands failed over the limit aren't retried.
+pub const MAX_OFFLINE_RETRIES: usize = 50;
+
+// ------ CmdFailure ------
+
+/// Failure of a command performed by `Orders::retry_when_online`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CmdFailure<Ms> {
+    /// The request hasn't reached the server (e.g. the connection has been lost).
+    /// The command is retried when it failed while the browser was offline.
+    Network(Ms),
+    /// Other failures (e.g. `404` responses) aren't retried.
+    Other(Ms),
+}
+
+impl<Ms> CmdFailure<Ms> {
+    /// `Network` for network errors of `fetch` (see `FetchError::is_network_error`),
+    /// `Other` for the rest.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///async fn save_todo(todo: Todo) -> Result<Msg, CmdFailure<Msg>> {
+    ///    TODOS.call(todo).await.map(Msg::Saved).map_err(|error| {
+    ///        CmdFailure::from_fetch_error(&error, Msg::SaveFailed)
+    ///    })
+    ///}
+    /// ```
+    pub fn from_fetch_error<E>(error: &FetchError<E>, msg: Ms) -> Self {
+        if error.is_network_error() {
+            CmdFailure::Network(msg)
+        } else {
+            CmdFailure::Other(msg)
+        }
+    }
+
+    pub fn into_msg(self) -> Ms {
+        match self {
+            CmdFailure::Network(msg) | CmdFailure::Other(msg) => msg,
+        }
+    }
+
+    pub(crate) fn map<OtherMs>(self, f: impl FnOnce(Ms) -> OtherMs) -> CmdFailure<OtherMs> {
+        match self {
+            CmdFailure::Network(msg) => CmdFailure::Network(f(msg)),
+            CmdFailure::Other(msg) => CmdFailure::Other(f(msg)),
+        }
+    }
+}
+
+// ------ OfflineRetries ------
+
+pub(crate) type CmdFactory<Ms> =
+    Rc<dyn Fn() -> LocalFutureObj<'static, Result<Ms, CmdFailure<Ms>>>>;
+
+#[derive(Default)]
+pub(crate) struct OfflineRetries {
+    /// Parked commands are waiting for their senders. A sender is canceled
+    /// when its command has been canceled by its `CmdHandle`.
+    parked: Vec<oneshot::Sender<()>>,
+    // The listener is registered with the first parked command and removed on unmount.
+    online_listener: Option<Closure<dyn FnMut(web_sys::Event)>>,
+}
+
+impl OfflineRetries {
+    pub(crate) fn len(&self) -> usize {
+        self.parked
+            .iter()
+            .filter(|sender| !sender.is_canceled())
+            .count()
+    }
+
+    /// Drop parked commands - they won't be retried.
+    pub(crate) fn clear(&mut self) {
+        self.parked.clear();
+    }
+
+    /// Drop parked commands and remove the `online` listener - see `App::unmount`.
+    pub(crate) fn stop(&mut self) {
+        self.clear();
+        if let Some(closure) = self.online_listener.take() {
+            util::window()
+                .remove_event_listener_with_callback("online", closure.as_ref().unchecked_ref())
+                .expect("Problem removing online listener");
+        }
+    }
+}
+
+/// Create a command which executes the command created by `cmd_factory` and resolves
+/// with its message. Network failures while the browser is offline are sent to `update`
+/// immediately and the command is executed again when the browser comes back online.
+/// The command resolves with `None` when its parked retry has been dropped.
+pub(crate) fn cmd<Ms: 'static, Mdl: 'static, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    cmd_factory: CmdFactory<Ms>,
+) -> impl Future<Output = Option<Ms>> {
+    let app = app.clone();
+    async move {
+        loop {
+            let msg = match cmd_factory().await {
+                Ok(msg) | Err(CmdFailure::Other(msg)) => return Some(msg),
+                Err(CmdFailure::Network(msg)) => msg,
+            };
+            if util::is_online() {
+                return Some(msg);
+            }
+            let online = match park(&app) {
+                Some(online) => online,
+                None => {
+                    if let Some(error_msg) =
+                        app.framework_error(FrameworkError::OfflineRetryLimitReached)
+                    {
+                        app.update(error_msg);
+                    }
+                    return Some(msg);
+                }
+            };
+            app.update(msg);
+            if online.await.is_err() {
+                // Dropped by `cancel_offline_retries` or `unmount`.
+                return None;
+            }
+        }
+    }
+}
+
+/// Park a command - the returned receiver is resolved when the browser comes back online.
+/// Returns `None` when the limit of parked commands has been reached - see `FrameworkError`.
+fn park<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+) -> Option<oneshot::Receiver<()>> {
+    let mut retries = app.data.offline_retries.borrow_mut();
+    retries.parked.retain(|sender| !sender.is_canceled());
+    if retries.parked.len() >= MAX_OFFLINE_RETRIES {
+        return None;
+    }
+    let (sender, receiver) = oneshot::channel();
+    retries.parked.push(sender);
+
+    if retries.online_listener.is_none() {
+        let closure: Closure<dyn FnMut(web_sys::Event)> =
+            Closure::new(enclose!((app) move |_: web_sys::Event| retry_all(&app)));
+        util::window()
+            .add_event_listener_with_callback("online", closure.as_ref().unchecked_ref())
+            .expect("Problem adding online listener");
+        retries.online_listener = Some(closure);
+    }
+    Some(receiver)
+}
+
+fn retry_all<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+) {
+    let parked = mem::replace(
+        &mut app.data.offline_retries.borrow_mut().parked,
+        Vec::new(),
+    );
+    for sender in parked {
+        // It fails when the command has been canceled.
+        let _ = sender.send(());
+    }
+}
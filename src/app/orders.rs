@@ -1,17 +1,46 @@
-use super::{App, RenderTimestampDelta, UndefinedGMsg};
-use crate::virtual_dom::View;
-use std::future::Future;
+use super::{
+    offline_retry::CmdFailure,
+    preload::{PreloadHandle, PreloadHint, PreloadStatus, RoutePrefetch},
+    toast::Toast,
+    App, CmdHandle, FrameStatsSnapshot, Lane, LaneStats, PageMeta, PerfViolation, RenderInfo,
+    StreamHandle, SubHandle, UndefinedGMsg,
+};
+use crate::{
+    browser::{
+        dom::{
+            global_events::EventHandle,
+            scroll_progress::{ScrollProgressHandle, ScrollProgressOpts},
+            viewport::{Viewport, ViewportHandle, ViewportOpts},
+        },
+        service::{
+            storage_manager::{StorageEstimate, StorageQuotaHandle, StorageQuotaOpts},
+            synced_slice::{SyncError, SyncedSlice},
+            websocket::{WebSocket, WebSocketError, WebSocketHandlers},
+        },
+        Capabilities, Modality, ShareData, ShareError, Unsupported, Url,
+    },
+    virtual_dom::{ElRef, EventTrigger, View},
+};
+use futures::{
+    future::{FutureExt, LocalFutureObj},
+    stream::Stream,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{any::Any, convert::identity, future::Future, rc::Rc, time::Duration};
+use wasm_bindgen::JsCast;
 
 pub mod container;
 pub mod proxy;
 
-pub use container::OrdersContainer;
+pub use container::{MockedOrders, OrdersContainer};
 pub use proxy::OrdersProxy;
 
 pub trait Orders<Ms: 'static, GMs = UndefinedGMsg> {
     type AppMs: 'static;
     type Mdl: 'static;
     type ElC: View<Self::AppMs> + 'static;
+    /// App's (root's) global message type - `GMs` of child modules can differ (see `proxy_global`).
+    type AppGMs: 'static;
 
     /// Automatically map message type. It allows you to pass `Orders` into child module.
     ///
@@ -25,38 +54,227 @@ pub trait Orders<Ms: 'static, GMs = UndefinedGMsg> {
     fn proxy<ChildMs: 'static>(
         &mut self,
         f: impl FnOnce(ChildMs) -> Ms + 'static + Clone,
-    ) -> OrdersProxy<ChildMs, Self::AppMs, Self::Mdl, Self::ElC, GMs>;
+    ) -> OrdersProxy<ChildMs, Self::AppMs, Self::Mdl, Self::ElC, GMs, Self::AppGMs>;
+
+    /// Similar to `proxy`, but `f` is a reusable `Fn` - e.g. a closure capturing the child's id.
+    /// Messages sent by the proxy and outputs of its commands, streams
+    /// and `after_next_render` callbacks are mapped by `f` and then by all parent proxies.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///Msg::Row(id, row_msg) => {
+    ///    let row = model.rows.get_mut(&id).unwrap();
+    ///    row::update(row_msg, row, &mut orders.proxy_with(move |row_msg| Msg::Row(id, row_msg)));
+    ///}
+    /// ```
+    fn proxy_with<ChildMs: 'static>(
+        &mut self,
+        f: impl Fn(ChildMs) -> Ms + 'static,
+    ) -> OrdersProxy<ChildMs, Self::AppMs, Self::Mdl, Self::ElC, GMs, Self::AppGMs>;
+
+    /// Map global messages of a child module. It allows reusable modules to declare their own
+    /// global message type instead of depending on the app's one.
+    /// Global messages sent by the proxy and outputs of its global commands are mapped by `f`
+    /// and then by all parent proxies. Combine it with `proxy` to map also module's messages.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///Msg::Child(child_msg) => {
+    ///    child::update(
+    ///        child_msg,
+    ///        &mut model.child,
+    ///        &mut orders.proxy(Msg::Child).proxy_global(GMsg::Child),
+    ///    );
+    ///}
+    /// ```
+    fn proxy_global<ChildGMs: 'static>(
+        &mut self,
+        f: impl Fn(ChildGMs) -> GMs + 'static,
+    ) -> OrdersProxy<Ms, Self::AppMs, Self::Mdl, Self::ElC, ChildGMs, Self::AppGMs>;
 
     /// Schedule web page rerender after model update. It's the default behaviour.
+    ///
+    /// Only the last call of `render`, `force_render_now` or `skip` in `update` is applied -
+    /// e.g. `orders.render().skip()` skips rendering. It's the same for proxies
+    /// (see `proxy`) - they share the parent's setting.
     fn render(&mut self) -> &mut Self;
 
     /// Force web page to rerender immediately after model update.
+    /// - The last call of `render`, `force_render_now` or `skip` wins (see `render`).
+    /// - Messages processed together (e.g. `update`'s follow-up messages) are rendered once,
+    ///   after the last of them - see `App::process_cmd_and_msg_queue`.
     fn force_render_now(&mut self) -> &mut Self;
 
     /// Don't rerender web page after model update.
+    /// - The last call of `render`, `force_render_now` or `skip` wins (see `render`).
+    /// - The changed model is rendered by the next render (e.g. caused by another message).
     fn skip(&mut self) -> &mut Self;
 
+    /// Render at most once per `ms` milliseconds - renders requested sooner after the last one
+    /// are postponed and coalesced. It's useful for high-frequency message sources
+    /// (e.g. `mousemove` streams). `0` (the default) disables throttling.
+    /// - It applies to scheduled renders (`render`), not to `force_render_now`.
+    /// - It's an app-wide setting - it's kept until the next call.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///orders.render_throttle(50);
+    /// ```
+    fn render_throttle(&mut self, ms: u32) -> &mut Self;
+
     /// Call function `update` with the given `msg` after model update.
     /// - You can call this function multiple times - messages will be sent in the same order.
     fn send_msg(&mut self, msg: Ms) -> &mut Self;
 
+    /// Send `msgs` to `update` one after another, without other effects in between,
+    /// and render at most once after the last one.
+    /// - `ForceRenderNow` requested by any of them renders once after the batch.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///orders.batch(vec![Msg::ClearFilters, Msg::SetSort(Sort::Date), Msg::Reload]);
+    /// ```
+    fn batch(&mut self, msgs: impl IntoIterator<Item = Ms>) -> &mut Self;
+
     /// Schedule given future `cmd` to be executed after model update.
-    /// - Result is send to function `update`.
+    /// - `cmd` may resolve to `Ms`, `Option<Ms>` or `()` - the message (if any) is sent
+    ///   to function `update`.
     /// - You can call this function multiple times - futures will be scheduled in the same order.
     ///
+    /// _Deprecated:_ `cmd` resolving to `Result<Ms, Ms>` is still accepted - both `Ok` and `Err`
+    /// messages are sent to `update`. Return `Ms` instead.
+    ///
     /// # Example
     ///
     /// ```rust,no_run
-    ///fn write_emoticon_after_delay() -> impl Future<Item=Msg, Error=Msg> {
-    ///    TimeoutFuture::new(2_000)
-    ///        .map(|_| Msg::WriteEmoticon)
-    ///        .map_err(|_| Msg::TimeoutError)
+    ///orders.perform_cmd(async {
+    ///    TimeoutFuture::new(2_000).await;
+    ///    Msg::WriteEmoticon
+    ///});
+    ///// Nothing is sent when the response is `None`.
+    ///orders.perform_cmd(async { fetch_update().await.map(Msg::UpdateFetched) });
+    /// ```
+    fn perform_cmd<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        cmd: impl Future<Output = MsU> + 'static,
+    ) -> &mut Self;
+
+    /// Similar to `perform_cmd`, but the command can be aborted - its message isn't sent
+    /// when the returned handle is dropped (or `CmdHandle::cancel` is called) before
+    /// the command resolves. Dropping the handle of a finished command does nothing.
+    ///
+    /// Store the handle in your model and replace it with each new command - e.g. only
+    /// the latest search request then sends its results, so stale results can't overwrite
+    /// fresh ones.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///// The previous search is aborted.
+    ///model.search_handle = Some(orders.perform_cmd_with_handle(search(model.query.clone())));
+    /// ```
+    fn perform_cmd_with_handle<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        cmd: impl Future<Output = MsU> + 'static,
+    ) -> CmdHandle;
+
+    /// Similar to `perform_cmd`, but the command is started after `delay_ms` milliseconds
+    /// and only when no other command with the same `key` has been debounced in the meantime -
+    /// e.g. only the last search request of a quickly typed query is sent.
+    ///
+    /// Pending commands are dropped by `App::unmount`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///orders.perform_cmd_debounced("search", 300, search(model.query.clone()));
+    /// ```
+    fn perform_cmd_debounced<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        key: impl Into<String>,
+        delay_ms: u32,
+        cmd: impl Future<Output = MsU> + 'static,
+    ) -> &mut Self;
+
+    /// Similar to `perform_cmd`, but at most `Lane`'s `max_concurrent` commands performed
+    /// in the lane run at the same time - the other ones wait in the lane's queue and they are
+    /// started in the submission order. See `seed::app::cmd_lanes`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///for card in &model.cards {
+    ///    orders.perform_cmd_in_lane(&Lane::named("thumbnails", 4), fetch_thumbnail(card.id));
     ///}
-    ///orders.perform_cmd(write_emoticon_after_delay());
     /// ```
-    fn perform_cmd<C>(&mut self, cmd: C) -> &mut Self
-    where
-        C: Future<Output = Result<Ms, Ms>> + 'static;
+    fn perform_cmd_in_lane<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        lane: &Lane,
+        cmd: impl Future<Output = MsU> + 'static,
+    ) -> &mut Self;
+
+    /// Similar to `perform_cmd_in_lane`, but the command can be aborted by the returned handle
+    /// (see `perform_cmd_with_handle`). A waiting command is removed from the lane's queue
+    /// when the handle is dropped - it's never started.
+    fn perform_cmd_in_lane_with_handle<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        lane: &Lane,
+        cmd: impl Future<Output = MsU> + 'static,
+    ) -> CmdHandle;
+
+    /// Numbers of running and waiting commands in `lane`.
+    fn lane_stats(&self, lane: &Lane) -> LaneStats;
+
+    /// Send each item of `stream` to `update`. The stream is started after model update,
+    /// in the same order as other effects (see `perform_cmd`).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///let (sender, receiver) = futures::channel::mpsc::unbounded();
+    ///orders.stream(receiver.map(Msg::Received));
+    /// ```
+    fn stream(&mut self, stream: impl Stream<Item = Ms> + 'static) -> &mut Self;
+
+    /// Similar to `stream`, but the stream is canceled when the returned handle is dropped
+    /// (or `StreamHandle::cancel` is called). Items arriving after that aren't sent,
+    /// even if they have been already buffered.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///model.messages_handle = Some(orders.stream_with_handle(socket_messages.map(Msg::Received)));
+    /// ```
+    fn stream_with_handle(&mut self, stream: impl Stream<Item = Ms> + 'static) -> StreamHandle;
+
+    /// Send `handler`'s message in every animation frame until the returned handle is dropped.
+    /// `RenderInfo` contains the frame timestamp and the difference from the stream's previous
+    /// frame (`None` for the first one).
+    ///
+    /// - Frames are sent even when nothing is rendered. Rendering caused by their messages
+    ///   happens in the next frame.
+    /// - The frame is shared with the scheduled render - no extra `requestAnimationFrame`
+    ///   is requested when a render is pending.
+    /// - Frames aren't sent after the handle is dropped, even if they are already queued.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///model.frames = Some(orders.request_animation_frame_stream(Msg::Frame));
+    ///
+    ///Msg::Frame(info) => {
+    ///    let delta = info.timestamp_delta.map_or(0., f64::from);
+    ///    model.position += model.velocity * delta;
+    ///}
+    /// ```
+    fn request_animation_frame_stream(
+        &mut self,
+        handler: impl FnOnce(RenderInfo) -> Ms + Clone + 'static,
+    ) -> StreamHandle;
 
     /// Similar to `send_msg`, but calls function `sink` with the given global message.
     fn send_g_msg(&mut self, g_msg: GMs) -> &mut Self;
@@ -66,8 +284,13 @@ pub trait Orders<Ms: 'static, GMs = UndefinedGMsg> {
     where
         C: Future<Output = Result<GMs, GMs>> + 'static;
 
+    /// Similar to `perform_cmd_with_handle`, but result is send to function `sink`.
+    fn perform_g_cmd_with_handle<C>(&mut self, g_cmd: C) -> CmdHandle
+    where
+        C: Future<Output = Result<GMs, GMs>> + 'static;
+
     /// Get app instance. Cloning is cheap because `App` contains only `Rc` fields.
-    fn clone_app(&self) -> App<Self::AppMs, Self::Mdl, Self::ElC, GMs>;
+    fn clone_app(&self) -> App<Self::AppMs, Self::Mdl, Self::ElC, Self::AppGMs>;
 
     /// Get function which maps module's `Msg` to app's (root's) one.
     ///
@@ -81,17 +304,707 @@ pub trait Orders<Ms: 'static, GMs = UndefinedGMsg> {
 
     /// Register the callback that will be executed after the next render.
     ///
-    /// Callback's only parameter is `Option<RenderTimestampDelta>` - the difference between
-    /// the old render timestamp and the new one.
-    /// The parameter has value `None` if it's the first rendering.
+    /// Callback's only parameter is `RenderInfo` - the render timestamp and the difference
+    /// between the old render timestamp and the new one (`None` if it's the first rendering).
     ///
+    /// - The callback is executed exactly once, after the next `rerender_vdom`.
+    /// - Callbacks registered while callbacks are executed (e.g. by `update` handling
+    ///   a callback's message) are executed after the following render.
+    /// - It may return `Ms`, `Option<Ms>` or `()` - the message (if any) is sent to `update`.
     /// - It's useful when you want to use DOM API or make animations.
     /// - You can call this function multiple times - callbacks will be executed in the same order.
     ///
     /// _Note:_ [performance.now()](https://developer.mozilla.org/en-US/docs/Web/API/Performance/now)
     ///  is used under the hood to get timestamps.
-    fn after_next_render(
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///orders.after_next_render(Msg::Rendered);
+    ///orders.after_next_render(|_| log!("rendered"));
+    /// ```
+    fn after_next_render<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        callback: impl FnOnce(RenderInfo) -> MsU + 'static,
+    ) -> &mut Self;
+
+    /// Get the report about available browser APIs.
+    /// APIs are probed lazily and the results are shared by the whole app.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///if orders.capabilities().has(Capability::ResizeObserver) {
+    ///    orders.send_msg(Msg::ObserveResizing);
+    ///}
+    /// ```
+    fn capabilities(&self) -> &Capabilities;
+
+    /// Call `handler` and send its message to `update` whenever user's input modality
+    /// (keyboard, pointer or touch) changes. Dropping the handle unsubscribes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///model.modality_sub = Some(orders.subscribe_input_modality(Msg::ModalityChanged));
+    /// ```
+    fn subscribe_input_modality(&mut self, handler: impl Fn(Modality) -> Ms + 'static)
+        -> SubHandle;
+
+    /// Get user's current input modality.
+    /// It's `None` until the user presses a key or a pointer for the first time.
+    fn input_modality(&self) -> Option<Modality>;
+
+    /// Subscribe to scroll progress (`0.0` - `1.0`) of `element` through the viewport.
+    /// - `handler` is called at most once per animation frame and only when the progress changes.
+    /// - Progress isn't computed while the element is far from the viewport.
+    /// - Dropping the returned handle unsubscribes.
+    /// - It returns `Unsupported` when the browser doesn't support `IntersectionObserver`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///let element = seed::document().get_element_by_id("article").unwrap();
+    ///model.progress_handle = orders
+    ///    .subscribe_scroll_progress(&element, ScrollProgressOpts::default(), Msg::ReadingProgress)
+    ///    .ok();
+    /// ```
+    fn subscribe_scroll_progress(
+        &mut self,
+        element: &web_sys::Element,
+        opts: ScrollProgressOpts,
+        handler: impl Fn(f64) -> Ms + 'static,
+    ) -> Result<ScrollProgressHandle, Unsupported>;
+
+    /// Like `subscribe_scroll_progress`, but it tracks the element of `el_ref`.
+    /// - The element is resolved once `after_next_render` callbacks have run,
+    ///   so `el_ref` can be attached in the `view` rendered after this `update`.
+    /// - Nothing is tracked when the element isn't rendered at that time.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///model.progress_handle = orders
+    ///    .subscribe_scroll_progress_el_ref(&model.article, ScrollProgressOpts::default(), Msg::ReadingProgress)
+    ///    .ok();
+    /// ```
+    fn subscribe_scroll_progress_el_ref<E: JsCast + 'static>(
+        &mut self,
+        el_ref: &ElRef<E>,
+        opts: ScrollProgressOpts,
+        handler: impl Fn(f64) -> Ms + 'static,
+    ) -> Result<ScrollProgressHandle, Unsupported>;
+
+    /// Subscribe to viewport metrics - size, device pixel ratio, named breakpoint,
+    /// scroll position and safe-area insets. See `seed::browser::dom::viewport`.
+    /// - The initial `Viewport` is sent immediately.
+    /// - `handler` is called at most once per animation frame and only when
+    ///   the `Viewport` changes.
+    /// - Dropping the returned handle unsubscribes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///model.viewport_handle = Some(orders.subscribe_viewport(
+    ///    ViewportOpts {
+    ///        breakpoints: &[(640, "sm"), (1024, "md")],
+    ///        track_scroll: true,
+    ///    },
+    ///    Msg::Viewport,
+    ///));
+    /// ```
+    fn subscribe_viewport(
+        &mut self,
+        opts: ViewportOpts,
+        handler: impl Fn(Viewport) -> Ms + 'static,
+    ) -> ViewportHandle;
+
+    /// Listen to `window`'s events until the returned handle is dropped.
+    /// See `seed::browser::dom::global_events`.
+    /// - `handler` can return `Msg`, `Option<Msg>` or `()`.
+    /// - Use `TEv` to get specific event types, `Ev` passes `web_sys::Event`.
+    /// - The model isn't needed - unlike with `AppBuilder::window_events`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///Msg::OpenModal => {
+    ///    model.modal = Some(Modal {
+    ///        key_handle: orders.subscribe_window_event(TEv::KeyDown, |event| {
+    ///            if event.key() == "Escape" {
+    ///                Some(Msg::CloseModal)
+    ///            } else {
+    ///                None
+    ///            }
+    ///        }),
+    ///    });
+    ///}
+    ///Msg::CloseModal => model.modal = None,
+    /// ```
+    fn subscribe_window_event<T: EventTrigger, MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        trigger: T,
+        handler: impl FnOnce(T::Event) -> MsU + Clone + 'static,
+    ) -> EventHandle;
+
+    /// Listen to `document`'s events (e.g. `visibilitychange`) until the returned handle
+    /// is dropped. See `subscribe_window_event`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///model.visibility_handle = Some(orders.subscribe_document_event(
+    ///    Ev::VisibilityChange,
+    ///    |_| Msg::VisibilityChanged,
+    ///));
+    /// ```
+    fn subscribe_document_event<T: EventTrigger, MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        trigger: T,
+        handler: impl FnOnce(T::Event) -> MsU + Clone + 'static,
+    ) -> EventHandle;
+
+    /// Remove DOM parked by `keep_alive` with the given `key`.
+    /// The subtree will be rendered from scratch when it's shown again.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///orders.drop_kept_alive("video");
+    /// ```
+    fn drop_kept_alive(&mut self, key: &str) -> &mut Self;
+
+    /// Similar to `perform_cmd`, but the command is created by `cmd_factory`, so it can be
+    /// executed again. When the command fails with `CmdFailure::Network` while the browser
+    /// is offline (`navigator.onLine` is `false`), the failure message is sent to `update`
+    /// as usual and the command is executed again when the browser comes back online.
+    /// Other failures aren't retried. See `seed::app::offline_retry`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///orders.retry_when_online(move || save_todo(todo.clone()));
+    /// ```
+    fn retry_when_online<C>(&mut self, cmd_factory: impl Fn() -> C + 'static) -> &mut Self
+    where
+        C: Future<Output = Result<Ms, CmdFailure<Ms>>> + 'static;
+
+    /// Similar to `retry_when_online`, but the command (including its parked retry)
+    /// is canceled when the returned handle is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///model.save_handle = Some(orders.retry_when_online_with_handle(move || {
+    ///    save_todo(todo.clone())
+    ///}));
+    /// ```
+    fn retry_when_online_with_handle<C>(
+        &mut self,
+        cmd_factory: impl Fn() -> C + 'static,
+    ) -> CmdHandle
+    where
+        C: Future<Output = Result<Ms, CmdFailure<Ms>>> + 'static;
+
+    /// Number of failed commands waiting for the browser to come back online.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///Msg::TodoSaveFailed => {
+    ///    model.pending_saves = orders.pending_offline_retries();
+    ///}
+    /// ```
+    fn pending_offline_retries(&self) -> usize;
+
+    /// Drop all commands waiting for the browser to come back online.
+    fn cancel_offline_retries(&mut self) -> &mut Self;
+
+    /// Hold the route transition until `cmd`'s message arrives - use it in `update` instead
+    /// of switching the route immediately. The previous view stays rendered and interactive,
+    /// so the new route doesn't flash an empty skeleton.
+    /// - `update` should switch the route state when it receives `cmd`'s message.
+    /// - When `cmd` doesn't finish in `timeout`, `on_timeout` is sent to force the switch
+    ///   and `cmd`'s message is sent later as usual.
+    /// - A new deferred navigation cancels the previous one - its messages are never sent.
+    /// - The URL has been already changed; only the view lags behind.
+    ///
+    /// See `subscribe_navigation_pending` for progress indicators.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///Msg::RouteChanged(Route::Report(id)) => {
+    ///    orders.defer_navigation(
+    ///        fetch_report(id),
+    ///        Duration::from_millis(500),
+    ///        Msg::ShowReport(id, None),
+    ///    );
+    ///}
+    ///Msg::ReportFetched(id, report) => {
+    ///    model.page = Page::Report(id, Some(report));
+    ///}
+    /// ```
+    fn defer_navigation<C>(&mut self, cmd: C, timeout: Duration, on_timeout: Ms) -> &mut Self
+    where
+        C: Future<Output = Result<Ms, Ms>> + 'static;
+
+    /// Cancel the pending deferred navigation - e.g. when the user navigates to a route
+    /// which doesn't need to wait for data.
+    fn cancel_deferred_navigation(&mut self) -> &mut Self;
+
+    /// Subscribe to the state of deferred navigations - `handler` is called with `true`
+    /// when a navigation starts waiting for its data and with `false` when the new view is shown.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///orders.subscribe_navigation_pending(Msg::NavigationPending);
+    /// ```
+    fn subscribe_navigation_pending(&mut self, handler: impl Fn(bool) -> Ms + 'static)
+        -> &mut Self;
+
+    /// Push `url` to the history (see `seed::push_route`) and send the message
+    /// returned by `routes` (or `not_found`) - the same way as when the user clicks a link.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///Msg::UserSaved(user) => {
+    ///    orders.push_route(Url::default().path(&["users", &user.id]));
+    ///}
+    /// ```
+    fn push_route<U: Into<Url>>(&mut self, url: U) -> &mut Self;
+
+    /// Similar to `push_route`, but the current history entry is replaced
+    /// (see `seed::replace_route`) - e.g. for redirects.
+    fn replace_route<U: Into<Url>>(&mut self, url: U) -> &mut Self;
+
+    /// Get the current `Url` without the base path (see `AppBuilder::base_path`) -
+    /// it already reflects routes pushed or replaced earlier in the same `update`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///let cancel_url = orders.current_url().to_string();
+    /// ```
+    fn current_url(&self) -> Url;
+
+    /// Get the app's context value of type `C` - e.g. a config loaded
+    /// by `AppBuilder::config_from_element`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///let api_url = orders.context::<Config>().map(|config| config.api_url.clone());
+    /// ```
+    fn context<C: 'static>(&self) -> Option<Rc<C>>;
+
+    /// Hydrate the `hydrate_lazy` subtree with `key` now - e.g. before scrolling to it
+    /// or when the user is about to interact with it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///Msg::ShowComments => {
+    ///    orders.force_hydrate("comments");
+    ///}
+    /// ```
+    fn force_hydrate(&mut self, key: &str) -> &mut Self;
+
+    /// Subscribe to hydrations of `hydrate_lazy` subtrees - `handler` is called with the key
+    /// of each hydrated subtree, e.g. to fetch its data.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///orders.subscribe_lazy_hydration(Msg::Hydrated);
+    /// ```
+    fn subscribe_lazy_hydration(&mut self, handler: impl Fn(String) -> Ms + 'static) -> &mut Self;
+
+    /// Open the native share sheet and send `handler`'s message with the result.
+    ///
+    /// _Note:_ Unlike other commands, `navigator.share` is called immediately (not on the next tick)
+    /// because browsers allow it only during a user gesture - call `share` from `update`
+    /// of a message sent by an event handler (e.g. a click). Use `seed::browser::can_share`
+    /// to render a fallback (e.g. `seed::browser::share::copy_to_clipboard`) in other browsers.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///Msg::Share => {
+    ///    orders.share(model.share_data.clone(), Msg::Shared);
+    ///}
+    ///Msg::Shared(Err(ShareError::Cancelled)) => (),
+    ///Msg::Shared(Err(error)) => model.error = Some(error.to_string()),
+    /// ```
+    fn share(
         &mut self,
-        callback: impl FnOnce(Option<RenderTimestampDelta>) -> Ms + 'static,
+        data: ShareData,
+        handler: impl FnOnce(Result<(), ShareError>) -> Ms + 'static,
     ) -> &mut Self;
+
+    /// Get frame-rate statistics of the recent renders. See `seed::app::frame_stats`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///Msg::RefreshHud => model.frame_stats = orders.frame_stats(),
+    /// ```
+    fn frame_stats(&self) -> FrameStatsSnapshot;
+
+    /// Call `handler` with the frame time (ms) and send its message to `update` when a render
+    /// takes longer than `FrameStatsOpts::long_frame_ms` after the previous one.
+    /// Notifications are rate-limited by `FrameStatsOpts::min_report_interval_ms`.
+    /// Dropping the handle unsubscribes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///model.long_frames_sub = Some(orders.subscribe_long_frames(Msg::LongFrame));
+    /// ```
+    fn subscribe_long_frames(&mut self, handler: impl Fn(f64) -> Ms + 'static) -> SubHandle;
+
+    /// Subscribe to violations of `AppBuilder::perf_budget` - e.g. to report them to telemetry.
+    /// `handler` is never called without the feature `perf-budget`.
+    ///
+    /// `handler`'s messages don't trigger renders - see `seed::app::perf_budget`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///orders.subscribe_perf_violations(Msg::PerfViolation);
+    /// ```
+    fn subscribe_perf_violations(
+        &mut self,
+        handler: impl Fn(PerfViolation) -> Ms + 'static,
+    ) -> &mut Self;
+
+    /// Set document metadata of the current route - e.g. when the route changes or when its data
+    /// are loaded. `None` fields are inherited from `AppBuilder::base_meta`.
+    /// Changed tags are written into `<head>` after the next render. See `seed::app::page_meta`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///Msg::UrlChanged(subs::UrlChanged(url)) => {
+    ///    orders.set_page_meta(PageMeta {
+    ///        title: Some("Blog".to_owned()),
+    ///        canonical: Some("https://seed-rs.org/blog".to_owned()),
+    ///        ..PageMeta::default()
+    ///    });
+    ///}
+    /// ```
+    fn set_page_meta(&mut self, meta: PageMeta) -> &mut Self;
+
+    /// Revert document metadata to `AppBuilder::base_meta`.
+    fn clear_page_meta(&mut self) -> &mut Self;
+
+    /// Insert `<link rel="preload">` (or `rel="prefetch"`) into `<head>`, e.g. for the critical
+    /// image of a likely next route. Links are deduplicated by `href`, `as`, `crossorigin` and `rel`
+    /// and removed when all their handles are dropped or when their TTL expires. See `seed::app::preload`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///model.hero_hint = Some(orders.preload(PreloadHint::new("/img/hero.jpg", AsKind::Image)));
+    /// ```
+    fn preload(&mut self, hint: PreloadHint) -> PreloadHandle;
+
+    /// Similar to `preload`, but `handler`'s message is sent when the hinted request
+    /// loads or fails (immediately, when it has already finished).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///let hint = PreloadHint::new("/fonts/inter.woff2", AsKind::Font).crossorigin(CrossOrigin::Anonymous);
+    ///model.font_hint = Some(orders.preload_with_status(hint, Msg::FontPreloaded));
+    /// ```
+    fn preload_with_status<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        hint: PreloadHint,
+        handler: impl FnOnce(PreloadStatus) -> MsU + 'static,
+    ) -> PreloadHandle;
+
+    /// Hint assets and perform data requests of a likely next route - e.g. on a message sent
+    /// by `prefetch_on_hover`. Assets are hinted like by `preload`; the hints are removed
+    /// when the returned handles are dropped. See `seed::app::preload`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///Msg::PostLikely(id) => {
+    ///    model.post_hints = orders.prefetch_route(
+    ///        RoutePrefetch::new()
+    ///            .asset(PreloadHint::new(cover_url(id), AsKind::Image).prefetch())
+    ///            .data(async move { Msg::PostFetched(fetch_post(id).await) }),
+    ///    );
+    ///}
+    /// ```
+    fn prefetch_route(&mut self, prefetch: RoutePrefetch<Ms>) -> Vec<PreloadHandle>;
+
+    /// Request persistent storage, so the browser doesn't evict IndexedDB and other data
+    /// under storage pressure. `handler` receives `Ok(true)` when the storage is persistent.
+    /// See `seed::browser::service::storage_manager`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///orders.request_persistent_storage(|granted| Msg::Persisted(granted.unwrap_or_default()));
+    /// ```
+    fn request_persistent_storage(
+        &mut self,
+        handler: impl FnOnce(Result<bool, Unsupported>) -> Ms + 'static,
+    ) -> &mut Self;
+
+    /// Check if the storage is persistent - e.g. to show a "Keep data on this device" button.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///orders.storage_persisted(Msg::PersistenceChecked);
+    /// ```
+    fn storage_persisted(
+        &mut self,
+        handler: impl FnOnce(Result<bool, Unsupported>) -> Ms + 'static,
+    ) -> &mut Self;
+
+    /// Estimate storage usage and quota of the origin.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///orders.storage_estimate(Msg::Quota);
+    /// ```
+    fn storage_estimate(
+        &mut self,
+        handler: impl FnOnce(Result<StorageEstimate, Unsupported>) -> Ms + 'static,
+    ) -> &mut Self;
+
+    /// Estimate storage periodically and send `handler`'s message each time usage crosses
+    /// `opts.threshold` of quota. Timers are scheduled by the app's `Clock`.
+    /// Nothing is sent in browsers without `navigator.storage`. Dropping the handle unsubscribes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///model.quota_handle = Some(orders.subscribe_storage_quota(
+    ///    StorageQuotaOpts {
+    ///        threshold: 0.9,
+    ///        interval: Duration::from_secs(300),
+    ///    },
+    ///    Msg::StorageAlmostFull,
+    ///));
+    /// ```
+    fn subscribe_storage_quota(
+        &mut self,
+        opts: StorageQuotaOpts,
+        handler: impl Fn(StorageEstimate) -> Ms + 'static,
+    ) -> StorageQuotaHandle;
+
+    /// Send `handler`'s message to `update` on each `orders.notify` with a value of type `SubMs`
+    /// (from any component). Dropping the handle unsubscribes - store it in the component's model.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///model.cart_sub = Some(orders.subscribe(|event: ItemAdded| Msg::ItemAdded(event.id)));
+    /// ```
+    fn subscribe<SubMs: Any + Clone>(
+        &mut self,
+        handler: impl Fn(SubMs) -> Ms + 'static,
+    ) -> SubHandle;
+
+    /// Send `message` to all subscribers of its type - see `orders.subscribe`.
+    /// Their messages are handled after the current `update`, like messages from `send_msg`.
+    /// It's no-op when there are no subscribers.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///orders.notify(ItemAdded { id: item.id });
+    /// ```
+    fn notify(&mut self, message: impl Any + Clone) -> &mut Self;
+
+    /// Similar to `perform_cmd`, but the command's output is sent to all subscribers
+    /// of its type (see `orders.subscribe`) instead of `update` - each subscriber receives it
+    /// mapped into its own message, in the subscription order.
+    ///
+    /// All subscribers' messages are processed together when the command resolves,
+    /// so the changes are rendered at once. Subscribers are resolved at that time.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///orders.perform_cmd_notify(async { UserRefreshed(fetch_user().await) });
+    /// ```
+    fn perform_cmd_notify<SubMs: Any + Clone>(
+        &mut self,
+        cmd: impl Future<Output = SubMs> + 'static,
+    ) -> &mut Self;
+
+    /// Change the app's locale (see `seed::app::locale`) and store it, so it's restored
+    /// after reload. Unsupported locales are resolved to the best supported one.
+    ///
+    /// When the locale has changed, the page is rerendered - even if `skip` is called -
+    /// and `LocaleChanged` is sent to subscribers (see `subscribe`).
+    /// Invalid tags are logged and ignored.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///Msg::LanguageSelected(tag) => {
+    ///    orders.set_locale(&tag);
+    ///}
+    /// ```
+    fn set_locale(&mut self, tag: &str) -> &mut Self;
+
+    /// Show a toast by the app's `ToastManager` - see `seed::app::toast`.
+    /// It's no-op when there is no `ToastManager`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///orders.toast(Toast::info("Saved").timeout(Duration::from_secs(4)).action("Undo", Msg::Undo));
+    /// ```
+    fn toast(&mut self, toast: Toast<Ms>) -> &mut Self;
+
+    /// Share the value stored in local storage under `key` with other tabs - `handler`'s message
+    /// is sent to `update` whenever another tab writes a new value by `orders.update_synced`.
+    /// Get the current value by `SyncedSlice::get`. Dropping the slice unsubscribes.
+    /// See `seed::browser::service::synced_slice`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///let theme = orders.sync_slice("theme", Msg::ThemeChanged);
+    ///Model {
+    ///    theme_value: theme.get().unwrap_or_default(),
+    ///    theme,
+    ///}
+    /// ```
+    fn sync_slice<T>(&mut self, key: &str, handler: impl Fn(T) -> Ms + 'static) -> SyncedSlice<T>
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Clone + 'static;
+
+    /// Store `value` of `slice` and send it to other tabs. This tab's `update` doesn't receive it.
+    ///
+    /// When the value can't be stored (e.g. the quota is exceeded), it's kept in this tab only
+    /// and a warning is logged.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///Msg::ThemeSelected(theme) => {
+    ///    model.theme_value = theme.clone();
+    ///    if let Err(error) = orders.update_synced(&model.theme, theme) {
+    ///        error!(error);
+    ///    }
+    ///}
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns error when `value` can't be serialized or local storage isn't available.
+    fn update_synced<T>(&mut self, slice: &SyncedSlice<T>, value: T) -> Result<(), SyncError>
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Clone + 'static;
+
+    /// Open a WebSocket connection to `url` and send `handlers`' messages to `update`
+    /// on its events. Store the returned handle in your model - dropping it closes the connection.
+    /// See `seed::browser::service::websocket`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///model.ws = Some(orders.open_websocket(
+    ///    "wss://example.com/ws",
+    ///    WebSocketHandlers::new()
+    ///        .on_open(|| Msg::Connected)
+    ///        .on_message(Msg::Received)
+    ///        .on_close(Msg::Closed),
+    ///)?);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns error when the URL is invalid or its port is blocked.
+    fn open_websocket(
+        &mut self,
+        url: &str,
+        handlers: WebSocketHandlers<Ms>,
+    ) -> Result<WebSocket, WebSocketError>;
+}
+
+// ------ Callback return values ------
+
+/// Values which callbacks and commands passed to `Orders` can return -
+/// `Ms`, `Option<Ms>` or `()` (no message).
+///
+/// `Kind` only distinguishes the implementations (`()` could also be `Ms`) - it's inferred,
+/// see `msg_return_kind`.
+pub trait MsgReturn<Ms, Kind> {
+    /// The message to send to `update`, if any.
+    fn into_msg(self) -> Option<Ms>;
+}
+
+/// Types of the parameter `Kind` of `MsgReturn`.
+pub mod msg_return_kind {
+    /// The callback returns `Ms`.
+    pub enum Msg {}
+    /// The callback returns `Option<Ms>`.
+    pub enum OptionMsg {}
+    /// The callback returns `()`.
+    pub enum NoMsg {}
+    /// The callback returns `Result<Ms, Ms>` (deprecated).
+    pub enum ResultMsg {}
+}
+
+impl<Ms> MsgReturn<Ms, msg_return_kind::Msg> for Ms {
+    fn into_msg(self) -> Option<Ms> {
+        Some(self)
+    }
+}
+
+impl<Ms> MsgReturn<Ms, msg_return_kind::OptionMsg> for Option<Ms> {
+    fn into_msg(self) -> Option<Ms> {
+        self
+    }
+}
+
+impl<Ms> MsgReturn<Ms, msg_return_kind::NoMsg> for () {
+    fn into_msg(self) -> Option<Ms> {
+        None
+    }
+}
+
+/// Both `Ok` and `Err` messages are sent to `update`.
+#[deprecated(
+    since = "0.5.0",
+    note = "Commands resolving to `Result<Ms, Ms>` are supported only for compatibility with `future::ok(msg)`. Return `Ms` instead."
+)]
+impl<Ms> MsgReturn<Ms, msg_return_kind::ResultMsg> for Result<Ms, Ms> {
+    fn into_msg(self) -> Option<Ms> {
+        Some(self.unwrap_or_else(identity))
+    }
+}
+
+/// Convert `callback`, which returns `Ms`, `Option<Ms>` or `()`, into one returning `Option<Ms>`.
+pub(crate) fn map_callback_return<
+    Ms: 'static,
+    MsU: MsgReturn<Ms, Kind> + 'static,
+    Kind,
+    T: 'static,
+>(
+    callback: impl FnOnce(T) -> MsU + 'static,
+) -> Box<dyn FnOnce(T) -> Option<Ms>> {
+    Box::new(move |value| callback(value).into_msg())
+}
+
+/// Convert `cmd`, which resolves to `Ms`, `Option<Ms>` or `()`, into a future resolving
+/// to `Option<Ms>`.
+pub(crate) fn map_cmd_output<Ms: 'static, MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+    cmd: impl Future<Output = MsU> + 'static,
+) -> LocalFutureObj<'static, Option<Ms>> {
+    LocalFutureObj::new(Box::new(cmd.map(|output| output.into_msg())))
 }
@@ -1,16 +1,84 @@
-use crate::app::orders::{proxy::OrdersProxy, Orders};
+use crate::app::orders::{
+    map_callback_return, map_cmd_output, proxy::OrdersProxy, MsgReturn, Orders,
+};
 use crate::app::{
-    effects::Effect, render_timestamp_delta::RenderTimestampDelta, App, ShouldRender, UndefinedGMsg,
+    cmd_handle::{self, CmdHandle},
+    cmd_lanes::{self, Lane, LaneStats},
+    context, debounced_cmds, deferred_navigation,
+    effects::Effect,
+    frame_stats::FrameStatsSnapshot,
+    locale::{self, LocaleChanged},
+    offline_retry::{self, CmdFactory, CmdFailure},
+    page_meta::{self, PageMeta},
+    preload::{self, PreloadHandle, PreloadHint, PreloadStatus, RoutePrefetch},
+    stream_handle::{self, StreamHandle},
+    subs::Subscriptions,
+    toast::{Toast, ToastRequest},
+    types::AfterNextRenderCallback,
+    App, FrameworkError, PerfViolation, RenderInfo, RenderTimestampDelta, ShouldRender, SubHandle,
+    UndefinedGMsg,
+};
+use crate::browser::{
+    dom::{
+        event_handler::{self, FromEvent},
+        global_events::EventHandle,
+        scroll_progress::{ScrollProgressHandle, ScrollProgressOpts},
+        viewport::{Viewport, ViewportHandle, ViewportOpts},
+    },
+    service::{
+        routing,
+        storage_manager::{self, StorageEstimate, StorageQuotaHandle, StorageQuotaOpts},
+        synced_slice::{SyncError, SyncedSlice},
+        websocket::{WebSocket, WebSocketError, WebSocketHandlers},
+    },
+    share::{self, ShareData, ShareError},
+    Capabilities, Modality, Unsupported, Url,
+};
+use crate::virtual_dom::{keep_alive, lazy_hydration, view::View, ElRef, EventTrigger};
+use futures::channel::mpsc;
+use futures::future::{FutureExt, LocalFutureObj};
+use futures::stream::{Stream, StreamExt};
+use indexmap::IndexMap;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    any::Any, collections::VecDeque, convert::identity, future::Future, rc::Rc, time::Duration,
 };
-use crate::virtual_dom::view::View;
-use futures::future::LocalFutureObj;
-use std::{collections::VecDeque, convert::identity, future::Future};
+use wasm_bindgen::JsCast;
 
 #[allow(clippy::module_name_repetitions)]
 pub struct OrdersContainer<Ms: 'static, Mdl: 'static, ElC: View<Ms>, GMs = UndefinedGMsg> {
     pub(crate) should_render: ShouldRender,
     pub(crate) effects: VecDeque<Effect<Ms, GMs>>,
-    app: App<Ms, Mdl, ElC, GMs>,
+    target: Target<Ms, Mdl, ElC, GMs>,
+}
+
+enum Target<Ms: 'static, Mdl: 'static, ElC: View<Ms>, GMs> {
+    App(App<Ms, Mdl, ElC, GMs>),
+    Mock(MockedOrders<Ms>),
+}
+
+/// Orders which need the app, recorded by `OrdersContainer::mock` - see `OrdersContainer::mocked`.
+pub struct MockedOrders<Ms> {
+    /// The value of the last `render_throttle`.
+    pub render_throttle_ms: Option<u32>,
+    /// Callbacks of `after_next_render` - call them with a `RenderInfo` to get their messages.
+    pub after_next_render_callbacks: Vec<AfterNextRenderCallback<Ms>>,
+    /// Commands of `perform_cmd_debounced` with their delays, by keys. A command replaces
+    /// the pending command with the same key, like in the app.
+    pub debounced_cmds: IndexMap<String, (u32, LocalFutureObj<'static, Option<Ms>>)>,
+    /// Handlers of `subscribe` - `notify` pushes their messages to `effects`, like in the app.
+    subscriptions: Subscriptions<Ms>,
+}
+
+impl<Ms> Default for MockedOrders<Ms> {
+    fn default() -> Self {
+        Self {
+            render_throttle_ms: None,
+            after_next_render_callbacks: Vec::new(),
+            debounced_cmds: IndexMap::new(),
+            subscriptions: Subscriptions::default(),
+        }
+    }
 }
 
 impl<Ms, Mdl, ElC: View<Ms>, GMs> OrdersContainer<Ms, Mdl, ElC, GMs> {
@@ -18,7 +86,70 @@ impl<Ms, Mdl, ElC: View<Ms>, GMs> OrdersContainer<Ms, Mdl, ElC, GMs> {
         Self {
             should_render: ShouldRender::Render,
             effects: VecDeque::new(),
-            app,
+            target: Target::App(app),
+        }
+    }
+
+    /// Orders without an app - pass them to `update` in unit tests, so they don't need a browser.
+    /// Then assert on `should_render` and `effects`; commands are `Effect::Cmd` futures.
+    ///
+    /// Only orders which don't need the app are supported - `proxy`, `proxy_with`,
+    /// `proxy_global`, `render`, `force_render_now`, `skip`, `send_msg`, `batch`, `perform_cmd`,
+    /// `perform_cmd_with_handle`, `stream`, `stream_with_handle`, `send_g_msg`, `perform_g_cmd`,
+    /// `perform_g_cmd_with_handle` and `msg_mapper`.
+    /// `render_throttle`, `after_next_render`, `perform_cmd_debounced` and `subscribe` are recorded
+    /// in `mocked`; `notify` sends messages of the recorded subscriptions.
+    /// `retry_when_online` commands are performed once - they aren't retried.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///let mut orders = OrdersContainer::<Msg, Model, Node<Msg>>::mock();
+    ///update(Msg::Save, &mut model, &mut orders);
+    ///assert_eq!(orders.should_render(), ShouldRender::Skip);
+    ///match orders.effects().pop_front() {
+    ///    Some(Effect::Cmd(cmd)) => assert_eq!(cmd.await, Some(Msg::Saved)),
+    ///    _ => panic!("`Msg::Save` should perform a command"),
+    ///}
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Other orders panic.
+    pub fn mock() -> Self {
+        Self {
+            should_render: ShouldRender::Render,
+            effects: VecDeque::new(),
+            target: Target::Mock(MockedOrders::default()),
+        }
+    }
+
+    /// The render requested by the last `render`, `force_render_now` or `skip`.
+    pub fn should_render(&self) -> ShouldRender {
+        self.should_render
+    }
+
+    /// Messages, commands and streams ordered so far, in the order of calls.
+    pub fn effects(&mut self) -> &mut VecDeque<Effect<Ms, GMs>> {
+        &mut self.effects
+    }
+
+    /// Orders recorded by `OrdersContainer::mock`.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the orders aren't mocked.
+    pub fn mocked(&mut self) -> &mut MockedOrders<Ms> {
+        match &mut self.target {
+            Target::Mock(mocked) => mocked,
+            Target::App(_) => panic!("Orders of a running app aren't mocked"),
+        }
+    }
+
+    fn app(&self) -> &App<Ms, Mdl, ElC, GMs> {
+        match &self.target {
+            Target::App(app) => app,
+            Target::Mock(_) => panic!("The order isn't supported by `OrdersContainer::mock`"),
         }
     }
 
@@ -28,12 +159,13 @@ impl<Ms, Mdl, ElC: View<Ms>, GMs> OrdersContainer<Ms, Mdl, ElC, GMs> {
     }
 }
 
-impl<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs> Orders<Ms, GMs>
+impl<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs: 'static> Orders<Ms, GMs>
     for OrdersContainer<Ms, Mdl, ElC, GMs>
 {
     type AppMs = Ms;
     type Mdl = Mdl;
     type ElC = ElC;
+    type AppGMs = GMs;
 
     #[allow(clippy::redundant_closure)]
     fn proxy<ChildMs: 'static>(
@@ -43,6 +175,20 @@ impl<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs> Orders<Ms, GMs>
         OrdersProxy::new(self, move |child_ms| f.clone()(child_ms))
     }
 
+    fn proxy_with<ChildMs: 'static>(
+        &mut self,
+        f: impl Fn(ChildMs) -> Ms + 'static,
+    ) -> OrdersProxy<ChildMs, Ms, Mdl, ElC, GMs> {
+        OrdersProxy::new(self, f)
+    }
+
+    fn proxy_global<ChildGMs: 'static>(
+        &mut self,
+        f: impl Fn(ChildGMs) -> GMs + 'static,
+    ) -> OrdersProxy<Ms, Ms, Mdl, ElC, ChildGMs, GMs> {
+        OrdersProxy::new_with_global(self, identity, f)
+    }
+
     fn render(&mut self) -> &mut Self {
         self.should_render = ShouldRender::Render;
         self
@@ -58,20 +204,125 @@ impl<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs> Orders<Ms, GMs>
         self
     }
 
+    fn render_throttle(&mut self, ms: u32) -> &mut Self {
+        match &mut self.target {
+            Target::App(app) => app.data.render_throttle_ms.set(ms),
+            Target::Mock(mocked) => mocked.render_throttle_ms = Some(ms),
+        }
+        self
+    }
+
     fn send_msg(&mut self, msg: Ms) -> &mut Self {
         self.effects.push_back(msg.into());
         self
     }
 
-    fn perform_cmd<C>(&mut self, cmd: C) -> &mut Self
-    where
-        C: Future<Output = Result<Ms, Ms>> + 'static,
-    {
-        let effect = Effect::Cmd(LocalFutureObj::new(Box::new(cmd)));
-        self.effects.push_back(effect);
+    fn batch(&mut self, msgs: impl IntoIterator<Item = Ms>) -> &mut Self {
+        self.effects
+            .push_back(Effect::Batch(msgs.into_iter().collect()));
+        self
+    }
+
+    fn perform_cmd<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        cmd: impl Future<Output = MsU> + 'static,
+    ) -> &mut Self {
+        self.effects.push_back(Effect::Cmd(map_cmd_output(cmd)));
+        self
+    }
+
+    fn perform_cmd_with_handle<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        cmd: impl Future<Output = MsU> + 'static,
+    ) -> CmdHandle {
+        let (cmd, handle) = cmd_handle::abortable(map_cmd_output(cmd));
+        self.effects
+            .push_back(Effect::Cmd(LocalFutureObj::new(Box::new(cmd))));
+        handle
+    }
+
+    fn perform_cmd_debounced<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        key: impl Into<String>,
+        delay_ms: u32,
+        cmd: impl Future<Output = MsU> + 'static,
+    ) -> &mut Self {
+        match &mut self.target {
+            Target::App(app) => {
+                debounced_cmds::debounce(app, key.into(), delay_ms, map_cmd_output(cmd));
+            }
+            Target::Mock(mocked) => {
+                let key = key.into();
+                // The replaced command is moved to the end, like a restarted timer.
+                mocked.debounced_cmds.shift_remove(&key);
+                mocked
+                    .debounced_cmds
+                    .insert(key, (delay_ms, map_cmd_output(cmd)));
+            }
+        }
+        self
+    }
+
+    fn perform_cmd_in_lane<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        lane: &Lane,
+        cmd: impl Future<Output = MsU> + 'static,
+    ) -> &mut Self {
+        cmd_lanes::perform(self.app(), lane, map_cmd_output(cmd));
+        self
+    }
+
+    fn perform_cmd_in_lane_with_handle<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        lane: &Lane,
+        cmd: impl Future<Output = MsU> + 'static,
+    ) -> CmdHandle {
+        let (cmd, handle) = cmd_handle::abortable(map_cmd_output(cmd));
+        match cmd_lanes::perform(self.app(), lane, LocalFutureObj::new(Box::new(cmd))) {
+            Some(remove_queued) => handle.on_cancel(remove_queued),
+            None => handle,
+        }
+    }
+
+    fn lane_stats(&self, lane: &Lane) -> LaneStats {
+        cmd_lanes::stats(self.app(), lane)
+    }
+
+    fn stream(&mut self, stream: impl Stream<Item = Ms> + 'static) -> &mut Self {
+        self.effects.push_back(Effect::Stream(stream.boxed_local()));
         self
     }
 
+    fn stream_with_handle(&mut self, stream: impl Stream<Item = Ms> + 'static) -> StreamHandle {
+        let (stream, handle) = stream_handle::cancelable(stream);
+        self.effects.push_back(Effect::Stream(stream));
+        handle
+    }
+
+    fn request_animation_frame_stream(
+        &mut self,
+        handler: impl FnOnce(RenderInfo) -> Ms + Clone + 'static,
+    ) -> StreamHandle {
+        let (sender, receiver) = mpsc::unbounded();
+        self.app()
+            .data
+            .animation_frame_senders
+            .borrow_mut()
+            .push(sender);
+        self.app().request_frame();
+
+        let mut previous_timestamp = None;
+        self.stream_with_handle(receiver.map(move |timestamp| {
+            let timestamp_delta = previous_timestamp
+                .replace(timestamp)
+                .map(|previous| RenderTimestampDelta::new(timestamp - previous));
+            handler.clone()(RenderInfo {
+                timestamp,
+                timestamp_delta,
+            })
+        }))
+    }
+
     fn send_g_msg(&mut self, g_msg: GMs) -> &mut Self {
         let effect = Effect::GMsg(g_msg);
         self.effects.push_back(effect);
@@ -82,28 +333,615 @@ impl<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs> Orders<Ms, GMs>
     where
         C: Future<Output = Result<GMs, GMs>> + 'static,
     {
+        let g_cmd = g_cmd.map(|result| Some(result.unwrap_or_else(identity)));
         let effect = Effect::GCmd(LocalFutureObj::new(Box::new(g_cmd)));
         self.effects.push_back(effect);
         self
     }
 
+    fn perform_g_cmd_with_handle<C>(&mut self, g_cmd: C) -> CmdHandle
+    where
+        C: Future<Output = Result<GMs, GMs>> + 'static,
+    {
+        let (g_cmd, handle) =
+            cmd_handle::abortable(g_cmd.map(|result| Some(result.unwrap_or_else(identity))));
+        let effect = Effect::GCmd(LocalFutureObj::new(Box::new(g_cmd)));
+        self.effects.push_back(effect);
+        handle
+    }
+
     fn clone_app(&self) -> App<Self::AppMs, Self::Mdl, Self::ElC, GMs> {
-        self.app.clone()
+        self.app().clone()
     }
 
     fn msg_mapper(&self) -> Box<dyn Fn(Ms) -> Self::AppMs> {
         Box::new(identity)
     }
 
-    fn after_next_render(
+    fn after_next_render<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        callback: impl FnOnce(RenderInfo) -> MsU + 'static,
+    ) -> &mut Self {
+        let callback = map_callback_return(callback);
+        match &mut self.target {
+            Target::App(app) => app
+                .data
+                .after_next_render_callbacks
+                .borrow_mut()
+                .push(callback),
+            Target::Mock(mocked) => mocked.after_next_render_callbacks.push(callback),
+        }
+        self
+    }
+
+    fn capabilities(&self) -> &Capabilities {
+        &self.app().cfg.capabilities
+    }
+
+    fn subscribe_input_modality(
+        &mut self,
+        handler: impl Fn(Modality) -> Ms + 'static,
+    ) -> SubHandle {
+        let (handle, alive) = SubHandle::new();
+        self.app()
+            .data
+            .input_modality_handlers
+            .borrow_mut()
+            .push((alive, Box::new(handler)));
+        handle
+    }
+
+    fn input_modality(&self) -> Option<Modality> {
+        self.app().data.input_modality_tracker.borrow().current()
+    }
+
+    fn subscribe_scroll_progress(
+        &mut self,
+        element: &web_sys::Element,
+        opts: ScrollProgressOpts,
+        handler: impl Fn(f64) -> Ms + 'static,
+    ) -> Result<ScrollProgressHandle, Unsupported> {
+        let app = self.app().clone();
+        ScrollProgressHandle::new(
+            &self.app().cfg.capabilities,
+            element,
+            opts,
+            move |progress| app.update(handler(progress)),
+        )
+    }
+
+    fn subscribe_scroll_progress_el_ref<E: JsCast + 'static>(
+        &mut self,
+        el_ref: &ElRef<E>,
+        opts: ScrollProgressOpts,
+        handler: impl Fn(f64) -> Ms + 'static,
+    ) -> Result<ScrollProgressHandle, Unsupported> {
+        let app = self.app().clone();
+        let (handle, start) = ScrollProgressHandle::for_el_ref(
+            &self.app().cfg.capabilities,
+            el_ref,
+            opts,
+            move |progress| app.update(handler(progress)),
+        )?;
+        self.after_next_render(move |_| start());
+        Ok(handle)
+    }
+
+    fn subscribe_viewport(
+        &mut self,
+        opts: ViewportOpts,
+        handler: impl Fn(Viewport) -> Ms + 'static,
+    ) -> ViewportHandle {
+        let handler = Rc::new(handler);
+        let app = self.app().clone();
+        let handle = ViewportHandle::new(opts, {
+            let handler = Rc::clone(&handler);
+            move |viewport| app.update(handler(viewport))
+        });
+        self.send_msg(handler(handle.viewport()));
+        handle
+    }
+
+    fn subscribe_window_event<T: EventTrigger, MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        trigger: T,
+        handler: impl FnOnce(T::Event) -> MsU + Clone + 'static,
+    ) -> EventHandle {
+        EventHandle::window(
+            trigger.ev(),
+            global_event_handler(self.app().clone(), move |event| {
+                handler.clone()(event).into_msg()
+            }),
+        )
+    }
+
+    fn subscribe_document_event<T: EventTrigger, MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        trigger: T,
+        handler: impl FnOnce(T::Event) -> MsU + Clone + 'static,
+    ) -> EventHandle {
+        EventHandle::document(
+            trigger.ev(),
+            global_event_handler(self.app().clone(), move |event| {
+                handler.clone()(event).into_msg()
+            }),
+        )
+    }
+
+    fn drop_kept_alive(&mut self, key: &str) -> &mut Self {
+        let parked = self.app().data.kept_alive.borrow_mut().remove(key);
+        if let Some(parked) = parked {
+            keep_alive::drop_parked(parked, &self.app().cfg.renderers);
+        }
+        self
+    }
+
+    fn retry_when_online<C>(&mut self, cmd_factory: impl Fn() -> C + 'static) -> &mut Self
+    where
+        C: Future<Output = Result<Ms, CmdFailure<Ms>>> + 'static,
+    {
+        let cmd_factory: CmdFactory<Ms> =
+            Rc::new(move || LocalFutureObj::new(Box::new(cmd_factory())));
+        let cmd = self.offline_retry_cmd(cmd_factory);
+        self.effects.push_back(Effect::Cmd(cmd));
+        self
+    }
+
+    fn retry_when_online_with_handle<C>(
+        &mut self,
+        cmd_factory: impl Fn() -> C + 'static,
+    ) -> CmdHandle
+    where
+        C: Future<Output = Result<Ms, CmdFailure<Ms>>> + 'static,
+    {
+        let cmd_factory: CmdFactory<Ms> =
+            Rc::new(move || LocalFutureObj::new(Box::new(cmd_factory())));
+        let (cmd, handle) = cmd_handle::abortable(self.offline_retry_cmd(cmd_factory));
+        self.effects
+            .push_back(Effect::Cmd(LocalFutureObj::new(Box::new(cmd))));
+        handle
+    }
+
+    fn pending_offline_retries(&self) -> usize {
+        self.app().data.offline_retries.borrow().len()
+    }
+
+    fn cancel_offline_retries(&mut self) -> &mut Self {
+        self.app().data.offline_retries.borrow_mut().clear();
+        self
+    }
+
+    fn defer_navigation<C>(&mut self, cmd: C, timeout: Duration, on_timeout: Ms) -> &mut Self
+    where
+        C: Future<Output = Result<Ms, Ms>> + 'static,
+    {
+        let cmd = LocalFutureObj::new(Box::new(cmd));
+        for msg in deferred_navigation::defer(self.app(), cmd, timeout, on_timeout) {
+            self.send_msg(msg);
+        }
+        self
+    }
+
+    fn cancel_deferred_navigation(&mut self) -> &mut Self {
+        for msg in deferred_navigation::cancel(self.app()) {
+            self.send_msg(msg);
+        }
+        self
+    }
+
+    fn subscribe_navigation_pending(
         &mut self,
-        callback: impl FnOnce(Option<RenderTimestampDelta>) -> Ms + 'static,
+        handler: impl Fn(bool) -> Ms + 'static,
     ) -> &mut Self {
-        self.app
+        self.app()
+            .data
+            .deferred_navigation
+            .borrow_mut()
+            .pending_handlers
+            .push(Box::new(handler));
+        self
+    }
+
+    fn push_route<U: Into<Url>>(&mut self, url: U) -> &mut Self {
+        let outcome = self
+            .app()
+            .resolve_navigation(routing::NavigationSource::Push(url.into()));
+        if let Some(msg) = outcome.msg {
+            self.send_msg(msg);
+        }
+        self
+    }
+
+    fn replace_route<U: Into<Url>>(&mut self, url: U) -> &mut Self {
+        let url = routing::replace_route_with_base_path(&self.app().cfg.base_path, url);
+        if let Some(msg) = self.app().navigate(url) {
+            self.send_msg(msg);
+        }
+        self
+    }
+
+    fn current_url(&self) -> Url {
+        // Updated by navigations - it doesn't contain the base path.
+        self.app().data.current_url.borrow().clone()
+    }
+
+    fn context<C: 'static>(&self) -> Option<Rc<C>> {
+        context::get(&self.app().cfg.contexts)
+    }
+
+    fn force_hydrate(&mut self, key: &str) -> &mut Self {
+        for msg in lazy_hydration::force_hydrate(self.app(), key) {
+            self.send_msg(msg);
+        }
+        self
+    }
+
+    fn subscribe_lazy_hydration(&mut self, handler: impl Fn(String) -> Ms + 'static) -> &mut Self {
+        self.app()
             .data
-            .after_next_render_callbacks
+            .lazy_hydration
             .borrow_mut()
-            .push(Box::new(callback));
+            .handlers
+            .push(Box::new(handler));
+        self
+    }
+
+    fn share(
+        &mut self,
+        data: ShareData,
+        handler: impl FnOnce(Result<(), ShareError>) -> Ms + 'static,
+    ) -> &mut Self {
+        // `share` calls the browser API right now, during the user gesture.
+        let result = share::share(&self.app().cfg.capabilities, &data);
+        self.perform_cmd(result.map(handler))
+    }
+
+    fn frame_stats(&self) -> FrameStatsSnapshot {
+        self.app().data.frame_stats.borrow().snapshot()
+    }
+
+    fn subscribe_long_frames(&mut self, handler: impl Fn(f64) -> Ms + 'static) -> SubHandle {
+        let (handle, alive) = SubHandle::new();
+        self.app()
+            .data
+            .frame_stats
+            .borrow_mut()
+            .long_frame_handlers
+            .push((alive, Box::new(handler)));
+        handle
+    }
+
+    fn subscribe_perf_violations(
+        &mut self,
+        handler: impl Fn(PerfViolation) -> Ms + 'static,
+    ) -> &mut Self {
+        #[cfg(feature = "perf-budget")]
+        self.app()
+            .data
+            .perf_violations
+            .borrow_mut()
+            .handlers
+            .push(Box::new(handler));
+        self
+    }
+
+    fn set_page_meta(&mut self, meta: PageMeta) -> &mut Self {
+        page_meta::set(self.app(), Some(meta));
+        self
+    }
+
+    fn clear_page_meta(&mut self) -> &mut Self {
+        page_meta::set(self.app(), None);
+        self
+    }
+
+    fn preload(&mut self, hint: PreloadHint) -> PreloadHandle {
+        preload::preload(self.app(), hint, None).0
+    }
+
+    fn preload_with_status<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        hint: PreloadHint,
+        handler: impl FnOnce(PreloadStatus) -> MsU + 'static,
+    ) -> PreloadHandle {
+        let (handle, msg) = preload::preload(self.app(), hint, Some(map_callback_return(handler)));
+        if let Some(msg) = msg {
+            self.send_msg(msg);
+        }
+        handle
+    }
+
+    fn prefetch_route(&mut self, prefetch: RoutePrefetch<Ms>) -> Vec<PreloadHandle> {
+        for cmd in prefetch.data {
+            self.perform_cmd(cmd);
+        }
+        prefetch
+            .hints
+            .into_iter()
+            .map(|hint| self.preload(hint))
+            .collect()
+    }
+
+    fn request_persistent_storage(
+        &mut self,
+        handler: impl FnOnce(Result<bool, Unsupported>) -> Ms + 'static,
+    ) -> &mut Self {
+        let result = storage_manager::persist(&self.app().cfg.capabilities);
+        self.perform_cmd(result.map(handler))
+    }
+
+    fn storage_persisted(
+        &mut self,
+        handler: impl FnOnce(Result<bool, Unsupported>) -> Ms + 'static,
+    ) -> &mut Self {
+        let result = storage_manager::persisted(&self.app().cfg.capabilities);
+        self.perform_cmd(result.map(handler))
+    }
+
+    fn storage_estimate(
+        &mut self,
+        handler: impl FnOnce(Result<StorageEstimate, Unsupported>) -> Ms + 'static,
+    ) -> &mut Self {
+        let result = storage_manager::estimate(&self.app().cfg.capabilities);
+        self.perform_cmd(result.map(handler))
+    }
+
+    fn subscribe_storage_quota(
+        &mut self,
+        opts: StorageQuotaOpts,
+        handler: impl Fn(StorageEstimate) -> Ms + 'static,
+    ) -> StorageQuotaHandle {
+        let app = self.app();
+        let (capabilities, clock) = (&app.cfg.capabilities, Rc::clone(&app.cfg.clock));
+        let app = app.clone();
+        StorageQuotaHandle::new(opts, capabilities, clock, move |estimate| {
+            app.update(handler(estimate))
+        })
+    }
+
+    fn subscribe<SubMs: Any + Clone>(
+        &mut self,
+        handler: impl Fn(SubMs) -> Ms + 'static,
+    ) -> SubHandle {
+        match &mut self.target {
+            Target::App(app) => app.data.subscriptions.borrow_mut().subscribe(handler),
+            Target::Mock(mocked) => mocked.subscriptions.subscribe(handler),
+        }
+    }
+
+    fn notify(&mut self, message: impl Any + Clone) -> &mut Self {
+        let messages = match &mut self.target {
+            Target::App(app) => app.data.subscriptions.borrow_mut().notify(&message),
+            Target::Mock(mocked) => mocked.subscriptions.notify(&message),
+        };
+        for msg in messages {
+            self.send_msg(msg);
+        }
+        self
+    }
+
+    fn perform_cmd_notify<SubMs: Any + Clone>(
+        &mut self,
+        cmd: impl Future<Output = SubMs> + 'static,
+    ) -> &mut Self {
+        let app = self.app().clone();
+        self.perform_cmd(cmd.map(move |message| {
+            let messages = app.data.subscriptions.borrow_mut().notify(&message);
+            // One queue - all messages are processed before the next render.
+            app.process_cmd_and_msg_queue(messages.into_iter().map(Effect::Msg).collect());
+        }))
+    }
+
+    fn set_locale(&mut self, tag: &str) -> &mut Self {
+        let app = self.app();
+        match locale::change(
+            &locale::active(&app.cfg.contexts),
+            &app.cfg.locale_opts,
+            tag,
+        ) {
+            Ok(Some(locale)) => {
+                // Views read the locale directly, so the page has to be rerendered
+                // even if the model hasn't changed - independently of `should_render`.
+                self.app().schedule_render();
+                self.notify(LocaleChanged(locale));
+            }
+            Ok(None) => (),
+            Err(error) => {
+                let msg = self
+                    .app()
+                    .framework_error(FrameworkError::InvalidLocale(error));
+                if let Some(msg) = msg {
+                    self.send_msg(msg);
+                }
+            }
+        }
         self
     }
+
+    fn toast(&mut self, toast: Toast<Ms>) -> &mut Self {
+        self.notify(ToastRequest::new(toast))
+    }
+
+    fn sync_slice<T>(&mut self, key: &str, handler: impl Fn(T) -> Ms + 'static) -> SyncedSlice<T>
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Clone + 'static,
+    {
+        let app = self.app().clone();
+        SyncedSlice::new(key, move |value| app.update(handler(value)))
+    }
+
+    fn update_synced<T>(&mut self, slice: &SyncedSlice<T>, value: T) -> Result<(), SyncError>
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Clone + 'static,
+    {
+        slice.set(value)
+    }
+
+    fn open_websocket(
+        &mut self,
+        url: &str,
+        handlers: WebSocketHandlers<Ms>,
+    ) -> Result<WebSocket, WebSocketError> {
+        let app = self.app().clone();
+        WebSocket::open(url, move |event| {
+            if let Some(msg) = handlers.msg(event) {
+                app.update(msg);
+            }
+        })
+    }
+}
+
+impl<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs: 'static> OrdersContainer<Ms, Mdl, ElC, GMs> {
+    /// Mocked orders perform the command once - there is no app to park it in.
+    fn offline_retry_cmd(
+        &self,
+        cmd_factory: CmdFactory<Ms>,
+    ) -> LocalFutureObj<'static, Option<Ms>> {
+        match &self.target {
+            Target::App(app) => LocalFutureObj::new(Box::new(offline_retry::cmd(app, cmd_factory))),
+            Target::Mock(_) => LocalFutureObj::new(Box::new(async move {
+                Some(cmd_factory().await.unwrap_or_else(CmdFailure::into_msg))
+            })),
+        }
+    }
+}
+
+/// Convert `window` / `document` events to `E` and send `handler`'s messages to `app`.
+fn global_event_handler<Ms, Mdl, ElC, GMs, E>(
+    app: App<Ms, Mdl, ElC, GMs>,
+    handler: impl FnOnce(E) -> Option<Ms> + Clone + 'static,
+) -> impl FnMut(web_sys::Event)
+where
+    Ms: 'static,
+    Mdl: 'static,
+    ElC: View<Ms> + 'static,
+    GMs: 'static,
+    E: FromEvent,
+{
+    let mut handler = event_handler::typed_handler(move |event: E| handler.clone()(event));
+    move |event| {
+        if let Some(Some(msg)) = handler(event) {
+            app.update(msg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::virtual_dom::Node;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[derive(Debug, PartialEq)]
+    enum Msg {
+        Save,
+        Saved(u32),
+        Child(ChildMsg),
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum ChildMsg {
+        Reset,
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum GMsg {
+        Saving,
+    }
+
+    fn update(msg: Msg, orders: &mut impl Orders<Msg, GMsg>) {
+        if let Msg::Save = msg {
+            orders
+                .skip()
+                .send_g_msg(GMsg::Saving)
+                .perform_cmd(async { Msg::Saved(7) });
+            orders.proxy(Msg::Child).send_msg(ChildMsg::Reset);
+        }
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn mock_records_orders() {
+        let mut orders = OrdersContainer::<Msg, (), Node<Msg>, GMsg>::mock();
+        assert_eq!(orders.should_render(), ShouldRender::Render);
+
+        update(Msg::Save, &mut orders);
+        assert_eq!(orders.should_render(), ShouldRender::Skip);
+
+        let effects = orders.effects();
+        assert_eq!(effects.len(), 3);
+        match effects.pop_front() {
+            Some(Effect::GMsg(g_msg)) => assert_eq!(g_msg, GMsg::Saving),
+            _ => panic!("global message expected"),
+        }
+        match effects.pop_front() {
+            Some(Effect::Cmd(cmd)) => assert_eq!(cmd.await, Some(Msg::Saved(7))),
+            _ => panic!("command expected"),
+        }
+        match effects.pop_front() {
+            Some(Effect::Msg(msg)) => assert_eq!(msg, Msg::Child(ChildMsg::Reset)),
+            _ => panic!("message expected"),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn mock_records_orders_on_host() {
+        let mut orders = OrdersContainer::<Msg, (), Node<Msg>, GMsg>::mock();
+        update(Msg::Save, &mut orders);
+        assert_eq!(orders.should_render(), ShouldRender::Skip);
+
+        let effects = orders.effects();
+        assert_eq!(effects.len(), 3);
+        match effects.remove(1) {
+            Some(Effect::Cmd(cmd)) => {
+                assert_eq!(futures::executor::block_on(cmd), Some(Msg::Saved(7)))
+            }
+            _ => panic!("command expected"),
+        }
+    }
+
+    #[derive(Clone)]
+    struct Stored(u32);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn mock_records_app_orders_on_host() {
+        let mut orders = OrdersContainer::<Msg, (), Node<Msg>, GMsg>::mock();
+        let _sub = orders.subscribe(|Stored(id): Stored| Msg::Saved(id));
+        orders
+            .render_throttle(100)
+            .after_next_render(|_| Msg::Save)
+            .perform_cmd_debounced("save", 300, async { Msg::Saved(1) })
+            .perform_cmd_debounced("save", 300, async { Msg::Saved(2) })
+            .notify(Stored(3))
+            .retry_when_online(|| async { Err(CmdFailure::Network(Msg::Saved(4))) });
+
+        let mocked = orders.mocked();
+        assert_eq!(mocked.render_throttle_ms, Some(100));
+        let callback = mocked.after_next_render_callbacks.remove(0);
+        let render_info = RenderInfo {
+            timestamp: 0.,
+            timestamp_delta: None,
+        };
+        assert_eq!(callback(render_info), Some(Msg::Save));
+        assert_eq!(mocked.debounced_cmds.len(), 1);
+        let (delay_ms, cmd) = mocked.debounced_cmds.remove("save").unwrap();
+        assert_eq!(delay_ms, 300);
+        assert_eq!(futures::executor::block_on(cmd), Some(Msg::Saved(2)));
+
+        let effects = orders.effects();
+        match effects.pop_front() {
+            Some(Effect::Msg(msg)) => assert_eq!(msg, Msg::Saved(3)),
+            _ => panic!("message expected"),
+        }
+        match effects.pop_front() {
+            // Mocked commands aren't retried.
+            Some(Effect::Cmd(cmd)) => {
+                assert_eq!(futures::executor::block_on(cmd), Some(Msg::Saved(4)))
+            }
+            _ => panic!("command expected"),
+        }
+    }
 }
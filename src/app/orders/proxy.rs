@@ -1,11 +1,41 @@
 use super::{
-    super::{App, Effect, MessageMapper, RenderTimestampDelta, UndefinedGMsg},
-    Orders, OrdersContainer,
+    super::{
+        cmd_handle::{self, CmdHandle},
+        cmd_lanes::{Lane, LaneStats},
+        offline_retry::CmdFailure,
+        preload::{PreloadHandle, PreloadHint, PreloadStatus, RoutePrefetch},
+        stream_handle::StreamHandle,
+        toast::Toast,
+        App, Effect, FrameStatsSnapshot, GMessageMapper, MessageMapper, PageMeta, PerfViolation,
+        RenderInfo, SubHandle, UndefinedGMsg,
+    },
+    map_callback_return, map_cmd_output, MsgReturn, Orders, OrdersContainer,
 };
-use crate::virtual_dom::View;
-use futures::future::LocalFutureObj;
+use crate::{
+    browser::{
+        dom::{
+            global_events::EventHandle,
+            scroll_progress::{ScrollProgressHandle, ScrollProgressOpts},
+            viewport::{Viewport, ViewportHandle, ViewportOpts},
+        },
+        service::{
+            storage_manager::{StorageEstimate, StorageQuotaHandle, StorageQuotaOpts},
+            synced_slice::{SyncError, SyncedSlice},
+            websocket::{WebSocket, WebSocketError, WebSocketHandlers},
+        },
+        Capabilities, Modality, ShareData, ShareError, Unsupported, Url,
+    },
+    virtual_dom::{ElRef, EventTrigger, View},
+};
+use futures::future::{FutureExt, LocalFutureObj};
+use futures::stream::{Stream, StreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+use std::any::Any;
+use std::convert::identity;
 use std::future::Future;
 use std::rc::Rc;
+use std::time::Duration;
+use wasm_bindgen::JsCast;
 
 #[allow(clippy::module_name_repetitions)]
 pub struct OrdersProxy<
@@ -15,9 +45,11 @@ pub struct OrdersProxy<
     Mdl: 'static,
     ElC: View<AppMs>,
     GMs: 'static = UndefinedGMsg,
+    AppGMs: 'static = GMs,
 > {
-    orders_container: &'a mut OrdersContainer<AppMs, Mdl, ElC, GMs>,
+    orders_container: &'a mut OrdersContainer<AppMs, Mdl, ElC, AppGMs>,
     f: Rc<dyn Fn(Ms) -> AppMs>,
+    g: Rc<dyn Fn(GMs) -> AppGMs>,
 }
 
 impl<'a, Ms: 'static, AppMs: 'static, Mdl, ElC: View<AppMs>, GMs>
@@ -26,29 +58,76 @@ impl<'a, Ms: 'static, AppMs: 'static, Mdl, ElC: View<AppMs>, GMs>
     pub fn new(
         orders_container: &'a mut OrdersContainer<AppMs, Mdl, ElC, GMs>,
         f: impl Fn(Ms) -> AppMs + 'static,
+    ) -> Self {
+        Self::new_with_global(orders_container, f, identity)
+    }
+}
+
+impl<'a, Ms: 'static, AppMs: 'static, Mdl, ElC: View<AppMs>, GMs, AppGMs>
+    OrdersProxy<'a, Ms, AppMs, Mdl, ElC, GMs, AppGMs>
+{
+    /// Similar to `new`, but also global messages are mapped by `g` - see `Orders::proxy_global`.
+    pub fn new_with_global(
+        orders_container: &'a mut OrdersContainer<AppMs, Mdl, ElC, AppGMs>,
+        f: impl Fn(Ms) -> AppMs + 'static,
+        g: impl Fn(GMs) -> AppGMs + 'static,
     ) -> Self {
         OrdersProxy {
             orders_container,
             f: Rc::new(f),
+            g: Rc::new(g),
         }
     }
 }
 
-impl<'a, Ms: 'static, AppMs: 'static, Mdl, ElC: View<AppMs> + 'static, GMs> Orders<Ms, GMs>
-    for OrdersProxy<'a, Ms, AppMs, Mdl, ElC, GMs>
+impl<
+        'a,
+        Ms: 'static,
+        AppMs: 'static,
+        Mdl,
+        ElC: View<AppMs> + 'static,
+        GMs: 'static,
+        AppGMs: 'static,
+    > Orders<Ms, GMs> for OrdersProxy<'a, Ms, AppMs, Mdl, ElC, GMs, AppGMs>
 {
     type AppMs = AppMs;
     type Mdl = Mdl;
     type ElC = ElC;
+    type AppGMs = AppGMs;
 
     fn proxy<ChildMs: 'static>(
         &mut self,
         f: impl FnOnce(ChildMs) -> Ms + 'static + Clone,
-    ) -> OrdersProxy<ChildMs, AppMs, Mdl, ElC, GMs> {
+    ) -> OrdersProxy<ChildMs, AppMs, Mdl, ElC, GMs, AppGMs> {
         let previous_f = self.f.clone();
         OrdersProxy {
             orders_container: self.orders_container,
             f: Rc::new(move |child_ms| previous_f(f.clone()(child_ms))),
+            g: self.g.clone(),
+        }
+    }
+
+    fn proxy_with<ChildMs: 'static>(
+        &mut self,
+        f: impl Fn(ChildMs) -> Ms + 'static,
+    ) -> OrdersProxy<ChildMs, AppMs, Mdl, ElC, GMs, AppGMs> {
+        let previous_f = self.f.clone();
+        OrdersProxy {
+            orders_container: self.orders_container,
+            f: Rc::new(move |child_ms| previous_f(f(child_ms))),
+            g: self.g.clone(),
+        }
+    }
+
+    fn proxy_global<ChildGMs: 'static>(
+        &mut self,
+        f: impl Fn(ChildGMs) -> GMs + 'static,
+    ) -> OrdersProxy<Ms, AppMs, Mdl, ElC, ChildGMs, AppGMs> {
+        let previous_g = self.g.clone();
+        OrdersProxy {
+            orders_container: self.orders_container,
+            f: self.f.clone(),
+            g: Rc::new(move |child_g_ms| previous_g(f(child_g_ms))),
         }
     }
 
@@ -67,6 +146,11 @@ impl<'a, Ms: 'static, AppMs: 'static, Mdl, ElC: View<AppMs> + 'static, GMs> Orde
         self
     }
 
+    fn render_throttle(&mut self, ms: u32) -> &mut Self {
+        self.orders_container.render_throttle(ms);
+        self
+    }
+
     #[allow(clippy::redundant_closure)]
     fn send_msg(&mut self, msg: Ms) -> &mut Self {
         let f = self.f.clone();
@@ -77,19 +161,108 @@ impl<'a, Ms: 'static, AppMs: 'static, Mdl, ElC: View<AppMs> + 'static, GMs> Orde
     }
 
     #[allow(clippy::redundant_closure)]
-    fn perform_cmd<C>(&mut self, cmd: C) -> &mut Self
-    where
-        C: Future<Output = Result<Ms, Ms>> + 'static,
-    {
+    fn batch(&mut self, msgs: impl IntoIterator<Item = Ms>) -> &mut Self {
+        let f = self.f.clone();
+        self.orders_container
+            .effects
+            .push_back(Effect::Batch(msgs.into_iter().collect()).map_msg(move |ms| f(ms)));
+        self
+    }
+
+    #[allow(clippy::redundant_closure)]
+    fn perform_cmd<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        cmd: impl Future<Output = MsU> + 'static,
+    ) -> &mut Self {
         let f = self.f.clone();
+        let effect = Effect::Cmd(map_cmd_output::<Ms, _, _>(cmd)).map_msg(move |ms| f(ms));
+        self.orders_container.effects.push_back(effect);
+        self
+    }
+
+    #[allow(clippy::redundant_closure)]
+    fn perform_cmd_with_handle<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        cmd: impl Future<Output = MsU> + 'static,
+    ) -> CmdHandle {
+        let f = self.f.clone();
+        let (cmd, handle) = cmd_handle::abortable(map_cmd_output::<Ms, _, _>(cmd));
         let effect = Effect::Cmd(LocalFutureObj::new(Box::new(cmd))).map_msg(move |ms| f(ms));
         self.orders_container.effects.push_back(effect);
+        handle
+    }
+
+    #[allow(clippy::redundant_closure)]
+    fn perform_cmd_debounced<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        key: impl Into<String>,
+        delay_ms: u32,
+        cmd: impl Future<Output = MsU> + 'static,
+    ) -> &mut Self {
+        let f = self.f.clone();
+        let cmd = map_cmd_output::<Ms, _, _>(cmd).map(move |ms| ms.map(|ms| f(ms)));
+        self.orders_container
+            .perform_cmd_debounced(key, delay_ms, cmd);
         self
     }
 
+    #[allow(clippy::redundant_closure)]
+    fn perform_cmd_in_lane<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        lane: &Lane,
+        cmd: impl Future<Output = MsU> + 'static,
+    ) -> &mut Self {
+        let f = self.f.clone();
+        let cmd = map_cmd_output::<Ms, _, _>(cmd).map(move |ms| ms.map(|ms| f(ms)));
+        self.orders_container.perform_cmd_in_lane(lane, cmd);
+        self
+    }
+
+    #[allow(clippy::redundant_closure)]
+    fn perform_cmd_in_lane_with_handle<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        lane: &Lane,
+        cmd: impl Future<Output = MsU> + 'static,
+    ) -> CmdHandle {
+        let f = self.f.clone();
+        let cmd = map_cmd_output::<Ms, _, _>(cmd).map(move |ms| ms.map(|ms| f(ms)));
+        self.orders_container
+            .perform_cmd_in_lane_with_handle(lane, cmd)
+    }
+
+    fn lane_stats(&self, lane: &Lane) -> LaneStats {
+        self.orders_container.lane_stats(lane)
+    }
+
+    #[allow(clippy::redundant_closure)]
+    fn stream(&mut self, stream: impl Stream<Item = Ms> + 'static) -> &mut Self {
+        let f = self.f.clone();
+        self.orders_container.stream(stream.map(move |ms| f(ms)));
+        self
+    }
+
+    #[allow(clippy::redundant_closure)]
+    fn stream_with_handle(&mut self, stream: impl Stream<Item = Ms> + 'static) -> StreamHandle {
+        let f = self.f.clone();
+        self.orders_container
+            .stream_with_handle(stream.map(move |ms| f(ms)))
+    }
+
+    fn request_animation_frame_stream(
+        &mut self,
+        handler: impl FnOnce(RenderInfo) -> Ms + Clone + 'static,
+    ) -> StreamHandle {
+        let f = self.f.clone();
+        self.orders_container
+            .request_animation_frame_stream(move |info| f(handler.clone()(info)))
+    }
+
+    #[allow(clippy::redundant_closure)]
     fn send_g_msg(&mut self, g_msg: GMs) -> &mut Self {
-        let effect = Effect::GMsg(g_msg);
-        self.orders_container.effects.push_back(effect);
+        let g = self.g.clone();
+        self.orders_container
+            .effects
+            .push_back(Effect::GMsg(g_msg).map_g_msg(move |g_ms| g(g_ms)));
         self
     }
 
@@ -97,12 +270,22 @@ impl<'a, Ms: 'static, AppMs: 'static, Mdl, ElC: View<AppMs> + 'static, GMs> Orde
     where
         C: Future<Output = Result<GMs, GMs>> + 'static,
     {
-        let effect = Effect::GCmd(LocalFutureObj::new(Box::new(g_cmd)));
-        self.orders_container.effects.push_back(effect);
+        let g = self.g.clone();
+        self.orders_container
+            .perform_g_cmd(g_cmd.map(move |result| map_g_cmd_output(&*g, result)));
         self
     }
 
-    fn clone_app(&self) -> App<Self::AppMs, Self::Mdl, Self::ElC, GMs> {
+    fn perform_g_cmd_with_handle<C>(&mut self, g_cmd: C) -> CmdHandle
+    where
+        C: Future<Output = Result<GMs, GMs>> + 'static,
+    {
+        let g = self.g.clone();
+        self.orders_container
+            .perform_g_cmd_with_handle(g_cmd.map(move |result| map_g_cmd_output(&*g, result)))
+    }
+
+    fn clone_app(&self) -> App<Self::AppMs, Self::Mdl, Self::ElC, AppGMs> {
         self.orders_container.clone_app()
     }
 
@@ -112,13 +295,374 @@ impl<'a, Ms: 'static, AppMs: 'static, Mdl, ElC: View<AppMs> + 'static, GMs> Orde
         Box::new(move |ms| f(ms))
     }
 
-    fn after_next_render(
+    #[allow(clippy::redundant_closure)]
+    fn after_next_render<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
         &mut self,
-        callback: impl FnOnce(Option<RenderTimestampDelta>) -> Ms + 'static,
+        callback: impl FnOnce(RenderInfo) -> MsU + 'static,
     ) -> &mut Self {
+        let f = self.f.clone();
+        let callback = map_callback_return::<Ms, _, _, _>(callback).map_msg(move |msg| f(msg));
+        self.orders_container.after_next_render(callback);
+        self
+    }
+
+    fn capabilities(&self) -> &Capabilities {
+        self.orders_container.capabilities()
+    }
+
+    fn subscribe_input_modality(
+        &mut self,
+        handler: impl Fn(Modality) -> Ms + 'static,
+    ) -> SubHandle {
         let f = self.f.clone();
         self.orders_container
-            .after_next_render(move |timestamp_delta| f(callback(timestamp_delta)));
+            .subscribe_input_modality(move |modality| f(handler(modality)))
+    }
+
+    fn input_modality(&self) -> Option<Modality> {
+        self.orders_container.input_modality()
+    }
+
+    fn subscribe_scroll_progress(
+        &mut self,
+        element: &web_sys::Element,
+        opts: ScrollProgressOpts,
+        handler: impl Fn(f64) -> Ms + 'static,
+    ) -> Result<ScrollProgressHandle, Unsupported> {
+        let f = self.f.clone();
+        self.orders_container
+            .subscribe_scroll_progress(element, opts, move |progress| f(handler(progress)))
+    }
+
+    fn subscribe_scroll_progress_el_ref<E: JsCast + 'static>(
+        &mut self,
+        el_ref: &ElRef<E>,
+        opts: ScrollProgressOpts,
+        handler: impl Fn(f64) -> Ms + 'static,
+    ) -> Result<ScrollProgressHandle, Unsupported> {
+        let f = self.f.clone();
+        self.orders_container
+            .subscribe_scroll_progress_el_ref(el_ref, opts, move |progress| f(handler(progress)))
+    }
+
+    fn subscribe_viewport(
+        &mut self,
+        opts: ViewportOpts,
+        handler: impl Fn(Viewport) -> Ms + 'static,
+    ) -> ViewportHandle {
+        let f = self.f.clone();
+        self.orders_container
+            .subscribe_viewport(opts, move |viewport| f(handler(viewport)))
+    }
+
+    fn subscribe_window_event<T: EventTrigger, MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        trigger: T,
+        handler: impl FnOnce(T::Event) -> MsU + Clone + 'static,
+    ) -> EventHandle {
+        let f = self.f.clone();
+        self.orders_container
+            .subscribe_window_event(trigger, move |event| {
+                map_callback_return::<Ms, _, _, _>(handler)(event).map(|msg| f(msg))
+            })
+    }
+
+    fn subscribe_document_event<T: EventTrigger, MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        trigger: T,
+        handler: impl FnOnce(T::Event) -> MsU + Clone + 'static,
+    ) -> EventHandle {
+        let f = self.f.clone();
+        self.orders_container
+            .subscribe_document_event(trigger, move |event| {
+                map_callback_return::<Ms, _, _, _>(handler)(event).map(|msg| f(msg))
+            })
+    }
+
+    fn drop_kept_alive(&mut self, key: &str) -> &mut Self {
+        self.orders_container.drop_kept_alive(key);
+        self
+    }
+
+    fn retry_when_online<C>(&mut self, cmd_factory: impl Fn() -> C + 'static) -> &mut Self
+    where
+        C: Future<Output = Result<Ms, CmdFailure<Ms>>> + 'static,
+    {
+        let f = self.f.clone();
+        self.orders_container.retry_when_online(move || {
+            let f = f.clone();
+            cmd_factory().map(move |result| {
+                result
+                    .map(|ms| f(ms))
+                    .map_err(|failure| failure.map(|ms| f(ms)))
+            })
+        });
         self
     }
+
+    fn retry_when_online_with_handle<C>(
+        &mut self,
+        cmd_factory: impl Fn() -> C + 'static,
+    ) -> CmdHandle
+    where
+        C: Future<Output = Result<Ms, CmdFailure<Ms>>> + 'static,
+    {
+        let f = self.f.clone();
+        self.orders_container
+            .retry_when_online_with_handle(move || {
+                let f = f.clone();
+                cmd_factory().map(move |result| {
+                    result
+                        .map(|ms| f(ms))
+                        .map_err(|failure| failure.map(|ms| f(ms)))
+                })
+            })
+    }
+
+    fn pending_offline_retries(&self) -> usize {
+        self.orders_container.pending_offline_retries()
+    }
+
+    fn cancel_offline_retries(&mut self) -> &mut Self {
+        self.orders_container.cancel_offline_retries();
+        self
+    }
+
+    fn defer_navigation<C>(&mut self, cmd: C, timeout: Duration, on_timeout: Ms) -> &mut Self
+    where
+        C: Future<Output = Result<Ms, Ms>> + 'static,
+    {
+        let f = self.f.clone();
+        let on_timeout = f(on_timeout);
+        self.orders_container.defer_navigation(
+            cmd.map(move |result| result.map(|ms| f(ms)).map_err(|ms| f(ms))),
+            timeout,
+            on_timeout,
+        );
+        self
+    }
+
+    fn cancel_deferred_navigation(&mut self) -> &mut Self {
+        self.orders_container.cancel_deferred_navigation();
+        self
+    }
+
+    fn subscribe_navigation_pending(
+        &mut self,
+        handler: impl Fn(bool) -> Ms + 'static,
+    ) -> &mut Self {
+        let f = self.f.clone();
+        self.orders_container
+            .subscribe_navigation_pending(move |pending| f(handler(pending)));
+        self
+    }
+
+    fn push_route<U: Into<Url>>(&mut self, url: U) -> &mut Self {
+        self.orders_container.push_route(url);
+        self
+    }
+
+    fn replace_route<U: Into<Url>>(&mut self, url: U) -> &mut Self {
+        self.orders_container.replace_route(url);
+        self
+    }
+
+    fn current_url(&self) -> Url {
+        self.orders_container.current_url()
+    }
+
+    fn context<C: 'static>(&self) -> Option<Rc<C>> {
+        self.orders_container.context()
+    }
+
+    fn force_hydrate(&mut self, key: &str) -> &mut Self {
+        self.orders_container.force_hydrate(key);
+        self
+    }
+
+    fn subscribe_lazy_hydration(&mut self, handler: impl Fn(String) -> Ms + 'static) -> &mut Self {
+        let f = self.f.clone();
+        self.orders_container
+            .subscribe_lazy_hydration(move |key| f(handler(key)));
+        self
+    }
+
+    fn share(
+        &mut self,
+        data: ShareData,
+        handler: impl FnOnce(Result<(), ShareError>) -> Ms + 'static,
+    ) -> &mut Self {
+        let f = self.f.clone();
+        self.orders_container
+            .share(data, move |result| f(handler(result)));
+        self
+    }
+
+    fn frame_stats(&self) -> FrameStatsSnapshot {
+        self.orders_container.frame_stats()
+    }
+
+    #[allow(clippy::redundant_closure)]
+    fn subscribe_long_frames(&mut self, handler: impl Fn(f64) -> Ms + 'static) -> SubHandle {
+        let f = self.f.clone();
+        self.orders_container
+            .subscribe_long_frames(move |frame_ms| f(handler(frame_ms)))
+    }
+
+    fn subscribe_perf_violations(
+        &mut self,
+        handler: impl Fn(PerfViolation) -> Ms + 'static,
+    ) -> &mut Self {
+        let f = self.f.clone();
+        self.orders_container
+            .subscribe_perf_violations(move |violation| f(handler(violation)));
+        self
+    }
+
+    fn set_page_meta(&mut self, meta: PageMeta) -> &mut Self {
+        self.orders_container.set_page_meta(meta);
+        self
+    }
+
+    fn clear_page_meta(&mut self) -> &mut Self {
+        self.orders_container.clear_page_meta();
+        self
+    }
+
+    fn preload(&mut self, hint: PreloadHint) -> PreloadHandle {
+        self.orders_container.preload(hint)
+    }
+
+    fn preload_with_status<MsU: MsgReturn<Ms, Kind> + 'static, Kind>(
+        &mut self,
+        hint: PreloadHint,
+        handler: impl FnOnce(PreloadStatus) -> MsU + 'static,
+    ) -> PreloadHandle {
+        let f = self.f.clone();
+        let handler = map_callback_return::<Ms, _, _, _>(handler);
+        self.orders_container
+            .preload_with_status(hint, move |status| handler(status).map(|msg| f(msg)))
+    }
+
+    fn prefetch_route(&mut self, prefetch: RoutePrefetch<Ms>) -> Vec<PreloadHandle> {
+        for cmd in prefetch.data {
+            self.perform_cmd(cmd);
+        }
+        prefetch
+            .hints
+            .into_iter()
+            .map(|hint| self.orders_container.preload(hint))
+            .collect()
+    }
+
+    fn request_persistent_storage(
+        &mut self,
+        handler: impl FnOnce(Result<bool, Unsupported>) -> Ms + 'static,
+    ) -> &mut Self {
+        let f = self.f.clone();
+        self.orders_container
+            .request_persistent_storage(move |result| f(handler(result)));
+        self
+    }
+
+    fn storage_persisted(
+        &mut self,
+        handler: impl FnOnce(Result<bool, Unsupported>) -> Ms + 'static,
+    ) -> &mut Self {
+        let f = self.f.clone();
+        self.orders_container
+            .storage_persisted(move |result| f(handler(result)));
+        self
+    }
+
+    fn storage_estimate(
+        &mut self,
+        handler: impl FnOnce(Result<StorageEstimate, Unsupported>) -> Ms + 'static,
+    ) -> &mut Self {
+        let f = self.f.clone();
+        self.orders_container
+            .storage_estimate(move |result| f(handler(result)));
+        self
+    }
+
+    fn subscribe_storage_quota(
+        &mut self,
+        opts: StorageQuotaOpts,
+        handler: impl Fn(StorageEstimate) -> Ms + 'static,
+    ) -> StorageQuotaHandle {
+        let f = self.f.clone();
+        self.orders_container
+            .subscribe_storage_quota(opts, move |estimate| f(handler(estimate)))
+    }
+
+    fn subscribe<SubMs: Any + Clone>(
+        &mut self,
+        handler: impl Fn(SubMs) -> Ms + 'static,
+    ) -> SubHandle {
+        let f = self.f.clone();
+        self.orders_container
+            .subscribe(move |message: SubMs| f(handler(message)))
+    }
+
+    fn notify(&mut self, message: impl Any + Clone) -> &mut Self {
+        self.orders_container.notify(message);
+        self
+    }
+
+    fn perform_cmd_notify<SubMs: Any + Clone>(
+        &mut self,
+        cmd: impl Future<Output = SubMs> + 'static,
+    ) -> &mut Self {
+        // Subscribers map the output by themselves.
+        self.orders_container.perform_cmd_notify(cmd);
+        self
+    }
+
+    fn set_locale(&mut self, tag: &str) -> &mut Self {
+        self.orders_container.set_locale(tag);
+        self
+    }
+
+    fn toast(&mut self, toast: Toast<Ms>) -> &mut Self {
+        let f = self.f.clone();
+        self.orders_container
+            .toast(toast.map_msg(move |msg| f(msg)));
+        self
+    }
+
+    fn sync_slice<T>(&mut self, key: &str, handler: impl Fn(T) -> Ms + 'static) -> SyncedSlice<T>
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Clone + 'static,
+    {
+        let f = self.f.clone();
+        self.orders_container
+            .sync_slice(key, move |value| f(handler(value)))
+    }
+
+    fn update_synced<T>(&mut self, slice: &SyncedSlice<T>, value: T) -> Result<(), SyncError>
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Clone + 'static,
+    {
+        self.orders_container.update_synced(slice, value)
+    }
+
+    fn open_websocket(
+        &mut self,
+        url: &str,
+        handlers: WebSocketHandlers<Ms>,
+    ) -> Result<WebSocket, WebSocketError> {
+        let f = self.f.clone();
+        self.orders_container
+            .open_websocket(url, handlers.map_msg(move |msg| f(msg)))
+    }
+}
+
+fn map_g_cmd_output<GMs, AppGMs>(
+    g: &dyn Fn(GMs) -> AppGMs,
+    result: Result<GMs, GMs>,
+) -> Result<AppGMs, AppGMs> {
+    match result {
+        Ok(g_msg) => Ok(g(g_msg)),
+        Err(g_msg) => Err(g(g_msg)),
+    }
 }
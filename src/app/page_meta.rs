@@ -0,0 +1,407 @@
+//! Document metadata - `<title>` and tags in `<head>`. See `Orders::set_page_meta`.
+//!
+//! Applied metadata is the base meta (`AppBuilder::base_meta`) with fields overridden by the meta
+//! of the current route; `None` fields (and missing `og` / `extra_meta` entries) are inherited.
+//!
+//! Changes are written in the write phase after the next render (see `scheduler::dom_write`),
+//! all at once, so scripts reading `<head>` never see a mix of two routes. Only tags with changed
+//! values are touched - some analytics tools observe mutations of `<head>`. Tags written by Seed
+//! are marked by the attribute `data-seed-meta` and they are removed once they aren't in
+//! the applied metadata; other tags are left alone.
+
+use super::App;
+use crate::browser::dom::scheduler;
+use crate::virtual_dom::View;
+use indexmap::IndexMap;
+use wasm_bindgen::JsCast;
+use web_sys::{Document, Element};
+
+/// Attribute of tags written by Seed.
+pub const PAGE_META_ATTR: &str = "data-seed-meta";
+
+/// Metadata bundle of a route. Set it by `Orders::set_page_meta`.
+///
+/// # Example
+///
+/// ```rust,no_run
+///orders.set_page_meta(PageMeta {
+///    title: Some(format!("{} - Blog", post.title)),
+///    description: Some(post.summary.clone()),
+///    ..PageMeta::default()
+///});
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PageMeta {
+    pub title: Option<String>,
+    /// `<meta name="description">`
+    pub description: Option<String>,
+    /// `<link rel="canonical">`
+    pub canonical: Option<String>,
+    /// `<meta name="robots">`
+    pub robots: Option<String>,
+    /// Open Graph properties without the `og:` prefix - e.g. `image` for `<meta property="og:image">`.
+    pub og: IndexMap<String, String>,
+    /// Other `<meta name="..">` tags by their names.
+    pub extra_meta: IndexMap<String, String>,
+}
+
+impl PageMeta {
+    /// Return `self` with fields and map entries overridden by `overrides`.
+    pub fn merge(&self, overrides: &Self) -> Self {
+        let merge_maps = |base: &IndexMap<String, String>, overrides: &IndexMap<String, String>| {
+            let mut merged = base.clone();
+            merged.extend(overrides.clone());
+            merged
+        };
+        Self {
+            title: overrides.title.clone().or_else(|| self.title.clone()),
+            description: overrides
+                .description
+                .clone()
+                .or_else(|| self.description.clone()),
+            canonical: overrides
+                .canonical
+                .clone()
+                .or_else(|| self.canonical.clone()),
+            robots: overrides.robots.clone().or_else(|| self.robots.clone()),
+            og: merge_maps(&self.og, &overrides.og),
+            extra_meta: merge_maps(&self.extra_meta, &overrides.extra_meta),
+        }
+    }
+}
+
+// ------ Scheduling ------
+
+#[derive(Default)]
+pub(crate) struct PageMetaState {
+    route: Option<PageMeta>,
+    write_scheduled: bool,
+}
+
+/// Replace the route meta (`None` reverts to the base meta) and schedule writing into `<head>`.
+pub(crate) fn set<Ms: 'static, Mdl: 'static, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    route: Option<PageMeta>,
+) {
+    let mut state = app.data.page_meta.borrow_mut();
+    state.route = route;
+    schedule_write(app, &mut state);
+}
+
+/// Schedule writing of the base meta. Called once when the app starts.
+pub(crate) fn init<Ms: 'static, Mdl: 'static, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+) {
+    if app.cfg.base_meta != PageMeta::default() {
+        schedule_write(app, &mut app.data.page_meta.borrow_mut());
+    }
+}
+
+fn schedule_write<Ms: 'static, Mdl: 'static, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    state: &mut PageMetaState,
+) {
+    // The scheduled write uses the latest route meta.
+    if state.write_scheduled {
+        return;
+    }
+    state.write_scheduled = true;
+    // The app's queue - the write is dropped when the app is unmounted.
+    let queue = &app.data.dom_ops;
+    let app = app.clone();
+    scheduler::with_queue(queue, || {
+        scheduler::dom_write(move |document| {
+            let meta = {
+                let mut state = app.data.page_meta.borrow_mut();
+                state.write_scheduled = false;
+                match &state.route {
+                    Some(route) => app.cfg.base_meta.merge(route),
+                    None => app.cfg.base_meta.clone(),
+                }
+            };
+            apply(document, &meta);
+        })
+    });
+}
+
+// ------ Writing ------
+
+/// Tag in `<head>` identified by the value of `key_attr` (e.g. `<meta name="robots">`).
+struct HeadTag {
+    name: &'static str,
+    key_attr: &'static str,
+    key: String,
+    value_attr: &'static str,
+    value: String,
+}
+
+impl HeadTag {
+    fn meta(key_attr: &'static str, key: impl Into<String>, content: &str) -> Self {
+        Self {
+            name: "meta",
+            key_attr,
+            key: key.into(),
+            value_attr: "content",
+            value: content.to_owned(),
+        }
+    }
+
+    fn selector(&self) -> String {
+        format!(
+            "{}[{}=\"{}\"]",
+            self.name,
+            self.key_attr,
+            self.key.replace('"', "\\\"")
+        )
+    }
+}
+
+fn head_tags(meta: &PageMeta) -> Vec<HeadTag> {
+    let mut tags = Vec::new();
+    if let Some(description) = &meta.description {
+        tags.push(HeadTag::meta("name", "description", description));
+    }
+    if let Some(robots) = &meta.robots {
+        tags.push(HeadTag::meta("name", "robots", robots));
+    }
+    if let Some(canonical) = &meta.canonical {
+        tags.push(HeadTag {
+            name: "link",
+            key_attr: "rel",
+            key: "canonical".to_owned(),
+            value_attr: "href",
+            value: canonical.clone(),
+        });
+    }
+    for (property, content) in &meta.og {
+        tags.push(HeadTag::meta(
+            "property",
+            format!("og:{}", property),
+            content,
+        ));
+    }
+    for (name, content) in &meta.extra_meta {
+        tags.push(HeadTag::meta("name", name.as_str(), content));
+    }
+    tags
+}
+
+/// Write `meta` into the document. Unchanged tags aren't touched.
+pub(crate) fn apply(document: &Document, meta: &PageMeta) {
+    if let Some(title) = &meta.title {
+        if &document.title() != title {
+            document.set_title(title);
+        }
+    }
+
+    let head = query_all(document, "head")
+        .pop()
+        .expect("Problem getting `<head>`");
+    let tags = head_tags(meta);
+    for tag in &tags {
+        write_tag(document, &head, tag);
+    }
+
+    for element in query_all(document, &format!("head > [{}]", PAGE_META_ATTR)) {
+        let is_applied = tags
+            .iter()
+            .any(|tag| element.matches(&tag.selector()).unwrap_or(false));
+        if !is_applied {
+            element.remove();
+        }
+    }
+}
+
+fn write_tag(document: &Document, head: &Element, tag: &HeadTag) {
+    let existing = document
+        .query_selector(&format!("head > {}", tag.selector()))
+        .ok()
+        .and_then(|element| element);
+
+    let element = match existing {
+        Some(element) => {
+            if element.get_attribute(tag.value_attr).as_ref() != Some(&tag.value) {
+                set_attribute(&element, tag.value_attr, &tag.value);
+            }
+            element
+        }
+        None => {
+            let element = document
+                .create_element(tag.name)
+                .expect("Problem creating head tag");
+            set_attribute(&element, tag.key_attr, &tag.key);
+            set_attribute(&element, tag.value_attr, &tag.value);
+            head.append_child(&element)
+                .expect("Problem appending head tag");
+            element
+        }
+    };
+    // A tag rendered by the server becomes Seed's once it's written.
+    if !element.has_attribute(PAGE_META_ATTR) {
+        set_attribute(&element, PAGE_META_ATTR, "");
+    }
+}
+
+fn set_attribute(element: &Element, name: &str, value: &str) {
+    element
+        .set_attribute(name, value)
+        .expect("Problem setting head tag attribute");
+}
+
+fn query_all(document: &Document, selector: &str) -> Vec<Element> {
+    let nodes = document
+        .query_selector_all(selector)
+        .expect("Problem querying head tags");
+    (0..nodes.length())
+        .filter_map(|index| nodes.item(index))
+        .filter_map(|node| node.dyn_into::<Element>().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser::util;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn map(entries: &[(&str, &str)]) -> IndexMap<String, String> {
+        entries
+            .iter()
+            .map(|(key, value)| ((*key).to_owned(), (*value).to_owned()))
+            .collect()
+    }
+
+    fn head_contents(document: &Document) -> Vec<String> {
+        query_all(document, &format!("head > [{}]", PAGE_META_ATTR))
+            .iter()
+            .map(Element::outer_html)
+            .collect()
+    }
+
+    fn observe_head(document: &Document) -> web_sys::MutationObserver {
+        let observer = web_sys::MutationObserver::new(&js_sys::Function::new_no_args(""))
+            .expect("Problem creating MutationObserver");
+        let mut options = web_sys::MutationObserverInit::new();
+        options.child_list(true).attributes(true).subtree(true);
+        observer
+            .observe_with_options(&query_all(document, "head")[0], &options)
+            .expect("Problem observing head");
+        observer
+    }
+
+    #[wasm_bindgen_test]
+    fn routes_override_base_meta() {
+        let document = util::document();
+        let original_title = document.title();
+        let base = PageMeta {
+            title: Some("Seed".to_owned()),
+            description: Some("A Rust framework".to_owned()),
+            og: map(&[("site_name", "Seed"), ("type", "website")]),
+            ..PageMeta::default()
+        };
+        let home = PageMeta::default();
+        let blog = PageMeta {
+            title: Some("Blog".to_owned()),
+            canonical: Some("https://seed-rs.org/blog".to_owned()),
+            og: map(&[("type", "blog")]),
+            ..PageMeta::default()
+        };
+        let post = PageMeta {
+            title: Some("Hello".to_owned()),
+            description: Some("First post".to_owned()),
+            robots: Some("noindex".to_owned()),
+            extra_meta: map(&[("author", "Martin")]),
+            ..PageMeta::default()
+        };
+
+        // Home
+        apply(&document, &base.merge(&home));
+        assert_eq!(document.title(), "Seed");
+        assert_eq!(
+            head_contents(&document),
+            vec![
+                r#"<meta name="description" content="A Rust framework" data-seed-meta="">"#,
+                r#"<meta property="og:site_name" content="Seed" data-seed-meta="">"#,
+                r#"<meta property="og:type" content="website" data-seed-meta="">"#,
+            ]
+        );
+
+        // Blog
+        let observer = observe_head(&document);
+        apply(&document, &base.merge(&blog));
+        assert_eq!(document.title(), "Blog");
+        assert_eq!(
+            head_contents(&document),
+            vec![
+                r#"<meta name="description" content="A Rust framework" data-seed-meta="">"#,
+                r#"<meta property="og:site_name" content="Seed" data-seed-meta="">"#,
+                r#"<meta property="og:type" content="blog" data-seed-meta="">"#,
+                r#"<link rel="canonical" href="https://seed-rs.org/blog" data-seed-meta="">"#,
+            ]
+        );
+        // `<title>`'s text, `og:type`'s content and the new `canonical`.
+        assert_eq!(observer.take_records().length(), 3);
+
+        // Blog again - nothing is rewritten.
+        apply(&document, &base.merge(&blog));
+        assert_eq!(observer.take_records().length(), 0);
+        observer.disconnect();
+
+        // Post
+        apply(&document, &base.merge(&post));
+        assert_eq!(document.title(), "Hello");
+        assert_eq!(
+            head_contents(&document),
+            vec![
+                r#"<meta name="description" content="First post" data-seed-meta="">"#,
+                r#"<meta property="og:site_name" content="Seed" data-seed-meta="">"#,
+                r#"<meta property="og:type" content="website" data-seed-meta="">"#,
+                r#"<meta name="robots" content="noindex" data-seed-meta="">"#,
+                r#"<meta name="author" content="Martin" data-seed-meta="">"#,
+            ]
+        );
+
+        // Route meta cleared - the base meta remains.
+        apply(&document, &base);
+        assert_eq!(document.title(), "Seed");
+        assert_eq!(
+            head_contents(&document),
+            vec![
+                r#"<meta name="description" content="A Rust framework" data-seed-meta="">"#,
+                r#"<meta property="og:site_name" content="Seed" data-seed-meta="">"#,
+                r#"<meta property="og:type" content="website" data-seed-meta="">"#,
+            ]
+        );
+
+        apply(&document, &PageMeta::default());
+        assert!(head_contents(&document).is_empty());
+        document.set_title(&original_title);
+    }
+
+    #[wasm_bindgen_test]
+    fn server_rendered_tags_are_updated_in_place() {
+        let document = util::document();
+        let head = query_all(&document, "head").pop().unwrap();
+        let tag = document.create_element("meta").unwrap();
+        tag.set_attribute("name", "keywords").unwrap();
+        tag.set_attribute("content", "rust").unwrap();
+        head.append_child(&tag).unwrap();
+
+        apply(
+            &document,
+            &PageMeta {
+                extra_meta: map(&[("keywords", "rust, wasm")]),
+                ..PageMeta::default()
+            },
+        );
+        assert_eq!(
+            head_contents(&document),
+            vec![r#"<meta name="keywords" content="rust, wasm" data-seed-meta="">"#]
+        );
+
+        apply(&document, &PageMeta::default());
+        assert!(tag.parent_node().is_none());
+    }
+}
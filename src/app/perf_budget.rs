@@ -0,0 +1,351 @@
+//! Performance budgets of `update` calls and renders. See `AppBuilder::perf_budget`.
+//!
+//! Enabled by the feature `perf-budget`. Otherwise nothing is measured, budgets are ignored
+//! and violation handlers are never called.
+//!
+//! Each `update` call and each render is measured by `performance.mark` / `performance.measure`,
+//! so measurements also appear in the browser profiler's timeline as `seed:update <message>`
+//! and `seed:render`. Messages are named by `AppBuilder::perf_msg_name` (or by their type).
+//!
+//! Messages of violation handlers are sent to `update`, but they don't trigger renders
+//! and they aren't measured - a render over the budget would trigger itself again otherwise.
+
+#[cfg(feature = "perf-budget")]
+use super::types::PerfMsgNameFn;
+#[cfg(feature = "perf-budget")]
+use crate::browser::util;
+#[cfg(feature = "perf-budget")]
+use std::{
+    any,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+};
+
+/// Max durations of `update` calls and renders. Set it by `AppBuilder::perf_budget`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PerfBudget {
+    /// Max duration of one `update` call in milliseconds.
+    pub update_ms: f64,
+    /// Max duration of one render (view + patch) in milliseconds.
+    pub render_ms: f64,
+    pub warn: WarnMode,
+}
+
+impl Default for PerfBudget {
+    fn default() -> Self {
+        Self {
+            update_ms: 4.0,
+            render_ms: 8.0,
+            warn: WarnMode::ConsoleGrouped,
+        }
+    }
+}
+
+/// How violations of `PerfBudget` are reported in the console.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WarnMode {
+    /// Collapsed console group with details.
+    ConsoleGrouped,
+    /// One-line console warning.
+    Console,
+    /// Only violation handlers are called - see `Orders::subscribe_perf_violations`.
+    Silent,
+}
+
+/// What has been measured.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PerfPhase {
+    Update,
+    Render,
+}
+
+/// Exceeded `PerfBudget`. See `Orders::subscribe_perf_violations`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerfViolation {
+    pub phase: PerfPhase,
+    /// Message name - see `AppBuilder::perf_msg_name`. `None` for renders.
+    pub msg: Option<String>,
+    pub duration_ms: f64,
+    pub budget_ms: f64,
+    /// Inserted, removed and replaced DOM nodes and changed texts. `None` for `update` calls.
+    pub dom_mutations: Option<u32>,
+    /// Number of violations by the same message name (or by renders) so far,
+    /// including this one.
+    pub count: u32,
+}
+
+#[cfg(feature = "perf-budget")]
+impl PerfViolation {
+    fn title(&self) -> String {
+        let subject = match &self.msg {
+            Some(msg) => format!("update `{}`", msg),
+            None => "render".to_owned(),
+        };
+        let mutations = match self.dom_mutations {
+            Some(mutations) => format!(", {} DOM mutations", mutations),
+            None => String::new(),
+        };
+        format!(
+            "Performance budget exceeded: {} took {:.1} ms (budget {} ms{})",
+            subject, self.duration_ms, self.budget_ms, mutations
+        )
+    }
+}
+
+// ------ DOM mutations ------
+
+#[cfg(feature = "perf-budget")]
+thread_local! {
+    /// DOM mutations so far - renders read the difference, see `Measurement::render`.
+    static DOM_MUTATIONS: Cell<u32> = Cell::new(0);
+}
+
+/// Count a DOM mutation for render measurements. It's a no-op without the feature `perf-budget`.
+#[inline]
+pub(crate) fn count_dom_mutation() {
+    #[cfg(feature = "perf-budget")]
+    DOM_MUTATIONS.with(|mutations| mutations.set(mutations.get().wrapping_add(1)));
+}
+
+#[cfg(feature = "perf-budget")]
+fn dom_mutations() -> u32 {
+    DOM_MUTATIONS.with(Cell::get)
+}
+
+// ------ Measurement ------
+
+#[cfg(feature = "perf-budget")]
+pub(crate) struct PerfViolations<Ms> {
+    counts: HashMap<(PerfPhase, Option<String>), u32>,
+    pub(crate) handlers: Vec<Box<dyn Fn(PerfViolation) -> Ms>>,
+    /// Handlers' messages are being sent to `update` - don't measure them.
+    pub(crate) reporting: bool,
+}
+
+#[cfg(feature = "perf-budget")]
+impl<Ms> Default for PerfViolations<Ms> {
+    fn default() -> Self {
+        Self {
+            counts: HashMap::new(),
+            handlers: Vec::new(),
+            reporting: false,
+        }
+    }
+}
+
+/// Running measurement of one `update` call or render.
+#[cfg(feature = "perf-budget")]
+pub(crate) struct Measurement {
+    phase: PerfPhase,
+    msg: Option<String>,
+    name: String,
+    start_ms: f64,
+    /// `DOM_MUTATIONS` at the start of a render.
+    start_mutations: Option<u32>,
+}
+
+#[cfg(feature = "perf-budget")]
+impl Measurement {
+    pub(crate) fn update<Ms>(msg: &Ms, msg_name: Option<PerfMsgNameFn<Ms>>) -> Self {
+        let msg = match msg_name {
+            Some(msg_name) => msg_name(msg),
+            None => any::type_name::<Ms>().to_owned(),
+        };
+        Self::start(PerfPhase::Update, format!("seed:update {}", msg), Some(msg))
+    }
+
+    pub(crate) fn render() -> Self {
+        let mut measurement = Self::start(PerfPhase::Render, "seed:render".to_owned(), None);
+        measurement.start_mutations = Some(dom_mutations());
+        measurement
+    }
+
+    fn start(phase: PerfPhase, name: String, msg: Option<String>) -> Self {
+        let performance = performance();
+        performance
+            .mark(&start_mark(&name))
+            .expect("Problem creating performance mark");
+        Self {
+            phase,
+            msg,
+            start_ms: performance.now(),
+            name,
+            start_mutations: None,
+        }
+    }
+
+    /// Stop the measurement and report it if it exceeds `budget`.
+    ///
+    /// Returns messages from violation handlers - they have to be sent by the caller
+    /// (see `App::send_perf_violation_msgs`).
+    pub(crate) fn finish<Ms>(
+        self,
+        budget: &PerfBudget,
+        violations: &RefCell<PerfViolations<Ms>>,
+    ) -> Vec<Ms> {
+        let performance = performance();
+        let duration_ms = performance.now() - self.start_ms;
+        let start_mark = start_mark(&self.name);
+        performance
+            .measure_with_start_mark(&self.name, &start_mark)
+            .expect("Problem creating performance measure");
+        // The profiler has recorded the entries already - don't let the buffer grow.
+        performance.clear_marks_with_mark_name(&start_mark);
+        performance.clear_measures_with_measure_name(&self.name);
+
+        let budget_ms = match self.phase {
+            PerfPhase::Update => budget.update_ms,
+            PerfPhase::Render => budget.render_ms,
+        };
+        if duration_ms <= budget_ms {
+            return Vec::new();
+        }
+
+        let mut violations = violations.borrow_mut();
+        let count = violations
+            .counts
+            .entry((self.phase, self.msg.clone()))
+            .or_insert(0);
+        *count += 1;
+        let violation = PerfViolation {
+            phase: self.phase,
+            msg: self.msg,
+            duration_ms,
+            budget_ms,
+            dom_mutations: self
+                .start_mutations
+                .map(|start| dom_mutations().wrapping_sub(start)),
+            count: *count,
+        };
+        warn(&violation, budget.warn, &self.name);
+        violations
+            .handlers
+            .iter()
+            .map(|handler| handler(violation.clone()))
+            .collect()
+    }
+}
+
+#[cfg(feature = "perf-budget")]
+fn warn(violation: &PerfViolation, mode: WarnMode, measure_name: &str) {
+    use web_sys::console;
+    match mode {
+        WarnMode::ConsoleGrouped => {
+            console::group_collapsed_1(&violation.title().into());
+            console::warn_1(&format!("Violations so far: {}", violation.count).into());
+            console::warn_1(
+                &format!(
+                    "See `{}` in the profiler's timeline for details.",
+                    measure_name
+                )
+                .into(),
+            );
+            console::group_end();
+        }
+        WarnMode::Console => {
+            console::warn_1(&format!("{} ({}x so far)", violation.title(), violation.count).into())
+        }
+        WarnMode::Silent => (),
+    }
+}
+
+#[cfg(feature = "perf-budget")]
+fn start_mark(name: &str) -> String {
+    format!("{}:start", name)
+}
+
+#[cfg(feature = "perf-budget")]
+fn performance() -> web_sys::Performance {
+    util::window()
+        .performance()
+        .expect("Problem getting `Performance`")
+}
+
+#[cfg(all(test, feature = "perf-budget"))]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    enum Msg {
+        Slow,
+    }
+
+    fn busy_wait(ms: f64) {
+        let start = performance().now();
+        while performance().now() - start < ms {}
+    }
+
+    #[wasm_bindgen_test]
+    fn violations_are_counted_per_message_variant() {
+        let budget = PerfBudget {
+            update_ms: 1.0,
+            render_ms: 1_000.0,
+            warn: WarnMode::Silent,
+        };
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let violations = RefCell::new(PerfViolations::default());
+        violations.borrow_mut().handlers.push(Box::new({
+            let received = Rc::clone(&received);
+            move |violation| received.borrow_mut().push(violation)
+        }));
+
+        for _ in 0..2 {
+            let measurement = Measurement::update(&Msg::Slow, None);
+            busy_wait(3.0);
+            assert_eq!(measurement.finish(&budget, &violations).len(), 1);
+        }
+        // Renders are within the budget.
+        assert!(Measurement::render()
+            .finish(&budget, &violations)
+            .is_empty());
+
+        let received = received.borrow();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[1].phase, PerfPhase::Update);
+        assert_eq!(received[1].count, 2);
+        assert!(received[1].duration_ms >= 3.0);
+        assert!(received[1].msg.as_ref().unwrap().contains("Msg"));
+    }
+
+    #[wasm_bindgen_test]
+    fn violations_contain_msg_names_and_dom_mutations() {
+        let budget = PerfBudget {
+            update_ms: -1.0,
+            render_ms: -1.0,
+            warn: WarnMode::Silent,
+        };
+        let violations = RefCell::new(PerfViolations::default());
+        violations
+            .borrow_mut()
+            .handlers
+            .push(Box::new(|violation| violation));
+
+        let msg_name: PerfMsgNameFn<Msg> = |msg| match msg {
+            Msg::Slow => "Slow".to_owned(),
+        };
+        let update = Measurement::update(&Msg::Slow, Some(msg_name)).finish(&budget, &violations);
+        assert_eq!(update[0].msg.as_deref(), Some("Slow"));
+        assert_eq!(update[0].dom_mutations, None);
+
+        let measurement = Measurement::render();
+        for _ in 0..3 {
+            count_dom_mutation();
+        }
+        let render = measurement.finish(&budget, &violations);
+        assert_eq!(render[0].dom_mutations, Some(3));
+    }
+
+    #[wasm_bindgen_test]
+    fn measurements_are_cleared() {
+        let measurement = Measurement::render();
+        measurement.finish(
+            &PerfBudget::default(),
+            &RefCell::new(PerfViolations::<()>::default()),
+        );
+
+        assert_eq!(performance().get_entries_by_name("seed:render").length(), 0);
+    }
+}
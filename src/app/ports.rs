@@ -0,0 +1,480 @@
+//! Typed ports between Seed apps on one page - e.g. a shell and a widget built separately
+//! (micro-frontends). See `App::create_port` and `App::connect_port`.
+//!
+//! A port is a `MessageChannel` - one app keeps `port1`, the other one gets `port2` through
+//! the port's JS handle (`Port::to_js`) passed by the host page. Payloads are sent as JSON,
+//! so the apps may be separate wasm modules with their own type layouts.
+//! `PortPayload::SCHEMA` is checked when the port is connected - change it with each
+//! incompatible change of the payload type.
+//!
+//! Received payloads go through the app's `update`. Dropping the port (or unmounting the app)
+//! sends `PortEvent::Closed` to the other app.
+//!
+//! ```rust,no_run
+//!// Shell
+//!let cart = shell.create_port::<CartEvent>("cart", Msg::FromWidget);
+//!// Widget, with the handle passed by the host page.
+//!let cart = widget.connect_port::<CartEvent>("cart", &cart_js_handle, Msg::FromShell)?;
+//!cart.send(&CartEvent::ItemAdded { id: 7 })?;
+//! ```
+
+use super::App;
+use crate::virtual_dom::View;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    cell::{Cell, RefCell},
+    error::Error,
+    fmt,
+    marker::PhantomData,
+    rc::{Rc, Weak},
+};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{MessageChannel, MessagePort};
+
+/// Payload sent through `Port`.
+///
+/// # Example
+///
+/// ```rust,no_run
+///#[derive(Serialize, Deserialize)]
+///enum CartEvent {
+///    ItemAdded { id: u32 },
+///    Cleared,
+///}
+///
+///impl PortPayload for CartEvent {
+///    const SCHEMA: &'static str = "cart/1";
+///}
+/// ```
+pub trait PortPayload: Serialize + DeserializeOwned + 'static {
+    /// Version of the payload format. Ports connect only when both apps have the same schema.
+    const SCHEMA: &'static str;
+}
+
+/// Event passed to the port's handler.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PortEvent<P> {
+    /// The other app has connected the port (sent only to the app which has created the port).
+    Connected,
+    Message(P),
+    /// The other app has dropped the port or it has been unmounted.
+    Closed,
+}
+
+/// Error of `App::connect_port` and `Port::send`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortError {
+    /// The value isn't a handle created by `Port::to_js`.
+    InvalidHandle,
+    NameMismatch {
+        expected: String,
+        found: String,
+    },
+    SchemaMismatch {
+        expected: String,
+        found: String,
+    },
+    /// Each handle can be connected only once.
+    AlreadyConnected,
+    Closed,
+}
+
+impl fmt::Display for PortError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PortError::InvalidHandle => write!(f, "The value isn't a port handle"),
+            PortError::NameMismatch { expected, found } => write!(
+                f,
+                "Port \"{}\" was expected, but the handle belongs to port \"{}\"",
+                expected, found
+            ),
+            PortError::SchemaMismatch { expected, found } => write!(
+                f,
+                "Port schema \"{}\" was expected, but the other app uses \"{}\"",
+                expected, found
+            ),
+            PortError::AlreadyConnected => write!(f, "The port has been already connected"),
+            PortError::Closed => write!(f, "The port is closed"),
+        }
+    }
+}
+
+impl Error for PortError {}
+
+/// Messages on the wire.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Envelope {
+    Connect { schema: String },
+    Message { payload: serde_json::Value },
+    Close,
+}
+
+fn post(port: &MessagePort, envelope: &Envelope) {
+    let data = serde_json::to_string(envelope).expect("Problem serializing port message");
+    port.post_message(&JsValue::from_str(&data))
+        .expect("Problem posting port message");
+}
+
+/// Port created by `App::create_port` or `App::connect_port`. It's closed when dropped.
+#[must_use = "the port is closed when dropped"]
+pub struct Port<P> {
+    shared: Rc<Shared>,
+    js_handle: JsValue,
+    phantom: PhantomData<P>,
+}
+
+impl<P: PortPayload> Port<P> {
+    /// Send `payload` to the other app.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PortError::Closed` when the port has been closed by the other app.
+    pub fn send(&self, payload: &P) -> Result<(), PortError> {
+        if self.shared.closed.get() {
+            return Err(PortError::Closed);
+        }
+        let payload = serde_json::to_value(payload).expect("Problem serializing port payload");
+        post(&self.shared.port, &Envelope::Message { payload });
+        Ok(())
+    }
+
+    /// Handle for the other app's `App::connect_port` - pass it through the host page.
+    pub fn to_js(&self) -> JsValue {
+        self.js_handle.clone()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.shared.closed.get()
+    }
+
+    /// Close the port - the same as dropping it.
+    pub fn close(self) {}
+}
+
+impl<P> Drop for Port<P> {
+    fn drop(&mut self) {
+        close(&self.shared, true);
+    }
+}
+
+impl<P> fmt::Debug for Port<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Port")
+            .field("closed", &self.shared.closed.get())
+            .finish()
+    }
+}
+
+pub(crate) struct Shared {
+    port: MessagePort,
+    closed: Cell<bool>,
+    listener: RefCell<Option<Closure<dyn FnMut(web_sys::MessageEvent)>>>,
+}
+
+/// Ports of the app - they are closed when the app is unmounted.
+pub(crate) type Ports = Vec<Weak<Shared>>;
+
+/// Close `shared` and send `Close` to the other app when `notify` is `true`.
+fn close(shared: &Shared, notify: bool) {
+    if shared.closed.replace(true) {
+        return;
+    }
+    if notify {
+        post(&shared.port, &Envelope::Close);
+    }
+    shared.port.set_onmessage(None);
+    shared.port.close();
+    let listener = shared.listener.borrow_mut().take();
+    // We may be inside of the listener's callback - drop it later.
+    spawn_local(async move { drop(listener) });
+}
+
+pub(crate) fn create<
+    P: PortPayload,
+    Ms: 'static,
+    Mdl: 'static,
+    ElC: View<Ms> + 'static,
+    GMs: 'static,
+>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    name: &str,
+    handler: impl Fn(PortEvent<P>) -> Ms + 'static,
+) -> Port<P> {
+    let channel = MessageChannel::new().expect("Problem creating MessageChannel");
+
+    let js_handle = js_sys::Object::new();
+    let fields: [(&str, JsValue); 4] = [
+        ("name", name.into()),
+        ("schema", P::SCHEMA.into()),
+        ("port", channel.port2().into()),
+        ("connected", false.into()),
+    ];
+    for (key, value) in &fields {
+        js_sys::Reflect::set(&js_handle, &(*key).into(), value)
+            .expect("Problem setting port handle field");
+    }
+
+    Port {
+        shared: listen(app, channel.port1(), handler),
+        js_handle: js_handle.into(),
+        phantom: PhantomData,
+    }
+}
+
+pub(crate) fn connect<
+    P: PortPayload,
+    Ms: 'static,
+    Mdl: 'static,
+    ElC: View<Ms> + 'static,
+    GMs: 'static,
+>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    name: &str,
+    js_handle: &JsValue,
+    handler: impl Fn(PortEvent<P>) -> Ms + 'static,
+) -> Result<Port<P>, PortError> {
+    let field = |key: &str| js_sys::Reflect::get(js_handle, &key.into()).ok();
+    if !js_handle.is_object() {
+        return Err(PortError::InvalidHandle);
+    }
+    let port = field("port")
+        .and_then(|port| port.dyn_into::<MessagePort>().ok())
+        .ok_or(PortError::InvalidHandle)?;
+    let found_name = field("name")
+        .and_then(|name| name.as_string())
+        .ok_or(PortError::InvalidHandle)?;
+    if found_name != name {
+        return Err(PortError::NameMismatch {
+            expected: name.to_owned(),
+            found: found_name,
+        });
+    }
+    let found_schema = field("schema")
+        .and_then(|schema| schema.as_string())
+        .ok_or(PortError::InvalidHandle)?;
+    if found_schema != P::SCHEMA {
+        return Err(PortError::SchemaMismatch {
+            expected: P::SCHEMA.to_owned(),
+            found: found_schema,
+        });
+    }
+    if field("connected").and_then(|connected| connected.as_bool()) != Some(false) {
+        return Err(PortError::AlreadyConnected);
+    }
+    js_sys::Reflect::set(js_handle, &"connected".into(), &true.into())
+        .expect("Problem setting port handle field");
+
+    let shared = listen(app, port, handler);
+    post(
+        &shared.port,
+        &Envelope::Connect {
+            schema: P::SCHEMA.to_owned(),
+        },
+    );
+    Ok(Port {
+        shared,
+        js_handle: js_handle.clone(),
+        phantom: PhantomData,
+    })
+}
+
+fn listen<P: PortPayload, Ms: 'static, Mdl: 'static, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    port: MessagePort,
+    handler: impl Fn(PortEvent<P>) -> Ms + 'static,
+) -> Rc<Shared> {
+    let shared = Rc::new(Shared {
+        port,
+        closed: Cell::new(false),
+        listener: RefCell::new(None),
+    });
+
+    let listener = Closure::wrap(Box::new({
+        let app = app.clone();
+        let shared = Rc::downgrade(&shared);
+        move |event: web_sys::MessageEvent| {
+            let envelope = event
+                .data()
+                .as_string()
+                .and_then(|data| serde_json::from_str::<Envelope>(&data).ok());
+            let port_event = match envelope {
+                Some(Envelope::Connect { schema }) if schema == P::SCHEMA => PortEvent::Connected,
+                Some(Envelope::Message { payload }) => match serde_json::from_value(payload) {
+                    Ok(payload) => PortEvent::Message(payload),
+                    Err(error) => {
+                        web_sys::console::warn_1(
+                            &format!("Port payload doesn't match `{}`: {}", P::SCHEMA, error)
+                                .into(),
+                        );
+                        return;
+                    }
+                },
+                Some(Envelope::Close) => {
+                    if let Some(shared) = shared.upgrade() {
+                        close(&shared, false);
+                    }
+                    PortEvent::Closed
+                }
+                _ => {
+                    web_sys::console::warn_1(&"Invalid port message".into());
+                    return;
+                }
+            };
+            app.update(handler(port_event));
+        }
+    }) as Box<dyn FnMut(web_sys::MessageEvent)>);
+    shared
+        .port
+        .set_onmessage(Some(listener.as_ref().unchecked_ref()));
+    shared.listener.replace(Some(listener));
+
+    let mut ports = app.data.ports.borrow_mut();
+    ports.retain(|port| port.strong_count() > 0);
+    ports.push(Rc::downgrade(&shared));
+    shared
+}
+
+/// Close all ports of the app. Called when the app is unmounted.
+pub(crate) fn close_all<Ms: 'static, Mdl: 'static, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+) {
+    let ports = app.data.ports.replace(Vec::new());
+    for shared in ports.iter().filter_map(Weak::upgrade) {
+        close(&shared, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as seed;
+    use crate::browser::util;
+    use crate::prelude::*;
+    use wasm_bindgen_futures::JsFuture;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    enum CartEvent {
+        ItemAdded { id: u32 },
+    }
+
+    impl PortPayload for CartEvent {
+        const SCHEMA: &'static str = "cart/1";
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct CartEventV2 {
+        id: String,
+    }
+
+    impl PortPayload for CartEventV2 {
+        const SCHEMA: &'static str = "cart/2";
+    }
+
+    type Received = Rc<RefCell<Vec<PortEvent<CartEvent>>>>;
+
+    #[derive(Clone)]
+    enum Msg {
+        Port(PortEvent<CartEvent>),
+    }
+
+    fn update(msg: Msg, model: &mut Received, orders: &mut impl Orders<Msg>) {
+        orders.skip();
+        match msg {
+            Msg::Port(event) => model.borrow_mut().push(event),
+        }
+    }
+
+    fn app(received: &Received) -> App<Msg, Received, Node<Msg>> {
+        let received = Rc::clone(received);
+        App::build(move |_, _| Init::new(received), update, |_| seed::empty())
+            .mount(util::document().create_element("div").unwrap())
+            .finish()
+            .run()
+    }
+
+    /// Wait until posted messages are delivered.
+    async fn deliver() {
+        let promise = js_sys::Promise::new(&mut |resolve, _| {
+            util::window()
+                .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, 10)
+                .unwrap();
+        });
+        JsFuture::from(promise).await.unwrap();
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn ports_connect_apps() {
+        let (shell_received, widget_received) = (Received::default(), Received::default());
+        let (shell, widget) = (app(&shell_received), app(&widget_received));
+
+        let shell_port = shell.create_port::<CartEvent>("cart", Msg::Port);
+        let handle = shell_port.to_js();
+
+        assert_eq!(
+            widget
+                .connect_port::<CartEvent>("orders", &handle, Msg::Port)
+                .unwrap_err(),
+            PortError::NameMismatch {
+                expected: "orders".to_owned(),
+                found: "cart".to_owned()
+            }
+        );
+        assert_eq!(
+            widget
+                .connect_port::<CartEventV2>("cart", &handle, |_| unreachable!())
+                .unwrap_err(),
+            PortError::SchemaMismatch {
+                expected: "cart/2".to_owned(),
+                found: "cart/1".to_owned()
+            }
+        );
+        assert_eq!(
+            widget
+                .connect_port::<CartEvent>("cart", &JsValue::NULL, Msg::Port)
+                .unwrap_err(),
+            PortError::InvalidHandle
+        );
+
+        let widget_port = widget
+            .connect_port::<CartEvent>("cart", &handle, Msg::Port)
+            .unwrap();
+        assert_eq!(
+            widget
+                .connect_port::<CartEvent>("cart", &handle, Msg::Port)
+                .unwrap_err(),
+            PortError::AlreadyConnected
+        );
+
+        widget_port.send(&CartEvent::ItemAdded { id: 1 }).unwrap();
+        shell_port.send(&CartEvent::ItemAdded { id: 2 }).unwrap();
+        deliver().await;
+        assert_eq!(
+            *shell_received.borrow(),
+            vec![
+                PortEvent::Connected,
+                PortEvent::Message(CartEvent::ItemAdded { id: 1 })
+            ]
+        );
+        assert_eq!(
+            *widget_received.borrow(),
+            vec![PortEvent::Message(CartEvent::ItemAdded { id: 2 })]
+        );
+
+        // Unmount closes the widget's side.
+        widget.unmount();
+        assert!(widget_port.is_closed());
+        deliver().await;
+        assert_eq!(shell_received.borrow().last(), Some(&PortEvent::Closed));
+        assert!(shell_port.is_closed());
+        assert_eq!(
+            shell_port.send(&CartEvent::ItemAdded { id: 3 }),
+            Err(PortError::Closed)
+        );
+        shell.unmount();
+    }
+}
@@ -0,0 +1,459 @@
+//! Subresource hints - `<link rel="preload">` and `<link rel="prefetch">` in `<head>`.
+//! See `Orders::preload`.
+//!
+//! Hints are deduplicated by `href`, `as`, `crossorigin` and `rel` - all handles of the same hint
+//! share one link. Hints which differ only in one of them get separate links, because the browser
+//! doesn't reuse a response fetched with other attributes.
+//! The link is removed when all its handles are dropped or when its TTL expires
+//! (`PreloadHint::ttl`, `AppBuilder::preload_ttl`), so long sessions don't accumulate stale links.
+//! Links are marked by the attribute `data-seed-preload`; unlike page meta tags
+//! (see `seed::app::page_meta`), they aren't touched when the route changes.
+//!
+//! Prefetch both data and assets of a likely route from one declaration - see `prefetch_on_hover`
+//! and `Orders::prefetch_route`:
+//!
+//! ```rust,no_run
+//!a![attrs! {At::Href => post_url(id)}, prefetch_on_hover(Msg::PostLikely(id))]
+//!// ...
+//!Msg::PostLikely(id) => {
+//!    model.post_hints = orders.prefetch_route(
+//!        RoutePrefetch::new()
+//!            .asset(PreloadHint::new(cover_url(id), AsKind::Image).prefetch())
+//!            .data(async move { Msg::PostFetched(fetch_post(id).await) }),
+//!    );
+//!}
+//! ```
+
+use super::App;
+use crate::browser::dom::event_handler::simple_ev;
+use crate::browser::{
+    time::{duration_to_ms, TimeoutHandle},
+    util,
+};
+use crate::virtual_dom::{Ev, Listener, View};
+use futures::future::LocalFutureObj;
+use std::{collections::HashMap, future::Future, rc::Rc, time::Duration};
+use wasm_bindgen::{closure::Closure, JsCast};
+use wasm_bindgen_futures::spawn_local;
+
+/// Attribute of links written by Seed.
+pub const PRELOAD_ATTR: &str = "data-seed-preload";
+
+/// Default of `AppBuilder::preload_ttl`.
+pub const DEFAULT_PRELOAD_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Destination of the hinted resource - the link's `as` attribute.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AsKind {
+    Image,
+    /// Requires `crossorigin` - a font preload without it isn't used by the browser.
+    Font,
+    /// Requests made by `fetch` - set `crossorigin` to match the request's credentials mode.
+    Fetch,
+}
+
+impl AsKind {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Image => "image",
+            Self::Font => "font",
+            Self::Fetch => "fetch",
+        }
+    }
+}
+
+/// `preload` fetches the resource for the current page with a high priority,
+/// `prefetch` fetches it for a future navigation when the browser is idle.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HintRel {
+    Preload,
+    Prefetch,
+}
+
+impl HintRel {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Preload => "preload",
+            Self::Prefetch => "prefetch",
+        }
+    }
+}
+
+/// The link's `crossorigin` attribute.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CrossOrigin {
+    Anonymous,
+    UseCredentials,
+}
+
+impl CrossOrigin {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Anonymous => "anonymous",
+            Self::UseCredentials => "use-credentials",
+        }
+    }
+}
+
+/// Resource hint passed to `Orders::preload`.
+///
+/// # Example
+///
+/// ```rust,no_run
+///PreloadHint::new("/fonts/inter.woff2", AsKind::Font).crossorigin(CrossOrigin::Anonymous)
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreloadHint {
+    pub href: String,
+    pub as_kind: AsKind,
+    pub crossorigin: Option<CrossOrigin>,
+    pub rel: HintRel,
+    /// Overrides `AppBuilder::preload_ttl`.
+    pub ttl: Option<Duration>,
+}
+
+impl PreloadHint {
+    /// `rel="preload"` hint without `crossorigin`.
+    pub fn new(href: impl Into<String>, as_kind: AsKind) -> Self {
+        Self {
+            href: href.into(),
+            as_kind,
+            crossorigin: None,
+            rel: HintRel::Preload,
+            ttl: None,
+        }
+    }
+
+    pub fn crossorigin(mut self, crossorigin: CrossOrigin) -> Self {
+        self.crossorigin = Some(crossorigin);
+        self
+    }
+
+    /// Use `rel="prefetch"` instead of `rel="preload"`.
+    pub fn prefetch(mut self) -> Self {
+        self.rel = HintRel::Prefetch;
+        self
+    }
+
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+}
+
+/// Result of the hinted request - see `Orders::preload_with_status`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PreloadStatus {
+    Loaded,
+    Failed,
+}
+
+/// Hint created by `Orders::preload`. The hint's link is removed when all handles
+/// of the same hint are dropped (or when its TTL expires).
+#[must_use = "the hint is removed when the handle is dropped"]
+pub struct PreloadHandle {
+    release: Option<Box<dyn FnOnce()>>,
+}
+
+impl PreloadHandle {
+    /// Keep the hint until its TTL expires, even when the handle is dropped.
+    pub fn forget(mut self) {
+        self.release = None;
+    }
+}
+
+impl Drop for PreloadHandle {
+    fn drop(&mut self) {
+        if let Some(release) = self.release.take() {
+            release();
+        }
+    }
+}
+
+// ------ RoutePrefetch ------
+
+/// Listeners which send `msg` when the user is likely to follow the link - it's hovered,
+/// focused or pressed (e.g. touched). Handle the message by `Orders::prefetch_route`.
+///
+/// # Example
+///
+/// ```rust,no_run
+///a![
+///    attrs! {At::Href => Urls::new(base_url).post(id)},
+///    prefetch_on_hover(Msg::PostLikely(id)),
+///    "Read more",
+///]
+/// ```
+pub fn prefetch_on_hover<Ms: Clone + 'static>(msg: Ms) -> Vec<Listener<Ms>> {
+    vec![
+        simple_ev(Ev::MouseEnter, msg.clone()),
+        simple_ev(Ev::Focus, msg.clone()),
+        simple_ev(Ev::PointerDown, msg),
+    ]
+}
+
+/// Data requests and asset hints of a likely route - see `Orders::prefetch_route`.
+pub struct RoutePrefetch<Ms> {
+    pub(crate) hints: Vec<PreloadHint>,
+    pub(crate) data: Vec<LocalFutureObj<'static, Ms>>,
+}
+
+impl<Ms> Default for RoutePrefetch<Ms> {
+    fn default() -> Self {
+        Self {
+            hints: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+}
+
+impl<Ms: 'static> RoutePrefetch<Ms> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hint an asset of the route - e.g. `PreloadHint::new(cover_url, AsKind::Image).prefetch()`.
+    pub fn asset(mut self, hint: PreloadHint) -> Self {
+        self.hints.push(hint);
+        self
+    }
+
+    /// Request data of the route - the command's message is sent to `update`.
+    pub fn data(mut self, cmd: impl Future<Output = Ms> + 'static) -> Self {
+        self.data.push(LocalFutureObj::new(Box::new(cmd)));
+        self
+    }
+}
+
+// ------ State ------
+
+type StatusHandler<Ms> = Box<dyn FnOnce(PreloadStatus) -> Option<Ms>>;
+
+/// The browser reuses a hinted response only for a request with the same attributes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct HintKey {
+    href: String,
+    as_kind: AsKind,
+    crossorigin: Option<CrossOrigin>,
+    rel: HintRel,
+}
+
+impl From<&PreloadHint> for HintKey {
+    fn from(hint: &PreloadHint) -> Self {
+        Self {
+            href: hint.href.clone(),
+            as_kind: hint.as_kind,
+            crossorigin: hint.crossorigin,
+            rel: hint.rel,
+        }
+    }
+}
+
+pub(crate) struct Preloads<Ms> {
+    next_id: usize,
+    hints: HashMap<HintKey, Hint<Ms>>,
+}
+
+impl<Ms> Default for Preloads<Ms> {
+    fn default() -> Self {
+        Self {
+            next_id: 0,
+            hints: HashMap::new(),
+        }
+    }
+}
+
+struct Hint<Ms> {
+    /// Distinguishes the hint from a later hint with the same key.
+    id: usize,
+    link: web_sys::Element,
+    handles: usize,
+    status: Option<PreloadStatus>,
+    handlers: Vec<StatusHandler<Ms>>,
+    _listener: Closure<dyn FnMut(web_sys::Event)>,
+    _ttl: TimeoutHandle,
+}
+
+impl<Ms: 'static> Hint<Ms> {
+    fn remove(self) {
+        self.link.remove();
+        // We may be inside of the listener's or the timer's callback - drop them later.
+        spawn_local(async move { drop(self) });
+    }
+}
+
+// ------ Hints ------
+
+/// Insert the link of `hint` unless there is a link of the same hint already.
+/// A message returned by `handler` is returned immediately when the hinted request has already finished.
+pub(crate) fn preload<Ms: 'static, Mdl: 'static, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    mut hint: PreloadHint,
+    handler: Option<StatusHandler<Ms>>,
+) -> (PreloadHandle, Option<Ms>) {
+    if hint.as_kind == AsKind::Font && hint.crossorigin.is_none() {
+        web_sys::console::warn_1(
+            &format!(
+                "Font preload `{}` requires `crossorigin` - `CrossOrigin::Anonymous` is used.",
+                hint.href
+            )
+            .into(),
+        );
+        hint.crossorigin = Some(CrossOrigin::Anonymous);
+    }
+
+    let key = HintKey::from(&hint);
+    let mut finished = None;
+    let id = {
+        let mut preloads = app.data.preloads.borrow_mut();
+        match preloads.hints.get_mut(&key) {
+            Some(existing) => {
+                existing.handles += 1;
+                if let Some(handler) = handler {
+                    match existing.status {
+                        Some(status) => finished = Some((handler, status)),
+                        None => existing.handlers.push(handler),
+                    }
+                }
+                existing.id
+            }
+            None => {
+                let id = preloads.next_id;
+                preloads.next_id += 1;
+                let inserted = insert(app, &hint, &key, id, handler);
+                preloads.hints.insert(key.clone(), inserted);
+                id
+            }
+        }
+    };
+    let msg = finished.and_then(|(handler, status)| handler(status));
+
+    let data = Rc::downgrade(&app.data);
+    let handle = PreloadHandle {
+        release: Some(Box::new(move || {
+            if let Some(data) = data.upgrade() {
+                let mut preloads = data.preloads.borrow_mut();
+                let is_last = match preloads.hints.get_mut(&key) {
+                    Some(hint) if hint.id == id => {
+                        hint.handles -= 1;
+                        hint.handles == 0
+                    }
+                    // The hint has expired.
+                    _ => false,
+                };
+                if is_last {
+                    if let Some(hint) = preloads.hints.remove(&key) {
+                        hint.remove();
+                    }
+                }
+            }
+        })),
+    };
+    (handle, msg)
+}
+
+fn insert<Ms: 'static, Mdl: 'static, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    hint: &PreloadHint,
+    key: &HintKey,
+    id: usize,
+    handler: Option<StatusHandler<Ms>>,
+) -> Hint<Ms> {
+    let document = util::document();
+    let link = document
+        .create_element("link")
+        .expect("Problem creating preload link");
+    let mut attributes = vec![
+        ("rel", hint.rel.as_str()),
+        ("href", hint.href.as_str()),
+        ("as", hint.as_kind.as_str()),
+        (PRELOAD_ATTR, ""),
+    ];
+    if let Some(crossorigin) = hint.crossorigin {
+        attributes.push(("crossorigin", crossorigin.as_str()));
+    }
+    for (name, value) in attributes {
+        link.set_attribute(name, value)
+            .expect("Problem setting preload link attribute");
+    }
+
+    let listener = Closure::wrap(Box::new({
+        let app = app.clone();
+        let key = key.clone();
+        move |event: web_sys::Event| {
+            let status = if event.type_() == "load" {
+                PreloadStatus::Loaded
+            } else {
+                PreloadStatus::Failed
+            };
+            let handlers = match app.data.preloads.borrow_mut().hints.get_mut(&key) {
+                Some(hint) if hint.id == id => {
+                    hint.status = Some(status);
+                    hint.handlers.drain(..).collect::<Vec<_>>()
+                }
+                _ => Vec::new(),
+            };
+            for handler in handlers {
+                if let Some(msg) = handler(status) {
+                    app.update(msg);
+                }
+            }
+        }
+    }) as Box<dyn FnMut(web_sys::Event)>);
+    for event in &["load", "error"] {
+        link.add_event_listener_with_callback(event, listener.as_ref().unchecked_ref())
+            .expect("Problem adding preload link listener");
+    }
+
+    let ttl = hint.ttl.unwrap_or(app.cfg.preload_ttl);
+    let ttl = app.cfg.clock.set_timeout(
+        duration_to_ms(ttl),
+        Box::new({
+            let app = app.clone();
+            let key = key.clone();
+            move || {
+                let mut preloads = app.data.preloads.borrow_mut();
+                if preloads.hints.get(&key).map(|hint| hint.id) == Some(id) {
+                    if let Some(hint) = preloads.hints.remove(&key) {
+                        hint.remove();
+                    }
+                }
+            }
+        }),
+    );
+
+    document
+        .query_selector("head")
+        .ok()
+        .and_then(|head| head)
+        .expect("Problem getting `<head>`")
+        .append_child(&link)
+        .expect("Problem appending preload link");
+
+    Hint {
+        id,
+        link,
+        handles: 1,
+        status: None,
+        handlers: handler.into_iter().collect(),
+        _listener: listener,
+        _ttl: ttl,
+    }
+}
+
+/// Remove all links. Called when the app is unmounted.
+pub(crate) fn clear<Ms: 'static, Mdl: 'static, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+) {
+    let hints = app
+        .data
+        .preloads
+        .borrow_mut()
+        .hints
+        .drain()
+        .map(|(_, hint)| hint)
+        .collect::<Vec<_>>();
+    for hint in hints {
+        hint.remove();
+    }
+}
@@ -0,0 +1,64 @@
+use std::{collections::HashMap, fmt, hash::Hash};
+
+/// How many messages are processed in one queue drain before Seed assumes
+/// there is a message loop (e.g. `Msg::A` sends `Msg::B` which sends `Msg::A`).
+///
+/// Configure it by `AppBuilder::queue_budget`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct QueueBudget {
+    /// Max number of messages (including global messages) processed in one drain.
+    pub max_messages: usize,
+    pub on_exceeded: OnQueueBudgetExceeded,
+}
+
+impl Default for QueueBudget {
+    fn default() -> Self {
+        Self {
+            max_messages: 10_000,
+            on_exceeded: OnQueueBudgetExceeded::Defer,
+        }
+    }
+}
+
+/// What to do when the `QueueBudget` is exceeded.
+/// Diagnostics are logged in both cases.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OnQueueBudgetExceeded {
+    /// Continue processing.
+    Warn,
+    /// Stop the drain and process the remaining effects in the next task
+    /// so the browser stays responsive.
+    Defer,
+}
+
+/// How many of the last processed messages are used for diagnostics.
+pub(crate) const RECENT_MESSAGES_LIMIT: usize = 100;
+
+/// Create a diagnostic message with the most frequent message variants.
+pub(crate) fn overflow_report<T: Eq + Hash + fmt::Debug>(
+    budget: &QueueBudget,
+    recent_messages: &[T],
+    remaining_effects: usize,
+) -> String {
+    let mut counts = HashMap::new();
+    for discriminant in recent_messages {
+        *counts.entry(discriminant).or_insert(0) += 1;
+    }
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    counts.sort_by(|(_, a), (_, b)| b.cmp(a));
+    let most_frequent = counts
+        .iter()
+        .take(5)
+        .map(|(discriminant, count)| format!("{:?} x{}", discriminant, count))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        "Message queue budget ({} messages) exceeded - probably a message loop. \
+         {} effects remaining. Most frequent of the last {} messages: {}",
+        budget.max_messages,
+        remaining_effects,
+        recent_messages.len(),
+        most_frequent,
+    )
+}
@@ -0,0 +1,12 @@
+use super::render_timestamp_delta::{RenderTimestamp, RenderTimestampDelta};
+
+/// Information about the render passed to `after_next_render` callbacks.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct RenderInfo {
+    /// [performance.now()](https://developer.mozilla.org/en-US/docs/Web/API/Performance/now)
+    /// of the render.
+    pub timestamp: RenderTimestamp,
+    /// The difference between the previous render timestamp and this one.
+    /// `None` if it's the first render.
+    pub timestamp_delta: Option<RenderTimestampDelta>,
+}
@@ -0,0 +1,260 @@
+//! Focus management after navigation. See `AppBuilder::route_focus`.
+//!
+//! After the render that follows a navigation (a link click, back/forward buttons,
+//! a hash change or an intercepted form), focus is moved to the configured target and the new
+//! document title is announced by a polite live region, so keyboard and screen reader users
+//! continue on the new page instead of the `<body>`.
+//!
+//! - Non-interactive targets (`<main>`, `<h1>`, ..) get `tabindex="-1"` to receive focus.
+//!   The attribute is removed when the target loses focus, so it stays out of the tab order.
+//! - Focusing doesn't scroll - the page position is left to the browser
+//!   or `AppBuilder::scroll_behavior`.
+//! - When the URL has a hash and an element with the same `id` exists, the element
+//!   is focused (and scrolled into view) instead of the configured target.
+//! - The initial page load isn't a navigation - the focus isn't moved.
+//!
+//! `skip_link` renders a "skip to main content" link for the beginning of the page.
+
+use crate::browser::{util, Url};
+use crate::virtual_dom::{At, Category, El, ElRef, Listener, Node, UpdateEl};
+use indexmap::IndexMap;
+use wasm_bindgen::{closure::Closure, JsCast};
+
+/// Id of the live region which announces page titles.
+pub const ANNOUNCER_ID: &str = "seed-route-announcer";
+
+const VISUALLY_HIDDEN: &str = "position:absolute;width:1px;height:1px;margin:-1px;padding:0;\
+                               overflow:hidden;clip:rect(0,0,0,0);white-space:nowrap;border:0";
+
+const SKIP_LINK_FOCUSED: &str = "position:absolute;top:0;left:0;z-index:1000";
+
+/// Target of focus after navigation - see `AppBuilder::route_focus`.
+#[derive(Clone, Copy, Debug)]
+pub enum RouteFocus {
+    /// The first `<main>` or `[role="main"]` element.
+    MainLandmark,
+    /// The first element matching the CSS selector (e.g. `"h1"`).
+    Element(&'static str),
+    /// The element returned by the function. `None` leaves the focus as it is.
+    Custom(fn() -> Option<web_sys::Element>),
+}
+
+impl RouteFocus {
+    fn target(&self) -> Option<web_sys::Element> {
+        let selector = match self {
+            Self::MainLandmark => "main, [role=\"main\"]",
+            Self::Element(selector) => selector,
+            Self::Custom(target) => return target(),
+        };
+        util::document()
+            .query_selector(selector)
+            .ok()
+            .and_then(|el| el)
+    }
+}
+
+/// Move focus after a navigation to `url` and announce the document title.
+pub(crate) fn apply(route_focus: RouteFocus, url: &Url) {
+    let document = util::document();
+    let hash_target = url
+        .hash
+        .as_ref()
+        .filter(|hash| !hash.is_empty())
+        .and_then(|hash| document.get_element_by_id(hash));
+
+    match hash_target {
+        Some(hash_target) => focus(&hash_target, false),
+        None => {
+            if let Some(target) = route_focus.target() {
+                focus(&target, true);
+            }
+        }
+    }
+    announce(&document.title());
+}
+
+/// Focus `element`, including non-interactive ones - see the module docs.
+pub(crate) fn focus(element: &web_sys::Element, prevent_scroll: bool) {
+    let element = match element.dyn_ref::<web_sys::HtmlElement>() {
+        Some(element) => element,
+        None => return,
+    };
+    if !element.has_attribute("tabindex") {
+        if element.set_attribute("tabindex", "-1").is_err() {
+            crate::error("Problem setting `tabindex` on the focus target");
+            return;
+        }
+        let remove_tabindex = Closure::once_into_js({
+            let element = element.clone();
+            move || {
+                element.remove_attribute("tabindex").ok();
+            }
+        });
+        let mut options = web_sys::AddEventListenerOptions::new();
+        options.once(true);
+        element
+            .add_event_listener_with_callback_and_add_event_listener_options(
+                "blur",
+                remove_tabindex.unchecked_ref(),
+                &options,
+            )
+            .expect("Problem adding the blur listener");
+    }
+    let mut options = web_sys::FocusOptions::new();
+    options.prevent_scroll(prevent_scroll);
+    if element.focus_with_options(&options).is_err() {
+        crate::error("Problem focusing the route focus target");
+    }
+    if !prevent_scroll {
+        element.scroll_into_view();
+    }
+}
+
+/// Announce `message` by the live region. The region is created on the first call.
+fn announce(message: &str) {
+    let document = util::document();
+    let region = document.get_element_by_id(ANNOUNCER_ID).or_else(|| {
+        let region = document.create_element("div").ok()?;
+        region.set_id(ANNOUNCER_ID);
+        region.set_attribute("aria-live", "polite").ok()?;
+        region.set_attribute("aria-atomic", "true").ok()?;
+        region.set_attribute("style", VISUALLY_HIDDEN).ok()?;
+        util::body().append_child(&region).ok()?;
+        Some(region)
+    });
+    match region {
+        Some(region) => region.set_text_content(Some(message)),
+        None => {
+            crate::error("Problem creating the route announcer");
+        }
+    }
+}
+
+/// A link which is visually hidden until it's focused and moves focus to `target`
+/// when it's activated. Render it as the first focusable element of the page.
+///
+/// # Example
+///
+/// ```rust,no_run
+///fn view(model: &Model) -> Vec<Node<Msg>> {
+///    vec![
+///        skip_link(&model.main, "Skip to main content"),
+///        nav![..],
+///        main![el_ref(&model.main), ..],
+///    ]
+///}
+/// ```
+pub fn skip_link<Ms: 'static, E: JsCast + 'static>(target: &ElRef<E>, label: &str) -> Node<Ms> {
+    let target = target.clone();
+    let on_click = move |event: web_sys::Event| {
+        event.prevent_default();
+        if let Some(element) = target.get().and_then(|el| el.dyn_into().ok()) {
+            focus(&element, false);
+        }
+        None
+    };
+    a![
+        class!["seed-skip-link"],
+        attrs! {At::Href => "#"},
+        attrs! {At::Style => VISUALLY_HIDDEN},
+        set_style_listener("focus", SKIP_LINK_FOCUSED),
+        set_style_listener("blur", VISUALLY_HIDDEN),
+        Listener::new("click", Some(Box::new(on_click)), Some(Category::Raw), None),
+        label,
+    ]
+}
+
+fn set_style_listener<Ms: 'static>(trigger: &str, style: &'static str) -> Listener<Ms> {
+    let handler = move |event: web_sys::Event| {
+        if let Some(link) = event
+            .current_target()
+            .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+        {
+            link.set_attribute("style", style).ok();
+        }
+        None
+    };
+    Listener::new(trigger, Some(Box::new(handler)), Some(Category::Raw), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{builder::init::Init, App, Orders};
+    use crate::browser::url;
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    struct Model;
+
+    #[derive(Clone)]
+    enum Msg {
+        UrlChanged,
+    }
+
+    fn update(_: Msg, _: &mut Model, _: &mut impl Orders<Msg>) {}
+
+    fn view(_: &Model) -> Vec<Node<Msg>> {
+        vec![
+            main![h1!["Page"]],
+            section![attrs! {At::Id => "details"}, "Details"],
+        ]
+    }
+
+    fn active_element() -> Option<web_sys::Element> {
+        util::document().active_element()
+    }
+
+    #[wasm_bindgen_test]
+    fn focus_follows_navigation() {
+        let original_url = util::window().location().href().unwrap();
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(|_, _| Init::new(Model), update, view)
+            .mount(mount_point.clone())
+            .routes(|_| Some(Msg::UrlChanged))
+            .route_focus(RouteFocus::MainLandmark)
+            .finish()
+            .run();
+        app.flush_render();
+        let main = mount_point.query_selector("main").unwrap().unwrap();
+        let details = util::document().get_element_by_id("details").unwrap();
+
+        // The initial page load isn't a navigation.
+        assert_ne!(active_element(), Some(main.clone()));
+
+        let navigate = || {
+            let msg = app.navigate(url::current()).unwrap();
+            app.update(msg);
+            app.flush_render();
+        };
+
+        navigate();
+        assert_eq!(active_element(), Some(main.clone()));
+        assert_eq!(main.get_attribute("tabindex"), Some("-1".to_owned()));
+        let announcer = util::document().get_element_by_id(ANNOUNCER_ID).unwrap();
+        assert_eq!(announcer.text_content(), Some(util::document().title()));
+
+        // `tabindex` is removed after blur.
+        main.dyn_ref::<web_sys::HtmlElement>()
+            .unwrap()
+            .blur()
+            .unwrap();
+        assert_eq!(main.get_attribute("tabindex"), None);
+
+        // The hash target wins.
+        util::history()
+            .replace_state_with_url(&JsValue::NULL, "", Some("#details"))
+            .unwrap();
+        navigate();
+        assert_eq!(active_element(), Some(details));
+
+        util::history()
+            .replace_state_with_url(&JsValue::NULL, "", Some(&original_url))
+            .unwrap();
+        mount_point.remove();
+        announcer.remove();
+    }
+}
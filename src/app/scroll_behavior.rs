@@ -0,0 +1,155 @@
+//! Scroll positions on navigation. See `AppBuilder::scroll_behavior`.
+//!
+//! With `ScrollBehavior::RestoreOrTop`:
+//! - The scroll position (`scrollX` and `scrollY`) of the rendered history entry is recorded
+//!   before the first render
+//!   of a new entry (e.g. after `push_route`), so the old page still has its height.
+//!   The history entry has been already replaced at that time, so the positions are kept
+//!   in memory instead of the entries' state. Entries are identified by their URL -
+//!   entries with the same URL share the position.
+//! - Back / forward navigations (`popstate`) restore the recorded position (or scroll to the top
+//!   for entries without it) by an `after_next_render` callback, so the DOM has its final height.
+//! - The other navigations scroll to the top.
+//! - Hash-only navigations (`#section`) are left to the browser's anchor scrolling.
+//!
+//! The browser's own restoration is disabled (`history.scrollRestoration = "manual"`).
+
+use crate::browser::util;
+use std::{collections::HashMap, mem};
+
+/// How Seed handles the scroll position on navigation - see `AppBuilder::scroll_behavior`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScrollBehavior {
+    /// Seed doesn't change the scroll position.
+    Browser,
+    /// Restore recorded positions on back / forward navigations and scroll to the top
+    /// on the other ones.
+    RestoreOrTop,
+}
+
+impl Default for ScrollBehavior {
+    fn default() -> Self {
+        Self::Browser
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct ScrollPositions {
+    positions: HashMap<String, (f64, f64)>,
+    /// `href` of the rendered history entry.
+    entry: Option<String>,
+    /// The current entry has been reached by a back / forward navigation.
+    traversal: bool,
+}
+
+impl ScrollPositions {
+    /// Mark the next entry change as a back / forward navigation.
+    pub(crate) fn traversed(&mut self) {
+        self.traversal = true;
+    }
+
+    /// Record the position of the rendered entry when the current entry is a new one.
+    /// Returns the position (`x`, `y`) to scroll to after the render - `None` when the scroll
+    /// position should stay as it is.
+    pub(crate) fn before_render(&mut self) -> Option<(f64, f64)> {
+        let window = util::window();
+        let href = window.location().href().ok()?;
+        let traversal = mem::replace(&mut self.traversal, false);
+        let previous = match self.entry.replace(href.clone()) {
+            Some(previous) if previous != href => previous,
+            // The first render or the entry hasn't been changed.
+            _ => return None,
+        };
+        self.positions.insert(
+            previous.clone(),
+            (
+                window.scroll_x().unwrap_or_default(),
+                window.scroll_y().unwrap_or_default(),
+            ),
+        );
+
+        if without_hash(&previous) == without_hash(&href) {
+            None
+        } else if traversal {
+            Some(self.positions.get(&href).copied().unwrap_or_default())
+        } else {
+            Some((0., 0.))
+        }
+    }
+}
+
+fn without_hash(href: &str) -> &str {
+    href.split('#').next().unwrap_or(href)
+}
+
+/// Disable the browser's restoration - it would scroll before the new page is rendered.
+pub(crate) fn disable_browser_restoration() {
+    if util::history()
+        .set_scroll_restoration(web_sys::ScrollRestoration::Manual)
+        .is_err()
+    {
+        crate::error("Problem setting `history.scrollRestoration`");
+    }
+}
+
+/// Give the restoration back to the browser - see `App::unmount`.
+pub(crate) fn restore_browser_restoration() {
+    if util::history()
+        .set_scroll_restoration(web_sys::ScrollRestoration::Auto)
+        .is_err()
+    {
+        crate::error("Problem setting `history.scrollRestoration`");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn go_to(href: &str) {
+        util::history()
+            .replace_state_with_url(&JsValue::NULL, "", Some(href))
+            .unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn positions_are_restored_on_traversal() {
+        let original_url = util::window().location().href().unwrap();
+        let window = util::window();
+        let tall = util::document().create_element("div").unwrap();
+        tall.set_attribute("style", "height:5000px").unwrap();
+        util::body().append_child(&tall).unwrap();
+        let mut positions = ScrollPositions::default();
+
+        go_to("/list");
+        // The first render.
+        assert_eq!(positions.before_render(), None);
+        window.scroll_to_with_x_and_y(0., 300.);
+        // The entry hasn't been changed.
+        assert_eq!(positions.before_render(), None);
+
+        go_to("/article");
+        assert_eq!(positions.before_render(), Some((0., 0.)));
+        window.scroll_to_with_x_and_y(0., 0.);
+
+        go_to("/article#comments");
+        assert_eq!(positions.before_render(), None);
+
+        go_to("/list");
+        positions.traversed();
+        assert_eq!(positions.before_render(), Some((0., 300.)));
+
+        // An entry without a recorded position.
+        go_to("/settings");
+        positions.traversed();
+        assert_eq!(positions.before_render(), Some((0., 0.)));
+
+        window.scroll_to_with_x_and_y(0., 0.);
+        tall.remove();
+        go_to(&original_url);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::browser::visibility::{self, Pausable, PausePolicy};
+use futures::stream::{LocalBoxStream, Stream, StreamExt};
+use std::{
+    cell::{Cell, RefCell},
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+/// Handle of a stream subscribed by `orders.stream_with_handle`.
+/// Dropping the handle (or calling `cancel`) ends the stream - no more items are sent,
+/// even if they have been already buffered.
+#[must_use = "the stream is canceled when the handle is dropped"]
+#[derive(Debug)]
+pub struct StreamHandle {
+    state: Rc<State>,
+}
+
+impl StreamHandle {
+    /// Cancel the stream.
+    pub fn cancel(self) {}
+
+    /// Stop sending the stream's messages while the document is hidden - see
+    /// `seed::browser::visibility`. Items produced in the meantime are handled by `policy`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///model.poll = Some(
+    ///    orders
+    ///        .stream_with_handle(streams::interval(5_000, || Msg::Poll))
+    ///        .pause_when_hidden(PausePolicy::CoalesceLatest),
+    ///);
+    /// ```
+    pub fn pause_when_hidden(self, policy: PausePolicy) -> Self {
+        self.state.policy.set(policy);
+        let state: Rc<dyn Pausable> = self.state.clone();
+        visibility::register(&state);
+        self
+    }
+}
+
+impl Drop for StreamHandle {
+    fn drop(&mut self) {
+        self.state.canceled.set(true);
+        // Wake the task, so the stream is dropped even if it never yields again.
+        if let Some(waker) = self.state.waker.borrow_mut().take() {
+            waker.wake();
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    canceled: Cell<bool>,
+    paused: Cell<bool>,
+    policy: Cell<PausePolicy>,
+    waker: RefCell<Option<Waker>>,
+}
+
+impl Pausable for State {
+    fn pause(&self) {
+        self.paused.set(true);
+    }
+
+    fn resume(&self) {
+        self.paused.set(false);
+        // Send the coalesced item.
+        if let Some(waker) = self.waker.borrow_mut().take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Stream which ends when its handle is dropped.
+struct Cancelable<Ms> {
+    stream: LocalBoxStream<'static, Ms>,
+    state: Rc<State>,
+    /// The latest item received while paused - see `PausePolicy::CoalesceLatest`.
+    coalesced: Option<Ms>,
+}
+
+// Fields aren't pinned - `stream` is boxed.
+impl<Ms> Unpin for Cancelable<Ms> {}
+
+impl<Ms> Stream for Cancelable<Ms> {
+    type Item = Ms;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Ms>> {
+        if self.state.canceled.get() {
+            return Poll::Ready(None);
+        }
+        self.state.waker.replace(Some(cx.waker().clone()));
+        if self.state.paused.get() {
+            // Drain the stream, so its items aren't buffered.
+            loop {
+                match self.stream.as_mut().poll_next(cx) {
+                    Poll::Ready(Some(item)) => {
+                        if self.state.policy.get() == PausePolicy::CoalesceLatest {
+                            self.coalesced = Some(item);
+                        }
+                    }
+                    Poll::Ready(None) => return Poll::Ready(None),
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+        }
+        if let Some(item) = self.coalesced.take() {
+            return Poll::Ready(Some(item));
+        }
+        self.stream.as_mut().poll_next(cx)
+    }
+}
+
+/// Make `stream` cancelable by the returned handle.
+pub(crate) fn cancelable<Ms: 'static>(
+    stream: impl Stream<Item = Ms> + 'static,
+) -> (LocalBoxStream<'static, Ms>, StreamHandle) {
+    let state = Rc::new(State::default());
+    let stream = Cancelable {
+        stream: stream.boxed_local(),
+        state: Rc::clone(&state),
+        coalesced: None,
+    };
+    (stream.boxed_local(), StreamHandle { state })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser::visibility::tests::{reset_hidden, set_hidden};
+    use futures::{channel::mpsc, FutureExt};
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn streams_pause_when_hidden() {
+        for &(policy, resumed_item) in &[
+            (PausePolicy::Drop, None),
+            (PausePolicy::CoalesceLatest, Some(3)),
+        ] {
+            let (sender, receiver) = mpsc::unbounded();
+            let (mut stream, handle) = cancelable(receiver);
+            let _handle = handle.pause_when_hidden(policy);
+
+            sender.unbounded_send(1).unwrap();
+            assert_eq!(stream.next().now_or_never(), Some(Some(1)));
+
+            set_hidden(true);
+            sender.unbounded_send(2).unwrap();
+            sender.unbounded_send(3).unwrap();
+            assert_eq!(stream.next().now_or_never(), None);
+
+            set_hidden(false);
+            assert_eq!(stream.next().now_or_never(), resumed_item.map(Some));
+            sender.unbounded_send(4).unwrap();
+            assert_eq!(stream.next().now_or_never(), Some(Some(4)));
+        }
+        reset_hidden();
+    }
+}
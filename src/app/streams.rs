@@ -0,0 +1,104 @@
+//! Streams for `orders.stream` and `orders.stream_with_handle`.
+
+use crate::browser::time::{self, Clock, TimeoutHandle};
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use futures::stream::{Stream, StreamExt};
+use std::{
+    cell::RefCell,
+    pin::Pin,
+    rc::{Rc, Weak},
+    task::{Context, Poll},
+};
+
+/// Stream which yields `handler`'s message every `ms` milliseconds.
+///
+/// The interval is started only once, when the stream is created, so it keeps ticking
+/// across renders. Ticks are scheduled by the app's clock
+/// (see `seed::browser::time::current_clock`). Dropping the stream (e.g. by dropping the handle
+/// of `orders.stream_with_handle`) clears the interval.
+///
+/// # Example
+///
+/// ```rust,no_run
+///model.timer = Some(orders.stream_with_handle(streams::interval(1_000, || Msg::Tick)));
+///// Stop the timer.
+///model.timer = None;
+/// ```
+pub fn interval<MsU>(
+    ms: u32,
+    handler: impl FnOnce() -> MsU + Clone + 'static,
+) -> impl Stream<Item = MsU> {
+    let (sender, receiver) = mpsc::unbounded();
+    let timer = Rc::new(RefCell::new(None));
+    schedule_tick(time::current_clock(), ms, sender, Rc::downgrade(&timer));
+    IntervalStream {
+        receiver,
+        _timer: timer,
+    }
+    .map(move |()| handler.clone()())
+}
+
+/// Send a tick after `ms` and schedule the next one while `timer` exists.
+fn schedule_tick(
+    clock: Rc<dyn Clock>,
+    ms: u32,
+    sender: UnboundedSender<()>,
+    timer: Weak<RefCell<Option<TimeoutHandle>>>,
+) {
+    let handle = clock.set_timeout(
+        ms,
+        Box::new({
+            let (clock, timer) = (Rc::clone(&clock), timer.clone());
+            move || {
+                // The receiver is dropped only together with `timer`.
+                let _ = sender.unbounded_send(());
+                schedule_tick(clock, ms, sender, timer);
+            }
+        }),
+    );
+    // The handle is dropped (and the timer cleared) when the stream has been dropped.
+    if let Some(timer) = timer.upgrade() {
+        timer.replace(Some(handle));
+    }
+}
+
+/// Stream of interval ticks. The interval is cleared when the stream is dropped.
+struct IntervalStream {
+    receiver: UnboundedReceiver<()>,
+    _timer: Rc<RefCell<Option<TimeoutHandle>>>,
+}
+
+impl Stream for IntervalStream {
+    type Item = ();
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<()>> {
+        self.receiver.poll_next_unpin(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser::time::{with_clock, MockClock};
+    use futures::FutureExt;
+    use std::time::Duration;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn interval_ticks_by_app_clock() {
+        let clock = MockClock::new();
+        let app_clock: Rc<dyn Clock> = Rc::new(clock.clone());
+        let mut ticks = with_clock(&app_clock, || interval(100, || "tick"));
+
+        clock.advance(Duration::from_millis(250));
+        assert_eq!(ticks.next().now_or_never(), Some(Some("tick")));
+        assert_eq!(ticks.next().now_or_never(), Some(Some("tick")));
+        assert_eq!(ticks.next().now_or_never(), None);
+
+        // Dropping the stream clears the interval.
+        drop(ticks);
+        assert_eq!(clock.pending_timers(), 0);
+    }
+}
@@ -0,0 +1,130 @@
+//! App-level pub/sub. See `orders.subscribe` and `orders.notify`.
+//!
+//! Subscribers are keyed by the notification's type, so any component can notify any other
+//! component without threading messages through their parents' `update` functions.
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+/// Subscription created by `orders.subscribe` or `orders.subscribe_input_modality`.
+/// Dropping the handle unsubscribes -
+/// store it in the component's model, so the component stops receiving notifications
+/// when it's removed from the model.
+#[must_use]
+#[derive(Debug)]
+pub struct SubHandle {
+    _alive: Rc<()>,
+}
+
+impl SubHandle {
+    /// New handle and a weak reference which can be upgraded while the handle is alive.
+    pub(crate) fn new() -> (Self, Weak<()>) {
+        let alive = Rc::new(());
+        let weak = Rc::downgrade(&alive);
+        (Self { _alive: alive }, weak)
+    }
+}
+
+struct Subscription<Ms> {
+    alive: Weak<()>,
+    handler: Box<dyn Fn(&dyn Any) -> Option<Ms>>,
+}
+
+#[allow(clippy::module_name_repetitions)]
+pub(crate) struct Subscriptions<Ms> {
+    subscriptions: HashMap<TypeId, Vec<Subscription<Ms>>>,
+}
+
+impl<Ms> Default for Subscriptions<Ms> {
+    fn default() -> Self {
+        Self {
+            subscriptions: HashMap::new(),
+        }
+    }
+}
+
+impl<Ms> Subscriptions<Ms> {
+    pub(crate) fn subscribe<SubMs: Any + Clone>(
+        &mut self,
+        handler: impl Fn(SubMs) -> Ms + 'static,
+    ) -> SubHandle {
+        let (handle, alive) = SubHandle::new();
+        self.subscriptions
+            .entry(TypeId::of::<SubMs>())
+            .or_insert_with(Vec::new)
+            .push(Subscription {
+                alive,
+                handler: Box::new(move |message| {
+                    message
+                        .downcast_ref::<SubMs>()
+                        .map(|message| handler(message.clone()))
+                }),
+            });
+        handle
+    }
+
+    /// Messages of all live subscribers of `SubMs`, in the subscription order.
+    /// Subscriptions with dropped handles are removed.
+    pub(crate) fn notify<SubMs: Any + Clone>(&mut self, message: &SubMs) -> Vec<Ms> {
+        let type_id = TypeId::of::<SubMs>();
+        let subscriptions = match self.subscriptions.get_mut(&type_id) {
+            Some(subscriptions) => subscriptions,
+            None => return Vec::new(),
+        };
+        subscriptions.retain(|subscription| subscription.alive.upgrade().is_some());
+        let messages = subscriptions
+            .iter()
+            .filter_map(|subscription| (subscription.handler)(message))
+            .collect();
+        if subscriptions.is_empty() {
+            self.subscriptions.remove(&type_id);
+        }
+        messages
+    }
+
+    /// Number of subscriptions with live handles.
+    pub(crate) fn len(&self) -> usize {
+        self.subscriptions
+            .values()
+            .flatten()
+            .filter(|subscription| subscription.alive.upgrade().is_some())
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[derive(Clone)]
+    struct Saved(u32);
+
+    #[wasm_bindgen_test]
+    fn notify_reaches_live_subscribers() {
+        let mut subscriptions = Subscriptions::default();
+        // No subscribers.
+        assert!(subscriptions.notify(&Saved(0)).is_empty());
+
+        let first = subscriptions.subscribe(|Saved(id)| format!("first {}", id));
+        let second = subscriptions.subscribe(|Saved(id)| format!("second {}", id));
+        let _other = subscriptions.subscribe(|message: String| message);
+        assert_eq!(
+            subscriptions.notify(&Saved(1)),
+            vec!["first 1".to_owned(), "second 1".to_owned()]
+        );
+
+        drop(first);
+        assert_eq!(subscriptions.notify(&Saved(2)), vec!["second 2".to_owned()]);
+        drop(second);
+        assert!(subscriptions.notify(&Saved(3)).is_empty());
+        assert!(!subscriptions
+            .subscriptions
+            .contains_key(&TypeId::of::<Saved>()));
+    }
+}
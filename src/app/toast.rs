@@ -0,0 +1,644 @@
+//! Toast notifications. See `orders.toast` and `ToastManager`.
+//!
+//! Any module sends toasts by `orders.toast(..)`; they are delivered through the pub/sub channel
+//! (see `orders.notify`) to the `ToastManager` embedded in the root model. The manager handles
+//! the queue limits, auto-dismissing (timers are scheduled by the app's `Clock`)
+//! and pausing while the toast region is hovered or focused.
+//!
+//! `toast_region_view` renders the region with the listeners needed for pausing and with ARIA
+//! attributes for screen readers (`aria-live="polite"`, `role="alert"` for errors).
+//! It's only a default - you can write your own view from `ToastManager::visible`
+//! and `ToastManager::msg`. Styling is up to you - see the classes in `toast_region_view`.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//!struct Model {
+//!    toasts: ToastManager<Msg>,
+//!}
+//!
+//!enum Msg {
+//!    Toasts(ToastMsg),
+//!    Delete,
+//!    Undo,
+//!}
+//!
+//!fn init(_: Url, orders: &mut impl Orders<Msg>) -> Init<Model> {
+//!    Init::new(Model {
+//!        toasts: ToastManager::new(orders, ToastOpts::default(), Msg::Toasts),
+//!    })
+//!}
+//!
+//!fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+//!    match msg {
+//!        Msg::Toasts(msg) => model.toasts.update(msg, orders),
+//!        // Any module can send toasts - messages are mapped by `orders.proxy`.
+//!        Msg::Delete => {
+//!            orders.toast(
+//!                Toast::info("Deleted")
+//!                    .timeout(Duration::from_secs(4))
+//!                    .action("Undo", Msg::Undo),
+//!            );
+//!        }
+//!        Msg::Undo => log!("undo"),
+//!    }
+//!}
+//!
+//!fn view(model: &Model) -> impl View<Msg> {
+//!    vec![
+//!        button![ev(Ev::Click, |_| Msg::Delete), "Delete"],
+//!        toast_region_view(&model.toasts),
+//!    ]
+//!}
+//! ```
+
+use super::{MessageMapper, Orders, SubHandle};
+use crate::browser::time::{self, Clock, TimeoutHandle};
+use crate::virtual_dom::{At, Category, El, Listener, Node, UpdateEl};
+use indexmap::IndexMap;
+use std::{cell::RefCell, collections::VecDeque, rc::Rc, time::Duration};
+use wasm_bindgen::JsCast;
+
+/// Default auto-dismiss timeout.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// ------ Toast ------
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ToastKind {
+    Info,
+    Success,
+    Warning,
+    /// Errors are announced immediately by screen readers (`role="alert"`).
+    Error,
+}
+
+impl ToastKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ToastKind::Info => "info",
+            ToastKind::Success => "success",
+            ToastKind::Warning => "warning",
+            ToastKind::Error => "error",
+        }
+    }
+}
+
+/// Toast - send it by `orders.toast`.
+#[derive(Debug, Clone)]
+pub struct Toast<Ms> {
+    pub kind: ToastKind,
+    pub text: String,
+    /// `None` - the toast stays until it's dismissed.
+    pub timeout: Option<Duration>,
+    /// Label of the action button and the message sent when it's clicked.
+    pub action: Option<(String, Ms)>,
+}
+
+impl<Ms> Toast<Ms> {
+    pub fn new(kind: ToastKind, text: impl Into<String>) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+            timeout: Some(DEFAULT_TIMEOUT),
+            action: None,
+        }
+    }
+
+    pub fn info(text: impl Into<String>) -> Self {
+        Self::new(ToastKind::Info, text)
+    }
+
+    pub fn success(text: impl Into<String>) -> Self {
+        Self::new(ToastKind::Success, text)
+    }
+
+    pub fn warning(text: impl Into<String>) -> Self {
+        Self::new(ToastKind::Warning, text)
+    }
+
+    pub fn error(text: impl Into<String>) -> Self {
+        Self::new(ToastKind::Error, text)
+    }
+
+    /// Dismiss the toast after `timeout` (paused while the region is hovered or focused).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Don't dismiss the toast automatically.
+    pub fn sticky(mut self) -> Self {
+        self.timeout = None;
+        self
+    }
+
+    /// Add an action button. Clicking it dismisses the toast and sends `msg`.
+    pub fn action(mut self, label: impl Into<String>, msg: Ms) -> Self {
+        self.action = Some((label.into(), msg));
+        self
+    }
+}
+
+impl<Ms: 'static, OtherMs: 'static> MessageMapper<Ms, OtherMs> for Toast<Ms> {
+    type SelfWithOtherMs = Toast<OtherMs>;
+    fn map_msg(self, f: impl FnOnce(Ms) -> OtherMs + 'static + Clone) -> Toast<OtherMs> {
+        Toast {
+            kind: self.kind,
+            text: self.text,
+            timeout: self.timeout,
+            action: self.action.map(|(label, msg)| (label, f(msg))),
+        }
+    }
+}
+
+/// Toast on its way from `orders.toast` to `ToastManager` - notifications have to be `Clone`,
+/// but messages don't.
+pub(crate) struct ToastRequest<Ms>(Rc<RefCell<Option<Toast<Ms>>>>);
+
+impl<Ms> ToastRequest<Ms> {
+    pub(crate) fn new(toast: Toast<Ms>) -> Self {
+        Self(Rc::new(RefCell::new(Some(toast))))
+    }
+}
+
+impl<Ms> Clone for ToastRequest<Ms> {
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0))
+    }
+}
+
+// ------ ToastManager ------
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ToastId(u64);
+
+/// Messages of `ToastManager` - handle them by `ToastManager::update`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ToastMsg {
+    /// New toasts have been sent by `orders.toast`.
+    Received,
+    Expired(ToastId),
+    Dismiss(ToastId),
+    Action(ToastId),
+    Hovered(bool),
+    Focused(bool),
+}
+
+/// Options for `ToastManager`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ToastOpts {
+    /// Other toasts wait in the queue until a visible one is dismissed.
+    pub max_visible: usize,
+    /// The oldest waiting toasts are dropped when the queue is full.
+    pub max_queued: usize,
+}
+
+impl Default for ToastOpts {
+    fn default() -> Self {
+        Self {
+            max_visible: 3,
+            max_queued: 20,
+        }
+    }
+}
+
+struct Entry<Ms> {
+    id: ToastId,
+    toast: Toast<Ms>,
+    /// Remaining time to auto-dismiss.
+    remaining: Option<Duration>,
+    started_ms: f64,
+    timer: Option<TimeoutHandle>,
+}
+
+/// Toast queue - embed it into the root model. See the module's docs.
+pub struct ToastManager<Ms: 'static> {
+    opts: ToastOpts,
+    to_msg: Rc<dyn Fn(ToastMsg) -> Ms>,
+    inbox: Rc<RefCell<VecDeque<Toast<Ms>>>>,
+    visible: Vec<Entry<Ms>>,
+    queued: VecDeque<(ToastId, Toast<Ms>)>,
+    next_id: u64,
+    hovered: bool,
+    focused: bool,
+    _sub: SubHandle,
+}
+
+impl<Ms: 'static> ToastManager<Ms> {
+    /// Subscribe to toasts sent by `orders.toast`. Call it with the root's `orders` -
+    /// toasts are delivered with messages mapped to the root's `Msg`.
+    pub fn new<GMs>(
+        orders: &mut impl Orders<Ms, GMs, AppMs = Ms>,
+        opts: ToastOpts,
+        to_msg: impl Fn(ToastMsg) -> Ms + 'static,
+    ) -> Self {
+        let to_msg: Rc<dyn Fn(ToastMsg) -> Ms> = Rc::new(to_msg);
+        let inbox = Rc::new(RefCell::new(VecDeque::new()));
+        let sub = orders.subscribe({
+            let (to_msg, inbox) = (Rc::clone(&to_msg), Rc::clone(&inbox));
+            move |request: ToastRequest<Ms>| {
+                if let Some(toast) = request.0.borrow_mut().take() {
+                    inbox.borrow_mut().push_back(toast);
+                }
+                to_msg(ToastMsg::Received)
+            }
+        });
+        Self {
+            opts,
+            to_msg,
+            inbox,
+            visible: Vec::new(),
+            queued: VecDeque::new(),
+            next_id: 0,
+            hovered: false,
+            focused: false,
+            _sub: sub,
+        }
+    }
+
+    pub fn update<GMs>(&mut self, msg: ToastMsg, orders: &mut impl Orders<Ms, GMs>) {
+        match msg {
+            ToastMsg::Received => {
+                let toasts = self.inbox.borrow_mut().drain(..).collect::<Vec<_>>();
+                for toast in toasts {
+                    self.push(toast, orders);
+                }
+            }
+            ToastMsg::Expired(id) | ToastMsg::Dismiss(id) => {
+                self.remove(id, orders);
+            }
+            ToastMsg::Action(id) => {
+                let action = self.remove(id, orders).and_then(|toast| toast.action);
+                if let Some((_, msg)) = action {
+                    orders.send_msg(msg);
+                }
+            }
+            ToastMsg::Hovered(hovered) => {
+                self.hovered = hovered;
+                self.sync_timers(orders);
+            }
+            ToastMsg::Focused(focused) => {
+                self.focused = focused;
+                self.sync_timers(orders);
+            }
+        }
+    }
+
+    /// Visible toasts, the oldest first.
+    pub fn visible(&self) -> impl Iterator<Item = (ToastId, &Toast<Ms>)> {
+        self.visible.iter().map(|entry| (entry.id, &entry.toast))
+    }
+
+    /// Number of toasts waiting for a free slot.
+    pub fn queued_len(&self) -> usize {
+        self.queued.len()
+    }
+
+    /// Auto-dismissing is paused while the region is hovered or focused.
+    pub fn is_paused(&self) -> bool {
+        self.hovered || self.focused
+    }
+
+    /// Map `ToastMsg` to your `Msg` - for custom views.
+    pub fn msg(&self, msg: ToastMsg) -> Ms {
+        (self.to_msg)(msg)
+    }
+
+    fn push<GMs>(&mut self, toast: Toast<Ms>, orders: &mut impl Orders<Ms, GMs>) {
+        let id = ToastId(self.next_id);
+        self.next_id += 1;
+        if self.visible.len() < self.opts.max_visible {
+            self.show(id, toast, orders);
+        } else {
+            self.queued.push_back((id, toast));
+            while self.queued.len() > self.opts.max_queued {
+                self.queued.pop_front();
+            }
+        }
+    }
+
+    fn show<GMs>(&mut self, id: ToastId, toast: Toast<Ms>, orders: &mut impl Orders<Ms, GMs>) {
+        let mut entry = Entry {
+            id,
+            remaining: toast.timeout,
+            toast,
+            started_ms: 0.,
+            timer: None,
+        };
+        if !self.is_paused() {
+            start_timer(&mut entry, &self.to_msg, orders);
+        }
+        self.visible.push(entry);
+    }
+
+    fn remove<GMs>(&mut self, id: ToastId, orders: &mut impl Orders<Ms, GMs>) -> Option<Toast<Ms>> {
+        if let Some(index) = self.visible.iter().position(|entry| entry.id == id) {
+            // Dropping the entry cancels its timer.
+            let entry = self.visible.remove(index);
+            while self.visible.len() < self.opts.max_visible {
+                match self.queued.pop_front() {
+                    Some((id, toast)) => self.show(id, toast, orders),
+                    None => break,
+                }
+            }
+            return Some(entry.toast);
+        }
+        let index = self
+            .queued
+            .iter()
+            .position(|(queued_id, _)| *queued_id == id)?;
+        self.queued.remove(index).map(|(_, toast)| toast)
+    }
+
+    /// Pause or resume timers of visible toasts.
+    fn sync_timers<GMs>(&mut self, orders: &mut impl Orders<Ms, GMs>) {
+        let paused = self.is_paused();
+        let clock = Rc::clone(&orders.clone_app().cfg.clock);
+        for entry in &mut self.visible {
+            match (paused, entry.timer.is_some()) {
+                (true, true) => {
+                    entry.timer = None;
+                    let elapsed = elapsed(&*clock, entry.started_ms);
+                    entry.remaining = entry
+                        .remaining
+                        .map(|remaining| remaining.checked_sub(elapsed).unwrap_or_default());
+                }
+                (false, false) => start_timer(entry, &self.to_msg, orders),
+                _ => (),
+            }
+        }
+    }
+}
+
+fn start_timer<Ms: 'static, GMs>(
+    entry: &mut Entry<Ms>,
+    to_msg: &Rc<dyn Fn(ToastMsg) -> Ms>,
+    orders: &impl Orders<Ms, GMs>,
+) {
+    let remaining = match entry.remaining {
+        Some(remaining) => remaining,
+        None => return,
+    };
+    let app = orders.clone_app();
+    let (msg_mapper, to_msg, id) = (orders.msg_mapper(), Rc::clone(to_msg), entry.id);
+    let clock = Rc::clone(&app.cfg.clock);
+    entry.started_ms = clock.now_ms();
+    entry.timer = Some(clock.set_timeout(
+        time::duration_to_ms(remaining),
+        Box::new(move || app.update(msg_mapper(to_msg(ToastMsg::Expired(id))))),
+    ));
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn elapsed(clock: &dyn Clock, started_ms: f64) -> Duration {
+    Duration::from_millis((clock.now_ms() - started_ms).max(0.) as u64)
+}
+
+// ------ View ------
+
+/// Default view of the toast region. Render it once, at the root of your view.
+///
+/// Classes: `seed-toasts` (region), `seed-toast` + `seed-toast-<kind>` (toast),
+/// `seed-toast-action` and `seed-toast-dismiss` (buttons).
+pub fn toast_region_view<Ms: 'static>(manager: &ToastManager<Ms>) -> Node<Ms> {
+    section![
+        class!["seed-toasts"],
+        attrs! {
+            At::from("role") => "region",
+            At::from("aria-label") => "Notifications",
+            At::from("aria-live") => "polite",
+        },
+        region_listener(manager, "mouseenter", |_| Some(ToastMsg::Hovered(true))),
+        region_listener(manager, "mouseleave", |_| Some(ToastMsg::Hovered(false))),
+        region_listener(manager, "focusin", |_| Some(ToastMsg::Focused(true))),
+        // Ignore focus moving between toasts.
+        region_listener(manager, "focusout", |event| {
+            if focus_stays_in_region(event) {
+                None
+            } else {
+                Some(ToastMsg::Focused(false))
+            }
+        }),
+        manager
+            .visible()
+            .map(|(id, toast)| toast_view(manager, id, toast))
+            .collect::<Vec<_>>(),
+    ]
+}
+
+fn toast_view<Ms: 'static>(manager: &ToastManager<Ms>, id: ToastId, toast: &Toast<Ms>) -> Node<Ms> {
+    let role = if toast.kind == ToastKind::Error {
+        "alert"
+    } else {
+        "status"
+    };
+    let kind_class = format!("seed-toast-{}", toast.kind.as_str());
+    div![
+        class!["seed-toast", kind_class.as_str()],
+        attrs! {At::from("role") => role},
+        span![toast.text.as_str()],
+        toast
+            .action
+            .as_ref()
+            .map_or_else(Vec::new, |(label, _)| vec![button![
+                class!["seed-toast-action"],
+                click_listener(manager, ToastMsg::Action(id)),
+                label.as_str()
+            ]]),
+        button![
+            class!["seed-toast-dismiss"],
+            attrs! {At::from("aria-label") => "Dismiss"},
+            click_listener(manager, ToastMsg::Dismiss(id)),
+            "×"
+        ],
+    ]
+}
+
+fn region_listener<Ms: 'static>(
+    manager: &ToastManager<Ms>,
+    trigger: &str,
+    f: impl Fn(&web_sys::Event) -> Option<ToastMsg> + 'static,
+) -> Listener<Ms> {
+    let to_msg = Rc::clone(&manager.to_msg);
+    let handler = move |event: web_sys::Event| f(&event).map(&*to_msg);
+    Listener::new(trigger, Some(Box::new(handler)), Some(Category::Raw), None)
+}
+
+fn click_listener<Ms: 'static>(manager: &ToastManager<Ms>, msg: ToastMsg) -> Listener<Ms> {
+    region_listener(manager, "click", move |_| Some(msg))
+}
+
+fn focus_stays_in_region(event: &web_sys::Event) -> bool {
+    let region = event
+        .current_target()
+        .and_then(|target| target.dyn_into::<web_sys::Node>().ok());
+    let related = event
+        .dyn_ref::<web_sys::FocusEvent>()
+        .and_then(web_sys::FocusEvent::related_target)
+        .and_then(|target| target.dyn_into::<web_sys::Node>().ok());
+    match (region, related) {
+        (Some(region), Some(related)) => region.contains(Some(&related)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{builder::init::Init, App};
+    use crate::browser::{time::MockClock, util};
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    struct Model {
+        toasts: ToastManager<Msg>,
+        undone: bool,
+    }
+
+    #[derive(Clone, Debug)]
+    enum Msg {
+        Toasts(ToastMsg),
+        Send(&'static str, Option<Duration>),
+        SendFromChild(ChildMsg),
+    }
+
+    #[derive(Clone, Debug)]
+    enum ChildMsg {
+        Delete,
+        Undo,
+    }
+
+    fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+        match msg {
+            Msg::Toasts(msg) => model.toasts.update(msg, orders),
+            Msg::Send(text, timeout) => {
+                let toast = Toast::info(text);
+                orders.toast(match timeout {
+                    Some(timeout) => toast.timeout(timeout),
+                    None => toast.sticky(),
+                });
+            }
+            // The child's action message reaches the parent through the proxy.
+            Msg::SendFromChild(ChildMsg::Undo) => model.undone = true,
+            Msg::SendFromChild(msg) => child_update(msg, &mut orders.proxy(Msg::SendFromChild)),
+        }
+    }
+
+    fn child_update(msg: ChildMsg, orders: &mut impl Orders<ChildMsg>) {
+        if let ChildMsg::Delete = msg {
+            orders.toast(Toast::error("Deleted").action("Undo", ChildMsg::Undo));
+        }
+    }
+
+    fn create_app(
+        clock: MockClock,
+        opts: ToastOpts,
+    ) -> (App<Msg, Model, Node<Msg>>, web_sys::Element) {
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(
+            move |_, orders| {
+                Init::new(Model {
+                    toasts: ToastManager::new(orders, opts, Msg::Toasts),
+                    undone: false,
+                })
+            },
+            update,
+            |model| toast_region_view(&model.toasts),
+        )
+        .mount(mount_point.clone())
+        .clock(clock)
+        .finish()
+        .run();
+        (app, mount_point)
+    }
+
+    fn visible_texts(app: &App<Msg, Model, Node<Msg>>) -> Vec<String> {
+        app.data
+            .model
+            .borrow()
+            .as_ref()
+            .unwrap()
+            .toasts
+            .visible()
+            .map(|(_, toast)| toast.text.clone())
+            .collect()
+    }
+
+    #[wasm_bindgen_test]
+    fn queue_limit_and_auto_dismiss() {
+        let clock = MockClock::new();
+        let (app, mount_point) = create_app(
+            clock.clone(),
+            ToastOpts {
+                max_visible: 2,
+                max_queued: 1,
+            },
+        );
+
+        app.update(Msg::Send("a", Some(Duration::from_secs(1))));
+        app.update(Msg::Send("b", Some(Duration::from_secs(3))));
+        app.update(Msg::Send("c", None));
+        // `c` is dropped from the full queue.
+        app.update(Msg::Send("d", Some(Duration::from_secs(1))));
+        assert_eq!(visible_texts(&app), vec!["a", "b"]);
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(visible_texts(&app), vec!["b", "d"]);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(visible_texts(&app), vec!["b"]);
+        clock.advance(Duration::from_secs(1));
+        assert!(visible_texts(&app).is_empty());
+        assert_eq!(clock.pending_timers(), 0);
+        util::body().remove_child(&mount_point).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn hovering_pauses_timers() {
+        let clock = MockClock::new();
+        let (app, mount_point) = create_app(clock.clone(), ToastOpts::default());
+
+        app.update(Msg::Send("a", Some(Duration::from_secs(4))));
+        clock.advance(Duration::from_secs(3));
+        app.update(Msg::Toasts(ToastMsg::Hovered(true)));
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(visible_texts(&app), vec!["a"]);
+
+        // Focus keeps it paused after the pointer leaves.
+        app.update(Msg::Toasts(ToastMsg::Focused(true)));
+        app.update(Msg::Toasts(ToastMsg::Hovered(false)));
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(visible_texts(&app), vec!["a"]);
+
+        // The remaining second.
+        app.update(Msg::Toasts(ToastMsg::Focused(false)));
+        clock.advance(Duration::from_millis(999));
+        assert_eq!(visible_texts(&app), vec!["a"]);
+        clock.advance(Duration::from_millis(1));
+        assert!(visible_texts(&app).is_empty());
+        util::body().remove_child(&mount_point).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn action_from_child_module() {
+        let (app, mount_point) = create_app(MockClock::new(), ToastOpts::default());
+
+        app.update(Msg::SendFromChild(ChildMsg::Delete));
+        app.flush_render();
+        let toast = mount_point.query_selector(".seed-toast").unwrap().unwrap();
+        assert_eq!(toast.get_attribute("role").unwrap(), "alert");
+        toast
+            .query_selector(".seed-toast-action")
+            .unwrap()
+            .unwrap()
+            .dyn_into::<web_sys::HtmlElement>()
+            .unwrap()
+            .click();
+
+        assert!(app.data.model.borrow().as_ref().unwrap().undone);
+        assert!(visible_texts(&app).is_empty());
+        util::body().remove_child(&mount_point).unwrap();
+    }
+}
@@ -1,10 +1,40 @@
-use super::OrdersContainer;
-use crate::browser::Url;
-use crate::virtual_dom::Listener;
+use super::{FrameworkError, OrdersContainer, RenderInfo};
+use crate::browser::{service::routing::FormSubmission, Capabilities, Url};
+use crate::virtual_dom::{Listener, Node};
 
 pub type UpdateFn<Ms, Mdl, ElC, GMs> = fn(Ms, &mut Mdl, &mut OrdersContainer<Ms, Mdl, ElC, GMs>);
+/// See `AppBuilder::update_middleware`.
+pub type UpdateMiddlewareFn<Ms, Mdl, ElC, GMs> = Box<
+    dyn Fn(
+        Ms,
+        &mut Mdl,
+        &mut OrdersContainer<Ms, Mdl, ElC, GMs>,
+        UpdateNext<'_, Ms, Mdl, ElC, GMs>,
+    ),
+>;
+/// Calls the next middleware or `update` - see `AppBuilder::update_middleware`.
+pub type UpdateNext<'a, Ms, Mdl, ElC, GMs> =
+    &'a dyn Fn(Ms, &mut Mdl, &mut OrdersContainer<Ms, Mdl, ElC, GMs>);
 pub type SinkFn<Ms, Mdl, ElC, GMs> = fn(GMs, &mut Mdl, &mut OrdersContainer<Ms, Mdl, ElC, GMs>);
 pub type ViewFn<Mdl, ElC> = fn(&Mdl) -> ElC;
 pub type RoutesFn<Ms> = fn(Url) -> Option<Ms>;
+pub type NotFoundFn<Ms> = fn(Url) -> Ms;
+pub type InterceptFormsFn<Ms> = fn(FormSubmission) -> Option<Ms>;
 pub type WindowEventsFn<Ms, Mdl> = fn(&Mdl) -> Vec<Listener<Ms>>;
-pub type MsgListeners<Ms> = Vec<Box<dyn Fn(&Ms)>>;
+pub type BeforeUnmountFn<Mdl> = Box<dyn FnOnce(&Mdl)>;
+/// See `AppBuilder::view_error_handler`.
+pub type ViewErrorHandlerFn<Ms> = Box<dyn Fn(&str) -> Node<Ms>>;
+/// See `AppBuilder::perf_msg_name`.
+pub type PerfMsgNameFn<Ms> = fn(&Ms) -> String;
+/// See `AppBuilder::capabilities_msg`.
+pub type CapabilitiesMsgFn<Ms> = fn(&Capabilities) -> Ms;
+/// See `AppBuilder::framework_errors`.
+pub type FrameworkErrorsFn<Ms> = fn(FrameworkError) -> Option<Ms>;
+/// Serializes messages for the devtools message log and deserializes dispatched messages.
+/// See `AppBuilder::devtools_codec`.
+pub struct MsgCodec<Ms> {
+    pub serialize: fn(&Ms) -> Option<String>,
+    pub deserialize: fn(&str) -> Result<Ms, String>,
+}
+/// See `orders.after_next_render`.
+pub type AfterNextRenderCallback<Ms> = Box<dyn FnOnce(RenderInfo) -> Option<Ms>>;
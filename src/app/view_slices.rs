@@ -0,0 +1,167 @@
+//! Views split into regions which receive only their slice of the model.
+//! See `AppBuilder::view_with_slices`.
+//!
+//! Nodes of regions are rendered behind the nodes of the root `view`, in the order
+//! of registration. A region is skipped when its slice is equal to the slice of the previous
+//! render - its `view` isn't called and its nodes (incl. their DOM and listeners) are reused.
+//!
+//! Region views have to depend only on their slice - e.g. a region reading
+//! `locale::current()` isn't re-rendered when only the locale changes.
+
+use crate::virtual_dom::{text_normalization, El, Node, Tag, TextNormalization, View};
+use std::{cell::RefCell, marker::PhantomData, mem};
+
+// ------ Region ------
+
+trait Region<Ms, Mdl> {
+    /// Remember the model's slice.
+    /// Returns `true` when it's equal to the slice remembered in the previous render.
+    fn update_slice(&self, model: &Mdl) -> bool;
+
+    fn view(&self, model: &Mdl) -> Vec<Node<Ms>>;
+
+    fn has_cached_slice(&self) -> bool;
+}
+
+struct SliceRegion<Mdl, Slice, P, V> {
+    project: P,
+    view: fn(&Slice) -> V,
+    last_slice: RefCell<Option<Slice>>,
+    phantom: PhantomData<Mdl>,
+}
+
+impl<Ms, Mdl, Slice, P, V> Region<Ms, Mdl> for SliceRegion<Mdl, Slice, P, V>
+where
+    Ms: 'static,
+    Slice: PartialEq + Clone,
+    P: Fn(&Mdl) -> &Slice,
+    V: View<Ms>,
+{
+    fn update_slice(&self, model: &Mdl) -> bool {
+        let slice = (self.project)(model);
+        let mut last_slice = self.last_slice.borrow_mut();
+        if last_slice.as_ref() == Some(slice) {
+            return true;
+        }
+        last_slice.replace(slice.clone());
+        false
+    }
+
+    fn view(&self, model: &Mdl) -> Vec<Node<Ms>> {
+        (self.view)((self.project)(model)).els()
+    }
+
+    fn has_cached_slice(&self) -> bool {
+        self.last_slice.borrow().is_some()
+    }
+}
+
+// ------ ViewSlices ------
+
+/// Regions registered by `AppBuilder::view_with_slices`.
+pub struct ViewSlices<Ms, Mdl> {
+    regions: Vec<Box<dyn Region<Ms, Mdl>>>,
+    /// Numbers of top-level nodes rendered by regions in the previous render.
+    rendered_lens: RefCell<Option<Vec<usize>>>,
+}
+
+impl<Ms: 'static, Mdl: 'static> ViewSlices<Ms, Mdl> {
+    pub(crate) fn new() -> Self {
+        Self {
+            regions: Vec::new(),
+            rendered_lens: RefCell::new(None),
+        }
+    }
+
+    /// Number of regions with a slice remembered from the previous render.
+    pub(crate) fn cached_slices(&self) -> usize {
+        self.regions
+            .iter()
+            .filter(|region| region.has_cached_slice())
+            .count()
+    }
+
+    /// Register a region - `view` receives the slice returned by `project`.
+    /// The region is rendered again only when the slice has been changed.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///App::builder(update, view)
+    ///    .view_with_slices(|slices| {
+    ///        slices
+    ///            .region(|model| &model.cart, cart::view)
+    ///            .region(|model| &model.footer, footer::view)
+    ///    })
+    /// ```
+    pub fn region<Slice, V>(
+        mut self,
+        project: impl Fn(&Mdl) -> &Slice + 'static,
+        view: fn(&Slice) -> V,
+    ) -> Self
+    where
+        Slice: PartialEq + Clone + 'static,
+        V: View<Ms> + 'static,
+    {
+        self.regions.push(Box::new(SliceRegion {
+            project,
+            view,
+            last_slice: RefCell::new(None),
+            phantom: PhantomData,
+        }));
+        self
+    }
+
+    /// Render nodes of all regions. Nodes of unchanged regions are moved from the end
+    /// of `old` (the previous render) and replaced by their copies without listeners,
+    /// so the patching of the copies against the moved nodes doesn't touch the DOM.
+    ///
+    /// `root_len` is the number of nodes rendered by the root `view`. Nodes are moved
+    /// only when they stay at their old positions - otherwise they would be patched
+    /// against other nodes, so the region is rendered again.
+    pub(crate) fn render(
+        &self,
+        model: &Mdl,
+        old: &mut El<Ms>,
+        root_len: usize,
+        mode: TextNormalization,
+    ) -> Vec<Node<Ms>> {
+        let rendered_lens = self.rendered_lens.borrow_mut().take();
+        // Regions are rendered behind the root `view`'s nodes.
+        let mut old_index = rendered_lens
+            .as_ref()
+            .and_then(|lens| old.children.len().checked_sub(lens.iter().sum()));
+
+        let mut nodes = Vec::new();
+        let mut lens = Vec::with_capacity(self.regions.len());
+        for (region_index, region) in self.regions.iter().enumerate() {
+            let is_unchanged = region.update_slice(model);
+            let old_range = old_index.and_then(|index| {
+                let len = rendered_lens.as_ref()?.get(region_index)?;
+                Some(index..index + len)
+            });
+            old_index = old_range.as_ref().map(|range| range.end);
+
+            let region_nodes = match old_range {
+                Some(old_range) if is_unchanged && old_range.start == root_len + nodes.len() => old
+                    .children[old_range]
+                    .iter_mut()
+                    .map(|node| {
+                        let copy = node.clone();
+                        mem::replace(node, copy)
+                    })
+                    .collect::<Vec<_>>(),
+                _ => {
+                    let mut root = El::empty(Tag::Placeholder);
+                    root.children = region.view(model);
+                    text_normalization::normalize_el(&mut root, mode);
+                    root.children
+                }
+            };
+            lens.push(region_nodes.len());
+            nodes.extend(region_nodes);
+        }
+        self.rendered_lens.replace(Some(lens));
+        nodes
+    }
+}
@@ -1,8 +1,17 @@
+pub mod capabilities;
 pub mod dom;
+pub mod input_modality;
 pub mod next_tick;
 pub mod service;
+pub mod share;
+pub mod time;
 pub mod url;
 pub mod util;
+pub mod visibility;
 
+pub use capabilities::{Capabilities, Capability, Unsupported};
+pub use input_modality::Modality;
 pub use next_tick::NextTick;
-pub use url::Url;
+pub use share::{can_share, ShareData, ShareError};
+pub use url::{Url, UrlError};
+pub use visibility::{PausePolicy, VisibilityResumed};
@@ -0,0 +1,230 @@
+//! Detection of browser APIs used by Seed and its services.
+//!
+//! Capabilities are probed lazily - each API is checked only once, when it's asked for the first time.
+//!
+//! Services check the report they are given and return `Unsupported` instead of throwing -
+//! e.g. `fetch` resolves to `RequestError::Unsupported`. `Orders` methods pass the app's report,
+//! so replace it by `AppBuilder::capabilities` to simulate a degraded environment in tests.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//!fn after_mount(_: Url, orders: &mut impl Orders<Msg>) -> AfterMount<Model> {
+//!    let supported = orders
+//!        .capabilities()
+//!        .assert_required(&[Capability::Fetch, Capability::History]);
+//!    AfterMount::new(Model { browser_not_supported: supported.is_err() })
+//!}
+//! ```
+
+use super::util::{self, get_property};
+use std::{cell::Cell, error, fmt};
+
+// ------ Capability ------
+
+/// Browser API which may be missing in older browsers or WebViews.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Capability {
+    Fetch,
+    AbortController,
+    History,
+    LocalStorage,
+    Performance,
+    ResizeObserver,
+    IntersectionObserver,
+    Clipboard,
+    BroadcastChannel,
+    /// `navigator.storage` - persistent storage and quota estimates.
+    StorageManager,
+    /// `navigator.share` - the native share sheet.
+    Share,
+}
+
+impl Capability {
+    /// All known capabilities.
+    pub const ALL: [Capability; 11] = [
+        Capability::Fetch,
+        Capability::AbortController,
+        Capability::History,
+        Capability::LocalStorage,
+        Capability::Performance,
+        Capability::ResizeObserver,
+        Capability::IntersectionObserver,
+        Capability::Clipboard,
+        Capability::BroadcastChannel,
+        Capability::StorageManager,
+        Capability::Share,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Capability::Fetch => "fetch",
+            Capability::AbortController => "AbortController",
+            Capability::History => "history",
+            Capability::LocalStorage => "localStorage",
+            Capability::Performance => "performance",
+            Capability::ResizeObserver => "ResizeObserver",
+            Capability::IntersectionObserver => "IntersectionObserver",
+            Capability::Clipboard => "clipboard",
+            Capability::BroadcastChannel => "BroadcastChannel",
+            Capability::StorageManager => "storage",
+            Capability::Share => "share",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Check if the API exists in the current environment.
+    fn probe(self) -> bool {
+        let window = util::window();
+        match self {
+            Capability::Clipboard => get_property(&window, "navigator")
+                .and_then(|navigator| get_property(&navigator, "clipboard"))
+                .is_some(),
+            Capability::StorageManager | Capability::Share => get_property(&window, "navigator")
+                .and_then(|navigator| get_property(&navigator, self.as_str()))
+                .is_some(),
+            // Accessing `localStorage` may throw (e.g. in Safari's private mode).
+            Capability::LocalStorage => window.local_storage().ok().flatten().is_some(),
+            _ => get_property(&window, self.as_str()).is_some(),
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// ------ Unsupported ------
+
+/// Error returned when required browser APIs are missing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unsupported {
+    pub missing: Vec<Capability>,
+}
+
+impl fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let missing = self
+            .missing
+            .iter()
+            .map(|capability| capability.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "Unsupported browser APIs: {}", missing)
+    }
+}
+
+impl error::Error for Unsupported {}
+
+// ------ Capabilities ------
+
+/// Report about available browser APIs.
+///
+/// You can get app's instance by `orders.capabilities()`.
+/// Use `Capabilities::mock` in tests to simulate a degraded environment.
+#[derive(Debug, Default, Clone)]
+pub struct Capabilities {
+    cache: [Cell<Option<bool>>; 11],
+}
+
+impl Capabilities {
+    /// Create a new report. Nothing is probed until a capability is requested.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a report with predefined values.
+    /// Capabilities not contained in `values` are probed as usual.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///let capabilities = Capabilities::mock(&[(Capability::ResizeObserver, false)]);
+    ///assert!(!capabilities.has(Capability::ResizeObserver));
+    /// ```
+    pub fn mock(values: &[(Capability, bool)]) -> Self {
+        let capabilities = Self::new();
+        for (capability, available) in values {
+            capabilities.cache[capability.index()].set(Some(*available));
+        }
+        capabilities
+    }
+
+    /// Is the given API available?
+    pub fn has(&self, capability: Capability) -> bool {
+        let cached = &self.cache[capability.index()];
+        cached.get().unwrap_or_else(|| {
+            let available = capability.probe();
+            cached.set(Some(available));
+            available
+        })
+    }
+
+    /// Return `Err(Unsupported)` if the given API isn't available.
+    pub fn require(&self, capability: Capability) -> Result<(), Unsupported> {
+        self.assert_required(&[capability])
+    }
+
+    /// Return `Err(Unsupported)` with all missing APIs if any of `required` isn't available.
+    pub fn assert_required(&self, required: &[Capability]) -> Result<(), Unsupported> {
+        let missing = required
+            .iter()
+            .copied()
+            .filter(|capability| !self.has(*capability))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(Unsupported { missing })
+        }
+    }
+
+    /// Return all missing APIs. It probes all not-yet-probed capabilities.
+    pub fn missing(&self) -> Vec<Capability> {
+        Capability::ALL
+            .iter()
+            .copied()
+            .filter(|capability| !self.has(*capability))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn mocked_capabilities() {
+        let capabilities = Capabilities::mock(&[
+            (Capability::Fetch, true),
+            (Capability::ResizeObserver, false),
+            (Capability::Clipboard, false),
+        ]);
+        assert!(capabilities.has(Capability::Fetch));
+        assert_eq!(
+            capabilities.assert_required(&[
+                Capability::Fetch,
+                Capability::ResizeObserver,
+                Capability::Clipboard
+            ]),
+            Err(Unsupported {
+                missing: vec![Capability::ResizeObserver, Capability::Clipboard]
+            })
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn probe_existing_capabilities() {
+        let capabilities = Capabilities::new();
+        assert!(capabilities.require(Capability::History).is_ok());
+        assert!(capabilities.has(Capability::Performance));
+    }
+}
@@ -2,10 +2,19 @@
 //! These are the types used internally by our virtual dom.
 
 pub mod cast;
+pub mod content_editable;
+pub mod csp;
 pub mod css_units;
+pub mod dialog;
 pub mod event_handler;
+pub mod flip;
+pub mod global_events;
 pub mod lifecycle_hooks;
 pub mod namespace;
+pub mod scheduler;
+pub mod scroll_progress;
+pub mod typed_input;
+pub mod viewport;
 pub mod virtual_dom_bridge;
 
 pub use lifecycle_hooks::LifecycleHooks;
@@ -205,6 +214,21 @@ pub mod tests {
         assert_eq!(expected, get_node_attrs(&node));
     }
 
+    /// Tests that invalid attribute names are skipped instead of panicking mid-patch
+    #[wasm_bindgen_test]
+    pub fn hostile_attr_names() {
+        let hostile = ["a b", "a\"b", "<script>", "onclick=\"alert(1)\"", "1x", ""];
+        let mut e = div![attrs! {At::Id => "ok"}];
+        for name in &hostile {
+            e.add_attr(name.to_string(), "x");
+        }
+        let node = el_to_websys(e);
+
+        let mut expected = IndexMap::new();
+        expected.insert("id".to_string(), "ok".to_string());
+        assert_eq!(expected, get_node_attrs(&node));
+    }
+
     /// Tests that method `replace_text` removes all text nodes and then adds a new one
     #[wasm_bindgen_test]
     pub fn replace_text() {
@@ -0,0 +1,257 @@
+//! Controlled `contenteditable` elements. See `content_editable_ev`.
+//!
+//! Elements with the `contenteditable` attribute are patched like controlled inputs:
+//! - While the element is focused and its rendered text equals the live text, its children
+//!   aren't patched - the user's edits (incl. their structure, e.g. `<br>`s inserted by
+//!   the browser) are kept and adopted as the element's vdom children.
+//! - When the rendered text differs (a programmatic update), the children are patched
+//!   and the caret is restored at the same text offset (or at the end of the shorter text).
+//!
+//! Keep children of editable elements plain - listeners of children aren't attached
+//! while their element is being edited.
+
+use crate::browser::util;
+use crate::virtual_dom::{At, AtValue, Category, El, Listener, Node, Tag};
+use wasm_bindgen::JsCast;
+
+/// Content of an edited `contenteditable` element - see `content_editable_ev`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EditableContent {
+    /// Rendered text (`innerText`) - line breaks are `\n`, non-breaking spaces are plain spaces.
+    pub text: String,
+    /// HTML with only basic formatting elements (`b`, `i`, `em`, `strong`, `br`, ..)
+    /// without any attributes. Text is escaped, other elements are replaced by their children
+    /// and `script`-like elements are dropped - it's safe to render the HTML again.
+    pub sanitized_html: String,
+}
+
+impl EditableContent {
+    fn from_element(element: &web_sys::HtmlElement) -> Self {
+        Self {
+            text: editable_text(element),
+            sanitized_html: sanitized_html(element),
+        }
+    }
+}
+
+fn editable_text(element: &web_sys::HtmlElement) -> String {
+    let mut text = element.inner_text().replace('\u{a0}', " ");
+    // Browsers append a line break for the trailing `<br>`.
+    if text.ends_with('\n') {
+        text.pop();
+    }
+    text
+}
+
+/// Handle edits of the `contenteditable` element (the `input` event).
+///
+/// # Example
+///
+/// ```rust,no_run
+///h1![
+///    attrs! {At::ContentEditable => true.as_at_value()},
+///    content_editable_ev(|content| Msg::TitleEdited(content.text)),
+///    model.title,
+///]
+/// ```
+pub fn content_editable_ev<Ms>(
+    handler: impl FnOnce(EditableContent) -> Ms + 'static + Clone,
+) -> Listener<Ms> {
+    let closure = move |event: web_sys::Event| {
+        let content = event
+            .current_target()
+            .as_ref()
+            .and_then(|target| target.dyn_ref::<web_sys::HtmlElement>())
+            .map(EditableContent::from_element);
+        match content {
+            Some(content) => Some((handler.clone())(content)),
+            None => {
+                crate::error("`content_editable_ev` has to be attached to an HTML element");
+                None
+            }
+        }
+    };
+    Listener::new(
+        "input",
+        Some(Box::new(closure)),
+        Some(Category::Input),
+        None,
+    )
+}
+
+// ------ Sanitization ------
+
+const ALLOWED_TAGS: &[&str] = &[
+    "b", "strong", "i", "em", "u", "s", "sub", "sup", "code", "br", "p", "div",
+];
+const DROPPED_TAGS: &[&str] = &[
+    "script", "style", "template", "iframe", "object", "embed", "svg", "math",
+];
+
+fn sanitized_html(element: &web_sys::HtmlElement) -> String {
+    let mut root = El::<()>::empty(Tag::Placeholder);
+    root.children = sanitized_children(element);
+    root.to_string()
+}
+
+fn sanitized_children(parent: &web_sys::Node) -> Vec<Node<()>> {
+    let child_nodes = parent.child_nodes();
+    let mut children = Vec::new();
+    for child in (0..child_nodes.length()).filter_map(|index| child_nodes.get(index)) {
+        match child.node_type() {
+            web_sys::Node::TEXT_NODE => {
+                let text = child.text_content().unwrap_or_default();
+                children.push(Node::new_text(text.replace('\u{a0}', " ")));
+            }
+            web_sys::Node::ELEMENT_NODE => {
+                let tag = child.node_name().to_lowercase();
+                if ALLOWED_TAGS.contains(&tag.as_str()) {
+                    let mut el = El::empty(Tag::from(tag));
+                    el.children = sanitized_children(&child);
+                    children.push(Node::Element(el));
+                } else if !DROPPED_TAGS.contains(&tag.as_str()) {
+                    children.extend(sanitized_children(&child));
+                }
+            }
+            _ => (),
+        }
+    }
+    children
+}
+
+// ------ Patching ------
+
+/// The element is editable by the user and it's focused.
+pub(crate) fn is_focused<Ms>(el: &El<Ms>, node: &web_sys::Node) -> bool {
+    let editable = match el.attrs.vals.get(&At::ContentEditable) {
+        Some(AtValue::Some(value)) => value != "false",
+        Some(AtValue::None) => true,
+        _ => false,
+    };
+    editable
+        && util::document()
+            .active_element()
+            .map_or(false, |active| node.is_same_node(Some(active.as_ref())))
+}
+
+/// The text of the vdom `children` equals the live text of `node` -
+/// either its `textContent` or `EditableContent::text`.
+pub(crate) fn text_equals<Ms>(children: &[Node<Ms>], node: &web_sys::Node) -> bool {
+    fn push_text<Ms>(children: &[Node<Ms>], text: &mut String) {
+        for child in children {
+            match child {
+                Node::Text(child_text) => text.push_str(&child_text.text),
+                Node::Element(child_el) => push_text(&child_el.children, text),
+                Node::Empty => (),
+            }
+        }
+    }
+    let mut text = String::new();
+    push_text(children, &mut text);
+    let text = text.replace('\u{a0}', " ");
+    let live_text = node.text_content().unwrap_or_default();
+    text == live_text.replace('\u{a0}', " ")
+        || node
+            .dyn_ref::<web_sys::HtmlElement>()
+            .map_or(false, |element| text == editable_text(element))
+}
+
+/// Caret position in `node` as the number of UTF-16 code units of the text before the caret.
+pub(crate) fn caret_offset(node: &web_sys::Node) -> Option<u32> {
+    let selection = util::window().get_selection().ok()??;
+    if selection.range_count() == 0 {
+        return None;
+    }
+    let range = selection.get_range_at(0).ok()?;
+    let container = range.start_container().ok()?;
+    if !node.contains(Some(&container)) {
+        return None;
+    }
+    let prefix = util::document().create_range().ok()?;
+    prefix.select_node_contents(node).ok()?;
+    prefix
+        .set_end(&container, range.start_offset().ok()?)
+        .ok()?;
+    #[allow(clippy::cast_possible_truncation)]
+    Some(String::from(prefix.to_string()).encode_utf16().count() as u32)
+}
+
+/// Collapse the selection at `offset` in `node` (or at the end of the text if it's shorter).
+pub(crate) fn restore_caret(node: &web_sys::Node, offset: u32) {
+    fn find(node: &web_sys::Node, offset: &mut u32) -> Option<(web_sys::Node, u32)> {
+        if node.node_type() == web_sys::Node::TEXT_NODE {
+            #[allow(clippy::cast_possible_truncation)]
+            let len = node
+                .text_content()
+                .unwrap_or_default()
+                .encode_utf16()
+                .count() as u32;
+            if *offset <= len {
+                return Some((node.clone(), *offset));
+            }
+            *offset -= len;
+            return None;
+        }
+        let child_nodes = node.child_nodes();
+        (0..child_nodes.length())
+            .filter_map(|index| child_nodes.get(index))
+            .find_map(|child| find(&child, offset))
+    }
+
+    let mut remaining = offset;
+    let (container, container_offset) =
+        find(node, &mut remaining).unwrap_or_else(|| (node.clone(), node.child_nodes().length()));
+    if let Ok(Some(selection)) = util::window().get_selection() {
+        if selection
+            .collapse_with_offset(Some(&container), container_offset)
+            .is_err()
+        {
+            crate::error("Problem restoring the caret in a contenteditable element");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn editable(html: &str) -> web_sys::HtmlElement {
+        let element = util::document()
+            .create_element("div")
+            .unwrap()
+            .unchecked_into::<web_sys::HtmlElement>();
+        element.set_attribute("contenteditable", "").unwrap();
+        element.set_inner_html(html);
+        util::body().append_child(&element).unwrap();
+        element
+    }
+
+    #[wasm_bindgen_test]
+    fn content_is_sanitized() {
+        let element = editable(
+            "<b onclick=\"alert(1)\">bold</b>&nbsp;<a href=\"javascript:x\">link</a>\
+             <script>alert(2)</script><span style=\"color: red\">&lt;tag&gt;</span><br>",
+        );
+        let content = EditableContent::from_element(&element);
+        assert_eq!(content.text, "bold link<tag>");
+        assert_eq!(content.sanitized_html, "<b>bold</b> link&lt;tag&gt;<br>");
+        util::body().remove_child(&element).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn caret_is_restored() {
+        let element = editable("Hello <b>dear</b> reader");
+        element.focus().unwrap();
+        restore_caret(&element, 8);
+        assert_eq!(caret_offset(&element), Some(8));
+
+        // The text is shorter - the caret is moved to its end.
+        element.set_inner_html("Hi");
+        restore_caret(&element, 8);
+        assert_eq!(caret_offset(&element), Some(2));
+        util::body().remove_child(&element).unwrap();
+    }
+}
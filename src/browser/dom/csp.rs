@@ -0,0 +1,140 @@
+//! Content Security Policy support. See `AppBuilder::csp_nonce`.
+//!
+//! Every app has its own nonce - it's the one passed to `AppBuilder::csp_nonce`
+//! or the nonce of the first `<script nonce="..">` in the document. Get it from
+//! `AppCfg::csp_nonce` when you create `<script>` or `<style>` elements outside of the vdom.
+//!
+//! Framework operations and their compatibility with a strict policy
+//! (`script-src 'nonce-..'; style-src 'nonce-..'`):
+//! - `<style>` and `<script>` elements created from the vdom (`style![..]`, `script![..]`)
+//!   get the `nonce` attribute.
+//! - Inline styles (`style!`, `St`) are written by `setAttribute("style", ..)` -
+//!   they are controlled by `style-src-attr` (`style-src` when it's missing).
+//! - Event handlers are attached by `addEventListener` - they don't need `'unsafe-inline'`.
+//!   Inline handler attributes (`onclick="..."`) and `javascript:` URLs are blocked -
+//!   debug builds warn when they are rendered.
+//! - Scripts in raw HTML (`raw!`, `El::from_html`) are never executed.
+//! - The framework doesn't evaluate strings as code (`eval`, `new Function`) -
+//!   `'unsafe-eval'` isn't needed.
+//! - Tags written to `<head>` by `Orders::set_page_meta` are only `<meta>` and `<link>`.
+
+use crate::browser::util;
+use crate::virtual_dom::{At, El, Node, Tag};
+#[cfg(debug_assertions)]
+use std::{cell::RefCell, collections::HashSet};
+use wasm_bindgen::{JsCast, JsValue};
+
+#[cfg(debug_assertions)]
+thread_local! {
+    // Names of attributes which have been already reported by `check_attr`.
+    static WARNED_ATTRS: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
+/// Nonce of the first script with it in the document.
+pub(crate) fn detect_nonce() -> Option<String> {
+    let script = util::document()
+        .query_selector("script[nonce]")
+        .ok()
+        .and_then(|script| script)?;
+    // Browsers hide the attribute's value, but the `nonce` property keeps it.
+    let nonce = js_sys::Reflect::get(&script, &JsValue::from_str("nonce"))
+        .ok()
+        .and_then(|nonce| nonce.as_string())
+        .or_else(|| script.get_attribute("nonce"))?;
+    if nonce.is_empty() {
+        None
+    } else {
+        Some(nonce)
+    }
+}
+
+/// Set the app's nonce on new `<script>` and `<style>` elements in `el` and its children.
+/// Call it before the new elements are attached to the document.
+pub(crate) fn apply_nonces<Ms>(el: &El<Ms>, nonce: Option<&str>) {
+    let nonce = match nonce {
+        Some(nonce) => nonce,
+        None => return,
+    };
+    if el.tag == Tag::Script || el.tag == Tag::Style {
+        if let Some(element) = el
+            .node_ws
+            .as_ref()
+            .and_then(|node| node.dyn_ref::<web_sys::Element>())
+        {
+            if element.set_attribute("nonce", nonce).is_err() {
+                crate::error("Problem setting the CSP nonce");
+            }
+        }
+    }
+    for child in &el.children {
+        if let Node::Element(child) = child {
+            apply_nonces(child, Some(nonce));
+        }
+    }
+}
+
+/// Warn about attributes blocked by a strict policy - inline event handlers
+/// and `javascript:` URLs. Every attribute name is reported only once. Only in debug builds.
+#[cfg_attr(not(debug_assertions), allow(unused_variables))]
+pub(crate) fn check_attr(element: &web_sys::Node, at: &At, value: &str) {
+    #[cfg(debug_assertions)]
+    {
+        let name = at.as_str().to_lowercase();
+        if is_blocked(element, &name, value)
+            && WARNED_ATTRS.with(|warned| warned.borrow_mut().insert(name.clone()))
+        {
+            web_sys::console::warn_1(
+                &format!(
+                    "The attribute `{}=\"{}\"` is blocked by Content Security Policies \
+                     without 'unsafe-inline' - use event handlers instead.",
+                    name, value
+                )
+                .into(),
+            );
+        }
+    }
+}
+
+#[cfg(debug_assertions)]
+fn is_blocked(element: &web_sys::Node, name: &str, value: &str) -> bool {
+    // Attributes with URLs - `javascript:` URLs in them are blocked by the policy.
+    const URL_ATTRS: &[&str] = &["href", "src", "action", "formaction", "xlink:href"];
+
+    // Only attributes backed by the element's `on<event>` handler properties are executed
+    // (e.g. `onclick`, but not `one` or `online-status`).
+    let is_inline_handler = name.starts_with("on")
+        && js_sys::Reflect::has(element, &JsValue::from_str(name)).unwrap_or_default();
+    let is_javascript_url =
+        URL_ATTRS.contains(&name) && value.trim_start().to_lowercase().starts_with("javascript:");
+    is_inline_handler || is_javascript_url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn nonce_is_detected() {
+        assert_eq!(detect_nonce(), None);
+
+        let script = util::document().create_element("script").unwrap();
+        script.set_attribute("nonce", "r4nd0m").unwrap();
+        util::body().append_child(&script).unwrap();
+        assert_eq!(detect_nonce(), Some("r4nd0m".to_owned()));
+        script.remove();
+    }
+
+    #[cfg(debug_assertions)]
+    #[wasm_bindgen_test]
+    fn only_inline_handlers_and_javascript_urls_are_blocked() {
+        let element = util::document().create_element("a").unwrap();
+        assert!(is_blocked(&element, "onclick", "alert(1)"));
+        assert!(is_blocked(&element, "href", " JavaScript:alert(1)"));
+        assert!(!is_blocked(&element, "one", "two"));
+        assert!(!is_blocked(&element, "online-status", "on"));
+        assert!(!is_blocked(&element, "href", "/javascript:"));
+    }
+}
@@ -0,0 +1,209 @@
+//! Declarative `<dialog>` and popover control. See `dialog_open` and `popover_open`.
+//!
+//! The `open` attribute isn't equivalent to `showModal()` - modal dialogs need the backdrop,
+//! the focus trap and the top layer, so Seed calls the methods for you. Marked elements are synced
+//! in the write phase after each render (see `scheduler::dom_write`) - `showModal()` / `close()`
+//! (or `showPopover()` / `hidePopover()`) are called only when the element's state differs from
+//! the rendered one, so multiple toggles between two renders resolve to the final state.
+//!
+//! Users close dialogs by themselves too (`Esc`, `<form method="dialog">`) and popovers
+//! are light-dismissed - update your model from `close_ev` / `popover_toggle_ev`, otherwise
+//! the next render opens the element again.
+//!
+//! Browsers without the APIs get only the `open` attribute (and a console warning) -
+//! style `dialog[open]` / `[popover][open]` as a fallback.
+
+use super::scheduler;
+use crate::browser::util::get_property;
+use crate::virtual_dom::{Category, El, Listener, Node};
+use std::cell::Cell;
+use wasm_bindgen::JsCast;
+
+/// Rendered state of a `<dialog>` or of an element with the `popover` attribute.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OpenState {
+    /// Modal dialog - see `dialog_open`.
+    Dialog(bool),
+    /// Popover - see `popover_open`.
+    Popover(bool),
+}
+
+/// Show the `<dialog>` as a modal (`showModal()`) while `open` is `true`, close it otherwise.
+///
+/// # Example
+///
+/// ```rust,no_run
+///dialog![
+///    dialog_open(model.confirm_delete),
+///    close_ev(Msg::ConfirmClosed),
+///    form![
+///        attrs! {At::Method => "dialog"},
+///        button![attrs! {At::Value => "delete"}, "Delete"],
+///        button![attrs! {At::Value => "cancel"}, "Cancel"],
+///    ],
+///]
+/// ```
+pub fn dialog_open(open: bool) -> OpenState {
+    OpenState::Dialog(open)
+}
+
+/// Show the element with the `popover` attribute (`showPopover()`) while `open` is `true`,
+/// hide it otherwise. Buttons with `popovertarget` keep working - sync your model
+/// by `popover_toggle_ev`.
+///
+/// # Example
+///
+/// ```rust,no_run
+///div![
+///    attrs! {At::Id => "menu", At::from("popover") => "auto"},
+///    popover_open(model.menu_open),
+///    popover_toggle_ev(Msg::MenuToggled),
+///    "Menu items",
+///]
+/// ```
+pub fn popover_open(open: bool) -> OpenState {
+    OpenState::Popover(open)
+}
+
+/// Handle the dialog's `close` event. `handler` receives `returnValue` - e.g. `value`
+/// of the button which submitted `<form method="dialog">`; `None` when it's empty.
+pub fn close_ev<Ms>(handler: impl FnOnce(Option<String>) -> Ms + 'static + Clone) -> Listener<Ms> {
+    let closure = move |event: web_sys::Event| {
+        let return_value = event
+            .current_target()
+            .and_then(|target| get_property(&target, "returnValue"))
+            .and_then(|value| value.as_string())
+            .filter(|value| !value.is_empty());
+        Some((handler.clone())(return_value))
+    };
+    Listener::new("close", Some(Box::new(closure)), Some(Category::Raw), None)
+}
+
+/// Handle the popover's `toggle` event. `handler` receives `true` when the popover has been shown.
+pub fn popover_toggle_ev<Ms>(handler: impl FnOnce(bool) -> Ms + 'static + Clone) -> Listener<Ms> {
+    let closure = move |event: web_sys::Event| {
+        let open = get_property(&event, "newState")
+            .and_then(|state| state.as_string())
+            .map_or(false, |state| state == "open");
+        Some((handler.clone())(open))
+    };
+    Listener::new("toggle", Some(Box::new(closure)), Some(Category::Raw), None)
+}
+
+// ------ Syncing ------
+
+thread_local! {
+    static DIALOG_WARNED: Cell<bool> = Cell::new(false);
+    static POPOVER_WARNED: Cell<bool> = Cell::new(false);
+}
+
+/// Schedule syncing of all marked elements in the patched vdom into the write phase.
+pub(crate) fn sync_all<Ms>(el: &El<Ms>) {
+    let mut marked = Vec::new();
+    collect(el, &mut marked);
+    if !marked.is_empty() {
+        scheduler::dom_write(move |_| {
+            for (element, state) in &marked {
+                sync(element, *state);
+            }
+        });
+    }
+}
+
+fn collect<Ms>(el: &El<Ms>, marked: &mut Vec<(web_sys::Element, OpenState)>) {
+    if let Some(state) = el.open_state {
+        if let Some(element) = el
+            .node_ws
+            .as_ref()
+            .and_then(|node| node.dyn_ref::<web_sys::Element>())
+        {
+            marked.push((element.clone(), state));
+        }
+    }
+    for child in &el.children {
+        if let Node::Element(child_el) = child {
+            collect(child_el, marked);
+        }
+    }
+}
+
+fn sync(element: &web_sys::Element, state: OpenState) {
+    let (open, show, hide, warned) = match state {
+        OpenState::Dialog(open) => (open, "showModal", "close", &DIALOG_WARNED),
+        OpenState::Popover(open) => (open, "showPopover", "hidePopover", &POPOVER_WARNED),
+    };
+    let method = get_property(element, if open { show } else { hide })
+        .and_then(|method| method.dyn_into::<js_sys::Function>().ok());
+
+    match method {
+        Some(method) => {
+            let is_open = match state {
+                OpenState::Dialog(_) => element.has_attribute("open"),
+                OpenState::Popover(_) => element.matches(":popover-open").unwrap_or(false),
+            };
+            if is_open != open {
+                if let Err(error) = method.call0(element) {
+                    crate::error(error);
+                }
+            }
+        }
+        None => {
+            if !warned.with(|warned| warned.replace(true)) {
+                web_sys::console::warn_1(
+                    &format!(
+                        "`{}` isn't supported by the browser - only the `open` attribute is set.",
+                        show
+                    )
+                    .into(),
+                );
+            }
+            let result = if open {
+                element.set_attribute("open", "")
+            } else {
+                element.remove_attribute("open")
+            };
+            result.expect("Problem setting `open` attribute");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser::util;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn append(tag: &str) -> web_sys::Element {
+        let element = util::document().create_element(tag).unwrap();
+        util::body().append_child(&element).unwrap();
+        element
+    }
+
+    #[wasm_bindgen_test]
+    fn dialog_is_shown_as_modal() {
+        let dialog = append("dialog");
+
+        sync(&dialog, OpenState::Dialog(true));
+        assert!(dialog.matches(":modal").unwrap());
+        // Already open - `showModal` would throw.
+        sync(&dialog, OpenState::Dialog(true));
+
+        sync(&dialog, OpenState::Dialog(false));
+        assert!(!dialog.has_attribute("open"));
+        dialog.remove();
+    }
+
+    #[wasm_bindgen_test]
+    fn unsupported_element_falls_back_to_open_attribute() {
+        // `div` doesn't have `showModal`.
+        let element = append("div");
+
+        sync(&element, OpenState::Dialog(true));
+        assert!(element.has_attribute("open"));
+        sync(&element, OpenState::Dialog(false));
+        assert!(!element.has_attribute("open"));
+        element.remove();
+    }
+}
@@ -2,10 +2,70 @@
 //! `web_sys::Event`
 
 use super::super::util;
-use crate::virtual_dom::{Category, Listener};
+use crate::virtual_dom::{Category, EventTrigger, Listener};
 use serde::de::DeserializeOwned;
+use std::any;
 use wasm_bindgen::JsCast;
 
+/// Types which event handlers can receive instead of `web_sys::Event`.
+pub trait FromEvent: Sized + 'static {
+    /// Returns `None` when `event` doesn't implement the interface `Self`.
+    fn from_event(event: web_sys::Event) -> Option<Self>;
+}
+
+impl<T: JsCast + 'static> FromEvent for T {
+    fn from_event(event: web_sys::Event) -> Option<Self> {
+        event.dyn_into().ok()
+    }
+}
+
+/// Pass events to `handler` as `E`. Events with another interface
+/// (e.g. plain `Event`s dispatched by some browsers) are logged and skipped.
+///
+/// The handler is boxed, so it's `'static` even when `Ms` isn't.
+pub(crate) fn typed_handler<Ms, E: FromEvent>(
+    handler: impl FnOnce(E) -> Ms + 'static + Clone,
+) -> Box<dyn FnMut(web_sys::Event) -> Option<Ms>> {
+    Box::new(move |event: web_sys::Event| {
+        let event_type = event.type_();
+        match E::from_event(event) {
+            Some(event) => Some((handler.clone())(event)),
+            None => {
+                crate::error(format!(
+                    "Skipping event \"{}\" - it isn't `{}`",
+                    event_type,
+                    any::type_name::<E>()
+                ));
+                None
+            }
+        }
+    })
+}
+
+/// Create an event handler which receives the event's DOM interface.
+/// Use `TEv` to get specific event types, `Ev` passes `web_sys::Event`.
+///
+/// # Example
+///
+/// ```rust,no_run
+///div![
+///    ev(TEv::PointerMove, |event| Msg::PointerMoved(event.pointer_id())),
+///    ev(TEv::KeyDown, |event| Msg::KeyPressed(event.key())),
+///    ev(Ev::Change, |event| Msg::Changed(event.type_())),
+///]
+/// ```
+pub fn ev<Ms, T: EventTrigger>(
+    trigger: T,
+    handler: impl FnOnce(T::Event) -> Ms + 'static + Clone,
+) -> Listener<Ms> {
+    Listener::new(
+        trigger.ev().as_str(),
+        Some(typed_handler(handler)),
+        Some(Category::Typed),
+        None,
+    )
+}
+
 /// Create an event that passes a String of field text, for fast input handling.
 pub fn input_ev<Ms, T: ToString + Copy>(
     trigger: T,
@@ -20,7 +80,7 @@ pub fn input_ev<Ms, T: ToString + Copy>(
             .map_err(crate::error)
             .unwrap_or_default();
 
-        (handler.clone())(value)
+        Some((handler.clone())(value))
     };
 
     Listener::new(
@@ -37,12 +97,9 @@ pub fn keyboard_ev<Ms, T: ToString + Copy>(
     trigger: T,
     handler: impl FnOnce(web_sys::KeyboardEvent) -> Ms + 'static + Clone,
 ) -> Listener<Ms> {
-    let closure = move |event: web_sys::Event| {
-        (handler.clone())(event.dyn_ref::<web_sys::KeyboardEvent>().unwrap().clone())
-    };
     Listener::new(
         &trigger.to_string(),
-        Some(Box::new(closure)),
+        Some(typed_handler(handler)),
         Some(Category::Keyboard),
         None,
     )
@@ -53,12 +110,9 @@ pub fn mouse_ev<Ms, T: ToString + Copy>(
     trigger: T,
     handler: impl FnOnce(web_sys::MouseEvent) -> Ms + 'static + Clone,
 ) -> Listener<Ms> {
-    let closure = move |event: web_sys::Event| {
-        (handler.clone())(event.dyn_ref::<web_sys::MouseEvent>().unwrap().clone())
-    };
     Listener::new(
         &trigger.to_string(),
-        Some(Box::new(closure)),
+        Some(typed_handler(handler)),
         Some(Category::Mouse),
         None,
     )
@@ -69,12 +123,9 @@ pub fn pointer_ev<Ms, T: ToString + Copy>(
     trigger: T,
     handler: impl FnOnce(web_sys::PointerEvent) -> Ms + 'static + Clone,
 ) -> Listener<Ms> {
-    let closure = move |event: web_sys::Event| {
-        (handler.clone())(event.dyn_ref::<web_sys::PointerEvent>().unwrap().clone())
-    };
     Listener::new(
         &trigger.to_string(),
-        Some(Box::new(closure)),
+        Some(typed_handler(handler)),
         Some(Category::Pointer),
         None,
     )
@@ -86,7 +137,7 @@ pub fn raw_ev<Ms, T: ToString + Copy>(
     trigger: T,
     handler: impl FnOnce(web_sys::Event) -> Ms + 'static + Clone,
 ) -> Listener<Ms> {
-    let closure = move |event: web_sys::Event| (handler.clone())(event);
+    let closure = move |event: web_sys::Event| Some((handler.clone())(event));
     Listener::new(
         &trigger.to_string(),
         Some(Box::new(closure)),
@@ -104,7 +155,7 @@ where
 {
     let msg_closure = message.clone();
     let handler = || msg_closure;
-    let closure = move |_| handler.clone()();
+    let closure = move |_| Some(handler.clone()());
     Listener::new(
         &trigger.to_string(),
         Some(Box::new(closure)),
@@ -122,12 +173,9 @@ pub const UPDATE_TRIGGER_EVENT_ID: &str = "triggerupdate";
 pub fn trigger_update_ev<Ms>(
     handler: impl FnOnce(web_sys::CustomEvent) -> Ms + 'static + Clone,
 ) -> Listener<Ms> {
-    let closure = move |event: web_sys::Event| {
-        (handler.clone())(event.dyn_ref::<web_sys::CustomEvent>().unwrap().clone())
-    };
     Listener::new(
         UPDATE_TRIGGER_EVENT_ID,
-        Some(Box::new(closure)),
+        Some(typed_handler(handler)),
         Some(Category::Custom),
         None,
     )
@@ -148,3 +196,43 @@ pub fn trigger_update_handler<Ms: DeserializeOwned>() -> Listener<Ms> {
             .expect("trigger_update_handler: Deserialization failed!")
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::virtual_dom::{mailbox::Mailbox, Ev, TEv};
+    use std::{cell::RefCell, rc::Rc};
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn inferred_event_types() {
+        // Compiles only when the closures receive the right event types.
+        let _: Listener<i32> = ev(TEv::PointerMove, |event| event.pointer_id());
+        let _: Listener<String> = ev(TEv::KeyDown, |event| event.key());
+        let _: Listener<f64> = ev(TEv::Wheel, |event| event.delta_y());
+        let _: Listener<i32> = ev(TEv::Click, |event| event.client_x());
+        let _: Listener<String> = ev(Ev::Change, |event: web_sys::Event| event.type_());
+    }
+
+    #[wasm_bindgen_test]
+    fn event_with_unexpected_type_is_skipped() {
+        let messages = Rc::new(RefCell::new(Vec::new()));
+        let mailbox = Mailbox::new({
+            let messages = Rc::clone(&messages);
+            move |key: String| messages.borrow_mut().push(key)
+        });
+        let element = util::document().create_element("div").unwrap();
+        let mut listener = ev(TEv::KeyDown, |event| event.key());
+        listener.attach(&element, mailbox);
+
+        let plain_event = web_sys::Event::new("keydown").unwrap();
+        element.dispatch_event(&plain_event).unwrap();
+        let keyboard_event = web_sys::KeyboardEvent::new("keydown").unwrap();
+        element.dispatch_event(&keyboard_event).unwrap();
+        listener.detach(&element);
+
+        assert_eq!(*messages.borrow(), vec![String::new()]);
+    }
+}
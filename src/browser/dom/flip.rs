@@ -0,0 +1,529 @@
+//! FLIP (First, Last, Invert, Play) animations of elements marked by `flip_group`.
+//!
+//! In each render:
+//! - First - positions of group members are recorded in the Read phase before patching.
+//!   Running animations are stopped there, so interrupted animations continue
+//!   from the elements' current (animated) positions.
+//! - Last - members are measured again after patching.
+//! - Invert - moved members get an inline `transform` moving them back to their first positions
+//!   (without a transition).
+//! - Play - in the next animation frame, the transition is enabled and the transform is removed.
+//!   Inline styles are restored on `transitionend` or, as a fallback, after the group's duration.
+//!
+//! Members are identified by their group key and their `el_key` - members without `el_key`
+//! aren't animated. Members which have entered a group get `FlipGroup::enter` treatment.
+//! Removed members are replaced by their copies in `<body>` with `position: fixed`,
+//! which get `FlipGroup::exit` treatment - styles of their ancestors don't apply to the copies.
+//!
+//! Inline properties `transform`, `opacity` and `transition` of members are owned by running
+//! animations - their previous values are restored when the animations finish.
+
+use crate::browser::{
+    time::{Clock, TimeoutHandle},
+    util::{self, ClosureNew, RequestAnimationFrameHandle},
+};
+use crate::virtual_dom::{El, Node};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    rc::{Rc, Weak},
+};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+/// Inline style properties owned by running animations.
+const PROPERTIES: [&str; 3] = ["transform", "opacity", "transition"];
+/// Delay after the group's duration before animations without `transitionend` are finished.
+const FALLBACK_DELAY_MS: u32 = 50;
+/// Moves shorter than this (in px) aren't animated.
+const MIN_MOVE: f64 = 0.5;
+
+thread_local! {
+    static MISSING_KEY_WARNED: Cell<bool> = Cell::new(false);
+}
+
+// ------ FlipEffect ------
+
+/// Treatment of elements entering or leaving a `flip_group`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FlipEffect {
+    None,
+    /// Fade in / out.
+    Fade,
+    /// Fade and scale from / to 80 %.
+    Scale,
+}
+
+impl FlipEffect {
+    /// Style properties of the hidden state.
+    fn hidden_properties(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            FlipEffect::None => &[],
+            FlipEffect::Fade => &[("opacity", "0")],
+            FlipEffect::Scale => &[("opacity", "0"), ("transform", "scale(0.8)")],
+        }
+    }
+}
+
+// ------ FlipGroup ------
+
+/// FLIP group marker created by `flip_group`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlipGroup {
+    pub key: String,
+    pub duration_ms: u32,
+    /// CSS timing function - e.g. `ease-out` or `cubic-bezier(0.2, 0, 0, 1)`.
+    pub easing: String,
+    pub enter: FlipEffect,
+    pub exit: FlipEffect,
+}
+
+/// Animate position changes of the element between renders - e.g. when a keyed list
+/// is reordered. Elements of a group are identified by their `el_key`.
+/// Defaults: 250 ms, `ease`, `FlipEffect::Fade` for entering and leaving elements.
+///
+/// # Example
+///
+/// ```rust,no_run
+///ul![keyed_nodes(&model.cards, |card| card.id, |card| {
+///    li![flip_group("cards").duration(300).exit(FlipEffect::Scale), &card.title]
+///})]
+/// ```
+pub fn flip_group(key: &(impl ToString + ?Sized)) -> FlipGroup {
+    FlipGroup {
+        key: key.to_string(),
+        duration_ms: 250,
+        easing: "ease".to_owned(),
+        enter: FlipEffect::Fade,
+        exit: FlipEffect::Fade,
+    }
+}
+
+impl FlipGroup {
+    pub fn duration(mut self, duration_ms: u32) -> Self {
+        self.duration_ms = duration_ms;
+        self
+    }
+
+    pub fn easing(mut self, easing: &str) -> Self {
+        self.easing = easing.to_owned();
+        self
+    }
+
+    /// Treatment of elements which weren't in the group in the previous render.
+    /// Elements rendered with the group's first members aren't animated.
+    pub fn enter(mut self, effect: FlipEffect) -> Self {
+        self.enter = effect;
+        self
+    }
+
+    /// Treatment of removed elements.
+    pub fn exit(mut self, effect: FlipEffect) -> Self {
+        self.exit = effect;
+        self
+    }
+
+    fn transition(&self) -> String {
+        format!(
+            "transform {duration}ms {easing}, opacity {duration}ms {easing}",
+            duration = self.duration_ms,
+            easing = self.easing
+        )
+    }
+}
+
+// ------ Members ------
+
+/// Group key and `el_key` of the member.
+type MemberId = (String, String);
+
+struct Member {
+    element: web_sys::HtmlElement,
+    group: FlipGroup,
+}
+
+fn collect_members<Ms>(el: &El<Ms>, members: &mut Vec<(MemberId, Member)>) {
+    if let Some(group) = &el.flip {
+        let element = el
+            .node_ws
+            .as_ref()
+            .and_then(|node| node.dyn_ref::<web_sys::HtmlElement>());
+        match (&el.key, element) {
+            (Some(key), Some(element)) => members.push((
+                (group.key.clone(), key.clone()),
+                Member {
+                    element: element.clone(),
+                    group: group.clone(),
+                },
+            )),
+            (None, _) => {
+                if !MISSING_KEY_WARNED.with(|warned| warned.replace(true)) {
+                    web_sys::console::warn_1(
+                        &format!(
+                            "`flip_group(\"{}\")`: elements without `el_key` aren't animated.",
+                            group.key
+                        )
+                        .into(),
+                    );
+                }
+            }
+            _ => (),
+        }
+    }
+    for child in &el.children {
+        if let Node::Element(child_el) = child {
+            collect_members(child_el, members);
+        }
+    }
+}
+
+/// Positions of group members before patching. See `FlipAnimations::first`.
+#[derive(Default)]
+pub(crate) struct First {
+    members: HashMap<MemberId, (Member, web_sys::DomRect)>,
+}
+
+// ------ Animation ------
+
+#[derive(Copy, Clone)]
+enum Kind {
+    Move,
+    Enter,
+    Exit(FlipEffect),
+}
+
+struct Animation {
+    element: web_sys::HtmlElement,
+    /// Inline values of `PROPERTIES` before the animation.
+    original: Vec<String>,
+    kind: Kind,
+    transition: String,
+    duration_ms: u32,
+}
+
+impl Animation {
+    fn new(element: web_sys::HtmlElement, group: &FlipGroup, kind: Kind) -> Self {
+        let style = element.style();
+        let original = PROPERTIES
+            .iter()
+            .map(|property| style.get_property_value(property).unwrap_or_default())
+            .collect();
+        let animation = Self {
+            element,
+            original,
+            kind,
+            transition: group.transition(),
+            duration_ms: group.duration_ms,
+        };
+        animation.set("transition", "none");
+        animation
+    }
+
+    fn original(&self, property: &str) -> &str {
+        let index = PROPERTIES
+            .iter()
+            .position(|owned| *owned == property)
+            .expect("property owned by animations");
+        &self.original[index]
+    }
+
+    fn set(&self, property: &str, value: &str) {
+        if self.element.style().set_property(property, value).is_err() {
+            crate::error(format!("FLIP: problem setting \"{}\"", property));
+        }
+    }
+
+    fn restore(&self, property: &str) {
+        let original = self.original(property);
+        if original.is_empty() {
+            if self.element.style().remove_property(property).is_err() {
+                crate::error(format!("FLIP: problem removing \"{}\"", property));
+            }
+        } else {
+            self.set(property, original);
+        }
+    }
+
+    /// Enable the transition and move to the final state.
+    fn play(&self) {
+        self.set("transition", &self.transition);
+        match self.kind {
+            Kind::Move => self.restore("transform"),
+            Kind::Enter => {
+                self.restore("transform");
+                self.restore("opacity");
+            }
+            Kind::Exit(effect) => {
+                for (property, value) in effect.hidden_properties() {
+                    self.set(property, value);
+                }
+            }
+        }
+    }
+
+    fn finish(&self, on_transition_end: Option<&Closure<dyn FnMut(web_sys::Event)>>) {
+        if let Some(on_transition_end) = on_transition_end {
+            self.element
+                .remove_event_listener_with_callback(
+                    "transitionend",
+                    on_transition_end.as_ref().unchecked_ref(),
+                )
+                .expect("Problem removing transitionend listener");
+        }
+        match self.kind {
+            Kind::Exit(_) => self.element.remove(),
+            Kind::Move | Kind::Enter => {
+                for property in &PROPERTIES {
+                    self.restore(property);
+                }
+            }
+        }
+    }
+}
+
+/// Copy of the removed element at its last position.
+fn exit_copy(
+    element: &web_sys::HtmlElement,
+    rect: &web_sys::DomRect,
+) -> Option<web_sys::HtmlElement> {
+    let copy = element
+        .clone_node_with_deep(true)
+        .ok()?
+        .dyn_into::<web_sys::HtmlElement>()
+        .ok()?;
+    copy.remove_attribute("id").ok()?;
+    let style = copy.style();
+    let properties = [
+        ("position", "fixed".to_owned()),
+        ("left", format!("{}px", rect.left())),
+        ("top", format!("{}px", rect.top())),
+        ("width", format!("{}px", rect.width())),
+        ("height", format!("{}px", rect.height())),
+        ("margin", "0".to_owned()),
+        ("box-sizing", "border-box".to_owned()),
+        ("pointer-events", "none".to_owned()),
+    ];
+    for (property, value) in &properties {
+        style.set_property(property, value).ok()?;
+    }
+    util::body().append_child(&copy).ok()?;
+    Some(copy)
+}
+
+// ------ FlipAnimations ------
+
+#[derive(Default)]
+struct Running {
+    animations: Vec<Animation>,
+    frame: Option<RequestAnimationFrameHandle>,
+    fallback: Option<TimeoutHandle>,
+    on_transition_end: Option<Closure<dyn FnMut(web_sys::Event)>>,
+}
+
+/// FLIP animations of an app. See `flip_group`.
+#[derive(Default)]
+pub(crate) struct FlipAnimations {
+    running: Rc<RefCell<Running>>,
+}
+
+impl FlipAnimations {
+    /// Read phase before patching - record positions of `old`'s group members
+    /// and stop running animations.
+    pub(crate) fn first<Ms>(&self, old: &El<Ms>) -> First {
+        let mut members = Vec::new();
+        collect_members(old, &mut members);
+        let first = First {
+            members: members
+                .into_iter()
+                .map(|(id, member)| {
+                    let rect = member.element.get_bounding_client_rect();
+                    (id, (member, rect))
+                })
+                .collect(),
+        };
+        self.stop();
+        first
+    }
+
+    /// Measure `new`'s group members after patching, invert their moves and play them
+    /// in the next animation frame.
+    pub(crate) fn play<Ms>(&self, new: &El<Ms>, mut first: First, clock: &Rc<dyn Clock>) {
+        if first.members.is_empty() {
+            return;
+        }
+        let first_groups = first
+            .members
+            .keys()
+            .map(|(group, _)| group.clone())
+            .collect::<HashSet<_>>();
+
+        let mut members = Vec::new();
+        collect_members(new, &mut members);
+        // Measure all members before the first write.
+        let last_rects = members
+            .iter()
+            .map(|(_, member)| member.element.get_bounding_client_rect())
+            .collect::<Vec<_>>();
+
+        let mut running = self.running.borrow_mut();
+        for ((id, member), last_rect) in members.into_iter().zip(last_rects) {
+            match first.members.remove(&id) {
+                Some((_, first_rect)) => {
+                    let dx = first_rect.left() - last_rect.left();
+                    let dy = first_rect.top() - last_rect.top();
+                    if dx.abs() < MIN_MOVE && dy.abs() < MIN_MOVE {
+                        continue;
+                    }
+                    let animation = Animation::new(member.element, &member.group, Kind::Move);
+                    let transform = format!(
+                        "translate({}px, {}px) {}",
+                        dx,
+                        dy,
+                        animation.original("transform")
+                    );
+                    animation.set("transform", transform.trim_end());
+                    running.animations.push(animation);
+                }
+                None if first_groups.contains(&member.group.key)
+                    && member.group.enter != FlipEffect::None =>
+                {
+                    let animation = Animation::new(member.element, &member.group, Kind::Enter);
+                    for (property, value) in member.group.enter.hidden_properties() {
+                        animation.set(property, value);
+                    }
+                    running.animations.push(animation);
+                }
+                None => (),
+            }
+        }
+
+        // Members that have been removed (not only moved out of the group).
+        for (member, first_rect) in first.members.into_iter().map(|(_, value)| value) {
+            if member.group.exit == FlipEffect::None || member.element.is_connected() {
+                continue;
+            }
+            if let Some(copy) = exit_copy(&member.element, &first_rect) {
+                running.animations.push(Animation::new(
+                    copy,
+                    &member.group,
+                    Kind::Exit(member.group.exit),
+                ));
+            }
+        }
+
+        if running.animations.is_empty() {
+            return;
+        }
+        let (weak_running, clock) = (Rc::downgrade(&self.running), Rc::clone(clock));
+        running.frame = Some(util::request_animation_frame(Closure::new(move |_| {
+            release(&weak_running, &*clock)
+        })));
+    }
+
+    /// Finish all running animations immediately.
+    pub(crate) fn stop(&self) {
+        let mut running = self.running.borrow_mut();
+        if running.animations.is_empty() {
+            return;
+        }
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        let _guard_pause = crate::debug::ownership_guard::pause();
+        running.frame = None;
+        running.fallback = None;
+        let Running {
+            animations,
+            on_transition_end,
+            ..
+        } = &mut *running;
+        for animation in animations.drain(..) {
+            animation.finish(on_transition_end.as_ref());
+        }
+    }
+}
+
+/// Play - enable transitions and move inverted elements to their final states.
+fn release(weak_running: &Weak<RefCell<Running>>, clock: &dyn Clock) {
+    let running = match weak_running.upgrade() {
+        Some(running) => running,
+        None => return,
+    };
+    #[cfg(all(feature = "debug-tools", debug_assertions))]
+    let _guard_pause = crate::debug::ownership_guard::pause();
+    let mut running = running.borrow_mut();
+
+    if running.on_transition_end.is_none() {
+        let weak_running = weak_running.clone();
+        running.on_transition_end = Some(Closure::new(move |event: web_sys::Event| {
+            finish_target(&weak_running, &event)
+        }));
+    }
+    let Running {
+        animations,
+        on_transition_end,
+        ..
+    } = &mut *running;
+    let on_transition_end = on_transition_end.as_ref().expect("transitionend listener");
+
+    let mut duration_ms = 0;
+    for animation in animations.iter() {
+        animation
+            .element
+            .add_event_listener_with_callback(
+                "transitionend",
+                on_transition_end.as_ref().unchecked_ref(),
+            )
+            .expect("Problem adding transitionend listener");
+        animation.play();
+        duration_ms = duration_ms.max(animation.duration_ms);
+    }
+
+    let weak_running = weak_running.clone();
+    running.fallback = Some(clock.set_timeout(
+        duration_ms + FALLBACK_DELAY_MS,
+        Box::new(move || finish_all(&weak_running)),
+    ));
+}
+
+/// Finish the animation of the `transitionend` event's target.
+/// Events bubbling from descendants are ignored.
+fn finish_target(weak_running: &Weak<RefCell<Running>>, event: &web_sys::Event) {
+    let (running, target) = match (weak_running.upgrade(), event.target()) {
+        (Some(running), Some(target)) => (running, target),
+        _ => return,
+    };
+    let target: &JsValue = target.as_ref();
+    #[cfg(all(feature = "debug-tools", debug_assertions))]
+    let _guard_pause = crate::debug::ownership_guard::pause();
+    let mut running = running.borrow_mut();
+    let Running {
+        animations,
+        on_transition_end,
+        ..
+    } = &mut *running;
+    let element_is_target = |animation: &Animation| {
+        let element: &JsValue = animation.element.as_ref();
+        element == target
+    };
+    if let Some(index) = animations.iter().position(element_is_target) {
+        animations.remove(index).finish(on_transition_end.as_ref());
+    }
+}
+
+/// Fallback for animations which haven't received `transitionend`
+/// (e.g. the transition has been overridden by CSS).
+fn finish_all(weak_running: &Weak<RefCell<Running>>) {
+    let running = match weak_running.upgrade() {
+        Some(running) => running,
+        None => return,
+    };
+    #[cfg(all(feature = "debug-tools", debug_assertions))]
+    let _guard_pause = crate::debug::ownership_guard::pause();
+    let mut running = running.borrow_mut();
+    let Running {
+        animations,
+        on_transition_end,
+        ..
+    } = &mut *running;
+    for animation in animations.drain(..) {
+        animation.finish(on_transition_end.as_ref());
+    }
+}
@@ -0,0 +1,66 @@
+//! Listeners of `window` and `document` events created by `orders.subscribe_window_event`
+//! and `orders.subscribe_document_event`.
+//!
+//! Unlike `AppBuilder::window_events`, a subscription isn't derived from the model - its
+//! listener is added once, with one closure, and removed when the `EventHandle` is dropped.
+//! Store the handle in the model (e.g. in the state of an open modal) to listen only
+//! while it exists.
+
+use crate::browser::util::{self, ClosureNew};
+use crate::virtual_dom::Ev;
+use std::fmt;
+use wasm_bindgen::{closure::Closure, JsCast};
+
+/// Subscription to events of `window` or `document`. Dropping the handle removes the listener.
+#[must_use = "the listener is removed when the handle is dropped"]
+pub struct EventHandle {
+    target: web_sys::EventTarget,
+    trigger: Ev,
+    closure: Closure<dyn FnMut(web_sys::Event)>,
+}
+
+impl EventHandle {
+    /// Add a listener of `trigger` to `target` until the handle is dropped.
+    pub fn new(
+        target: web_sys::EventTarget,
+        trigger: Ev,
+        handler: impl FnMut(web_sys::Event) + 'static,
+    ) -> Self {
+        let closure = Closure::new(handler);
+        target
+            .add_event_listener_with_callback(trigger.as_str(), closure.as_ref().unchecked_ref())
+            .expect("Problem adding global listener");
+        Self {
+            target,
+            trigger,
+            closure,
+        }
+    }
+
+    /// Listen to `window`'s events.
+    pub fn window(trigger: Ev, handler: impl FnMut(web_sys::Event) + 'static) -> Self {
+        Self::new(util::window().into(), trigger, handler)
+    }
+
+    /// Listen to `document`'s events - e.g. `visibilitychange` or `selectionchange`.
+    pub fn document(trigger: Ev, handler: impl FnMut(web_sys::Event) + 'static) -> Self {
+        Self::new(util::document().into(), trigger, handler)
+    }
+}
+
+impl Drop for EventHandle {
+    fn drop(&mut self) {
+        self.target
+            .remove_event_listener_with_callback(
+                self.trigger.as_str(),
+                self.closure.as_ref().unchecked_ref(),
+            )
+            .expect("Problem removing global listener");
+    }
+}
+
+impl fmt::Debug for EventHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EventHandle({})", self.trigger.as_str())
+    }
+}
@@ -0,0 +1,199 @@
+//! DOM read / write scheduling to avoid layout thrashing.
+//!
+//! Closures registered by `dom_read` and `dom_write` are executed in the next render -
+//! reads before patching, writes after patching. When no render is scheduled,
+//! they are executed in a standalone animation frame - again all reads before all writes.
+//! Order of closures is preserved within each category.
+//!
+//! Each app has its own queue - closures scheduled while the app's `update`, `view` or render
+//! is running belong to the app, so they are dropped when the app is unmounted and they aren't
+//! executed by other apps' renders. Closures scheduled outside of apps (e.g. in window event
+//! listeners) are executed by the next render of any app or in their own animation frame.
+
+use crate::browser::util::{self, ClosureNew, RequestAnimationFrameHandle};
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    rc::{Rc, Weak},
+};
+use wasm_bindgen::closure::Closure;
+use web_sys::Document;
+
+type DomOp = Box<dyn FnOnce(&Document)>;
+
+#[derive(Default)]
+pub(crate) struct Queue {
+    reads: VecDeque<DomOp>,
+    writes: VecDeque<DomOp>,
+    frame_handle: Option<RequestAnimationFrameHandle>,
+}
+
+/// Queue of one app (see `AppData::dom_ops`) or of closures scheduled outside of apps.
+pub(crate) type SharedQueue = Rc<RefCell<Queue>>;
+
+thread_local! {
+    /// Queue of the app whose `update`, `view` or render is running.
+    static RUNNING: RefCell<Option<SharedQueue>> = RefCell::new(None);
+    /// Queue of closures scheduled outside of apps.
+    static DETACHED: SharedQueue = Rc::new(RefCell::new(Queue::default()));
+}
+
+/// Schedule a closure which reads from DOM (e.g. measures layout).
+///
+/// # Example
+///
+/// ```rust,no_run
+///seed::dom_read(|document| {
+///    let height = document.body().unwrap().client_height();
+///    log!(height);
+///});
+/// ```
+pub fn dom_read(op: impl FnOnce(&Document) + 'static) {
+    let queue = current();
+    queue.borrow_mut().reads.push_back(Box::new(op));
+    schedule_frame(&queue);
+}
+
+/// Schedule a closure which writes into DOM outside of the virtual DOM tree
+/// (e.g. into `<head>` or into the mount point's attributes).
+pub fn dom_write(op: impl FnOnce(&Document) + 'static) {
+    let queue = current();
+    queue.borrow_mut().writes.push_back(Box::new(op));
+    schedule_frame(&queue);
+}
+
+/// Call `f` with `queue` as the queue of `dom_read` and `dom_write` calls.
+pub(crate) fn with_queue<R>(queue: &SharedQueue, f: impl FnOnce() -> R) -> R {
+    let previous = RUNNING.with(|running| running.replace(Some(Rc::clone(queue))));
+    let output = f();
+    RUNNING.with(|running| running.replace(previous));
+    output
+}
+
+fn current() -> SharedQueue {
+    RUNNING
+        .with(|running| running.borrow().clone())
+        .unwrap_or_else(detached)
+}
+
+fn detached() -> SharedQueue {
+    DETACHED.with(Rc::clone)
+}
+
+fn schedule_frame(queue: &SharedQueue) {
+    let mut queue_ref = queue.borrow_mut();
+    if queue_ref.frame_handle.is_none() {
+        let weak_queue = Rc::downgrade(queue);
+        queue_ref.frame_handle = Some(util::request_animation_frame(Closure::new(move |_| {
+            // The app's queue doesn't exist when the app has been dropped.
+            if let Some(queue) = Weak::upgrade(&weak_queue) {
+                queue.borrow_mut().frame_handle.take();
+                flush_queue(&queue);
+            }
+        })));
+    }
+}
+
+/// Execute all scheduled reads of the app with `queue` and reads scheduled outside of apps.
+/// Closures scheduled during the flush are executed too.
+pub(crate) fn flush_reads(queue: &SharedQueue) {
+    flush_category(&detached(), |queue| queue.reads.pop_front());
+    flush_category(queue, |queue| queue.reads.pop_front());
+}
+
+/// Execute all scheduled writes of the app with `queue` and writes scheduled outside of apps.
+/// Closures scheduled during the flush are executed too.
+pub(crate) fn flush_writes(queue: &SharedQueue) {
+    #[cfg(all(feature = "debug-tools", debug_assertions))]
+    let _guard_pause = crate::debug::ownership_guard::pause();
+    flush_category(&detached(), |queue| queue.writes.pop_front());
+    flush_category(queue, |queue| queue.writes.pop_front());
+}
+
+/// Execute all reads and then all writes of the app with `queue`
+/// and of closures scheduled outside of apps.
+pub(crate) fn flush_queue(queue: &SharedQueue) {
+    flush_reads(queue);
+    flush_writes(queue);
+}
+
+/// Execute all scheduled reads and then all scheduled writes of the running app
+/// (or of closures scheduled outside of apps).
+pub fn flush() {
+    flush_queue(&current());
+}
+
+/// Drop all closures of the app with `queue` without executing them - see `App::unmount`.
+pub(crate) fn clear(queue: &SharedQueue) {
+    queue.replace(Queue::default());
+}
+
+fn flush_category(queue: &SharedQueue, mut pop: impl FnMut(&mut Queue) -> Option<DomOp>) {
+    let document = util::document();
+    // The queue mustn't be borrowed while the closure runs - it can schedule new closures.
+    let mut next_op = || pop(&mut queue.borrow_mut());
+    while let Some(op) = next_op() {
+        with_queue(queue, || op(&document));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn reads_before_writes_in_fifo_order() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let push = |entry: &'static str| {
+            let log = Rc::clone(&log);
+            move |_: &Document| log.borrow_mut().push(entry)
+        };
+
+        dom_write(push("write 1"));
+        dom_read(push("read 1"));
+        dom_write(push("write 2"));
+        dom_read(push("read 2"));
+        flush();
+
+        assert_eq!(
+            *log.borrow(),
+            vec!["read 1", "read 2", "write 1", "write 2"]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn clear_drops_closures() {
+        let queue = SharedQueue::default();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let log_clone = Rc::clone(&log);
+        with_queue(&queue, || {
+            dom_write(move |_| log_clone.borrow_mut().push("write"))
+        });
+        clear(&queue);
+        flush_queue(&queue);
+
+        assert!(log.borrow().is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    fn queues_are_separated() {
+        let (first, second) = (SharedQueue::default(), SharedQueue::default());
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let push = |entry: &'static str| {
+            let log = Rc::clone(&log);
+            move |_: &Document| log.borrow_mut().push(entry)
+        };
+
+        with_queue(&first, || dom_write(push("first")));
+        with_queue(&second, || dom_write(push("second")));
+        dom_write(push("detached"));
+        clear(&first);
+        flush_queue(&second);
+
+        assert_eq!(*log.borrow(), vec!["detached", "second"]);
+    }
+}
@@ -0,0 +1,465 @@
+//! Scroll progress of an element through the viewport.
+//!
+//! Progress is computed at most once per animation frame and only while the element is near
+//! the viewport - an `IntersectionObserver` switches the computation on and off,
+//! so idle pages don't pay for scroll listeners.
+//!
+//! The tracked element can be also given by an `ElRef` - it's resolved after the next render,
+//! see `orders.subscribe_scroll_progress_el_ref`.
+
+use super::scheduler;
+use crate::browser::{
+    util::{self, ClosureNew},
+    visibility::{self, Pausable},
+    Capabilities, Capability, Unsupported,
+};
+use crate::virtual_dom::ElRef;
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+use wasm_bindgen::{closure::Closure, JsCast};
+
+/// How far from the viewport the element has to be to stop computing progress.
+const OBSERVER_ROOT_MARGIN: &str = "100% 100% 100% 100%";
+
+const WINDOW_EVENTS: [&str; 2] = ["scroll", "resize"];
+
+// ------ ScrollProgressOpts ------
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Axis {
+    Vertical,
+    Horizontal,
+}
+
+impl Default for Axis {
+    fn default() -> Self {
+        Axis::Vertical
+    }
+}
+
+/// Options for `orders.subscribe_scroll_progress`.
+///
+/// Progress is `0.0` when the element's leading edge is `offset_start` pixels
+/// above the viewport's trailing edge (i.e. the element is entering the viewport)
+/// and `1.0` when the element's trailing edge is `offset_end` pixels below the viewport's
+/// leading edge (i.e. the element has left the viewport).
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct ScrollProgressOpts {
+    pub axis: Axis,
+    pub offset_start: f64,
+    pub offset_end: f64,
+}
+
+/// Compute progress from the element's position relative to the viewport.
+///
+/// - `element_start` - distance between the viewport's leading edge and the element's leading edge.
+/// - `element_size` - element's height (or width for `Axis::Horizontal`).
+/// - `viewport_size` - viewport's height (or width).
+pub fn compute_progress(
+    element_start: f64,
+    element_size: f64,
+    viewport_size: f64,
+    opts: &ScrollProgressOpts,
+) -> f64 {
+    let traveled = viewport_size - opts.offset_start - element_start;
+    let total = viewport_size + element_size - opts.offset_start - opts.offset_end;
+    if total <= 0. {
+        return if traveled > 0. { 1. } else { 0. };
+    }
+    (traveled / total).max(0.).min(1.)
+}
+
+// ------ ScrollProgressHandle ------
+
+/// Observation suspended by `ScrollProgressHandle::pause_when_hidden`.
+struct Observation {
+    tracker: Rc<Tracker>,
+    observer: web_sys::IntersectionObserver,
+}
+
+impl Pausable for Observation {
+    fn pause(&self) {
+        self.observer.unobserve(&self.tracker.element);
+        self.tracker.active.set(false);
+    }
+
+    fn resume(&self) {
+        // The observer reports the current intersection, so the progress is recomputed.
+        self.observer.observe(&self.tracker.element);
+    }
+}
+
+struct Tracker {
+    element: web_sys::Element,
+    opts: ScrollProgressOpts,
+    callback: Box<dyn Fn(f64)>,
+    active: Cell<bool>,
+    last_progress: Cell<Option<f64>>,
+    read_scheduled: Cell<bool>,
+}
+
+impl Tracker {
+    /// Schedule progress computation into the next frame's read phase;
+    /// multiple calls during one frame are coalesced.
+    fn schedule_frame(self: &Rc<Self>) {
+        if self.read_scheduled.replace(true) {
+            return;
+        }
+        let tracker = Rc::downgrade(self);
+        scheduler::dom_read(move |_| {
+            // The tracker doesn't exist when the handle has been dropped.
+            if let Some(tracker) = tracker.upgrade() {
+                tracker.read_scheduled.set(false);
+                tracker.compute();
+            }
+        });
+    }
+
+    fn compute(&self) {
+        let rect = self.element.get_bounding_client_rect();
+        let window = util::window();
+        let (start, size, viewport) = match self.opts.axis {
+            Axis::Vertical => (rect.top(), rect.height(), window.inner_height()),
+            Axis::Horizontal => (rect.left(), rect.width(), window.inner_width()),
+        };
+        let viewport = viewport.ok().and_then(|size| size.as_f64()).unwrap_or(0.);
+
+        let progress = compute_progress(start, size, viewport, &self.opts);
+        if self.last_progress.replace(Some(progress)) != Some(progress) {
+            (self.callback)(progress);
+        }
+    }
+}
+
+/// Observation of one element. Listeners are removed when it's dropped.
+struct Subscription {
+    tracker: Rc<Tracker>,
+    observer: web_sys::IntersectionObserver,
+    _observer_closure: Closure<dyn FnMut(js_sys::Array)>,
+    window_closure: Closure<dyn FnMut(web_sys::Event)>,
+    observation: Option<Rc<dyn Pausable>>,
+}
+
+impl Subscription {
+    fn new(
+        element: &web_sys::Element,
+        opts: ScrollProgressOpts,
+        callback: Box<dyn Fn(f64)>,
+    ) -> Self {
+        let tracker = Rc::new(Tracker {
+            element: element.clone(),
+            opts,
+            callback,
+            active: Cell::new(false),
+            last_progress: Cell::new(None),
+            read_scheduled: Cell::new(false),
+        });
+
+        let window_closure = Closure::new({
+            let tracker = Rc::downgrade(&tracker);
+            move |_: web_sys::Event| {
+                if let Some(tracker) = tracker.upgrade() {
+                    if tracker.active.get() {
+                        tracker.schedule_frame();
+                    }
+                }
+            }
+        });
+        let window = util::window();
+        for event_name in &WINDOW_EVENTS {
+            window
+                .add_event_listener_with_callback(
+                    event_name,
+                    window_closure.as_ref().unchecked_ref(),
+                )
+                .expect("Problem adding scroll progress listener");
+        }
+
+        let observer_closure = Closure::new({
+            let tracker = Rc::downgrade(&tracker);
+            move |entries: js_sys::Array| {
+                if let Some(tracker) = tracker.upgrade() {
+                    let entry = entries.iter().last().and_then(|entry| {
+                        entry.dyn_into::<web_sys::IntersectionObserverEntry>().ok()
+                    });
+                    if let Some(entry) = entry {
+                        tracker.active.set(entry.is_intersecting());
+                        // Compute also when the element leaves, so the last value is 0 or 1.
+                        tracker.schedule_frame();
+                    }
+                }
+            }
+        });
+        let mut observer_init = web_sys::IntersectionObserverInit::new();
+        observer_init.root_margin(OBSERVER_ROOT_MARGIN);
+        let observer = web_sys::IntersectionObserver::new_with_options(
+            observer_closure.as_ref().unchecked_ref(),
+            &observer_init,
+        )
+        .expect("Problem creating IntersectionObserver");
+        observer.observe(element);
+
+        Self {
+            tracker,
+            observer,
+            _observer_closure: observer_closure,
+            window_closure,
+            observation: None,
+        }
+    }
+
+    fn pause_when_hidden(&mut self) {
+        let observation: Rc<dyn Pausable> = Rc::new(Observation {
+            tracker: Rc::clone(&self.tracker),
+            observer: self.observer.clone(),
+        });
+        visibility::register(&observation);
+        self.observation = Some(observation);
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+        let window = util::window();
+        for event_name in &WINDOW_EVENTS {
+            window
+                .remove_event_listener_with_callback(
+                    event_name,
+                    self.window_closure.as_ref().unchecked_ref(),
+                )
+                .expect("Problem removing scroll progress listener");
+        }
+    }
+}
+
+enum State {
+    /// Waiting for the element of an `ElRef`.
+    Pending {
+        opts: ScrollProgressOpts,
+        callback: Box<dyn Fn(f64)>,
+        pause_when_hidden: bool,
+    },
+    Running(Subscription),
+    /// The element of the `ElRef` hasn't been rendered.
+    Idle,
+}
+
+/// Scroll progress subscription. Dropping the handle unsubscribes.
+#[must_use]
+pub struct ScrollProgressHandle {
+    state: Rc<RefCell<State>>,
+}
+
+impl ScrollProgressHandle {
+    /// Start tracking `element`. `callback` is invoked with new progress values.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Unsupported` when `capabilities` don't contain `IntersectionObserver`
+    /// - see `seed::browser::Capabilities`.
+    pub fn new(
+        capabilities: &Capabilities,
+        element: &web_sys::Element,
+        opts: ScrollProgressOpts,
+        callback: impl Fn(f64) + 'static,
+    ) -> Result<Self, Unsupported> {
+        capabilities.require(Capability::IntersectionObserver)?;
+        let subscription = Subscription::new(element, opts, Box::new(callback));
+        Ok(Self {
+            state: Rc::new(RefCell::new(State::Running(subscription))),
+        })
+    }
+
+    /// Create a handle which tracks the element of `el_ref` once the returned `start` is called
+    /// (after the next render). Nothing is tracked when the element isn't rendered at that time.
+    pub(crate) fn for_el_ref<E: JsCast + 'static>(
+        capabilities: &Capabilities,
+        el_ref: &ElRef<E>,
+        opts: ScrollProgressOpts,
+        callback: impl Fn(f64) + 'static,
+    ) -> Result<(Self, impl FnOnce()), Unsupported> {
+        capabilities.require(Capability::IntersectionObserver)?;
+        let state = Rc::new(RefCell::new(State::Pending {
+            opts,
+            callback: Box::new(callback),
+            pause_when_hidden: false,
+        }));
+
+        let start = {
+            let state = Rc::downgrade(&state);
+            let el_ref = el_ref.clone();
+            move || {
+                // The state doesn't exist when the handle has been dropped.
+                let state = match state.upgrade() {
+                    Some(state) => state,
+                    None => return,
+                };
+                let pending = state.replace(State::Idle);
+                if let State::Pending {
+                    opts,
+                    callback,
+                    pause_when_hidden,
+                } = pending
+                {
+                    if let Some(element) = el_ref.get() {
+                        let mut subscription =
+                            Subscription::new(element.unchecked_ref(), opts, callback);
+                        if pause_when_hidden {
+                            subscription.pause_when_hidden();
+                        }
+                        state.replace(State::Running(subscription));
+                    }
+                }
+            }
+        };
+        Ok((Self { state }, start))
+    }
+
+    /// Stop observing the element while the document is hidden - see `seed::browser::visibility`.
+    pub fn pause_when_hidden(self) -> Self {
+        match &mut *self.state.borrow_mut() {
+            State::Pending {
+                pause_when_hidden, ..
+            } => *pause_when_hidden = true,
+            State::Running(subscription) => subscription.pause_when_hidden(),
+            State::Idle => (),
+        }
+        self
+    }
+
+    /// The last computed progress.
+    pub fn progress(&self) -> Option<f64> {
+        match &*self.state.borrow() {
+            State::Running(subscription) => subscription.tracker.last_progress.get(),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Debug for ScrollProgressHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "ScrollProgressHandle")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser::visibility::tests::{reset_hidden, set_hidden};
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn tracker(handle: &ScrollProgressHandle) -> Option<Rc<Tracker>> {
+        match &*handle.state.borrow() {
+            State::Running(subscription) => Some(Rc::clone(&subscription.tracker)),
+            _ => None,
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn scroll_progress_pauses_when_hidden() {
+        let element = util::document().create_element("div").unwrap();
+        util::body().append_child(&element).unwrap();
+        let handle = ScrollProgressHandle::new(
+            &Capabilities::new(),
+            &element,
+            ScrollProgressOpts::default(),
+            |_| (),
+        )
+        .unwrap()
+        .pause_when_hidden();
+        let tracker = tracker(&handle).unwrap();
+        tracker.active.set(true);
+
+        set_hidden(true);
+        assert!(!tracker.active.get());
+        // Scrolling doesn't schedule computations.
+        util::window()
+            .dispatch_event(&web_sys::Event::new("scroll").unwrap())
+            .unwrap();
+        assert!(!tracker.read_scheduled.get());
+
+        set_hidden(false);
+        reset_hidden();
+        element.remove();
+    }
+
+    #[wasm_bindgen_test]
+    fn progress_through_viewport() {
+        let opts = ScrollProgressOpts::default();
+        // Element (height 100) below the viewport (height 500).
+        assert_eq!(compute_progress(600., 100., 500., &opts), 0.);
+        // Leading edge touches viewport's bottom.
+        assert_eq!(compute_progress(500., 100., 500., &opts), 0.);
+        // Halfway: traveled 300 of 600.
+        assert_eq!(compute_progress(200., 100., 500., &opts), 0.5);
+        // Trailing edge touches viewport's top.
+        assert_eq!(compute_progress(-100., 100., 500., &opts), 1.);
+        assert_eq!(compute_progress(-300., 100., 500., &opts), 1.);
+    }
+
+    #[wasm_bindgen_test]
+    fn progress_with_offsets() {
+        let opts = ScrollProgressOpts {
+            axis: Axis::Vertical,
+            offset_start: 100.,
+            offset_end: 100.,
+        };
+        assert_eq!(compute_progress(400., 100., 500., &opts), 0.);
+        assert_eq!(compute_progress(0., 100., 500., &opts), 1.);
+        assert_eq!(compute_progress(200., 100., 500., &opts), 0.5);
+    }
+
+    #[wasm_bindgen_test]
+    fn el_ref_is_resolved_on_start() {
+        let element = util::document().create_element("div").unwrap();
+        util::body().append_child(&element).unwrap();
+        let el_ref = ElRef::<web_sys::HtmlElement>::new();
+
+        let (handle, start) = ScrollProgressHandle::for_el_ref(
+            &Capabilities::new(),
+            &el_ref,
+            ScrollProgressOpts::default(),
+            |_| (),
+        )
+        .unwrap();
+        let handle = handle.pause_when_hidden();
+        assert!(tracker(&handle).is_none());
+        el_ref.shared_node_ws.replace(Some(element.clone().into()));
+        start();
+        assert_eq!(tracker(&handle).unwrap().element, element);
+        match &*handle.state.borrow() {
+            State::Running(subscription) => assert!(subscription.observation.is_some()),
+            _ => panic!("running subscription expected"),
+        }
+
+        // The element isn't rendered.
+        let (handle, start) = ScrollProgressHandle::for_el_ref(
+            &Capabilities::new(),
+            &ElRef::<web_sys::Element>::new(),
+            ScrollProgressOpts::default(),
+            |_| (),
+        )
+        .unwrap();
+        start();
+        assert!(tracker(&handle).is_none());
+        assert_eq!(handle.progress(), None);
+
+        // The handle has been dropped.
+        let (handle, start) = ScrollProgressHandle::for_el_ref(
+            &Capabilities::new(),
+            &el_ref,
+            ScrollProgressOpts::default(),
+            |_| (),
+        )
+        .unwrap();
+        drop(handle);
+        start();
+
+        element.remove();
+    }
+}
@@ -0,0 +1,374 @@
+//! Typed handlers of `date`, `number` and `range` inputs - see `date_input_ev`,
+//! `number_input_ev` and `range_input_ev`.
+//!
+//! Values of these inputs differ across browsers:
+//!
+//! | | Chrome / Edge | Firefox | Safari |
+//! |---|---|---|---|
+//! | `date` - incomplete date | `""` | `""` | `""` (desktop Safari < 14.1 renders a text input - any text) |
+//! | `number` - partial number (`1e`, `-`) | `""` + `validity.badInput` | `""` + `validity.badInput` | the raw text |
+//! | `number` - decimal comma locale | `.` in `value` | `.` in `value` | the raw text, e.g. `1,5` (iOS keypads) |
+//! | `range` - value off `step` | rounded to `step` | rounded to `step` | rounded to `step` |
+//!
+//! The handlers parse `value` into `Option<InputDate>` / `Option<f64>` / `f64`.
+//! Partial values (the user is still typing) don't send any message, so the model keeps
+//! the last complete value and the patch doesn't overwrite what the user is typing.
+//!
+//! Values written by the patch (`At::Value`) are normalized to the format each input type
+//! requires - ISO `YYYY-MM-DD` for `date` and `.` decimals for `number` and `range` -
+//! regardless of the display locale. A `number` input whose text already represents
+//! the new value (e.g. `1.50` for `1.5`) isn't rewritten.
+//!
+//! Browsers round values of `range` inputs to their `step` - keep values of your model
+//! on the step, otherwise the rendered value differs from the model.
+
+use crate::virtual_dom::{Category, Listener};
+use std::fmt;
+use wasm_bindgen::JsCast;
+
+/// The maximum year of `<input type="date">`.
+/// https://html.spec.whatwg.org/multipage/input.html#date-state-(type=date)
+const MAX_YEAR: u32 = 275_760;
+
+// ------ InputDate ------
+
+/// Valid calendar date of a `date` input. Displayed as ISO `YYYY-MM-DD`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InputDate {
+    pub year: u32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl InputDate {
+    /// Returns `None` for dates which don't exist (e.g. `2021-02-29`) or which are out of
+    /// the `date` input's range (years `1` - `275760`).
+    pub fn new(year: u32, month: u32, day: u32) -> Option<Self> {
+        if year < 1 || year > MAX_YEAR || month < 1 || month > 12 {
+            return None;
+        }
+        if day < 1 || day > days_in_month(year, month) {
+            return None;
+        }
+        Some(Self { year, month, day })
+    }
+
+    /// Parse the ISO value `YYYY-MM-DD`. Components without leading zeros (`2020-1-5`)
+    /// are accepted. Empty, partial, non-existing and out-of-range dates are `None`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.trim().split('-');
+        let mut number = || -> Option<u32> {
+            let part = parts.next()?;
+            if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            part.parse().ok()
+        };
+        let (year, month, day) = (number()?, number()?, number()?);
+        if parts.next().is_some() {
+            return None;
+        }
+        Self::new(year, month, day)
+    }
+}
+
+impl fmt::Display for InputDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// ------ NumberValue ------
+
+/// Parsed value of a `number` or `range` input.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum NumberValue {
+    Empty,
+    /// The user hasn't finished typing (e.g. `-`, `1e`) or the text isn't a number.
+    Partial,
+    Number(f64),
+}
+
+impl NumberValue {
+    /// Parse the input's value. Both `.` and `,` are accepted as the decimal separator;
+    /// when both are present, the last one is the decimal separator and the other one
+    /// separates groups of digits (`1.234,5` and `1,234.5` are `1234.5`).
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        if value.is_empty() {
+            return NumberValue::Empty;
+        }
+        let decimal_separator = match (value.rfind('.'), value.rfind(',')) {
+            (Some(dot), Some(comma)) if comma > dot => ',',
+            (Some(_), _) => '.',
+            (None, Some(_)) => ',',
+            (None, None) => '.',
+        };
+        let normalized = value
+            .chars()
+            .filter_map(|c| match c {
+                ',' | '.' if c == decimal_separator => Some('.'),
+                ',' | '.' => None,
+                _ => Some(c),
+            })
+            .collect::<String>();
+        // Rust parses `inf` and `NaN` - number inputs don't.
+        if !normalized
+            .chars()
+            .all(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+        {
+            return NumberValue::Partial;
+        }
+        match normalized.parse::<f64>() {
+            Ok(number) if number.is_finite() => NumberValue::Number(number),
+            _ => NumberValue::Partial,
+        }
+    }
+}
+
+// ------ Handlers ------
+
+fn event_input(event: &web_sys::Event) -> Option<web_sys::HtmlInputElement> {
+    let input = event
+        .target()
+        .and_then(|target| target.dyn_into::<web_sys::HtmlInputElement>().ok());
+    if input.is_none() {
+        crate::error("Typed input handlers have to be attached to an `input` element");
+    }
+    input
+}
+
+fn input_number(input: &web_sys::HtmlInputElement) -> NumberValue {
+    match NumberValue::parse(&input.value()) {
+        // Chrome and Firefox report partial numbers as an empty value.
+        NumberValue::Empty if input.validity().bad_input() => NumberValue::Partial,
+        number => number,
+    }
+}
+
+/// Handle changes of `<input type="date">`. The handler receives `None` when the input
+/// has been cleared; partial dates (a part hasn't been filled in yet) are skipped.
+///
+/// # Example
+///
+/// ```rust,no_run
+///input![
+///    attrs! {At::Type => "date", At::Value => model.due.map(|date| date.to_string()).unwrap_or_default()},
+///    date_input_ev(Msg::DueChanged),
+///]
+/// ```
+pub fn date_input_ev<Ms>(
+    handler: impl FnOnce(Option<InputDate>) -> Ms + 'static + Clone,
+) -> Listener<Ms> {
+    let closure = move |event: web_sys::Event| {
+        let input = event_input(&event)?;
+        let value = input.value();
+        if value.trim().is_empty() {
+            // An incomplete date is reported as an empty value with `badInput`.
+            if input.validity().bad_input() {
+                return None;
+            }
+            return Some((handler.clone())(None));
+        }
+        InputDate::parse(&value).map(|date| (handler.clone())(Some(date)))
+    };
+    Listener::new(
+        "input",
+        Some(Box::new(closure)),
+        Some(Category::Input),
+        None,
+    )
+}
+
+/// Handle changes of `<input type="number">`. The handler receives `None` when the input
+/// has been cleared; partial numbers (`-`, `1e`) are skipped. See `NumberValue::parse`.
+///
+/// # Example
+///
+/// ```rust,no_run
+///input![
+///    attrs! {At::Type => "number", At::Step => "0.1", At::Value => model.amount.to_string()},
+///    number_input_ev(Msg::AmountChanged),
+///]
+/// ```
+pub fn number_input_ev<Ms>(
+    handler: impl FnOnce(Option<f64>) -> Ms + 'static + Clone,
+) -> Listener<Ms> {
+    let closure = move |event: web_sys::Event| match input_number(&event_input(&event)?) {
+        NumberValue::Empty => Some((handler.clone())(None)),
+        NumberValue::Partial => None,
+        NumberValue::Number(number) => Some((handler.clone())(Some(number))),
+    };
+    Listener::new(
+        "input",
+        Some(Box::new(closure)),
+        Some(Category::Input),
+        None,
+    )
+}
+
+/// Handle changes of `<input type="range">` - its value is always a number.
+///
+/// # Example
+///
+/// ```rust,no_run
+///input![
+///    attrs! {At::Type => "range", At::Min => "0", At::Max => "100", At::Value => model.volume.to_string()},
+///    range_input_ev(Msg::VolumeChanged),
+///]
+/// ```
+pub fn range_input_ev<Ms>(handler: impl FnOnce(f64) -> Ms + 'static + Clone) -> Listener<Ms> {
+    let closure = move |event: web_sys::Event| match input_number(&event_input(&event)?) {
+        NumberValue::Number(number) => Some((handler.clone())(number)),
+        NumberValue::Empty | NumberValue::Partial => None,
+    };
+    Listener::new(
+        "input",
+        Some(Box::new(closure)),
+        Some(Category::Input),
+        None,
+    )
+}
+
+// ------ Value syncing ------
+
+/// Normalize `value` written to `input` to the format its type requires.
+/// Returns `None` when the input's current text already represents the value
+/// and mustn't be rewritten.
+pub(crate) fn normalize_value(input: &web_sys::HtmlInputElement, value: &str) -> Option<String> {
+    match input.type_().as_str() {
+        "date" => Some(
+            InputDate::parse(value)
+                .map(|date| date.to_string())
+                .unwrap_or_else(|| value.to_owned()),
+        ),
+        "number" | "range" => match NumberValue::parse(value) {
+            NumberValue::Number(number) => {
+                if input_number(input) == NumberValue::Number(number) {
+                    None
+                } else {
+                    Some(number.to_string())
+                }
+            }
+            NumberValue::Empty => {
+                // Keep the partial number being typed - the model still has no value.
+                if input_number(input) == NumberValue::Partial {
+                    None
+                } else {
+                    Some(String::new())
+                }
+            }
+            NumberValue::Partial => Some(value.to_owned()),
+        },
+        _ => Some(value.to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser::util;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn input(type_: &str) -> web_sys::HtmlInputElement {
+        let input = util::document()
+            .create_element("input")
+            .unwrap()
+            .unchecked_into::<web_sys::HtmlInputElement>();
+        input.set_type(type_);
+        input
+    }
+
+    #[wasm_bindgen_test]
+    fn dates_are_parsed() {
+        assert_eq!(InputDate::parse("2020-02-29"), InputDate::new(2020, 2, 29));
+        assert_eq!(
+            InputDate::parse(" 2020-1-5 ").map(|date| date.to_string()),
+            Some("2020-01-05".to_owned())
+        );
+        assert_eq!(
+            InputDate::parse("275760-09-13").map(|date| date.to_string()),
+            Some("275760-09-13".to_owned())
+        );
+        for value in &[
+            "",
+            "2020",
+            "2020-02",
+            "2020-02-",
+            "2021-02-29",
+            "1900-02-29",
+            "2020-13-01",
+            "2020-04-31",
+            "0000-01-01",
+            "275761-01-01",
+            "+2020-01-01",
+            "2020-01-01-01",
+            "Invalid Date",
+            "01/02/2020",
+        ] {
+            assert_eq!(InputDate::parse(value), None, "{}", value);
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn numbers_are_parsed() {
+        let cases = [
+            ("", NumberValue::Empty),
+            ("  ", NumberValue::Empty),
+            ("1.5", NumberValue::Number(1.5)),
+            ("1,5", NumberValue::Number(1.5)),
+            ("1.", NumberValue::Number(1.)),
+            ("-0,25", NumberValue::Number(-0.25)),
+            ("1.234,5", NumberValue::Number(1234.5)),
+            ("1,234.5", NumberValue::Number(1234.5)),
+            ("1e3", NumberValue::Number(1000.)),
+            ("-", NumberValue::Partial),
+            ("+", NumberValue::Partial),
+            (".", NumberValue::Partial),
+            ("1e", NumberValue::Partial),
+            ("1e-", NumberValue::Partial),
+            ("inf", NumberValue::Partial),
+            ("NaN", NumberValue::Partial),
+            ("1e999", NumberValue::Partial),
+            ("12abc", NumberValue::Partial),
+        ];
+        for (value, expected) in &cases {
+            assert_eq!(NumberValue::parse(value), *expected, "{}", value);
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn values_are_normalized_for_input_types() {
+        let date = input("date");
+        assert_eq!(
+            normalize_value(&date, "2020-1-5"),
+            Some("2020-01-05".to_owned())
+        );
+        assert_eq!(normalize_value(&date, ""), Some(String::new()));
+
+        let number = input("number");
+        assert_eq!(normalize_value(&number, "1,5"), Some("1.5".to_owned()));
+        number.set_value("1.50");
+        // The text being typed already represents the value.
+        assert_eq!(normalize_value(&number, "1.5"), None);
+        assert_eq!(normalize_value(&number, "2"), Some("2".to_owned()));
+        assert_eq!(normalize_value(&number, ""), Some(String::new()));
+
+        let range = input("range");
+        assert_eq!(normalize_value(&range, "7,0"), Some("7".to_owned()));
+
+        let text = input("text");
+        assert_eq!(normalize_value(&text, "1,5"), Some("1,5".to_owned()));
+    }
+}
@@ -0,0 +1,359 @@
+//! Viewport metrics - size, device pixel ratio, named breakpoint, scroll position
+//! and safe-area insets - delivered as one `Viewport` value.
+//!
+//! `resize`, `orientationchange`, `scroll` (optional) and device pixel ratio changes
+//! (a `matchMedia` resolution query) only schedule a read into the next animation frame
+//! (see `seed::dom_read`), so there is at most one read per frame. The callback is invoked
+//! only when the read `Viewport` differs from the previous one.
+//!
+//! Safe-area insets (`env(safe-area-inset-*)`) aren't exposed to scripts directly,
+//! so they are read from the computed padding of an invisible probe element.
+
+use super::scheduler;
+use crate::browser::util::{self, ClosureNew};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+use wasm_bindgen::{closure::Closure, JsCast};
+
+const SAFE_AREA_SIDES: [&str; 4] = ["top", "right", "bottom", "left"];
+
+// ------ ViewportOpts ------
+
+/// Options for `orders.subscribe_viewport`.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct ViewportOpts {
+    /// Minimal viewport widths (in CSS pixels) of named breakpoints, in ascending order -
+    /// e.g. `&[(640, "sm"), (1024, "md")]`. See `Viewport::breakpoint`.
+    pub breakpoints: &'static [(u32, &'static str)],
+    /// Read scroll position on `scroll`. `Viewport::scroll_x` and `scroll_y` are `0`
+    /// when it's `false`.
+    pub track_scroll: bool,
+}
+
+// ------ Viewport ------
+
+/// Safe-area insets in CSS pixels - areas of the viewport covered by notches,
+/// rounded corners or system bars. They are non-zero only with `viewport-fit=cover`.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct SafeAreaInsets {
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+    pub left: f64,
+}
+
+/// Viewport metrics sent by `orders.subscribe_viewport`.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Viewport {
+    /// `window.innerWidth` in CSS pixels.
+    pub width: f64,
+    /// `window.innerHeight` in CSS pixels.
+    pub height: f64,
+    /// `window.devicePixelRatio`.
+    pub dpr: f64,
+    /// The last breakpoint from `ViewportOpts::breakpoints` whose width is lower or equal
+    /// to `width`. `None` if the viewport is narrower than all of them.
+    pub breakpoint: Option<&'static str>,
+    pub scroll_x: f64,
+    pub scroll_y: f64,
+    pub safe_area: SafeAreaInsets,
+}
+
+/// Find the breakpoint of the viewport `width` - see `Viewport::breakpoint`.
+pub fn breakpoint(breakpoints: &[(u32, &'static str)], width: f64) -> Option<&'static str> {
+    breakpoints
+        .iter()
+        .rev()
+        .find(|(min_width, _)| f64::from(*min_width) <= width)
+        .map(|(_, name)| *name)
+}
+
+// ------ ViewportHandle ------
+
+struct Tracker {
+    opts: ViewportOpts,
+    callback: Box<dyn Fn(Viewport)>,
+    probe: web_sys::HtmlElement,
+    last_viewport: Cell<Viewport>,
+    read_scheduled: Cell<bool>,
+    /// Query matching the last device pixel ratio; it's replaced when the ratio changes.
+    resolution_query: RefCell<Option<(f64, web_sys::MediaQueryList)>>,
+    listener: RefCell<Option<Closure<dyn FnMut(web_sys::Event)>>>,
+}
+
+impl Tracker {
+    /// Schedule a read into the next frame's read phase;
+    /// multiple calls during one frame are coalesced.
+    fn schedule_frame(self: &Rc<Self>) {
+        if self.read_scheduled.replace(true) {
+            return;
+        }
+        let tracker = Rc::downgrade(self);
+        scheduler::dom_read(move |_| {
+            // The tracker doesn't exist when the handle has been dropped.
+            if let Some(tracker) = tracker.upgrade() {
+                tracker.read_scheduled.set(false);
+                let viewport = tracker.read();
+                if tracker.last_viewport.replace(viewport) != viewport {
+                    (tracker.callback)(viewport);
+                }
+            }
+        });
+    }
+
+    fn read(&self) -> Viewport {
+        let window = util::window();
+        let size = |size: Result<wasm_bindgen::JsValue, _>| {
+            size.ok().and_then(|size| size.as_f64()).unwrap_or(0.)
+        };
+        let width = size(window.inner_width());
+        let dpr = window.device_pixel_ratio();
+        self.watch_resolution(dpr);
+
+        let (scroll_x, scroll_y) = if self.opts.track_scroll {
+            (
+                window.scroll_x().unwrap_or(0.),
+                window.scroll_y().unwrap_or(0.),
+            )
+        } else {
+            (0., 0.)
+        };
+
+        Viewport {
+            width,
+            height: size(window.inner_height()),
+            dpr,
+            breakpoint: breakpoint(self.opts.breakpoints, width),
+            scroll_x,
+            scroll_y,
+            safe_area: self.read_safe_area(),
+        }
+    }
+
+    fn read_safe_area(&self) -> SafeAreaInsets {
+        let style = match util::window().get_computed_style(&self.probe) {
+            Ok(Some(style)) => style,
+            _ => return SafeAreaInsets::default(),
+        };
+        let padding = |side: &str| {
+            style
+                .get_property_value(&format!("padding-{}", side))
+                .ok()
+                .and_then(|value| value.trim_end_matches("px").parse().ok())
+                .unwrap_or(0.)
+        };
+        SafeAreaInsets {
+            top: padding(SAFE_AREA_SIDES[0]),
+            right: padding(SAFE_AREA_SIDES[1]),
+            bottom: padding(SAFE_AREA_SIDES[2]),
+            left: padding(SAFE_AREA_SIDES[3]),
+        }
+    }
+
+    /// Listen to changes of the device pixel ratio `dpr`
+    /// (e.g. zooming or moving the window to another screen).
+    fn watch_resolution(&self, dpr: f64) {
+        let mut resolution_query = self.resolution_query.borrow_mut();
+        if let Some((old_dpr, _)) = resolution_query.as_ref() {
+            if *old_dpr == dpr {
+                return;
+            }
+        }
+        let listener = self.listener.borrow();
+        let listener = match listener.as_ref() {
+            Some(listener) => listener.as_ref().unchecked_ref(),
+            None => return,
+        };
+        if let Some((_, query)) = resolution_query.take() {
+            query
+                .remove_event_listener_with_callback("change", listener)
+                .expect("Problem removing resolution listener");
+        }
+        if let Ok(Some(query)) = util::window().match_media(&format!("(resolution: {}dppx)", dpr)) {
+            query
+                .add_event_listener_with_callback("change", listener)
+                .expect("Problem adding resolution listener");
+            *resolution_query = Some((dpr, query));
+        }
+    }
+
+    fn window_events(&self) -> &'static [&'static str] {
+        if self.opts.track_scroll {
+            &["resize", "orientationchange", "scroll"]
+        } else {
+            &["resize", "orientationchange"]
+        }
+    }
+}
+
+/// Viewport subscription. Dropping the handle unsubscribes.
+#[must_use]
+pub struct ViewportHandle {
+    tracker: Rc<Tracker>,
+}
+
+impl ViewportHandle {
+    /// Start tracking the viewport. `callback` is invoked with changed `Viewport`s;
+    /// the initial one is available through `viewport`.
+    pub fn new(opts: ViewportOpts, callback: impl Fn(Viewport) + 'static) -> Self {
+        let probe = util::document()
+            .create_element("div")
+            .expect("Problem creating viewport probe")
+            .unchecked_into::<web_sys::HtmlElement>();
+        // CSSOM is used instead of the `style` attribute, so it works also with strict CSP.
+        let style = probe.style();
+        let properties = [
+            ("position", "fixed"),
+            ("top", "0"),
+            ("left", "0"),
+            ("visibility", "hidden"),
+            ("pointer-events", "none"),
+        ];
+        for (name, value) in &properties {
+            style
+                .set_property(name, value)
+                .expect("Problem setting viewport probe style");
+        }
+        for side in &SAFE_AREA_SIDES {
+            style
+                .set_property(
+                    &format!("padding-{}", side),
+                    &format!("env(safe-area-inset-{})", side),
+                )
+                .expect("Problem setting viewport probe style");
+        }
+        util::body()
+            .append_child(&probe)
+            .expect("Problem appending viewport probe");
+
+        let tracker = Rc::new(Tracker {
+            opts,
+            callback: Box::new(callback),
+            probe,
+            last_viewport: Cell::new(Viewport::default()),
+            read_scheduled: Cell::new(false),
+            resolution_query: RefCell::new(None),
+            listener: RefCell::new(None),
+        });
+
+        let listener = Closure::new({
+            let tracker = Rc::downgrade(&tracker);
+            move |_: web_sys::Event| {
+                if let Some(tracker) = tracker.upgrade() {
+                    tracker.schedule_frame();
+                }
+            }
+        });
+        let window = util::window();
+        for event_name in tracker.window_events() {
+            window
+                .add_event_listener_with_callback(event_name, listener.as_ref().unchecked_ref())
+                .expect("Problem adding viewport listener");
+        }
+        tracker.listener.replace(Some(listener));
+
+        // The initial read is synchronous, so the first `Viewport` is available immediately.
+        tracker.last_viewport.set(tracker.read());
+        Self { tracker }
+    }
+
+    /// The last read viewport.
+    pub fn viewport(&self) -> Viewport {
+        self.tracker.last_viewport.get()
+    }
+}
+
+impl Drop for ViewportHandle {
+    fn drop(&mut self) {
+        let listener = match self.tracker.listener.borrow_mut().take() {
+            Some(listener) => listener,
+            None => return,
+        };
+        let window = util::window();
+        for event_name in self.tracker.window_events() {
+            window
+                .remove_event_listener_with_callback(event_name, listener.as_ref().unchecked_ref())
+                .expect("Problem removing viewport listener");
+        }
+        if let Some((_, query)) = self.tracker.resolution_query.borrow_mut().take() {
+            query
+                .remove_event_listener_with_callback("change", listener.as_ref().unchecked_ref())
+                .expect("Problem removing resolution listener");
+        }
+        self.tracker.probe.remove();
+    }
+}
+
+impl std::fmt::Debug for ViewportHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "ViewportHandle")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    const BREAKPOINTS: &[(u32, &str)] = &[(640, "sm"), (1024, "md")];
+
+    #[wasm_bindgen_test]
+    fn breakpoints() {
+        assert_eq!(breakpoint(BREAKPOINTS, 320.), None);
+        assert_eq!(breakpoint(BREAKPOINTS, 640.), Some("sm"));
+        assert_eq!(breakpoint(BREAKPOINTS, 1023.), Some("sm"));
+        assert_eq!(breakpoint(BREAKPOINTS, 1920.), Some("md"));
+        assert_eq!(breakpoint(&[], 1920.), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn reads_are_coalesced_and_deduplicated() {
+        let viewports = Rc::new(RefCell::new(Vec::new()));
+        let handle = ViewportHandle::new(
+            ViewportOpts {
+                breakpoints: BREAKPOINTS,
+                track_scroll: true,
+            },
+            {
+                let viewports = Rc::clone(&viewports);
+                move |viewport| viewports.borrow_mut().push(viewport)
+            },
+        );
+        let initial = handle.viewport();
+        let inner_width = util::window().inner_width().unwrap().as_f64().unwrap();
+        assert_eq!(initial.width, inner_width);
+        assert_eq!(initial.breakpoint, breakpoint(BREAKPOINTS, inner_width));
+        assert!(initial.dpr > 0.);
+
+        let dispatch = |event_name| {
+            util::window()
+                .dispatch_event(&web_sys::Event::new(event_name).unwrap())
+                .unwrap();
+        };
+
+        // Nothing has changed.
+        dispatch("resize");
+        scheduler::flush();
+        assert!(viewports.borrow().is_empty());
+
+        // Events in one frame lead to one read.
+        handle.tracker.last_viewport.set(Viewport::default());
+        dispatch("resize");
+        dispatch("scroll");
+        dispatch("orientationchange");
+        scheduler::flush();
+        assert_eq!(*viewports.borrow(), vec![initial]);
+
+        // The probe is removed and events are ignored after the handle is dropped.
+        let probe = handle.tracker.probe.clone();
+        drop(handle);
+        assert!(!probe.is_connected());
+        dispatch("resize");
+        scheduler::flush();
+        assert_eq!(viewports.borrow().len(), 1);
+    }
+}
@@ -1,7 +1,12 @@
 //! This file contains interactions with `web_sys`.
-
-use super::Namespace;
-use crate::virtual_dom::{At, AtValue, Attrs, El, Node, Style, Text};
+//!
+//! Public functions are the seam between diffing and DOM writing - they can be used
+//! by advanced integrations (e.g. custom renderers - see `seed::virtual_dom::Renderer`).
+//! Children of elements marked by `rendered_by` are skipped - they belong to custom renderers.
+
+use super::{csp, typed_input, Namespace};
+use crate::app::perf_budget::count_dom_mutation;
+use crate::virtual_dom::{inner_html, At, AtValue, Attrs, CSSValue, El, Node, Style, Text};
 use wasm_bindgen::JsCast;
 use web_sys::Document;
 
@@ -14,14 +19,99 @@ fn set_style(el_ws: &web_sys::Node, style: &Style) {
         .expect("Problem setting style");
 }
 
-pub(crate) fn assign_ws_nodes_to_el<Ms>(document: &Document, el: &mut El<Ms>) {
+/// Patch `old` style to `new` through CSSOM - only changed properties are set or removed.
+/// Elements without CSSOM style (e.g. SVG elements) get the whole attribute rewritten.
+fn patch_style(el_ws: &web_sys::Node, old: &Style, new: &Style) {
+    let declaration = match el_ws.dyn_ref::<web_sys::HtmlElement>() {
+        Some(element) => element.style(),
+        None => return set_style(el_ws, new),
+    };
+    for (name, new_value) in &new.vals {
+        if old.vals.get(name) == Some(new_value) {
+            continue;
+        }
+        let result = match new_value {
+            CSSValue::Some(value) => {
+                let value = value.trim_end();
+                if value.ends_with("!important") {
+                    let value = value[..value.len() - "!important".len()].trim_end();
+                    declaration.set_property_with_priority(name.as_str(), value, "important")
+                } else {
+                    declaration.set_property(name.as_str(), value)
+                }
+            }
+            CSSValue::Ignored => declaration.remove_property(name.as_str()).map(drop),
+        };
+        if result.is_err() {
+            crate::error(format!(
+                "Problem setting style property \"{}\"",
+                name.as_str()
+            ));
+        }
+    }
+    for name in old.vals.keys() {
+        if !new.vals.contains_key(name) && declaration.remove_property(name.as_str()).is_err() {
+            crate::error(format!(
+                "Problem removing style property \"{}\"",
+                name.as_str()
+            ));
+        }
+    }
+}
+
+/// Add and remove only changed class tokens - toggling one class of twenty
+/// is one `classList.remove` call, not rewriting the whole attribute.
+fn patch_class(el_ws: &web_sys::Node, old_classes: &str, new_classes: &str) {
+    let class_list = match node_to_element(el_ws) {
+        Ok(element) => element.class_list(),
+        Err(err) => {
+            crate::error(err);
+            return;
+        }
+    };
+    let old_tokens = old_classes.split_whitespace().collect::<Vec<_>>();
+    let new_tokens = new_classes.split_whitespace().collect::<Vec<_>>();
+    for token in &old_tokens {
+        if !new_tokens.contains(token) && class_list.remove_1(token).is_err() {
+            crate::error(format!("Problem removing class \"{}\"", token));
+        }
+    }
+    for token in &new_tokens {
+        if !old_tokens.contains(token) && class_list.add_1(token).is_err() {
+            crate::error(format!("Problem adding class \"{}\"", token));
+        }
+    }
+}
+
+/// Write (or remove) the source attribute. See `seed::debug`.
+#[cfg(all(feature = "debug-tools", debug_assertions))]
+fn set_source(el_ws: &web_sys::Node, source: Option<crate::debug::Source>) {
+    let el_ws = el_ws
+        .dyn_ref::<web_sys::Element>()
+        .expect("Problem casting Node as Element while setting source");
+    match source {
+        Some(source) => el_ws
+            .set_attribute(crate::debug::SOURCE_ATTR, &source.to_string())
+            .expect("Problem setting source attribute"),
+        None => el_ws
+            .remove_attribute(crate::debug::SOURCE_ATTR)
+            .expect("Problem removing source attribute"),
+    }
+}
+
+/// Recursively create `web_sys::Node`s for `el` and its children, and place them in the vdom
+/// Nodes' fields. Created nodes aren't attached to the document - see `attach_el_and_children`.
+pub fn assign_ws_nodes_to_el<Ms>(document: &Document, el: &mut El<Ms>) {
     el.node_ws = Some(make_websys_el(el, document));
+    if el.renderer.is_some() {
+        return;
+    }
     for mut child in &mut el.children {
         assign_ws_nodes(document, &mut child);
     }
 }
 /// Recursively create `web_sys::Node`s, and place them in the vdom Nodes' fields.
-pub(crate) fn assign_ws_nodes<Ms>(document: &Document, node: &mut Node<Ms>)
+pub fn assign_ws_nodes<Ms>(document: &Document, node: &mut Node<Ms>)
 where
     Ms: 'static,
 {
@@ -50,8 +140,14 @@ fn node_to_element(el_ws: &web_sys::Node) -> Result<&web_sys::Element, &'static
 }
 
 fn set_attr_value(el_ws: &web_sys::Node, at: &At, at_value: &AtValue) {
+    // Invalid names would throw a DOM exception; skip them instead of breaking the whole render.
+    if let Err(err) = at.validate() {
+        crate::error(err);
+        return;
+    }
     match at_value {
         AtValue::Some(value) => {
+            csp::check_attr(el_ws, at, value);
             node_to_element(el_ws)
                 .and_then(|element| {
                     element
@@ -87,6 +183,18 @@ fn set_attr_value(el_ws: &web_sys::Node, at: &At, at_value: &AtValue) {
     }
 }
 
+/// `date`, `number` and `range` inputs require locale-independent values
+/// and their partially typed values mustn't be overwritten - see `typed_input`.
+fn set_control_value(el_ws: &web_sys::Node, value: &str) -> Result<(), &'static str> {
+    match el_ws.dyn_ref::<web_sys::HtmlInputElement>() {
+        Some(input) => match typed_input::normalize_value(input, value) {
+            Some(value) => crate::util::set_value(el_ws, &value),
+            None => Ok(()),
+        },
+        None => crate::util::set_value(el_ws, value),
+    }
+}
+
 /// Create and return a `web_sys` Element from our virtual-dom `El`. The `web_sys`
 /// Element is a close analog to JS/DOM elements.
 ///
@@ -94,10 +202,7 @@ fn set_attr_value(el_ws: &web_sys::Node, at: &At, at_value: &AtValue) {
 /// * [`web_sys` Element](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.Element.html)
 /// * [MDN docs](https://developer.mozilla.org/en-US/docs/Web/HTML/Element\)
 /// * See also: [`web_sys` Node](https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.Node.html)
-pub(crate) fn make_websys_el<Ms>(
-    el_vdom: &mut El<Ms>,
-    document: &web_sys::Document,
-) -> web_sys::Node {
+pub fn make_websys_el<Ms>(el_vdom: &mut El<Ms>, document: &web_sys::Document) -> web_sys::Node {
     let tag = el_vdom.tag.as_str();
 
     let el_ws = match el_vdom.namespace {
@@ -126,12 +231,37 @@ pub(crate) fn make_websys_el<Ms>(
         set_style(&el_ws, &el_vdom.style)
     }
 
+    inner_html::drop_children(el_vdom);
+    if let Some(html) = &el_vdom.inner_html {
+        set_inner_html(&el_ws, html);
+    }
+
+    #[cfg(all(feature = "debug-tools", debug_assertions))]
+    {
+        set_source(&el_ws, el_vdom.source);
+        if let Some(renderer) = el_vdom.renderer {
+            el_ws
+                .set_attribute(crate::debug::ownership_guard::RENDERER_ATTR, renderer)
+                .expect("Problem setting renderer attribute");
+        }
+    }
+
     el_ws.into()
 }
 
+/// Replace the content of `el_ws` - see `inner_html`.
+pub fn set_inner_html(el_ws: &web_sys::Node, html: &str) {
+    count_dom_mutation();
+    el_ws
+        .dyn_ref::<web_sys::Element>()
+        .expect("Problem casting Node as Element while setting inner HTML")
+        .set_inner_html(html);
+}
+
 /// Similar to `attach_el_and_children`, but for text nodes
 pub fn attach_text_node(text: &mut Text, parent: &web_sys::Node) {
     let node_ws = text.node_ws.take().expect("Missing websys node for Text");
+    count_dom_mutation();
     parent
         .append_child(&node_ws)
         .expect("Problem appending text node");
@@ -145,6 +275,9 @@ pub fn attach_children<Ms>(el_vdom: &mut El<Ms>) {
         .node_ws
         .as_ref()
         .expect("Missing websys el in attach_children");
+    if el_vdom.renderer.is_some() {
+        return;
+    }
     // appending the its children to the el_ws
     for child in &mut el_vdom.children {
         match child {
@@ -168,6 +301,7 @@ pub fn attach_el_and_children<Ms>(el_vdom: &mut El<Ms>, parent: &web_sys::Node)
         .expect("Missing websys el in attach_el_and_children");
 
     // Append the element
+    count_dom_mutation();
 
     // todo: This error can occur with raw html elements, but am unsure of the cause.
     if parent.append_child(el_ws).is_err() {
@@ -175,12 +309,14 @@ pub fn attach_el_and_children<Ms>(el_vdom: &mut El<Ms>, parent: &web_sys::Node)
     }
 
     // appending the its children to the el_ws
-    for child in &mut el_vdom.children {
-        match child {
-            // Raise the active level once per recursion.
-            Node::Element(child_el) => attach_el_and_children(child_el, el_ws),
-            Node::Text(child_text) => attach_text_node(child_text, el_ws),
-            Node::Empty => (),
+    if el_vdom.renderer.is_none() {
+        for child in &mut el_vdom.children {
+            match child {
+                // Raise the active level once per recursion.
+                Node::Element(child_el) => attach_el_and_children(child_el, el_ws),
+                Node::Text(child_text) => attach_text_node(child_text, el_ws),
+                Node::Empty => (),
+            }
         }
     }
 
@@ -218,6 +354,18 @@ fn set_default_element_state<Ms>(el_ws: &web_sys::Node, el_vdom: &El<Ms>) {
             textarea.set_value(value);
         }
     }
+
+    // The attribute "value" may have been set before "type" - e.g. `1,5` of a `number` input
+    // would be sanitized to an empty value. See `typed_input::normalize_value`.
+    if let Some(input) = el_ws.dyn_ref::<web_sys::HtmlInputElement>() {
+        if let Some(AtValue::Some(value)) = el_vdom.attrs.vals.get(&At::Value) {
+            if let Some(value) = typed_input::normalize_value(input, value) {
+                if value != input.value() {
+                    input.set_value(&value);
+                }
+            }
+        }
+    }
 }
 
 /// Recursively remove all children.
@@ -236,13 +384,25 @@ pub fn patch_el_details<Ms>(old: &mut El<Ms>, new: &mut El<Ms>, old_el_ws: &web_
         (update_actions.actions)(old_el_ws) // todo
     }
 
+    #[cfg(all(feature = "debug-tools", debug_assertions))]
+    {
+        if old.source != new.source {
+            set_source(old_el_ws, new.source);
+        }
+    }
+
     if old.attrs != new.attrs {
         for (key, new_val) in &new.attrs.vals {
             match old.attrs.vals.get(key) {
                 Some(old_val) => {
                     // The value's different
                     if old_val != new_val {
-                        set_attr_value(old_el_ws, key, new_val);
+                        match (key, old_val, new_val) {
+                            (At::Class, AtValue::Some(old_classes), AtValue::Some(new_classes)) => {
+                                patch_class(old_el_ws, old_classes, new_classes)
+                            }
+                            _ => set_attr_value(old_el_ws, key, new_val),
+                        }
                     }
                 }
                 None => {
@@ -254,8 +414,8 @@ pub fn patch_el_details<Ms>(old: &mut El<Ms>, new: &mut El<Ms>, old_el_ws: &web_
             // to use set_value or set_checked.
             match key {
                 At::Value => match new_val {
-                    AtValue::Some(new_val) => crate::util::set_value(old_el_ws, new_val),
-                    AtValue::None | AtValue::Ignored => crate::util::set_value(old_el_ws, ""),
+                    AtValue::Some(new_val) => set_control_value(old_el_ws, new_val),
+                    AtValue::None | AtValue::Ignored => set_control_value(old_el_ws, ""),
                 },
                 At::Checked => match new_val {
                     AtValue::Some(_) | AtValue::None => crate::util::set_checked(old_el_ws, true),
@@ -270,23 +430,13 @@ pub fn patch_el_details<Ms>(old: &mut El<Ms>, new: &mut El<Ms>, old_el_ws: &web_
         // Remove attributes that aren't in the new vdom.
         for name in old.attrs.vals.keys() {
             if new.attrs.vals.get(name).is_none() {
-                // todo get to the bottom of this
-                match old_el_ws.dyn_ref::<web_sys::Element>() {
-                    Some(el) => el
-                        .remove_attribute(name.as_str())
-                        .expect("Removing an attribute"),
-                    None => {
-                        crate::error("Minor error on html element (setting attrs)");
-                    }
-                }
+                set_attr_value(old_el_ws, name, &AtValue::Ignored);
             }
         }
     }
 
-    // Patch style.
     if old.style != new.style {
-        // We can't patch each part of style; rewrite the whole attribute.
-        set_style(old_el_ws, &new.style)
+        patch_style(old_el_ws, &old.style, &new.style)
     }
 }
 
@@ -294,124 +444,8 @@ pub fn patch_el_details<Ms>(old: &mut El<Ms>, new: &mut El<Ms>, old_el_ws: &web_
 impl<Ms> From<&web_sys::Element> for El<Ms> {
     /// Create a vdom node from a `web_sys::Element`. Used in creating elements from html
     /// and markdown strings. Includes children, recursively added.
-    #[allow(clippy::too_many_lines)]
     fn from(ws_el: &web_sys::Element) -> Self {
-        // Result of tag_name is all caps, but tag From<String> expects lower.
-        // Probably is more pure to match by xlmns attribute instead.
-        let mut el = match ws_el.tag_name().to_lowercase().as_ref() {
-            "svg" => El::empty_svg(ws_el.tag_name().to_lowercase().into()),
-            _ => El::empty(ws_el.tag_name().to_lowercase().into()),
-        };
-
-        // Populate attributes
-        let mut attrs = Attrs::empty();
-        ws_el
-            .get_attribute_names()
-            .for_each(&mut |attr_name, _, _| {
-                let attr_name = attr_name
-                    .as_string()
-                    .expect("problem converting attr to string");
-                if let Some(attr_val) = ws_el.get_attribute(&attr_name) {
-                    attrs.add(attr_name.into(), &attr_val);
-                }
-            });
-        el.attrs = attrs;
-
-        // todo This is the same list in `shortcuts::element_svg!`.
-        // todo: Fix this repetition: Use `/scripts/populate_tags.rs`
-        // todo to consolodate these lists.
-        let svg_tags = [
-            "line",
-            "rect",
-            "circle",
-            "ellipse",
-            "polygon",
-            "polyline",
-            "mesh",
-            "path",
-            "defs",
-            "g",
-            "marker",
-            "mask",
-            "pattern",
-            "svg",
-            "switch",
-            "symbol",
-            "unknown",
-            "linear_gradient",
-            "radial_gradient",
-            "mesh_gradient",
-            "stop",
-            "image",
-            "r#use",
-            "altGlyph",
-            "altGlyphDef",
-            "altGlyphItem",
-            "glyph",
-            "glyphRef",
-            "textPath",
-            "text",
-            "tref",
-            "tspan",
-            "clipPath",
-            "cursor",
-            "filter",
-            "foreignObject",
-            "hathpath",
-            "meshPatch",
-            "meshRow",
-            "view",
-            "colorProfile",
-            "animage",
-            "animateColor",
-            "animateMotion",
-            "animateTransform",
-            "discard",
-            "mpath",
-            "set",
-            "desc",
-            "metadata",
-            "title",
-            "feBlend",
-            "feColorMatrix",
-            "feComponentTransfer",
-            "feComposite",
-            "feConvolveMatrix",
-            "feDiffuseLighting",
-            "feDisplacementMap",
-            "feDropShadow",
-            "feFlood",
-            "feFuncA",
-            "feFuncB",
-            "feFuncG",
-            "feFuncR",
-            "feGaussianBlur",
-            "feImage",
-            "feMerge",
-            "feMergeNode",
-            "feMorphology",
-            "feOffset",
-            "feSpecularLighting",
-            "feTile",
-            "feTurbulence",
-            "font",
-            "hkern",
-            "vkern",
-            "hatch",
-            "solidcolor",
-        ];
-
-        if svg_tags.contains(&ws_el.tag_name().to_lowercase().as_str()) {
-            el.namespace = Some(Namespace::Svg);
-        }
-
-        if let Some(ns) = ws_el.namespace_uri() {
-            // Prevent attaching a `xlmns` attribute to normal HTML elements.
-            if ns != "http://www.w3.org/1999/xhtml" {
-                el.namespace = Some(ns.into());
-            }
-        }
-
+        let mut el = el_from_ws_without_children(ws_el);
         let children = ws_el.child_nodes();
         for i in 0..children.length() {
             let child = children
@@ -425,6 +459,132 @@ impl<Ms> From<&web_sys::Element> for El<Ms> {
         el
     }
 }
+
+/// Create a vdom element with the tag, attributes and namespace of `ws_el`.
+#[allow(clippy::too_many_lines)]
+pub(crate) fn el_from_ws_without_children<Ms>(ws_el: &web_sys::Element) -> El<Ms> {
+    // Result of tag_name is all caps, but tag From<String> expects lower.
+    // Probably is more pure to match by xlmns attribute instead.
+    let mut el = match ws_el.tag_name().to_lowercase().as_ref() {
+        "svg" => El::empty_svg(ws_el.tag_name().to_lowercase().into()),
+        _ => El::empty(ws_el.tag_name().to_lowercase().into()),
+    };
+
+    // Populate attributes
+    let mut attrs = Attrs::empty();
+    ws_el
+        .get_attribute_names()
+        .for_each(&mut |attr_name, _, _| {
+            let attr_name = attr_name
+                .as_string()
+                .expect("problem converting attr to string");
+            if let Some(attr_val) = ws_el.get_attribute(&attr_name) {
+                attrs.add(attr_name.into(), &attr_val);
+            }
+        });
+    // Adopted inline styles are patched per property like `style!`.
+    if let Some(AtValue::Some(style)) = attrs.vals.shift_remove(&At::Style) {
+        el.style = Style::parse(&style);
+    }
+    el.attrs = attrs;
+
+    // todo This is the same list in `shortcuts::element_svg!`.
+    // todo: Fix this repetition: Use `/scripts/populate_tags.rs`
+    // todo to consolodate these lists.
+    let svg_tags = [
+        "line",
+        "rect",
+        "circle",
+        "ellipse",
+        "polygon",
+        "polyline",
+        "mesh",
+        "path",
+        "defs",
+        "g",
+        "marker",
+        "mask",
+        "pattern",
+        "svg",
+        "switch",
+        "symbol",
+        "unknown",
+        "linear_gradient",
+        "radial_gradient",
+        "mesh_gradient",
+        "stop",
+        "image",
+        "r#use",
+        "altGlyph",
+        "altGlyphDef",
+        "altGlyphItem",
+        "glyph",
+        "glyphRef",
+        "textPath",
+        "text",
+        "tref",
+        "tspan",
+        "clipPath",
+        "cursor",
+        "filter",
+        "foreignObject",
+        "hathpath",
+        "meshPatch",
+        "meshRow",
+        "view",
+        "colorProfile",
+        "animage",
+        "animateColor",
+        "animateMotion",
+        "animateTransform",
+        "discard",
+        "mpath",
+        "set",
+        "desc",
+        "metadata",
+        "title",
+        "feBlend",
+        "feColorMatrix",
+        "feComponentTransfer",
+        "feComposite",
+        "feConvolveMatrix",
+        "feDiffuseLighting",
+        "feDisplacementMap",
+        "feDropShadow",
+        "feFlood",
+        "feFuncA",
+        "feFuncB",
+        "feFuncG",
+        "feFuncR",
+        "feGaussianBlur",
+        "feImage",
+        "feMerge",
+        "feMergeNode",
+        "feMorphology",
+        "feOffset",
+        "feSpecularLighting",
+        "feTile",
+        "feTurbulence",
+        "font",
+        "hkern",
+        "vkern",
+        "hatch",
+        "solidcolor",
+    ];
+
+    if svg_tags.contains(&ws_el.tag_name().to_lowercase().as_str()) {
+        el.namespace = Some(Namespace::Svg);
+    }
+
+    if let Some(ns) = ws_el.namespace_uri() {
+        // Prevent attaching a `xlmns` attribute to normal HTML elements.
+        if ns != "http://www.w3.org/1999/xhtml" {
+            el.namespace = Some(ns.into());
+        }
+    }
+    el
+}
+
 impl<Ms> From<&web_sys::Element> for Node<Ms> {
     fn from(ws_el: &web_sys::Element) -> Node<Ms> {
         Node::Element(ws_el.into())
@@ -454,12 +614,9 @@ pub fn node_from_ws<Ms>(node: &web_sys::Node) -> Option<Node<Ms>> {
     }
 }
 
-/// Insert a new node into the specified part of the DOM tree.
-pub(crate) fn insert_node(
-    node: &web_sys::Node,
-    parent: &web_sys::Node,
-    next: Option<web_sys::Node>,
-) {
+/// Insert a new node into the specified part of the DOM tree - before `next` or as the last child.
+pub fn insert_node(node: &web_sys::Node, parent: &web_sys::Node, next: Option<web_sys::Node>) {
+    count_dom_mutation();
     match next {
         Some(n) => {
             parent
@@ -472,13 +629,17 @@ pub(crate) fn insert_node(
     };
 }
 
-pub(crate) fn remove_node(node: &web_sys::Node, parent: &web_sys::Node) {
+/// Remove `node` from `parent`. Lifecycle hooks aren't invoked.
+pub fn remove_node(node: &web_sys::Node, parent: &web_sys::Node) {
+    count_dom_mutation();
     parent
         .remove_child(node)
         .expect("Problem removing old el_ws when updating to empty");
 }
 
-pub(crate) fn replace_child(new: &web_sys::Node, old: &web_sys::Node, parent: &web_sys::Node) {
+/// Replace `parent`'s child `old` with `new`.
+pub fn replace_child(new: &web_sys::Node, old: &web_sys::Node, parent: &web_sys::Node) {
+    count_dom_mutation();
     parent
         .replace_child(new, old)
         .expect("Problem replacing element");
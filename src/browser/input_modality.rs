@@ -0,0 +1,220 @@
+//! Tracking of the user's current input modality - keyboard, pointer or touch.
+//!
+//! Heuristics follow the [focus-visible polyfill](https://github.com/WICG/focus-visible):
+//! - `keydown` switches to `Keyboard`, unless it's a modifier-only press
+//!   or a shortcut with `Alt`, `Ctrl` or `Meta`.
+//! - `pointerdown` switches to `Pointer` or `Touch` according to its `pointerType`.
+//! - `touchstart` switches to `Touch`.
+
+use super::util::{self, ClosureNew};
+use wasm_bindgen::{closure::Closure, JsCast};
+
+// ------ Modality ------
+
+/// User's input modality.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Modality {
+    Keyboard,
+    Pointer,
+    Touch,
+}
+
+// ------ ModalityEvent ------
+
+/// Simplified DOM event relevant for modality tracking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModalityEvent {
+    KeyDown {
+        key: String,
+        alt: bool,
+        ctrl: bool,
+        meta: bool,
+    },
+    PointerDown {
+        pointer_type: String,
+    },
+    TouchStart,
+}
+
+impl ModalityEvent {
+    fn from_dom_event(event: &web_sys::Event) -> Option<Self> {
+        match event.type_().as_str() {
+            "keydown" => {
+                event
+                    .dyn_ref::<web_sys::KeyboardEvent>()
+                    .map(|event| ModalityEvent::KeyDown {
+                        key: event.key(),
+                        alt: event.alt_key(),
+                        ctrl: event.ctrl_key(),
+                        meta: event.meta_key(),
+                    })
+            }
+            "pointerdown" => {
+                event
+                    .dyn_ref::<web_sys::PointerEvent>()
+                    .map(|event| ModalityEvent::PointerDown {
+                        pointer_type: event.pointer_type(),
+                    })
+            }
+            "touchstart" => Some(ModalityEvent::TouchStart),
+            _ => None,
+        }
+    }
+}
+
+// ------ ModalityTracker ------
+
+/// Pure modality state machine. It doesn't touch DOM, so it's easy to test.
+#[derive(Debug, Default, Clone)]
+pub struct ModalityTracker {
+    current: Option<Modality>,
+}
+
+impl ModalityTracker {
+    /// Current modality; `None` before the first relevant event.
+    pub fn current(&self) -> Option<Modality> {
+        self.current
+    }
+
+    /// Process the event and return the new modality if it has been changed.
+    pub fn handle(&mut self, event: &ModalityEvent) -> Option<Modality> {
+        let modality = match event {
+            ModalityEvent::KeyDown {
+                key,
+                alt,
+                ctrl,
+                meta,
+            } => {
+                if *alt || *ctrl || *meta || is_modifier_key(key) {
+                    return None;
+                }
+                Modality::Keyboard
+            }
+            ModalityEvent::PointerDown { pointer_type } => match pointer_type.as_str() {
+                "touch" => Modality::Touch,
+                _ => Modality::Pointer,
+            },
+            ModalityEvent::TouchStart => Modality::Touch,
+        };
+        if self.current == Some(modality) {
+            return None;
+        }
+        self.current = Some(modality);
+        Some(modality)
+    }
+}
+
+fn is_modifier_key(key: &str) -> bool {
+    match key {
+        "Shift" | "Control" | "Alt" | "Meta" | "AltGraph" | "CapsLock" | "Fn" | "OS" => true,
+        _ => false,
+    }
+}
+
+// ------ ModalityListeners ------
+
+const EVENT_NAMES: [&str; 3] = ["keydown", "pointerdown", "touchstart"];
+
+/// Capture-phase document listeners. They are removed when dropped.
+pub struct ModalityListeners {
+    closure: Closure<dyn FnMut(web_sys::Event)>,
+}
+
+impl ModalityListeners {
+    /// Add listeners to the document. `handler` is invoked for each relevant event.
+    pub fn new(mut handler: impl FnMut(ModalityEvent) + 'static) -> Self {
+        let closure = Closure::new(move |event: web_sys::Event| {
+            if let Some(event) = ModalityEvent::from_dom_event(&event) {
+                handler(event)
+            }
+        });
+
+        let document = util::document();
+        for event_name in &EVENT_NAMES {
+            document
+                .add_event_listener_with_callback_and_bool(
+                    event_name,
+                    closure.as_ref().unchecked_ref(),
+                    true,
+                )
+                .expect("Problem adding input modality listener");
+        }
+        Self { closure }
+    }
+}
+
+impl Drop for ModalityListeners {
+    fn drop(&mut self) {
+        let document = util::document();
+        for event_name in &EVENT_NAMES {
+            document
+                .remove_event_listener_with_callback_and_bool(
+                    event_name,
+                    self.closure.as_ref().unchecked_ref(),
+                    true,
+                )
+                .expect("Problem removing input modality listener");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn key(key: &str) -> ModalityEvent {
+        ModalityEvent::KeyDown {
+            key: key.to_owned(),
+            alt: false,
+            ctrl: false,
+            meta: false,
+        }
+    }
+
+    fn pointer(pointer_type: &str) -> ModalityEvent {
+        ModalityEvent::PointerDown {
+            pointer_type: pointer_type.to_owned(),
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn type_then_click_then_tab() {
+        let mut tracker = ModalityTracker::default();
+        assert_eq!(tracker.current(), None);
+
+        assert_eq!(tracker.handle(&key("a")), Some(Modality::Keyboard));
+        assert_eq!(tracker.handle(&key("b")), None);
+        assert_eq!(tracker.handle(&pointer("mouse")), Some(Modality::Pointer));
+        assert_eq!(tracker.handle(&key("Tab")), Some(Modality::Keyboard));
+        assert_eq!(tracker.current(), Some(Modality::Keyboard));
+    }
+
+    #[wasm_bindgen_test]
+    fn modifiers_and_shortcuts_are_ignored() {
+        let mut tracker = ModalityTracker::default();
+        tracker.handle(&pointer("pen"));
+
+        assert_eq!(tracker.handle(&key("Shift")), None);
+        assert_eq!(
+            tracker.handle(&ModalityEvent::KeyDown {
+                key: "c".to_owned(),
+                alt: false,
+                ctrl: true,
+                meta: false,
+            }),
+            None
+        );
+        assert_eq!(tracker.current(), Some(Modality::Pointer));
+    }
+
+    #[wasm_bindgen_test]
+    fn touch() {
+        let mut tracker = ModalityTracker::default();
+        assert_eq!(tracker.handle(&pointer("touch")), Some(Modality::Touch));
+        assert_eq!(tracker.handle(&ModalityEvent::TouchStart), None);
+        assert_eq!(tracker.handle(&pointer("mouse")), Some(Modality::Pointer));
+    }
+}
@@ -1,3 +1,6 @@
 pub mod fetch;
 pub mod routing;
 pub mod storage;
+pub mod storage_manager;
+pub mod synced_slice;
+pub mod websocket;
@@ -1,7 +1,10 @@
 //! High-level interface for `web_sys` HTTP requests.
 use std::future::Future;
 
-use gloo_timers::callback::Timeout;
+use crate::browser::{
+    time::{self, TimeoutHandle},
+    Capabilities, Capability, Unsupported,
+};
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json;
 use std::{borrow::Cow, cell::RefCell, collections::HashMap, convert::identity, rc::Rc};
@@ -9,6 +12,12 @@ use wasm_bindgen::JsValue;
 use wasm_bindgen_futures::JsFuture;
 use web_sys;
 
+pub mod cache;
+pub mod endpoint;
+
+pub use cache::{CacheEntry, CachePolicy};
+pub use endpoint::{Endpoint, PathParams};
+
 // ---------- Aliases for foreign types ----------
 
 pub type DomException = web_sys::DomException;
@@ -88,6 +97,19 @@ pub enum FailReason<T> {
 #[derive(Debug, Clone)]
 pub enum RequestError {
     DomException(web_sys::DomException),
+    /// The browser doesn't support `fetch` - see `seed::browser::Capabilities`.
+    Unsupported(Unsupported),
+}
+
+impl RequestError {
+    /// The request hasn't reached the server (e.g. the browser is offline).
+    /// Aborted requests (also by the request's timeout) aren't network errors.
+    pub fn is_network_error(&self) -> bool {
+        match self {
+            RequestError::DomException(exception) => exception.name() != "AbortError",
+            RequestError::Unsupported(_) => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -96,14 +118,41 @@ pub enum DataError {
     SerdeError(Rc<serde_json::Error>, Json),
 }
 
+/// Error returned from typed calls like `Endpoint::call`.
+///
+/// `E` is the type of the error body your API sends together with 4xx / 5xx responses.
+#[derive(Debug, Clone)]
+pub enum FetchError<E = ()> {
+    /// Path template and given path parameters don't match.
+    PathParams(String),
+    /// Request was aborted, timed out, there was network error etc.
+    RequestError(RequestError),
+    /// Response status code is in range 400-599.
+    /// The second field is the deserialized error body, if the server sent one in the expected format.
+    Status(Status, Option<E>),
+    /// Converting body data to the required type failed.
+    DataError(DataError),
+}
+
+impl<E> FetchError<E> {
+    /// See `RequestError::is_network_error`.
+    pub fn is_network_error(&self) -> bool {
+        match self {
+            FetchError::RequestError(error) => error.is_network_error(),
+            _ => false,
+        }
+    }
+}
+
 // ---------- RequestController ----------
 
 #[derive(Debug, Clone)]
 /// It allows to abort request or disable request's timeout.
 /// You can get it by calling method `Request.controller`.
 pub struct RequestController {
-    abort_controller: Rc<web_sys::AbortController>,
-    timeout_handle: Rc<RefCell<Option<Timeout>>>,
+    /// `None` when the browser doesn't support `AbortController`.
+    abort_controller: Rc<Option<web_sys::AbortController>>,
+    timeout_handle: Rc<RefCell<Option<TimeoutHandle>>>,
 }
 
 impl RequestController {
@@ -113,7 +162,9 @@ impl RequestController {
     pub fn abort(&self) {
         // Cancel timeout by dropping it.
         self.timeout_handle.replace(None);
-        self.abort_controller.abort();
+        if let Some(abort_controller) = &*self.abort_controller {
+            abort_controller.abort();
+        }
     }
     /// Disable request's timeout.
     /// Returns error if timeout is already disabled.
@@ -129,9 +180,8 @@ impl RequestController {
 impl Default for RequestController {
     fn default() -> Self {
         Self {
-            abort_controller: Rc::new(
-                web_sys::AbortController::new().expect("fetch: create AbortController - failed"),
-            ),
+            // Requests can't be aborted (nor time out) without `AbortController`.
+            abort_controller: Rc::new(web_sys::AbortController::new().ok()),
             timeout_handle: Rc::new(RefCell::new(None)),
         }
     }
@@ -229,6 +279,32 @@ pub struct Response<T> {
     pub data: T,
 }
 
+impl<T> Response<T> {
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
+
+    /// Read the body as `String`. The body can be read only once.
+    pub async fn text(&self) -> Result<String, FetchError> {
+        let data_error = |js_value_error: JsValue| {
+            FetchError::DataError(DataError::DomException(js_value_error.into()))
+        };
+        let promise = self.raw.text().map_err(data_error)?;
+        let text = JsFuture::from(promise).await.map_err(data_error)?;
+        Ok(text
+            .as_string()
+            .expect("fetch: cannot convert js_value to string"))
+    }
+
+    /// Read the body and deserialize it from JSON. The body can be read only once.
+    pub async fn json<D: DeserializeOwned>(&self) -> Result<D, FetchError> {
+        let text = self.text().await?;
+        serde_json::from_str(&text).map_err(|serde_error| {
+            FetchError::DataError(DataError::SerdeError(Rc::new(serde_error), text))
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ResponseWithDataResult<T> {
     pub raw: web_sys::Response,
@@ -241,7 +317,7 @@ pub struct ResponseWithDataResult<T> {
 /// HTTP Method types.
 ///
 /// [https://developer.mozilla.org/en-US/docs/Web/HTTP/Methods](https://developer.mozilla.org/en-US/docs/Web/HTTP/Methods)
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Method {
     Get,
     Head,
@@ -276,6 +352,38 @@ impl Default for Method {
     }
 }
 
+// ---------- fetch ----------
+
+/// Send `request`. Responses with any status code (incl. 4xx and 5xx) resolve to `Ok` -
+/// check `Response::status`. Network errors and aborted or timed out requests resolve
+/// to `FetchError::RequestError`.
+///
+/// It's lazy - fetching is started when `Future` is executed (e.g. by `orders.perform_cmd`).
+///
+/// # Example
+///
+/// ```rust,no_run
+///orders.perform_cmd(async {
+///    let user = async {
+///        let response = fetch(Request::new("/api/user")).await?;
+///        response.json::<User>().await
+///    };
+///    Msg::UserFetched(user.await)
+///});
+/// ```
+pub async fn fetch(request: Request) -> Result<Response<()>, FetchError> {
+    request
+        .fetch_with_validators(None)
+        .await
+        .result
+        .map(|response| Response {
+            raw: response.raw,
+            status: response.status,
+            data: (),
+        })
+        .map_err(FetchError::RequestError)
+}
+
 // ---------- Request ----------
 
 /// Request is the entry point for all fetch requests.
@@ -288,6 +396,7 @@ pub struct Request {
     method: Method,
     body: Option<JsValue>,
     cache: Option<web_sys::RequestCache>,
+    cache_policy: CachePolicy,
     credentials: Option<web_sys::RequestCredentials>,
     integrity: Option<String>,
     mode: Option<web_sys::RequestMode>,
@@ -296,6 +405,7 @@ pub struct Request {
     referrer_policy: Option<web_sys::ReferrerPolicy>,
     timeout: Option<u32>,
     controller: RequestController,
+    capabilities: Option<Capabilities>,
 }
 
 impl Request {
@@ -331,6 +441,16 @@ impl Request {
         self
     }
 
+    /// Set body to `text`.
+    pub fn body_text(self, text: &str) -> Self {
+        self.body(JsValue::from_str(text))
+    }
+
+    /// Set body to `bytes`.
+    pub fn body_bytes(self, bytes: &[u8]) -> Self {
+        self.body(js_sys::Uint8Array::from(bytes).into())
+    }
+
     /// Serialize a Rust data structure as JSON; eg the payload in a POST request.
     /// _Note_: If you want to setup `Content-Type` header automatically, use method `send_json`.
     pub fn body_json<T: Serialize>(self, body_json: &T) -> Self {
@@ -353,6 +473,25 @@ impl Request {
         self
     }
 
+    /// Set how the response cache is used - see `CachePolicy`.
+    /// Default is `CachePolicy::Bypass`.
+    ///
+    /// _Note_: It's independent of the browser's HTTP cache configured by method `cache`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///// The server answers `304 Not Modified` when the list hasn't changed
+    ///// and `fetch_json` resolves with the cached list.
+    ///fetch::Request::new("/api/items")
+    ///    .cache_policy(CachePolicy::Revalidate)
+    ///    .fetch_json_data(Msg::ItemsFetched)
+    /// ```
+    pub const fn cache_policy(mut self, cache_policy: CachePolicy) -> Self {
+        self.cache_policy = cache_policy;
+        self
+    }
+
     /// https://developer.mozilla.org/en-US/docs/Web/API/Request/credentials
     pub fn credentials(mut self, request_credentials: web_sys::RequestCredentials) -> Self {
         self.credentials = Some(request_credentials);
@@ -414,6 +553,14 @@ impl Request {
         self
     }
 
+    /// Check browser APIs in `capabilities` instead of the current environment -
+    /// e.g. pass `orders.capabilities()` to respect `AppBuilder::capabilities`.
+    /// Requests resolve to `RequestError::Unsupported` without `fetch`.
+    pub fn capabilities(mut self, capabilities: &Capabilities) -> Self {
+        self.capabilities = Some(capabilities.clone());
+        self
+    }
+
     /// Fetch.
     ///
     /// It never fails. Use callback `f` to map `FetchObject<()>`.
@@ -441,80 +588,55 @@ impl Request {
     where
         U: 'static,
     {
-        let fetch_result = self
-            .send_request()
-            .await
-            .map(|raw_response: web_sys::Response| ResponseWithDataResult {
-                status: Status::from(&raw_response),
-                raw: raw_response,
-                data: Ok(()),
-            })
-            .map_err(|js_value_error| RequestError::DomException(js_value_error.into()));
-        Ok(f(FetchObject {
-            request: self,
-            result: fetch_result,
-        }))
+        Ok(f(self.fetch_with_validators(None).await))
     }
 
     /// Same as method `fetch`, but try to convert body to `String` and insert it into `Response` field `data`.
     /// https://developer.mozilla.org/en-US/docs/Web/API/Body/text
+    ///
+    /// It uses the response cache - see `CachePolicy`.
     pub async fn fetch_string<U>(self, f: impl FnOnce(FetchObject<String>) -> U) -> Result<U, U>
     where
         U: 'static,
     {
-        let fetch_object = self.fetch(identity).await.unwrap();
-        let fetch_result = fetch_object.result;
-        let request = fetch_object.request;
+        let cached = match (self.cache_policy, self.method) {
+            (CachePolicy::Revalidate, Method::Get) => cache::cached(&self.url),
+            _ => None,
+        };
+        let fetch_object = self.fetch_with_validators(cached.as_ref()).await;
 
-        let fetch_object = match fetch_result {
-            // There was problem with fetching - just change generic parameter from () to String.
-            Err(request_error) => FetchObject::<String> {
-                request,
-                result: Err(request_error),
-            },
-            Ok(response) => {
-                match response.raw.text() {
-                    // Converting body to String failed.
-                    Err(js_value_error) => FetchObject::<String> {
-                        request,
+        if let (Some(_), Ok(response)) = (&cached, &fetch_object.result) {
+            if response.status.code == 304 {
+                // Not modified - resolve with the cached body.
+                if let Some(entry) = cache::revalidate(&fetch_object.request.url, &response.raw) {
+                    let fetch_object = FetchObject::<String> {
                         result: Ok(ResponseWithDataResult {
-                            raw: response.raw,
-                            status: response.status,
-                            data: Err(DataError::DomException(js_value_error.into())),
+                            // `Response::clone` would clone the body - clone the JS reference instead.
+                            raw: Clone::clone(&response.raw),
+                            status: entry.status,
+                            data: Ok(entry.body),
                         }),
-                    },
-                    Ok(promise) => {
-                        let js_future_result = JsFuture::from(promise).await;
-                        match js_future_result {
-                            // Converting `promise` to `JsFuture` failed.
-                            Err(js_value_error) => FetchObject::<String> {
-                                request,
-                                result: Ok(ResponseWithDataResult {
-                                    raw: response.raw,
-                                    status: response.status,
-                                    data: Err(DataError::DomException(js_value_error.into())),
-                                }),
-                            },
-                            Ok(js_value) => {
-                                // Converting from body.text() result to String should never fail,
-                                // so `expect` should be enough.
-                                let text = js_value
-                                    .as_string()
-                                    .expect("fetch: cannot convert js_value to string");
-                                FetchObject::<String> {
-                                    request,
-                                    result: Ok(ResponseWithDataResult {
-                                        raw: response.raw,
-                                        status: response.status,
-                                        data: Ok(text),
-                                    }),
-                                }
-                            }
-                        }
-                    }
+                        request: fetch_object.request,
+                    };
+                    return Ok(f(fetch_object));
                 }
             }
-        };
+        }
+
+        let fetch_object = Self::read_text(fetch_object).await;
+        let request = &fetch_object.request;
+        if let (CachePolicy::Revalidate, Method::Get) = (request.cache_policy, request.method) {
+            if let Ok(ResponseWithDataResult {
+                raw,
+                status,
+                data: Ok(text),
+            }) = &fetch_object.result
+            {
+                if status.is_ok() {
+                    cache::store(&request.url, raw, status, text);
+                }
+            }
+        }
         Ok(f(fetch_object))
     }
 
@@ -599,8 +721,98 @@ impl Request {
 
     // ------ PRIVATE ------
 
-    async fn send_request(&self) -> Result<web_sys::Response, JsValue> {
-        let request_init = self.init_request_and_start_timeout();
+    /// Check `capability` in the report set by `capabilities` or in the current environment.
+    fn require(&self, capability: Capability) -> Result<(), Unsupported> {
+        match &self.capabilities {
+            Some(capabilities) => capabilities.require(capability),
+            None => Capabilities::new().require(capability),
+        }
+    }
+
+    /// Fetch with conditional headers of the cached response `cached`.
+    async fn fetch_with_validators(self, cached: Option<&CacheEntry>) -> FetchObject<()> {
+        if let Err(unsupported) = self.require(Capability::Fetch) {
+            return FetchObject {
+                request: self,
+                result: Err(RequestError::Unsupported(unsupported)),
+            };
+        }
+        let fetch_result = self
+            .send_request(cached)
+            .await
+            .map(|raw_response: web_sys::Response| ResponseWithDataResult {
+                status: Status::from(&raw_response),
+                raw: raw_response,
+                data: Ok(()),
+            })
+            .map_err(|js_value_error| RequestError::DomException(js_value_error.into()));
+        FetchObject {
+            request: self,
+            result: fetch_result,
+        }
+    }
+
+    /// Convert body of the fetched response to `String`.
+    async fn read_text(fetch_object: FetchObject<()>) -> FetchObject<String> {
+        let fetch_result = fetch_object.result;
+        let request = fetch_object.request;
+
+        match fetch_result {
+            // There was problem with fetching - just change generic parameter from () to String.
+            Err(request_error) => FetchObject::<String> {
+                request,
+                result: Err(request_error),
+            },
+            Ok(response) => {
+                match response.raw.text() {
+                    // Converting body to String failed.
+                    Err(js_value_error) => FetchObject::<String> {
+                        request,
+                        result: Ok(ResponseWithDataResult {
+                            raw: response.raw,
+                            status: response.status,
+                            data: Err(DataError::DomException(js_value_error.into())),
+                        }),
+                    },
+                    Ok(promise) => {
+                        let js_future_result = JsFuture::from(promise).await;
+                        match js_future_result {
+                            // Converting `promise` to `JsFuture` failed.
+                            Err(js_value_error) => FetchObject::<String> {
+                                request,
+                                result: Ok(ResponseWithDataResult {
+                                    raw: response.raw,
+                                    status: response.status,
+                                    data: Err(DataError::DomException(js_value_error.into())),
+                                }),
+                            },
+                            Ok(js_value) => {
+                                // Converting from body.text() result to String should never fail,
+                                // so `expect` should be enough.
+                                let text = js_value
+                                    .as_string()
+                                    .expect("fetch: cannot convert js_value to string");
+                                FetchObject::<String> {
+                                    request,
+                                    result: Ok(ResponseWithDataResult {
+                                        raw: response.raw,
+                                        status: response.status,
+                                        data: Ok(text),
+                                    }),
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    async fn send_request(
+        &self,
+        cached: Option<&CacheEntry>,
+    ) -> Result<web_sys::Response, JsValue> {
+        let request_init = self.init_request_and_start_timeout(cached);
 
         let fetch_promise = web_sys::window()
             .expect("fetch: cannot find window")
@@ -609,7 +821,7 @@ impl Request {
         JsFuture::from(fetch_promise).await.map(Into::into)
     }
 
-    fn init_request_and_start_timeout(&self) -> web_sys::RequestInit {
+    fn init_request_and_start_timeout(&self, cached: Option<&CacheEntry>) -> web_sys::RequestInit {
         let mut init = web_sys::RequestInit::new();
 
         // headers
@@ -619,6 +831,21 @@ impl Request {
                 .append(name.as_str(), value.as_str())
                 .expect("fetch: cannot create header")
         }
+        // conditional headers - explicitly set headers have priority
+        for (name, value) in cached
+            .map(CacheEntry::validator_headers)
+            .unwrap_or_default()
+        {
+            if !self
+                .headers
+                .keys()
+                .any(|header| header.eq_ignore_ascii_case(name))
+            {
+                headers
+                    .append(name, &value)
+                    .expect("fetch: cannot create header")
+            }
+        }
         init.headers(&headers);
 
         // method
@@ -669,14 +896,58 @@ impl Request {
             let abort_controller = self.controller.clone();
             *self.controller.timeout_handle.borrow_mut() = Some(
                 // abort request on timeout
-                Timeout::new(*timeout, move || abort_controller.abort()),
+                time::current_clock()
+                    .set_timeout(*timeout, Box::new(move || abort_controller.abort())),
             );
         }
 
         // controller
         // https://developer.mozilla.org/en-US/docs/Web/API/AbortController/signal
-        init.signal(Some(&self.controller.abort_controller.signal()));
+        if let Some(abort_controller) = &*self.controller.abort_controller {
+            if self.require(Capability::AbortController).is_ok() {
+                init.signal(Some(&abort_controller.signal()));
+            }
+        }
 
         init
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Item {
+        id: u32,
+        name: String,
+    }
+
+    #[wasm_bindgen_test]
+    async fn fetch_json() {
+        let item = Item {
+            id: 1,
+            name: "Seed".to_owned(),
+        };
+        let url = format!(
+            "data:application/json,{}",
+            String::from(js_sys::encode_uri_component(
+                &serde_json::to_string(&item).unwrap()
+            ))
+        );
+        let response = fetch(Request::new(url)).await.unwrap();
+        assert!(response.status().is_ok());
+        assert_eq!(response.json::<Item>().await.unwrap(), item);
+    }
+
+    #[wasm_bindgen_test]
+    async fn fetch_not_found() {
+        let response = fetch(Request::new("/seed-missing-resource")).await.unwrap();
+        assert_eq!(response.status().code, 404);
+        assert!(response.status().is_error());
+    }
+}
@@ -0,0 +1,347 @@
+//! Conditional requests - see `CachePolicy`.
+//!
+//! Bodies of successful `GET` responses with a validator (`ETag` or `Last-Modified`) are stored
+//! in memory, keyed by the request URL. The next request for the same URL with
+//! `CachePolicy::Revalidate` sends the validators in `If-None-Match` / `If-Modified-Since`
+//! headers and when the server answers `304 Not Modified`, the cached body is used
+//! as the response body.
+//!
+//! Only `GET` responses are cached, so the entries are keyed just by URLs. At most
+//! `MAX_CACHE_ENTRIES` responses are kept - storing another one removes the least recently
+//! validated entry. Cached bodies stay in memory until they are evicted - call `clear_cache`
+//! when they shouldn't outlive e.g. the user's session.
+//!
+//! # References
+//! * [MDN docs](https://developer.mozilla.org/en-US/docs/Web/HTTP/Conditional_requests)
+
+use super::Status;
+use crate::browser::time;
+use indexmap::IndexMap;
+use std::cell::RefCell;
+
+/// Max number of cached responses.
+pub const MAX_CACHE_ENTRIES: usize = 100;
+
+thread_local! {
+    /// Entries ordered from the least recently validated one.
+    static CACHE: RefCell<IndexMap<String, CacheEntry>> = RefCell::new(IndexMap::new());
+}
+
+// ------ CachePolicy ------
+
+/// How `Request` uses the response cache. Set it by `Request::cache_policy`.
+///
+/// Only `GET` requests fetched by methods which read the body (`fetch_string`, `fetch_json`,
+/// `Endpoint::call`, ..) use the cache - other requests bypass it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// The cache isn't read or written. It's the default.
+    Bypass,
+    /// The response is stored when it has a validator (`ETag` or `Last-Modified`).
+    /// Requests for cached URLs are conditional - the cached body is reused
+    /// when the server responds with `304 Not Modified`.
+    Revalidate,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        CachePolicy::Bypass
+    }
+}
+
+// ------ CacheEntry ------
+
+/// Cached response. See `cached`.
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    /// Value of the response header `ETag`.
+    pub etag: Option<String>,
+    /// Value of the response header `Last-Modified`.
+    pub last_modified: Option<String>,
+    /// Status of the original (not `304`) response.
+    pub status: Status,
+    pub body: String,
+    /// Time (`Clock::now_ms` of `seed::browser::time::current_clock`) when the server
+    /// confirmed the body the last time.
+    pub validated_at: f64,
+}
+
+impl CacheEntry {
+    /// Conditional request headers - pairs `(name, value)`.
+    pub(crate) fn validator_headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = Vec::new();
+        if let Some(etag) = &self.etag {
+            headers.push(("If-None-Match", etag.clone()));
+        }
+        if let Some(last_modified) = &self.last_modified {
+            headers.push(("If-Modified-Since", last_modified.clone()));
+        }
+        headers
+    }
+}
+
+/// Get the cached response for `url`.
+pub fn cached(url: &str) -> Option<CacheEntry> {
+    CACHE.with(|cache| cache.borrow().get(url).cloned())
+}
+
+/// Remove all cached responses - e.g. on logout, so the next user can't get
+/// the previous user's data by `cached`.
+pub fn clear_cache() {
+    CACHE.with(|cache| cache.borrow_mut().clear());
+}
+
+/// Number of cached responses - see `seed::debug::memory_report`.
+pub(crate) fn len() -> usize {
+    CACHE.with(|cache| cache.borrow().len())
+}
+
+fn header(response: &web_sys::Response, name: &str) -> Option<String> {
+    response.headers().get(name).unwrap_or(None)
+}
+
+/// Store the body of the successful response `raw` or remove the entry
+/// when the response doesn't have any validator.
+/// The least recently validated entry is removed when the cache is full.
+pub(crate) fn store(url: &str, raw: &web_sys::Response, status: &Status, body: &str) {
+    let etag = header(raw, "ETag");
+    let last_modified = header(raw, "Last-Modified");
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        cache.shift_remove(url);
+        if etag.is_none() && last_modified.is_none() {
+            return;
+        }
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            let oldest = cache.keys().next().cloned();
+            if let Some(oldest) = oldest {
+                cache.shift_remove(&oldest);
+            }
+        }
+        cache.insert(
+            url.to_owned(),
+            CacheEntry {
+                etag,
+                last_modified,
+                status: status.clone(),
+                body: body.to_owned(),
+                validated_at: time::current_clock().now_ms(),
+            },
+        );
+    });
+}
+
+/// Refresh the entry confirmed by the `304` response `raw` and return it.
+/// The response may contain updated validators.
+pub(crate) fn revalidate(url: &str, raw: &web_sys::Response) -> Option<CacheEntry> {
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        // Moved to the end - it's the most recently validated entry now.
+        let mut entry = cache.shift_remove(url)?;
+        if let Some(etag) = header(raw, "ETag") {
+            entry.etag = Some(etag);
+        }
+        if let Some(last_modified) = header(raw, "Last-Modified") {
+            entry.last_modified = Some(last_modified);
+        }
+        entry.validated_at = time::current_clock().now_ms();
+        cache.insert(url.to_owned(), entry.clone());
+        Some(entry)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{endpoint::fetch_typed, FetchError, Method, Request};
+    use super::*;
+    use crate::browser::util;
+    use std::rc::Rc;
+    use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    /// Replaces `window.fetch` with a mock which answers with the given responses
+    /// and records conditional headers of the received requests.
+    struct MockServer {
+        requests: Rc<RefCell<Vec<(Option<String>, Option<String>)>>>,
+        original_fetch: JsValue,
+        _fetch: Closure<dyn FnMut(String, JsValue) -> js_sys::Promise>,
+    }
+
+    impl MockServer {
+        /// Responses are `(status, body, headers)`.
+        fn new(responses: Vec<(u16, &'static str, Vec<(&'static str, &'static str)>)>) -> Self {
+            let requests = Rc::new(RefCell::new(Vec::new()));
+            let mut responses = responses.into_iter();
+            let fetch = Closure::wrap(Box::new({
+                let requests = Rc::clone(&requests);
+                move |_: String, init: JsValue| {
+                    let headers: web_sys::Headers = js_sys::Reflect::get(&init, &"headers".into())
+                        .unwrap()
+                        .unchecked_into();
+                    requests.borrow_mut().push((
+                        headers.get("If-None-Match").unwrap(),
+                        headers.get("If-Modified-Since").unwrap(),
+                    ));
+
+                    let (status, body, response_headers) =
+                        responses.next().expect("unexpected request");
+                    let headers = web_sys::Headers::new().unwrap();
+                    for (name, value) in response_headers {
+                        headers.append(name, value).unwrap();
+                    }
+                    let mut init = web_sys::ResponseInit::new();
+                    init.status(status).headers(&headers);
+                    // `304` responses can't have a body.
+                    let body = if status == 304 { None } else { Some(body) };
+                    let response =
+                        web_sys::Response::new_with_opt_str_and_init(body, &init).unwrap();
+                    js_sys::Promise::resolve(&JsValue::from(response))
+                }
+            })
+                as Box<dyn FnMut(String, JsValue) -> js_sys::Promise>);
+
+            let original_fetch = js_sys::Reflect::get(&util::window(), &"fetch".into()).unwrap();
+            js_sys::Reflect::set(&util::window(), &"fetch".into(), fetch.as_ref()).unwrap();
+            clear_cache();
+            Self {
+                requests,
+                original_fetch,
+                _fetch: fetch,
+            }
+        }
+
+        fn requests(&self) -> Vec<(Option<String>, Option<String>)> {
+            self.requests.borrow().clone()
+        }
+    }
+
+    impl Drop for MockServer {
+        fn drop(&mut self) {
+            js_sys::Reflect::set(&util::window(), &"fetch".into(), &self.original_fetch).unwrap();
+            clear_cache();
+        }
+    }
+
+    async fn get(url: &'static str) -> Result<Vec<u32>, FetchError> {
+        fetch_typed(Request::new(url).cache_policy(CachePolicy::Revalidate)).await
+    }
+
+    #[wasm_bindgen_test]
+    async fn not_modified_response_uses_cached_body() {
+        let server = MockServer::new(vec![
+            (200, "[1,2]", vec![("ETag", "\"v1\"")]),
+            (304, "", vec![]),
+        ]);
+
+        assert_eq!(get("/items").await.unwrap(), vec![1, 2]);
+        let validated_at = cached("/items").unwrap().validated_at;
+        assert_eq!(get("/items").await.unwrap(), vec![1, 2]);
+
+        assert_eq!(
+            server.requests(),
+            vec![(None, None), (Some("\"v1\"".to_owned()), None)]
+        );
+        let entry = cached("/items").unwrap();
+        assert_eq!(entry.status.code, 200);
+        assert!(entry.validated_at >= validated_at);
+    }
+
+    #[wasm_bindgen_test]
+    async fn changed_etag_replaces_cached_body() {
+        let server = MockServer::new(vec![
+            (200, "[1]", vec![("ETag", "\"v1\"")]),
+            (200, "[1,2,3]", vec![("ETag", "\"v2\"")]),
+            (304, "", vec![]),
+        ]);
+
+        assert_eq!(get("/items").await.unwrap(), vec![1]);
+        assert_eq!(get("/items").await.unwrap(), vec![1, 2, 3]);
+        assert_eq!(get("/items").await.unwrap(), vec![1, 2, 3]);
+
+        assert_eq!(
+            server.requests(),
+            vec![
+                (None, None),
+                (Some("\"v1\"".to_owned()), None),
+                (Some("\"v2\"".to_owned()), None)
+            ]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn last_modified_validator() {
+        let last_modified = "Wed, 21 Oct 2015 07:28:00 GMT";
+        let server = MockServer::new(vec![
+            (200, "[7]", vec![("Last-Modified", last_modified)]),
+            (304, "", vec![]),
+        ]);
+
+        assert_eq!(get("/items").await.unwrap(), vec![7]);
+        assert_eq!(get("/items").await.unwrap(), vec![7]);
+        assert_eq!(server.requests()[1], (None, Some(last_modified.to_owned())));
+    }
+
+    #[wasm_bindgen_test]
+    async fn responses_without_validator_are_not_cached() {
+        let server = MockServer::new(vec![
+            (200, "[1]", vec![("ETag", "\"v1\"")]),
+            (200, "[2]", vec![]),
+            (200, "[3]", vec![]),
+        ]);
+
+        assert_eq!(get("/items").await.unwrap(), vec![1]);
+        assert_eq!(get("/items").await.unwrap(), vec![2]);
+        assert!(cached("/items").is_none());
+        assert_eq!(get("/items").await.unwrap(), vec![3]);
+        assert_eq!(server.requests()[2], (None, None));
+    }
+
+    #[wasm_bindgen_test]
+    async fn cache_is_bypassed() {
+        let server = MockServer::new(vec![
+            (200, "[1]", vec![("ETag", "\"v1\"")]),
+            (200, "[1]", vec![("ETag", "\"v1\"")]),
+            (200, "[2]", vec![("ETag", "\"v2\"")]),
+        ]);
+
+        assert_eq!(get("/items").await.unwrap(), vec![1]);
+        // The default policy.
+        let items: Vec<u32> = fetch_typed::<_, ()>(Request::new("/items")).await.unwrap();
+        assert_eq!(items, vec![1]);
+        // Non-GET requests.
+        let post = Request::new("/items")
+            .method(Method::Post)
+            .cache_policy(CachePolicy::Revalidate);
+        let items: Vec<u32> = fetch_typed::<_, ()>(post).await.unwrap();
+        assert_eq!(items, vec![2]);
+
+        assert_eq!(server.requests(), vec![(None, None); 3]);
+        assert_eq!(cached("/items").unwrap().etag, Some("\"v1\"".to_owned()));
+    }
+
+    #[wasm_bindgen_test]
+    fn least_recently_validated_entries_are_evicted() {
+        let headers = web_sys::Headers::new().unwrap();
+        headers.append("ETag", "\"v1\"").unwrap();
+        let mut init = web_sys::ResponseInit::new();
+        init.headers(&headers);
+        let raw = web_sys::Response::new_with_opt_str_and_init(None, &init).unwrap();
+        let status = Status::from(&raw);
+        let url = |index: usize| format!("/items/{}", index);
+
+        clear_cache();
+        for index in 0..MAX_CACHE_ENTRIES {
+            store(&url(index), &raw, &status, "[]");
+        }
+        assert!(revalidate(&url(0), &raw).is_some());
+        store(&url(MAX_CACHE_ENTRIES), &raw, &status, "[]");
+
+        assert_eq!(len(), MAX_CACHE_ENTRIES);
+        assert!(cached(&url(0)).is_some());
+        assert!(cached(&url(1)).is_none());
+        assert!(cached(&url(MAX_CACHE_ENTRIES)).is_some());
+        clear_cache();
+    }
+}
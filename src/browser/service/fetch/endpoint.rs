@@ -0,0 +1,252 @@
+//! Typed API endpoints.
+//!
+//! `Endpoint` binds HTTP method, path template, request body type, response type
+//! and error body type together, so they can be declared once (e.g. in a shared `api.rs`)
+//! and all calls go through the same path substitution, serialization and status handling.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//!// api.rs
+//!pub const GET_USER: Endpoint<(), User, ApiError> = Endpoint::get("/users/{id}");
+//!pub const UPDATE_USER: Endpoint<User, User, ApiError> = Endpoint::put("/users/{id}");
+//!
+//!// update
+//!let user_fetched = api::GET_USER.call(&[("id", &user_id)], &[], &());
+//!orders.perform_cmd(async { Msg::UserFetched(user_fetched.await) });
+//! ```
+
+use super::{DataError, FetchError, Method, Request};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{convert::identity, fmt, future::Future, marker::PhantomData, rc::Rc};
+
+/// Path parameters - pairs `(name, value)` substituted into `{name}` placeholders.
+/// Values are URI-encoded.
+pub type PathParams<'a> = &'a [(&'a str, &'a str)];
+
+// ------ Endpoint ------
+
+/// API endpoint with compile-time association of method, path, request and response types.
+///
+/// - `Req` - request body type; use `()` for requests without body.
+/// - `Resp` - successful response body type.
+/// - `E` - error body type sent with 4xx and 5xx responses.
+pub struct Endpoint<Req, Resp, E = ()> {
+    method: Method,
+    path: &'static str,
+    phantom: PhantomData<(Req, Resp, E)>,
+}
+
+impl<Req, Resp, E> Clone for Endpoint<Req, Resp, E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Req, Resp, E> Copy for Endpoint<Req, Resp, E> {}
+
+impl<Req, Resp, E> fmt::Debug for Endpoint<Req, Resp, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Endpoint {} {}", self.method.as_str(), self.path)
+    }
+}
+
+impl<Req, Resp, E> Endpoint<Req, Resp, E> {
+    /// Create a new endpoint. Path template placeholders are written as `{name}`.
+    pub const fn new(method: Method, path: &'static str) -> Self {
+        Self {
+            method,
+            path,
+            phantom: PhantomData,
+        }
+    }
+
+    pub const fn get(path: &'static str) -> Self {
+        Self::new(Method::Get, path)
+    }
+
+    pub const fn post(path: &'static str) -> Self {
+        Self::new(Method::Post, path)
+    }
+
+    pub const fn put(path: &'static str) -> Self {
+        Self::new(Method::Put, path)
+    }
+
+    pub const fn patch(path: &'static str) -> Self {
+        Self::new(Method::Patch, path)
+    }
+
+    pub const fn delete(path: &'static str) -> Self {
+        Self::new(Method::Delete, path)
+    }
+
+    pub const fn method(&self) -> Method {
+        self.method
+    }
+
+    pub const fn path(&self) -> &'static str {
+        self.path
+    }
+}
+
+impl<Req, Resp, E> Endpoint<Req, Resp, E>
+where
+    Req: Serialize,
+    Resp: DeserializeOwned + 'static,
+    E: DeserializeOwned + 'static,
+{
+    /// Create `Request` for this endpoint.
+    /// Use it when you need to configure the request (e.g. add headers) before fetching.
+    ///
+    /// Body is serialized only for methods other than `GET` and `HEAD`.
+    pub fn request(
+        &self,
+        path_params: PathParams,
+        query: PathParams,
+        body: &Req,
+    ) -> Result<Request, FetchError<E>> {
+        let mut url = fill_path_template(self.path, path_params).map_err(FetchError::PathParams)?;
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(
+                &query
+                    .iter()
+                    .map(|(name, value)| format!("{}={}", encode(name), encode(value)))
+                    .collect::<Vec<_>>()
+                    .join("&"),
+            );
+        }
+
+        let request = Request::new(url).method(self.method);
+        Ok(match self.method {
+            Method::Get | Method::Head => request,
+            _ => request.send_json(body),
+        })
+    }
+
+    /// Call the endpoint.
+    ///
+    /// The returned future owns all its data, so it can be passed directly into `orders.perform_cmd`
+    /// (after mapping to your `Msg`).
+    pub fn call(
+        &self,
+        path_params: PathParams,
+        query: PathParams,
+        body: &Req,
+    ) -> impl Future<Output = Result<Resp, FetchError<E>>> {
+        let request = self.request(path_params, query, body);
+        async move { fetch_typed(request?).await }
+    }
+}
+
+/// Fetch given `request` and decode the response into `Resp` or error body `E`.
+pub async fn fetch_typed<Resp, E>(request: Request) -> Result<Resp, FetchError<E>>
+where
+    Resp: DeserializeOwned + 'static,
+    E: DeserializeOwned + 'static,
+{
+    let fetch_object = match request.fetch_string(identity).await {
+        Ok(fetch_object) | Err(fetch_object) => fetch_object,
+    };
+    let response = fetch_object.result.map_err(FetchError::RequestError)?;
+    let text = response.data.map_err(FetchError::DataError)?;
+
+    if response.status.is_error() {
+        let error_body = serde_json::from_str(&text).ok();
+        return Err(FetchError::Status(response.status, error_body));
+    }
+    serde_json::from_str(&text).map_err(|serde_error| {
+        FetchError::DataError(DataError::SerdeError(Rc::new(serde_error), text))
+    })
+}
+
+fn encode(value: &str) -> String {
+    js_sys::encode_uri_component(value).into()
+}
+
+/// Replace `{name}` placeholders in `template` with URI-encoded values from `path_params`.
+/// All occurrences of a repeated placeholder are replaced by the same value.
+///
+/// Returns an error when a placeholder has no value, a parameter isn't used in the template,
+/// or the template contains an unclosed placeholder.
+pub(crate) fn fill_path_template(
+    template: &str,
+    path_params: PathParams,
+) -> Result<String, String> {
+    let mut path = String::with_capacity(template.len());
+    let mut used_params = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        path.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("unclosed placeholder in path template `{}`", template))?;
+        let name = &rest[start + 1..start + end];
+        let value = path_params
+            .iter()
+            .find(|(param_name, _)| *param_name == name)
+            .map(|(_, value)| value)
+            .ok_or_else(|| {
+                format!(
+                    "missing path parameter `{}` for path template `{}`",
+                    name, template
+                )
+            })?;
+        path.push_str(&encode(value));
+        // A placeholder may be repeated - e.g. `/users/{id}/friends/{id}`.
+        if !used_params.contains(&name) {
+            used_params.push(name);
+        }
+        rest = &rest[start + end + 1..];
+    }
+    path.push_str(rest);
+
+    if used_params.len() != path_params.len() {
+        let unused = path_params
+            .iter()
+            .map(|(name, _)| *name)
+            .filter(|name| !used_params.contains(name))
+            .collect::<Vec<_>>();
+        return Err(format!(
+            "path template `{}` expects {} parameter(s), got {} (unused: {:?})",
+            template,
+            used_params.len(),
+            path_params.len(),
+            unused
+        ));
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn fill_path_template_substitutes_and_encodes() {
+        let path = fill_path_template(
+            "/users/{id}/posts/{post_id}",
+            &[("post_id", "hello world"), ("id", "42")],
+        );
+        assert_eq!(path, Ok("/users/42/posts/hello%20world".to_owned()));
+    }
+
+    #[wasm_bindgen_test]
+    fn fill_path_template_substitutes_repeated_placeholders() {
+        let path = fill_path_template("/{id}/edit/{id}", &[("id", "7")]);
+        assert_eq!(path, Ok("/7/edit/7".to_owned()));
+    }
+
+    #[wasm_bindgen_test]
+    fn fill_path_template_checks_params() {
+        assert!(fill_path_template("/users/{id}", &[]).is_err());
+        assert!(fill_path_template("/users/{id}", &[("id", "1"), ("other", "2")]).is_err());
+        assert!(fill_path_template("/users/{id", &[("id", "1")]).is_err());
+        assert_eq!(fill_path_template("/users", &[]), Ok("/users".to_owned()));
+    }
+}
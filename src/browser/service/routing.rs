@@ -3,18 +3,66 @@ use super::super::{
     util::{self, ClosureNew},
     Url,
 };
+use serde::Serialize;
+use std::cell::RefCell;
 use std::convert::{identity, TryFrom, TryInto};
 use wasm_bindgen::{closure::Closure, JsCast, JsValue};
 
+pub mod form_interceptor;
+pub mod navigation;
+pub mod route_matcher;
+
+pub use form_interceptor::{setup_form_listener, FormDataValue, FormSubmission};
+pub use navigation::{
+    resolve_navigation, HistoryOp, LinkClick, NavigationOutcome, NavigationSource, RoutingCfg,
+};
+pub use route_matcher::{RouteMatcher, RouteParams, Router};
+
 /// Add a new route using history's `push_state` method.
 ///
+/// _Note:_ The app's base path (see `AppBuilder::base_path`) isn't prepended -
+/// use `orders.push_route` in apps with a base path.
+///
 /// # Refenences
 /// * [MDN docs](https://developer.mozilla.org/en-US/docs/Web/API/History_API)
 pub fn push_route<U: Into<Url>>(url: U) -> Url {
+    push_route_with_base_path(&[], url)
+}
+
+/// `push_route` with `base_path` prepended to the pushed path.
+pub(crate) fn push_route_with_base_path<U: Into<Url>>(base_path: &[String], url: U) -> Url {
     let url = url.into();
+    let (data, title, path) = history_entry(base_path, &url);
+    util::history()
+        .push_state_with_url(&data, title, Some(&path))
+        .expect("Problem pushing state");
+    url
+}
+
+/// Similar to `push_route`, but the current history entry is replaced
+/// by history's `replace_state` method.
+///
+/// # Refenences
+/// * [MDN docs](https://developer.mozilla.org/en-US/docs/Web/API/History/replaceState)
+pub fn replace_route<U: Into<Url>>(url: U) -> Url {
+    replace_route_with_base_path(&[], url)
+}
+
+/// `replace_route` with `base_path` prepended to the path.
+pub(crate) fn replace_route_with_base_path<U: Into<Url>>(base_path: &[String], url: U) -> Url {
+    let url = url.into();
+    let (data, title, path) = history_entry(base_path, &url);
+    util::history()
+        .replace_state_with_url(&data, title, Some(&path))
+        .expect("Problem replacing state");
+    url
+}
+
+/// State data, title and path of the history entry for `url`.
+fn history_entry<'a>(base_path: &[String], url: &'a Url) -> (JsValue, &'a str, String) {
     // We use data to evaluate the path instead of the path displayed in the url.
     let data =
-        JsValue::from_str(&serde_json::to_string(&url).expect("Problem serializing route data"));
+        JsValue::from_str(&serde_json::to_string(url).expect("Problem serializing route data"));
 
     // title is currently unused by Firefox.
     let title = match &url.title {
@@ -24,45 +72,49 @@ pub fn push_route<U: Into<Url>>(url: U) -> Url {
 
     // Prepending / means replace
     // the existing path. Not doing so will add the path to the existing one.
-    let mut path = String::from("/") + &url.path.join("/");
-    if let Some(search) = &url.search {
-        path = path + "?" + search;
-    }
-
-    if let Some(hash) = &url.hash {
-        path = path + "#" + hash;
-    }
+    let path = url.relative_url(url::absolute_path(base_path, &url.path));
+    (data, title, path)
+}
 
-    util::history()
-        .push_state_with_url(&data, title, Some(&path))
-        .expect("Problem pushing state");
-    url
+/// Similar to `push_route`, but `state` is attached to the new history entry.
+/// `routes` receive it in `Url::state` when the user navigates back or forward to the entry.
+///
+/// # Example
+///
+/// ```rust,no_run
+///seed::push_route_with_state(vec!["editor"], &Draft { text: model.text.clone() });
+/// ```
+///
+/// # Panics
+///
+/// Panics when `state` can't be serialized to JSON.
+pub fn push_route_with_state<U: Into<Url>>(url: U, state: &impl Serialize) -> Url {
+    let mut url = url.into();
+    url.state = Some(serde_json::to_value(state).expect("Problem serializing route state"));
+    push_route(url)
 }
 
 /// Add a listener that handles routing for navigation events like forward and back.
-pub fn setup_popstate_listener<Ms>(
-    update: impl Fn(Ms) + 'static,
+/// `navigate` handles the `NavigationSource::PopState` - see `resolve_navigation`.
+/// `base_path` is removed from URLs of entries which haven't been pushed by Seed.
+pub fn setup_popstate_listener(
+    base_path: Vec<String>,
+    navigate: impl Fn(NavigationSource) -> bool + 'static,
     updated_listener: impl Fn(Closure<dyn FnMut(web_sys::Event)>) + 'static,
-    routes: fn(Url) -> Option<Ms>,
-) where
-    Ms: 'static,
-{
+) {
     let closure = Closure::new(move |ev: web_sys::Event| {
         let ev = ev
             .dyn_ref::<web_sys::PopStateEvent>()
             .expect("Problem casting as Popstate event");
 
-        let url = match ev.state().as_string() {
-            Some(state_str) => {
-                serde_json::from_str(&state_str).expect("Problem deserializing popstate state")
-            }
-            // Only update when requested for an update by the user.
-            None => url::current(),
-        };
+        let url = ev
+            .state()
+            .as_string()
+            .and_then(|state_str| serde_json::from_str(&state_str).ok())
+            // The entry hasn't been pushed by Seed (e.g. its state is `null`).
+            .unwrap_or_else(|| url::current_without_base_path(&base_path));
 
-        if let Some(routing_msg) = routes(url) {
-            update(routing_msg);
-        }
+        navigate(NavigationSource::PopState(url));
     });
 
     (util::window().as_ref() as &web_sys::EventTarget)
@@ -73,14 +125,11 @@ pub fn setup_popstate_listener<Ms>(
 }
 
 /// Add a listener that handles routing when the url hash is changed.
-pub fn setup_hashchange_listener<Ms>(
-    update: impl Fn(Ms) + 'static,
+/// `navigate` handles the `NavigationSource::HashChange` - see `resolve_navigation`.
+pub fn setup_hashchange_listener(
+    navigate: impl Fn(NavigationSource) -> bool + 'static,
     updated_listener: impl Fn(Closure<dyn FnMut(web_sys::Event)>) + 'static,
-    routes: fn(Url) -> Option<Ms>,
-) where
-    Ms: 'static,
-{
-    // todo: DRY with popstate listener
+) {
     let closure = Closure::new(move |ev: web_sys::Event| {
         let ev = ev
             .dyn_ref::<web_sys::HashChangeEvent>()
@@ -91,9 +140,7 @@ pub fn setup_hashchange_listener<Ms>(
             .try_into()
             .expect("cast hashchange event url to `Url`");
 
-        if let Some(routing_msg) = routes(url) {
-            update(routing_msg);
-        }
+        navigate(NavigationSource::HashChange(url));
     });
 
     (util::window().as_ref() as &web_sys::EventTarget)
@@ -103,57 +150,131 @@ pub fn setup_hashchange_listener<Ms>(
     updated_listener(closure);
 }
 
+thread_local! {
+    /// Mount points of apps with link listeners in the order of their registration.
+    static LINK_SCOPES: RefCell<Vec<web_sys::Element>> = RefCell::new(Vec::new());
+}
+
+/// Links with this attribute (or with `rel="external"`) are never routed by Seed.
+pub const EXTERNAL_LINK_ATTR: &str = "data-seed-external";
+
 /// Set up a listener that intercepts clicks on elements containing an Href attribute,
 /// so we can prevent page refresh for internal links, and route internally.  Run this on load.
-#[allow(clippy::option_map_unit_fn)]
-pub fn setup_link_listener<Ms>(update: impl Fn(Ms) + 'static, routes: fn(Url) -> Option<Ms>)
-where
-    Ms: 'static,
-{
+///
+/// Clicks are left to the browser when:
+/// - A modifier key (Ctrl, Cmd, Shift or Alt) is pressed or the button isn't the primary one.
+/// - The link has a `target` other than `_self` or the `download` attribute.
+/// - The link has the attribute `data-seed-external` or `rel="external"`.
+/// - The `href` isn't an absolute path (e.g. `mailto:` links) or it points to another origin.
+/// - The path is outside of the base path (see `AppBuilder::base_path`).
+///
+/// When more apps on the page route links, each link is routed only by the app with the innermost
+/// mount point containing it. Links outside of all mount points are routed by the first app.
+///
+/// `navigate` handles the `NavigationSource::LinkClick` (see `resolve_navigation`) and returns
+/// `true` when the page load has to be prevented.
+pub fn setup_link_listener(
+    mount_point: web_sys::Element,
+    navigate: impl Fn(NavigationSource) -> bool + 'static,
+    updated_listener: impl Fn(Closure<dyn FnMut(web_sys::Event)>) + 'static,
+) {
+    LINK_SCOPES.with(|scopes| scopes.borrow_mut().push(mount_point.clone()));
+
     let closure = Closure::new(move |event: web_sys::Event| {
-        event.target()
-            .and_then(|et| et.dyn_into::<web_sys::Element>().ok())
-            .and_then(|el| el.closest("[href]").ok())
-            .and_then(identity)  // Option::flatten not stable (https://github.com/rust-lang/rust/issues/60258)
-            .and_then(|href_el| match href_el.tag_name().as_str() {
-                // Base and Link tags use href for something other than navigation.
-                "Base" | "Link" => None,
-                _ => Some(href_el)
-            })
-            .and_then(|href_el| href_el.get_attribute("href"))
-            // The first character being / or empty href indicates a rel link, which is what
-            // we're intercepting.
-            // @TODO: Resolve it properly, see Elm implementation:
-            // @TODO: https://github.com/elm/browser/blob/9f52d88b424dd12cab391195d5b090dd4639c3b0/src/Elm/Kernel/Browser.js#L157
-            .and_then(|href| {
-                if href.is_empty() || href.starts_with('/') {
-                    Some(href)
-                } else {
-                    None
-                }
-            })
-            .map(|href| {
-                // @TODO should be empty href ignored?
-                if href.is_empty() {
-                    event.prevent_default(); // Prevent page refresh
-                } else {
-                    // Only update when requested for an update by the user.
-                    let url = Url::try_from(href).expect("cast link href to `Url`");
-                    if let Some(redirect_msg) = routes(url.clone()) {
-                        // Route internally, overriding the default history
-                        push_route(url);
-                        event.prevent_default(); // Prevent page refresh
-                        update(redirect_msg);
-                    }
-                }
-            });
+        // `Option::flatten` (`identity`) not stable (https://github.com/rust-lang/rust/issues/60258)
+        let click = event.dyn_ref::<web_sys::MouseEvent>().and_then(|event| {
+            event
+                .target()
+                .and_then(|et| et.dyn_into::<web_sys::Element>().ok())
+                .and_then(|el| el.closest("[href]").ok())
+                .and_then(identity)
+                .filter(|link| owns_link(&mount_point, link))
+                .map(|link| link_click(event, &link))
+        });
+        if let Some(click) = click {
+            if navigate(NavigationSource::LinkClick(click)) {
+                event.prevent_default(); // Prevent page refresh
+            }
+        }
     });
 
     (util::document().as_ref() as &web_sys::EventTarget)
         .add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())
         .expect("Problem setting up link interceptor");
 
-    closure.forget(); // todo: Can we store the closure somewhere to avoid using forget?
+    updated_listener(closure);
+}
+
+/// Remove the link listener of the app mounted to `mount_point` - see `setup_link_listener`.
+pub(crate) fn remove_link_listener(
+    mount_point: &web_sys::Element,
+    closure: &Closure<dyn FnMut(web_sys::Event)>,
+) {
+    LINK_SCOPES.with(|scopes| {
+        scopes
+            .borrow_mut()
+            .retain(|scope| !scope.is_same_node(Some(mount_point)))
+    });
+    (util::document().as_ref() as &web_sys::EventTarget)
+        .remove_event_listener_with_callback("click", closure.as_ref().unchecked_ref())
+        .expect("Problem removing link interceptor");
+}
+
+/// Is `link` routed by the app mounted to `mount_point`? See `setup_link_listener`.
+fn owns_link(mount_point: &web_sys::Element, link: &web_sys::Element) -> bool {
+    LINK_SCOPES.with(|scopes| {
+        let scopes = scopes.borrow();
+        let owner = scopes
+            .iter()
+            .filter(|scope| scope.contains(Some(link.as_ref())))
+            // The innermost scope is contained by all other scopes containing the link.
+            .fold(None, |innermost: Option<&web_sys::Element>, scope| {
+                match innermost {
+                    Some(innermost) if !innermost.contains(Some(scope.as_ref())) => Some(innermost),
+                    _ => Some(scope),
+                }
+            })
+            .or_else(|| scopes.first());
+        owner.map_or(false, |owner| owner.is_same_node(Some(mount_point)))
+    })
+}
+
+/// Read the click on `link` for `resolve_navigation`.
+fn link_click(event: &web_sys::MouseEvent, link: &web_sys::Element) -> LinkClick {
+    let href = link.get_attribute("href").unwrap_or_default();
+    // Protocol-relative URLs (`//example.com/page`) may point to another origin.
+    let same_origin = href.starts_with('/') && {
+        let location = util::window().location();
+        location
+            .href()
+            .ok()
+            .and_then(|base| web_sys::Url::new_with_base(&href, &base).ok())
+            .map_or(false, |resolved| {
+                let is_http = resolved.protocol() == "http:" || resolved.protocol() == "https:";
+                is_http && Some(resolved.origin()) == location.origin().ok()
+            })
+    };
+    let url = if href.starts_with('/') {
+        Url::try_from(href.clone()).ok()
+    } else {
+        None
+    };
+    LinkClick {
+        url,
+        button: event.button(),
+        modifier_key: event.ctrl_key() || event.meta_key() || event.shift_key() || event.alt_key(),
+        default_prevented: event.default_prevented(),
+        tag_name: link.tag_name(),
+        target: link.get_attribute("target"),
+        download: link.has_attribute("download"),
+        external: link.has_attribute(EXTERNAL_LINK_ATTR)
+            || link.get_attribute("rel").map_or(false, |rel| {
+                rel.split_ascii_whitespace()
+                    .any(|token| token.eq_ignore_ascii_case("external"))
+            }),
+        same_origin,
+        href,
+    }
 }
 
 #[cfg(test)]
@@ -171,6 +292,7 @@ mod tests {
             hash: None,
             search: None,
             title: None,
+            state: None,
         };
 
         let actual: Url = "/path1/path2".to_string().try_into().unwrap();
@@ -184,6 +306,7 @@ mod tests {
             hash: Some("hash".into()),
             search: Some("search=query".into()),
             title: None,
+            state: None,
         };
 
         let actual: Url = "/path?search=query#hash".to_string().try_into().unwrap();
@@ -197,12 +320,234 @@ mod tests {
             hash: Some("hash".into()),
             search: None,
             title: None,
+            state: None,
         };
 
         let actual: Url = "/path#hash".to_string().try_into().unwrap();
         assert_eq!(expected, actual)
     }
 
+    #[wasm_bindgen_test]
+    fn url_builder() {
+        let url = Url::default()
+            .path(&["users", "Jane Doe"])
+            .search_param("q", "rust & wasm")
+            .search_param("page", "2")
+            .hash("top");
+        let serialized = url.to_string();
+        assert_eq!(
+            serialized,
+            "/users/Jane%20Doe?q=rust%20%26%20wasm&page=2#top"
+        );
+
+        let parsed: Url = serialized.try_into().unwrap();
+        assert_eq!(parsed, url);
+        assert_eq!(parsed.search_params()["q"], vec!["rust & wasm"]);
+
+        assert_eq!(Url::default().to_string(), "/");
+    }
+
+    /// Paths with reserved characters, unicode and `+`, generated by a seeded xorshift,
+    /// so failures are reproducible. Dot segments (`.` and `..`) are excluded - browsers
+    /// normalize them even when they are percent-encoded.
+    fn generated_paths(count: usize) -> Vec<Vec<String>> {
+        const PARTS: [&str; 24] = [
+            "a", "Z", "0", " ", "/", "%", "%2F", "+", "?", "#", "&", "=", ":", "@", ";", ",", "'",
+            "\"", "é", "日本", "🦀", ".", "~", "!",
+        ];
+        let mut state: u32 = 0x9E37_79B9;
+        let mut next = |max: usize| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as usize % max
+        };
+        let mut paths = Vec::new();
+        for _ in 0..count {
+            let mut path = Vec::new();
+            for _ in 0..=next(3) {
+                let mut segment = String::new();
+                for _ in 0..=next(4) {
+                    segment.push_str(PARTS[next(PARTS.len())]);
+                }
+                if segment.chars().all(|c| c == '.') {
+                    segment.push('a');
+                }
+                path.push(segment);
+            }
+            paths.push(path);
+        }
+        paths
+    }
+
+    #[wasm_bindgen_test]
+    fn path_segments_round_trip() {
+        let original_url = util::window().location().href().unwrap();
+
+        for path in generated_paths(200) {
+            let url = Url::default().path(&path);
+            let serialized = url.to_string();
+
+            // Links, `hashchange` and forms.
+            let parsed: Url = serialized.clone().try_into().unwrap();
+            assert_eq!(parsed.path, path, "parsed `{}`", serialized);
+            assert_eq!(parsed.to_string(), serialized);
+
+            // `push_route` and `url::current`.
+            push_route(url);
+            assert_eq!(url::current().path, path, "current `{}`", serialized);
+
+            // `popstate`.
+            let state = util::history().state().unwrap().as_string().unwrap();
+            let url: Url = serde_json::from_str(&state).unwrap();
+            assert_eq!(url.path, path, "state of `{}`", serialized);
+        }
+
+        assert_eq!(
+            Url::default().path(&["Q3/Q4", "a+b"]).to_string(),
+            "/Q3%2FQ4/a%2Bb"
+        );
+        util::history()
+            .replace_state_with_url(&JsValue::NULL, "", Some(&original_url))
+            .unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn route_state() {
+        #[derive(Debug, PartialEq, serde::Deserialize, Serialize)]
+        struct Draft {
+            text: String,
+            scroll_y: f64,
+        }
+
+        let original_url = util::window().location().href().unwrap();
+        let draft = Draft {
+            text: "Hello".to_owned(),
+            scroll_y: 120.,
+        };
+        push_route_with_state(vec!["editor"], &draft);
+
+        let state = util::history().state().unwrap().as_string().unwrap();
+        let url: Url = serde_json::from_str(&state).unwrap();
+        assert_eq!(url.path, vec!["editor"]);
+        assert_eq!(url.state::<Draft>(), Some(draft));
+        assert_eq!(url.state::<String>(), None);
+
+        // Entries pushed by non-Seed code don't have any state.
+        let url: Url = "/editor".to_string().try_into().unwrap();
+        assert_eq!(url.state::<Draft>(), None);
+
+        util::history()
+            .replace_state_with_url(&JsValue::NULL, "", Some(&original_url))
+            .unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn base_path() {
+        let original_url = util::window().location().href().unwrap();
+        let base_path = vec!["admin".to_owned()];
+
+        push_route_with_base_path(&base_path, vec!["users", "1"]);
+        assert_eq!(
+            util::window().location().pathname().unwrap(),
+            "/admin/users/1"
+        );
+        assert_eq!(
+            url::current_without_base_path(&base_path).path,
+            vec!["users", "1"]
+        );
+        assert_eq!(url::current().path, vec!["admin", "users", "1"]);
+
+        replace_route_with_base_path(&base_path, vec!["users", "2"]);
+        assert_eq!(
+            util::window().location().pathname().unwrap(),
+            "/admin/users/2"
+        );
+
+        // Outside of the base path.
+        let other_base_path = vec!["shop".to_owned()];
+        assert_eq!(
+            url::current_without_base_path(&other_base_path).path,
+            vec!["admin", "users", "2"]
+        );
+
+        util::history()
+            .replace_state_with_url(&JsValue::NULL, "", Some(&original_url))
+            .unwrap();
+    }
+
+    fn link(html: &str) -> web_sys::Element {
+        let container = util::document().create_element("div").unwrap();
+        container.set_inner_html(html);
+        container.first_element_child().unwrap()
+    }
+
+    fn click(init: &web_sys::MouseEventInit) -> web_sys::MouseEvent {
+        web_sys::MouseEvent::new_with_mouse_event_init_dict("click", init).unwrap()
+    }
+
+    /// `href` of the clicked `link` if the click can be routed.
+    fn routable_href(event: &web_sys::MouseEvent, link: &web_sys::Element) -> Option<String> {
+        let click = link_click(event, link);
+        if click.is_routable() {
+            Some(click.href)
+        } else {
+            None
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn link_click_with_modifiers() {
+        let link = link(r#"<a href="/page">Page</a>"#);
+        let plain = click(&web_sys::MouseEventInit::new());
+        assert_eq!(routable_href(&plain, &link), Some("/page".to_owned()));
+
+        let modified_clicks = vec![
+            web_sys::MouseEventInit::new().ctrl_key(true).clone(),
+            web_sys::MouseEventInit::new().meta_key(true).clone(),
+            web_sys::MouseEventInit::new().shift_key(true).clone(),
+            web_sys::MouseEventInit::new().alt_key(true).clone(),
+            // The middle button.
+            web_sys::MouseEventInit::new().button(1).clone(),
+        ];
+        for init in &modified_clicks {
+            assert_eq!(routable_href(&click(init), &link), None);
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn link_targets() {
+        let plain = click(&web_sys::MouseEventInit::new());
+        let routed = [
+            r#"<a href="/page">Page</a>"#,
+            r#"<a href="/page" target="_self">Page</a>"#,
+            r#"<a href="/page" rel="noopener">Page</a>"#,
+        ];
+        for html in &routed {
+            assert_eq!(
+                routable_href(&plain, &link(html)),
+                Some("/page".to_owned()),
+                "{}",
+                html
+            );
+        }
+
+        let ignored = [
+            r#"<a href="/page" target="_blank">Page</a>"#,
+            r#"<a href="/page" target="preview">Page</a>"#,
+            r#"<a href="/report.pdf" download>Report</a>"#,
+            r#"<a href="/legacy" data-seed-external>Legacy</a>"#,
+            r#"<a href="/legacy" rel="noopener external">Legacy</a>"#,
+            r#"<a href="mailto:info@example.com">Mail</a>"#,
+            r#"<a href="tel:+420123456789">Call</a>"#,
+            r#"<a href="https://example.com/page">Example</a>"#,
+            r#"<a href="//example.com/page">Example</a>"#,
+        ];
+        for html in &ignored {
+            assert_eq!(routable_href(&plain, &link(html)), None, "{}", html);
+        }
+    }
+
     #[wasm_bindgen_test]
     fn parse_url_with_hash_routing() {
         let expected = Url {
@@ -210,6 +555,7 @@ mod tests {
             hash: Some("/discover".into()),
             search: None,
             title: None,
+            state: None,
         };
 
         let actual: Url = "/#/discover".to_string().try_into().unwrap();
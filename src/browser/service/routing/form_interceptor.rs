@@ -0,0 +1,342 @@
+//! Progressive enhancement of native forms. See `AppBuilder::intercept_forms`.
+//!
+//! Forms work without JS (e.g. `<form action="/search" method="get">`) and the running app
+//! intercepts their submissions:
+//! - `GET` submissions are routed like clicked links - the query string is built from the form
+//!   data and the URL is passed to `routes`.
+//! - Other submissions (and `GET` submissions not matched by `routes`) are passed to the app's
+//!   form interceptor as `FormSubmission`, so the app can send the data by `fetch`.
+//!
+//! The native submission isn't prevented when the form (or the submit button) has the attribute
+//! `data-seed-native-submit`, when the action isn't same-origin, when the form targets another
+//! browsing context, for `method="dialog"` and when neither `routes` nor the interceptor
+//! return a message.
+//!
+//! Submit button overrides `formaction`, `formmethod`, `formenctype` and `formtarget`
+//! are respected and the button's `name` and `value` are included in the form data.
+
+use super::{navigation, push_route_with_base_path};
+use crate::browser::{service::fetch::Method, util, Url};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+/// Forms and submit buttons with this attribute are always submitted natively.
+pub const NATIVE_SUBMIT_ATTR: &str = "data-seed-native-submit";
+
+// ------ FormSubmission ------
+
+/// Value of a form data entry.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormDataValue {
+    Text(String),
+    File(web_sys::File),
+}
+
+/// Intercepted form submission - see `AppBuilder::intercept_forms`.
+#[derive(Debug, Clone)]
+pub struct FormSubmission {
+    /// `Method::Get` or `Method::Post`.
+    pub method: Method,
+    /// Same-origin action URL without the origin - e.g. `/api/comments?draft=true`.
+    pub action: String,
+    /// Lowercased encoding type - e.g. `multipart/form-data`.
+    pub enctype: String,
+    /// Form data, including files. Send it as the request body (`Request::body(data.into())`)
+    /// to mimic a multipart submission.
+    pub data: web_sys::FormData,
+}
+
+impl FormSubmission {
+    /// `true` when the form would be submitted natively as `multipart/form-data`.
+    pub fn is_multipart(&self) -> bool {
+        self.method == Method::Post && self.enctype == "multipart/form-data"
+    }
+
+    /// Form data entries in the document order.
+    pub fn entries(&self) -> Vec<(String, FormDataValue)> {
+        let iterator = match js_sys::try_iter(self.data.as_ref()) {
+            Ok(Some(iterator)) => iterator,
+            _ => return Vec::new(),
+        };
+        iterator
+            .filter_map(Result::ok)
+            .map(|entry| {
+                let entry = js_sys::Array::from(&entry);
+                let name = entry.get(0).as_string().unwrap_or_default();
+                let value = entry.get(1);
+                let value = match value.dyn_into::<web_sys::File>() {
+                    Ok(file) => FormDataValue::File(file),
+                    Err(value) => FormDataValue::Text(value.as_string().unwrap_or_default()),
+                };
+                (name, value)
+            })
+            .collect()
+    }
+
+    /// Form data encoded as `application/x-www-form-urlencoded`.
+    /// Files are replaced by their names - like in native non-multipart submissions.
+    pub fn url_encoded(&self) -> String {
+        let params = web_sys::UrlSearchParams::new().expect("create `UrlSearchParams`");
+        for (name, value) in self.entries() {
+            match value {
+                FormDataValue::Text(text) => params.append(&name, &text),
+                FormDataValue::File(file) => params.append(&name, &file.name()),
+            }
+        }
+        params.to_string().into()
+    }
+
+    /// URL of the native `GET` submission - the action with the query string
+    /// replaced by the form data.
+    pub fn navigation_url(&self) -> Url {
+        let dummy_base_url = "http://example.com";
+        let url = web_sys::Url::new_with_base(&self.action, dummy_base_url)
+            .expect("form action is a valid relative URL");
+        url.set_search(&self.url_encoded());
+        Url::from(url)
+    }
+}
+
+/// Get the submission of `form` submitted by `submitter` or `None` when the form should be
+/// submitted natively. See the module docs.
+pub(crate) fn form_submission(
+    form: &web_sys::HtmlFormElement,
+    submitter: Option<&web_sys::Element>,
+) -> Option<FormSubmission> {
+    let opted_out = |el: &web_sys::Element| el.has_attribute(NATIVE_SUBMIT_ATTR);
+    if opted_out(form) || submitter.map_or(false, opted_out) {
+        return None;
+    }
+    let overridden = |name: &str| submitter.and_then(|submitter| submitter.get_attribute(name));
+
+    let target = overridden("formtarget").unwrap_or_else(|| form.target());
+    if !target.is_empty() && !target.eq_ignore_ascii_case("_self") {
+        return None;
+    }
+
+    let method = overridden("formmethod").unwrap_or_else(|| form.method());
+    let method = match method.to_ascii_lowercase().as_str() {
+        "post" => Method::Post,
+        "dialog" => return None,
+        // Missing and invalid values mean `GET`.
+        _ => Method::Get,
+    };
+
+    // Properties `action` and `formAction` are resolved against the document URL.
+    let action = match submitter.filter(|submitter| submitter.has_attribute("formaction")) {
+        Some(submitter) => js_sys::Reflect::get(submitter, &JsValue::from_str("formAction"))
+            .ok()?
+            .as_string()?,
+        None => form.action(),
+    };
+    let action = web_sys::Url::new(&action).ok()?;
+    if action.origin() != util::window().location().origin().ok()? {
+        return None;
+    }
+
+    let enctype = overridden("formenctype")
+        .unwrap_or_else(|| form.enctype())
+        .to_ascii_lowercase();
+
+    let data = web_sys::FormData::new_with_form(form).ok()?;
+    if let Some(submitter) = submitter {
+        let name = submitter.get_attribute("name").unwrap_or_default();
+        if !name.is_empty() {
+            let value = js_sys::Reflect::get(submitter, &JsValue::from_str("value"))
+                .ok()
+                .and_then(|value| value.as_string())
+                .unwrap_or_default();
+            data.append_with_str(&name, &value).ok()?;
+        }
+    }
+
+    Some(FormSubmission {
+        method,
+        action: format!("{}{}{}", action.pathname(), action.search(), action.hash()),
+        enctype,
+        data,
+    })
+}
+
+/// Set up a listener that intercepts form submissions - see the module docs.
+/// `GET` submissions outside of `base_path` (see `AppBuilder::base_path`) aren't routed.
+/// Remove it by `remove_form_listener`.
+pub fn setup_form_listener<Ms>(
+    base_path: Vec<String>,
+    update: impl Fn(Ms) + 'static,
+    routes: impl Fn(Url) -> Option<Ms> + 'static,
+    intercept: impl Fn(FormSubmission) -> Option<Ms> + 'static,
+    updated_listener: impl Fn(Closure<dyn FnMut(web_sys::Event)>) + 'static,
+) where
+    Ms: 'static,
+{
+    let closure: Closure<dyn FnMut(web_sys::Event)> = Closure::new(move |event: web_sys::Event| {
+        if event.default_prevented() {
+            return;
+        }
+        let form = match event
+            .target()
+            .and_then(|target| target.dyn_into::<web_sys::HtmlFormElement>().ok())
+        {
+            Some(form) => form,
+            None => return,
+        };
+        // `SubmitEvent.submitter` isn't supported by all browsers.
+        let submitter = js_sys::Reflect::get(&event, &JsValue::from_str("submitter"))
+            .ok()
+            .and_then(|submitter| submitter.dyn_into::<web_sys::Element>().ok());
+        let submission = match form_submission(&form, submitter.as_ref()) {
+            Some(submission) => submission,
+            None => return,
+        };
+
+        if submission.method == Method::Get {
+            let redirect = navigation::strip_base_path(submission.navigation_url(), &base_path)
+                .and_then(|url| routes(url.clone()).map(|msg| (url, msg)));
+            if let Some((url, msg)) = redirect {
+                push_route_with_base_path(&base_path, url);
+                event.prevent_default();
+                update(msg);
+                return;
+            }
+        }
+        if let Some(msg) = intercept(submission) {
+            event.prevent_default();
+            update(msg);
+        }
+    });
+
+    (util::document().as_ref() as &web_sys::EventTarget)
+        .add_event_listener_with_callback("submit", closure.as_ref().unchecked_ref())
+        .expect("Problem setting up form interceptor");
+
+    updated_listener(closure);
+}
+
+/// Remove the listener set up by `setup_form_listener`.
+pub(crate) fn remove_form_listener(closure: &Closure<dyn FnMut(web_sys::Event)>) {
+    (util::document().as_ref() as &web_sys::EventTarget)
+        .remove_event_listener_with_callback("submit", closure.as_ref().unchecked_ref())
+        .expect("Problem removing form interceptor");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn form(html: &str) -> web_sys::HtmlFormElement {
+        let container = util::document().create_element("div").unwrap();
+        container.set_inner_html(html);
+        container
+            .first_element_child()
+            .unwrap()
+            .dyn_into::<web_sys::HtmlFormElement>()
+            .unwrap()
+    }
+
+    fn text_entries(submission: &FormSubmission) -> Vec<(String, String)> {
+        submission
+            .entries()
+            .into_iter()
+            .map(|(name, value)| match value {
+                FormDataValue::Text(text) => (name, text),
+                FormDataValue::File(file) => (name, format!("file:{}", file.name())),
+            })
+            .collect()
+    }
+
+    #[wasm_bindgen_test]
+    fn get_submission() {
+        let form = form(
+            r#"<form action="/search?page=2#results">
+                <input name="q" value="rust wasm">
+                <input name="lang" value="en">
+            </form>"#,
+        );
+        let submission = form_submission(&form, None).unwrap();
+        assert_eq!(submission.method, Method::Get);
+        assert_eq!(submission.action, "/search?page=2#results");
+        assert!(!submission.is_multipart());
+        assert_eq!(submission.url_encoded(), "q=rust+wasm&lang=en");
+
+        let url = submission.navigation_url();
+        assert_eq!(url.path, vec!["search".to_owned()]);
+        assert_eq!(url.search, Some("q=rust+wasm&lang=en".to_owned()));
+        assert_eq!(url.hash, Some("results".to_owned()));
+    }
+
+    #[wasm_bindgen_test]
+    fn submitter_overrides() {
+        let form = form(
+            r#"<form action="/comments" method="post">
+                <input name="text" value="Hi">
+                <button name="intent" value="draft" formaction="/drafts"
+                    formmethod="get" formenctype="text/plain"></button>
+            </form>"#,
+        );
+        let submitter = form.query_selector("button").unwrap().unwrap();
+
+        let submission = form_submission(&form, None).unwrap();
+        assert_eq!(submission.method, Method::Post);
+        assert_eq!(submission.action, "/comments");
+        assert_eq!(submission.enctype, "application/x-www-form-urlencoded");
+
+        let submission = form_submission(&form, Some(&submitter)).unwrap();
+        assert_eq!(submission.method, Method::Get);
+        assert_eq!(submission.action, "/drafts");
+        assert_eq!(submission.enctype, "text/plain");
+        assert_eq!(
+            text_entries(&submission),
+            vec![
+                ("text".to_owned(), "Hi".to_owned()),
+                ("intent".to_owned(), "draft".to_owned())
+            ]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn multipart_submission() {
+        let form = form(
+            r#"<form action="/upload" method="post" enctype="multipart/form-data">
+                <input name="title" value="Report">
+                <input name="attachment" type="file">
+            </form>"#,
+        );
+        let submission = form_submission(&form, None).unwrap();
+        assert!(submission.is_multipart());
+        // An empty file input is submitted as an empty file.
+        assert_eq!(
+            text_entries(&submission),
+            vec![
+                ("title".to_owned(), "Report".to_owned()),
+                ("attachment".to_owned(), "file:".to_owned())
+            ]
+        );
+        assert_eq!(submission.url_encoded(), "title=Report&attachment=");
+    }
+
+    #[wasm_bindgen_test]
+    fn native_submissions() {
+        let declined = |html: &str| form_submission(&form(html), None).is_none();
+        assert!(declined(
+            r#"<form action="/search" data-seed-native-submit></form>"#
+        ));
+        assert!(declined(
+            r#"<form action="https://example.com/search"></form>"#
+        ));
+        assert!(declined(
+            r#"<form action="/search" target="_blank"></form>"#
+        ));
+        assert!(declined(r#"<form method="dialog"></form>"#));
+        assert!(!declined(
+            r#"<form action="/search" target="_self"></form>"#
+        ));
+
+        let form =
+            form(r#"<form action="/search"><button data-seed-native-submit></button></form>"#);
+        let submitter = form.query_selector("button").unwrap().unwrap();
+        assert!(form_submission(&form, Some(&submitter)).is_none());
+    }
+}
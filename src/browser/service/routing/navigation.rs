@@ -0,0 +1,513 @@
+//! Navigation decisions.
+//!
+//! The browser listeners (`setup_popstate_listener`, `setup_hashchange_listener`
+//! and `setup_link_listener`) only convert events into `NavigationSource`s and apply
+//! `NavigationOutcome`s returned by `resolve_navigation`. The function doesn't touch
+//! browser APIs, so the routing rules are tested by plain `cargo test`.
+
+use crate::app::types::{NotFoundFn, RoutesFn};
+use crate::browser::Url;
+
+// ------ NavigationSource ------
+
+/// Navigation resolved by `resolve_navigation`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NavigationSource {
+    /// Click on an element with `href`.
+    LinkClick(LinkClick),
+    /// `popstate` - the URL of the history entry without the base path.
+    PopState(Url),
+    /// `hashchange` - the new URL including the base path.
+    HashChange(Url),
+    /// Programmatic navigation (e.g. `orders.push_route`) - the URL without the base path.
+    Push(Url),
+}
+
+/// Click on an element with `href` - see `setup_link_listener`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LinkClick {
+    /// The `href` attribute.
+    pub href: String,
+    /// `href` parsed into `Url` including the base path. `None` if it isn't an absolute path.
+    pub url: Option<Url>,
+    /// `MouseEvent.button` - `0` is the primary button.
+    pub button: i16,
+    /// Is Ctrl, Cmd, Shift or Alt pressed?
+    pub modifier_key: bool,
+    /// Has the click been already handled by `event.preventDefault()`?
+    pub default_prevented: bool,
+    /// Tag name of the element with `href` (e.g. `A`).
+    pub tag_name: String,
+    /// The `target` attribute.
+    pub target: Option<String>,
+    /// Has the element the `download` attribute?
+    pub download: bool,
+    /// Has the element the attribute `data-seed-external` or `rel="external"`?
+    pub external: bool,
+    /// Does `href` point to an `http(s)` URL with the page's origin?
+    pub same_origin: bool,
+}
+
+impl LinkClick {
+    /// Can the click be handled by the app? See `setup_link_listener`.
+    pub(crate) fn is_routable(&self) -> bool {
+        if self.default_prevented || self.button != 0 || self.modifier_key {
+            return false;
+        }
+        // Base and Link tags use href for something other than navigation.
+        if self.tag_name.eq_ignore_ascii_case("base") || self.tag_name.eq_ignore_ascii_case("link")
+        {
+            return false;
+        }
+        let opens_elsewhere = self
+            .target
+            .as_ref()
+            .map_or(false, |target| !target.is_empty() && target != "_self");
+        if opens_elsewhere || self.external || self.download {
+            return false;
+        }
+        // @TODO: Resolve relative hrefs properly, see Elm implementation:
+        // @TODO: https://github.com/elm/browser/blob/9f52d88b424dd12cab391195d5b090dd4639c3b0/src/Elm/Kernel/Browser.js#L157
+        self.href.is_empty() || (self.href.starts_with('/') && self.same_origin)
+    }
+}
+
+// ------ NavigationOutcome ------
+
+/// Routing settings of the app for `resolve_navigation`.
+pub struct RoutingCfg<'a, Ms> {
+    /// See `AppBuilder::base_path`.
+    pub base_path: &'a [String],
+    /// See `AppBuilder::not_found`.
+    pub not_found: Option<NotFoundFn<Ms>>,
+}
+
+/// History change which has to be made by the app.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HistoryOp {
+    /// The browser has already changed the history or the navigation isn't handled.
+    None,
+    /// Push a new entry with the URL.
+    Push(Url),
+}
+
+/// What the app does with a `NavigationSource`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavigationOutcome<Ms> {
+    /// Prevent the browser's default action (e.g. loading the link's page).
+    pub intercept: bool,
+    /// The app's new URL (without the base path). `None` if the navigation isn't handled.
+    pub url: Option<Url>,
+    /// Message for `update`.
+    pub msg: Option<Ms>,
+    pub history: HistoryOp,
+}
+
+impl<Ms> NavigationOutcome<Ms> {
+    /// The navigation is left to the browser.
+    fn ignored() -> Self {
+        Self {
+            intercept: false,
+            url: None,
+            msg: None,
+            history: HistoryOp::None,
+        }
+    }
+}
+
+/// Decide how the app handles `incoming` when it's at `current` URL (without the base path).
+///
+/// - Links are intercepted only when they are routable (see `setup_link_listener`)
+///   and `routes` or `not_found` return a message for them. Their URL is pushed to the history.
+/// - URLs outside of the base path are left to the browser.
+/// - `hashchange` to the current URL is ignored - it follows `popstate` when the user
+///   traverses to an entry with another hash.
+pub fn resolve_navigation<Ms>(
+    current: &Url,
+    incoming: NavigationSource,
+    routes: RoutesFn<Ms>,
+    cfg: &RoutingCfg<Ms>,
+) -> NavigationOutcome<Ms> {
+    let route = |url: &Url| {
+        routes(url.clone()).or_else(|| cfg.not_found.map(|not_found| not_found(url.clone())))
+    };
+    match incoming {
+        NavigationSource::LinkClick(click) => {
+            if !click.is_routable() {
+                return NavigationOutcome::ignored();
+            }
+            // Empty `href` doesn't reload the page.
+            if click.href.is_empty() {
+                return NavigationOutcome {
+                    intercept: true,
+                    ..NavigationOutcome::ignored()
+                };
+            }
+            let url = match click
+                .url
+                .and_then(|url| strip_base_path(url, cfg.base_path))
+            {
+                Some(url) => url,
+                None => return NavigationOutcome::ignored(),
+            };
+            match route(&url) {
+                Some(msg) => NavigationOutcome {
+                    intercept: true,
+                    url: Some(url.clone()),
+                    msg: Some(msg),
+                    history: HistoryOp::Push(url),
+                },
+                None => NavigationOutcome::ignored(),
+            }
+        }
+        NavigationSource::PopState(url) => NavigationOutcome {
+            intercept: false,
+            msg: route(&url),
+            url: Some(url),
+            history: HistoryOp::None,
+        },
+        NavigationSource::HashChange(url) => match strip_base_path(url, cfg.base_path) {
+            Some(url) if !same_location(&url, current) => NavigationOutcome {
+                intercept: false,
+                msg: route(&url),
+                url: Some(url),
+                history: HistoryOp::None,
+            },
+            _ => NavigationOutcome::ignored(),
+        },
+        NavigationSource::Push(url) => NavigationOutcome {
+            intercept: false,
+            msg: route(&url),
+            url: Some(url.clone()),
+            history: HistoryOp::Push(url),
+        },
+    }
+}
+
+/// Remove `base_path` from `url`'s path. Returns `None` when the path doesn't start with it.
+pub(crate) fn strip_base_path(mut url: Url, base_path: &[String]) -> Option<Url> {
+    if !url.path.starts_with(base_path) {
+        return None;
+    }
+    url.path.drain(..base_path.len());
+    // The base path itself is the app's root path (`/`).
+    if url.path.is_empty() {
+        url.path.push(String::new());
+    }
+    Some(url)
+}
+
+/// Do `a` and `b` point to the same location? Titles and history states are ignored.
+fn same_location(a: &Url, b: &Url) -> bool {
+    a.path == b.path && a.search == b.search && a.hash == b.hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Msg {
+        Home,
+        Page(String),
+        NotFound,
+    }
+
+    fn routes(url: Url) -> Option<Msg> {
+        match url
+            .path
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            [""] => Some(Msg::Home),
+            ["page", name] => Some(Msg::Page((*name).to_owned())),
+            _ => None,
+        }
+    }
+
+    fn url(path: &[&str]) -> Url {
+        Url::new(path.to_vec())
+    }
+
+    fn link(href: &str, path: &[&str]) -> LinkClick {
+        LinkClick {
+            href: href.to_owned(),
+            url: Some(url(path)),
+            tag_name: "A".to_owned(),
+            same_origin: true,
+            ..LinkClick::default()
+        }
+    }
+
+    fn not_found(_: Url) -> Msg {
+        Msg::NotFound
+    }
+
+    fn cfg(base_path: &[String]) -> RoutingCfg<'_, Msg> {
+        RoutingCfg {
+            base_path,
+            not_found: None,
+        }
+    }
+
+    fn routed(path: &[&str], msg: Msg) -> NavigationOutcome<Msg> {
+        NavigationOutcome {
+            intercept: true,
+            url: Some(url(path)),
+            msg: Some(msg),
+            history: HistoryOp::Push(url(path)),
+        }
+    }
+
+    #[test]
+    fn link_clicks() {
+        let page_a = || link("/page/a", &["page", "a"]);
+        let cases = vec![
+            (
+                "routed",
+                page_a(),
+                routed(&["page", "a"], Msg::Page("a".into())),
+            ),
+            ("root", link("/", &[""]), routed(&[""], Msg::Home)),
+            (
+                "unmatched",
+                link("/unknown", &["unknown"]),
+                NavigationOutcome::ignored(),
+            ),
+            (
+                "empty href",
+                LinkClick {
+                    href: String::new(),
+                    url: None,
+                    ..page_a()
+                },
+                NavigationOutcome {
+                    intercept: true,
+                    ..NavigationOutcome::ignored()
+                },
+            ),
+            (
+                "relative href",
+                LinkClick {
+                    href: "page/a".into(),
+                    ..page_a()
+                },
+                NavigationOutcome::ignored(),
+            ),
+            (
+                "mailto",
+                LinkClick {
+                    href: "mailto:a@example.com".into(),
+                    url: None,
+                    same_origin: false,
+                    ..page_a()
+                },
+                NavigationOutcome::ignored(),
+            ),
+            (
+                "another origin",
+                LinkClick {
+                    href: "//example.com/page/a".into(),
+                    same_origin: false,
+                    ..page_a()
+                },
+                NavigationOutcome::ignored(),
+            ),
+            (
+                "modifier key",
+                LinkClick {
+                    modifier_key: true,
+                    ..page_a()
+                },
+                NavigationOutcome::ignored(),
+            ),
+            (
+                "middle button",
+                LinkClick {
+                    button: 1,
+                    ..page_a()
+                },
+                NavigationOutcome::ignored(),
+            ),
+            (
+                "default prevented",
+                LinkClick {
+                    default_prevented: true,
+                    ..page_a()
+                },
+                NavigationOutcome::ignored(),
+            ),
+            (
+                "target _blank",
+                LinkClick {
+                    target: Some("_blank".into()),
+                    ..page_a()
+                },
+                NavigationOutcome::ignored(),
+            ),
+            (
+                "target _self",
+                LinkClick {
+                    target: Some("_self".into()),
+                    ..page_a()
+                },
+                routed(&["page", "a"], Msg::Page("a".into())),
+            ),
+            (
+                "download",
+                LinkClick {
+                    download: true,
+                    ..page_a()
+                },
+                NavigationOutcome::ignored(),
+            ),
+            (
+                "external",
+                LinkClick {
+                    external: true,
+                    ..page_a()
+                },
+                NavigationOutcome::ignored(),
+            ),
+            (
+                "link tag",
+                LinkClick {
+                    tag_name: "link".into(),
+                    ..page_a()
+                },
+                NavigationOutcome::ignored(),
+            ),
+        ];
+        for (name, click, expected) in cases {
+            let outcome = resolve_navigation(
+                &url(&[""]),
+                NavigationSource::LinkClick(click),
+                routes,
+                &cfg(&[]),
+            );
+            assert_eq!(outcome, expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn base_path() {
+        let base_path = vec!["app".to_owned()];
+        let cases = vec![
+            (
+                link("/app/page/a", &["app", "page", "a"]),
+                routed(&["page", "a"], Msg::Page("a".into())),
+            ),
+            (link("/app", &["app"]), routed(&[""], Msg::Home)),
+            (
+                link("/page/a", &["page", "a"]),
+                NavigationOutcome::ignored(),
+            ),
+        ];
+        for (click, expected) in cases {
+            let href = click.href.clone();
+            let outcome = resolve_navigation(
+                &url(&[""]),
+                NavigationSource::LinkClick(click),
+                routes,
+                &cfg(&base_path),
+            );
+            assert_eq!(outcome, expected, "{}", href);
+        }
+
+        let outcome = resolve_navigation(
+            &url(&[""]),
+            NavigationSource::HashChange(url(&["other", "page"]).hash("top")),
+            routes,
+            &cfg(&base_path),
+        );
+        assert_eq!(outcome, NavigationOutcome::ignored());
+    }
+
+    #[test]
+    fn unknown_route_is_not_found() {
+        let cfg = RoutingCfg {
+            base_path: &[],
+            not_found: Some(not_found),
+        };
+        let outcome = resolve_navigation(
+            &url(&[""]),
+            NavigationSource::LinkClick(link("/unknown", &["unknown"])),
+            routes,
+            &cfg,
+        );
+        assert_eq!(outcome, routed(&["unknown"], Msg::NotFound));
+
+        let outcome = resolve_navigation(
+            &url(&[""]),
+            NavigationSource::PopState(url(&["unknown"])),
+            routes,
+            &cfg,
+        );
+        assert_eq!(outcome.msg, Some(Msg::NotFound));
+    }
+
+    #[test]
+    fn history_events() {
+        let current = url(&["page", "a"]);
+        let popstate = resolve_navigation(
+            &current,
+            NavigationSource::PopState(url(&["page", "b"])),
+            routes,
+            &cfg(&[]),
+        );
+        assert_eq!(
+            popstate,
+            NavigationOutcome {
+                intercept: false,
+                url: Some(url(&["page", "b"])),
+                msg: Some(Msg::Page("b".into())),
+                history: HistoryOp::None,
+            }
+        );
+
+        // Unmatched URLs are the app's URLs too, but without a message.
+        let popstate = resolve_navigation(
+            &current,
+            NavigationSource::PopState(url(&["unknown"])),
+            routes,
+            &cfg(&[]),
+        );
+        assert_eq!(popstate.url, Some(url(&["unknown"])));
+        assert_eq!(popstate.msg, None);
+
+        let hashchange = resolve_navigation(
+            &current,
+            NavigationSource::HashChange(url(&["page", "a"]).hash("top")),
+            routes,
+            &cfg(&[]),
+        );
+        assert_eq!(hashchange.url, Some(url(&["page", "a"]).hash("top")));
+        assert_eq!(hashchange.msg, Some(Msg::Page("a".into())));
+        assert_eq!(hashchange.history, HistoryOp::None);
+
+        let push = resolve_navigation(
+            &current,
+            NavigationSource::Push(url(&["page", "c"])),
+            routes,
+            &cfg(&[]),
+        );
+        assert_eq!(push.msg, Some(Msg::Page("c".into())));
+        assert_eq!(push.history, HistoryOp::Push(url(&["page", "c"])));
+        assert!(!push.intercept);
+    }
+
+    #[test]
+    fn duplicate_hashchange_is_ignored() {
+        // `popstate` has already moved the app to the URL.
+        let current = url(&["page", "a"]).hash("top");
+        let outcome = resolve_navigation(
+            &current,
+            NavigationSource::HashChange(url(&["page", "a"]).hash("top")),
+            routes,
+            &cfg(&[]),
+        );
+        assert_eq!(outcome, NavigationOutcome::ignored());
+    }
+}
@@ -0,0 +1,264 @@
+//! Path patterns with parameters - see `RouteMatcher` and `Router`.
+//!
+//! Pattern segments are separated by `/`:
+//! - `users` matches the same URL segment.
+//! - `:id` matches any segment and captures it as the parameter `id`.
+//! - `*` as the last segment matches the rest of the path (also empty) - see `RouteParams::rest`.
+//!
+//! Empty segments are ignored both in patterns and in URLs, so trailing slashes don't matter -
+//! `/users/:id` matches `/users/42/` as well. Segments of `Url::path` are already
+//! percent-decoded, so they are matched and captured as they are.
+
+use crate::browser::Url;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Param(String),
+    Wildcard,
+}
+
+// ------ RouteParams ------
+
+/// Captured parameters of a matched pattern.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RouteParams {
+    /// Decoded segments captured by `:name` placeholders.
+    pub params: HashMap<String, String>,
+    /// Decoded segments matched by the wildcard `*`. It's empty for patterns without wildcard.
+    pub rest: Vec<String>,
+}
+
+impl RouteParams {
+    /// Get the parameter `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+}
+
+// ------ RouteMatcher ------
+
+/// Path pattern like `/users/:id/posts/:post_id` or `/files/*`. See the module docs for the syntax.
+///
+/// # Example
+///
+/// ```rust,no_run
+///let matcher = RouteMatcher::new("/users/:id/posts/:post_id");
+///if let Some(params) = matcher.matches(&url) {
+///    log!(params.get("id"), params.get("post_id"));
+///}
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteMatcher {
+    segments: Vec<Segment>,
+}
+
+impl RouteMatcher {
+    /// Parse `pattern`.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the wildcard `*` isn't the last segment or a parameter doesn't have a name.
+    pub fn new(pattern: &str) -> Self {
+        let parts = pattern
+            .split('/')
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>();
+        let last_index = parts.len().saturating_sub(1);
+        let segments = parts
+            .iter()
+            .enumerate()
+            .map(|(index, part)| {
+                if *part == "*" {
+                    assert!(
+                        index == last_index,
+                        "wildcard `*` has to be the last segment of route pattern `{}`",
+                        pattern
+                    );
+                    Segment::Wildcard
+                } else if part.starts_with(':') {
+                    assert!(
+                        part.len() > 1,
+                        "missing parameter name in route pattern `{}`",
+                        pattern
+                    );
+                    Segment::Param(part[1..].to_owned())
+                } else {
+                    Segment::Literal((*part).to_owned())
+                }
+            })
+            .collect();
+        Self { segments }
+    }
+
+    /// Match `url`'s path. Returns `None` when the path doesn't match the pattern.
+    pub fn matches(&self, url: &Url) -> Option<RouteParams> {
+        let mut path = url
+            .path
+            .iter()
+            .filter(|segment| !segment.is_empty())
+            .cloned();
+        let mut params = RouteParams::default();
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Wildcard => {
+                    params.rest = path.collect();
+                    return Some(params);
+                }
+                Segment::Literal(literal) => {
+                    if path.next()? != *literal {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    params.params.insert(name.clone(), path.next()?);
+                }
+            }
+        }
+        if path.next().is_some() {
+            return None;
+        }
+        Some(params)
+    }
+}
+
+// ------ Router ------
+
+/// Patterns with their handlers. The first pattern which matches the URL and whose handler
+/// returns `Some` wins.
+///
+/// # Example
+///
+/// ```rust,no_run
+///fn routes(url: Url) -> Option<Msg> {
+///    Router::new()
+///        .route("/", |_| Some(Msg::ShowHome))
+///        .route("/users/:id", |params| {
+///            params.get("id")?.parse().ok().map(Msg::ShowUser)
+///        })
+///        .route("/docs/*", |params| Some(Msg::ShowDoc(params.rest.join("/"))))
+///        .resolve(&url)
+///}
+/// ```
+pub struct Router<Ms> {
+    routes: Vec<(RouteMatcher, Box<dyn Fn(RouteParams) -> Option<Ms>>)>,
+}
+
+impl<Ms> Default for Router<Ms> {
+    fn default() -> Self {
+        Self { routes: Vec::new() }
+    }
+}
+
+impl<Ms> Router<Ms> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `pattern` - see `RouteMatcher::new`.
+    pub fn route(
+        mut self,
+        pattern: &str,
+        handler: impl Fn(RouteParams) -> Option<Ms> + 'static,
+    ) -> Self {
+        self.routes
+            .push((RouteMatcher::new(pattern), Box::new(handler)));
+        self
+    }
+
+    /// Get the message of the first matching route.
+    pub fn resolve(&self, url: &Url) -> Option<Ms> {
+        self.routes
+            .iter()
+            .filter_map(|(matcher, handler)| matcher.matches(url).and_then(handler))
+            .next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn url(url: &str) -> Url {
+        url.to_owned().try_into().unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn params_are_decoded() {
+        let params = RouteMatcher::new("/users/:id/posts/:post_id")
+            .matches(&url("/users/42/posts/hello%20world"))
+            .unwrap();
+        assert_eq!(params.get("id"), Some("42"));
+        assert_eq!(params.get("post_id"), Some("hello world"));
+        assert!(params.rest.is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    fn params_are_decoded_once() {
+        let params = RouteMatcher::new("/posts/:slug")
+            .matches(&url("/posts/a%2Fb%2525"))
+            .unwrap();
+        assert_eq!(params.get("slug"), Some("a/b%25"));
+    }
+
+    #[wasm_bindgen_test]
+    fn trailing_slashes_and_segment_counts() {
+        let matcher = RouteMatcher::new("/users/:id/");
+        assert!(matcher.matches(&url("/users/42")).is_some());
+        assert!(matcher.matches(&url("/users/42/")).is_some());
+        assert!(matcher.matches(&url("/users")).is_none());
+        assert!(matcher.matches(&url("/users/42/posts")).is_none());
+        assert!(matcher.matches(&url("/teams/42")).is_none());
+
+        let root = RouteMatcher::new("/");
+        assert!(root.matches(&url("/")).is_some());
+        assert!(root.matches(&url("/users")).is_none());
+    }
+
+    #[wasm_bindgen_test]
+    fn wildcard_tail() {
+        let matcher = RouteMatcher::new("/files/:drive/*");
+        let params = matcher.matches(&url("/files/c/my%20docs/a.txt")).unwrap();
+        assert_eq!(params.get("drive"), Some("c"));
+        assert_eq!(params.rest, vec!["my docs".to_owned(), "a.txt".to_owned()]);
+
+        let params = matcher.matches(&url("/files/c/")).unwrap();
+        assert!(params.rest.is_empty());
+        assert!(matcher.matches(&url("/files")).is_none());
+    }
+
+    #[wasm_bindgen_test]
+    fn router_uses_first_accepted_route() {
+        #[derive(Debug, PartialEq)]
+        enum Msg {
+            Home,
+            User(u32),
+            UserSlug(String),
+        }
+
+        let router = Router::new()
+            .route("/", |_| Some(Msg::Home))
+            .route("/users/:id", |params| {
+                params.get("id")?.parse().ok().map(Msg::User)
+            })
+            .route("/users/:slug", |params| {
+                params
+                    .get("slug")
+                    .map(|slug| Msg::UserSlug(slug.to_owned()))
+            });
+
+        assert_eq!(router.resolve(&url("/")), Some(Msg::Home));
+        assert_eq!(router.resolve(&url("/users/7")), Some(Msg::User(7)));
+        assert_eq!(
+            router.resolve(&url("/users/martin")),
+            Some(Msg::UserSlug("martin".to_owned()))
+        );
+        assert_eq!(router.resolve(&url("/teams")), None);
+    }
+}
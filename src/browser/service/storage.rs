@@ -8,6 +8,8 @@
 extern crate serde;
 extern crate serde_json;
 
+use crate::browser::{Capabilities, Capability, Unsupported};
+
 pub type Storage = web_sys::Storage;
 
 #[allow(clippy::module_name_repetitions)]
@@ -20,6 +22,15 @@ pub fn get_storage() -> Option<Storage> {
     }
 }
 
+/// Similar to `get_storage`, but it consults `capabilities` (see `orders.capabilities()`)
+/// and returns typed error when local storage isn't available.
+pub fn try_get_storage(capabilities: &Capabilities) -> Result<Storage, Unsupported> {
+    capabilities.require(Capability::LocalStorage)?;
+    get_storage().ok_or_else(|| Unsupported {
+        missing: vec![Capability::LocalStorage],
+    })
+}
+
 /// Create a new store, from a serializable data structure.
 pub fn store_data<T>(storage: &Storage, name: &str, data: &T)
 where
@@ -0,0 +1,453 @@
+//! Persistent storage and storage quota (`navigator.storage`).
+//!
+//! Browsers may evict data of the origin (IndexedDB, Cache Storage, ..) under storage pressure
+//! unless the storage is persistent. Request it by `orders.request_persistent_storage`,
+//! check quota usage by `orders.storage_estimate` and warn users before writes start failing
+//! by `orders.subscribe_storage_quota`.
+//!
+//! The returned futures never fail with a JS error - they resolve with `Err(Unsupported)`
+//! in browsers without the API.
+//!
+//! # References
+//! * [MDN docs](https://developer.mozilla.org/en-US/docs/Web/API/StorageManager)
+
+use crate::browser::{
+    time::{self, Clock, TimeoutHandle},
+    util,
+    visibility::{self, Pausable},
+    Capabilities, Capability, Unsupported,
+};
+use futures::future::{self, FutureExt, LocalBoxFuture};
+use std::{
+    cell::{Cell, RefCell},
+    future::Future,
+    rc::Rc,
+    time::Duration,
+};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+
+// ------ StorageEstimate ------
+
+/// Storage usage and quota of the origin in bytes. See `estimate`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct StorageEstimate {
+    pub usage: u64,
+    pub quota: u64,
+}
+
+impl StorageEstimate {
+    /// Used fraction of quota - `0.0` when quota is unknown.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn usage_fraction(&self) -> f64 {
+        if self.quota == 0 {
+            return 0.;
+        }
+        self.usage as f64 / self.quota as f64
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn from_js(estimate: &JsValue) -> Self {
+        let get = |name: &str| {
+            js_sys::Reflect::get(estimate, &JsValue::from_str(name))
+                .ok()
+                .and_then(|value| value.as_f64())
+                .map_or(0, |value| value.max(0.) as u64)
+        };
+        Self {
+            usage: get("usage"),
+            quota: get("quota"),
+        }
+    }
+}
+
+// ------ API ------
+
+/// Request persistent storage. Resolves with `Ok(true)` if the storage is persistent.
+///
+/// Browsers decide on their own (e.g. by site engagement or by asking the user);
+/// a rejected request resolves with `Ok(false)`.
+pub fn persist(capabilities: &Capabilities) -> impl Future<Output = Result<bool, Unsupported>> {
+    call(capabilities, "persist")
+        .map(|result| result.map(|granted| granted.map_or(false, |granted| granted.is_truthy())))
+}
+
+/// Is the storage persistent?
+pub fn persisted(capabilities: &Capabilities) -> impl Future<Output = Result<bool, Unsupported>> {
+    call(capabilities, "persisted").map(|result| {
+        result.map(|persisted| persisted.map_or(false, |persisted| persisted.is_truthy()))
+    })
+}
+
+/// Estimate storage usage and quota of the origin.
+pub fn estimate(
+    capabilities: &Capabilities,
+) -> impl Future<Output = Result<StorageEstimate, Unsupported>> {
+    call(capabilities, "estimate").map(|result| {
+        result.and_then(|estimate| {
+            estimate
+                .map(|estimate| StorageEstimate::from_js(&estimate))
+                .ok_or_else(unsupported)
+        })
+    })
+}
+
+/// Call `navigator.storage.<method>()`.
+/// Resolves with `Ok(None)` when the returned promise has been rejected.
+fn call(
+    capabilities: &Capabilities,
+    method: &str,
+) -> impl Future<Output = Result<Option<JsValue>, Unsupported>> {
+    let promise = capabilities
+        .require(Capability::StorageManager)
+        .and_then(|_| storage_manager().ok_or_else(unsupported))
+        .and_then(|storage| {
+            js_sys::Reflect::get(&storage, &JsValue::from_str(method))
+                .ok()
+                .and_then(|method| method.dyn_into::<js_sys::Function>().ok())
+                .and_then(|method| method.call0(&storage).ok())
+                .and_then(|promise| promise.dyn_into::<js_sys::Promise>().ok())
+                .ok_or_else(unsupported)
+        });
+    match promise {
+        Ok(promise) => JsFuture::from(promise)
+            .map(|result| Ok(result.ok()))
+            .left_future(),
+        Err(error) => future::err(error).right_future(),
+    }
+}
+
+fn storage_manager() -> Option<JsValue> {
+    js_sys::Reflect::get(&util::window(), &JsValue::from_str("navigator"))
+        .and_then(|navigator| js_sys::Reflect::get(&navigator, &JsValue::from_str("storage")))
+        .ok()
+        .filter(|storage| !storage.is_undefined() && !storage.is_null())
+}
+
+fn unsupported() -> Unsupported {
+    Unsupported {
+        missing: vec![Capability::StorageManager],
+    }
+}
+
+// ------ StorageQuotaHandle ------
+
+/// Options for `orders.subscribe_storage_quota`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StorageQuotaOpts {
+    /// Fraction of quota (`0.0` - `1.0`) which triggers the handler.
+    pub threshold: f64,
+    /// Delay between estimates.
+    pub interval: Duration,
+}
+
+impl Default for StorageQuotaOpts {
+    fn default() -> Self {
+        Self {
+            threshold: 0.8,
+            interval: Duration::from_secs(60),
+        }
+    }
+}
+
+type Estimator = Box<dyn Fn() -> LocalBoxFuture<'static, Result<StorageEstimate, Unsupported>>>;
+
+struct Monitor {
+    opts: StorageQuotaOpts,
+    clock: Rc<dyn Clock>,
+    estimator: Estimator,
+    callback: Box<dyn Fn(StorageEstimate)>,
+    above_threshold: Cell<bool>,
+    timer: RefCell<Option<TimeoutHandle>>,
+    /// `clock` time when the monitoring has been paused.
+    paused_at: Cell<Option<f64>>,
+}
+
+impl Monitor {
+    fn check(self: &Rc<Self>) {
+        let estimate = (self.estimator)();
+        let monitor = Rc::downgrade(self);
+        spawn_local(async move {
+            let estimate = estimate.await;
+            // The monitor doesn't exist when the handle has been dropped.
+            if let Some(monitor) = monitor.upgrade() {
+                // There is nothing to monitor in browsers without the API.
+                if let Ok(estimate) = estimate {
+                    monitor.on_estimate(estimate);
+                    // The next check is scheduled on resume.
+                    if monitor.paused_at.get().is_none() {
+                        monitor.schedule_check();
+                    }
+                }
+            }
+        });
+    }
+
+    /// Call the callback when usage crosses the threshold from below.
+    fn on_estimate(&self, estimate: StorageEstimate) {
+        let above_threshold = estimate.usage_fraction() >= self.opts.threshold;
+        if above_threshold && !self.above_threshold.get() {
+            (self.callback)(estimate);
+        }
+        self.above_threshold.set(above_threshold);
+    }
+
+    fn schedule_check(self: &Rc<Self>) {
+        let monitor = Rc::downgrade(self);
+        let timer = self.clock.set_timeout(
+            time::duration_to_ms(self.opts.interval),
+            Box::new(move || {
+                if let Some(monitor) = monitor.upgrade() {
+                    monitor.check();
+                }
+            }),
+        );
+        self.timer.replace(Some(timer));
+    }
+}
+
+/// Monitoring suspended by `StorageQuotaHandle::pause_when_hidden`.
+struct Monitoring(Rc<Monitor>);
+
+impl Pausable for Monitoring {
+    fn pause(&self) {
+        self.0.paused_at.set(Some(self.0.clock.now_ms()));
+        self.0.timer.replace(None);
+    }
+
+    fn resume(&self) {
+        let paused_at = match self.0.paused_at.take() {
+            Some(paused_at) => paused_at,
+            None => return,
+        };
+        let hidden_ms = (self.0.clock.now_ms() - paused_at).max(0.);
+        // Estimates aren't caught up - at most one is made immediately.
+        if Duration::from_secs_f64(hidden_ms / 1000.) >= self.0.opts.interval {
+            self.0.check();
+        } else {
+            self.0.schedule_check();
+        }
+    }
+}
+
+/// Storage quota subscription - see `orders.subscribe_storage_quota`. Dropping the handle unsubscribes.
+#[must_use]
+pub struct StorageQuotaHandle {
+    monitor: Rc<Monitor>,
+    monitoring: Option<Rc<dyn Pausable>>,
+}
+
+impl StorageQuotaHandle {
+    /// Estimate storage now and then every `opts.interval` and invoke `callback` each time usage
+    /// crosses `opts.threshold` from below. Timers are scheduled by `clock`.
+    /// Nothing is monitored when `capabilities` report that the API is unsupported.
+    pub fn new(
+        opts: StorageQuotaOpts,
+        capabilities: &Capabilities,
+        clock: Rc<dyn Clock>,
+        callback: impl Fn(StorageEstimate) + 'static,
+    ) -> Self {
+        let capabilities = capabilities.clone();
+        Self::with_estimator(
+            opts,
+            clock,
+            move || estimate(&capabilities).boxed_local(),
+            callback,
+        )
+    }
+
+    fn with_estimator(
+        opts: StorageQuotaOpts,
+        clock: Rc<dyn Clock>,
+        estimator: impl Fn() -> LocalBoxFuture<'static, Result<StorageEstimate, Unsupported>> + 'static,
+        callback: impl Fn(StorageEstimate) + 'static,
+    ) -> Self {
+        let monitor = Rc::new(Monitor {
+            opts,
+            clock,
+            estimator: Box::new(estimator),
+            callback: Box::new(callback),
+            above_threshold: Cell::new(false),
+            timer: RefCell::new(None),
+            paused_at: Cell::new(None),
+        });
+        monitor.check();
+        Self {
+            monitor,
+            monitoring: None,
+        }
+    }
+
+    /// Don't estimate while the document is hidden - see `seed::browser::visibility`.
+    pub fn pause_when_hidden(mut self) -> Self {
+        let monitoring: Rc<dyn Pausable> = Rc::new(Monitoring(Rc::clone(&self.monitor)));
+        visibility::register(&monitoring);
+        self.monitoring = Some(monitoring);
+        self
+    }
+
+    /// Is usage above the threshold according to the last estimate?
+    pub fn above_threshold(&self) -> bool {
+        self.monitor.above_threshold.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser::{
+        time::MockClock,
+        visibility::tests::{reset_hidden, set_hidden},
+        NextTick,
+    };
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    /// Let spawned estimates resolve.
+    async fn settle() {
+        NextTick::new().await;
+        NextTick::new().await;
+    }
+
+    fn mock_estimator(
+        usages: Vec<u64>,
+    ) -> impl Fn() -> LocalBoxFuture<'static, Result<StorageEstimate, Unsupported>> {
+        let usages = RefCell::new(usages.into_iter());
+        move || {
+            let usage = usages
+                .borrow_mut()
+                .next()
+                .expect("no more mocked estimates");
+            future::ok(StorageEstimate { usage, quota: 100 }).boxed_local()
+        }
+    }
+
+    #[wasm_bindgen_test]
+    async fn quota_monitor_fires_when_threshold_is_crossed() {
+        let clock = MockClock::new();
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let handle = StorageQuotaHandle::with_estimator(
+            StorageQuotaOpts {
+                threshold: 0.8,
+                interval: Duration::from_secs(1),
+            },
+            Rc::new(clock.clone()),
+            mock_estimator(vec![50, 85, 90, 40, 95]),
+            {
+                let received = Rc::clone(&received);
+                move |estimate| received.borrow_mut().push(estimate.usage)
+            },
+        );
+
+        settle().await;
+        for _ in 0..4 {
+            clock.advance(Duration::from_secs(1));
+            settle().await;
+        }
+
+        assert_eq!(*received.borrow(), vec![85, 95]);
+        assert!(handle.above_threshold());
+        drop(handle);
+        assert_eq!(clock.pending_timers(), 0);
+    }
+
+    #[wasm_bindgen_test]
+    async fn quota_monitor_pauses_when_hidden() {
+        let clock = MockClock::new();
+        let estimates = Rc::new(Cell::new(0));
+        let _handle = StorageQuotaHandle::with_estimator(
+            StorageQuotaOpts {
+                threshold: 0.8,
+                interval: Duration::from_secs(1),
+            },
+            Rc::new(clock.clone()),
+            {
+                let estimates = Rc::clone(&estimates);
+                move || {
+                    estimates.set(estimates.get() + 1);
+                    future::ok(StorageEstimate {
+                        usage: 10,
+                        quota: 100,
+                    })
+                    .boxed_local()
+                }
+            },
+            |_| (),
+        )
+        .pause_when_hidden();
+        settle().await;
+        assert_eq!(clock.pending_timers(), 1);
+
+        set_hidden(true);
+        assert_eq!(clock.pending_timers(), 0);
+        // Hidden only for a moment - the timer is rescheduled.
+        set_hidden(false);
+        assert_eq!(estimates.get(), 1);
+        assert_eq!(clock.pending_timers(), 1);
+
+        set_hidden(true);
+        clock.advance(Duration::from_secs(5));
+        settle().await;
+        assert_eq!(estimates.get(), 1);
+        // Hidden longer than the interval - one estimate is made immediately.
+        set_hidden(false);
+        assert_eq!(estimates.get(), 2);
+        reset_hidden();
+    }
+
+    #[wasm_bindgen_test]
+    async fn quota_monitor_stops_when_unsupported() {
+        let clock = MockClock::new();
+        let _handle = StorageQuotaHandle::with_estimator(
+            StorageQuotaOpts::default(),
+            Rc::new(clock.clone()),
+            || future::err(unsupported()).boxed_local(),
+            |_| panic!("unsupported browsers don't report usage"),
+        );
+
+        settle().await;
+        assert_eq!(clock.pending_timers(), 0);
+    }
+
+    #[wasm_bindgen_test]
+    async fn quota_monitor_respects_capabilities() {
+        let clock = MockClock::new();
+        let capabilities = Capabilities::mock(&[(Capability::StorageManager, false)]);
+        let _handle = StorageQuotaHandle::new(
+            StorageQuotaOpts::default(),
+            &capabilities,
+            Rc::new(clock.clone()),
+            |_| panic!("unsupported browsers don't report usage"),
+        );
+
+        settle().await;
+        assert_eq!(clock.pending_timers(), 0);
+    }
+
+    #[wasm_bindgen_test]
+    async fn missing_storage_manager_is_unsupported() {
+        let capabilities = Capabilities::mock(&[(Capability::StorageManager, false)]);
+
+        assert_eq!(persist(&capabilities).await, Err(unsupported()));
+        assert_eq!(estimate(&capabilities).await, Err(unsupported()));
+    }
+
+    #[wasm_bindgen_test]
+    fn estimate_from_js() {
+        let estimate = js_sys::Object::new();
+        js_sys::Reflect::set(&estimate, &"usage".into(), &JsValue::from_f64(250.)).unwrap();
+        js_sys::Reflect::set(&estimate, &"quota".into(), &JsValue::from_f64(1_000.)).unwrap();
+
+        let estimate = StorageEstimate::from_js(&estimate);
+        assert_eq!(
+            estimate,
+            StorageEstimate {
+                usage: 250,
+                quota: 1_000
+            }
+        );
+        assert!((estimate.usage_fraction() - 0.25).abs() < f64::EPSILON);
+        assert!(StorageEstimate::default().usage_fraction().abs() < f64::EPSILON);
+    }
+}
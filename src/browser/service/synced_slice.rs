@@ -0,0 +1,357 @@
+//! Values shared by all tabs of the app - e.g. theme, locale or feature toggles.
+//! See `orders.sync_slice` and `orders.update_synced`.
+//!
+//! A slice is stored in local storage under its key, together with a write counter and the id
+//! of the tab which wrote it. Other tabs receive the `storage` event and send the new value
+//! to their `update`. The writing tab doesn't receive its own writes.
+//!
+//! Conflicts are resolved by "last write wins": each write's counter is higher than all counters
+//! the tab has seen (ties are broken by tab ids), and events with lower counters - delivered
+//! out of order - are discarded.
+//!
+//! When a write exceeds the storage quota, the slice falls back to the current tab only
+//! (see `SyncedSlice::is_local_only`) and a warning is logged.
+//!
+//! # References
+//! * [MDN docs](https://developer.mozilla.org/en-US/docs/Web/API/Window/storage_event)
+
+use super::storage;
+use crate::browser::{time, util};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{cell::RefCell, error::Error, fmt, rc::Rc};
+use wasm_bindgen::{closure::Closure, JsCast};
+
+thread_local! {
+    static TAB_ID: String = new_tab_id();
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn new_tab_id() -> String {
+    format!(
+        "{:x}-{:x}",
+        time::current_clock().now_ms() as u64,
+        (js_sys::Math::random() * f64::from(u32::MAX)) as u32
+    )
+}
+
+fn tab_id() -> String {
+    TAB_ID.with(Clone::clone)
+}
+
+// ------ SyncError ------
+
+/// Error of `orders.update_synced`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncError {
+    /// Local storage isn't available.
+    Unsupported,
+    /// The value can't be serialized to JSON.
+    Serialization(String),
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SyncError::Unsupported => write!(f, "Local storage isn't available"),
+            SyncError::Serialization(error) => {
+                write!(f, "Synced value can't be serialized: {}", error)
+            }
+        }
+    }
+}
+
+impl Error for SyncError {}
+
+// ------ SyncedSlice ------
+
+/// Stored value with the write's order.
+#[derive(Serialize, Deserialize)]
+struct Record<T> {
+    counter: u64,
+    tab: String,
+    value: T,
+}
+
+struct State<T> {
+    key: String,
+    /// Counter and tab id of the latest write seen by this tab.
+    last_write: Option<(u64, String)>,
+    value: Option<T>,
+    local_only: bool,
+}
+
+/// Value shared by all tabs, created by `orders.sync_slice`. Store it in your model -
+/// dropping it stops receiving other tabs' writes.
+#[must_use]
+pub struct SyncedSlice<T> {
+    state: Rc<RefCell<State<T>>>,
+    handler: Rc<dyn Fn(T)>,
+    listener: Option<Closure<dyn FnMut(web_sys::Event)>>,
+}
+
+impl<T> fmt::Debug for SyncedSlice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SyncedSlice")
+            .field("key", &self.state.borrow().key)
+            .finish()
+    }
+}
+
+impl<T: Serialize + DeserializeOwned + PartialEq + Clone + 'static> SyncedSlice<T> {
+    /// Load the value stored under `key` and invoke `handler` with each value written
+    /// by another tab.
+    pub fn new(key: impl Into<String>, handler: impl Fn(T) + 'static) -> Self {
+        let key = key.into();
+        let record = storage::get_storage()
+            .and_then(|storage| storage.get_item(&key).unwrap_or(None))
+            .and_then(|json| parse::<T>(&key, &json));
+        let state = Rc::new(RefCell::new(State {
+            key,
+            last_write: record
+                .as_ref()
+                .map(|record| (record.counter, record.tab.clone())),
+            value: record.map(|record| record.value),
+            local_only: false,
+        }));
+        let handler: Rc<dyn Fn(T)> = Rc::new(handler);
+
+        let listener = Closure::new({
+            let state = Rc::clone(&state);
+            let handler = Rc::clone(&handler);
+            move |event: web_sys::Event| {
+                if let Some(event) = event.dyn_ref::<web_sys::StorageEvent>() {
+                    if event.key().as_ref() == Some(&state.borrow().key) {
+                        handle_write(&state, &*handler, event.new_value());
+                    }
+                }
+            }
+        });
+        util::window()
+            .add_event_listener_with_callback("storage", listener.as_ref().unchecked_ref())
+            .expect("Problem adding storage listener");
+
+        Self {
+            state,
+            handler,
+            listener: Some(listener),
+        }
+    }
+
+    /// The latest value - written by this tab or received from another one.
+    /// `None` when the value hasn't been written yet.
+    pub fn get(&self) -> Option<T> {
+        self.state.borrow().value.clone()
+    }
+
+    /// Store `value` and notify other tabs. See `orders.update_synced`.
+    ///
+    /// # Errors
+    ///
+    /// Returns error when `value` can't be serialized or local storage isn't available.
+    pub fn set(&self, value: T) -> Result<(), SyncError> {
+        let mut state = self.state.borrow_mut();
+        let record = Record {
+            counter: state
+                .last_write
+                .as_ref()
+                .map_or(1, |(counter, _)| counter + 1),
+            tab: tab_id(),
+            value: &value,
+        };
+        let json = serde_json::to_string(&record)
+            .map_err(|error| SyncError::Serialization(error.to_string()))?;
+        let storage = storage::get_storage().ok_or(SyncError::Unsupported)?;
+
+        if storage.set_item(&state.key, &json).is_err() {
+            if !state.local_only {
+                web_sys::console::warn_1(
+                    &format!(
+                        "Synced value \"{}\" can't be stored (quota exceeded?) - \
+                         it's kept in this tab only.",
+                        state.key
+                    )
+                    .into(),
+                );
+            }
+            state.local_only = true;
+        } else {
+            state.local_only = false;
+        }
+        state.last_write = Some((record.counter, record.tab));
+        state.value = Some(value);
+        Ok(())
+    }
+
+    /// `true` when the latest write couldn't be stored - other tabs didn't receive it.
+    pub fn is_local_only(&self) -> bool {
+        self.state.borrow().local_only
+    }
+
+    /// Handle the stored JSON `new_value` like a `storage` event caused by another tab's write.
+    /// It allows to test multi-tab scenarios (echoes, out-of-order events) in a single tab.
+    pub fn simulate_storage_event(&self, new_value: Option<&str>) {
+        handle_write(
+            &self.state,
+            &*self.handler,
+            new_value.map(ToOwned::to_owned),
+        );
+    }
+
+    /// Create a stored JSON value as if it was written by the tab `tab` with the write `counter`.
+    /// See `simulate_storage_event`.
+    ///
+    /// # Errors
+    ///
+    /// Returns error when `value` can't be serialized.
+    pub fn simulated_record(counter: u64, tab: &str, value: &T) -> Result<String, SyncError> {
+        serde_json::to_string(&Record {
+            counter,
+            tab: tab.to_owned(),
+            value,
+        })
+        .map_err(|error| SyncError::Serialization(error.to_string()))
+    }
+}
+
+impl<T> Drop for SyncedSlice<T> {
+    fn drop(&mut self) {
+        if let Some(listener) = self.listener.take() {
+            util::window()
+                .remove_event_listener_with_callback("storage", listener.as_ref().unchecked_ref())
+                .expect("Problem removing storage listener");
+        }
+    }
+}
+
+fn parse<T: DeserializeOwned>(key: &str, json: &str) -> Option<Record<T>> {
+    match serde_json::from_str(json) {
+        Ok(record) => Some(record),
+        Err(error) => {
+            crate::error(format!(
+                "Synced value \"{}\" can't be deserialized: {}",
+                key, error
+            ));
+            None
+        }
+    }
+}
+
+/// Send the value written by another tab to `handler`, unless it's an echo of this tab's write,
+/// it's older than the latest seen write or the value hasn't changed.
+fn handle_write<T: DeserializeOwned + PartialEq + Clone>(
+    state: &RefCell<State<T>>,
+    handler: &dyn Fn(T),
+    new_value: Option<String>,
+) {
+    let value = {
+        let mut state = state.borrow_mut();
+        // The value has been removed (e.g. `localStorage.clear()`).
+        let json = match new_value {
+            Some(json) => json,
+            None => return,
+        };
+        let record = match parse::<T>(&state.key, &json) {
+            Some(record) => record,
+            None => return,
+        };
+        if record.tab == tab_id() {
+            return;
+        }
+        let write = (record.counter, record.tab);
+        if state
+            .last_write
+            .as_ref()
+            .map_or(false, |last_write| &write <= last_write)
+        {
+            return;
+        }
+        state.last_write = Some(write);
+        if state.value.as_ref() == Some(&record.value) {
+            return;
+        }
+        state.value = Some(record.value.clone());
+        record.value
+    };
+    // `handler` may write to the slice, so the state mustn't be borrowed.
+    handler(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn record(counter: u64, tab: &str, value: &str) -> String {
+        SyncedSlice::simulated_record(counter, tab, &value.to_owned()).unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn other_tabs_writes() {
+        let key = "seed-test-synced-theme";
+        storage::get_storage().unwrap().remove_item(key).unwrap();
+
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let slice = SyncedSlice::new(key, {
+            let received = Rc::clone(&received);
+            move |theme: String| received.borrow_mut().push(theme)
+        });
+        assert_eq!(slice.get(), None);
+
+        // Own write - it's stored, but it isn't sent back.
+        slice.set("dark".to_owned()).unwrap();
+        let stored = storage::get_storage()
+            .unwrap()
+            .get_item(key)
+            .unwrap()
+            .unwrap();
+        slice.simulate_storage_event(Some(&stored));
+        assert!(received.borrow().is_empty());
+
+        // Another tab's write.
+        slice.simulate_storage_event(Some(&record(2, "other", "light")));
+        // Out-of-order event.
+        slice.simulate_storage_event(Some(&record(1, "other", "blue")));
+        // Duplicate event.
+        slice.simulate_storage_event(Some(&record(2, "other", "light")));
+        // The same counter from another tab - tab ids break the tie.
+        slice.simulate_storage_event(Some(&record(2, "other-b", "light")));
+        // Removed value and invalid JSON.
+        slice.simulate_storage_event(None);
+        slice.simulate_storage_event(Some("{"));
+        assert_eq!(*received.borrow(), vec!["light".to_owned()]);
+        assert_eq!(slice.get(), Some("light".to_owned()));
+
+        // The next own write wins over all seen writes.
+        slice.set("sepia".to_owned()).unwrap();
+        let stored = storage::get_storage()
+            .unwrap()
+            .get_item(key)
+            .unwrap()
+            .unwrap();
+        assert!(stored.starts_with(r#"{"counter":3,"#));
+        assert!(!slice.is_local_only());
+
+        // A new slice (e.g. after reload) loads the stored value.
+        drop(slice);
+        let slice = SyncedSlice::new(key, |_: String| ());
+        assert_eq!(slice.get(), Some("sepia".to_owned()));
+        storage::get_storage().unwrap().remove_item(key).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn serialization_error() {
+        use std::collections::HashMap;
+
+        let slice = SyncedSlice::new("seed-test-synced-map", |_: HashMap<Vec<u8>, u8>| ());
+        let mut map = HashMap::new();
+        map.insert(vec![1], 1);
+        // JSON keys have to be strings.
+        match slice.set(map) {
+            Err(SyncError::Serialization(_)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert_eq!(slice.get(), None);
+    }
+}
@@ -0,0 +1,300 @@
+//! WebSocket connections integrated with `Orders`. See `orders.open_websocket`.
+//!
+//! The `WebSocket` handle owns the underlying `web_sys::WebSocket` together with its event
+//! closures - store it in your model. Dropping the handle detaches the closures and closes
+//! the connection with the code `1000` (normal closure).
+//!
+//! Binary frames are received as `WebSocketMessage::Binary` (the socket's `binaryType`
+//! is `arraybuffer`).
+//!
+//! # Reconnecting
+//!
+//! The handle doesn't reconnect on its own. Inspect `CloseEvent` in your `on_close` message
+//! (e.g. reconnect only when `was_clean` is `false` or on the code `1006` - abnormal closure)
+//! and replace the handle in your model by a new one from `orders.open_websocket`.
+//!
+//! # References
+//! * [MDN docs](https://developer.mozilla.org/en-US/docs/Web/API/WebSocket)
+
+use crate::browser::util::ClosureNew;
+use std::{error::Error, fmt, rc::Rc};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+/// https://developer.mozilla.org/en-US/docs/Web/API/CloseEvent#Status_codes
+const NORMAL_CLOSURE: u16 = 1000;
+
+// ------ WebSocketMessage ------
+
+/// Data frame received from the server.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebSocketMessage {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl WebSocketMessage {
+    fn from_event(event: &web_sys::MessageEvent) -> Self {
+        let data = event.data();
+        if let Some(text) = data.as_string() {
+            return WebSocketMessage::Text(text);
+        }
+        WebSocketMessage::Binary(js_sys::Uint8Array::new(&data).to_vec())
+    }
+}
+
+// ------ CloseEvent ------
+
+/// Information about the closed connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloseEvent {
+    /// Close code sent by the server, or `1006` when the connection was lost.
+    pub code: u16,
+    pub reason: String,
+    /// `false` when the connection wasn't closed by the closing handshake.
+    pub was_clean: bool,
+}
+
+// ------ WebSocketEvent ------
+
+/// Event of the `WebSocket` handle. See `WebSocket::open`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebSocketEvent {
+    Open,
+    Message(WebSocketMessage),
+    Close(CloseEvent),
+    Error,
+}
+
+// ------ WebSocketError ------
+
+/// Error of `WebSocket::open` and `WebSocket::send_*`.
+#[derive(Debug, Clone)]
+pub enum WebSocketError {
+    /// The URL is invalid or its port is blocked.
+    Open(JsValue),
+    /// The connection isn't open.
+    Send(JsValue),
+}
+
+impl fmt::Display for WebSocketError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WebSocketError::Open(error) => write!(f, "WebSocket can't be opened: {:?}", error),
+            WebSocketError::Send(error) => write!(f, "WebSocket frame can't be sent: {:?}", error),
+        }
+    }
+}
+
+impl Error for WebSocketError {}
+
+// ------ WebSocketHandlers ------
+
+/// Functions mapping `WebSocket` events to your messages. Events without a handler are ignored.
+///
+/// # Example
+///
+/// ```rust,no_run
+///WebSocketHandlers::new()
+///    .on_open(|| Msg::Connected)
+///    .on_message(Msg::Received)
+///    .on_close(Msg::Closed)
+/// ```
+pub struct WebSocketHandlers<Ms> {
+    on_open: Option<Rc<dyn Fn() -> Ms>>,
+    on_message: Option<Rc<dyn Fn(WebSocketMessage) -> Ms>>,
+    on_close: Option<Rc<dyn Fn(CloseEvent) -> Ms>>,
+    on_error: Option<Rc<dyn Fn() -> Ms>>,
+}
+
+impl<Ms: 'static> WebSocketHandlers<Ms> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_open(mut self, handler: impl Fn() -> Ms + 'static) -> Self {
+        self.on_open = Some(Rc::new(handler));
+        self
+    }
+
+    pub fn on_message(mut self, handler: impl Fn(WebSocketMessage) -> Ms + 'static) -> Self {
+        self.on_message = Some(Rc::new(handler));
+        self
+    }
+
+    /// Use `CloseEvent` to decide whether to reconnect.
+    pub fn on_close(mut self, handler: impl Fn(CloseEvent) -> Ms + 'static) -> Self {
+        self.on_close = Some(Rc::new(handler));
+        self
+    }
+
+    /// The error event doesn't carry any details - the close event with the code follows.
+    pub fn on_error(mut self, handler: impl Fn() -> Ms + 'static) -> Self {
+        self.on_error = Some(Rc::new(handler));
+        self
+    }
+
+    /// Map the event to a message, if there is a handler for it.
+    pub fn msg(&self, event: WebSocketEvent) -> Option<Ms> {
+        match event {
+            WebSocketEvent::Open => self.on_open.as_ref().map(|handler| handler()),
+            WebSocketEvent::Message(message) => {
+                self.on_message.as_ref().map(|handler| handler(message))
+            }
+            WebSocketEvent::Close(close_event) => {
+                self.on_close.as_ref().map(|handler| handler(close_event))
+            }
+            WebSocketEvent::Error => self.on_error.as_ref().map(|handler| handler()),
+        }
+    }
+
+    pub fn map_msg<OtherMs: 'static>(
+        self,
+        f: impl Fn(Ms) -> OtherMs + Clone + 'static,
+    ) -> WebSocketHandlers<OtherMs> {
+        WebSocketHandlers {
+            on_open: self.on_open.map(|handler| {
+                let f = f.clone();
+                Rc::new(move || f(handler())) as Rc<dyn Fn() -> OtherMs>
+            }),
+            on_message: self.on_message.map(|handler| {
+                let f = f.clone();
+                Rc::new(move |message| f(handler(message)))
+                    as Rc<dyn Fn(WebSocketMessage) -> OtherMs>
+            }),
+            on_close: self.on_close.map(|handler| {
+                let f = f.clone();
+                Rc::new(move |close_event| f(handler(close_event)))
+                    as Rc<dyn Fn(CloseEvent) -> OtherMs>
+            }),
+            on_error: self
+                .on_error
+                .map(|handler| Rc::new(move || f(handler())) as Rc<dyn Fn() -> OtherMs>),
+        }
+    }
+}
+
+impl<Ms> Default for WebSocketHandlers<Ms> {
+    fn default() -> Self {
+        Self {
+            on_open: None,
+            on_message: None,
+            on_close: None,
+            on_error: None,
+        }
+    }
+}
+
+// ------ WebSocket ------
+
+/// Open WebSocket connection. Dropping the handle closes the connection.
+#[must_use]
+pub struct WebSocket {
+    ws: web_sys::WebSocket,
+    _on_open: Closure<dyn FnMut(web_sys::Event)>,
+    _on_message: Closure<dyn FnMut(web_sys::MessageEvent)>,
+    _on_close: Closure<dyn FnMut(web_sys::CloseEvent)>,
+    _on_error: Closure<dyn FnMut(web_sys::Event)>,
+}
+
+impl WebSocket {
+    /// Connect to `url` (e.g. `wss://example.com/ws`) and invoke `callback` on socket's events.
+    ///
+    /// # Errors
+    ///
+    /// Returns `WebSocketError::Open` when the URL is invalid or its port is blocked.
+    pub fn open(
+        url: &str,
+        callback: impl Fn(WebSocketEvent) + 'static,
+    ) -> Result<Self, WebSocketError> {
+        let ws = web_sys::WebSocket::new(url).map_err(WebSocketError::Open)?;
+        ws.set_binary_type(web_sys::BinaryType::Arraybuffer);
+
+        let callback = Rc::new(callback);
+        let on_open = Closure::new({
+            let callback = Rc::clone(&callback);
+            move |_: web_sys::Event| callback(WebSocketEvent::Open)
+        });
+        let on_message = Closure::new({
+            let callback = Rc::clone(&callback);
+            move |event: web_sys::MessageEvent| {
+                callback(WebSocketEvent::Message(WebSocketMessage::from_event(
+                    &event,
+                )))
+            }
+        });
+        let on_close = Closure::new({
+            let callback = Rc::clone(&callback);
+            move |event: web_sys::CloseEvent| {
+                callback(WebSocketEvent::Close(CloseEvent {
+                    code: event.code(),
+                    reason: event.reason(),
+                    was_clean: event.was_clean(),
+                }))
+            }
+        });
+        let on_error = Closure::new(move |_: web_sys::Event| callback(WebSocketEvent::Error));
+
+        ws.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        ws.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        ws.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+        ws.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+
+        Ok(Self {
+            ws,
+            _on_open: on_open,
+            _on_message: on_message,
+            _on_close: on_close,
+            _on_error: on_error,
+        })
+    }
+
+    /// Send a text frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns `WebSocketError::Send` when the connection isn't open.
+    pub fn send_text(&self, text: &str) -> Result<(), WebSocketError> {
+        self.ws.send_with_str(text).map_err(WebSocketError::Send)
+    }
+
+    /// Send a binary frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns `WebSocketError::Send` when the connection isn't open.
+    pub fn send_bytes(&self, bytes: &[u8]) -> Result<(), WebSocketError> {
+        self.ws
+            .send_with_array_buffer_view(&js_sys::Uint8Array::from(bytes))
+            .map_err(WebSocketError::Send)
+    }
+
+    /// The underlying socket - e.g. to read `buffered_amount`.
+    pub fn raw_socket(&self) -> &web_sys::WebSocket {
+        &self.ws
+    }
+}
+
+impl Drop for WebSocket {
+    fn drop(&mut self) {
+        // Handlers are detached first - the app mustn't receive events
+        // from a socket it doesn't own anymore.
+        self.ws.set_onopen(None);
+        self.ws.set_onmessage(None);
+        self.ws.set_onclose(None);
+        self.ws.set_onerror(None);
+
+        let ready_state = self.ws.ready_state();
+        if ready_state == web_sys::WebSocket::CONNECTING || ready_state == web_sys::WebSocket::OPEN
+        {
+            self.ws
+                .close_with_code(NORMAL_CLOSURE)
+                .expect("Problem closing WebSocket");
+        }
+    }
+}
+
+impl fmt::Debug for WebSocket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WebSocket({})", self.ws.url())
+    }
+}
@@ -0,0 +1,280 @@
+//! Native share sheet (Web Share API) with a clipboard fallback.
+//!
+//! Browsers allow `navigator.share` only during a user gesture (e.g. a click handler).
+//! That's why `share` and `copy_to_clipboard` call the browser API immediately when they are
+//! called - not when the returned future is polled - and why `Orders::share` doesn't wait
+//! for the next tick like other commands do. Only the result is delivered asynchronously.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//!fn view(model: &Model) -> Node<Msg> {
+//!    // `capabilities` is `orders.capabilities().clone()` saved in `init`.
+//!    if seed::browser::can_share(&model.capabilities, &model.share_data) {
+//!        button!["Share", ev(Ev::Click, |_| Msg::Share)]
+//!    } else {
+//!        button!["Copy link", ev(Ev::Click, |_| Msg::CopyLink)]
+//!    }
+//!}
+//! ```
+
+use super::{
+    util::{self, get_property},
+    Capabilities, Capability, Unsupported,
+};
+use futures::future::{self, FutureExt};
+use std::{error, fmt, future::Future};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+// ------ ShareData ------
+
+/// Data for the share sheet. At least one field should be set.
+///
+/// # Example
+///
+/// ```rust,no_run
+///let data = ShareData {
+///    title: Some("Seed".to_owned()),
+///    url: Some("https://seed-rs.org".to_owned()),
+///    ..ShareData::default()
+///};
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ShareData {
+    pub title: Option<String>,
+    pub text: Option<String>,
+    pub url: Option<String>,
+    /// Not all browsers can share files - check it by `can_share`.
+    pub files: Vec<web_sys::File>,
+}
+
+impl ShareData {
+    fn to_js(&self) -> JsValue {
+        let data = js_sys::Object::new();
+        let fields = [
+            ("title", &self.title),
+            ("text", &self.text),
+            ("url", &self.url),
+        ];
+        for (name, value) in fields.iter() {
+            if let Some(value) = value {
+                set_property(&data, name, &JsValue::from_str(value));
+            }
+        }
+        if !self.files.is_empty() {
+            let files = self.files.iter().collect::<js_sys::Array>();
+            set_property(&data, "files", &files);
+        }
+        data.into()
+    }
+}
+
+// ------ ShareError ------
+
+/// Error of `share` and `copy_to_clipboard`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShareError {
+    /// The browser doesn't support the API - see `seed::browser::Capabilities`.
+    /// Use a fallback - e.g. `copy_to_clipboard`.
+    Unsupported(Unsupported),
+    /// The user dismissed the share sheet.
+    Cancelled,
+    /// The permission has been denied or the API wasn't called during a user gesture.
+    NotAllowed(String),
+    /// The data are invalid - e.g. a malformed URL.
+    InvalidData(String),
+}
+
+impl ShareError {
+    fn from_js(error: &JsValue) -> Self {
+        let name = get_property(error, "name")
+            .and_then(|name| name.as_string())
+            .unwrap_or_default();
+        let message = get_property(error, "message")
+            .and_then(|message| message.as_string())
+            .unwrap_or_else(|| format!("{:?}", error));
+        match name.as_str() {
+            "AbortError" => ShareError::Cancelled,
+            "TypeError" | "DataError" => ShareError::InvalidData(message),
+            _ => ShareError::NotAllowed(message),
+        }
+    }
+}
+
+impl fmt::Display for ShareError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShareError::Unsupported(unsupported) => write!(f, "{}", unsupported),
+            ShareError::Cancelled => write!(f, "Sharing has been cancelled"),
+            ShareError::NotAllowed(message) => write!(f, "Sharing isn't allowed: {}", message),
+            ShareError::InvalidData(message) => write!(f, "Invalid share data: {}", message),
+        }
+    }
+}
+
+impl error::Error for ShareError {}
+
+// ------ API ------
+
+/// Can the browser share `data` through the native share sheet?
+/// Use it to decide between a share button and a fallback (e.g. a copy-link button).
+pub fn can_share(capabilities: &Capabilities, data: &ShareData) -> bool {
+    if !capabilities.has(Capability::Share) {
+        return false;
+    }
+    let navigator = navigator();
+    match get_method(&navigator, "canShare") {
+        Some(can_share) => can_share
+            .call1(&navigator, &data.to_js())
+            .map_or(false, |result| result.is_truthy()),
+        // Browsers without `canShare` can't share files.
+        None => data.files.is_empty(),
+    }
+}
+
+/// Open the native share sheet with `data`.
+///
+/// The browser API is called immediately - call `share` synchronously from an event handler
+/// or use `Orders::share` in `update`. See the module docs.
+pub fn share(
+    capabilities: &Capabilities,
+    data: &ShareData,
+) -> impl Future<Output = Result<(), ShareError>> {
+    let promise = match capabilities.require(Capability::Share) {
+        Ok(()) if can_share(capabilities, data) => {
+            call_promise(&navigator(), "share", &data.to_js(), Capability::Share)
+        }
+        Ok(()) => Err(ShareError::InvalidData(
+            "The browser can't share the data".to_owned(),
+        )),
+        Err(unsupported) => Err(ShareError::Unsupported(unsupported)),
+    };
+    settle(promise)
+}
+
+/// Copy `text` to the clipboard - a fallback for browsers without the native share sheet.
+///
+/// The browser API is called immediately, like in `share`.
+pub fn copy_to_clipboard(
+    capabilities: &Capabilities,
+    text: &str,
+) -> impl Future<Output = Result<(), ShareError>> {
+    let promise = match capabilities.require(Capability::Clipboard) {
+        Ok(()) => get_property(&navigator(), "clipboard")
+            .ok_or_else(|| unsupported(Capability::Clipboard))
+            .and_then(|clipboard| {
+                call_promise(
+                    &clipboard,
+                    "writeText",
+                    &JsValue::from_str(text),
+                    Capability::Clipboard,
+                )
+            }),
+        Err(unsupported) => Err(ShareError::Unsupported(unsupported)),
+    };
+    settle(promise)
+}
+
+fn settle(
+    promise: Result<js_sys::Promise, ShareError>,
+) -> impl Future<Output = Result<(), ShareError>> {
+    match promise {
+        Ok(promise) => JsFuture::from(promise)
+            .map(|result| {
+                result
+                    .map(|_| ())
+                    .map_err(|error| ShareError::from_js(&error))
+            })
+            .left_future(),
+        Err(error) => future::err(error).right_future(),
+    }
+}
+
+// ------ Helpers ------
+
+fn navigator() -> JsValue {
+    get_property(&util::window(), "navigator").expect("Problem getting `navigator`")
+}
+
+/// Call `target.method(arg)` which returns a `Promise`.
+/// A missing method or a non-`Promise` result means the `capability` isn't fully supported.
+fn call_promise(
+    target: &JsValue,
+    method: &str,
+    arg: &JsValue,
+    capability: Capability,
+) -> Result<js_sys::Promise, ShareError> {
+    let method = get_method(target, method).ok_or_else(|| unsupported(capability))?;
+    method
+        .call1(target, arg)
+        .map_err(|error| ShareError::from_js(&error))?
+        .dyn_into::<js_sys::Promise>()
+        .map_err(|_| unsupported(capability))
+}
+
+fn unsupported(capability: Capability) -> ShareError {
+    ShareError::Unsupported(Unsupported {
+        missing: vec![capability],
+    })
+}
+
+fn get_method(target: &JsValue, name: &str) -> Option<js_sys::Function> {
+    get_property(target, name).and_then(|method| method.dyn_into::<js_sys::Function>().ok())
+}
+
+fn set_property(target: &js_sys::Object, name: &str, value: &JsValue) {
+    js_sys::Reflect::set(target, &JsValue::from_str(name), value)
+        .expect("Problem setting share data property");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn error(name: &str, message: &str) -> JsValue {
+        let error = js_sys::Error::new(message);
+        error.set_name(name);
+        error.into()
+    }
+
+    #[wasm_bindgen_test]
+    fn share_errors_are_classified() {
+        assert_eq!(
+            ShareError::from_js(&error("AbortError", "Share canceled")),
+            ShareError::Cancelled
+        );
+        assert_eq!(
+            ShareError::from_js(&error("NotAllowedError", "Must be handling a user gesture")),
+            ShareError::NotAllowed("Must be handling a user gesture".to_owned())
+        );
+        assert_eq!(
+            ShareError::from_js(&error("TypeError", "Invalid URL")),
+            ShareError::InvalidData("Invalid URL".to_owned())
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn share_data_to_js() {
+        let data = ShareData {
+            title: Some("Seed".to_owned()),
+            url: Some("https://seed-rs.org".to_owned()),
+            ..ShareData::default()
+        }
+        .to_js();
+
+        assert_eq!(
+            get_property(&data, "title").and_then(|title| title.as_string()),
+            Some("Seed".to_owned())
+        );
+        assert_eq!(
+            get_property(&data, "url").and_then(|url| url.as_string()),
+            Some("https://seed-rs.org".to_owned())
+        );
+        assert!(get_property(&data, "text").is_none());
+        assert!(get_property(&data, "files").is_none());
+    }
+}
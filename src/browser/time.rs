@@ -0,0 +1,272 @@
+//! Time source of the framework.
+//!
+//! All time-dependent framework features (deferred navigations, deferred message queues,
+//! render timestamps, `cmds::timeout`, `streams::interval`, fetch timeouts, ..) read time
+//! and schedule timers through the app's `Clock`. Features created outside of the app
+//! (e.g. requests) use the clock of the app whose `update` is running - see `current_clock`.
+//! It's `BrowserClock` by default; use `MockClock` in tests to control time manually:
+//!
+//! ```rust,no_run
+//!let clock = MockClock::new();
+//!let app = App::builder(update, view).clock(clock.clone()).build_and_start();
+//!app.update(Msg::Search("seed".to_owned()));
+//!clock.advance(Duration::from_millis(300));
+//! ```
+
+use crate::browser::util;
+use futures::future;
+use gloo_timers::callback::Timeout;
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    convert::TryFrom,
+    fmt,
+    future::Future,
+    mem,
+    rc::Rc,
+    time::Duration,
+};
+
+thread_local! {
+    /// Clock of the app whose `update` is running - see `with_clock`.
+    static RUNNING: RefCell<Option<Rc<dyn Clock>>> = RefCell::new(None);
+}
+
+/// Convert `duration` to milliseconds accepted by browser timers.
+/// It saturates on `u32::MAX`.
+pub fn duration_to_ms(duration: Duration) -> u32 {
+    u32::try_from(duration.as_millis()).unwrap_or(u32::MAX)
+}
+
+/// Clock of the app whose `update` (or `init`) is running, `BrowserClock` outside of them.
+/// Timers of `cmds::timeout`, `streams::interval` and fetch timeouts are scheduled by it.
+pub fn current_clock() -> Rc<dyn Clock> {
+    RUNNING
+        .with(|running| running.borrow().clone())
+        .unwrap_or_else(|| Rc::new(BrowserClock))
+}
+
+/// Call `f` (the app's `update` or `init`) with the app's `clock` - `current_clock` returns it.
+pub(crate) fn with_clock<R>(clock: &Rc<dyn Clock>, f: impl FnOnce() -> R) -> R {
+    let previous = RUNNING.with(|running| running.replace(Some(Rc::clone(clock))));
+    let output = f();
+    RUNNING.with(|running| running.replace(previous));
+    output
+}
+
+/// Poll `future` with the app's `clock` as `current_clock` - e.g. requests sent by commands
+/// time out by the app's clock.
+pub(crate) fn with_clock_polled<F: Future + 'static>(
+    clock: Rc<dyn Clock>,
+    future: F,
+) -> impl Future<Output = F::Output> {
+    let mut future = Box::pin(future);
+    future::poll_fn(move |cx| with_clock(&clock, || future.as_mut().poll(cx)))
+}
+
+/// Time source and timer scheduler.
+pub trait Clock {
+    /// Milliseconds since an arbitrary origin (e.g. `performance.now()`).
+    fn now_ms(&self) -> f64;
+
+    /// Call `callback` after `ms` milliseconds. Dropping the returned handle cancels the timer.
+    fn set_timeout(&self, ms: u32, callback: Box<dyn FnOnce()>) -> TimeoutHandle;
+}
+
+/// Timer scheduled by `Clock::set_timeout`. It's cancelled when dropped.
+#[must_use = "the timer is cancelled when the handle is dropped"]
+pub struct TimeoutHandle {
+    cancel: Option<Box<dyn FnOnce()>>,
+}
+
+impl TimeoutHandle {
+    /// Create a handle which calls `cancel` when it's dropped.
+    pub fn new(cancel: impl FnOnce() + 'static) -> Self {
+        Self {
+            cancel: Some(Box::new(cancel)),
+        }
+    }
+
+    /// Keep the timer running even when the handle is dropped.
+    pub fn forget(mut self) {
+        // `cancel` may own the timer (e.g. `gloo_timers::callback::Timeout`) - leak it.
+        mem::forget(self.cancel.take());
+    }
+}
+
+impl fmt::Debug for TimeoutHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TimeoutHandle").finish()
+    }
+}
+
+impl Drop for TimeoutHandle {
+    fn drop(&mut self) {
+        if let Some(cancel) = self.cancel.take() {
+            cancel();
+        }
+    }
+}
+
+// ------ BrowserClock ------
+
+/// `Clock` backed by `performance.now()` and `setTimeout`.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct BrowserClock;
+
+impl Clock for BrowserClock {
+    fn now_ms(&self) -> f64 {
+        util::window()
+            .performance()
+            .expect("Problem getting `Performance`")
+            .now()
+    }
+
+    fn set_timeout(&self, ms: u32, callback: Box<dyn FnOnce()>) -> TimeoutHandle {
+        let timeout = Timeout::new(ms, callback);
+        TimeoutHandle::new(move || drop(timeout))
+    }
+}
+
+// ------ MockClock ------
+
+type TimerId = (u64, u64);
+
+#[derive(Default)]
+struct MockClockState {
+    now_ms: Cell<u64>,
+    next_timer_id: Cell<u64>,
+    // Ordered by due time and then by the order of scheduling.
+    timers: RefCell<BTreeMap<TimerId, Box<dyn FnOnce()>>>,
+}
+
+/// `Clock` for tests - time stands still until `advance` is called.
+/// Clones share the same time and timers.
+#[derive(Clone, Default)]
+pub struct MockClock {
+    state: Rc<MockClockState>,
+}
+
+impl MockClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move time forward and call all timers which are due, in order.
+    /// Timers scheduled by the called timers are called too if they are due.
+    pub fn advance(&self, duration: Duration) {
+        let target_ms = self.state.now_ms.get() + u64::from(duration_to_ms(duration));
+        loop {
+            let due = {
+                let mut timers = self.state.timers.borrow_mut();
+                let first_due = timers
+                    .keys()
+                    .next()
+                    .copied()
+                    .filter(|(due_ms, _)| *due_ms <= target_ms);
+                first_due.and_then(|id| timers.remove(&id).map(|callback| (id.0, callback)))
+            };
+            match due {
+                Some((due_ms, callback)) => {
+                    self.state.now_ms.set(due_ms);
+                    callback();
+                }
+                None => break,
+            }
+        }
+        self.state.now_ms.set(target_ms);
+    }
+
+    /// Number of scheduled timers.
+    pub fn pending_timers(&self) -> usize {
+        self.state.timers.borrow().len()
+    }
+}
+
+impl Clock for MockClock {
+    #[allow(clippy::cast_precision_loss)]
+    fn now_ms(&self) -> f64 {
+        self.state.now_ms.get() as f64
+    }
+
+    fn set_timeout(&self, ms: u32, callback: Box<dyn FnOnce()>) -> TimeoutHandle {
+        let sequence = self.state.next_timer_id.get();
+        self.state.next_timer_id.set(sequence + 1);
+        let id = (self.state.now_ms.get() + u64::from(ms), sequence);
+        self.state.timers.borrow_mut().insert(id, callback);
+
+        let state = Rc::downgrade(&self.state);
+        TimeoutHandle::new(move || {
+            if let Some(state) = state.upgrade() {
+                state.timers.borrow_mut().remove(&id);
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn mock_clock_fires_due_timers_in_order() {
+        let clock = MockClock::new();
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        let push = |entry: &'static str| {
+            let log = Rc::clone(&log);
+            Box::new(move || log.borrow_mut().push(entry))
+        };
+        clock.set_timeout(20, push("b")).forget();
+        clock.set_timeout(10, push("a")).forget();
+        clock.set_timeout(20, push("c")).forget();
+        let cancelled = clock.set_timeout(5, push("cancelled"));
+        drop(cancelled);
+
+        clock.advance(Duration::from_millis(15));
+        assert_eq!(*log.borrow(), vec!["a"]);
+        assert!((clock.now_ms() - 15.).abs() < f64::EPSILON);
+
+        clock.advance(Duration::from_millis(5));
+        assert_eq!(*log.borrow(), vec!["a", "b", "c"]);
+        assert_eq!(clock.pending_timers(), 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn mock_clock_fires_nested_timers() {
+        let clock = MockClock::new();
+        let fired_at = Rc::new(Cell::new(None));
+
+        let inner_clock = clock.clone();
+        let inner_fired_at = Rc::clone(&fired_at);
+        clock
+            .set_timeout(
+                10,
+                Box::new(move || {
+                    let clock = inner_clock.clone();
+                    inner_clock
+                        .set_timeout(
+                            10,
+                            Box::new(move || inner_fired_at.set(Some(clock.now_ms()))),
+                        )
+                        .forget();
+                }),
+            )
+            .forget();
+
+        clock.advance(Duration::from_millis(30));
+        assert!((fired_at.get().expect("nested timer wasn't fired") - 20.).abs() < f64::EPSILON);
+    }
+
+    #[wasm_bindgen_test]
+    fn duration_to_ms_saturates() {
+        assert_eq!(duration_to_ms(Duration::from_millis(1_500)), 1_500);
+        assert_eq!(
+            duration_to_ms(Duration::from_secs(u64::max_value())),
+            u32::max_value()
+        );
+    }
+}
@@ -1,9 +1,39 @@
-use crate::browser::util;
-use serde::{Deserialize, Serialize};
-use std::convert::TryFrom;
+use crate::browser::{service::routing::navigation, util};
+use indexmap::IndexMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{convert::TryFrom, error, fmt};
+
+mod query;
+
+/// Error of `Url::search_as` and `Url::set_search`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UrlError {
+    /// The query doesn't match the type or the value can't be serialized into a query
+    /// (e.g. it isn't a struct or it contains nested structs).
+    InvalidQuery(String),
+}
+
+impl fmt::Display for UrlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UrlError::InvalidQuery(error) => write!(f, "Invalid URL query: {}", error),
+        }
+    }
+}
+
+impl error::Error for UrlError {}
+
+impl serde::de::Error for UrlError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        UrlError::InvalidQuery(msg.to_string())
+    }
+}
 
 /// For setting up landing page routing. Unlike normal routing, we can't rely
 /// on the popstate state, so must go off path, hash, and search directly.
+///
+/// _Note:_ The path contains the app's base path (see `AppBuilder::base_path`) -
+/// use `orders.current_url()` to get the URL without it.
 pub fn current() -> Url {
     let current_url = util::window().location().href().expect("get `href`");
 
@@ -12,14 +42,69 @@ pub fn current() -> Url {
         .into()
 }
 
+// ------ Base path ------
+
+/// `current` without `base_path` (see `AppBuilder::base_path`).
+/// The path is kept when it doesn't start with the base path.
+pub(crate) fn current_without_base_path(base_path: &[String]) -> Url {
+    let url = current();
+    navigation::strip_base_path(url.clone(), base_path).unwrap_or(url)
+}
+
+/// Absolute path with `base_path` - e.g. `["users", "1"]` => `/admin/users/1`.
+pub(crate) fn absolute_path(base_path: &[String], path: &[String]) -> String {
+    let segments = base_path.iter().chain(path).cloned().collect::<Vec<_>>();
+    serialize_path(&segments)
+}
+
+// ------ Path segments ------
+
+/// Parse `pathname` (e.g. `/docs/Q3%2FQ4`) into path segments (`["docs", "Q3/Q4"]`).
+///
+/// It's the only path parser - all navigations (the current URL, links, `popstate`,
+/// `hashchange` and forms) use it, so segments are always split on literal `/` first
+/// and percent-decoded after that. Encoded slashes (`%2F`) stay in their segments
+/// and `+` isn't decoded to space. Malformed escape sequences are kept as they are.
+pub(crate) fn parse_path(pathname: &str) -> Vec<String> {
+    let pathname = if pathname.starts_with('/') {
+        &pathname[1..]
+    } else {
+        pathname
+    };
+    pathname.split('/').map(decode_segment).collect()
+}
+
+/// Percent-encode `path` segments and join them - the reverse of `parse_path`.
+pub(crate) fn serialize_path(path: &[String]) -> String {
+    let segments = path
+        .iter()
+        .map(|segment| encode_segment(segment))
+        .collect::<Vec<_>>();
+    String::from("/") + &segments.join("/")
+}
+
+pub(crate) fn decode_segment(segment: &str) -> String {
+    js_sys::decode_uri_component(segment)
+        .map(String::from)
+        .unwrap_or_else(|_| segment.to_owned())
+}
+
+fn encode_segment(segment: &str) -> String {
+    js_sys::encode_uri_component(segment).into()
+}
+
 /// Contains all information used in pushing and handling routes.
 /// Based on [React-Reason's router](https://github.com/reasonml/reason-react/blob/master/docs/router.md).
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Url {
+    /// Percent-decoded path segments - they are encoded when the URL is serialized.
     pub path: Vec<String>,
     pub search: Option<String>,
     pub hash: Option<String>,
     pub title: Option<String>,
+    /// History entry's state - see `push_route_with_state` and `Url::state`.
+    #[serde(default)]
+    pub state: Option<serde_json::Value>,
 }
 
 impl Url {
@@ -33,6 +118,7 @@ impl Url {
             hash: None,
             search: None,
             title: None,
+            state: None,
         }
     }
 
@@ -54,20 +140,149 @@ impl Url {
         self
     }
 
+    /// Builder-pattern method for defining path. Segments are percent-encoded
+    /// on serialization (`/` included) and an empty path is serialized as `/`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///let url = Url::default().path(&["users", &user.id]).search_param("tab", "posts");
+    ///orders.push_route(url);
+    /// ```
+    pub fn path(mut self, segments: &[impl ToString]) -> Self {
+        self.path = segments.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    /// Builder-pattern method for appending a percent-encoded `key=value` pair to search.
+    pub fn search_param(mut self, key: &str, value: &str) -> Self {
+        let param = format!("{}={}", query::encode(key), query::encode(value));
+        self.search = Some(match self.search {
+            Some(search) if !search.is_empty() => search + "&" + &param,
+            _ => param,
+        });
+        self
+    }
+
     pub fn title(mut self, title: &str) -> Self {
         self.title = Some(title.into());
         self
     }
+
+    /// Deserialize the state of the history entry - see `push_route_with_state`.
+    /// Returns `None` when the entry doesn't have any state (e.g. it's been pushed by non-Seed
+    /// code) or when the state isn't `T`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///fn routes(url: Url) -> Option<Msg> {
+    ///    let draft = url.state::<Draft>();
+    ///    Some(Msg::ChangePage(url.path, draft))
+    ///}
+    /// ```
+    pub fn state<T: DeserializeOwned>(&self) -> Option<T> {
+        self.state
+            .clone()
+            .and_then(|state| serde_json::from_value(state).ok())
+    }
+
+    /// Parsed `search` - values are percent-decoded and `+` is decoded to space.
+    /// Values of repeated keys are in the order of their occurrence.
+    ///
+    /// `search` is parsed on each call - keep the result when you need it multiple times.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///// `?page=3&tags=a&tags=b`
+    ///let tags = url.search_params().get("tags").cloned().unwrap_or_default();
+    /// ```
+    pub fn search_params(&self) -> IndexMap<String, Vec<String>> {
+        query::parse(self.search.as_ref().map_or("", String::as_str))
+    }
+
+    /// Deserialize `search` into `T` - repeated keys can be deserialized into sequences
+    /// (e.g. `Vec<String>`), other fields receive the last value. Missing and empty values
+    /// are deserialized into `None`s.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///#[derive(Deserialize)]
+    ///struct PostsQuery {
+    ///    page: Option<u32>,
+    ///    #[serde(default)]
+    ///    tags: Vec<String>,
+    ///}
+    ///
+    ///// `?page=3&tags=a&tags=b`
+    ///let query: PostsQuery = url.search_as()?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns error when the query doesn't match `T` - e.g. a number field has a non-numeric value.
+    pub fn search_as<T: DeserializeOwned>(&self) -> Result<T, UrlError> {
+        T::deserialize(query::QueryDeserializer(&self.search_params()))
+    }
+
+    /// Serialize `query` into `search` - the reverse of `search_as`. Sequences are serialized
+    /// as repeated keys and `None`s are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///let mut url = Url::new(vec!["posts"]);
+    ///url.set_search(&PostsQuery { page: Some(2), tags: vec!["rust".to_owned()] })?;
+    ///seed::push_route(url);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns error when `query` isn't a struct or a map or when it contains nested values.
+    pub fn set_search(&mut self, query: &impl Serialize) -> Result<(), UrlError> {
+        let search = query::serialize(query)?;
+        self.search = if search.is_empty() {
+            None
+        } else {
+            Some(search)
+        };
+        Ok(())
+    }
+}
+
+impl Default for Url {
+    /// URL with an empty path (`/`).
+    fn default() -> Self {
+        Url::new(Vec::<String>::new())
+    }
+}
+
+impl fmt::Display for Url {
+    /// Relative URL without the base path - e.g. `/users/1?tab=posts#top`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.relative_url(serialize_path(&self.path)))
+    }
+}
+
+impl Url {
+    /// `path` followed by search and hash.
+    pub(crate) fn relative_url(&self, path: String) -> String {
+        let mut url = path;
+        if let Some(search) = &self.search {
+            url = url + "?" + search;
+        }
+        if let Some(hash) = &self.hash {
+            url = url + "#" + hash;
+        }
+        url
+    }
 }
 
 impl From<web_sys::Url> for Url {
     fn from(url: web_sys::Url) -> Self {
-        let path = {
-            let mut path = url.pathname();
-            // Remove leading `/`.
-            path.remove(0);
-            path.split('/').map(ToOwned::to_owned).collect::<Vec<_>>()
-        };
+        let path = parse_path(&url.pathname());
 
         let hash = {
             let mut hash = url.hash();
@@ -96,6 +311,7 @@ impl From<web_sys::Url> for Url {
             hash,
             search,
             title: None,
+            state: None,
         }
     }
 }
@@ -0,0 +1,374 @@
+//! Query string (de)serialization. See `Url::search_as` and `Url::set_search`.
+//!
+//! Values are percent-decoded as UTF-8 and `+` is decoded to space. Repeated keys
+//! are deserialized into sequences (e.g. `Vec<String>`), other fields receive the last value.
+//! Missing and empty values are deserialized into `None`s.
+
+use super::UrlError;
+use indexmap::IndexMap;
+use serde::de::{self, DeserializeSeed, IntoDeserializer, MapAccess, Visitor};
+use serde::{forward_to_deserialize_any, Serialize};
+use serde_json::Value;
+
+/// Parse `search` without the leading `?`.
+pub(crate) fn parse(search: &str) -> IndexMap<String, Vec<String>> {
+    let mut params = IndexMap::<String, Vec<String>>::new();
+    for pair in search.split('&').filter(|pair| !pair.is_empty()) {
+        let mut pair = pair.splitn(2, '=');
+        let key = decode(pair.next().unwrap_or_default());
+        let value = decode(pair.next().unwrap_or_default());
+        params.entry(key).or_default().push(value);
+    }
+    params
+}
+
+fn decode(component: &str) -> String {
+    let component = component.replace('+', " ");
+    js_sys::decode_uri_component(&component)
+        .map(String::from)
+        // Keep malformed escape sequences as they are.
+        .unwrap_or(component)
+}
+
+pub(crate) fn encode(component: &str) -> String {
+    js_sys::encode_uri_component(component).into()
+}
+
+/// Serialize `query` into `search` without the leading `?`.
+pub(crate) fn serialize(query: &impl Serialize) -> Result<String, UrlError> {
+    let object = match serde_json::to_value(query) {
+        Ok(Value::Object(object)) => object,
+        Ok(_) => {
+            return Err(UrlError::InvalidQuery(
+                "the query has to be a struct or a map".to_owned(),
+            ))
+        }
+        Err(error) => return Err(UrlError::InvalidQuery(error.to_string())),
+    };
+
+    let mut pairs = Vec::new();
+    for (key, value) in object {
+        let values = match value {
+            Value::Null => Vec::new(),
+            Value::Array(items) => items,
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                Value::String(value) => value,
+                Value::Number(number) => number.to_string(),
+                Value::Bool(value) => value.to_string(),
+                Value::Null => continue,
+                _ => {
+                    return Err(UrlError::InvalidQuery(format!(
+                        "the value of `{}` is nested",
+                        key
+                    )))
+                }
+            };
+            pairs.push(format!("{}={}", encode(&key), encode(&value)));
+        }
+    }
+    Ok(pairs.join("&"))
+}
+
+// ------ Deserializers ------
+
+pub(crate) struct QueryDeserializer<'a>(pub(crate) &'a IndexMap<String, Vec<String>>);
+
+impl<'de, 'a> de::Deserializer<'de> for QueryDeserializer<'a> {
+    type Error = UrlError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, UrlError> {
+        visitor.visit_map(QueryMapAccess {
+            entries: self.0.iter(),
+            values: None,
+        })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct QueryMapAccess<'a> {
+    entries: indexmap::map::Iter<'a, String, Vec<String>>,
+    values: Option<&'a [String]>,
+}
+
+impl<'de, 'a> MapAccess<'de> for QueryMapAccess<'a> {
+    type Error = UrlError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, UrlError> {
+        match self.entries.next() {
+            Some((key, values)) => {
+                self.values = Some(values);
+                let key: de::value::StrDeserializer<UrlError> = key.as_str().into_deserializer();
+                seed.deserialize(key).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, UrlError> {
+        let values = self
+            .values
+            .take()
+            .ok_or_else(|| UrlError::InvalidQuery("a value without a key".to_owned()))?;
+        seed.deserialize(ValuesDeserializer(values))
+    }
+}
+
+/// All values of a key.
+struct ValuesDeserializer<'a>(&'a [String]);
+
+impl<'a> ValuesDeserializer<'a> {
+    fn last(&self) -> ValueDeserializer<'a> {
+        ValueDeserializer(self.0.last().map_or("", String::as_str))
+    }
+}
+
+/// Deserialize the last value.
+macro_rules! deserialize_last {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, UrlError> {
+                self.last().$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for ValuesDeserializer<'a> {
+    type Error = UrlError;
+
+    deserialize_last! {
+        deserialize_any deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_f32 deserialize_f64
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, UrlError> {
+        if self.0.iter().all(String::is_empty) {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, UrlError> {
+        let values = self.0.iter().map(|value| ValueDeserializer(value.as_str()));
+        visitor.visit_seq(de::value::SeqDeserializer::new(values))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, UrlError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, UrlError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, UrlError> {
+        self.last().deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// One value - numbers and bools are parsed from it.
+struct ValueDeserializer<'a>(&'a str);
+
+impl<'de, 'a> IntoDeserializer<'de, UrlError> for ValueDeserializer<'a> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Parse the value and pass it to the visitor.
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident, $expected:expr;)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, UrlError> {
+                match self.0.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => Err(UrlError::InvalidQuery(format!(
+                        "`{}` isn't {}",
+                        self.0, $expected
+                    ))),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for ValueDeserializer<'a> {
+    type Error = UrlError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, UrlError> {
+        visitor.visit_str(self.0)
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool, "a bool";
+        deserialize_i8 => visit_i8, "an integer";
+        deserialize_i16 => visit_i16, "an integer";
+        deserialize_i32 => visit_i32, "an integer";
+        deserialize_i64 => visit_i64, "an integer";
+        deserialize_u8 => visit_u8, "an unsigned integer";
+        deserialize_u16 => visit_u16, "an unsigned integer";
+        deserialize_u32 => visit_u32, "an unsigned integer";
+        deserialize_u64 => visit_u64, "an unsigned integer";
+        deserialize_f32 => visit_f32, "a number";
+        deserialize_f64 => visit_f64, "a number";
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, UrlError> {
+        if self.0.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, UrlError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, UrlError> {
+        let variant: de::value::StrDeserializer<UrlError> = self.0.into_deserializer();
+        visitor.visit_enum(variant)
+    }
+
+    forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Url;
+    use super::*;
+    use serde::Deserialize;
+    use std::convert::TryFrom;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Sort {
+        Newest,
+        Oldest,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Search {
+        page: Option<u32>,
+        q: Option<String>,
+        #[serde(default)]
+        tags: Vec<String>,
+        sort: Option<Sort>,
+    }
+
+    fn url(relative_url: &str) -> Url {
+        Url::try_from(relative_url.to_owned()).unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn search_params_are_decoded() {
+        let url = url("/posts?q=caf%C3%A9+au+lait&tags=a&tags=b%26c&empty&page=3");
+        let mut expected = IndexMap::new();
+        expected.insert("q".to_owned(), vec!["café au lait".to_owned()]);
+        expected.insert("tags".to_owned(), vec!["a".to_owned(), "b&c".to_owned()]);
+        expected.insert("empty".to_owned(), vec![String::new()]);
+        expected.insert("page".to_owned(), vec!["3".to_owned()]);
+        assert_eq!(url.search_params(), expected);
+
+        // `search` changed directly is parsed too.
+        let mut url = url;
+        url.search = Some("page=4".to_owned());
+        assert_eq!(url.search_params()["page"], vec!["4"]);
+    }
+
+    #[wasm_bindgen_test]
+    fn search_is_deserialized() {
+        let search: Search = url("/posts?page=3&tags=a&tags=b&sort=oldest&q=%F0%9F%8C%B1")
+            .search_as()
+            .unwrap();
+        assert_eq!(
+            search,
+            Search {
+                page: Some(3),
+                q: Some("🌱".to_owned()),
+                tags: vec!["a".to_owned(), "b".to_owned()],
+                sort: Some(Sort::Oldest),
+            }
+        );
+
+        let search: Search = url("/posts").search_as().unwrap();
+        assert_eq!(
+            search,
+            Search {
+                page: None,
+                q: None,
+                tags: Vec::new(),
+                sort: None,
+            }
+        );
+
+        assert!(url("/posts?page=three").search_as::<Search>().is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn search_is_serialized() {
+        let search = Search {
+            page: Some(2),
+            q: Some("a b&c".to_owned()),
+            tags: vec!["x".to_owned(), "y".to_owned()],
+            sort: None,
+        };
+        let mut url = Url::new(vec!["posts"]);
+        url.set_search(&search).unwrap();
+        assert_eq!(
+            url.search,
+            Some("page=2&q=a%20b%26c&tags=x&tags=y".to_owned())
+        );
+        assert_eq!(url.search_as::<Search>().unwrap(), search);
+
+        assert!(url.set_search(&"not a struct").is_err());
+    }
+}
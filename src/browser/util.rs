@@ -4,7 +4,7 @@
 // @TODO refactor (ideally once `Unsized` and `Specialization` are stable)
 
 use wasm_bindgen::closure::Closure;
-use wasm_bindgen::JsCast;
+use wasm_bindgen::{JsCast, JsValue};
 use web_sys;
 
 pub type RequestAnimationFrameTime = f64;
@@ -28,6 +28,39 @@ pub fn window() -> web_sys::Window {
     web_sys::window().expect("Can't find the global Window")
 }
 
+/// Return `navigator.onLine`. It's `true` when the browser doesn't support it.
+pub fn is_online() -> bool {
+    js_sys::Reflect::get(&window(), &wasm_bindgen::JsValue::from_str("navigator"))
+        .and_then(|navigator| {
+            js_sys::Reflect::get(&navigator, &wasm_bindgen::JsValue::from_str("onLine"))
+        })
+        .ok()
+        .and_then(|on_line| on_line.as_bool())
+        .unwrap_or(true)
+}
+
+/// Return property's value if it exists and it's neither `undefined` nor `null`.
+pub(crate) fn get_property(target: &JsValue, name: &str) -> Option<JsValue> {
+    js_sys::Reflect::get(target, &JsValue::from_str(name))
+        .ok()
+        .filter(|value| !value.is_undefined() && !value.is_null())
+}
+
+/// 1-based position of `element` among its element siblings.
+#[cfg(all(
+    any(feature = "debug-tools", feature = "dev-reload-ux"),
+    debug_assertions
+))]
+pub(crate) fn element_position(element: &web_sys::Element) -> usize {
+    let mut position = 1;
+    let mut sibling = element.previous_element_sibling();
+    while let Some(element) = sibling {
+        position += 1;
+        sibling = element.previous_element_sibling();
+    }
+    position
+}
+
 /// Convenience function to access the `web_sys` DOM document.
 pub fn document() -> web_sys::Document {
     window()
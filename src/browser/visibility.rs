@@ -0,0 +1,294 @@
+//! Pausing of subscriptions in hidden tabs.
+//!
+//! Subscriptions created with `pause_when_hidden` (see `StreamHandle`, `ScrollProgressHandle`
+//! and `StorageQuotaHandle`) are registered in one coordinator. It listens
+//! to `visibilitychange` and suspends them when the document becomes hidden
+//! and resumes them when it's visible again:
+//! - Streams stop sending messages. Items produced in the meantime are dropped or coalesced
+//!   into the latest one according to their `PausePolicy` - nothing is replayed on return.
+//! - Scroll progress stops observing its element.
+//! - Storage quota monitoring cancels its timer. It estimates immediately on return
+//!   when the tab has been hidden longer than the monitoring interval (measured by its clock).
+//!
+//! Subscriptions registered while the document is hidden are paused immediately.
+//! Apps receive `VisibilityResumed` notifications (see `orders.subscribe`) with the hidden
+//! duration measured by the app's clock, so they can decide whether they should refetch data
+//! after a long absence.
+
+use super::{
+    time::Clock,
+    util::{self, ClosureNew},
+};
+use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
+    time::Duration,
+};
+use wasm_bindgen::{closure::Closure, JsCast};
+
+// ------ PausePolicy ------
+
+/// What happens with stream items produced while the stream is paused -
+/// see `StreamHandle::pause_when_hidden`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PausePolicy {
+    /// Drop all items.
+    Drop,
+    /// Keep only the latest item and send it on return.
+    CoalesceLatest,
+}
+
+impl Default for PausePolicy {
+    fn default() -> Self {
+        Self::Drop
+    }
+}
+
+// ------ VisibilityResumed ------
+
+/// Notification sent when the document becomes visible again.
+///
+/// # Example
+///
+/// ```rust,no_run
+///model.resumed_sub = orders.subscribe(|VisibilityResumed { hidden_for }| {
+///    Msg::Resumed(hidden_for)
+///});
+///
+///Msg::Resumed(hidden_for) => {
+///    if hidden_for > Duration::from_secs(300) {
+///        orders.perform_cmd(fetch_feed());
+///    }
+///}
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct VisibilityResumed {
+    pub hidden_for: Duration,
+}
+
+// ------ Pausable ------
+
+/// Subscription suspended by the coordinator.
+pub(crate) trait Pausable {
+    fn pause(&self);
+    fn resume(&self);
+}
+
+/// Listener of `VisibilityResumed` registered by `on_resume`. Dropping it unregisters.
+pub(crate) struct ResumeListener {
+    _tracker: Rc<dyn Pausable>,
+}
+
+/// Measures by its clock how long the document has been hidden.
+struct ResumeTracker {
+    clock: Rc<dyn Clock>,
+    hidden_since: Cell<Option<f64>>,
+    listener: Box<dyn Fn(Duration)>,
+}
+
+impl Pausable for ResumeTracker {
+    fn pause(&self) {
+        self.hidden_since.set(Some(self.clock.now_ms()));
+    }
+
+    fn resume(&self) {
+        if let Some(hidden_since) = self.hidden_since.take() {
+            let hidden_ms = (self.clock.now_ms() - hidden_since).max(0.);
+            (self.listener)(Duration::from_secs_f64(hidden_ms / 1000.));
+        }
+    }
+}
+
+// ------ Coordinator ------
+
+#[derive(Default)]
+struct Coordinator {
+    pausables: Vec<Weak<dyn Pausable>>,
+    hidden: bool,
+    closure: Option<Closure<dyn FnMut(web_sys::Event)>>,
+}
+
+thread_local! {
+    static COORDINATOR: RefCell<Coordinator> = RefCell::new(Coordinator::default());
+}
+
+/// Is the document hidden (e.g. a background tab or a minimized window)?
+pub fn is_hidden() -> bool {
+    util::document().hidden()
+}
+
+/// Suspend `pausable` while the document is hidden. It's unregistered when it's dropped.
+pub(crate) fn register(pausable: &Rc<dyn Pausable>) {
+    let hidden = COORDINATOR.with(|coordinator| {
+        let mut coordinator = coordinator.borrow_mut();
+        coordinator.ensure_listener();
+        // Dropped pausables are removed only here - they aren't unregistered explicitly.
+        coordinator
+            .pausables
+            .retain(|pausable| pausable.upgrade().is_some());
+        coordinator.pausables.push(Rc::downgrade(pausable));
+        coordinator.hidden
+    });
+    if hidden {
+        pausable.pause();
+    }
+}
+
+/// Call `listener` with the hidden duration measured by `clock` each time the document
+/// becomes visible.
+pub(crate) fn on_resume(
+    clock: Rc<dyn Clock>,
+    listener: impl Fn(Duration) + 'static,
+) -> ResumeListener {
+    let tracker: Rc<dyn Pausable> = Rc::new(ResumeTracker {
+        clock,
+        hidden_since: Cell::new(None),
+        listener: Box::new(listener),
+    });
+    register(&tracker);
+    ResumeListener { _tracker: tracker }
+}
+
+impl Coordinator {
+    fn ensure_listener(&mut self) {
+        if self.closure.is_some() {
+            return;
+        }
+        self.hidden = is_hidden();
+        let closure = Closure::new(|_: web_sys::Event| visibility_changed(is_hidden()));
+        util::document()
+            .add_event_listener_with_callback("visibilitychange", closure.as_ref().unchecked_ref())
+            .expect("Problem adding visibilitychange listener");
+        self.closure = Some(closure);
+    }
+}
+
+fn visibility_changed(hidden: bool) {
+    // Callbacks are invoked without the borrow - they may register new subscriptions.
+    let pausables = COORDINATOR.with(|coordinator| {
+        let mut coordinator = coordinator.borrow_mut();
+        if coordinator.hidden == hidden {
+            return Vec::new();
+        }
+        coordinator.hidden = hidden;
+        coordinator
+            .pausables
+            .retain(|pausable| pausable.strong_count() > 0);
+        coordinator
+            .pausables
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>()
+    });
+
+    for pausable in pausables {
+        if hidden {
+            pausable.pause();
+        } else {
+            pausable.resume();
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::browser::time::MockClock;
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    /// Override `document.hidden` and dispatch `visibilitychange`.
+    pub(crate) fn set_hidden(hidden: bool) {
+        let document = util::document();
+        let descriptor = js_sys::Object::new();
+        js_sys::Reflect::set(&descriptor, &"value".into(), &JsValue::from_bool(hidden)).unwrap();
+        js_sys::Reflect::set(&descriptor, &"configurable".into(), &JsValue::TRUE).unwrap();
+        js_sys::Object::define_property(&document, &"hidden".into(), &descriptor);
+        let event = web_sys::Event::new("visibilitychange").unwrap();
+        document.dispatch_event(&event).unwrap();
+    }
+
+    /// Remove the `document.hidden` override.
+    pub(crate) fn reset_hidden() {
+        js_sys::Reflect::delete_property(&util::document(), &"hidden".into()).unwrap();
+    }
+
+    #[derive(Default)]
+    struct Counter {
+        pauses: Cell<u32>,
+        resumes: Cell<u32>,
+    }
+
+    impl Pausable for Counter {
+        fn pause(&self) {
+            self.pauses.set(self.pauses.get() + 1);
+        }
+        fn resume(&self) {
+            self.resumes.set(self.resumes.get() + 1);
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn dropped_pausables_are_removed() {
+        let registered = || COORDINATOR.with(|coordinator| coordinator.borrow().pausables.len());
+        let alive = || {
+            COORDINATOR.with(|coordinator| {
+                let coordinator = coordinator.borrow();
+                coordinator
+                    .pausables
+                    .iter()
+                    .filter(|pausable| pausable.upgrade().is_some())
+                    .count()
+            })
+        };
+        for _ in 0..5 {
+            let pausable: Rc<dyn Pausable> = Rc::new(Counter::default());
+            register(&pausable);
+        }
+        // Only the last dropped pausable hasn't been removed yet.
+        assert_eq!(registered(), alive() + 1);
+    }
+
+    #[wasm_bindgen_test]
+    fn pausables_follow_visibility() {
+        let counter = Rc::new(Counter::default());
+        let pausable: Rc<dyn Pausable> = counter.clone();
+        register(&pausable);
+        let clock = MockClock::new();
+        let resumed = Rc::new(Cell::new(None));
+        let _listener = on_resume(Rc::new(clock.clone()), {
+            let resumed = Rc::clone(&resumed);
+            move |hidden_for| resumed.set(Some(hidden_for))
+        });
+
+        set_hidden(true);
+        assert!(is_hidden());
+        assert_eq!(counter.pauses.get(), 1);
+        // Repeated events are ignored.
+        set_hidden(true);
+        assert_eq!(counter.pauses.get(), 1);
+        assert_eq!(resumed.get(), None);
+
+        // The hidden duration is measured by the listener's clock.
+        clock.advance(Duration::from_secs(90));
+        set_hidden(false);
+        assert_eq!(counter.resumes.get(), 1);
+        assert_eq!(resumed.get(), Some(Duration::from_secs(90)));
+
+        // Dropped subscriptions are unregistered.
+        drop(pausable);
+        drop(counter);
+        set_hidden(true);
+        COORDINATOR.with(|coordinator| {
+            let coordinator = coordinator.borrow();
+            assert!(coordinator
+                .pausables
+                .iter()
+                .all(|pausable| pausable.strong_count() > 0));
+        });
+        set_hidden(false);
+        reset_hidden();
+    }
+}
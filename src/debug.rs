@@ -0,0 +1,162 @@
+//! View-source attribution - rendered elements point to the Rust code which created them.
+//!
+//! Enabled by the feature `debug-tools` in debug builds. Otherwise this module isn't compiled
+//! and element macros don't store anything.
+//!
+//! Elements created by element macros (`div!`, `custom!`, ..) are rendered with the attribute
+//! `data-seed-src="src/views/header.rs:42"`. Call `seedFindSource(selector)` in the browser
+//! console (or `seed::debug::find_source` in Rust) to list sources of matching elements.
+//!
+//! See `ownership_guard` for detection of DOM mutations made by other code
+//! and `memory` for counts of data retained by apps.
+//!
+//! _Note:_ The source is the macro call - i.e. elements created by your helper functions point
+//! to the macro call inside the helper. Mark the helper with `#[track_caller]` to point them
+//! to the helper's caller instead.
+
+use crate::browser::util;
+use std::{cell::RefCell, fmt};
+
+pub mod memory;
+pub mod ownership_guard;
+pub use memory::{memory_report, MemoryReport, Usage};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+/// Name of the attribute with element's source.
+pub const SOURCE_ATTR: &str = "data-seed-src";
+
+/// Name of the `window` function mapping a CSS selector to sources.
+pub const FIND_SOURCE_KEY: &str = "seedFindSource";
+
+/// The place in Rust code where an element was created.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Source {
+    pub file: &'static str,
+    pub line: u32,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+/// Sources of elements matching `selector`. Elements without the source attribute
+/// (e.g. created by `raw!` or outside of Seed) are skipped.
+///
+/// # Example
+///
+/// ```rust,no_run
+///seed::log(seed::debug::find_source(".header button"));
+/// ```
+pub fn find_source(selector: &str) -> Vec<String> {
+    let elements = match util::document().query_selector_all(selector) {
+        Ok(elements) => elements,
+        Err(_) => {
+            crate::error(format!("Invalid selector \"{}\"", selector));
+            return Vec::new();
+        }
+    };
+    (0..elements.length())
+        .filter_map(|index| elements.item(index))
+        .filter_map(|node| node.dyn_into::<web_sys::Element>().ok())
+        .filter_map(|element| element.get_attribute(SOURCE_ATTR))
+        .collect()
+}
+
+thread_local! {
+    /// `window.seedFindSource` registered by the first started app.
+    static CONSOLE_HELPER: RefCell<Option<Closure<dyn Fn(String) -> js_sys::Array>>> =
+        RefCell::new(None);
+}
+
+/// Expose `find_source` as `window.seedFindSource` for the browser console.
+/// The helper is shared by all apps - it's registered only once.
+pub(crate) fn register_console_helper() {
+    if CONSOLE_HELPER.with(|helper| helper.borrow().is_some()) {
+        return;
+    }
+    let find = Closure::wrap(Box::new(|selector: String| {
+        find_source(&selector)
+            .into_iter()
+            .map(JsValue::from)
+            .collect::<js_sys::Array>()
+    }) as Box<dyn Fn(String) -> js_sys::Array>);
+
+    js_sys::Reflect::set(
+        &util::window(),
+        &JsValue::from_str(FIND_SOURCE_KEY),
+        find.as_ref(),
+    )
+    .expect("Problem registering seedFindSource");
+    CONSOLE_HELPER.with(|helper| *helper.borrow_mut() = Some(find));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser::dom::virtual_dom_bridge;
+    use crate::prelude::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn rendered_source(node: &mut Node<()>) -> Option<String> {
+        let el = match node {
+            Node::Element(el) => el,
+            _ => panic!("Element expected"),
+        };
+        virtual_dom_bridge::make_websys_el(el, &util::document())
+            .dyn_into::<web_sys::Element>()
+            .unwrap()
+            .get_attribute(SOURCE_ATTR)
+    }
+
+    #[wasm_bindgen_test]
+    fn element_macros_point_to_caller() {
+        let (mut node, line) = (div!["a"], line!());
+        assert_eq!(
+            rendered_source(&mut node),
+            Some(format!("{}:{}", file!(), line))
+        );
+
+        let (mut node, line) = (custom![Tag::from("code-block")], line!());
+        assert_eq!(
+            rendered_source(&mut node),
+            Some(format!("{}:{}", file!(), line))
+        );
+    }
+
+    #[track_caller]
+    fn view_button() -> Node<()> {
+        button!["a"]
+    }
+
+    #[wasm_bindgen_test]
+    fn tracked_helpers_point_to_caller() {
+        let (mut node, line) = (view_button(), line!());
+        assert_eq!(
+            rendered_source(&mut node),
+            Some(format!("{}:{}", file!(), line))
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn find_source_in_document() {
+        let (mut node, line): (Node<()>, _) =
+            (div![attrs! {At::Id => "debug-find-source"}], line!());
+        let el_ws = match &mut node {
+            Node::Element(el) => virtual_dom_bridge::make_websys_el(el, &util::document()),
+            _ => unreachable!(),
+        };
+        util::body().append_child(&el_ws).unwrap();
+
+        assert_eq!(
+            find_source("#debug-find-source"),
+            vec![format!("{}:{}", file!(), line)]
+        );
+        assert!(find_source("#debug-missing").is_empty());
+
+        util::body().remove_child(&el_ws).unwrap();
+    }
+}
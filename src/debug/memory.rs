@@ -0,0 +1,271 @@
+//! Memory profiling - numbers of vdom nodes, listeners, callbacks, cached and parked data
+//! and subscriptions retained by an app. See `memory_report` and `AppBuilder::memory_growth_check`.
+//!
+//! Reports don't walk the vdom - vdom nodes and element listeners are counted where subtrees
+//! are inserted into or removed from the vdom (see `seed::app::memory_growth`) and listener
+//! closures, which aren't owned by any app collection once attached, are counted
+//! by `Listener::attach` / `Listener::detach`. Other numbers are read from the app's collections.
+
+use crate::app::App;
+use crate::browser::service::fetch::cache;
+use crate::virtual_dom::{Listener, Node, View};
+use std::{cell::Cell, fmt, mem};
+
+thread_local! {
+    static LISTENER_CLOSURES: Cell<usize> = Cell::new(0);
+}
+
+pub(crate) fn listener_attached() {
+    LISTENER_CLOSURES.with(|count| count.set(count.get() + 1));
+}
+
+pub(crate) fn listener_detached() {
+    LISTENER_CLOSURES.with(|count| count.set(count.get().saturating_sub(1)));
+}
+
+// ------ Usage ------
+
+/// Number of items and their approximate size in bytes, when it's knowable.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Usage {
+    pub count: usize,
+    /// Sizes of the items' Rust values incl. closures' captured variables, but without
+    /// heap data they point to and without JS objects.
+    pub approx_bytes: Option<usize>,
+}
+
+impl Usage {
+    fn add(&mut self, bytes: usize) {
+        self.count += 1;
+        self.approx_bytes = Some(self.approx_bytes.unwrap_or(0) + bytes);
+    }
+
+    fn of_items(count: usize, item_bytes: usize) -> Self {
+        Self {
+            count,
+            approx_bytes: Some(count * item_bytes),
+        }
+    }
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.approx_bytes {
+            Some(bytes) => write!(f, "{} (~{} B)", self.count, bytes),
+            None => write!(f, "{}", self.count),
+        }
+    }
+}
+
+// ------ MemoryReport ------
+
+/// Data retained by an app. See `memory_report`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct MemoryReport {
+    /// Element and text nodes of the rendered vdom (`AppData::main_el_vdom`).
+    pub vdom_nodes: Usage,
+    /// Listeners attached to rendered elements.
+    pub element_listeners: Usage,
+    /// Attached `window` listeners - see `AppBuilder::window_events`.
+    pub window_listeners: Usage,
+    /// Listeners registered by `App::add_message_listener`.
+    pub msg_listeners: Usage,
+    /// Callbacks waiting for the next render - see `orders.after_next_render`.
+    pub after_render_callbacks: Usage,
+    /// Subtrees parked by `keep_alive`; the size is the size of their vdom nodes.
+    pub kept_alive: Usage,
+    /// Slices remembered by view regions - see `AppBuilder::view_with_slices`.
+    pub cached_slices: Usage,
+    /// Subscriptions registered by `orders.subscribe` and `App::subscribe`.
+    pub subscriptions: Usage,
+    /// Responses cached by all apps - see `seed::browser::service::fetch::cache`.
+    pub fetch_cache_entries: usize,
+    /// Listener closures attached to DOM by all apps. Closures of listeners not counted
+    /// in `element_listeners` or `window_listeners` of any app are retained by the browser
+    /// without being reachable from a vdom.
+    pub listener_closures: usize,
+}
+
+impl MemoryReport {
+    /// Named counts - e.g. for logging or comparing reports.
+    pub fn counts(&self) -> [(&'static str, usize); 10] {
+        [
+            ("vdom nodes", self.vdom_nodes.count),
+            ("element listeners", self.element_listeners.count),
+            ("window listeners", self.window_listeners.count),
+            ("msg listeners", self.msg_listeners.count),
+            ("after render callbacks", self.after_render_callbacks.count),
+            ("kept alive subtrees", self.kept_alive.count),
+            ("cached slices", self.cached_slices.count),
+            ("subscriptions", self.subscriptions.count),
+            ("fetch cache entries", self.fetch_cache_entries),
+            ("listener closures", self.listener_closures),
+        ]
+    }
+}
+
+impl fmt::Display for MemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let usages = [
+            ("vdom nodes", self.vdom_nodes),
+            ("element listeners", self.element_listeners),
+            ("window listeners", self.window_listeners),
+            ("msg listeners", self.msg_listeners),
+            ("after render callbacks", self.after_render_callbacks),
+            ("kept alive subtrees", self.kept_alive),
+            ("cached slices", self.cached_slices),
+            ("subscriptions", self.subscriptions),
+        ];
+        for (name, usage) in &usages {
+            writeln!(f, "{}: {}", name, usage)?;
+        }
+        writeln!(
+            f,
+            "fetch cache entries (all apps): {}",
+            self.fetch_cache_entries
+        )?;
+        write!(
+            f,
+            "listener closures (all apps): {}",
+            self.listener_closures
+        )
+    }
+}
+
+/// Count data retained by `app`.
+///
+/// # Example
+///
+/// ```rust,no_run
+///seed::log!(seed::debug::memory_report(&app).to_string());
+/// ```
+pub fn memory_report<Ms, Mdl, ElC, GMs>(app: &App<Ms, Mdl, ElC, GMs>) -> MemoryReport
+where
+    Ms: 'static,
+    Mdl: 'static,
+    ElC: View<Ms> + 'static,
+    GMs: 'static,
+{
+    let data = &app.data;
+    let mut report = MemoryReport::default();
+
+    let vdom_counts = data.memory_counts.get();
+    report.vdom_nodes = Usage::of_items(vdom_counts.nodes, mem::size_of::<Node<Ms>>());
+    report.element_listeners =
+        Usage::of_items(vdom_counts.listeners, mem::size_of::<Listener<Ms>>());
+
+    for listener in data.window_listeners.borrow().iter() {
+        if listener.closure.is_some() {
+            report.window_listeners.add(mem::size_of::<Listener<Ms>>());
+        }
+    }
+    for listener in data.msg_listeners.borrow().iter() {
+        report.msg_listeners.add(mem::size_of_val(&**listener));
+    }
+    for callback in data.after_next_render_callbacks.borrow().iter() {
+        report
+            .after_render_callbacks
+            .add(mem::size_of_val(&**callback));
+    }
+
+    for parked in data.kept_alive.borrow().values() {
+        report
+            .kept_alive
+            .add(parked.counts.nodes * mem::size_of::<Node<Ms>>());
+    }
+
+    report.cached_slices.count = app.cfg.view_slices.cached_slices();
+    report.subscriptions.count =
+        data.subscriptions.borrow().len() + data.msg_subscriptions.borrow().len();
+    report.fetch_cache_entries = cache::len();
+    report.listener_closures = LISTENER_CLOSURES.with(Cell::get);
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser::util;
+    use crate::prelude::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn report_counts_retained_data() {
+        #[derive(Default)]
+        struct Model {
+            rows: u32,
+            sub_handle: Option<SubHandle>,
+        }
+        #[derive(Clone)]
+        enum Msg {
+            AddRow,
+            ClearRows,
+            Subscribe,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::AddRow => model.rows += 1,
+                Msg::ClearRows => model.rows = 0,
+                Msg::Subscribe => {
+                    model.sub_handle = Some(orders.subscribe(|_: u8| Msg::AddRow));
+                    orders.after_next_render(|_| None::<Msg>);
+                }
+            }
+        }
+
+        fn view(model: &Model) -> Node<Msg> {
+            div![(0..model.rows)
+                .map(|row| { button![row.to_string(), ev(Ev::Click, |_| Msg::AddRow)] })]
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(|_, _| Init::new(Model::default()), update, view)
+            .mount(mount_point.clone())
+            .finish()
+            .run();
+        app.update(Msg::AddRow);
+        app.update(Msg::AddRow);
+        app.flush_render();
+        let closures_before = LISTENER_CLOSURES.with(Cell::get);
+
+        let report = memory_report(&app);
+        // The root placeholder, `div`, 2 `button`s and their texts.
+        assert_eq!(report.vdom_nodes.count, 6);
+        assert_eq!(report.element_listeners.count, 2);
+        assert_eq!(report.subscriptions.count, 0);
+        assert_eq!(report.after_render_callbacks.count, 0);
+
+        app.update(Msg::Subscribe);
+        app.update(Msg::AddRow);
+        let report = memory_report(&app);
+        assert_eq!(report.subscriptions.count, 1);
+        assert_eq!(report.after_render_callbacks.count, 1);
+
+        app.flush_render();
+        let report = memory_report(&app);
+        assert_eq!(report.element_listeners.count, 3);
+        assert_eq!(report.after_render_callbacks.count, 0);
+        assert_eq!(report.listener_closures, closures_before + 1);
+        assert_eq!(report.fetch_cache_entries, cache::len());
+
+        // Counts are updated by patching - removed subtrees are subtracted.
+        app.update(Msg::ClearRows);
+        app.flush_render();
+        let report = memory_report(&app);
+        assert_eq!(report.vdom_nodes.count, 2);
+        assert_eq!(report.element_listeners.count, 0);
+        app.update(Msg::AddRow);
+        app.flush_render();
+        assert_eq!(memory_report(&app).vdom_nodes.count, 4);
+
+        let closures_before = LISTENER_CLOSURES.with(Cell::get);
+        app.unmount();
+        assert_eq!(memory_report(&app).vdom_nodes.count, 0);
+        assert_eq!(LISTENER_CLOSURES.with(Cell::get), closures_before - 1);
+        mount_point.remove();
+    }
+}
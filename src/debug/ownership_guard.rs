@@ -0,0 +1,314 @@
+//! Detection of DOM mutations made by other code inside Seed-managed subtrees.
+//!
+//! A `MutationObserver` watches the mount point of each app. It's disconnected while Seed patches
+//! DOM or executes `dom_write` closures, so only external mutations are reported - as console
+//! warnings with the mutated node's path.
+//!
+//! Mutations are ignored inside:
+//! - children of custom-rendered elements (see `rendered_by`),
+//! - elements with the attribute `data-seed-allow-mutations`,
+//! - elements matching selectors registered by `allow_external_mutations`.
+
+use crate::browser::util::element_position;
+use std::cell::RefCell;
+use wasm_bindgen::{closure::Closure, JsCast};
+
+/// Mutations inside elements with this attribute aren't reported.
+pub const ALLOW_MUTATIONS_ATTR: &str = "data-seed-allow-mutations";
+
+/// Attribute of custom-rendered elements. Their children are managed by renderers.
+pub const RENDERER_ATTR: &str = "data-seed-renderer";
+
+struct Guard {
+    root: web_sys::Element,
+    observer: web_sys::MutationObserver,
+    _callback: Closure<dyn FnMut(js_sys::Array)>,
+}
+
+#[derive(Default)]
+struct Guards {
+    guards: Vec<Guard>,
+    allowed_selectors: Vec<String>,
+    // Number of active `Pause`s.
+    pause_depth: u32,
+}
+
+thread_local! {
+    static GUARDS: RefCell<Guards> = RefCell::new(Guards::default());
+}
+
+/// Don't report mutations inside elements matching `selector` - e.g. an element managed
+/// by a jQuery plugin.
+///
+/// # Example
+///
+/// ```rust,no_run
+///seed::debug::ownership_guard::allow_external_mutations(".datepicker");
+/// ```
+pub fn allow_external_mutations(selector: impl Into<String>) {
+    GUARDS.with(|guards| guards.borrow_mut().allowed_selectors.push(selector.into()));
+}
+
+/// Start watching `root` - the mount point of an app.
+pub(crate) fn install(root: &web_sys::Element) {
+    let callback_root = root.clone();
+    let callback = Closure::wrap(Box::new(move |records: js_sys::Array| {
+        report(&records, &callback_root);
+    }) as Box<dyn FnMut(js_sys::Array)>);
+    let observer = web_sys::MutationObserver::new(callback.as_ref().unchecked_ref())
+        .expect("Problem creating MutationObserver");
+
+    GUARDS.with(|guards| {
+        let mut guards = guards.borrow_mut();
+        let guard = Guard {
+            root: root.clone(),
+            observer,
+            _callback: callback,
+        };
+        if guards.pause_depth == 0 {
+            observe(&guard);
+        }
+        guards.guards.push(guard);
+    });
+}
+
+/// Stop watching `root` - see `App::unmount`. Pending records are discarded,
+/// so mutations made by the teardown aren't reported.
+pub(crate) fn uninstall(root: &web_sys::Element) {
+    GUARDS.with(|guards| {
+        guards.borrow_mut().guards.retain(|guard| {
+            if guard.root.is_same_node(Some(root)) {
+                guard.observer.disconnect();
+                false
+            } else {
+                true
+            }
+        })
+    });
+}
+
+/// Disconnects observers until the returned value is dropped.
+/// Use it around all DOM mutations made by Seed.
+pub(crate) fn pause() -> Pause {
+    let undelivered_records = GUARDS.with(|guards| {
+        let mut guards = guards.borrow_mut();
+        guards.pause_depth += 1;
+        if guards.pause_depth > 1 {
+            return Vec::new();
+        }
+        guards
+            .guards
+            .iter()
+            .map(|guard| {
+                // Records which haven't been delivered yet are external and `disconnect`
+                // would discard them.
+                let records = guard.observer.take_records();
+                guard.observer.disconnect();
+                (records, guard.root.clone())
+            })
+            .collect()
+    });
+    for (records, root) in undelivered_records {
+        report(&records, &root);
+    }
+    Pause
+}
+
+pub(crate) struct Pause;
+
+impl Drop for Pause {
+    fn drop(&mut self) {
+        GUARDS.with(|guards| {
+            let mut guards = guards.borrow_mut();
+            guards.pause_depth -= 1;
+            if guards.pause_depth == 0 {
+                guards.guards.iter().for_each(observe);
+            }
+        });
+    }
+}
+
+fn observe(guard: &Guard) {
+    let mut options = web_sys::MutationObserverInit::new();
+    options
+        .child_list(true)
+        .attributes(true)
+        .character_data(true)
+        .subtree(true);
+    guard
+        .observer
+        .observe_with_options(&guard.root, &options)
+        .expect("Problem observing mount point");
+}
+
+fn report(records: &js_sys::Array, root: &web_sys::Element) {
+    for warning in warnings(records, root) {
+        web_sys::console::warn_1(&warning.into());
+    }
+}
+
+/// Warnings for `MutationRecord`s which aren't allowed.
+fn warnings(records: &js_sys::Array, root: &web_sys::Element) -> Vec<String> {
+    let allowed_selectors = GUARDS.with(|guards| guards.borrow().allowed_selectors.join(", "));
+    records
+        .iter()
+        .filter_map(|record| record.dyn_into::<web_sys::MutationRecord>().ok())
+        .filter_map(|record| {
+            let target = record.target()?;
+            let element = match target.dyn_ref::<web_sys::Element>() {
+                Some(element) => element.clone(),
+                None => target.parent_element()?,
+            };
+            if is_allowed(&element, &allowed_selectors) {
+                return None;
+            }
+            let mutation = match record.attribute_name() {
+                Some(attribute) => format!("{} (`{}`)", record.type_(), attribute),
+                None => record.type_(),
+            };
+            Some(format!(
+                "External DOM mutation in Seed-managed element `{}`: {}. \
+                 Seed may patch wrong nodes now. Render the subtree by a custom renderer \
+                 (`rendered_by`) or allow it by `{}` attribute \
+                 or `seed::debug::ownership_guard::allow_external_mutations`.",
+                path(&target, root),
+                mutation,
+                ALLOW_MUTATIONS_ATTR,
+            ))
+        })
+        .collect()
+}
+
+fn is_allowed(element: &web_sys::Element, allowed_selectors: &str) -> bool {
+    let closest = |selector: &str| {
+        element
+            .closest(selector)
+            .map_or(false, |closest| closest.is_some())
+    };
+    closest(&format!("[{}], [{}]", ALLOW_MUTATIONS_ATTR, RENDERER_ATTR))
+        || (!allowed_selectors.is_empty() && closest(allowed_selectors))
+}
+
+/// Path from `root` to `node` - e.g. `ul:nth-child(2) > li:nth-child(3)`.
+fn path(node: &web_sys::Node, root: &web_sys::Element) -> String {
+    let root: &web_sys::Node = root.as_ref();
+    let mut segments = Vec::new();
+    let mut current = Some(node.clone());
+    while let Some(node) = current {
+        if node.is_same_node(Some(root)) {
+            break;
+        }
+        let segment = match node.dyn_ref::<web_sys::Element>() {
+            Some(element) => format!(
+                "{}:nth-child({})",
+                element.tag_name().to_lowercase(),
+                element_position(element)
+            ),
+            None => node.node_name(),
+        };
+        segments.push(segment);
+        current = node.parent_node();
+    }
+    segments.push("mount point".to_owned());
+    segments.reverse();
+    segments.join(" > ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser::util;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn collect_warnings(root: &web_sys::Element, mutate: impl FnOnce()) -> Vec<String> {
+        let callback =
+            Closure::wrap(Box::new(|_: js_sys::Array| ()) as Box<dyn FnMut(js_sys::Array)>);
+        let observer = web_sys::MutationObserver::new(callback.as_ref().unchecked_ref()).unwrap();
+        let guard = Guard {
+            root: root.clone(),
+            observer,
+            _callback: callback,
+        };
+        observe(&guard);
+        mutate();
+        let warnings = warnings(&guard.observer.take_records(), root);
+        guard.observer.disconnect();
+        warnings
+    }
+
+    #[wasm_bindgen_test]
+    fn external_mutations_are_reported() {
+        let root = util::document().create_element("div").unwrap();
+        root.set_inner_html("<ul><li>a</li><li>b</li></ul>");
+        let li = root.query_selector("li:last-child").unwrap().unwrap();
+
+        let warnings = collect_warnings(&root, || {
+            li.set_attribute("class", "active").unwrap();
+        });
+
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("mount point > ul:nth-child(1) > li:nth-child(2)"));
+        assert!(warnings[0].contains("attributes (`class`)"));
+    }
+
+    #[wasm_bindgen_test]
+    fn allowed_mutations_are_ignored() {
+        let root = util::document().create_element("div").unwrap();
+        root.set_inner_html(&format!(
+            r#"<div {}><span></span></div><div class="plugin"></div><canvas {}="chart"></canvas>"#,
+            ALLOW_MUTATIONS_ATTR, RENDERER_ATTR
+        ));
+        allow_external_mutations(".plugin");
+
+        let warnings = collect_warnings(&root, || {
+            let span = root.query_selector("span").unwrap().unwrap();
+            span.set_text_content(Some("x"));
+            let plugin = root.query_selector(".plugin").unwrap().unwrap();
+            plugin.set_inner_html("<input>");
+            let canvas = root.query_selector("canvas").unwrap().unwrap();
+            canvas.set_attribute("width", "100").unwrap();
+        });
+        // Don't affect other tests.
+        GUARDS.with(|guards| {
+            guards
+                .borrow_mut()
+                .allowed_selectors
+                .retain(|selector| selector != ".plugin")
+        });
+
+        assert!(warnings.is_empty(), "{:?}", warnings);
+    }
+
+    #[wasm_bindgen_test]
+    fn paused_guard_ignores_mutations() {
+        let root = util::document().create_element("div").unwrap();
+        install(&root);
+
+        let pause = pause();
+        root.set_inner_html("<p></p>");
+        drop(pause);
+
+        let warnings = GUARDS.with(|guards| {
+            let guards = guards.borrow();
+            let guard = guards
+                .guards
+                .iter()
+                .find(|guard| guard.root.is_same_node(Some(&root)))
+                .unwrap();
+            warnings(&guard.observer.take_records(), &root)
+        });
+        uninstall(&root);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+
+        let is_installed = GUARDS.with(|guards| {
+            guards
+                .borrow()
+                .guards
+                .iter()
+                .any(|guard| guard.root.is_same_node(Some(&root)))
+        });
+        assert!(!is_installed);
+    }
+}
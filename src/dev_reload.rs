@@ -0,0 +1,334 @@
+//! UI state preserved across page reloads during development.
+//!
+//! Enabled by the feature `dev-reload-ux` in debug builds. Otherwise this module isn't compiled.
+//!
+//! Before the page is unloaded, Seed stores into `sessionStorage`:
+//! - scroll positions of the window and of scrolled elements,
+//! - the focused element and the selection in it (inputs and text areas),
+//! - open `<details>` and `<dialog>` elements.
+//!
+//! The snapshot is restored once, after the first render of the reloaded app. Elements are
+//! identified by their paths from the nearest ancestor with `id` (e.g. `[id="app"] > ul:nth-child(2)`);
+//! elements which don't exist anymore are skipped.
+//!
+//! _Note:_ The restored scroll positions override the browser's scroll restoration.
+
+use crate::browser::util::{self, element_position};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+/// `sessionStorage` key of the snapshot.
+pub const STORAGE_KEY: &str = "seed:dev-reload-ux";
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Snapshot {
+    window_scroll: (f64, f64),
+    scroll_positions: Vec<ScrollPosition>,
+    focus: Option<Focus>,
+    /// Paths of open `<details>` and `<dialog>` elements.
+    open: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ScrollPosition {
+    path: String,
+    top: i32,
+    left: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Focus {
+    path: String,
+    /// `selectionStart` and `selectionEnd` of inputs and text areas.
+    selection: Option<(u32, u32)>,
+}
+
+thread_local! {
+    static INSTALLED: Cell<bool> = Cell::new(false);
+}
+
+/// Store a snapshot on `beforeunload`. It's called for each app, but the listener is added once.
+pub(crate) fn install() {
+    if INSTALLED.with(|installed| installed.replace(true)) {
+        return;
+    }
+    let listener = Closure::wrap(Box::new(|_: web_sys::Event| {
+        save(&take_snapshot());
+    }) as Box<dyn FnMut(web_sys::Event)>);
+    util::window()
+        .add_event_listener_with_callback("beforeunload", listener.as_ref().unchecked_ref())
+        .expect("Problem adding beforeunload listener");
+    // The listener lives as long as the page.
+    listener.forget();
+}
+
+/// Restore and remove the stored snapshot, if any. Call it after the first render.
+pub(crate) fn restore() {
+    let storage = match session_storage() {
+        Some(storage) => storage,
+        None => return,
+    };
+    let json = match storage.get_item(STORAGE_KEY).ok().and_then(|json| json) {
+        Some(json) => json,
+        None => return,
+    };
+    let _ = storage.remove_item(STORAGE_KEY);
+    match serde_json::from_str::<Snapshot>(&json) {
+        Ok(snapshot) => {
+            apply_snapshot(&snapshot);
+            web_sys::console::info_1(
+                &"Seed dev-reload-ux: UI state of the previous page load has been restored \
+                  (it overrides the browser's scroll restoration)."
+                    .into(),
+            );
+        }
+        Err(error) => {
+            crate::error(format!("Invalid dev-reload-ux snapshot: {}", error));
+        }
+    }
+}
+
+fn save(snapshot: &Snapshot) {
+    if let Some(storage) = session_storage() {
+        let json = serde_json::to_string(snapshot).expect("Problem serializing UI snapshot");
+        // Storage may be full - the snapshot is just a convenience.
+        let _ = storage.set_item(STORAGE_KEY, &json);
+    }
+}
+
+fn session_storage() -> Option<web_sys::Storage> {
+    util::window()
+        .session_storage()
+        .ok()
+        .and_then(|storage| storage)
+}
+
+// ------ Snapshot ------
+
+pub(crate) fn take_snapshot() -> Snapshot {
+    let window = util::window();
+    let document = util::document();
+
+    let scroll_positions = query_all("*")
+        .into_iter()
+        .filter(|element| element.scroll_top() != 0 || element.scroll_left() != 0)
+        // `documentElement` is scrolled by `window`.
+        .filter(|element| Some(element) != document.document_element().as_ref())
+        .map(|element| ScrollPosition {
+            path: path(&element),
+            top: element.scroll_top(),
+            left: element.scroll_left(),
+        })
+        .collect();
+
+    let body: Option<web_sys::Element> = document.body().map(Into::into);
+    let focus = document
+        .active_element()
+        .filter(|element| Some(element) != body.as_ref())
+        .map(|element| Focus {
+            path: path(&element),
+            selection: selection(&element),
+        });
+
+    Snapshot {
+        window_scroll: (
+            window.page_x_offset().unwrap_or_default(),
+            window.page_y_offset().unwrap_or_default(),
+        ),
+        scroll_positions,
+        focus,
+        open: query_all("details[open], dialog[open]")
+            .iter()
+            .map(path)
+            .collect(),
+    }
+}
+
+pub(crate) fn apply_snapshot(snapshot: &Snapshot) {
+    let document = util::document();
+    let find = |path: &str| {
+        document
+            .query_selector(path)
+            .ok()
+            .and_then(|element| element)
+    };
+
+    for path in &snapshot.open {
+        if let Some(element) = find(path) {
+            let _ = element.set_attribute("open", "");
+        }
+    }
+    // Open elements first - their content may be scrolled.
+    for position in &snapshot.scroll_positions {
+        if let Some(element) = find(&position.path) {
+            element.set_scroll_top(position.top);
+            element.set_scroll_left(position.left);
+        }
+    }
+    let (x, y) = snapshot.window_scroll;
+    util::window().scroll_to_with_x_and_y(x, y);
+
+    if let Some(focus) = &snapshot.focus {
+        if let Some(element) = find(&focus.path) {
+            if let Some(element) = element.dyn_ref::<web_sys::HtmlElement>() {
+                let _ = element.focus();
+            }
+            if let Some(selection) = focus.selection {
+                set_selection(&element, selection);
+            }
+        }
+    }
+}
+
+// ------ Helpers ------
+
+fn query_all(selector: &str) -> Vec<web_sys::Element> {
+    let nodes = util::document()
+        .query_selector_all(selector)
+        .expect("Problem querying elements");
+    (0..nodes.length())
+        .filter_map(|index| nodes.item(index))
+        .filter_map(|node| node.dyn_into::<web_sys::Element>().ok())
+        .collect()
+}
+
+/// Selector of `element` - a path from the nearest ancestor with `id` or from the root.
+fn path(element: &web_sys::Element) -> String {
+    let mut segments = Vec::new();
+    let mut current = Some(element.clone());
+    while let Some(element) = current {
+        let id = element.id();
+        if !id.is_empty() {
+            segments.push(format!("[id=\"{}\"]", id.replace('"', "\\\"")));
+            break;
+        }
+        let parent = element.parent_element();
+        segments.push(match parent {
+            Some(_) => format!(
+                "{}:nth-child({})",
+                element.tag_name().to_lowercase(),
+                element_position(&element)
+            ),
+            None => element.tag_name().to_lowercase(),
+        });
+        current = parent;
+    }
+    segments.reverse();
+    segments.join(" > ")
+}
+
+/// Selection of inputs and text areas. Other elements (and some input types) don't have it.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn selection(element: &web_sys::Element) -> Option<(u32, u32)> {
+    let get = |name: &str| {
+        js_sys::Reflect::get(element, &JsValue::from_str(name))
+            .ok()
+            .and_then(|value| value.as_f64())
+    };
+    Some((get("selectionStart")? as u32, get("selectionEnd")? as u32))
+}
+
+fn set_selection(element: &web_sys::Element, (start, end): (u32, u32)) {
+    let set_selection_range =
+        js_sys::Reflect::get(element, &JsValue::from_str("setSelectionRange"))
+            .ok()
+            .and_then(|method| method.dyn_into::<js_sys::Function>().ok());
+    if let Some(set_selection_range) = set_selection_range {
+        let _ = set_selection_range.call2(element, &start.into(), &end.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    const FIXTURE: &str = r#"
+        <div style="height: 50px; overflow: auto"><div style="height: 500px"></div></div>
+        <input value="Hello Seed">
+        <details><summary>More</summary><p>Details</p></details>
+        <details><summary>Closed</summary></details>
+    "#;
+
+    fn render_fixture() -> web_sys::Element {
+        let fixture = util::document().create_element("section").unwrap();
+        fixture.set_id("dev-reload-fixture");
+        fixture.set_inner_html(FIXTURE);
+        util::body().append_child(&fixture).unwrap();
+        fixture
+    }
+
+    fn select(fixture: &web_sys::Element, selector: &str) -> web_sys::Element {
+        fixture.query_selector(selector).unwrap().unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn snapshot_is_restored_after_reload() {
+        // Interact with the page.
+        let fixture = render_fixture();
+        select(&fixture, "div").set_scroll_top(120);
+        let input = select(&fixture, "input")
+            .dyn_into::<web_sys::HtmlInputElement>()
+            .unwrap();
+        input.focus().unwrap();
+        input.set_selection_range(6, 10).unwrap();
+        select(&fixture, "details")
+            .set_attribute("open", "")
+            .unwrap();
+
+        // Unload.
+        save(&take_snapshot());
+        fixture.remove();
+
+        // Reload - the same DOM is rendered from scratch.
+        let fixture = render_fixture();
+        restore();
+
+        assert_eq!(select(&fixture, "div").scroll_top(), 120);
+        let input = select(&fixture, "input")
+            .dyn_into::<web_sys::HtmlInputElement>()
+            .unwrap();
+        assert!(util::document().active_element() == Some(input.clone().into()));
+        assert_eq!(input.selection_start().unwrap(), Some(6));
+        assert_eq!(input.selection_end().unwrap(), Some(10));
+        assert!(select(&fixture, "details:nth-child(3)").has_attribute("open"));
+        assert!(!select(&fixture, "details:nth-child(4)").has_attribute("open"));
+        // The snapshot is restored only once.
+        assert!(session_storage()
+            .unwrap()
+            .get_item(STORAGE_KEY)
+            .unwrap()
+            .is_none());
+
+        fixture.remove();
+    }
+
+    #[wasm_bindgen_test]
+    fn missing_elements_are_skipped() {
+        let fixture = render_fixture();
+        select(&fixture, "details")
+            .set_attribute("open", "")
+            .unwrap();
+        select(&fixture, "input")
+            .dyn_into::<web_sys::HtmlElement>()
+            .unwrap()
+            .focus()
+            .unwrap();
+        let snapshot = take_snapshot();
+        fixture.remove();
+
+        // The app renders something else after the reload.
+        let fixture = util::document().create_element("section").unwrap();
+        fixture.set_id("dev-reload-fixture");
+        util::body().append_child(&fixture).unwrap();
+        apply_snapshot(&snapshot);
+
+        assert!(!snapshot.open.is_empty());
+        assert!(snapshot.focus.is_some());
+        assert_eq!(fixture.child_element_count(), 0);
+        fixture.remove();
+    }
+}
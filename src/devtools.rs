@@ -0,0 +1,481 @@
+//! Introspection protocol for browser devtools extensions.
+//!
+//! Enabled by the feature `devtools`. When the feature is off, this module isn't compiled
+//! and apps don't register themselves at all.
+//!
+//! Each started app is pushed into the JS array `window.__SEED_APPS__` as an object:
+//!
+//! ```js
+//!{ id: 0, protocolVersion: 1, handle: function(requestJson) { return responseJson; } }
+//! ```
+//!
+//! Requests and responses are JSON-serialized `RequestEnvelope` and `ResponseEnvelope`.
+//! See `examples/devtools/index.html` for a minimal client. The entry is removed
+//! when the app is unmounted.
+//!
+//! - `Request::GetMessageLog` requires the feature `debug-tools` in debug builds.
+//!   The log keeps the last `MESSAGE_LOG_CAPACITY` messages.
+//! - `Request::Dispatch` and message payloads in the log require `AppBuilder::devtools_codec`.
+
+use crate::{
+    app::App,
+    browser::util,
+    virtual_dom::{AtValue, CSSValue, El, Node, View},
+};
+use serde::{Deserialize, Serialize};
+#[cfg(all(feature = "debug-tools", debug_assertions))]
+use std::collections::VecDeque;
+use std::{
+    convert::TryFrom,
+    sync::atomic::{AtomicU32, Ordering},
+};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::spawn_local;
+
+/// Version of the wire format. It's increased on every breaking change of the message types.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Name of the `window` property containing registered apps.
+pub const REGISTRY_KEY: &str = "__SEED_APPS__";
+
+/// Max number of entries in the message log. The oldest entries are dropped.
+pub const MESSAGE_LOG_CAPACITY: usize = 500;
+
+static NEXT_APP_ID: AtomicU32 = AtomicU32::new(0);
+
+// ------ Messages ------
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RequestEnvelope {
+    pub version: u32,
+    pub request: Request,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Request {
+    /// Get the current virtual DOM.
+    GetVdom,
+    /// Get the log of processed messages.
+    GetMessageLog,
+    /// Send serialized message to the app's `update` function.
+    Dispatch { payload: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResponseEnvelope {
+    pub version: u32,
+    pub app_id: u32,
+    pub response: Response,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Response {
+    Vdom {
+        vdom: Option<NodeSnapshot>,
+    },
+    /// Processed messages, the oldest first.
+    MessageLog {
+        entries: Vec<MessageLogEntry>,
+    },
+    /// The message has been passed to the app's `update` function.
+    Dispatched,
+    /// The app doesn't support the request (e.g. its `Msg` isn't (de)serializable).
+    Unsupported {
+        request: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Message passed to the app's `update` function.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageLogEntry {
+    /// Time of processing in milliseconds - see `seed::browser::time::Clock::now_ms`.
+    pub timestamp: f64,
+    /// JSON of the message, `None` without `AppBuilder::devtools_codec`.
+    pub payload: Option<String>,
+}
+
+/// Serializable snapshot of a virtual DOM node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum NodeSnapshot {
+    Element {
+        tag: String,
+        attrs: Vec<(String, String)>,
+        style: Vec<(String, String)>,
+        children: Vec<NodeSnapshot>,
+    },
+    Text {
+        text: String,
+    },
+    Empty,
+}
+
+impl<Ms> From<&El<Ms>> for NodeSnapshot {
+    fn from(el: &El<Ms>) -> Self {
+        NodeSnapshot::Element {
+            tag: el.tag.as_str().to_owned(),
+            attrs: el
+                .attrs
+                .vals
+                .iter()
+                .filter_map(|(at, value)| match value {
+                    AtValue::Ignored => None,
+                    AtValue::None => Some((at.as_str().to_owned(), String::new())),
+                    AtValue::Some(value) => Some((at.as_str().to_owned(), value.clone())),
+                })
+                .collect(),
+            style: el
+                .style
+                .vals
+                .iter()
+                .filter_map(|(st, value)| match value {
+                    CSSValue::Ignored => None,
+                    CSSValue::Some(value) => Some((st.as_str().to_owned(), value.clone())),
+                })
+                .collect(),
+            children: el.children.iter().map(NodeSnapshot::from).collect(),
+        }
+    }
+}
+
+impl<Ms> From<&Node<Ms>> for NodeSnapshot {
+    fn from(node: &Node<Ms>) -> Self {
+        match node {
+            Node::Element(el) => el.into(),
+            Node::Text(text) => NodeSnapshot::Text {
+                text: text.text.to_string(),
+            },
+            Node::Empty => NodeSnapshot::Empty,
+        }
+    }
+}
+
+// ------ State ------
+
+#[derive(Default)]
+pub(crate) struct DevtoolsState {
+    registration: Option<Registration>,
+    #[cfg(all(feature = "debug-tools", debug_assertions))]
+    log: VecDeque<MessageLogEntry>,
+}
+
+struct Registration {
+    entry: js_sys::Object,
+    _handler: Closure<dyn Fn(String) -> String>,
+}
+
+// ------ Registration ------
+
+/// Register the app in `window.__SEED_APPS__`. It's called automatically when the app starts.
+pub(crate) fn register<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+) {
+    let app_id = NEXT_APP_ID.fetch_add(1, Ordering::Relaxed);
+    let handler = Closure::wrap(Box::new({
+        let app = app.clone();
+        move |request: String| {
+            let response = handle_request(&app, &request);
+            serde_json::to_string(&ResponseEnvelope {
+                version: PROTOCOL_VERSION,
+                app_id,
+                response,
+            })
+            .expect("Problem serializing devtools response")
+        }
+    }) as Box<dyn Fn(String) -> String>);
+
+    let window = util::window();
+    let registry_key = JsValue::from_str(REGISTRY_KEY);
+    let registry = js_sys::Reflect::get(&window, &registry_key)
+        .ok()
+        .filter(js_sys::Array::is_array)
+        .map_or_else(
+            || {
+                let registry = js_sys::Array::new();
+                js_sys::Reflect::set(&window, &registry_key, &registry)
+                    .expect("Problem creating devtools registry");
+                registry
+            },
+            // Not `Array::from` - it'd copy the registry.
+            JsCast::unchecked_into::<js_sys::Array>,
+        );
+
+    let entry = js_sys::Object::new();
+    let set = |key: &str, value: &JsValue| {
+        js_sys::Reflect::set(&entry, &JsValue::from_str(key), value)
+            .expect("Problem registering app in devtools registry");
+    };
+    set("id", &JsValue::from(app_id));
+    set("protocolVersion", &JsValue::from(PROTOCOL_VERSION));
+    set("handle", handler.as_ref());
+    registry.push(&entry);
+
+    // The handler references the app - it's dropped in `unregister`.
+    app.data.devtools.borrow_mut().registration = Some(Registration {
+        entry,
+        _handler: handler,
+    });
+}
+
+/// Remove the app from `window.__SEED_APPS__`. It's called when the app is unmounted.
+pub(crate) fn unregister<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+) {
+    let registration = match app.data.devtools.borrow_mut().registration.take() {
+        Some(registration) => registration,
+        None => return,
+    };
+    let registry = js_sys::Reflect::get(&util::window(), &JsValue::from_str(REGISTRY_KEY))
+        .ok()
+        .filter(js_sys::Array::is_array)
+        .map(JsCast::unchecked_into::<js_sys::Array>);
+    if let Some(registry) = registry {
+        // The array is modified in place - clients may keep a reference to it.
+        // `index_of` returns -1 when the entry has been removed by other code.
+        if let Ok(index) = u32::try_from(registry.index_of(&registration.entry, 0)) {
+            for index in index + 1..registry.length() {
+                registry.set(index - 1, registry.get(index));
+            }
+            registry.pop();
+        }
+    }
+    // We may be inside of the handler (e.g. a dispatched message unmounts the app) - drop it later.
+    spawn_local(async move { drop(registration) });
+}
+
+/// Append `message` to the message log.
+#[cfg(all(feature = "debug-tools", debug_assertions))]
+pub(crate) fn log_message<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    message: &Ms,
+) {
+    let entry = MessageLogEntry {
+        timestamp: app.cfg.clock.now_ms(),
+        payload: app
+            .cfg
+            .devtools_codec
+            .as_ref()
+            .and_then(|codec| (codec.serialize)(message)),
+    };
+    let log = &mut app.data.devtools.borrow_mut().log;
+    if log.len() == MESSAGE_LOG_CAPACITY {
+        log.pop_front();
+    }
+    log.push_back(entry);
+}
+
+fn handle_request<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    request: &str,
+) -> Response {
+    let envelope = match serde_json::from_str::<RequestEnvelope>(request) {
+        Ok(envelope) => envelope,
+        Err(error) => {
+            return Response::Error {
+                message: format!("Invalid request: {}", error),
+            }
+        }
+    };
+    if envelope.version != PROTOCOL_VERSION {
+        return Response::Error {
+            message: format!(
+                "Unsupported protocol version {}, expected {}",
+                envelope.version, PROTOCOL_VERSION
+            ),
+        };
+    }
+    match envelope.request {
+        Request::GetVdom => Response::Vdom {
+            vdom: app
+                .data
+                .main_el_vdom
+                .borrow()
+                .as_ref()
+                .map(NodeSnapshot::from),
+        },
+        Request::GetMessageLog => message_log(app),
+        Request::Dispatch { payload } => {
+            let codec = match &app.cfg.devtools_codec {
+                Some(codec) => codec,
+                None => {
+                    return Response::Unsupported {
+                        request: "Dispatch".to_owned(),
+                    }
+                }
+            };
+            match (codec.deserialize)(&payload) {
+                Ok(message) => {
+                    app.update(message);
+                    Response::Dispatched
+                }
+                Err(error) => Response::Error {
+                    message: format!("Invalid message: {}", error),
+                },
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "debug-tools", debug_assertions))]
+fn message_log<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+) -> Response {
+    Response::MessageLog {
+        entries: app.data.devtools.borrow().log.iter().cloned().collect(),
+    }
+}
+
+#[cfg(not(all(feature = "debug-tools", debug_assertions)))]
+fn message_log<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    _: &App<Ms, Mdl, ElC, GMs>,
+) -> Response {
+    Response::Unsupported {
+        request: "GetMessageLog".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as seed;
+    use crate::app::{builder::init::Init, Orders};
+    use crate::virtual_dom::{St, Tag, Text};
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn request_wire_format() {
+        let request = RequestEnvelope {
+            version: 1,
+            request: Request::Dispatch {
+                payload: "\"Increment\"".to_owned(),
+            },
+        };
+        let json = r#"{"version":1,"request":{"type":"Dispatch","payload":"\"Increment\""}}"#;
+        assert_eq!(serde_json::to_string(&request).unwrap(), json);
+        assert_eq!(
+            serde_json::from_str::<RequestEnvelope>(
+                r#"{"version":1,"request":{"type":"GetVdom"}}"#
+            )
+            .unwrap()
+            .request,
+            Request::GetVdom
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn response_wire_format() {
+        let mut el = El::<()>::empty(Tag::Div);
+        el.add_attr("id", "app");
+        el.add_style(St::Color, "red");
+        el.children.push(Node::Text(Text::new("Hi")));
+
+        let response = ResponseEnvelope {
+            version: 1,
+            app_id: 3,
+            response: Response::Vdom {
+                vdom: Some((&el).into()),
+            },
+        };
+        let json = concat!(
+            r#"{"version":1,"app_id":3,"response":{"type":"Vdom","vdom":"#,
+            r#"{"type":"Element","tag":"div","attrs":[["id","app"]],"style":[["color","red"]],"#,
+            r#""children":[{"type":"Text","text":"Hi"}]}}}"#
+        );
+        assert_eq!(serde_json::to_string(&response).unwrap(), json);
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    enum Msg {
+        Increment,
+    }
+
+    fn update(msg: Msg, model: &mut u32, orders: &mut impl Orders<Msg>) {
+        orders.skip();
+        match msg {
+            Msg::Increment => *model += 1,
+        }
+    }
+
+    fn registry() -> js_sys::Array {
+        js_sys::Reflect::get(&util::window(), &JsValue::from_str(REGISTRY_KEY))
+            .unwrap()
+            .unchecked_into()
+    }
+
+    fn send(entry: &JsValue, request: Request) -> Response {
+        let handle: js_sys::Function = js_sys::Reflect::get(entry, &JsValue::from_str("handle"))
+            .unwrap()
+            .unchecked_into();
+        let request = serde_json::to_string(&RequestEnvelope {
+            version: PROTOCOL_VERSION,
+            request,
+        })
+        .unwrap();
+        let response = handle
+            .call1(&JsValue::NULL, &JsValue::from_str(&request))
+            .unwrap()
+            .as_string()
+            .unwrap();
+        serde_json::from_str::<ResponseEnvelope>(&response)
+            .unwrap()
+            .response
+    }
+
+    #[wasm_bindgen_test]
+    fn dispatch_and_unregister() {
+        let app = App::build(|_, _| Init::new(0), update, |_| seed::empty())
+            .mount(util::document().create_element("div").unwrap())
+            .devtools_codec()
+            .finish()
+            .run();
+        let registry = registry();
+        let entry = registry.get(registry.length() - 1);
+
+        assert_eq!(
+            send(
+                &entry,
+                Request::Dispatch {
+                    payload: "\"Increment\"".to_owned()
+                }
+            ),
+            Response::Dispatched
+        );
+        assert_eq!(*app.data.model.borrow(), Some(1));
+        match send(
+            &entry,
+            Request::Dispatch {
+                payload: "\"Decrement\"".to_owned(),
+            },
+        ) {
+            Response::Error { .. } => (),
+            response => panic!("unexpected response {:?}", response),
+        }
+
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        {
+            match send(&entry, Request::GetMessageLog) {
+                Response::MessageLog { entries } => assert_eq!(
+                    entries
+                        .into_iter()
+                        .map(|entry| entry.payload)
+                        .collect::<Vec<_>>(),
+                    vec![Some("\"Increment\"".to_owned())]
+                ),
+                response => panic!("unexpected response {:?}", response),
+            }
+        }
+
+        let length = registry.length();
+        app.unmount();
+        assert_eq!(registry.length(), length - 1);
+        assert_eq!(registry.index_of(&entry, 0), -1);
+    }
+}
@@ -3,7 +3,7 @@ pub mod events;
 pub mod styles;
 pub mod tags;
 
-pub use attributes::At;
-pub use events::Ev;
+pub use attributes::{At, InvalidAttrName};
+pub use events::{Ev, EventTrigger, TEv, TypedEv};
 pub use styles::St;
 pub use tags::Tag;
@@ -54,3 +54,127 @@ macro_rules! make_attrs {
 
 mod attribute_names;
 pub use attribute_names::At;
+
+// ------ Validation ------
+
+/// Error returned when an attribute name doesn't match the XML `Name` grammar.
+///
+/// [https://www.w3.org/TR/xml/#NT-Name](https://www.w3.org/TR/xml/#NT-Name)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidAttrName(pub String);
+
+impl std::fmt::Display for InvalidAttrName {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Invalid attribute name: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidAttrName {}
+
+impl At {
+    /// Create an attribute from a name built at runtime (e.g. from user data).
+    /// Returns error if the name isn't a valid attribute name.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    ///assert_eq!(At::try_custom("data-id"), Ok(At::Custom("data-id".to_owned())));
+    ///assert!(At::try_custom("onclick=\"alert(1)\"").is_err());
+    /// ```
+    pub fn try_custom(name: impl Into<String>) -> Result<Self, InvalidAttrName> {
+        let at = At::from(name.into());
+        at.validate()?;
+        Ok(at)
+    }
+
+    /// Check that the attribute name is valid. Only `At::Custom` can be invalid.
+    pub fn validate(&self) -> Result<(), InvalidAttrName> {
+        match self {
+            At::Custom(name) if !is_valid_attr_name(name) => Err(InvalidAttrName(name.clone())),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Does `name` match the XML `Name` grammar?
+pub fn is_valid_attr_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if is_name_start_char(first) => chars.all(is_name_char),
+        _ => false,
+    }
+}
+
+fn is_name_start_char(c: char) -> bool {
+    match c {
+        ':' | 'A'..='Z' | '_' | 'a'..='z' => true,
+        '\u{C0}'..='\u{D6}'
+        | '\u{D8}'..='\u{F6}'
+        | '\u{F8}'..='\u{2FF}'
+        | '\u{370}'..='\u{37D}'
+        | '\u{37F}'..='\u{1FFF}'
+        | '\u{200C}'..='\u{200D}'
+        | '\u{2070}'..='\u{218F}'
+        | '\u{2C00}'..='\u{2FEF}'
+        | '\u{3001}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}'
+        | '\u{FDF0}'..='\u{FFFD}'
+        | '\u{10000}'..='\u{EFFFF}' => true,
+        _ => false,
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    is_name_start_char(c)
+        || match c {
+            '-' | '.' | '0'..='9' | '\u{B7}' => true,
+            '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}' => true,
+            _ => false,
+        }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn valid_attr_names() {
+        for name in &["data-id", "aria-label", "xlink:href", "_x", "é-ü", "x.y-1"] {
+            assert!(is_valid_attr_name(name), "{}", name);
+            assert!(At::try_custom(*name).is_ok(), "{}", name);
+        }
+        assert_eq!(At::try_custom("class"), Ok(At::Class));
+    }
+
+    #[wasm_bindgen_test]
+    fn hostile_attr_names() {
+        let hostile = [
+            "",
+            " ",
+            "a b",
+            "a\"b",
+            "a'b",
+            "a=b",
+            "a>b",
+            "<script>",
+            "onclick=\"alert(1)\"",
+            "1abc",
+            "-abc",
+            "a\u{0}b",
+            "a\nb",
+            "a/b",
+            "\u{FFFF}",
+            "\u{2028}",
+        ];
+        for name in &hostile {
+            assert!(!is_valid_attr_name(name), "{:?}", name);
+            assert_eq!(
+                At::Custom((*name).to_owned()).validate(),
+                Err(InvalidAttrName((*name).to_owned()))
+            );
+        }
+    }
+}
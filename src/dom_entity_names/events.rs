@@ -1,3 +1,6 @@
+use crate::browser::dom::event_handler::FromEvent;
+use std::marker::PhantomData;
+
 /// Similar to tag population.
 macro_rules! make_events {
     // Create shortcut macros for any element; populate these functions in this module.
@@ -63,5 +66,75 @@ macro_rules! make_events {
     }
 }
 
+/// Creates typed counterparts of `Ev` variants - see `TEv`.
+macro_rules! make_typed_events {
+    { $($event_camel:ident => $interface:ident),+ } => {
+
+        /// Typed events. Handlers created by `ev(TEv::PointerMove, |event| ..)`
+        /// receive the event's DOM interface (`web_sys::PointerEvent` in this case).
+        ///
+        /// Use `Ev` for events which aren't listed here - their handlers receive `web_sys::Event`.
+        pub struct TEv;
+
+        #[allow(non_upper_case_globals)]
+        impl TEv {
+            $(
+                pub const $event_camel: TypedEv<web_sys::$interface> = TypedEv::new(Ev::$event_camel);
+            )+
+        }
+    }
+}
+
 mod event_names;
 pub use event_names::Ev;
+
+mod event_interfaces;
+pub use event_interfaces::TEv;
+
+// ------ TypedEv ------
+
+/// `Ev` together with the type of events fired for it.
+#[derive(Debug)]
+pub struct TypedEv<E> {
+    ev: Ev,
+    event_type: PhantomData<E>,
+}
+
+impl<E> TypedEv<E> {
+    pub const fn new(ev: Ev) -> Self {
+        Self {
+            ev,
+            event_type: PhantomData,
+        }
+    }
+}
+
+impl<E> Clone for TypedEv<E> {
+    fn clone(&self) -> Self {
+        Self::new(self.ev)
+    }
+}
+
+impl<E> Copy for TypedEv<E> {}
+
+// ------ EventTrigger ------
+
+/// Event name together with the type passed to event handlers. See `seed::prelude::ev`.
+pub trait EventTrigger: Copy {
+    type Event: FromEvent;
+    fn ev(&self) -> Ev;
+}
+
+impl EventTrigger for Ev {
+    type Event = web_sys::Event;
+    fn ev(&self) -> Ev {
+        *self
+    }
+}
+
+impl<E: FromEvent> EventTrigger for TypedEv<E> {
+    type Event = E;
+    fn ev(&self) -> Ev {
+        self.ev
+    }
+}
@@ -0,0 +1,33 @@
+// DOM interfaces of events: https://developer.mozilla.org/en-US/docs/Web/API/Event#Introduction
+// Events which aren't listed here are represented by `web_sys::Event`.
+use super::{Ev, TypedEv};
+
+make_typed_events! {
+    Focus => FocusEvent, Blur => FocusEvent,
+    PageHide => PageTransitionEvent, PageShow => PageTransitionEvent, PopState => PopStateEvent,
+    BeforeUnload => BeforeUnloadEvent,
+
+    AnimationStart => AnimationEvent, AnimationEnd => AnimationEvent, AnimationIteration => AnimationEvent,
+    TransitionStart => TransitionEvent, TransitionEnd => TransitionEvent, TranstionRun => TransitionEvent,
+
+    CompositionStart => CompositionEvent, CompositionUpdate => CompositionEvent, CompositionEnd => CompositionEvent,
+
+    Cut => ClipboardEvent, Copy => ClipboardEvent, Paste => ClipboardEvent,
+
+    KeyDown => KeyboardEvent, KeyUp => KeyboardEvent, KeyPress => KeyboardEvent,
+
+    AuxClick => MouseEvent, Click => MouseEvent, ContextMenu => MouseEvent, DblClick => MouseEvent,
+    MouseDown => MouseEvent, MouseEnter => MouseEvent, MouseLeave => MouseEvent,
+    MouseMove => MouseEvent, MouseOver => MouseEvent, MouseOut => MouseEvent, MouseUp => MouseEvent,
+    Wheel => WheelEvent,
+
+    PointerOver => PointerEvent, PointerEnter => PointerEvent,
+    PointerDown => PointerEvent, PointerMove => PointerEvent, PointerUp => PointerEvent,
+    PointerCancel => PointerEvent, PointerOut => PointerEvent, PointerLeave => PointerEvent,
+    GotPointerCapture => PointerEvent, LostPointerCapture => PointerEvent,
+
+    Drag => DragEvent, DragEnd => DragEvent, DragEnter => DragEvent, DragStart => DragEvent, DragLeave => DragEvent,
+    DragOver => DragEvent, Drop => DragEvent,
+
+    Input => InputEvent
+}
@@ -12,6 +12,7 @@ make_events! {
 
     FullScreenChange => "fullscreenchange", FullScreenError => "fullscreenerror", Resize => "resize",
     Scroll => "scroll", Cut => "cut", Copy => "copy", Paste => "paste",
+    VisibilityChange => "visibilitychange", SelectionChange => "selectionchange",
 
     KeyDown => "keydown", KeyUp => "keyup",
     KeyPress => "keypress", AuxClick => "auxclick", Click => "click", ContextMenu => "contextmenu", DblClick => "dblclick",
@@ -21,6 +21,16 @@ macro_rules! make_styles {
                     St::Custom(val) => &val
                 }
             }
+
+            /// Similar to `From<&str>`, but unknown names (e.g. custom properties) aren't logged.
+            pub(crate) fn from_css_name(name: &str) -> Self {
+                match name {
+                    $ (
+                          $st => St::$st_pascal_case,
+                    ) +
+                    _ => St::Custom(name.to_owned())
+                }
+            }
         }
 
         impl From<&str> for St {
@@ -10,8 +10,10 @@ pub use crate::{
     browser::dom::cast::{
         to_html_el, to_input, to_kbevent, to_mouse_event, to_select, to_textarea,
     },
+    browser::dom::scheduler::{dom_read, dom_write},
     browser::service::fetch::{Method, Request},
-    browser::service::routing::push_route,
+    browser::service::routing::{push_route, push_route_with_state, replace_route},
+    browser::time,
     browser::url::Url,
     browser::util::{
         self, body, canvas, canvas_context_2d, cookies, document, error, history, html_document,
@@ -24,7 +26,14 @@ use wasm_bindgen::{closure::Closure, JsCast};
 pub mod shortcuts;
 pub mod app;
 pub mod browser;
+#[cfg(all(feature = "debug-tools", debug_assertions))]
+pub mod debug;
+#[cfg(all(feature = "dev-reload-ux", debug_assertions))]
+pub mod dev_reload;
+#[cfg(feature = "devtools")]
+pub mod devtools;
 pub mod dom_entity_names;
+pub mod test;
 pub mod virtual_dom;
 
 /// Create an element flagged in a way that it will not be rendered. Useful
@@ -75,24 +84,57 @@ pub fn set_timeout(handler: Box<dyn Fn()>, timeout: i32) {
 pub mod prelude {
     pub use crate::{
         app::{
-            builder::init::Init, AfterMount, App, BeforeMount, MessageMapper, MountType, Orders,
-            RenderTimestampDelta, UrlHandling,
+            builder::init::Init, cmds, locale, prefetch_on_hover, skip_link, streams,
+            toast_region_view, ActiveLocale, AfterMount, App, AsKind, BeforeMount, CmdFailure,
+            CmdHandle, ConfigError, CrossOrigin, FrameStatsOpts, FrameStatsSnapshot,
+            FrameworkError, GMessageMapper, Lane, LaneStats, Locale, LocaleChanged, LocaleOpts,
+            MemoryGrowthCheck, MessageMapper, MountType, OnQueueBudgetExceeded, Orders, PageMeta,
+            PerfBudget, PerfViolation, Port, PortEvent, PortPayload, PreloadHandle, PreloadHint,
+            PreloadStatus, QueueBudget, QueueOverflow, RenderInfo, RenderTimestampDelta,
+            RouteFocus, RoutePrefetch, ScrollBehavior, StreamHandle, SubHandle, Toast,
+            ToastManager, ToastMsg, ToastOpts, UrlHandling, WarnMode,
         },
+        browser::dom::content_editable::{content_editable_ev, EditableContent},
         browser::dom::css_units::*,
+        browser::dom::dialog::{close_ev, dialog_open, popover_open, popover_toggle_ev},
         browser::dom::event_handler::{
-            input_ev, keyboard_ev, mouse_ev, pointer_ev, raw_ev, simple_ev, trigger_update_handler,
+            ev, input_ev, keyboard_ev, mouse_ev, pointer_ev, raw_ev, simple_ev,
+            trigger_update_handler,
         },
+        browser::dom::flip::{flip_group, FlipEffect, FlipGroup},
+        browser::dom::global_events::EventHandle,
         browser::dom::lifecycle_hooks::{did_mount, did_update, will_unmount},
+        browser::dom::scroll_progress::{Axis, ScrollProgressHandle, ScrollProgressOpts},
+        browser::dom::typed_input::{
+            date_input_ev, number_input_ev, range_input_ev, InputDate, NumberValue,
+        },
+        browser::dom::viewport::{SafeAreaInsets, Viewport, ViewportHandle, ViewportOpts},
+        browser::service::routing::{
+            FormDataValue, FormSubmission, RouteMatcher, RouteParams, Router,
+        },
+        browser::service::storage_manager::{
+            StorageEstimate, StorageQuotaHandle, StorageQuotaOpts,
+        },
+        browser::service::synced_slice::{SyncError, SyncedSlice},
+        browser::service::websocket::{
+            CloseEvent, WebSocket, WebSocketError, WebSocketHandlers, WebSocketMessage,
+        },
+        browser::time::{Clock, MockClock},
         browser::util::{
             request_animation_frame, ClosureNew, RequestAnimationFrameHandle,
             RequestAnimationFrameTime,
         },
-        browser::Url,
+        browser::{
+            Capabilities, Capability, Modality, PausePolicy, ShareData, ShareError, Url, UrlError,
+            VisibilityResumed,
+        },
         // macros are exported in crate root
         // https://github.com/rust-lang-nursery/reference/blob/master/src/macros-by-example.md
         shortcuts::*,
         virtual_dom::{
-            AsAtValue, At, AtValue, CSSValue, El, Ev, Listener, Node, St, Tag, UpdateEl, View,
+            active_link, active_when, el_key, el_ref, hydrate_lazy, inner_html, keep_alive,
+            keyed_nodes, preserve_whitespace, rendered_by, AsAtValue, At, AtValue, CSSValue, El,
+            ElRef, Ev, LinkMatch, Listener, Node, St, TEv, Tag, TextNormalization, UpdateEl, View,
         },
     };
     pub use indexmap::IndexMap; // for attrs and style to work.
@@ -29,6 +29,7 @@ macro_rules! element {
                             {
                                 #[allow(unused_mut)]
                                 let mut el = El::empty($crate::virtual_dom::Tag::$Tag_camel);
+                                el.set_caller_source();
                                 $d (
                                     $d part.update(&mut el);
                                 )*
@@ -55,6 +56,7 @@ macro_rules! element_svg {
                             {
                                 #[allow(unused_mut)]
                                 let mut el = El::empty_svg($crate::virtual_dom::Tag::$Tag_camel);
+                                el.set_caller_source();
                                 $d ( $d part.update(&mut el); )*
                                 $crate::virtual_dom::Node::Element(el)
                             }
@@ -186,6 +188,7 @@ macro_rules! custom {
         {
             let default_tag_name = "missing-tag-name";
             let mut el = El::empty($crate::virtual_dom::Tag::from(default_tag_name));
+            el.set_caller_source();
             $ ( $part.update(&mut el); )*
 
             if let $crate::virtual_dom::Tag::Custom(tag_name) = &el.tag {
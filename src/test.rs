@@ -0,0 +1,12 @@
+//! Helpers for integration tests of Seed apps (e.g. by `wasm-bindgen-test`).
+//!
+//! Dispatch events by `simulate` and wait for their render by `App::next_render`:
+//!
+//! ```rust,no_run
+//!simulate::input(&model.title_input, "Seed");
+//!simulate::key_down(&model.title_input, "Enter");
+//!app.next_render().await;
+//!assert_eq!(model_title(&app), "Seed");
+//! ```
+
+pub mod simulate;
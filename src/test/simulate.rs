@@ -0,0 +1,191 @@
+//! Dispatch user events - they bubble and they are cancelable like the browser's ones.
+//! Each function returns `false` when a listener has prevented the event's default action.
+
+use crate::browser::util;
+use crate::virtual_dom::ElRef;
+use wasm_bindgen::JsCast;
+
+/// Target of simulated events - `ElRef` or a `web_sys` node.
+pub trait Target {
+    /// # Panics
+    ///
+    /// Panics when `ElRef`'s element isn't rendered.
+    fn event_target(&self) -> web_sys::EventTarget;
+}
+
+impl<E: JsCast + Clone> Target for ElRef<E> {
+    fn event_target(&self) -> web_sys::EventTarget {
+        self.get()
+            .expect("The element of `ElRef` isn't rendered")
+            .unchecked_into()
+    }
+}
+
+impl<T: AsRef<web_sys::EventTarget>> Target for T {
+    fn event_target(&self) -> web_sys::EventTarget {
+        self.as_ref().clone()
+    }
+}
+
+fn event_init() -> web_sys::EventInit {
+    let mut init = web_sys::EventInit::new();
+    init.bubbles(true).cancelable(true);
+    init
+}
+
+fn dispatch(target: &impl Target, event: &web_sys::Event) -> bool {
+    target
+        .event_target()
+        .dispatch_event(event)
+        .expect("Problem dispatching simulated event")
+}
+
+/// Dispatch `click`.
+pub fn click(target: &impl Target) -> bool {
+    let mut init = web_sys::MouseEventInit::new();
+    init.bubbles(true).cancelable(true).button(0);
+    let event = web_sys::MouseEvent::new_with_mouse_event_init_dict("click", &init)
+        .expect("Problem creating click event");
+    dispatch(target, &event)
+}
+
+/// Set the value of `input`, `textarea` or `select` to `value` and dispatch `input`.
+///
+/// # Panics
+///
+/// Panics when the target doesn't have a value.
+pub fn input(target: &impl Target, value: &str) -> bool {
+    set_value(target, value);
+    let event = web_sys::Event::new_with_event_init_dict("input", &event_init())
+        .expect("Problem creating input event");
+    dispatch(target, &event)
+}
+
+/// Set the value of `input`, `textarea` or `select` to `value` and dispatch `change`.
+///
+/// # Panics
+///
+/// Panics when the target doesn't have a value.
+pub fn change(target: &impl Target, value: &str) -> bool {
+    set_value(target, value);
+    let event = web_sys::Event::new_with_event_init_dict("change", &event_init())
+        .expect("Problem creating change event");
+    dispatch(target, &event)
+}
+
+/// Dispatch `keydown` with `key` - e.g. `"Enter"` or `"a"`.
+pub fn key_down(target: &impl Target, key: &str) -> bool {
+    dispatch(target, &keyboard_event("keydown", key))
+}
+
+/// Dispatch `keyup` with `key` - e.g. `"Enter"` or `"a"`.
+pub fn key_up(target: &impl Target, key: &str) -> bool {
+    dispatch(target, &keyboard_event("keyup", key))
+}
+
+fn keyboard_event(type_: &str, key: &str) -> web_sys::KeyboardEvent {
+    let mut init = web_sys::KeyboardEventInit::new();
+    init.bubbles(true).cancelable(true).key(key);
+    web_sys::KeyboardEvent::new_with_keyboard_event_init_dict(type_, &init)
+        .expect("Problem creating keyboard event")
+}
+
+fn set_value(target: &impl Target, value: &str) {
+    util::set_value(&target.event_target(), value).expect("Problem setting simulated value");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate as seed;
+    use crate::prelude::*;
+    use crate::test::simulate;
+    use std::{cell::RefCell, rc::Rc};
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[derive(Default)]
+    struct Model {
+        clicks: u32,
+        title: String,
+        submitted: Rc<RefCell<Vec<String>>>,
+        input: ElRef<web_sys::HtmlInputElement>,
+    }
+
+    #[derive(Clone)]
+    enum Msg {
+        Clicked,
+        TitleChanged(String),
+        KeyPressed(String),
+    }
+
+    fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
+        match msg {
+            Msg::Clicked => model.clicks += 1,
+            Msg::TitleChanged(title) => model.title = title,
+            Msg::KeyPressed(key) => {
+                if key == "Enter" {
+                    model.submitted.borrow_mut().push(model.title.clone())
+                }
+            }
+        }
+    }
+
+    fn view(model: &Model) -> Node<Msg> {
+        div![
+            ev(Ev::Click, |_| Msg::Clicked),
+            span![
+                class!["summary"],
+                format!("{} {}", model.clicks, model.title)
+            ],
+            input![
+                el_ref(&model.input),
+                input_ev(Ev::Input, Msg::TitleChanged),
+                ev(TEv::KeyDown, |event| Msg::KeyPressed(event.key())),
+            ],
+        ]
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn simulated_events_update_app() {
+        let submitted = Rc::new(RefCell::new(Vec::new()));
+        let title_input = ElRef::new();
+        let mount_point = seed::document().create_element("div").unwrap();
+        let app = App::build(
+            {
+                let (submitted, input) = (Rc::clone(&submitted), title_input.clone());
+                move |_, _| {
+                    Init::new_with_url_handling(
+                        Model {
+                            submitted,
+                            input,
+                            ..Model::default()
+                        },
+                        UrlHandling::None,
+                    )
+                }
+            },
+            update,
+            view,
+        )
+        .mount(mount_point.clone())
+        .finish()
+        .run();
+        app.next_render().await;
+        let summary = mount_point.query_selector(".summary").unwrap().unwrap();
+        assert_eq!(summary.text_content().unwrap(), "0 ");
+
+        // Bubbles to the `div`.
+        assert!(simulate::click(&summary));
+        assert!(simulate::input(&title_input, "Seed"));
+        assert!(simulate::key_down(&title_input, "Enter"));
+        assert!(simulate::key_up(&title_input, "Enter"));
+        app.next_render().await;
+        assert_eq!(summary.text_content().unwrap(), "1 Seed");
+        assert_eq!(*submitted.borrow(), vec!["Seed"]);
+
+        // Nothing is scheduled.
+        app.next_render().await;
+        app.unmount();
+    }
+}
@@ -1,38 +1,62 @@
+pub mod active_link;
 pub mod attrs;
+pub mod el_key;
+pub mod el_ref;
+pub mod html_string;
+pub(crate) mod hydration;
+pub mod inner_html;
+pub mod keep_alive;
+pub mod lazy_hydration;
 pub mod listener;
 pub mod mailbox;
 pub mod node;
 pub mod patch;
+pub mod renderer;
 pub mod style;
+pub mod text_normalization;
 pub mod update_el;
 pub mod values;
+pub(crate) mod vdom_eq;
 pub mod view;
 
+pub use active_link::{active_link, active_when, ActiveLink, LinkMatch};
 pub use attrs::Attrs;
+pub use el_key::{el_key, keyed_nodes, ElKey};
+pub use el_ref::{el_ref, ElRef};
+pub use html_string::render_to_string;
+pub use inner_html::{inner_html, InnerHtml};
+pub use keep_alive::{keep_alive, KeepAlive};
+pub use lazy_hydration::hydrate_lazy;
 pub use listener::{Category, Listener};
 pub use mailbox::Mailbox;
 pub use node::{El, Node, Text};
+pub use renderer::{rendered_by, RenderedBy, Renderer};
 pub use style::Style;
+pub use text_normalization::{preserve_whitespace, TextNormalization};
 pub use update_el::UpdateEl;
 pub use values::{AsAtValue, AtValue, CSSValue};
 pub use view::View;
 
-pub use crate::dom_entity_names::{At, Ev, St, Tag};
+pub use crate::dom_entity_names::{At, Ev, EventTrigger, St, TEv, Tag, TypedEv};
 
 #[cfg(test)]
 pub mod tests {
-    use futures::future;
-    use std::{cell::RefCell, rc::Rc};
+    use futures::{future, StreamExt};
+    use std::{
+        cell::{Cell, RefCell},
+        convert::identity,
+        rc::Rc,
+    };
     use wasm_bindgen::JsCast;
     use wasm_bindgen_test::*;
     use web_sys::{self, Element};
 
     use crate as seed;
     use crate::{
-        browser::{dom::virtual_dom_bridge, util},
+        browser::{dom::{scheduler, virtual_dom_bridge}, util, NextTick},
         class,
         prelude::*,
-        virtual_dom::{mailbox::Mailbox, patch},
+        virtual_dom::{lazy_hydration, mailbox::Mailbox, patch, Renderer},
     };
 
     wasm_bindgen_test_configure!(run_in_browser);
@@ -261,6 +285,130 @@ pub mod tests {
         }
     }
 
+    /// Tests that reversing a keyed list moves DOM nodes instead of recreating them
+    /// and that the focused input keeps focus.
+    #[wasm_bindgen_test]
+    fn keyed_children_reversed() {
+        let app = create_app();
+        let mailbox = Mailbox::new(|_msg: Msg| {});
+        let doc = util::document();
+        let parent = doc.create_element("div").unwrap();
+        util::body().append_child(&parent).unwrap();
+
+        let list = |ids: &[u32]| -> Node<Msg> {
+            ul![ids
+                .iter()
+                .map(|id| li![el_key(id), input![attrs! {At::Id => id.to_string()}]])]
+        };
+
+        let mut vdom = ul![];
+        virtual_dom_bridge::assign_ws_nodes(&doc, &mut vdom);
+        let ul_ws = vdom.el().unwrap().node_ws.clone().unwrap();
+        parent.append_child(&ul_ws).unwrap();
+        vdom = call_patch(&doc, &parent, &mailbox, vdom, list(&[1, 2, 3, 4]), &app);
+        let items = iter_child_nodes(&ul_ws).collect::<Vec<_>>();
+
+        let input = doc
+            .get_element_by_id("2")
+            .unwrap()
+            .dyn_into::<web_sys::HtmlElement>()
+            .unwrap();
+        input.focus().unwrap();
+
+        call_patch(&doc, &parent, &mailbox, vdom, list(&[4, 3, 2, 1]), &app);
+        let reversed = iter_child_nodes(&ul_ws).collect::<Vec<_>>();
+        assert_eq!(reversed.len(), 4);
+        for (item, reversed_item) in items.iter().rev().zip(&reversed) {
+            assert!(item.is_same_node(Some(reversed_item)));
+        }
+        assert_eq!(
+            ul_ws.dyn_ref::<Element>().unwrap().inner_html(),
+            "<li><input id=\"4\"></li><li><input id=\"3\"></li>\
+             <li><input id=\"2\"></li><li><input id=\"1\"></li>"
+        );
+        assert_eq!(doc.active_element(), Some(input.into()));
+
+        util::body().remove_child(&parent).unwrap();
+    }
+
+    /// Tests that keyed siblings are matched by keys and unkeyed siblings by their positions
+    /// and that duplicate keys fall back to positional patching.
+    #[wasm_bindgen_test]
+    fn keyed_and_unkeyed_children() {
+        let app = create_app();
+        let mailbox = Mailbox::new(|_msg: Msg| {});
+        let doc = util::document();
+        let parent = doc.create_element("div").unwrap();
+
+        let mut vdom = div![];
+        virtual_dom_bridge::assign_ws_nodes(&doc, &mut vdom);
+        let div_ws = vdom.el().unwrap().node_ws.clone().unwrap();
+        parent.append_child(&div_ws).unwrap();
+        let html = || div_ws.dyn_ref::<Element>().unwrap().inner_html();
+        let child = |index| div_ws.child_nodes().item(index).unwrap();
+
+        vdom = call_patch(
+            &doc,
+            &parent,
+            &mailbox,
+            vdom,
+            div![
+                span![el_key(&"a"), "A"],
+                "text",
+                span![el_key(&"b"), "B"],
+                p!["P"],
+            ],
+            &app,
+        );
+        assert_eq!(html(), "<span>A</span>text<span>B</span><p>P</p>");
+        let (a, text, b, p) = (child(0), child(1), child(2), child(3));
+
+        vdom = call_patch(
+            &doc,
+            &parent,
+            &mailbox,
+            vdom,
+            div![
+                span![el_key(&"b"), "B"],
+                "changed text",
+                span![el_key(&"c"), "C"],
+                p!["P"],
+                span![el_key(&"a"), "A"],
+            ],
+            &app,
+        );
+        assert_eq!(
+            html(),
+            "<span>B</span>changed text<span>C</span><p>P</p><span>A</span>"
+        );
+        assert!(child(0).is_same_node(Some(&b)));
+        assert!(child(1).is_same_node(Some(&text)));
+        assert!(child(3).is_same_node(Some(&p)));
+        assert!(child(4).is_same_node(Some(&a)));
+
+        // Duplicate keys.
+        vdom = call_patch(
+            &doc,
+            &parent,
+            &mailbox,
+            vdom,
+            div![span![el_key(&"a"), "1"], span![el_key(&"a"), "2"]],
+            &app,
+        );
+        assert_eq!(html(), "<span>1</span><span>2</span>");
+        let first = child(0);
+        call_patch(
+            &doc,
+            &parent,
+            &mailbox,
+            vdom,
+            div![span![el_key(&"a"), "2"], span![el_key(&"a"), "1"]],
+            &app,
+        );
+        assert_eq!(html(), "<span>2</span><span>1</span>");
+        assert!(child(0).is_same_node(Some(&first)));
+    }
+
     /// Test if attribute `disabled` is correctly added and then removed.
     #[wasm_bindgen_test]
     fn attr_disabled() {
@@ -662,6 +810,232 @@ pub mod tests {
         assert!(node_ref.borrow().is_none(), "will_unmount wasn't called");
     }
 
+    #[wasm_bindgen_test]
+    fn keep_alive_parks_dom() {
+        let app = create_app();
+        let mailbox = Mailbox::new(|_msg: Msg| {});
+
+        let doc = util::document();
+        let parent = doc.create_element("div").unwrap();
+        // Scroll positions are kept only for elements in the document.
+        util::body().append_child(&parent).unwrap();
+
+        let view = |shown: bool| -> Node<Msg> {
+            div![keep_alive(shown, "list", || {
+                ul![
+                    style! {St::Height => px(20); St::Overflow => "auto"},
+                    (0..10).map(|i| li![i.to_string()])
+                ]
+            })]
+        };
+
+        let mut vdom = Node::Element(El::empty(Tag::Div));
+        virtual_dom_bridge::assign_ws_nodes(&doc, &mut vdom);
+        let old_ws = vdom.el().unwrap().node_ws.clone().unwrap();
+        parent.append_child(&old_ws).unwrap();
+
+        vdom = call_patch(&doc, &parent, &mailbox, vdom, view(true), &app);
+        let list_ws = old_ws
+            .first_child()
+            .unwrap()
+            .dyn_into::<web_sys::Element>()
+            .unwrap();
+        list_ws.set_scroll_top(15);
+        let scroll_top = list_ws.scroll_top();
+        assert!(scroll_top > 0);
+
+        // Hide - the placeholder replaces the list.
+        vdom = call_patch(&doc, &parent, &mailbox, vdom, view(false), &app);
+        assert_eq!(old_ws.child_nodes().length(), 1);
+        assert!(!list_ws.is_same_node(old_ws.first_child().as_ref()));
+        assert!(app.data.kept_alive.borrow().contains_key("list"));
+
+        // Show - the original list node is back with its scroll position.
+        call_patch(&doc, &parent, &mailbox, vdom, view(true), &app);
+        assert!(list_ws.is_same_node(old_ws.first_child().as_ref()));
+        assert_eq!(list_ws.scroll_top(), scroll_top);
+        assert!(app.data.kept_alive.borrow().is_empty());
+
+        util::body().remove_child(&parent).unwrap();
+    }
+
+    /// Tests that media paused by the browser while parked play again when shown
+    /// and that `unmount` drops parked subtrees.
+    #[wasm_bindgen_test(async)]
+    async fn keep_alive_resumes_media() {
+        let app = create_app();
+        let mailbox = Mailbox::new(|_msg: Msg| {});
+
+        let doc = util::document();
+        let parent = doc.create_element("div").unwrap();
+        util::body().append_child(&parent).unwrap();
+
+        let view =
+            |shown: bool| -> Node<Msg> { div![keep_alive(shown, "player", || div![video![]])] };
+
+        let mut vdom = Node::Element(El::empty(Tag::Div));
+        virtual_dom_bridge::assign_ws_nodes(&doc, &mut vdom);
+        let old_ws = vdom.el().unwrap().node_ws.clone().unwrap();
+        parent.append_child(&old_ws).unwrap();
+
+        vdom = call_patch(&doc, &parent, &mailbox, vdom, view(true), &app);
+        let video_ws = old_ws
+            .first_child()
+            .unwrap()
+            .first_child()
+            .unwrap()
+            .dyn_into::<web_sys::HtmlMediaElement>()
+            .unwrap();
+        // `play` clears `paused` immediately; its promise is rejected - there is no source.
+        let _ = video_ws.play();
+        assert!(!video_ws.paused());
+
+        // Hide - the browser pauses the detached video.
+        vdom = call_patch(&doc, &parent, &mailbox, vdom, view(false), &app);
+        for _ in 0..5 {
+            NextTick::new().await;
+        }
+        assert!(video_ws.paused());
+
+        // Show - the video plays again.
+        vdom = call_patch(&doc, &parent, &mailbox, vdom, view(true), &app);
+        assert!(!video_ws.paused());
+
+        // Hide again and unmount - the parked subtree is dropped.
+        call_patch(&doc, &parent, &mailbox, vdom, view(false), &app);
+        assert!(app.data.kept_alive.borrow().contains_key("player"));
+        app.unmount();
+        assert!(app.data.kept_alive.borrow().is_empty());
+
+        util::body().remove_child(&parent).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn not_found_on_initial_url() {
+        #[derive(Default)]
+        struct Model {
+            not_found_url: Option<Url>,
+        }
+        #[derive(Clone)]
+        enum Msg {
+            Routed,
+            NotFound(Url),
+        }
+
+        fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Routed => (),
+                Msg::NotFound(url) => model.not_found_url = Some(url),
+            }
+        }
+
+        let app = App::build(
+            |_, _| Init::new(Model::default()),
+            update,
+            |_| seed::empty(),
+        )
+        .mount(seed::body())
+        .routes(|url| {
+            if url.path.first().map(String::as_str) == Some("known-page") {
+                Some(Msg::Routed)
+            } else {
+                None
+            }
+        })
+        .not_found(Msg::NotFound)
+        .build_and_start();
+
+        assert_eq!(
+            app.data.model.borrow().as_ref().unwrap().not_found_url,
+            Some(seed::browser::url::current())
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn custom_renderer() {
+        struct MockRenderer {
+            log: Rc<RefCell<Vec<String>>>,
+        }
+        impl Renderer<Msg> for MockRenderer {
+            fn create(&self, el: &El<Msg>, _: &web_sys::Node) {
+                self.log
+                    .borrow_mut()
+                    .push(format!("create {}", el.children.len()));
+            }
+            fn update(&self, old: &El<Msg>, new: &El<Msg>, _: &web_sys::Node) {
+                self.log.borrow_mut().push(format!(
+                    "update {} -> {}",
+                    old.children.len(),
+                    new.children.len()
+                ));
+            }
+            fn remove(&self, old: &El<Msg>, _: &web_sys::Node) {
+                self.log
+                    .borrow_mut()
+                    .push(format!("remove {}", old.children.len()));
+            }
+        }
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let app = App::build(|_, _| Init::new(Model {}), |_, _, _| (), |_| seed::empty())
+            .mount(util::body())
+            .renderer(
+                "mock",
+                MockRenderer {
+                    log: Rc::clone(&log),
+                },
+            )
+            .finish();
+        let mailbox = Mailbox::new(|_msg: Msg| {});
+
+        let doc = util::document();
+        let parent = doc.create_element("div").unwrap();
+
+        let mut vdom = call_patch(
+            &doc,
+            &parent,
+            &mailbox,
+            seed::empty(),
+            div![canvas![rendered_by("mock"), p!["a"]]],
+            &app,
+        );
+        // The host is rendered by Seed, its children by the renderer.
+        assert_eq!(parent.inner_html(), "<div><canvas></canvas></div>");
+
+        vdom = call_patch(
+            &doc,
+            &parent,
+            &mailbox,
+            vdom,
+            div![canvas![rendered_by("mock"), p!["a"], p!["b"]]],
+            &app,
+        );
+        vdom = call_patch(&doc, &parent, &mailbox, vdom, div![canvas![p!["c"]]], &app);
+        assert_eq!(parent.inner_html(), "<div><canvas><p>c</p></canvas></div>");
+
+        vdom = call_patch(
+            &doc,
+            &parent,
+            &mailbox,
+            vdom,
+            div![canvas![rendered_by("mock"), p!["d"]]],
+            &app,
+        );
+        assert_eq!(parent.inner_html(), "<div><canvas></canvas></div>");
+        call_patch(&doc, &parent, &mailbox, vdom, div![], &app);
+
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                "create 1",
+                "update 1 -> 2",
+                "remove 2",
+                "create 1",
+                "remove 1"
+            ]
+        );
+    }
+
     /// Tests an update() function that repeatedly sends messages or performs commands.
     #[wasm_bindgen_test(async)]
     async fn update_promises() {
@@ -710,7 +1084,7 @@ pub mod tests {
                 model.counters.messages_sent += 1;
             }
             if model.counters.commands_scheduled < MESSAGES_TO_SEND {
-                orders.perform_cmd(future::ok(Msg::CommandPerformed));
+                orders.perform_cmd(future::ready(Msg::CommandPerformed));
                 model.counters.commands_scheduled += 1;
             }
 
@@ -752,4 +1126,4670 @@ pub mod tests {
             })
             .expect("test_value_sender.send probably wasn't called!");
     }
+
+    /// Tests that a message loop doesn't freeze the browser - the queue is drained in chunks.
+    #[wasm_bindgen_test(async)]
+    async fn message_loop_is_deferred() {
+        // ARRANGE
+
+        let (test_value_sender, test_value_receiver) = futures::channel::oneshot::channel::<i32>();
+
+        const BUDGET: usize = 100;
+        const MESSAGES_TO_PROCESS: i32 = 250;
+
+        #[derive(Default)]
+        struct Model {
+            messages_received: i32,
+            framework_errors: Vec<FrameworkError>,
+            test_value_sender: Option<futures::channel::oneshot::Sender<i32>>,
+        }
+        #[derive(Clone)]
+        enum Msg {
+            Ping,
+            Pong,
+            FrameworkError(FrameworkError),
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            orders.skip();
+            if let Msg::FrameworkError(error) = msg {
+                model.framework_errors.push(error);
+                return;
+            }
+            model.messages_received += 1;
+
+            if model.messages_received == MESSAGES_TO_PROCESS {
+                model
+                    .test_value_sender
+                    .take()
+                    .unwrap()
+                    .send(model.messages_received)
+                    .unwrap();
+                return;
+            }
+            match msg {
+                Msg::Ping => orders.send_msg(Msg::Pong),
+                Msg::Pong => orders.send_msg(Msg::Ping),
+                Msg::FrameworkError(_) => unreachable!(),
+            };
+        }
+
+        let app = App::build(
+            |_, _| {
+                Init::new(Model {
+                    test_value_sender: Some(test_value_sender),
+                    ..Default::default()
+                })
+            },
+            update,
+            |_| seed::empty(),
+        )
+        .mount(seed::body())
+        .queue_budget(QueueBudget {
+            max_messages: BUDGET,
+            on_exceeded: OnQueueBudgetExceeded::Defer,
+        })
+        .framework_errors(|error| Some(Msg::FrameworkError(error)))
+        .finish()
+        .run();
+
+        // ACT
+        app.update(Msg::Ping);
+
+        // ASSERT
+        assert_eq!(
+            app.data.model.borrow().as_ref().unwrap().messages_received,
+            BUDGET as i32
+        );
+        let messages_received = test_value_receiver
+            .await
+            .expect("test_value_sender.send probably wasn't called!");
+        assert_eq!(messages_received, MESSAGES_TO_PROCESS);
+
+        let model = app.data.model.borrow();
+        match model.as_ref().unwrap().framework_errors.first() {
+            Some(FrameworkError::QueueBudgetExceeded(report)) => {
+                assert!(report.contains("Message queue budget (100 messages) exceeded"));
+                assert!(
+                    report.contains("x50"),
+                    "Ping and Pong alternate: {}",
+                    report
+                );
+            }
+            error => panic!("The diagnostic hasn't been delivered: {:?}", error),
+        }
+    }
+
+    /// Tests that the output of `perform_cmd_notify` reaches all subscribers
+    /// through their proxies, in the subscription order.
+    #[wasm_bindgen_test(async)]
+    async fn command_notifies_subscribers() {
+        #[derive(Clone)]
+        struct UserRefreshed(&'static str);
+
+        struct Model {
+            _subs: Vec<SubHandle>,
+            received: Rc<RefCell<Vec<String>>>,
+        }
+        enum Msg {
+            Refresh,
+            Header(UserRefreshed),
+            Settings(SettingsMsg),
+        }
+        enum SettingsMsg {
+            Page(UserRefreshed),
+            Permissions(PermissionsMsg),
+        }
+        enum PermissionsMsg {
+            Gate(UserRefreshed),
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            let mut received = model.received.borrow_mut();
+            match msg {
+                Msg::Refresh => {
+                    orders.proxy(Msg::Settings).perform_cmd_notify(async {
+                        NextTick::new().await;
+                        UserRefreshed("ann")
+                    });
+                }
+                Msg::Header(UserRefreshed(name)) => received.push(format!("header {}", name)),
+                Msg::Settings(SettingsMsg::Page(UserRefreshed(name))) => {
+                    received.push(format!("settings {}", name))
+                }
+                Msg::Settings(SettingsMsg::Permissions(PermissionsMsg::Gate(UserRefreshed(
+                    name,
+                )))) => received.push(format!("permissions {}", name)),
+            }
+        }
+
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let app = App::build(
+            {
+                let received = Rc::clone(&received);
+                move |_, orders| {
+                    let subs = vec![
+                        orders.subscribe(Msg::Header),
+                        orders.proxy(Msg::Settings).subscribe(SettingsMsg::Page),
+                        orders
+                            .proxy(Msg::Settings)
+                            .proxy(SettingsMsg::Permissions)
+                            .subscribe(PermissionsMsg::Gate),
+                    ];
+                    Init::new(Model {
+                        _subs: subs,
+                        received,
+                    })
+                }
+            },
+            update,
+            |_| seed::empty(),
+        )
+        .mount(seed::body())
+        .finish()
+        .run();
+
+        app.update(Msg::Refresh);
+        for _ in 0..5 {
+            NextTick::new().await;
+        }
+
+        assert_eq!(
+            *received.borrow(),
+            vec!["header ann", "settings ann", "permissions ann"]
+        );
+    }
+
+    /// Tests that outputs of commands and `after_next_render` callbacks of a component
+    /// nested in two levels of `proxy_with` reach the component's `update`.
+    #[wasm_bindgen_test(async)]
+    async fn nested_proxies_map_commands() {
+        struct Model {
+            rows: Vec<Vec<String>>,
+        }
+        enum Msg {
+            List(ListMsg),
+        }
+        enum ListMsg {
+            Row(usize, RowMsg),
+        }
+        enum RowMsg {
+            Load,
+            Loaded(&'static str),
+            Rendered,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::List(list_msg) => {
+                    list_update(list_msg, &mut model.rows, &mut orders.proxy_with(Msg::List))
+                }
+            }
+        }
+        fn list_update(msg: ListMsg, rows: &mut [Vec<String>], orders: &mut impl Orders<ListMsg>) {
+            match msg {
+                ListMsg::Row(id, row_msg) => row_update(
+                    row_msg,
+                    &mut rows[id],
+                    &mut orders.proxy_with(move |row_msg| ListMsg::Row(id, row_msg)),
+                ),
+            }
+        }
+        fn row_update(msg: RowMsg, log: &mut Vec<String>, orders: &mut impl Orders<RowMsg>) {
+            match msg {
+                RowMsg::Load => {
+                    orders
+                        .perform_cmd(async {
+                            NextTick::new().await;
+                            RowMsg::Loaded("data")
+                        })
+                        .after_next_render(|_| RowMsg::Rendered);
+                }
+                RowMsg::Loaded(data) => log.push(data.to_owned()),
+                RowMsg::Rendered => log.push("rendered".to_owned()),
+            }
+        }
+
+        let app = App::build(
+            |_, _| {
+                Init::new(Model {
+                    rows: vec![Vec::new(), Vec::new()],
+                })
+            },
+            update,
+            |_| seed::empty(),
+        )
+        .mount(seed::body())
+        .finish()
+        .run();
+
+        app.update(Msg::List(ListMsg::Row(1, RowMsg::Load)));
+        app.flush_render();
+        for _ in 0..5 {
+            NextTick::new().await;
+        }
+
+        assert_eq!(
+            app.data.model.borrow().as_ref().unwrap().rows,
+            vec![
+                Vec::<String>::new(),
+                vec!["rendered".to_owned(), "data".to_owned()]
+            ]
+        );
+    }
+
+    /// Tests that messages of canceled commands don't reach `update`.
+    #[wasm_bindgen_test(async)]
+    async fn canceled_command_is_not_dispatched() {
+        struct Model {
+            handles: Vec<CmdHandle>,
+            received: Rc<RefCell<Vec<u32>>>,
+        }
+        #[derive(Clone)]
+        enum Msg {
+            Fetch(u32),
+            CancelFirst,
+            Fetched(u32),
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            orders.skip();
+            match msg {
+                Msg::Fetch(id) => {
+                    let cmd = async move {
+                        NextTick::new().await;
+                        Msg::Fetched(id)
+                    };
+                    model.handles.push(orders.perform_cmd_with_handle(cmd));
+                }
+                Msg::CancelFirst => model.handles.remove(0).cancel(),
+                Msg::Fetched(id) => model.received.borrow_mut().push(id),
+            }
+        }
+
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let app = App::build(
+            {
+                let received = Rc::clone(&received);
+                move |_, _| {
+                    Init::new(Model {
+                        handles: Vec::new(),
+                        received,
+                    })
+                }
+            },
+            update,
+            |_| seed::empty(),
+        )
+        .mount(seed::body())
+        .finish()
+        .run();
+
+        app.update(Msg::Fetch(1));
+        app.update(Msg::Fetch(2));
+        app.update(Msg::CancelFirst);
+        for _ in 0..5 {
+            NextTick::new().await;
+        }
+
+        assert_eq!(*received.borrow(), vec![2]);
+
+        // Dropping the handle of a finished command is a no-op.
+        app.update(Msg::CancelFirst);
+        NextTick::new().await;
+        assert_eq!(*received.borrow(), vec![2]);
+    }
+
+    /// Tests that `streams::interval` stops ticking when its handle is dropped
+    /// and that `cmds::timeout` sends its message.
+    #[wasm_bindgen_test]
+    async fn timers() {
+        use std::time::Duration;
+
+        struct Model {
+            interval: Option<StreamHandle>,
+            received: Rc<RefCell<Vec<Msg>>>,
+        }
+        #[derive(Clone, Debug, PartialEq)]
+        enum Msg {
+            Start,
+            Stop,
+            Tick,
+            TimedOut,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Start => {
+                    model.interval =
+                        Some(orders.stream_with_handle(streams::interval(10, || Msg::Tick)));
+                    orders.perform_cmd(cmds::timeout(5, || Msg::TimedOut));
+                }
+                Msg::Stop => model.interval = None,
+                Msg::Tick | Msg::TimedOut => model.received.borrow_mut().push(msg),
+            }
+        }
+
+        let clock = MockClock::new();
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let app = App::build(
+            {
+                let received = Rc::clone(&received);
+                move |_, _| {
+                    Init::new(Model {
+                        interval: None,
+                        received,
+                    })
+                }
+            },
+            update,
+            // Ticks rerender the view - the interval has to keep ticking.
+            |model| div![model.received.borrow().len().to_string()],
+        )
+        .mount(util::document().create_element("div").unwrap())
+        .clock(clock.clone())
+        .finish()
+        .run();
+        let next_ticks = || async {
+            for _ in 0..5 {
+                NextTick::new().await;
+            }
+        };
+
+        app.update(Msg::Start);
+        clock.advance(Duration::from_millis(5));
+        next_ticks().await;
+        assert_eq!(*received.borrow(), vec![Msg::TimedOut]);
+
+        clock.advance(Duration::from_millis(25));
+        next_ticks().await;
+        assert_eq!(
+            *received.borrow(),
+            vec![Msg::TimedOut, Msg::Tick, Msg::Tick, Msg::Tick]
+        );
+
+        // The stream with its timer is dropped when its task is polled again.
+        app.update(Msg::Stop);
+        next_ticks().await;
+        assert_eq!(clock.pending_timers(), 0);
+        clock.advance(Duration::from_millis(50));
+        next_ticks().await;
+        assert_eq!(received.borrow().len(), 4);
+        app.unmount();
+    }
+
+    /// Tests that animation frame streams share the frame with scheduled renders
+    /// and stop when their handles are dropped.
+    #[wasm_bindgen_test]
+    async fn animation_frame_stream() {
+        struct Model {
+            frames: Option<StreamHandle>,
+            received: Rc<RefCell<Vec<RenderInfo>>>,
+        }
+        enum Msg {
+            Start,
+            Stop,
+            Frame(RenderInfo),
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Start => {
+                    model.frames = Some(orders.request_animation_frame_stream(Msg::Frame));
+                }
+                Msg::Stop => model.frames = None,
+                Msg::Frame(info) => model.received.borrow_mut().push(info),
+            }
+        }
+
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let app = App::build(
+            {
+                let received = Rc::clone(&received);
+                move |_, _| {
+                    Init::new(Model {
+                        frames: None,
+                        received,
+                    })
+                }
+            },
+            update,
+            |model| div![model.received.borrow().len().to_string()],
+        )
+        .mount(seed::body())
+        .finish()
+        .run();
+
+        app.update(Msg::Start);
+        // The render and the stream wait for the same frame.
+        assert!(app.data.render_scheduled.get());
+        assert_eq!(app.data.animation_frame_senders.borrow().len(), 1);
+        cmds::timeout(100, || ()).await;
+
+        let frames = received.borrow().len();
+        assert!(frames > 1, "frames: {}", frames);
+        assert_eq!(received.borrow()[0].timestamp_delta, None);
+        assert!(received.borrow()[1].timestamp_delta.is_some());
+        assert!(app.data.scheduled_render_handle.borrow().is_some());
+
+        app.update(Msg::Stop);
+        let received_count = received.borrow().len();
+        cmds::timeout(50, || ()).await;
+        assert_eq!(received.borrow().len(), received_count);
+        assert!(app.data.animation_frame_senders.borrow().is_empty());
+        assert!(app.data.scheduled_render_handle.borrow().is_none());
+
+        app.unmount();
+    }
+
+    /// Tests that lane commands are started in the submission order within the lane's limit
+    /// and that waiting commands aren't started after their handles are dropped.
+    #[wasm_bindgen_test]
+    async fn cmd_lanes() {
+        use futures::channel::oneshot;
+        use std::collections::HashMap;
+
+        struct Model {
+            receivers: Vec<Option<oneshot::Receiver<()>>>,
+            handles: HashMap<usize, CmdHandle>,
+            started: Rc<RefCell<Vec<usize>>>,
+            done: Vec<usize>,
+            stats: LaneStats,
+        }
+        #[derive(Clone)]
+        enum Msg {
+            Submit(usize),
+            Cancel(usize),
+            ReadStats,
+            Done(usize),
+        }
+
+        fn lane() -> Lane {
+            Lane::named("test", 2)
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Submit(id) => {
+                    let receiver = model.receivers[id].take().unwrap();
+                    let started = Rc::clone(&model.started);
+                    let cmd = async move {
+                        started.borrow_mut().push(id);
+                        receiver.await.unwrap();
+                        Msg::Done(id)
+                    };
+                    let handle = orders.perform_cmd_in_lane_with_handle(&lane(), cmd);
+                    model.handles.insert(id, handle);
+                }
+                Msg::Cancel(id) => {
+                    model.handles.remove(&id);
+                }
+                Msg::ReadStats => model.stats = orders.lane_stats(&lane()),
+                Msg::Done(id) => model.done.push(id),
+            }
+        }
+
+        let (senders, receivers): (Vec<_>, Vec<_>) =
+            (0..5).map(|_| oneshot::channel::<()>()).unzip();
+        let started = Rc::new(RefCell::new(Vec::new()));
+        let app = App::build(
+            {
+                let started = Rc::clone(&started);
+                move |_, _| {
+                    Init::new(Model {
+                        receivers: receivers.into_iter().map(Some).collect(),
+                        handles: HashMap::new(),
+                        started,
+                        done: Vec::new(),
+                        stats: LaneStats::default(),
+                    })
+                }
+            },
+            update,
+            |_| seed::empty(),
+        )
+        .mount(seed::body())
+        .finish()
+        .run();
+
+        let stats = || {
+            app.update(Msg::ReadStats);
+            app.data.model.borrow().as_ref().unwrap().stats
+        };
+        let next_ticks = || async {
+            for _ in 0..5 {
+                NextTick::new().await;
+            }
+        };
+
+        for id in 0..5 {
+            app.update(Msg::Submit(id));
+        }
+        next_ticks().await;
+        assert_eq!(*started.borrow(), vec![0, 1]);
+        assert_eq!(
+            stats(),
+            LaneStats {
+                running: 2,
+                queued: 3
+            }
+        );
+
+        // The waiting command is removed from the queue.
+        app.update(Msg::Cancel(3));
+        assert_eq!(
+            stats(),
+            LaneStats {
+                running: 2,
+                queued: 2
+            }
+        );
+
+        let mut senders = senders.into_iter().map(Some).collect::<Vec<_>>();
+        for id in &[1, 0, 2, 4] {
+            senders[*id].take().unwrap().send(()).unwrap();
+            next_ticks().await;
+        }
+        assert_eq!(*started.borrow(), vec![0, 1, 2, 4]);
+        assert_eq!(
+            app.data.model.borrow().as_ref().unwrap().done,
+            vec![1, 0, 2, 4]
+        );
+        assert_eq!(stats(), LaneStats::default());
+    }
+
+    /// Tests that `App::unmount` drops waiting lane commands and results of running commands.
+    #[wasm_bindgen_test]
+    async fn unmount_drops_cmd_results() {
+        use futures::channel::oneshot;
+
+        struct Model {
+            received: Rc<RefCell<Vec<u32>>>,
+        }
+
+        fn update(msg: u32, model: &mut Model, _: &mut impl Orders<u32>) {
+            model.received.borrow_mut().push(msg)
+        }
+
+        let started = Rc::new(RefCell::new(Vec::new()));
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let app = App::build(
+            {
+                let received = Rc::clone(&received);
+                move |_, _| Init::new(Model { received })
+            },
+            update,
+            |_| seed::empty(),
+        )
+        .mount(seed::body())
+        .finish()
+        .run();
+
+        let (sender, receiver) = oneshot::channel::<()>();
+        {
+            let mut orders = seed::app::OrdersContainer::new(app.clone());
+            let lane = Lane::named("unmount", 1);
+            let mut receiver = Some(receiver);
+            for id in 0..2 {
+                let started = Rc::clone(&started);
+                let receiver = receiver.take();
+                orders.perform_cmd_in_lane(&lane, async move {
+                    started.borrow_mut().push(id);
+                    if let Some(receiver) = receiver {
+                        receiver.await.unwrap();
+                    }
+                    id
+                });
+            }
+        }
+        NextTick::new().await;
+        NextTick::new().await;
+        assert_eq!(*started.borrow(), vec![0]);
+
+        app.unmount();
+        sender.send(()).unwrap();
+        for _ in 0..5 {
+            NextTick::new().await;
+        }
+        assert_eq!(*started.borrow(), vec![0]);
+        assert!(received.borrow().is_empty());
+
+        // Ignored with a warning.
+        app.update(2);
+        assert!(received.borrow().is_empty());
+    }
+
+    /// Tests that global messages of canceled global commands don't reach `sink`.
+    #[wasm_bindgen_test(async)]
+    async fn canceled_global_command_is_not_sunk() {
+        struct Model {
+            handles: Vec<CmdHandle>,
+            received: Rc<RefCell<Vec<u32>>>,
+        }
+        #[derive(Clone)]
+        enum Msg {
+            Fetch(u32),
+            CancelFirst,
+        }
+        #[derive(Clone)]
+        enum GMsg {
+            Fetched(u32),
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+            orders.skip();
+            match msg {
+                Msg::Fetch(id) => {
+                    let g_cmd = async move {
+                        NextTick::new().await;
+                        Ok(GMsg::Fetched(id))
+                    };
+                    model.handles.push(orders.perform_g_cmd_with_handle(g_cmd));
+                }
+                Msg::CancelFirst => model.handles.remove(0).cancel(),
+            }
+        }
+
+        fn sink(g_msg: GMsg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+            orders.skip();
+            match g_msg {
+                GMsg::Fetched(id) => model.received.borrow_mut().push(id),
+            }
+        }
+
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let app = App::build(
+            {
+                let received = Rc::clone(&received);
+                move |_, _| {
+                    Init::new(Model {
+                        handles: Vec::new(),
+                        received,
+                    })
+                }
+            },
+            update,
+            |_| seed::empty(),
+        )
+        .mount(seed::body())
+        .sink(sink)
+        .finish()
+        .run();
+
+        app.update(Msg::Fetch(1));
+        app.update(Msg::Fetch(2));
+        app.update(Msg::CancelFirst);
+        for _ in 0..5 {
+            NextTick::new().await;
+        }
+
+        assert_eq!(*received.borrow(), vec![2]);
+    }
+
+    /// Tests that commands can resolve to `Ms`, `Option<Ms>`, `()` and `Result<Ms, Ms>`
+    /// and that only their messages reach `update` - also through `orders.proxy`.
+    #[wasm_bindgen_test(async)]
+    async fn command_outputs() {
+        struct Model {
+            received: Rc<RefCell<Vec<String>>>,
+        }
+        #[derive(Clone)]
+        enum Msg {
+            Start,
+            Done(&'static str),
+            Child(ChildMsg),
+        }
+        #[derive(Clone)]
+        enum ChildMsg {
+            Done,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            orders.skip();
+            match msg {
+                Msg::Start => {
+                    orders
+                        .perform_cmd(async { Msg::Done("msg") })
+                        .perform_cmd(async { Some(Msg::Done("some")) })
+                        .perform_cmd(async { None::<Msg> })
+                        .perform_cmd(async {})
+                        .perform_cmd(future::ok::<_, Msg>(Msg::Done("ok")))
+                        .perform_cmd(future::err::<Msg, _>(Msg::Done("err")));
+                    orders
+                        .proxy(Msg::Child)
+                        .perform_cmd(async { Some(ChildMsg::Done) });
+                }
+                Msg::Done(output) => model.received.borrow_mut().push(output.to_owned()),
+                Msg::Child(ChildMsg::Done) => model.received.borrow_mut().push("child".to_owned()),
+            }
+        }
+
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let app = App::build(
+            {
+                let received = Rc::clone(&received);
+                move |_, _| Init::new(Model { received })
+            },
+            update,
+            |_| seed::empty(),
+        )
+        .mount(seed::body())
+        .finish()
+        .run();
+
+        app.update(Msg::Start);
+        for _ in 0..5 {
+            NextTick::new().await;
+        }
+
+        assert_eq!(
+            *received.borrow(),
+            vec!["msg", "some", "ok", "err", "child"]
+        );
+    }
+
+    /// Tests that stream items are sent to `update` until the stream's handle is dropped.
+    #[wasm_bindgen_test(async)]
+    async fn stream_items_until_handle_is_dropped() {
+        struct Model {
+            handle: Option<StreamHandle>,
+            received: Rc<RefCell<Vec<(&'static str, u32)>>>,
+        }
+        #[derive(Clone)]
+        enum Msg {
+            Canceled(u32),
+            Endless(u32),
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            orders.skip();
+            match msg {
+                Msg::Canceled(item) => {
+                    model.received.borrow_mut().push(("canceled", item));
+                    if item == 2 {
+                        model.handle = None;
+                    }
+                }
+                Msg::Endless(item) => model.received.borrow_mut().push(("endless", item)),
+            }
+        }
+
+        let (canceled_sender, canceled_receiver) = futures::channel::mpsc::unbounded();
+        let (endless_sender, endless_receiver) = futures::channel::mpsc::unbounded();
+        // Buffered before the streams start.
+        for item in 1..=3 {
+            canceled_sender.unbounded_send(item).unwrap();
+            endless_sender.unbounded_send(item).unwrap();
+        }
+
+        let received = Rc::new(RefCell::new(Vec::new()));
+        App::build(
+            {
+                let received = Rc::clone(&received);
+                move |_, orders| {
+                    let handle = orders.stream_with_handle(canceled_receiver.map(Msg::Canceled));
+                    orders.stream(endless_receiver.map(Msg::Endless));
+                    Init::new(Model {
+                        handle: Some(handle),
+                        received,
+                    })
+                }
+            },
+            update,
+            |_| seed::empty(),
+        )
+        .mount(seed::body())
+        .finish()
+        .run();
+
+        for _ in 0..5 {
+            NextTick::new().await;
+        }
+        canceled_sender.unbounded_send(4).unwrap_err();
+        endless_sender.unbounded_send(4).unwrap();
+        for _ in 0..5 {
+            NextTick::new().await;
+        }
+
+        let received = received.borrow();
+        let items = |stream| {
+            received
+                .iter()
+                .filter(|(name, _)| *name == stream)
+                .map(|(_, item)| *item)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(items("canceled"), vec![1, 2]);
+        assert_eq!(items("endless"), vec![1, 2, 3, 4]);
+    }
+
+    /// Tests that commands failed with network errors while offline are performed again
+    /// on the `online` event, that other failures aren't retried and that parked commands
+    /// are canceled by their handles and by `unmount`.
+    #[wasm_bindgen_test]
+    async fn failed_commands_are_retried_when_online() {
+        fn set_online(on_line: bool) {
+            let navigator = js_sys::Reflect::get(&util::window(), &"navigator".into()).unwrap();
+            let descriptor = js_sys::Object::new();
+            js_sys::Reflect::set(&descriptor, &"value".into(), &on_line.into()).unwrap();
+            js_sys::Reflect::set(&descriptor, &"configurable".into(), &true.into()).unwrap();
+            js_sys::Reflect::define_property(
+                navigator.unchecked_ref::<js_sys::Object>(),
+                &"onLine".into(),
+                &descriptor,
+            )
+            .unwrap();
+        }
+        fn dispatch_online() {
+            set_online(true);
+            let event = web_sys::Event::new("online").unwrap();
+            util::window().dispatch_event(&event).unwrap();
+        }
+
+        #[derive(Default)]
+        struct Model {
+            log: Vec<String>,
+            sync_handle: Option<CmdHandle>,
+        }
+        enum Msg {
+            Start,
+            CancelSync,
+            Saved(u32),
+            SaveFailed(u32),
+            LoadFailed,
+            Synced,
+            SyncFailed,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Start => {
+                    let attempts = Rc::new(Cell::new(0));
+                    orders.retry_when_online(move || {
+                        attempts.set(attempts.get() + 1);
+                        if attempts.get() == 1 {
+                            future::err(CmdFailure::Network(Msg::SaveFailed(attempts.get())))
+                        } else {
+                            future::ok(Msg::Saved(attempts.get()))
+                        }
+                    });
+                    orders.retry_when_online(|| future::err(CmdFailure::Other(Msg::LoadFailed)));
+                    model.sync_handle = Some(orders.retry_when_online_with_handle(|| {
+                        future::ready(if util::is_online() {
+                            Ok(Msg::Synced)
+                        } else {
+                            Err(CmdFailure::Network(Msg::SyncFailed))
+                        })
+                    }));
+                }
+                Msg::CancelSync => model.sync_handle = None,
+                Msg::Saved(attempts) => model.log.push(format!("saved {}", attempts)),
+                Msg::SaveFailed(attempts) => model.log.push(format!("save failed {}", attempts)),
+                Msg::LoadFailed => model.log.push("load failed".to_owned()),
+                Msg::Synced => model.log.push("synced".to_owned()),
+                Msg::SyncFailed => model.log.push("sync failed".to_owned()),
+            }
+        }
+
+        let next_ticks = || async {
+            for _ in 0..5 {
+                NextTick::new().await;
+            }
+        };
+        let pending = |app: &App<Msg, Model, Node<Msg>>| app.data.offline_retries.borrow().len();
+        let log = |app: &App<Msg, Model, Node<Msg>>| {
+            app.data.model.borrow().as_ref().unwrap().log.clone()
+        };
+
+        set_online(false);
+        let app = App::build(
+            |_, _| Init::new(Model::default()),
+            update,
+            |_| seed::empty(),
+        )
+        .mount(util::document().create_element("div").unwrap())
+        .finish()
+        .run();
+
+        app.update(Msg::Start);
+        next_ticks().await;
+        assert_eq!(
+            log(&app),
+            vec!["save failed 1", "load failed", "sync failed"]
+        );
+        assert_eq!(pending(&app), 2);
+
+        // Canceled by its handle.
+        app.update(Msg::CancelSync);
+        assert_eq!(pending(&app), 1);
+
+        dispatch_online();
+        next_ticks().await;
+        assert_eq!(
+            log(&app),
+            vec!["save failed 1", "load failed", "sync failed", "saved 2"]
+        );
+        assert_eq!(pending(&app), 0);
+
+        // Dropped by `unmount` together with the `online` listener.
+        set_online(false);
+        app.update(Msg::Start);
+        next_ticks().await;
+        assert_eq!(pending(&app), 2);
+        app.unmount();
+        assert_eq!(pending(&app), 0);
+        dispatch_online();
+        next_ticks().await;
+        assert_eq!(log(&app).len(), 7);
+    }
+
+    /// Tests that a deferred subtree is rendered and interactive once it's hydrated.
+    #[wasm_bindgen_test]
+    fn lazy_hydration() {
+        const KEY: &str = "lazy-hydration-test";
+
+        #[derive(Default)]
+        struct Model {
+            log: Vec<String>,
+        }
+        #[derive(Clone)]
+        enum Msg {
+            Render,
+            Hydrate,
+            Hydrated(String),
+            Clicked,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Render => (),
+                Msg::Hydrate => {
+                    orders.force_hydrate(KEY);
+                }
+                Msg::Hydrated(key) => model.log.push(format!("hydrated {}", key)),
+                Msg::Clicked => model.log.push("clicked".to_owned()),
+            }
+            orders.force_render_now();
+        }
+
+        fn view(_: &Model) -> Node<Msg> {
+            div![
+                // Push the deferred section below the fold.
+                div![style! {St::Height => px(10_000)}],
+                hydrate_lazy(KEY, || button![
+                    attrs! {At::Id => "lazy-hydration-button"},
+                    ev(Ev::Click, |_| Msg::Clicked)
+                ]),
+            ]
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+
+        let app = App::build(
+            |_, orders| {
+                orders.subscribe_lazy_hydration(Msg::Hydrated);
+                Init::new(Model::default())
+            },
+            update,
+            view,
+        )
+        .mount(mount_point.clone())
+        .finish()
+        .run();
+        app.update(Msg::Render);
+
+        let button = || util::document().get_element_by_id("lazy-hydration-button");
+        assert!(button().is_none());
+        assert_eq!(
+            mount_point
+                .query_selector(&format!("[{}]", lazy_hydration::LAZY_HYDRATION_ATTR))
+                .unwrap()
+                .unwrap()
+                .get_attribute(lazy_hydration::LAZY_HYDRATION_ATTR),
+            Some(KEY.to_owned())
+        );
+
+        app.update(Msg::Hydrate);
+        button()
+            .expect("deferred subtree hasn't been hydrated")
+            .dyn_into::<web_sys::HtmlElement>()
+            .unwrap()
+            .click();
+
+        assert_eq!(
+            app.data.model.borrow().as_ref().unwrap().log,
+            vec![format!("hydrated {}", KEY), "clicked".to_owned()]
+        );
+
+        app.unmount();
+        util::body().remove_child(&mount_point).unwrap();
+    }
+
+    /// Tests that server markup in the place of a deferred subtree is kept until it's hydrated
+    /// and that hydrated keys aren't shared by apps.
+    #[wasm_bindgen_test]
+    fn lazy_hydration_adopts_server_markup() {
+        const KEY: &str = "lazy-hydration-server-markup-test";
+
+        #[derive(Clone)]
+        enum Msg {
+            Render,
+            Hydrate,
+        }
+
+        fn update(msg: Msg, _: &mut (), orders: &mut impl Orders<Msg>) {
+            if let Msg::Hydrate = msg {
+                orders.force_hydrate(KEY);
+            }
+            orders.force_render_now();
+        }
+
+        fn view(_: &()) -> Node<Msg> {
+            div![
+                // Push the deferred section below the fold.
+                div![style! {St::Height => px(10_000)}],
+                hydrate_lazy(KEY, || section!["Rendered comments"]),
+            ]
+        }
+
+        let mount = |html: &str| {
+            let mount_point = util::document().create_element("div").unwrap();
+            mount_point.set_inner_html(html);
+            util::body().append_child(&mount_point).unwrap();
+            let app = App::build(
+                |_, _| Init {
+                    mount_type: MountType::Hydrate,
+                    ..Init::new(())
+                },
+                update,
+                view,
+            )
+            .mount(mount_point.clone())
+            .finish()
+            .run();
+            app.update(Msg::Render);
+            (app, mount_point)
+        };
+        let server_html =
+            "<div><div style=\"height: 10000px;\"></div><section>Server comments</section></div>";
+
+        let (app, mount_point) = mount(server_html);
+        let section = mount_point.query_selector("section").unwrap().unwrap();
+        assert_eq!(section.text_content().unwrap(), "Server comments");
+        assert_eq!(
+            section.get_attribute(lazy_hydration::LAZY_HYDRATION_ATTR),
+            Some(KEY.to_owned())
+        );
+
+        app.update(Msg::Hydrate);
+        let hydrated_section = mount_point.query_selector("section").unwrap().unwrap();
+        assert!(!hydrated_section.is_same_node(Some(section.as_ref())));
+        assert_eq!(
+            hydrated_section.text_content().unwrap(),
+            "Rendered comments"
+        );
+
+        // The key has been hydrated only in the first app.
+        let (other_app, other_mount_point) = mount(server_html);
+        let other_section = other_mount_point
+            .query_selector("section")
+            .unwrap()
+            .unwrap();
+        assert_eq!(other_section.text_content().unwrap(), "Server comments");
+
+        app.unmount();
+        other_app.unmount();
+        util::body().remove_child(&mount_point).unwrap();
+        util::body().remove_child(&other_mount_point).unwrap();
+    }
+
+    /// Tests that exactly one link is active after each navigation, even when renders are skipped.
+    #[wasm_bindgen_test]
+    fn active_links() {
+        struct Model;
+        #[derive(Clone)]
+        enum Msg {
+            UrlChanged,
+            Render,
+        }
+
+        fn update(msg: Msg, _: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::UrlChanged => {
+                    orders.skip();
+                }
+                Msg::Render => {
+                    orders.force_render_now();
+                }
+            }
+        }
+
+        fn view(_: &Model) -> Node<Msg> {
+            nav![
+                a![
+                    attrs! {At::Href => "/"},
+                    active_link(LinkMatch::Exact),
+                    "Home"
+                ],
+                a![
+                    attrs! {At::Href => "/blog"},
+                    class!["link"],
+                    active_link(LinkMatch::Prefix),
+                    "Blog"
+                ],
+                a![
+                    attrs! {At::Href => "/about"},
+                    active_when(|url| url.path.first().map(String::as_str) == Some("about"))
+                        .class("current"),
+                    "About"
+                ],
+            ]
+        }
+
+        let original_url = util::window().location().href().unwrap();
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(|_, _| Init::new(Model), update, view)
+            .mount(mount_point.clone())
+            .routes(|_| Some(Msg::UrlChanged))
+            .finish()
+            .run();
+
+        // Returns `href`s of links with `aria-current` and checks their classes.
+        let active_links = || {
+            let links = mount_point.query_selector_all("a").unwrap();
+            (0..links.length())
+                .map(|index| links.item(index).unwrap().dyn_into::<Element>().unwrap())
+                .filter(|link| {
+                    link.get_attribute("aria-current")
+                        .map_or(false, |value| value == "page")
+                })
+                .map(|link| {
+                    let href = link.get_attribute("href").unwrap();
+                    let class = link.get_attribute("class");
+                    (href, class)
+                })
+                .collect::<Vec<_>>()
+        };
+        let inactive_classes = || {
+            let links = mount_point
+                .query_selector_all("a:not([aria-current])")
+                .unwrap();
+            (0..links.length())
+                .filter_map(|index| {
+                    links
+                        .item(index)
+                        .unwrap()
+                        .dyn_into::<Element>()
+                        .unwrap()
+                        .get_attribute("class")
+                })
+                .collect::<Vec<_>>()
+        };
+        let click = |href: &str| {
+            mount_point
+                .query_selector(&format!("a[href=\"{}\"]", href))
+                .unwrap()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlElement>()
+                .unwrap()
+                .click()
+        };
+
+        click("/blog");
+        assert_eq!(
+            active_links(),
+            vec![("/blog".to_owned(), Some("link active".to_owned()))]
+        );
+        assert!(inactive_classes().is_empty());
+
+        click("/about");
+        assert_eq!(
+            active_links(),
+            vec![("/about".to_owned(), Some("current".to_owned()))]
+        );
+        assert_eq!(inactive_classes(), vec!["link".to_owned()]);
+
+        // The render keeps the state.
+        app.update(Msg::Render);
+        assert_eq!(
+            active_links(),
+            vec![("/about".to_owned(), Some("current".to_owned()))]
+        );
+
+        click("/");
+        assert_eq!(
+            active_links(),
+            vec![("/".to_owned(), Some("active".to_owned()))]
+        );
+        assert_eq!(inactive_classes(), vec!["link".to_owned()]);
+
+        util::history()
+            .replace_state_with_url(&JsValue::NULL, "", Some(&original_url))
+            .unwrap();
+        util::body().remove_child(&mount_point).unwrap();
+    }
+
+    /// Tests that `after_next_render` callbacks are executed once after the next render
+    /// and that their messages (if any) are dispatched.
+    #[wasm_bindgen_test]
+    fn after_next_render_callbacks() {
+        struct Model {
+            log: Rc<RefCell<Vec<String>>>,
+        }
+        #[derive(Clone)]
+        enum Msg {
+            Schedule,
+            Rendered(bool),
+            Child(ChildMsg),
+        }
+        #[derive(Clone)]
+        enum ChildMsg {
+            Rendered,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Schedule => {
+                    let log = Rc::clone(&model.log);
+                    orders
+                        .after_next_render(|info| Msg::Rendered(info.timestamp_delta.is_some()))
+                        .after_next_render(move |_| log.borrow_mut().push("unit".to_owned()))
+                        .after_next_render(|_| None::<Msg>)
+                        .proxy(Msg::Child)
+                        .after_next_render(|_| ChildMsg::Rendered);
+                }
+                Msg::Rendered(has_delta) => model
+                    .log
+                    .borrow_mut()
+                    .push(format!("rendered {}", has_delta)),
+                Msg::Child(ChildMsg::Rendered) => {
+                    model.log.borrow_mut().push("child".to_owned());
+                }
+            }
+        }
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(
+            {
+                let log = Rc::clone(&log);
+                move |_, _| Init::new(Model { log })
+            },
+            update,
+            |_| seed::empty(),
+        )
+        .mount(mount_point.clone())
+        .finish()
+        .run();
+        app.flush_render();
+
+        app.update(Msg::Schedule);
+        assert!(log.borrow().is_empty());
+        app.flush_render();
+        assert_eq!(
+            *log.borrow(),
+            // `()` callbacks are executed during the render, messages are dispatched after it.
+            vec![
+                "unit".to_owned(),
+                "rendered true".to_owned(),
+                "child".to_owned()
+            ]
+        );
+
+        // Callbacks are executed only once.
+        app.update(Msg::Rendered(false));
+        app.flush_render();
+        assert_eq!(log.borrow().len(), 4);
+        util::body().remove_child(&mount_point).unwrap();
+    }
+
+    /// Tests that callbacks registered while handling a callback's message wait
+    /// for the following render and that they receive the render timestamps.
+    #[wasm_bindgen_test]
+    fn after_next_render_reregistration() {
+        #[derive(Default)]
+        struct Model {
+            timestamps: Vec<f64>,
+        }
+        #[derive(Clone)]
+        enum Msg {
+            Rendered(RenderInfo),
+            Render,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Rendered(info) => {
+                    model.timestamps.push(info.timestamp);
+                    orders.after_next_render(Msg::Rendered).skip();
+                }
+                Msg::Render => (),
+            }
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(
+            |_, orders| {
+                orders.after_next_render(Msg::Rendered);
+                Init::new(Model::default())
+            },
+            update,
+            |_| seed::empty(),
+        )
+        .mount(mount_point.clone())
+        .finish()
+        .run();
+        let timestamps = || app.data.model.borrow().as_ref().unwrap().timestamps.clone();
+
+        app.flush_render();
+        assert_eq!(timestamps().len(), 1);
+        app.flush_render();
+        assert_eq!(timestamps().len(), 1);
+
+        app.update(Msg::Render);
+        app.flush_render();
+        let timestamps = timestamps();
+        assert_eq!(timestamps.len(), 2);
+        assert!(timestamps[1] >= timestamps[0]);
+        assert_eq!(app.data.render_timestamp.get(), Some(timestamps[1]));
+
+        app.unmount();
+        mount_point.remove();
+    }
+
+    /// Tests the documented order of effects around `force_render_now` - the render waits
+    /// for the queued messages - and that effects left
+    /// in the queue (e.g. by a panicking render) are processed by the next `update`.
+    #[wasm_bindgen_test]
+    fn effect_queue_order() {
+        struct Model {
+            log: Rc<RefCell<Vec<&'static str>>>,
+        }
+        #[derive(Clone)]
+        enum Msg {
+            ForceRender,
+            Queued,
+            Rendered,
+            Leftover,
+            Next,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            let entry = match msg {
+                Msg::ForceRender => {
+                    orders
+                        .send_msg(Msg::Queued)
+                        .after_next_render(|_| Msg::Rendered)
+                        .force_render_now();
+                    "force render"
+                }
+                Msg::Queued => "queued",
+                Msg::Rendered => "after render",
+                Msg::Leftover => "leftover",
+                Msg::Next => "next",
+            };
+            model.log.borrow_mut().push(entry);
+        }
+
+        fn view(model: &Model) -> Node<Msg> {
+            model.log.borrow_mut().push("view");
+            seed::empty()
+        }
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(
+            {
+                let log = Rc::clone(&log);
+                move |_, _| Init::new(Model { log })
+            },
+            update,
+            view,
+        )
+        .mount(mount_point.clone())
+        .finish()
+        .run();
+        app.flush_render();
+        log.borrow_mut().clear();
+
+        app.update(Msg::ForceRender);
+        assert_eq!(
+            *log.borrow(),
+            vec!["force render", "queued", "view", "after render"]
+        );
+        log.borrow_mut().clear();
+
+        // Simulate effects left by a render which panicked.
+        app.data
+            .effect_queue
+            .borrow_mut()
+            .push_back(crate::app::Effect::Msg(Msg::Leftover));
+        app.update(Msg::Next);
+        assert_eq!(*log.borrow(), vec!["leftover", "next"]);
+        assert!(app.data.effect_queue.borrow().is_empty());
+        util::body().remove_child(&mount_point).unwrap();
+    }
+
+    /// Tests that notifications from one component reach subscribers in another one
+    /// until their handles are dropped.
+    #[wasm_bindgen_test]
+    fn subscribe_and_notify() {
+        #[derive(Clone)]
+        struct Saved(u32);
+
+        struct Model {
+            sub: Option<SubHandle>,
+            received: Rc<RefCell<Vec<u32>>>,
+        }
+        #[derive(Clone)]
+        enum Msg {
+            Subscribe,
+            Unsubscribe,
+            Editor(EditorMsg),
+            Saved(u32),
+        }
+        #[derive(Clone)]
+        enum EditorMsg {
+            Save(u32),
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Subscribe => model.sub = Some(orders.subscribe(|Saved(id)| Msg::Saved(id))),
+                Msg::Unsubscribe => model.sub = None,
+                Msg::Editor(msg) => editor_update(msg, &mut orders.proxy(Msg::Editor)),
+                Msg::Saved(id) => model.received.borrow_mut().push(id),
+            }
+        }
+
+        fn editor_update(msg: EditorMsg, orders: &mut impl Orders<EditorMsg>) {
+            match msg {
+                EditorMsg::Save(id) => {
+                    orders.notify(Saved(id));
+                }
+            }
+        }
+
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(
+            {
+                let received = Rc::clone(&received);
+                move |_, _| {
+                    Init::new(Model {
+                        sub: None,
+                        received,
+                    })
+                }
+            },
+            update,
+            |_| seed::empty(),
+        )
+        .mount(mount_point.clone())
+        .finish()
+        .run();
+
+        // No subscribers.
+        app.update(Msg::Editor(EditorMsg::Save(1)));
+        app.update(Msg::Subscribe);
+        app.update(Msg::Editor(EditorMsg::Save(2)));
+        app.update(Msg::Unsubscribe);
+        app.update(Msg::Editor(EditorMsg::Save(3)));
+
+        assert_eq!(*received.borrow(), vec![2]);
+        util::body().remove_child(&mount_point).unwrap();
+    }
+
+    /// Tests that one notification reaches subscribers in components with different message types.
+    #[wasm_bindgen_test]
+    fn notify_reaches_subscribers_of_all_components() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct UserLoggedIn {
+            name: String,
+        }
+
+        #[derive(Default)]
+        struct Model {
+            sub_handles: Vec<SubHandle>,
+            header_user: Option<String>,
+            sidebar_greetings: Vec<String>,
+        }
+        enum Msg {
+            Subscribe,
+            LogIn(&'static str),
+            Header(HeaderMsg),
+            Sidebar(SidebarMsg),
+        }
+        enum HeaderMsg {
+            UserChanged(UserLoggedIn),
+        }
+        enum SidebarMsg {
+            Greet(String),
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Subscribe => {
+                    model.sub_handles = vec![
+                        orders.proxy(Msg::Header).subscribe(HeaderMsg::UserChanged),
+                        orders
+                            .proxy(Msg::Sidebar)
+                            .subscribe(|user: UserLoggedIn| SidebarMsg::Greet(user.name)),
+                    ];
+                }
+                Msg::LogIn(name) => {
+                    orders.notify(UserLoggedIn {
+                        name: name.to_owned(),
+                    });
+                }
+                Msg::Header(HeaderMsg::UserChanged(user)) => model.header_user = Some(user.name),
+                Msg::Sidebar(SidebarMsg::Greet(name)) => model.sidebar_greetings.push(name),
+            }
+        }
+
+        let app = App::build(|_, _| Init::new(Model::default()), update, |_| empty![])
+            .mount(util::document().create_element("div").unwrap())
+            .finish()
+            .run();
+        app.update(Msg::Subscribe);
+        app.update(Msg::LogIn("Alice"));
+
+        {
+            let model = app.data.model.borrow();
+            let model = model.as_ref().unwrap();
+            assert_eq!(model.header_user, Some("Alice".to_owned()));
+            assert_eq!(model.sidebar_greetings, vec!["Alice".to_owned()]);
+        }
+        app.unmount();
+    }
+
+    /// Tests that toggles between renders resolve to the final state and that `close_ev`
+    /// receives `returnValue`.
+    #[wasm_bindgen_test]
+    fn dialog_open_state() {
+        struct Model {
+            open: bool,
+            closed: Rc<RefCell<Vec<Option<String>>>>,
+        }
+        #[derive(Clone)]
+        enum Msg {
+            Toggle,
+            Closed(Option<String>),
+        }
+
+        fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Toggle => model.open = !model.open,
+                Msg::Closed(return_value) => {
+                    model.open = false;
+                    model.closed.borrow_mut().push(return_value);
+                }
+            }
+        }
+
+        fn view(model: &Model) -> Node<Msg> {
+            dialog![dialog_open(model.open), close_ev(Msg::Closed), "Dialog"]
+        }
+
+        let closed = Rc::new(RefCell::new(Vec::new()));
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(
+            {
+                let closed = Rc::clone(&closed);
+                move |_, _| {
+                    Init::new(Model {
+                        open: false,
+                        closed,
+                    })
+                }
+            },
+            update,
+            view,
+        )
+        .mount(mount_point.clone())
+        .finish()
+        .run();
+        app.flush_render();
+        let dialog = mount_point.query_selector("dialog").unwrap().unwrap();
+
+        app.update(Msg::Toggle);
+        app.update(Msg::Toggle);
+        app.update(Msg::Toggle);
+        app.flush_render();
+        assert!(dialog.matches(":modal").unwrap());
+
+        js_sys::Reflect::set(&dialog, &"returnValue".into(), &"ok".into()).unwrap();
+        dialog
+            .dispatch_event(&web_sys::Event::new("close").unwrap())
+            .unwrap();
+        assert_eq!(*closed.borrow(), vec![Some("ok".to_owned())]);
+
+        app.flush_render();
+        assert!(!dialog.has_attribute("open"));
+        util::body().remove_child(&mount_point).unwrap();
+    }
+
+    /// Tests that the taken over server-rendered text and the text rendered by the app
+    /// are collapsed the same way and that the first render doesn't replace the text nodes.
+    #[wasm_bindgen_test]
+    fn text_normalization_with_takeover() {
+        struct Model;
+
+        fn view(_: &Model) -> Node<Msg> {
+            div![
+                "\n    Hello ",
+                b!["dear\n    reader"],
+                ",\n    welcome",
+                pre!["  keep\n  this"],
+            ]
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        mount_point.set_inner_html(
+            "<div>\n    Hello <b>dear\n    reader</b>,\n    welcome<pre>  keep\n  this</pre></div>",
+        );
+        util::body().append_child(&mount_point).unwrap();
+        let app: App<Msg, Model, Node<Msg>> = App::build(
+            |_, _| Init {
+                mount_type: MountType::Takeover,
+                ..Init::new(Model)
+            },
+            |_, _, _| (),
+            view,
+        )
+        .mount(mount_point.clone())
+        .text_normalization(TextNormalization::Collapse)
+        .finish()
+        .run();
+
+        let expected = "<div>Hello <b>dear reader</b>, welcome<pre>  keep\n  this</pre></div>";
+        assert_eq!(mount_point.inner_html(), expected);
+
+        let hello = mount_point.first_child().unwrap().first_child().unwrap();
+        app.flush_render();
+        assert_eq!(mount_point.inner_html(), expected);
+        assert!(mount_point
+            .first_child()
+            .unwrap()
+            .first_child()
+            .unwrap()
+            .is_same_node(Some(&hello)));
+        util::body().remove_child(&mount_point).unwrap();
+    }
+
+    /// Tests that `MountType::Takeover` reuses the server-rendered nodes,
+    /// removes formatting whitespace and patches the differences from the `view`.
+    #[wasm_bindgen_test]
+    fn takeover_reuses_server_rendered_nodes() {
+        struct Model;
+
+        fn view(_: &Model) -> Node<Msg> {
+            div![
+                class!["app"],
+                h1!["Title"],
+                ul![li!["A"], li!["B"], li!["C"]],
+                video![attrs! {At::Src => "movie.mp4"}],
+            ]
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        mount_point.set_inner_html(
+            "<div class=\"app\">\n  <h1>Title</h1>\n  <ul><li>A</li><li>X</li></ul>\n  \
+             <video src=\"movie.mp4\"></video>\n</div>",
+        );
+        util::body().append_child(&mount_point).unwrap();
+        let root = mount_point.first_element_child().unwrap();
+        let title = root.query_selector("h1").unwrap().unwrap();
+        let video = root.query_selector("video").unwrap().unwrap();
+
+        let app: App<Msg, Model, Node<Msg>> = App::build(
+            |_, _| Init {
+                mount_type: MountType::Takeover,
+                ..Init::new(Model)
+            },
+            |_, _, _| (),
+            view,
+        )
+        .mount(mount_point.clone())
+        .finish()
+        .run();
+        app.flush_render();
+
+        let expected = "<div class=\"app\"><h1>Title</h1><ul><li>A</li><li>B</li><li>C</li></ul>\
+                        <video src=\"movie.mp4\"></video></div>";
+        assert_eq!(mount_point.inner_html(), expected);
+        assert!(mount_point
+            .first_child()
+            .unwrap()
+            .is_same_node(Some(root.as_ref())));
+        assert!(root
+            .query_selector("h1")
+            .unwrap()
+            .unwrap()
+            .is_same_node(Some(title.as_ref())));
+        assert!(root
+            .query_selector("video")
+            .unwrap()
+            .unwrap()
+            .is_same_node(Some(video.as_ref())));
+        util::body().remove_child(&mount_point).unwrap();
+    }
+
+    /// Tests that `MountType::Hydrate` reuses the matching server-rendered nodes
+    /// and recreates children with a different structure.
+    #[wasm_bindgen_test]
+    fn hydrate_reuses_matching_nodes() {
+        struct Model;
+
+        fn view(_: &Model) -> Node<Msg> {
+            div![
+                class!["app"],
+                h1!["Title"],
+                p!["Hello ", strong!["world"]],
+                ul![li!["A"], li!["B"]],
+            ]
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        mount_point.set_inner_html(
+            "<div class=\"app\">\n  <h1>Title</h1>\n  <p>Hello <strong>world</strong></p>\n  \
+             <ul><li>A</li><p>B</p></ul>\n</div>",
+        );
+        util::body().append_child(&mount_point).unwrap();
+        let root = mount_point.first_element_child().unwrap();
+        let title = root.query_selector("h1").unwrap().unwrap();
+        let strong = root.query_selector("strong").unwrap().unwrap();
+        let ul = root.query_selector("ul").unwrap().unwrap();
+        let first_li = root.query_selector("li").unwrap().unwrap();
+
+        let app: App<Msg, Model, Node<Msg>> = App::build(
+            |_, _| Init {
+                mount_type: MountType::Hydrate,
+                ..Init::new(Model)
+            },
+            |_, _, _| (),
+            view,
+        )
+        .mount(mount_point.clone())
+        .finish()
+        .run();
+        app.flush_render();
+
+        let expected = "<div class=\"app\"><h1>Title</h1><p>Hello <strong>world</strong></p>\
+                        <ul><li>A</li><li>B</li></ul></div>";
+        assert_eq!(mount_point.inner_html(), expected);
+        let is_reused = |selector: &str, node: &web_sys::Element| {
+            root.query_selector(selector)
+                .unwrap()
+                .unwrap()
+                .is_same_node(Some(node.as_ref()))
+        };
+        assert!(mount_point
+            .first_child()
+            .unwrap()
+            .is_same_node(Some(root.as_ref())));
+        assert!(is_reused("h1", &title));
+        assert!(is_reused("strong", &strong));
+        assert!(is_reused("ul", &ul));
+        // Children of `ul` don't match - they've been recreated.
+        assert!(!is_reused("li", &first_li));
+        util::body().remove_child(&mount_point).unwrap();
+    }
+
+    /// Tests that `<script>` and `<style>` elements created by Seed carry the CSP nonce.
+    #[wasm_bindgen_test]
+    fn csp_nonce() {
+        fn view(_: &()) -> Node<Msg> {
+            div![
+                Script![attrs! {At::Type => "application/ld+json"}, "{}"],
+                Node::Element(El::empty(Tag::Style)),
+                p!["No nonce"],
+            ]
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+
+        let app: App<Msg, (), Node<Msg>> = App::build(|_, _| Init::new(()), |_, _, _| (), view)
+            .mount(mount_point.clone())
+            .csp_nonce("r4nd0m".to_owned())
+            .build_and_start();
+        app.flush_render();
+
+        let elements = mount_point.query_selector_all("script, style, p").unwrap();
+        assert_eq!(elements.length(), 3);
+        // Browsers may hide the attribute's value - read the `nonce` property.
+        let nonces = (0..elements.length())
+            .map(|index| {
+                let element = elements.item(index).unwrap();
+                js_sys::Reflect::get(&element, &"nonce".into())
+                    .unwrap()
+                    .as_string()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(nonces, vec!["r4nd0m", "r4nd0m", ""]);
+
+        app.unmount();
+        util::body().remove_child(&mount_point).unwrap();
+    }
+
+    /// Tests that regions of `view_with_slices` are rendered behind the root view
+    /// and that unchanged regions are reused with their listeners.
+    #[wasm_bindgen_test]
+    fn view_slices() {
+        thread_local! {
+            static CART_VIEWS: Cell<usize> = Cell::new(0);
+        }
+        struct Model {
+            title: String,
+            cart: Vec<String>,
+        }
+        enum Msg {
+            Rename,
+            AddItem,
+        }
+
+        fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Rename => model.title = "Checkout".to_owned(),
+                Msg::AddItem => model.cart.push("Apple".to_owned()),
+            }
+        }
+        fn view(model: &Model) -> Node<Msg> {
+            h1![model.title]
+        }
+        #[allow(clippy::ptr_arg)]
+        fn cart_view(cart: &Vec<String>) -> Node<Msg> {
+            CART_VIEWS.with(|views| views.set(views.get() + 1));
+            div![
+                ul![cart.iter().map(|item| li![item])],
+                button![ev(Ev::Click, |_| Msg::AddItem)],
+            ]
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(
+            |_, _| {
+                Init::new(Model {
+                    title: "Shop".to_owned(),
+                    cart: Vec::new(),
+                })
+            },
+            update,
+            view,
+        )
+        .mount(mount_point.clone())
+        .view_with_slices(|slices| slices.region(|model| &model.cart, cart_view))
+        .finish()
+        .run();
+        app.flush_render();
+
+        let cart_views = || CART_VIEWS.with(Cell::get);
+        let button = mount_point.query_selector("button").unwrap().unwrap();
+        assert_eq!(
+            mount_point.inner_html(),
+            "<h1>Shop</h1><div><ul></ul><button></button></div>"
+        );
+        assert_eq!(cart_views(), 1);
+
+        // The cart hasn't been changed - its region is reused.
+        app.update(Msg::Rename);
+        app.flush_render();
+        assert_eq!(
+            mount_point.inner_html(),
+            "<h1>Checkout</h1><div><ul></ul><button></button></div>"
+        );
+        assert_eq!(cart_views(), 1);
+
+        // Listeners of the reused region still work.
+        button.unchecked_ref::<web_sys::HtmlElement>().click();
+        app.flush_render();
+        assert_eq!(
+            mount_point.inner_html(),
+            "<h1>Checkout</h1><div><ul><li>Apple</li></ul><button></button></div>"
+        );
+        assert_eq!(cart_views(), 2);
+        assert!(mount_point
+            .query_selector("button")
+            .unwrap()
+            .unwrap()
+            .is_same_node(Some(button.as_ref())));
+
+        app.unmount();
+        util::body().remove_child(&mount_point).unwrap();
+    }
+
+    /// Tests that an unchanged region is rendered correctly when the number of nodes
+    /// rendered by the root view shrinks or grows.
+    #[wasm_bindgen_test]
+    fn view_slices_with_changed_root_len() {
+        thread_local! {
+            static FOOTER_VIEWS: Cell<usize> = Cell::new(0);
+        }
+        struct Model {
+            paragraphs: usize,
+            footer: String,
+        }
+        enum Msg {
+            SetParagraphs(usize),
+            Clicked,
+        }
+
+        fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::SetParagraphs(paragraphs) => model.paragraphs = paragraphs,
+                Msg::Clicked => model.footer = "Clicked".to_owned(),
+            }
+        }
+        fn view(model: &Model) -> Vec<Node<Msg>> {
+            (0..model.paragraphs)
+                .map(|index| p![index.to_string()])
+                .collect()
+        }
+        #[allow(clippy::ptr_arg)]
+        fn footer_view(footer: &String) -> Node<Msg> {
+            FOOTER_VIEWS.with(|views| views.set(views.get() + 1));
+            footer![footer, ev(Ev::Click, |_| Msg::Clicked)]
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        let app = App::build(
+            |_, _| {
+                Init::new(Model {
+                    paragraphs: 2,
+                    footer: "Footer".to_owned(),
+                })
+            },
+            update,
+            view,
+        )
+        .mount(mount_point.clone())
+        .view_with_slices(|slices| slices.region(|model| &model.footer, footer_view))
+        .finish()
+        .run();
+        app.flush_render();
+        assert_eq!(
+            mount_point.inner_html(),
+            "<p>0</p><p>1</p><footer>Footer</footer>"
+        );
+
+        // Shrink.
+        app.update(Msg::SetParagraphs(1));
+        app.flush_render();
+        assert_eq!(mount_point.inner_html(), "<p>0</p><footer>Footer</footer>");
+
+        // Grow.
+        app.update(Msg::SetParagraphs(3));
+        app.flush_render();
+        assert_eq!(
+            mount_point.inner_html(),
+            "<p>0</p><p>1</p><p>2</p><footer>Footer</footer>"
+        );
+        let views_before_reuse = FOOTER_VIEWS.with(Cell::get);
+
+        // The root view's length is the same - the region is reused with its listener.
+        app.update(Msg::SetParagraphs(3));
+        app.flush_render();
+        assert_eq!(FOOTER_VIEWS.with(Cell::get), views_before_reuse);
+        mount_point
+            .query_selector("footer")
+            .unwrap()
+            .unwrap()
+            .unchecked_ref::<web_sys::HtmlElement>()
+            .click();
+        app.flush_render();
+        assert_eq!(
+            mount_point.inner_html(),
+            "<p>0</p><p>1</p><p>2</p><footer>Clicked</footer>"
+        );
+
+        app.unmount();
+    }
+
+    /// Tests that `ElRef` is set after a render, updated when its element is recreated
+    /// and cleared when the element is removed.
+    #[wasm_bindgen_test]
+    fn el_refs() {
+        struct Model {
+            input: ElRef<web_sys::HtmlInputElement>,
+            shown: Option<Tag>,
+        }
+        enum Msg {
+            Show(Option<Tag>),
+        }
+
+        fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Show(shown) => model.shown = shown,
+            }
+        }
+
+        fn view(model: &Model) -> Node<Msg> {
+            match &model.shown {
+                Some(Tag::Input) => div![input![el_ref(&model.input)]],
+                Some(_) => div![textarea![el_ref(&model.input)]],
+                None => div![],
+            }
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(
+            |_, _| {
+                Init::new(Model {
+                    input: ElRef::new(),
+                    shown: Some(Tag::Input),
+                })
+            },
+            update,
+            view,
+        )
+        .mount(mount_point.clone())
+        .finish()
+        .run();
+        app.flush_render();
+        let input = app.data.model.borrow().as_ref().unwrap().input.clone();
+
+        let rendered = mount_point.query_selector("input").unwrap().unwrap();
+        let referenced = input.get().expect("rendered input");
+        assert!(rendered.is_same_node(Some(referenced.as_ref())));
+
+        // The element isn't `HtmlInputElement` anymore.
+        app.update(Msg::Show(Some(Tag::TextArea)));
+        app.flush_render();
+        assert!(input.get().is_none());
+        assert!(input.shared_node_ws.borrow().is_some());
+
+        app.update(Msg::Show(Some(Tag::Input)));
+        app.flush_render();
+        let recreated = mount_point.query_selector("input").unwrap().unwrap();
+        assert!(!recreated.is_same_node(Some(rendered.as_ref())));
+        assert!(recreated.is_same_node(Some(input.get().unwrap().as_ref())));
+
+        app.update(Msg::Show(None));
+        app.flush_render();
+        assert!(input.shared_node_ws.borrow().is_none());
+        util::body().remove_child(&mount_point).unwrap();
+    }
+
+    /// Tests that children of a focused `contenteditable` element aren't rewritten while
+    /// the rendered text equals the live text and that the caret survives a programmatic update.
+    #[wasm_bindgen_test]
+    fn focused_content_editable() {
+        use crate::browser::dom::content_editable;
+
+        struct Model {
+            title: String,
+        }
+        enum Msg {
+            Edited(String),
+            Reset,
+        }
+
+        fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Edited(title) => model.title = title,
+                Msg::Reset => model.title = "Hi".to_owned(),
+            }
+        }
+
+        fn view(model: &Model) -> Node<Msg> {
+            h1![
+                attrs! {At::ContentEditable => "true"},
+                content_editable_ev(|content| Msg::Edited(content.text)),
+                model.title,
+            ]
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(
+            |_, _| {
+                Init::new(Model {
+                    title: "Hello".to_owned(),
+                })
+            },
+            update,
+            view,
+        )
+        .mount(mount_point.clone())
+        .finish()
+        .run();
+        app.flush_render();
+
+        let title = mount_point
+            .first_element_child()
+            .unwrap()
+            .unchecked_into::<web_sys::HtmlElement>();
+        title.focus().unwrap();
+        // The browser inserts the typed text as another text node.
+        let typed = util::document().create_text_node(" world");
+        title.append_child(&typed).unwrap();
+        content_editable::restore_caret(&title, 11);
+        title
+            .dispatch_event(&web_sys::Event::new("input").unwrap())
+            .unwrap();
+        app.flush_render();
+
+        assert_eq!(
+            app.data.model.borrow().as_ref().unwrap().title,
+            "Hello world"
+        );
+        assert_eq!(title.child_nodes().length(), 2);
+        assert!(title
+            .last_child()
+            .unwrap()
+            .is_same_node(Some(typed.as_ref())));
+        assert_eq!(content_editable::caret_offset(&title), Some(11));
+
+        app.update(Msg::Reset);
+        app.flush_render();
+        assert_eq!(title.inner_html(), "Hi");
+        assert_eq!(content_editable::caret_offset(&title), Some(2));
+
+        title.blur().unwrap();
+        util::body().remove_child(&mount_point).unwrap();
+    }
+
+    /// Tests that the last call of `render`, `force_render_now` or `skip` wins.
+    #[wasm_bindgen_test]
+    fn last_should_render_call_wins() {
+        use crate::app::{orders::OrdersContainer, ShouldRender};
+
+        let mut orders = OrdersContainer::new(create_app());
+        orders.render().skip();
+        assert_eq!(orders.should_render, ShouldRender::Skip);
+        orders.skip().force_render_now();
+        assert_eq!(orders.should_render, ShouldRender::ForceRenderNow);
+        orders.proxy(identity).skip();
+        assert_eq!(orders.should_render, ShouldRender::Skip);
+        orders.skip().proxy(identity).render();
+        assert_eq!(orders.should_render, ShouldRender::Render);
+    }
+
+    /// Tests that the DOM isn't patched when `skip` is called after `render`.
+    #[wasm_bindgen_test]
+    fn skip_after_render() {
+        struct Model(u32);
+        enum Msg {
+            Skipped,
+            Rendered,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            model.0 += 1;
+            match msg {
+                Msg::Skipped => {
+                    orders.render().proxy(identity).skip();
+                }
+                Msg::Rendered => {
+                    orders.skip().render();
+                }
+            }
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(
+            |_, _| Init::new(Model(0)),
+            update,
+            |model| div![model.0.to_string()],
+        )
+        .mount(mount_point.clone())
+        .finish()
+        .run();
+        app.flush_render();
+        assert_eq!(mount_point.inner_html(), "<div>0</div>");
+
+        app.update(Msg::Skipped);
+        app.flush_render();
+        assert_eq!(mount_point.inner_html(), "<div>0</div>");
+
+        app.update(Msg::Rendered);
+        app.flush_render();
+        assert_eq!(mount_point.inner_html(), "<div>2</div>");
+        util::body().remove_child(&mount_point).unwrap();
+    }
+
+    /// Tests that a locale change rerenders the page even when rendering is skipped,
+    /// that subscribers receive `LocaleChanged` and that apps don't share locales.
+    #[wasm_bindgen_test]
+    fn locale_change_rerenders() {
+        struct Model {
+            _locale_sub: SubHandle,
+            changes: Rc<RefCell<Vec<String>>>,
+        }
+        enum Msg {
+            SetLocale(&'static str),
+            LocaleChanged(Locale),
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            orders.skip();
+            match msg {
+                Msg::SetLocale(tag) => {
+                    orders.set_locale(tag).skip();
+                }
+                Msg::LocaleChanged(locale) => model.changes.borrow_mut().push(locale.to_string()),
+            }
+        }
+
+        let storage_key = "seed-test-app-locale";
+        let storage = crate::browser::service::storage::get_storage().unwrap();
+        storage.remove_item(storage_key).unwrap();
+
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(
+            {
+                let changes = Rc::clone(&changes);
+                move |_, orders| {
+                    Init::new(Model {
+                        _locale_sub: orders
+                            .subscribe(|LocaleChanged(locale)| Msg::LocaleChanged(locale)),
+                        changes,
+                    })
+                }
+            },
+            update,
+            |_| div![locale::current().to_string()],
+        )
+        .mount(mount_point.clone())
+        .locale_opts(LocaleOpts {
+            supported: vec!["en".to_owned(), "cs".to_owned()],
+            fallback: "cs".to_owned(),
+            storage_key: Some(storage_key.to_owned()),
+        })
+        .finish()
+        .run();
+        app.flush_render();
+        let detected = locale::active(&app.cfg.contexts).get().to_string();
+        assert!(detected == "en" || detected == "cs");
+        assert_eq!(mount_point.inner_html(), format!("<div>{}</div>", detected));
+
+        app.update(Msg::SetLocale("en-GB"));
+        app.update(Msg::SetLocale("cs-CZ"));
+        // Invalid tags are ignored.
+        app.update(Msg::SetLocale("c$"));
+        app.flush_render();
+        assert_eq!(mount_point.inner_html(), "<div>cs</div>");
+        assert_eq!(
+            storage.get_item(storage_key).unwrap(),
+            Some("cs".to_owned())
+        );
+        let mut expected = vec!["en".to_owned(), "cs".to_owned()];
+        if detected == "en" {
+            expected.remove(0);
+        }
+        assert_eq!(*changes.borrow(), expected);
+
+        // Each app has its own locale.
+        let other_mount_point = util::document().create_element("div").unwrap();
+        let other_app: App<Msg, Model, Node<Msg>> = App::build(
+            {
+                let changes = Rc::clone(&changes);
+                move |_, orders| {
+                    Init::new(Model {
+                        _locale_sub: orders
+                            .subscribe(|LocaleChanged(locale)| Msg::LocaleChanged(locale)),
+                        changes,
+                    })
+                }
+            },
+            update,
+            |_| div![locale::current().to_string()],
+        )
+        .mount(other_mount_point.clone())
+        .locale_opts(LocaleOpts {
+            supported: vec!["de".to_owned()],
+            fallback: "de".to_owned(),
+            storage_key: None,
+        })
+        .finish()
+        .run();
+        other_app.flush_render();
+        assert_eq!(other_mount_point.inner_html(), "<div>de</div>");
+        app.schedule_render();
+        app.flush_render();
+        assert_eq!(mount_point.inner_html(), "<div>cs</div>");
+        other_app.unmount();
+
+        storage.remove_item(storage_key).unwrap();
+        util::body().remove_child(&mount_point).unwrap();
+    }
+
+    /// Tests that `unmount` removes the app's nodes, invokes `before_unmount`
+    /// and that `update` is a no-op afterwards.
+    #[wasm_bindgen_test]
+    fn unmount() {
+        struct Model {
+            clicks: u32,
+            updates: Rc<RefCell<u32>>,
+        }
+        enum Msg {
+            Clicked,
+        }
+
+        fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Clicked => model.clicks += 1,
+            }
+            *model.updates.borrow_mut() += 1;
+        }
+
+        let updates = Rc::new(RefCell::new(0));
+        let unmounted_clicks = Rc::new(RefCell::new(None));
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(
+            {
+                let updates = Rc::clone(&updates);
+                move |_, _| Init::new(Model { clicks: 0, updates })
+            },
+            update,
+            |model| div![model.clicks.to_string()],
+        )
+        .mount(mount_point.clone())
+        .before_unmount({
+            let unmounted_clicks = Rc::clone(&unmounted_clicks);
+            move |model| *unmounted_clicks.borrow_mut() = Some(model.clicks)
+        })
+        .finish()
+        .run();
+
+        app.update(Msg::Clicked);
+        app.flush_render();
+        assert_eq!(mount_point.inner_html(), "<div>1</div>");
+
+        // The scheduled render and DOM writes are canceled.
+        let writes = Rc::new(Cell::new(0));
+        scheduler::with_queue(&app.data.dom_ops, || {
+            crate::dom_write({
+                let writes = Rc::clone(&writes);
+                move |_| writes.set(writes.get() + 1)
+            })
+        });
+        app.update(Msg::Clicked);
+        app.unmount();
+        scheduler::flush_queue(&app.data.dom_ops);
+        assert_eq!(writes.get(), 0);
+        assert_eq!(mount_point.inner_html(), "");
+        assert_eq!(*unmounted_clicks.borrow(), Some(2));
+        assert!(app.data.popstate_closure.borrow().is_none());
+        assert!(app.data.hashchange_closure.borrow().is_none());
+        assert!(app.data.scheduled_render_handle.borrow().is_none());
+
+        app.update(Msg::Clicked);
+        app.flush_render();
+        app.unmount();
+        assert_eq!(*updates.borrow(), 2);
+        assert_eq!(mount_point.inner_html(), "");
+        assert_eq!(*unmounted_clicks.borrow(), Some(2));
+        util::body().remove_child(&mount_point).unwrap();
+    }
+
+    /// Tests that `unmount` drops only the app's own scheduled DOM writes.
+    #[wasm_bindgen_test]
+    fn unmount_keeps_dom_writes_of_other_apps() {
+        struct Model {
+            writes: Rc<Cell<u32>>,
+        }
+        struct Msg;
+
+        fn update(_: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
+            let writes = Rc::clone(&model.writes);
+            crate::dom_write(move |_| writes.set(writes.get() + 1));
+        }
+
+        let start_app = |writes: &Rc<Cell<u32>>| {
+            let writes = Rc::clone(writes);
+            App::build(
+                move |_, _| Init::new(Model { writes }),
+                update,
+                |_| div![],
+            )
+            .mount(util::document().create_element("div").unwrap())
+            .finish()
+            .run()
+        };
+        let (writes_a, writes_b) = (Rc::new(Cell::new(0)), Rc::new(Cell::new(0)));
+        let app_a = start_app(&writes_a);
+        let app_b = start_app(&writes_b);
+
+        app_a.update(Msg);
+        app_b.update(Msg);
+        app_a.unmount();
+        app_b.flush_render();
+        assert_eq!(writes_a.get(), 0);
+        assert_eq!(writes_b.get(), 1);
+
+        app_b.unmount();
+    }
+
+    /// Tests that nothing started by the app fires after `unmount`
+    /// and that the browser's scroll restoration is restored.
+    #[wasm_bindgen_test]
+    async fn unmount_releases_timers() {
+        use std::time::Duration;
+
+        #[derive(Clone, Debug, PartialEq)]
+        enum Msg {
+            Start,
+            Tick,
+            TimedOut,
+            NavigationDone,
+            NavigationTimedOut,
+            NavigationPending(bool),
+        }
+
+        fn update(msg: Msg, model: &mut Rc<RefCell<Vec<Msg>>>, orders: &mut impl Orders<Msg>) {
+            if let Msg::Start = msg {
+                orders
+                    .stream(streams::interval(10, || Msg::Tick))
+                    .perform_cmd(cmds::timeout(50, || Msg::TimedOut))
+                    .defer_navigation(
+                        cmds::timeout(500, || Ok(Msg::NavigationDone)),
+                        Duration::from_millis(100),
+                        Msg::NavigationTimedOut,
+                    );
+            }
+            model.borrow_mut().push(msg);
+        }
+
+        let clock = MockClock::new();
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let app = App::build(
+            {
+                let received = Rc::clone(&received);
+                move |_, orders| {
+                    orders.subscribe_navigation_pending(Msg::NavigationPending);
+                    Init::new(received)
+                }
+            },
+            update,
+            |_| seed::empty(),
+        )
+        .mount(util::document().create_element("div").unwrap())
+        .clock(clock.clone())
+        .manage_scroll_restoration(true)
+        .finish()
+        .run();
+        let next_ticks = || async {
+            for _ in 0..5 {
+                NextTick::new().await;
+            }
+        };
+        assert_eq!(
+            util::history().scroll_restoration().unwrap(),
+            web_sys::ScrollRestoration::Manual
+        );
+
+        app.update(Msg::Start);
+        next_ticks().await;
+        assert!(clock.pending_timers() >= 3);
+        let received_before_unmount = received.borrow().clone();
+
+        app.unmount();
+        next_ticks().await;
+        assert_eq!(clock.pending_timers(), 0);
+        assert_eq!(
+            util::history().scroll_restoration().unwrap(),
+            web_sys::ScrollRestoration::Auto
+        );
+
+        clock.advance(Duration::from_secs(1));
+        next_ticks().await;
+        assert_eq!(*received.borrow(), received_before_unmount);
+    }
+
+    /// Tests that links are routed only by the app which owns them.
+    #[wasm_bindgen_test]
+    fn apps_route_own_links() {
+        struct Model<Ms> {
+            href: &'static str,
+            received: Rc<RefCell<Vec<Ms>>>,
+        }
+
+        #[derive(Clone)]
+        enum CartMsg {
+            UrlChanged(Url),
+        }
+        #[derive(Clone)]
+        enum ChatMsg {
+            UrlChanged(Url),
+        }
+
+        fn update<Ms: 'static>(msg: Ms, model: &mut Model<Ms>, _: &mut impl Orders<Ms>) {
+            model.received.borrow_mut().push(msg)
+        }
+
+        fn view<Ms: 'static>(model: &Model<Ms>) -> Node<Ms> {
+            a![attrs! {At::Href => model.href}, "Link"]
+        }
+
+        fn mount_app<Ms: Clone + 'static>(
+            href: &'static str,
+            routes: fn(Url) -> Option<Ms>,
+            received: Rc<RefCell<Vec<Ms>>>,
+        ) -> (App<Ms, Model<Ms>, Node<Ms>>, Element) {
+            let mount_point = util::document().create_element("div").unwrap();
+            util::body().append_child(&mount_point).unwrap();
+            let app = App::build(
+                move |_, _| Init::new(Model { href, received }),
+                update,
+                view,
+            )
+            .mount(mount_point.clone())
+            .routes(routes)
+            .finish()
+            .run();
+            app.flush_render();
+            (app, mount_point)
+        }
+
+        let original_url = util::window().location().href().unwrap();
+        let cart_msgs = Rc::new(RefCell::new(Vec::new()));
+        let chat_msgs = Rc::new(RefCell::new(Vec::new()));
+        let (cart, cart_mount_point) = mount_app(
+            "/cart",
+            |url| Some(CartMsg::UrlChanged(url)),
+            Rc::clone(&cart_msgs),
+        );
+        let (chat, chat_mount_point) = mount_app(
+            "/chat",
+            |url| Some(ChatMsg::UrlChanged(url)),
+            Rc::clone(&chat_msgs),
+        );
+
+        let click = |mount_point: &Element| {
+            mount_point
+                .query_selector("a")
+                .unwrap()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlElement>()
+                .unwrap()
+                .click()
+        };
+        click(&chat_mount_point);
+        click(&cart_mount_point);
+
+        let cart_paths = cart_msgs
+            .borrow()
+            .iter()
+            .map(|CartMsg::UrlChanged(url)| url.path.clone())
+            .collect::<Vec<_>>();
+        let chat_paths = chat_msgs
+            .borrow()
+            .iter()
+            .map(|ChatMsg::UrlChanged(url)| url.path.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(cart_paths, vec![vec!["cart"]]);
+        assert_eq!(chat_paths, vec![vec!["chat"]]);
+
+        cart.unmount();
+        chat.unmount();
+        util::body().remove_child(&cart_mount_point).unwrap();
+        util::body().remove_child(&chat_mount_point).unwrap();
+        util::history()
+            .replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&original_url))
+            .unwrap();
+    }
+
+    /// Tests that messages returned by `App::subscribe` handlers are queued behind the current
+    /// message and that removed subscriptions stop firing, even when they remove themselves.
+    #[wasm_bindgen_test]
+    fn msg_subscriptions() {
+        #[derive(Debug, Clone, PartialEq)]
+        enum Msg {
+            Save,
+            Saved,
+            Other,
+        }
+
+        fn update(msg: Msg, received: &mut Vec<Msg>, orders: &mut impl Orders<Msg>) {
+            if msg == Msg::Save {
+                orders.send_msg(Msg::Other);
+            }
+            received.push(msg);
+        }
+
+        let app = App::build(|_, _| Init::new(Vec::new()), update, |_| seed::empty())
+            .mount(util::document().create_element("div").unwrap())
+            .finish()
+            .run();
+        let received = || app.data.model.borrow().clone().unwrap();
+
+        let handle = app.subscribe(|msg| match msg {
+            Msg::Save => Some(Msg::Saved),
+            _ => None,
+        });
+        let other_handle = app.subscribe(|_| None);
+
+        app.update(Msg::Save);
+        // The follow-up message is queued behind the effects of `update`.
+        assert_eq!(received(), vec![Msg::Save, Msg::Other, Msg::Saved]);
+
+        assert!(app.unsubscribe(handle));
+        assert!(!app.unsubscribe(handle));
+        app.update(Msg::Save);
+        assert_eq!(
+            received(),
+            vec![Msg::Save, Msg::Other, Msg::Saved, Msg::Save, Msg::Other]
+        );
+        assert!(app.unsubscribe(other_handle));
+
+        // Handlers may unsubscribe - e.g. one-shot subscriptions.
+        let one_shot = Rc::new(Cell::new(None));
+        let handle = app.subscribe({
+            let (app, one_shot) = (app.clone(), Rc::clone(&one_shot));
+            move |_| {
+                if let Some(handle) = one_shot.take() {
+                    app.unsubscribe(handle);
+                }
+                Some(Msg::Saved)
+            }
+        });
+        one_shot.set(Some(handle));
+        app.update(Msg::Other);
+        app.update(Msg::Other);
+        assert_eq!(&received()[5..], &[Msg::Other, Msg::Saved, Msg::Other][..]);
+        assert!(!app.unsubscribe(handle));
+    }
+
+    /// Tests that intercepted `GET` forms are routed and other forms are sent
+    /// to `intercept_forms` as messages.
+    #[wasm_bindgen_test]
+    fn intercepted_forms() {
+        #[derive(Debug, Clone)]
+        enum Msg {
+            UrlChanged(Url),
+            Submitted(FormSubmission),
+        }
+
+        fn update(msg: Msg, received: &mut Vec<String>, _: &mut impl Orders<Msg>) {
+            received.push(match msg {
+                Msg::UrlChanged(url) => format!("url {:?} {:?}", url.path, url.search),
+                Msg::Submitted(submission) => format!(
+                    "{:?} {} {}",
+                    submission.method,
+                    submission.action,
+                    submission.url_encoded()
+                ),
+            });
+        }
+
+        fn routes(url: Url) -> Option<Msg> {
+            if url.path == ["search"] {
+                Some(Msg::UrlChanged(url))
+            } else {
+                None
+            }
+        }
+
+        let original_url = util::window().location().href().unwrap();
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(|_, _| Init::new(Vec::new()), update, |_| seed::empty())
+            .mount(mount_point.clone())
+            .routes(routes)
+            .intercept_forms(|submission| match submission.method {
+                crate::Method::Post => Some(Msg::Submitted(submission)),
+                _ => None,
+            })
+            .finish()
+            .run();
+        let received = || app.data.model.borrow().clone().unwrap();
+        let submit = |html: &str| {
+            mount_point.set_inner_html(html);
+            let event = web_sys::Event::new_with_event_init_dict(
+                "submit",
+                web_sys::EventInit::new().bubbles(true).cancelable(true),
+            )
+            .unwrap();
+            mount_point
+                .first_element_child()
+                .unwrap()
+                .dispatch_event(&event)
+                .unwrap();
+            event.default_prevented()
+        };
+
+        // `GET` as navigation.
+        assert!(submit(
+            r#"<form action="/search"><input name="q" value="seed"></form>"#
+        ));
+        // `POST` as message.
+        assert!(submit(
+            r#"<form action="/comments" method="post"><input name="text" value="Hi"></form>"#
+        ));
+        // Neither `routes` nor `intercept_forms` accept it - it's submitted natively
+        // (untrusted events don't submit forms, so the test page isn't left).
+        assert!(!submit(r#"<form action="/other"></form>"#));
+        assert_eq!(
+            received(),
+            vec![
+                r#"url ["search"] Some("q=seed")"#.to_owned(),
+                "Post /comments text=Hi".to_owned(),
+            ]
+        );
+
+        // The listener is removed by `unmount`.
+        app.unmount();
+        assert!(app.data.form_closure.borrow().is_none());
+        assert!(!submit(
+            r#"<form action="/comments" method="post"><input name="text" value="Hi"></form>"#
+        ));
+        util::body().remove_child(&mount_point).unwrap();
+        util::history()
+            .replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&original_url))
+            .unwrap();
+    }
+
+    mod deferred_navigation {
+        use super::*;
+        use futures::FutureExt;
+        use gloo_timers::callback::Timeout;
+        use std::{future::Future, time::Duration};
+
+        #[derive(Default)]
+        struct Model {
+            page: u32,
+            log: Vec<String>,
+        }
+        enum Msg {
+            Navigate {
+                page: u32,
+                load_ms: u32,
+                timeout: Duration,
+            },
+            Loaded(u32),
+            TimedOut(u32),
+            NavigationPending(bool),
+        }
+
+        fn delay(ms: u32) -> impl Future<Output = ()> {
+            let (sender, receiver) = futures::channel::oneshot::channel();
+            Timeout::new(ms, move || sender.send(()).unwrap()).forget();
+            receiver.map(|_| ())
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Navigate {
+                    page,
+                    load_ms,
+                    timeout,
+                } => {
+                    orders.defer_navigation(
+                        delay(load_ms).map(move |_| Ok(Msg::Loaded(page))),
+                        timeout,
+                        Msg::TimedOut(page),
+                    );
+                }
+                Msg::Loaded(page) => {
+                    model.log.push(format!("loaded {}", page));
+                    model.page = page;
+                }
+                Msg::TimedOut(page) => {
+                    model.log.push(format!("timed out {}", page));
+                    model.page = page;
+                }
+                Msg::NavigationPending(pending) => {
+                    model.log.push(format!("pending {}", pending));
+                }
+            }
+        }
+
+        fn create_app(clock: impl Clock + 'static) -> App<Msg, Model, Node<Msg>> {
+            App::build(
+                |_, orders| {
+                    orders.subscribe_navigation_pending(Msg::NavigationPending);
+                    Init::new(Model::default())
+                },
+                update,
+                |_| seed::empty(),
+            )
+            .mount(seed::body())
+            .clock(clock)
+            .finish()
+            .run()
+        }
+
+        /// Tests that the second navigation cancels the first one.
+        #[wasm_bindgen_test(async)]
+        async fn double_navigation() {
+            let app = create_app(MockClock::new());
+
+            app.update(Msg::Navigate {
+                page: 1,
+                load_ms: 50,
+                timeout: Duration::from_secs(1),
+            });
+            app.update(Msg::Navigate {
+                page: 2,
+                load_ms: 10,
+                timeout: Duration::from_secs(1),
+            });
+            assert_eq!(app.data.model.borrow().as_ref().unwrap().page, 0);
+
+            delay(100).await;
+
+            let model = app.data.model.borrow();
+            let model = model.as_ref().unwrap();
+            assert_eq!(model.page, 2);
+            assert_eq!(model.log, vec!["pending true", "loaded 2", "pending false"]);
+        }
+
+        /// Tests that the timeout forces the swap and data are delivered later.
+        #[wasm_bindgen_test(async)]
+        async fn navigation_timeout() {
+            let clock = MockClock::new();
+            let app = create_app(clock.clone());
+
+            app.update(Msg::Navigate {
+                page: 1,
+                load_ms: 50,
+                timeout: Duration::from_millis(10),
+            });
+            clock.advance(Duration::from_millis(9));
+            assert_eq!(app.data.model.borrow().as_ref().unwrap().page, 0);
+
+            clock.advance(Duration::from_millis(1));
+            assert_eq!(app.data.model.borrow().as_ref().unwrap().page, 1);
+            assert_eq!(clock.pending_timers(), 0);
+
+            delay(100).await;
+
+            let model = app.data.model.borrow();
+            let model = model.as_ref().unwrap();
+            assert_eq!(
+                model.log,
+                vec!["pending true", "timed out 1", "pending false", "loaded 1"]
+            );
+        }
+    }
+
+    mod debounced_cmds {
+        use super::*;
+        use futures::FutureExt;
+        use gloo_timers::callback::Timeout;
+        use std::{future::Future, time::Duration};
+
+        #[derive(Default)]
+        struct Model {
+            results: Vec<String>,
+        }
+        enum Msg {
+            Search(&'static str),
+            Found(&'static str),
+        }
+
+        fn delay(ms: u32) -> impl Future<Output = ()> {
+            let (sender, receiver) = futures::channel::oneshot::channel();
+            Timeout::new(ms, move || sender.send(()).unwrap()).forget();
+            receiver.map(|_| ())
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Search(query) => {
+                    orders.perform_cmd_debounced("search", 300, async move { Msg::Found(query) });
+                }
+                Msg::Found(query) => model.results.push(query.to_owned()),
+            }
+        }
+
+        fn create_app(clock: impl Clock + 'static) -> App<Msg, Model, Node<Msg>> {
+            App::build(
+                |_, _| Init::new(Model::default()),
+                update,
+                |_| seed::empty(),
+            )
+            .mount(seed::body())
+            .clock(clock)
+            .finish()
+            .run()
+        }
+
+        /// Tests that only the last command debounced within the delay is started.
+        #[wasm_bindgen_test(async)]
+        async fn only_last_cmd_runs() {
+            let clock = MockClock::new();
+            let app = create_app(clock.clone());
+
+            app.update(Msg::Search("s"));
+            clock.advance(Duration::from_millis(100));
+            app.update(Msg::Search("se"));
+            clock.advance(Duration::from_millis(100));
+            app.update(Msg::Search("see"));
+            clock.advance(Duration::from_millis(299));
+            assert_eq!(clock.pending_timers(), 1);
+
+            clock.advance(Duration::from_millis(1));
+            assert_eq!(clock.pending_timers(), 0);
+            delay(10).await;
+            assert_eq!(
+                app.data.model.borrow().as_ref().unwrap().results,
+                vec!["see"]
+            );
+        }
+
+        /// Tests that `App::unmount` cancels pending debounced commands.
+        #[wasm_bindgen_test(async)]
+        async fn unmount_cancels_cmds() {
+            let clock = MockClock::new();
+            let app = create_app(clock.clone());
+
+            app.update(Msg::Search("seed"));
+            assert_eq!(clock.pending_timers(), 1);
+            app.unmount();
+            assert_eq!(clock.pending_timers(), 0);
+
+            clock.advance(Duration::from_millis(300));
+            delay(10).await;
+            assert!(app.data.model.borrow().as_ref().unwrap().results.is_empty());
+        }
+    }
+
+    /// Tests that apps send `VisibilityResumed` to subscribers.
+    #[wasm_bindgen_test]
+    fn visibility_resumed_is_notified() {
+        use crate::browser::visibility::tests::{reset_hidden, set_hidden};
+        use std::time::Duration;
+
+        struct Model {
+            _sub: SubHandle,
+            resumed: Vec<Duration>,
+        }
+
+        fn update(hidden_for: Duration, model: &mut Model, _: &mut impl Orders<Duration>) {
+            model.resumed.push(hidden_for)
+        }
+
+        let app = App::build(
+            |_, orders| {
+                Init::new(Model {
+                    _sub: orders.subscribe(|VisibilityResumed { hidden_for }| hidden_for),
+                    resumed: Vec::new(),
+                })
+            },
+            update,
+            |_| seed::empty(),
+        )
+        .mount(seed::body())
+        .finish()
+        .run();
+
+        set_hidden(true);
+        assert!(app.data.model.borrow().as_ref().unwrap().resumed.is_empty());
+        set_hidden(false);
+        assert_eq!(app.data.model.borrow().as_ref().unwrap().resumed.len(), 1);
+        reset_hidden();
+        app.unmount();
+    }
+
+    /// Tests that the scroll position is restored after the render which follows `popstate`.
+    #[wasm_bindgen_test]
+    fn popstate_restores_scroll_position() {
+        use wasm_bindgen::JsValue;
+
+        struct Model;
+
+        #[derive(Clone)]
+        struct UrlChanged;
+
+        fn update(_: UrlChanged, _: &mut Model, _: &mut impl Orders<UrlChanged>) {}
+
+        fn view(_: &Model) -> Node<UrlChanged> {
+            div![style! {St::Height => "5000px"}]
+        }
+
+        let original_url = util::window().location().href().unwrap();
+        let window = util::window();
+        let go_to = |href: &str| {
+            util::history()
+                .replace_state_with_url(&JsValue::NULL, "", Some(href))
+                .unwrap();
+        };
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+
+        go_to("/list");
+        let app = App::build(|_, _| Init::new(Model), update, view)
+            .mount(mount_point.clone())
+            .routes(|_| Some(UrlChanged))
+            .manage_scroll_restoration(true)
+            .finish()
+            .run();
+        app.flush_render();
+        window.scroll_to_with_x_and_y(0., 400.);
+
+        seed::push_route(vec!["article"]);
+        app.update(UrlChanged);
+        app.flush_render();
+        assert_eq!(window.scroll_y().unwrap(), 0.);
+
+        // Back button.
+        go_to("/list");
+        window
+            .dispatch_event(&web_sys::PopStateEvent::new("popstate").unwrap())
+            .unwrap();
+        app.flush_render();
+        assert_eq!(window.scroll_y().unwrap(), 400.);
+
+        app.unmount();
+        mount_point.remove();
+        go_to(&original_url);
+    }
+
+    /// Tests that `orders.batch` renders once - even when the messages force renders.
+    #[wasm_bindgen_test]
+    fn batch_renders_once() {
+        thread_local! {
+            static RENDERS: Cell<u32> = Cell::new(0);
+        }
+
+        #[derive(Clone)]
+        enum Msg {
+            Batch,
+            Step,
+        }
+
+        #[derive(Default)]
+        struct Model {
+            steps: u32,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Batch => {
+                    orders.skip().batch(vec![Msg::Step; 3]);
+                }
+                Msg::Step => {
+                    model.steps += 1;
+                    orders.force_render_now();
+                }
+            }
+        }
+
+        fn view(model: &Model) -> Node<Msg> {
+            RENDERS.with(|renders| renders.set(renders.get() + 1));
+            div![model.steps.to_string()]
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(|_, _| Init::new(Model::default()), update, view)
+            .mount(mount_point.clone())
+            .finish()
+            .run();
+        app.flush_render();
+        RENDERS.with(|renders| renders.set(0));
+
+        app.update(Msg::Batch);
+        assert_eq!(RENDERS.with(Cell::get), 1);
+        assert_eq!(app.data.model.borrow().as_ref().unwrap().steps, 3);
+        assert!(app.data.scheduled_render_handle.borrow().is_none());
+        assert_eq!(mount_point.text_content().unwrap(), "3");
+
+        app.unmount();
+        mount_point.remove();
+    }
+
+    /// Tests that a panicking `view` is replaced by the fallback of `view_error_handler`.
+    /// Panics abort without unwinding on the default wasm target.
+    #[cfg(panic = "unwind")]
+    #[wasm_bindgen_test]
+    fn view_error_handler_renders_fallback() {
+        #[derive(Default)]
+        struct Model {
+            broken: bool,
+        }
+        enum Msg {
+            Break,
+            Fix,
+        }
+
+        fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
+            model.broken = match msg {
+                Msg::Break => true,
+                Msg::Fix => false,
+            };
+        }
+
+        fn view(model: &Model) -> Node<Msg> {
+            if model.broken {
+                panic!("broken view");
+            }
+            div!["ok"]
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(|_, _| Init::new(Model::default()), update, view)
+            .mount(mount_point.clone())
+            .view_error_handler(|message| p![message])
+            .finish()
+            .run();
+        app.flush_render();
+        assert_eq!(mount_point.inner_html(), "<div>ok</div>");
+
+        app.update(Msg::Break);
+        app.flush_render();
+        assert_eq!(mount_point.inner_html(), "<p>broken view</p>");
+
+        app.update(Msg::Fix);
+        app.flush_render();
+        assert_eq!(mount_point.inner_html(), "<div>ok</div>");
+
+        app.unmount();
+        mount_point.remove();
+    }
+
+    /// Tests that `view_error_handler` doesn't affect a working view and it receives
+    /// messages of `&str` and `String` panics. It runs on the default (aborting) wasm target.
+    #[wasm_bindgen_test]
+    fn view_error_handler_without_panics() {
+        use crate::app::panic_message;
+
+        thread_local! {
+            static HANDLER_CALLS: Cell<u32> = Cell::new(0);
+        }
+        #[derive(Default)]
+        struct Model {
+            count: u32,
+        }
+        enum Msg {
+            Increment,
+        }
+
+        fn update(_: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
+            model.count += 1;
+        }
+
+        fn view(model: &Model) -> Node<Msg> {
+            div![model.count.to_string()]
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        let app = App::build(|_, _| Init::new(Model::default()), update, view)
+            .mount(mount_point.clone())
+            .view_error_handler(|message| {
+                HANDLER_CALLS.with(|calls| calls.set(calls.get() + 1));
+                p![message]
+            })
+            .finish()
+            .run();
+        app.flush_render();
+        app.update(Msg::Increment);
+        app.flush_render();
+        assert_eq!(mount_point.inner_html(), "<div>1</div>");
+        assert_eq!(HANDLER_CALLS.with(Cell::get), 0);
+
+        app.unmount();
+
+        let payload: Box<dyn std::any::Any + Send> = Box::new("broken view");
+        assert_eq!(panic_message(&*payload), "broken view");
+        let payload: Box<dyn std::any::Any + Send> = Box::new(format!("broken {}", 1));
+        assert_eq!(panic_message(&*payload), "broken 1");
+        let payload: Box<dyn std::any::Any + Send> = Box::new(1);
+        assert_eq!(panic_message(&*payload), "view panicked");
+    }
+
+    /// Tests that effects left in the queue (e.g. by a panic unwinding out of a forced render)
+    /// are processed by the next `update` before its own effects.
+    /// It runs on the default (aborting) wasm target.
+    #[wasm_bindgen_test]
+    fn leftover_queued_effects_are_processed_by_next_update() {
+        use crate::app::Effect;
+
+        #[derive(Default)]
+        struct Model {
+            log: Vec<&'static str>,
+        }
+        enum Msg {
+            Leftover,
+            Next,
+            FollowUp,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Leftover => model.log.push("leftover"),
+                Msg::Next => {
+                    model.log.push("next");
+                    orders.send_msg(Msg::FollowUp);
+                }
+                Msg::FollowUp => model.log.push("follow-up"),
+            }
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        let app = App::build(
+            |_, _| Init::new(Model::default()),
+            update,
+            |_| seed::empty(),
+        )
+        .mount(mount_point)
+        .finish()
+        .run();
+        app.flush_render();
+
+        app.data
+            .effect_queue
+            .borrow_mut()
+            .push_back(Effect::Msg(Msg::Leftover));
+        app.update(Msg::Next);
+
+        assert_eq!(
+            app.data.model.borrow().as_ref().unwrap().log,
+            vec!["leftover", "next", "follow-up"]
+        );
+        assert!(app.data.effect_queue.borrow().is_empty());
+
+        app.unmount();
+    }
+
+    /// Tests that `force_render_now` requested by several messages of one queue drain
+    /// leads to one render.
+    #[wasm_bindgen_test]
+    fn forced_renders_are_combined() {
+        thread_local! {
+            static RENDERS: Cell<u32> = Cell::new(0);
+        }
+        #[derive(Default)]
+        struct Model {
+            steps: u32,
+        }
+        enum Msg {
+            Step(u32),
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            let Msg::Step(remaining) = msg;
+            model.steps += 1;
+            if remaining > 0 {
+                orders.send_msg(Msg::Step(remaining - 1));
+            }
+            orders.force_render_now();
+        }
+
+        fn view(model: &Model) -> Node<Msg> {
+            RENDERS.with(|renders| renders.set(renders.get() + 1));
+            div![model.steps.to_string()]
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(|_, _| Init::new(Model::default()), update, view)
+            .mount(mount_point.clone())
+            .finish()
+            .run();
+        app.flush_render();
+        RENDERS.with(|renders| renders.set(0));
+
+        // A lone forced render is still synchronous.
+        app.update(Msg::Step(0));
+        assert_eq!(RENDERS.with(Cell::get), 1);
+        assert_eq!(mount_point.text_content().unwrap(), "1");
+
+        app.update(Msg::Step(3));
+        assert_eq!(RENDERS.with(Cell::get), 2);
+        assert_eq!(mount_point.text_content().unwrap(), "5");
+
+        app.unmount();
+        mount_point.remove();
+    }
+
+    /// Tests that `render_throttle` postpones and coalesces scheduled renders
+    /// and that `force_render_now` isn't throttled.
+    #[wasm_bindgen_test]
+    fn render_throttle() {
+        use std::time::Duration;
+
+        thread_local! {
+            static RENDERS: Cell<u32> = Cell::new(0);
+        }
+        #[derive(Default)]
+        struct Model {
+            moves: u32,
+        }
+        enum Msg {
+            Throttle(u32),
+            Move,
+            Force,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Throttle(ms) => {
+                    orders.render_throttle(ms).skip();
+                }
+                Msg::Move => model.moves += 1,
+                Msg::Force => {
+                    orders.force_render_now();
+                }
+            }
+        }
+
+        fn view(model: &Model) -> Node<Msg> {
+            RENDERS.with(|renders| renders.set(renders.get() + 1));
+            div![model.moves.to_string()]
+        }
+
+        let clock = MockClock::new();
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(|_, _| Init::new(Model::default()), update, view)
+            .mount(mount_point.clone())
+            .clock(clock.clone())
+            .finish()
+            .run();
+        app.flush_render();
+        RENDERS.with(|renders| renders.set(0));
+        app.update(Msg::Throttle(100));
+
+        // The last render happened just now - the render waits for the window's end.
+        app.update(Msg::Move);
+        app.update(Msg::Move);
+        app.update(Msg::Move);
+        assert!(!app.data.render_scheduled.get());
+        assert_eq!(clock.pending_timers(), 1);
+
+        clock.advance(Duration::from_millis(99));
+        assert!(!app.data.render_scheduled.get());
+        clock.advance(Duration::from_millis(1));
+        assert!(app.data.render_scheduled.get());
+        app.flush_render();
+        assert_eq!(RENDERS.with(Cell::get), 1);
+        assert_eq!(mount_point.text_content().unwrap(), "3");
+        // The fired timer has been dropped by the render.
+        assert!(app.data.throttled_render.borrow().is_none());
+
+        // Forced renders ignore the throttle and cancel the postponed render.
+        app.update(Msg::Move);
+        app.update(Msg::Force);
+        assert_eq!(RENDERS.with(Cell::get), 2);
+        assert_eq!(clock.pending_timers(), 0);
+
+        // Renders requested after the window are scheduled immediately.
+        clock.advance(Duration::from_millis(100));
+        app.update(Msg::Move);
+        assert!(app.data.render_scheduled.get());
+        assert_eq!(clock.pending_timers(), 0);
+
+        app.update(Msg::Throttle(0));
+        app.unmount();
+        mount_point.remove();
+    }
+
+    #[wasm_bindgen_test]
+    async fn websocket_messages_reach_update() {
+        #[derive(Default)]
+        struct Model {
+            ws: Option<WebSocket>,
+            received: Vec<WebSocketMessage>,
+            closed: Option<CloseEvent>,
+        }
+        enum Msg {
+            Connect,
+            Send(&'static str),
+            Received(WebSocketMessage),
+            Closed(CloseEvent),
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Connect => {
+                    model.ws = Some(
+                        orders
+                            .open_websocket(
+                                "ws://example.com/ws",
+                                WebSocketHandlers::new()
+                                    .on_message(Msg::Received)
+                                    .on_close(Msg::Closed),
+                            )
+                            .unwrap(),
+                    );
+                }
+                Msg::Send(text) => model.ws.as_ref().unwrap().send_text(text).unwrap(),
+                Msg::Received(message) => model.received.push(message),
+                Msg::Closed(close_event) => model.closed = Some(close_event),
+            }
+        }
+
+        let app = App::build(|_, _| Init::new(Model::default()), update, |_| empty![])
+            .mount(util::document().create_element("div").unwrap())
+            .finish()
+            .run();
+
+        // The socket is opened by a loopback mock - it receives its own frames (asynchronously,
+        // like from a server) and nothing is sent to the network.
+        let global = js_sys::global();
+        let real_web_socket = js_sys::Reflect::get(&global, &"WebSocket".into()).unwrap();
+        let mock_web_socket = js_sys::Function::new_no_args(
+            "return class extends EventTarget {
+                constructor(url) {
+                    super();
+                    this.url = url;
+                    this.readyState = 1;
+                }
+                send(data) {
+                    const event = new MessageEvent('message', { data });
+                    Promise.resolve().then(() => this.dispatchEvent(event));
+                }
+                close() {
+                    this.readyState = 2;
+                }
+                dispatchEvent(event) {
+                    const handler = this['on' + event.type];
+                    if (handler) { handler(event); }
+                    return super.dispatchEvent(event);
+                }
+            }",
+        )
+        .call0(&JsValue::NULL)
+        .unwrap();
+        js_sys::Reflect::set(&global, &"WebSocket".into(), &mock_web_socket).unwrap();
+        app.update(Msg::Connect);
+        js_sys::Reflect::set(&global, &"WebSocket".into(), &real_web_socket).unwrap();
+
+        let socket = app
+            .data
+            .model
+            .borrow()
+            .as_ref()
+            .unwrap()
+            .ws
+            .as_ref()
+            .unwrap()
+            .raw_socket()
+            .clone();
+
+        app.update(Msg::Send("ping"));
+        NextTick::new().await;
+        assert_eq!(
+            app.data.model.borrow().as_ref().unwrap().received,
+            vec![WebSocketMessage::Text("ping".to_owned())]
+        );
+
+        // The close code and reason are exposed to `update`.
+        let close_init = js_sys::Object::new();
+        js_sys::Reflect::set(&close_init, &"code".into(), &4000.into()).unwrap();
+        js_sys::Reflect::set(&close_init, &"reason".into(), &"restart".into()).unwrap();
+        let close_event =
+            web_sys::CloseEvent::new_with_event_init_dict("close", close_init.unchecked_ref())
+                .unwrap();
+        socket.dispatch_event(&close_event).unwrap();
+        assert_eq!(
+            app.data.model.borrow().as_ref().unwrap().closed,
+            Some(CloseEvent {
+                code: 4000,
+                reason: "restart".to_owned(),
+                was_clean: false,
+            })
+        );
+
+        // Dropping the handle detaches handlers and closes the socket.
+        app.data.model.borrow_mut().as_mut().unwrap().ws = None;
+        assert_eq!(socket.ready_state(), web_sys::WebSocket::CLOSING);
+        socket.send_with_str("pong").unwrap();
+        NextTick::new().await;
+        assert_eq!(app.data.model.borrow().as_ref().unwrap().received.len(), 1);
+        app.unmount();
+    }
+
+    #[wasm_bindgen_test]
+    fn window_and_document_event_subscriptions() {
+        #[derive(Default)]
+        struct Model {
+            handles: Vec<EventHandle>,
+            events: Vec<String>,
+        }
+        enum Msg {
+            Subscribe,
+            Unsubscribe,
+            Event(String),
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Subscribe => {
+                    model.handles = vec![
+                        orders.subscribe_window_event(Ev::Online, |event| {
+                            if event.cancelable() {
+                                None
+                            } else {
+                                Some(Msg::Event(format!("window {}", event.type_())))
+                            }
+                        }),
+                        orders.subscribe_document_event(Ev::SelectionChange, |event| {
+                            Msg::Event(format!("document {}", event.type_()))
+                        }),
+                    ];
+                }
+                Msg::Unsubscribe => model.handles.clear(),
+                Msg::Event(event) => model.events.push(event),
+            }
+        }
+
+        let app = App::build(|_, _| Init::new(Model::default()), update, |_| empty![])
+            .mount(util::body())
+            .finish()
+            .run();
+        let dispatch = |target: &web_sys::EventTarget, event_name, cancelable: bool| {
+            let mut init = web_sys::EventInit::new();
+            init.cancelable(cancelable);
+            let event = web_sys::Event::new_with_event_init_dict(event_name, &init).unwrap();
+            target.dispatch_event(&event).unwrap();
+        };
+        let events = || app.data.model.borrow().as_ref().unwrap().events.clone();
+
+        dispatch(&util::window(), "online", false);
+        assert!(events().is_empty());
+
+        app.update(Msg::Subscribe);
+        dispatch(&util::window(), "online", false);
+        // The handler's `None` is ignored.
+        dispatch(&util::window(), "online", true);
+        dispatch(&util::document(), "selectionchange", false);
+        assert_eq!(events(), vec!["window online", "document selectionchange"]);
+
+        // Dropped handles remove listeners.
+        app.update(Msg::Unsubscribe);
+        dispatch(&util::window(), "online", false);
+        dispatch(&util::document(), "selectionchange", false);
+        assert_eq!(events().len(), 2);
+        app.unmount();
+    }
+
+    /// Only changed attributes, class tokens and style properties are written to DOM.
+    #[wasm_bindgen_test]
+    fn attributes_class_and_style_patched_incrementally() {
+        let app = create_app();
+        let mailbox = Mailbox::new(|_msg: Msg| {});
+        let doc = util::document();
+        let parent = doc.create_element("div").unwrap();
+
+        let classes = |toggled: bool| {
+            let mut classes = (0..20)
+                .map(|index| format!("class-{}", index))
+                .collect::<Vec<_>>();
+            if toggled {
+                classes[7] = "class-toggled".to_owned();
+            }
+            classes.join(" ")
+        };
+        let vdom = |toggled: bool| -> Node<Msg> {
+            div![
+                attrs! {
+                    At::Class => classes(toggled),
+                    At::Title => if toggled { "new" } else { "old" },
+                    At::Id => "patched",
+                },
+                style! {
+                    St::Color => if toggled { "red" } else { "blue" },
+                    St::Width => px(10),
+                    St::Height => px(20),
+                },
+            ]
+        };
+
+        let mut old_vdom = vdom(false);
+        virtual_dom_bridge::assign_ws_nodes(&doc, &mut old_vdom);
+        let el_ws = if let Node::Element(el) = &old_vdom {
+            el.node_ws.as_ref().unwrap().clone()
+        } else {
+            panic!("not an element");
+        };
+        parent.append_child(&el_ws).unwrap();
+
+        let set_attribute_calls = js_sys::Array::new();
+        js_sys::Function::new_with_args(
+            "element, calls",
+            "const set = element.setAttribute; \
+             element.setAttribute = function(name, value) { \
+                 calls.push(name); \
+                 return set.call(this, name, value); \
+             };",
+        )
+        .call2(&wasm_bindgen::JsValue::NULL, &el_ws, &set_attribute_calls)
+        .unwrap();
+
+        call_patch(&doc, &parent, &mailbox, old_vdom, vdom(true), &app);
+
+        let calls = set_attribute_calls
+            .iter()
+            .map(|name| name.as_string().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(calls, vec!["title"]);
+
+        let element = el_ws.dyn_ref::<web_sys::HtmlElement>().unwrap();
+        assert_eq!(element.class_list().length(), 20);
+        assert!(element.class_list().contains("class-toggled"));
+        assert!(!element.class_list().contains("class-7"));
+        assert_eq!(element.title(), "new");
+        let style = element.style();
+        assert_eq!(style.get_property_value("color").unwrap(), "red");
+        assert_eq!(style.get_property_value("width").unwrap(), "10px");
+        assert_eq!(style.get_property_value("height").unwrap(), "20px");
+    }
+
+    /// Tests that a child module with its own global message type reaches the app's `sink`
+    /// through `orders.proxy_global` - incl. outputs of its global commands.
+    #[wasm_bindgen_test(async)]
+    async fn child_global_messages_are_mapped() {
+        mod child {
+            use crate::prelude::*;
+
+            #[derive(Clone)]
+            pub enum Msg {
+                Save,
+            }
+            #[derive(Clone)]
+            pub enum GMsg {
+                Saving,
+                Saved(u32),
+            }
+
+            pub fn update(msg: Msg, orders: &mut impl Orders<Msg, GMsg>) {
+                match msg {
+                    Msg::Save => {
+                        orders.send_g_msg(GMsg::Saving);
+                        orders.perform_g_cmd(async { Ok(GMsg::Saved(7)) });
+                    }
+                }
+            }
+        }
+
+        struct Model {
+            received: Rc<RefCell<Vec<String>>>,
+        }
+        #[derive(Clone)]
+        enum Msg {
+            Child(child::Msg),
+        }
+        #[derive(Clone)]
+        enum GMsg {
+            Child(child::GMsg),
+        }
+
+        fn update(msg: Msg, _: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+            orders.skip();
+            match msg {
+                Msg::Child(child_msg) => child::update(
+                    child_msg,
+                    &mut orders.proxy(Msg::Child).proxy_global(GMsg::Child),
+                ),
+            }
+        }
+
+        fn sink(g_msg: GMsg, model: &mut Model, orders: &mut impl Orders<Msg, GMsg>) {
+            orders.skip();
+            let received = match g_msg {
+                GMsg::Child(child::GMsg::Saving) => "saving".to_owned(),
+                GMsg::Child(child::GMsg::Saved(id)) => format!("saved {}", id),
+            };
+            model.received.borrow_mut().push(received);
+        }
+
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let app = App::build(
+            {
+                let received = Rc::clone(&received);
+                move |_, _| Init::new(Model { received })
+            },
+            update,
+            |_| seed::empty(),
+        )
+        .mount(seed::body())
+        .sink(sink)
+        .finish()
+        .run();
+
+        app.update(Msg::Child(child::Msg::Save));
+        assert_eq!(*received.borrow(), vec!["saving"]);
+        for _ in 0..3 {
+            NextTick::new().await;
+        }
+        assert_eq!(*received.borrow(), vec!["saving", "saved 7"]);
+    }
+
+    /// Tests that FLIP animations invert moves, play them in the next frame
+    /// and restore members' inline styles when they finish.
+    #[wasm_bindgen_test(async)]
+    async fn flip_animations_restore_styles() {
+        async fn animation_frame() {
+            let promise = js_sys::Promise::new(&mut |resolve, _| {
+                util::window().request_animation_frame(&resolve).unwrap();
+            });
+            wasm_bindgen_futures::JsFuture::from(promise).await.unwrap();
+        }
+
+        #[derive(Clone)]
+        enum Msg {
+            Reverse,
+            Remove(u32),
+        }
+
+        struct Model {
+            items: Vec<u32>,
+        }
+
+        fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Reverse => model.items.reverse(),
+                Msg::Remove(item) => model.items.retain(|other| *other != item),
+            }
+        }
+
+        fn view(model: &Model) -> Node<Msg> {
+            ul![keyed_nodes(
+                &model.items,
+                |item| *item,
+                |item| {
+                    li![
+                        flip_group("items").duration(20).exit(FlipEffect::Scale),
+                        attrs! {At::Id => format!("flip-{}", item)},
+                        style! {
+                            St::Height => px(10),
+                            St::Opacity => if *item == 1 { "0.5" } else { "1" },
+                        },
+                        item.to_string(),
+                    ]
+                }
+            )]
+        }
+
+        let style = |item: u32| {
+            util::document()
+                .get_element_by_id(&format!("flip-{}", item))
+                .unwrap()
+                .unchecked_into::<web_sys::HtmlElement>()
+                .style()
+        };
+        let property =
+            |item: u32, property: &str| style(item).get_property_value(property).unwrap();
+        let exit_copies = || {
+            util::document()
+                .query_selector_all("body > li")
+                .unwrap()
+                .length()
+        };
+
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let clock = MockClock::new();
+        let app = App::build(
+            |_, _| {
+                Init::new(Model {
+                    items: vec![1, 2, 3],
+                })
+            },
+            update,
+            view,
+        )
+        .mount(mount_point.clone())
+        .clock(clock.clone())
+        .finish()
+        .run();
+        app.flush_render();
+        assert_eq!(property(1, "transform"), "");
+
+        // Invert.
+        app.update(Msg::Reverse);
+        app.flush_render();
+        assert_eq!(property(1, "transform"), "translate(0px, -20px)");
+        assert_eq!(property(3, "transform"), "translate(0px, 20px)");
+        assert_eq!(property(1, "transition"), "none");
+        // The middle item hasn't moved.
+        assert_eq!(property(2, "transform"), "");
+        assert_eq!(property(2, "transition"), "");
+
+        // Play.
+        animation_frame().await;
+        assert_eq!(property(1, "transform"), "");
+        assert!(property(1, "transition").contains("20ms"));
+
+        // The fallback finishes animations without `transitionend`.
+        clock.advance(std::time::Duration::from_millis(100));
+        for item in 1..=3 {
+            assert_eq!(property(item, "transform"), "");
+            assert_eq!(property(item, "transition"), "");
+        }
+        assert_eq!(property(1, "opacity"), "0.5");
+
+        // The removed item is replaced by its copy until the animation is finished.
+        app.update(Msg::Remove(2));
+        app.flush_render();
+        assert_eq!(exit_copies(), 1);
+        assert_eq!(property(1, "transform"), "translate(0px, 10px)");
+
+        app.unmount();
+        assert_eq!(exit_copies(), 0);
+        mount_point.remove();
+    }
+
+    /// Tests that all sinks receive global messages in registration order, each of them
+    /// exactly once per emission, and that the strongest requested render wins.
+    #[wasm_bindgen_test]
+    fn multiple_sinks_receive_global_messages() {
+        #[derive(Clone)]
+        enum GMsg {
+            Ping,
+            Pong,
+        }
+
+        struct Model {
+            received: Rc<RefCell<Vec<&'static str>>>,
+            count: usize,
+        }
+
+        fn update(_: (), _: &mut Model, orders: &mut impl Orders<(), GMsg>) {
+            orders.skip();
+        }
+
+        fn first_sink(g_msg: GMsg, model: &mut Model, orders: &mut impl Orders<(), GMsg>) {
+            orders.skip();
+            match g_msg {
+                GMsg::Ping => {
+                    model.received.borrow_mut().push("first: ping");
+                    orders.send_g_msg(GMsg::Pong);
+                }
+                GMsg::Pong => model.received.borrow_mut().push("first: pong"),
+            }
+        }
+
+        fn second_sink(g_msg: GMsg, model: &mut Model, _: &mut impl Orders<(), GMsg>) {
+            model.count += 1;
+            match g_msg {
+                GMsg::Ping => model.received.borrow_mut().push("second: ping"),
+                GMsg::Pong => model.received.borrow_mut().push("second: pong"),
+            }
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let app = App::build(
+            {
+                let received = Rc::clone(&received);
+                move |_, _| Init::new(Model { received, count: 0 })
+            },
+            update,
+            |model| div![model.count.to_string()],
+        )
+        .mount(mount_point.clone())
+        .add_sink(first_sink)
+        .add_sink(second_sink)
+        .finish()
+        .run();
+        app.flush_render();
+        assert_eq!(mount_point.text_content().unwrap(), "0");
+
+        app.sink(GMsg::Ping);
+        assert_eq!(
+            *received.borrow(),
+            vec!["first: ping", "second: ping", "first: pong", "second: pong"]
+        );
+        // The first sink skips rendering, but the second one doesn't.
+        app.flush_render();
+        assert_eq!(mount_point.text_content().unwrap(), "2");
+        app.unmount();
+    }
+
+    /// Tests that `orders.current_url` and `routes` get URLs without the base path
+    /// and pushed routes get it prepended.
+    #[wasm_bindgen_test]
+    fn current_url_without_base_path() {
+        #[derive(Clone)]
+        enum Msg {
+            UrlChanged(Url),
+            Save,
+        }
+
+        struct Model {
+            received: Rc<RefCell<Vec<String>>>,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            orders.skip();
+            match msg {
+                Msg::UrlChanged(url) => model.received.borrow_mut().push(url.to_string()),
+                Msg::Save => {
+                    orders.push_route(Url::new(vec!["users", "1"]));
+                    model
+                        .received
+                        .borrow_mut()
+                        .push(orders.current_url().to_string());
+                }
+            }
+        }
+
+        let original_url = util::window().location().href().unwrap();
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let app = App::build(
+            {
+                let received = Rc::clone(&received);
+                // The test runner's URL is outside of the base path.
+                move |_, _| Init::new_with_url_handling(Model { received }, UrlHandling::None)
+            },
+            update,
+            |_| seed::empty(),
+        )
+        .mount(util::document().create_element("div").unwrap())
+        .base_path(&["app"])
+        .routes(|url| Some(Msg::UrlChanged(url)))
+        .finish()
+        .run();
+
+        app.update(Msg::Save);
+        assert_eq!(
+            util::window().location().pathname().unwrap(),
+            "/app/users/1"
+        );
+        assert_eq!(*received.borrow(), vec!["/users/1", "/users/1"]);
+
+        app.unmount();
+        util::history()
+            .replace_state_with_url(&JsValue::NULL, "", Some(&original_url))
+            .unwrap();
+    }
+
+    /// Tests that message listeners are removed when their handles are dropped,
+    /// even by the listeners themselves, and kept when the handles are forgotten.
+    #[wasm_bindgen_test]
+    fn message_listeners_removed_by_handles() {
+        #[derive(Clone, Debug)]
+        struct Msg;
+
+        fn update(_: Msg, _: &mut (), orders: &mut impl Orders<Msg>) {
+            orders.skip();
+        }
+
+        let app = App::build(|_, _| Init::new(()), update, |_| seed::empty())
+            .mount(util::document().create_element("div").unwrap())
+            .finish()
+            .run();
+
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let self_removing = Rc::new(RefCell::new(None));
+        let handle = app.add_message_listener({
+            let calls = Rc::clone(&calls);
+            let self_removing = Rc::clone(&self_removing);
+            move |_| {
+                calls.borrow_mut().push("self-removing");
+                self_removing.borrow_mut().take();
+            }
+        });
+        *self_removing.borrow_mut() = Some(handle);
+        app.add_message_listener({
+            let calls = Rc::clone(&calls);
+            move |_| calls.borrow_mut().push("forgotten")
+        })
+        .forget();
+        let dropped = app.add_message_listener({
+            let calls = Rc::clone(&calls);
+            move |_| calls.borrow_mut().push("dropped")
+        });
+
+        app.update(Msg);
+        assert_eq!(
+            *calls.borrow(),
+            vec!["self-removing", "forgotten", "dropped"]
+        );
+
+        calls.borrow_mut().clear();
+        drop(dropped);
+        app.update(Msg);
+        assert_eq!(*calls.borrow(), vec!["forgotten"]);
+        app.unmount();
+    }
+
+    /// Tests that preload hints are deduplicated by their attributes and their links are removed
+    /// when all handles are dropped or when their TTL expires.
+    #[wasm_bindgen_test]
+    fn preload_hints_removed_by_handles_and_ttl() {
+        struct Model {
+            handles: Vec<PreloadHandle>,
+        }
+
+        #[derive(Clone)]
+        enum Msg {
+            Preload(&'static str, AsKind),
+            PreloadForgotten(&'static str),
+            DropHandle,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            orders.skip();
+            match msg {
+                Msg::Preload(href, as_kind) => {
+                    model
+                        .handles
+                        .push(orders.preload(PreloadHint::new(href, as_kind)));
+                }
+                Msg::PreloadForgotten(href) => orders
+                    .preload(
+                        PreloadHint::new(href, AsKind::Image)
+                            .prefetch()
+                            .ttl(std::time::Duration::from_secs(10)),
+                    )
+                    .forget(),
+                Msg::DropHandle => {
+                    model.handles.pop();
+                }
+            }
+        }
+
+        let links = || {
+            let nodes = util::document()
+                .query_selector_all("head > link[data-seed-preload]")
+                .unwrap();
+            (0..nodes.length())
+                .map(|index| {
+                    let link = nodes.item(index).unwrap().dyn_into::<Element>().unwrap();
+                    format!(
+                        "{} {} {} {}",
+                        link.get_attribute("rel").unwrap(),
+                        link.get_attribute("as").unwrap(),
+                        link.get_attribute("href").unwrap(),
+                        link.get_attribute("crossorigin").unwrap_or_default(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let clock = MockClock::new();
+        let app = App::build(
+            |_, _| {
+                Init::new(Model {
+                    handles: Vec::new(),
+                })
+            },
+            update,
+            |_| seed::empty(),
+        )
+        .mount(util::document().create_element("div").unwrap())
+        .clock(clock.clone())
+        .finish()
+        .run();
+
+        app.update(Msg::Preload("/hero.png", AsKind::Image));
+        app.update(Msg::Preload("/hero.png", AsKind::Image));
+        assert_eq!(links(), vec!["preload image /hero.png "]);
+
+        // One of two handles is dropped.
+        app.update(Msg::DropHandle);
+        assert_eq!(links(), vec!["preload image /hero.png "]);
+        app.update(Msg::DropHandle);
+        assert!(links().is_empty());
+
+        // The same `href` with another `as` isn't reused by the browser.
+        app.update(Msg::Preload("/hero.png", AsKind::Image));
+        app.update(Msg::Preload("/hero.png", AsKind::Fetch));
+        assert_eq!(
+            links(),
+            vec!["preload image /hero.png ", "preload fetch /hero.png "]
+        );
+        app.update(Msg::DropHandle);
+        assert_eq!(links(), vec!["preload image /hero.png "]);
+        app.update(Msg::DropHandle);
+        assert!(links().is_empty());
+
+        // Fonts get `crossorigin`.
+        app.update(Msg::Preload("/inter.woff2", AsKind::Font));
+        assert_eq!(links(), vec!["preload font /inter.woff2 anonymous"]);
+
+        app.update(Msg::PreloadForgotten("/next.png"));
+        assert_eq!(
+            links(),
+            vec![
+                "preload font /inter.woff2 anonymous",
+                "prefetch image /next.png ",
+            ]
+        );
+        clock.advance(std::time::Duration::from_secs(10));
+        assert_eq!(links(), vec!["preload font /inter.woff2 anonymous"]);
+
+        app.unmount();
+        assert!(links().is_empty());
+    }
+
+    /// Tests that hovering a link with `prefetch_on_hover` hints the route's assets
+    /// and performs its data requests through a proxy.
+    #[wasm_bindgen_test(async)]
+    async fn route_prefetched_on_hover() {
+        struct Model {
+            hints: Vec<PreloadHandle>,
+            fetched: Rc<Cell<usize>>,
+        }
+
+        #[derive(Clone)]
+        enum Msg {
+            Post(PostMsg),
+        }
+
+        #[derive(Clone)]
+        enum PostMsg {
+            Likely,
+            Fetched,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Post(PostMsg::Likely) => {
+                    model.hints = orders.proxy(Msg::Post).prefetch_route(
+                        RoutePrefetch::new()
+                            .asset(PreloadHint::new("/post-cover.png", AsKind::Image).prefetch())
+                            .data(async { PostMsg::Fetched }),
+                    );
+                }
+                Msg::Post(PostMsg::Fetched) => model.fetched.set(model.fetched.get() + 1),
+            }
+        }
+
+        let hinted = || {
+            util::document()
+                .query_selector("head > link[data-seed-preload][href='/post-cover.png']")
+                .unwrap()
+                .map(|link| link.get_attribute("rel").unwrap())
+        };
+
+        let fetched = Rc::new(Cell::new(0));
+        let mount_point = util::document().create_element("div").unwrap();
+        let app = App::build(
+            {
+                let fetched = Rc::clone(&fetched);
+                move |_, _| {
+                    Init::new(Model {
+                        hints: Vec::new(),
+                        fetched,
+                    })
+                }
+            },
+            update,
+            |_| a![prefetch_on_hover(Msg::Post(PostMsg::Likely)), "Post"],
+        )
+        .mount(mount_point.clone())
+        .finish()
+        .run();
+        app.flush_render();
+
+        mount_point
+            .first_element_child()
+            .unwrap()
+            .dispatch_event(&web_sys::Event::new("mouseenter").unwrap())
+            .unwrap();
+        assert_eq!(hinted(), Some("prefetch".to_owned()));
+
+        for _ in 0..5 {
+            NextTick::new().await;
+        }
+        assert_eq!(fetched.get(), 1);
+
+        app.unmount();
+        assert_eq!(hinted(), None);
+    }
+
+    /// Tests that a render of an unchanged vdom doesn't touch the DOM,
+    /// but `after_next_render` callbacks are still called.
+    #[wasm_bindgen_test]
+    fn unchanged_vdom_is_not_patched() {
+        #[derive(Clone)]
+        enum Msg {
+            Same,
+            Rendered,
+        }
+
+        struct Model {
+            rendered: Rc<Cell<usize>>,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Same => {
+                    orders.after_next_render(|_| Msg::Rendered);
+                }
+                Msg::Rendered => {
+                    orders.skip();
+                    model.rendered.set(model.rendered.get() + 1);
+                }
+            }
+        }
+
+        fn view(_: &Model) -> Node<Msg> {
+            div![
+                class!["counter"],
+                attrs! {At::Title => "unchanged"},
+                style! {St::Color => "red"},
+                button![ev(Ev::Click, |_| Msg::Same), "Same"],
+                "text",
+            ]
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        let rendered = Rc::new(Cell::new(0));
+        let app = App::build(
+            {
+                let rendered = Rc::clone(&rendered);
+                move |_, _| Init::new(Model { rendered })
+            },
+            update,
+            view,
+        )
+        .mount(mount_point.clone())
+        .finish()
+        .run();
+        app.flush_render();
+        let button = mount_point.query_selector("button").unwrap().unwrap();
+
+        // Record DOM writes until `restore` is called.
+        let calls = js_sys::Array::new();
+        let restore = js_sys::Function::new_with_args(
+            "calls",
+            "const spied = [ \
+                 [EventTarget.prototype, 'addEventListener'], \
+                 [Element.prototype, 'setAttribute'], \
+                 [Node.prototype, 'appendChild'], \
+                 [Node.prototype, 'insertBefore'], \
+             ]; \
+             const originals = spied.map(([proto, name]) => proto[name]); \
+             spied.forEach(([proto, name], index) => { \
+                 proto[name] = function(...args) { \
+                     calls.push(name); \
+                     return originals[index].apply(this, args); \
+                 }; \
+             }); \
+             return () => spied.forEach(([proto, name], index) => proto[name] = originals[index]);",
+        )
+        .call1(&JsValue::NULL, &calls)
+        .unwrap()
+        .dyn_into::<js_sys::Function>()
+        .unwrap();
+
+        app.update(Msg::Same);
+        app.flush_render();
+        restore.call0(&JsValue::NULL).unwrap();
+
+        assert_eq!(calls.length(), 0);
+        assert_eq!(rendered.get(), 1);
+
+        // The kept listener still works.
+        button.dyn_into::<web_sys::HtmlElement>().unwrap().click();
+        app.flush_render();
+        assert_eq!(rendered.get(), 2);
+    }
+
+    /// Tests that update middlewares wrap each `update` call in registration order,
+    /// including messages sent by orders and commands.
+    #[wasm_bindgen_test(async)]
+    async fn update_middlewares_wrap_update() {
+        let (done_sender, done_receiver) = futures::channel::oneshot::channel::<()>();
+
+        #[derive(Debug, Clone)]
+        enum Msg {
+            Start,
+            Sent,
+            Performed,
+            Ignored,
+        }
+
+        struct Model {
+            done_sender: Option<futures::channel::oneshot::Sender<()>>,
+            log: Rc<RefCell<Vec<String>>>,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            orders.skip();
+            model.log.borrow_mut().push(format!("update {:?}", msg));
+            match msg {
+                Msg::Start => {
+                    orders
+                        .send_msg(Msg::Sent)
+                        .perform_cmd(async { Msg::Performed });
+                }
+                Msg::Performed => {
+                    model.done_sender.take().unwrap().send(()).unwrap();
+                }
+                Msg::Sent | Msg::Ignored => (),
+            }
+        }
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let app = App::build(
+            {
+                let log = Rc::clone(&log);
+                move |_, _| {
+                    Init::new_with_url_handling(
+                        Model {
+                            done_sender: Some(done_sender),
+                            log,
+                        },
+                        UrlHandling::None,
+                    )
+                }
+            },
+            update,
+            |_| seed::empty(),
+        )
+        .mount(util::document().create_element("div").unwrap())
+        .update_middleware({
+            let log = Rc::clone(&log);
+            move |msg, model, orders, next| {
+                log.borrow_mut().push(format!("first {:?}", msg));
+                next(msg, model, orders);
+            }
+        })
+        .update_middleware({
+            let log = Rc::clone(&log);
+            move |msg, model, orders, next| {
+                log.borrow_mut().push(format!("second {:?}", msg));
+                if let Msg::Ignored = msg {
+                    return;
+                }
+                next(msg, model, orders);
+            }
+        })
+        .finish()
+        .run();
+
+        app.update(Msg::Ignored);
+        app.update(Msg::Start);
+        done_receiver.await.unwrap();
+
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                "first Ignored",
+                "second Ignored",
+                "first Start",
+                "second Start",
+                "update Start",
+                "first Sent",
+                "second Sent",
+                "update Sent",
+                "first Performed",
+                "second Performed",
+                "update Performed",
+            ]
+        );
+        app.unmount();
+    }
+
+    /// Tests that `inner_html` content is written only when the HTML changes
+    /// and that it's cleared when the element switches to children.
+    #[wasm_bindgen_test]
+    fn inner_html_written_only_when_changed() {
+        #[derive(Clone)]
+        enum Msg {
+            Set(Option<&'static str>),
+        }
+
+        fn update(msg: Msg, model: &mut Option<&'static str>, _: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Set(html) => *model = html,
+            }
+        }
+
+        let mount_point = seed::document().create_element("div").unwrap();
+        let app = App::build(
+            |_, _| Init::new_with_url_handling(Some("<p>Hi</p>"), UrlHandling::None),
+            update,
+            |model| match model {
+                Some(html) => div![class!["post"], inner_html(*html)],
+                None => div![class!["post"], "Empty"],
+            },
+        )
+        .mount(mount_point.clone())
+        .finish()
+        .run();
+        app.flush_render();
+
+        let post = mount_point.first_element_child().unwrap();
+        assert_eq!(post.inner_html(), "<p>Hi</p>");
+        let paragraph = post.first_element_child().unwrap();
+
+        // Unchanged HTML isn't written again - the paragraph is still the same node.
+        app.update(Msg::Set(Some("<p>Hi</p>")));
+        app.flush_render();
+        assert!(post
+            .first_element_child()
+            .unwrap()
+            .is_same_node(Some(&paragraph)));
+
+        app.update(Msg::Set(Some("<p>Hello</p>")));
+        app.flush_render();
+        assert_eq!(post.inner_html(), "<p>Hello</p>");
+        assert!(!post
+            .first_element_child()
+            .unwrap()
+            .is_same_node(Some(&paragraph)));
+
+        // Children replace the HTML.
+        app.update(Msg::Set(None));
+        app.flush_render();
+        assert_eq!(post.inner_html(), "Empty");
+
+        app.update(Msg::Set(Some("<p>Hi</p>")));
+        app.flush_render();
+        assert_eq!(post.inner_html(), "<p>Hi</p>");
+    }
+
+    /// Tests that the capabilities message is sent first, services return `Unsupported`
+    /// in a mocked degraded environment and lazy subtrees are hydrated without `IntersectionObserver`.
+    #[wasm_bindgen_test]
+    async fn degraded_capabilities() {
+        use crate::browser::{
+            service::fetch::{self, FetchError, RequestError},
+            Unsupported,
+        };
+
+        const KEY: &str = "degraded-capabilities-test";
+
+        #[derive(Default)]
+        struct Model {
+            log: Vec<String>,
+        }
+        #[derive(Clone)]
+        enum Msg {
+            Checked(Result<(), Unsupported>),
+            Started,
+            Hydrated(String),
+        }
+
+        fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
+            model.log.push(match msg {
+                Msg::Checked(result) => {
+                    format!("checked {:?}", result.map_err(|error| error.missing))
+                }
+                Msg::Started => "started".to_owned(),
+                Msg::Hydrated(key) => format!("hydrated {}", key),
+            });
+        }
+
+        fn view(_: &Model) -> Node<Msg> {
+            div![hydrate_lazy(KEY, || span!["hydrated"])]
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        let app = App::build(
+            |_, orders| {
+                orders
+                    .subscribe_lazy_hydration(Msg::Hydrated)
+                    .send_msg(Msg::Started);
+                Init::new_with_url_handling(Model::default(), UrlHandling::None)
+            },
+            update,
+            view,
+        )
+        .mount(mount_point.clone())
+        .capabilities(Capabilities::mock(&[
+            (Capability::Fetch, false),
+            (Capability::IntersectionObserver, false),
+            (Capability::History, true),
+        ]))
+        .capabilities_msg(|capabilities| {
+            Msg::Checked(capabilities.assert_required(&[Capability::Fetch, Capability::History]))
+        })
+        .finish()
+        .run();
+
+        let request =
+            fetch::Request::new("/degraded-capabilities").capabilities(&app.cfg.capabilities);
+        match fetch::fetch(request).await {
+            Err(FetchError::RequestError(RequestError::Unsupported(unsupported))) => {
+                assert_eq!(unsupported.missing, vec![Capability::Fetch])
+            }
+            _ => panic!("fetch should be unsupported"),
+        }
+        let element = util::document().create_element("div").unwrap();
+        let mut orders = seed::app::OrdersContainer::new(app.clone());
+        assert!(orders
+            .subscribe_scroll_progress(&element, ScrollProgressOpts::default(), |_| Msg::Started)
+            .is_err());
+
+        for _ in 0..3 {
+            NextTick::new().await;
+        }
+        app.flush_render();
+        assert_eq!(
+            app.data.model.borrow().as_ref().unwrap().log,
+            vec![
+                "checked Err([Fetch])".to_owned(),
+                "started".to_owned(),
+                format!("hydrated {}", KEY),
+            ]
+        );
+        assert_eq!(mount_point.text_content().unwrap(), "hydrated");
+
+        app.unmount();
+    }
+
+    /// Tests that a forced render panicking in `view` (without `view_error_handler`) keeps
+    /// the old vdom, so the next render patches it and delivers the pending follow-up message.
+    /// Panics abort without unwinding on the default wasm target.
+    #[cfg(panic = "unwind")]
+    #[wasm_bindgen_test]
+    fn panicking_forced_render_keeps_pending_effects() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        #[derive(Default)]
+        struct Model {
+            broken: bool,
+            committed: bool,
+        }
+        enum Msg {
+            Commit,
+            Committed,
+            Fix,
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Commit => {
+                    model.broken = true;
+                    orders
+                        .after_next_render(|_| Msg::Committed)
+                        .force_render_now();
+                }
+                Msg::Committed => model.committed = true,
+                Msg::Fix => {
+                    model.broken = false;
+                    orders.force_render_now();
+                }
+            }
+        }
+
+        fn view(model: &Model) -> Node<Msg> {
+            if model.broken {
+                panic!("broken view");
+            }
+            div!["ok"]
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(|_, _| Init::new(Model::default()), update, view)
+            .mount(mount_point.clone())
+            .finish()
+            .run();
+        app.flush_render();
+
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| app.update(Msg::Commit))).is_err());
+        assert!(app.data.main_el_vdom.borrow().is_some());
+        assert!(!app.data.model.borrow().as_ref().unwrap().committed);
+
+        app.update(Msg::Fix);
+        assert!(app.data.model.borrow().as_ref().unwrap().committed);
+        assert_eq!(mount_point.inner_html(), "<div>ok</div>");
+
+        app.unmount();
+        mount_point.remove();
+    }
+
+    /// Tests that input modality handlers are called until their handles are dropped.
+    #[wasm_bindgen_test]
+    fn input_modality_unsubscribe() {
+        use crate::{app::SubHandle, browser::Modality, test::simulate};
+
+        #[derive(Default)]
+        struct Model {
+            sub: Option<SubHandle>,
+            modalities: Vec<Modality>,
+        }
+        enum Msg {
+            Subscribe,
+            Unsubscribe,
+            Changed(Modality),
+        }
+
+        fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+            match msg {
+                Msg::Subscribe => model.sub = Some(orders.subscribe_input_modality(Msg::Changed)),
+                Msg::Unsubscribe => model.sub = None,
+                Msg::Changed(modality) => model.modalities.push(modality),
+            }
+        }
+
+        let mount_point = util::document().create_element("div").unwrap();
+        util::body().append_child(&mount_point).unwrap();
+        let app = App::build(
+            |_, _| Init::new(Model::default()),
+            update,
+            |_| seed::empty(),
+        )
+        .mount(mount_point.clone())
+        .finish()
+        .run();
+        let document = util::document();
+        let modalities = || app.data.model.borrow().as_ref().unwrap().modalities.clone();
+
+        app.update(Msg::Subscribe);
+        simulate::key_down(&document, "a");
+        let touch_start = web_sys::Event::new("touchstart").unwrap();
+        document.dispatch_event(&touch_start).unwrap();
+        assert_eq!(modalities(), vec![Modality::Keyboard, Modality::Touch]);
+
+        app.update(Msg::Unsubscribe);
+        simulate::key_down(&document, "b");
+        assert_eq!(modalities(), vec![Modality::Keyboard, Modality::Touch]);
+        assert!(app.data.input_modality_handlers.borrow().is_empty());
+        assert_eq!(
+            app.data.input_modality_tracker.borrow().current(),
+            Some(Modality::Keyboard)
+        );
+
+        app.unmount();
+        mount_point.remove();
+    }
 }
@@ -0,0 +1,291 @@
+//! Active state of navigation links. See `active_link` and `active_when`.
+//!
+//! Marked links get a class (`active` by default) and `aria-current="page"` while they match
+//! the current URL. The URL is stored in `AppData` - it's refreshed before each render and
+//! on each navigation handled by `routes`. Marked links are updated directly in DOM
+//! on navigations, so they are correct even when `update` skips the render.
+//!
+//! Links with `href` starting with `#` (hash routing) are matched against the current URL's hash,
+//! other links against its path. Search and hash of `href` are ignored.
+
+use super::{At, AtValue, Attrs, El, Node, UpdateEl};
+use crate::browser::{url, Url};
+use std::{fmt, rc::Rc};
+use wasm_bindgen::JsCast;
+
+/// Default class of active links.
+pub const ACTIVE_CLASS: &str = "active";
+
+/// How `active_link` compares the link's `href` with the current URL.
+#[derive(Clone)]
+pub enum LinkMatch {
+    /// Paths are equal - e.g. for the home link (`/`).
+    Exact,
+    /// The current path starts with the link's path - e.g. `/blog` is active on `/blog/hello`.
+    Prefix,
+    /// The closure decides. See `active_when`.
+    Custom(Rc<dyn Fn(&Url) -> bool>),
+}
+
+impl fmt::Debug for LinkMatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LinkMatch::Exact => write!(f, "Exact"),
+            LinkMatch::Prefix => write!(f, "Prefix"),
+            LinkMatch::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+/// Active state of a link - add it to `a![]`. See `active_link` and `active_when`.
+#[derive(Debug, Clone)]
+pub struct ActiveLink {
+    matching: LinkMatch,
+    class: String,
+}
+
+impl ActiveLink {
+    /// Use `class` instead of `active`.
+    pub fn class(mut self, class: impl Into<String>) -> Self {
+        self.class = class.into();
+        self
+    }
+
+    fn is_active(&self, href: Option<&str>, url: &Url) -> bool {
+        let exact = match &self.matching {
+            LinkMatch::Custom(matches) => return matches(url),
+            LinkMatch::Exact => true,
+            LinkMatch::Prefix => false,
+        };
+        let href = match href {
+            Some(href) => href,
+            None => return false,
+        };
+        let (link_path, current_path) = if href.starts_with('#') {
+            (
+                segments(&href[1..]),
+                segments(url.hash.as_ref().map_or("", String::as_str)),
+            )
+        } else {
+            (
+                segments(href),
+                url.path
+                    .iter()
+                    .filter(|segment| !segment.is_empty())
+                    .cloned()
+                    .collect(),
+            )
+        };
+        if exact {
+            link_path == current_path
+        } else {
+            current_path.starts_with(&link_path)
+        }
+    }
+}
+
+/// Decoded path segments of `href` without search and hash - see `url::parse_path`.
+fn segments(href: &str) -> Vec<String> {
+    url::parse_path(
+        href.split(|c| c == '?' || c == '#')
+            .next()
+            .unwrap_or_default(),
+    )
+    .into_iter()
+    .filter(|segment| !segment.is_empty())
+    .collect()
+}
+
+/// Mark the link as active when its `href` matches the current URL.
+///
+/// # Example
+///
+/// ```rust,no_run
+///nav![
+///    a![attrs! {At::Href => "/"}, active_link(LinkMatch::Exact), "Home"],
+///    a![attrs! {At::Href => "/blog"}, active_link(LinkMatch::Prefix).class("current"), "Blog"],
+///]
+/// ```
+pub fn active_link(matching: LinkMatch) -> ActiveLink {
+    ActiveLink {
+        matching,
+        class: ACTIVE_CLASS.to_owned(),
+    }
+}
+
+/// Mark the link as active when `matches` returns `true` for the current URL.
+///
+/// # Example
+///
+/// ```rust,no_run
+///a![
+///    attrs! {At::Href => "/posts?tag=rust"},
+///    active_when(|url| url.search.as_ref().map_or(false, |search| search.contains("tag=rust"))),
+///    "Rust posts"
+///]
+/// ```
+pub fn active_when(matches: impl Fn(&Url) -> bool + 'static) -> ActiveLink {
+    active_link(LinkMatch::Custom(Rc::new(matches)))
+}
+
+impl<Ms> UpdateEl<El<Ms>> for ActiveLink {
+    fn update(self, el: &mut El<Ms>) {
+        el.active_link = Some(self);
+    }
+}
+
+// ------ Resolving ------
+
+/// Set classes and `aria-current` of marked links in a new vdom before it's patched.
+pub(crate) fn resolve_all<Ms>(el: &mut El<Ms>, url: &Url) {
+    for_each_link(el, &mut |el| {
+        resolve(el, url);
+    });
+}
+
+/// Update marked links in the current vdom and in DOM after navigation.
+pub(crate) fn refresh_all<Ms>(el: &mut El<Ms>, url: &Url) {
+    for_each_link(el, &mut |el| {
+        if !resolve(el, url) {
+            return;
+        }
+        if let Some(element) = el
+            .node_ws
+            .as_ref()
+            .and_then(|node| node.dyn_ref::<web_sys::Element>())
+        {
+            for name in &[At::Class, At::from("aria-current")] {
+                match el.attrs.vals.get(name) {
+                    Some(AtValue::Some(value)) => element
+                        .set_attribute(name.as_str(), value)
+                        .expect("Problem setting active link attribute"),
+                    _ => element
+                        .remove_attribute(name.as_str())
+                        .expect("Problem removing active link attribute"),
+                }
+            }
+        }
+    });
+}
+
+fn for_each_link<Ms>(el: &mut El<Ms>, f: &mut impl FnMut(&mut El<Ms>)) {
+    if el.active_link.is_some() {
+        f(el);
+    }
+    for child in &mut el.children {
+        if let Node::Element(child_el) = child {
+            for_each_link(child_el, f);
+        }
+    }
+}
+
+/// Update `el`'s attributes. Returns `true` if they have been changed.
+fn resolve<Ms>(el: &mut El<Ms>, url: &Url) -> bool {
+    let link = match &el.active_link {
+        Some(link) => link,
+        None => return false,
+    };
+    let href = match el.attrs.vals.get(&At::Href) {
+        Some(AtValue::Some(href)) => Some(href.as_str()),
+        _ => None,
+    };
+    let active = link.is_active(href, url);
+    let class = link.class.clone();
+    let old_attrs = el.attrs.clone();
+
+    set_class(&mut el.attrs, &class, active);
+    let aria_current = At::from("aria-current");
+    if active {
+        el.attrs.add(aria_current, "page");
+    } else {
+        el.attrs.vals.shift_remove(&aria_current);
+    }
+    el.attrs != old_attrs
+}
+
+fn set_class(attrs: &mut Attrs, class: &str, active: bool) {
+    let mut classes = match attrs.vals.get(&At::Class) {
+        Some(AtValue::Some(classes)) => classes
+            .split_whitespace()
+            .filter(|existing| *existing != class)
+            .map(ToOwned::to_owned)
+            .collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+    if active {
+        classes.push(class.to_owned());
+    }
+    if classes.is_empty() {
+        attrs.vals.shift_remove(&At::Class);
+    } else {
+        attrs.add(At::Class, classes.join(" "));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::virtual_dom::Tag;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn is_active(matching: LinkMatch, href: &str, url: &Url) -> bool {
+        active_link(matching).is_active(Some(href), url)
+    }
+
+    #[wasm_bindgen_test]
+    fn path_matching() {
+        let url = Url::new(vec!["blog", "hello"]).search("page=2");
+
+        assert!(is_active(LinkMatch::Exact, "/blog/hello?page=1", &url));
+        assert!(!is_active(LinkMatch::Exact, "/blog", &url));
+        assert!(is_active(LinkMatch::Prefix, "/blog", &url));
+        assert!(!is_active(LinkMatch::Prefix, "/blogs", &url));
+        assert!(!is_active(LinkMatch::Exact, "/", &url));
+        assert!(is_active(LinkMatch::Exact, "/", &Url::new(vec![""])));
+        assert!(!active_link(LinkMatch::Prefix).is_active(None, &url));
+
+        // Segments are compared decoded.
+        let url = Url::default().path(&["docs", "Q3/Q4 report"]);
+        assert!(is_active(LinkMatch::Exact, "/docs/Q3%2FQ4%20report", &url));
+        assert!(!is_active(LinkMatch::Exact, "/docs/Q3/Q4%20report", &url));
+    }
+
+    #[wasm_bindgen_test]
+    fn hash_matching() {
+        let url = Url::new(vec![""]).hash("/blog/hello");
+
+        assert!(is_active(LinkMatch::Prefix, "#/blog", &url));
+        assert!(!is_active(LinkMatch::Exact, "#/blog", &url));
+        // Path links ignore the hash.
+        assert!(is_active(
+            LinkMatch::Exact,
+            "/",
+            &Url::new(vec![""]).hash("/blog")
+        ));
+        assert!(is_active(LinkMatch::Exact, "#/", &Url::new(vec![""])));
+    }
+
+    #[wasm_bindgen_test]
+    fn classes_are_toggled() {
+        let mut el = El::<()>::empty(Tag::A);
+        el.attrs.add(At::Href, "/blog");
+        el.attrs.add(At::Class, "link");
+        el.active_link = Some(active_link(LinkMatch::Prefix));
+
+        assert!(resolve(&mut el, &Url::new(vec!["blog"])));
+        assert_eq!(
+            el.attrs.vals.get(&At::Class),
+            Some(&AtValue::Some("link active".to_owned()))
+        );
+        assert!(!resolve(&mut el, &Url::new(vec!["blog"])));
+
+        assert!(resolve(&mut el, &Url::new(vec!["about"])));
+        assert_eq!(
+            el.attrs.vals.get(&At::Class),
+            Some(&AtValue::Some("link".to_owned()))
+        );
+        assert!(el.attrs.vals.get(&At::from("aria-current")).is_none());
+    }
+}
@@ -15,10 +15,21 @@ impl fmt::Display for Attrs {
         let string = self
             .vals
             .iter()
+            .filter(|(k, _)| match k.validate() {
+                Ok(()) => true,
+                Err(_err) => {
+                    // The string rendering also runs on the server - there is no console.
+                    #[cfg(target_arch = "wasm32")]
+                    crate::error(_err);
+                    false
+                }
+            })
             .filter_map(|(k, v)| match v {
                 AtValue::Ignored => None,
                 AtValue::None => Some(k.as_str().to_string()),
-                AtValue::Some(value) => Some(format!("{}=\"{}\"", k.as_str(), value)),
+                AtValue::Some(value) => {
+                    Some(format!("{}=\"{}\"", k.as_str(), escape_attr_value(value)))
+                }
             })
             .collect::<Vec<_>>()
             .join(" ");
@@ -26,6 +37,21 @@ impl fmt::Display for Attrs {
     }
 }
 
+/// Escape characters which could close the attribute value or start new markup.
+fn escape_attr_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 impl Attrs {
     pub const fn new(vals: IndexMap<At, AtValue>) -> Self {
         Self { vals }
@@ -99,3 +125,25 @@ impl Attrs {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn display_skips_invalid_names_and_escapes_values() {
+        let mut attrs = Attrs::empty();
+        attrs.add(At::Id, "a\" onclick=\"alert(1)");
+        attrs.add(At::Custom("onclick=\"alert(1)\"".to_owned()), "x");
+        attrs.add(At::Custom("a b".to_owned()), AtValue::None);
+        attrs.add(At::Custom("data-x".to_owned()), "<b>&</b>");
+
+        assert_eq!(
+            attrs.to_string(),
+            "id=\"a&quot; onclick=&quot;alert(1)\" data-x=\"&lt;b&gt;&amp;&lt;/b&gt;\""
+        );
+    }
+}
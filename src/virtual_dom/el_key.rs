@@ -0,0 +1,208 @@
+//! Keyed children. See `el_key` and `keyed_nodes`.
+
+use super::Node;
+#[cfg(debug_assertions)]
+use std::collections::HashSet;
+
+/// Element key created by `el_key`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElKey(pub String);
+
+/// Identify the element among its siblings. Keyed children are matched with the previous
+/// render's children by their keys instead of by their positions - when a list is reordered,
+/// its DOM nodes are moved, so they keep focus, text selection, scroll positions, etc.
+///
+/// Keys have to be unique among siblings - children with duplicate keys are patched by position.
+/// Unkeyed siblings are matched by their positions among the unkeyed children.
+/// Prefer `keyed_nodes` for lists.
+///
+/// # Example
+///
+/// ```rust,no_run
+///ul![model.todos.iter().map(|todo| {
+///    li![el_key(&todo.id), input![attrs! {At::Value => todo.title}]]
+///})]
+/// ```
+pub fn el_key(key: &impl ToString) -> ElKey {
+    ElKey(key.to_string())
+}
+
+/// Render `items` by `view` and key the elements by `key` (see `el_key`).
+/// It's the recommended way to render lists.
+///
+/// Debug builds warn about:
+/// - Duplicate keys.
+/// - Items iterated from a `HashMap` or `HashSet` in another order than in the previous render
+///   of the same parent element - the order of hash collections isn't deterministic, so the list
+///   would be reordered without any change. Sort the items or use `BTreeMap`, `IndexMap` or `Vec`.
+/// - Nodes returned by `view` which aren't elements - they can't be keyed.
+///
+/// Release builds don't do any checks.
+///
+/// # Example
+///
+/// ```rust,no_run
+///ul![keyed_nodes(&model.todos, |todo| todo.id, |todo| li![&todo.title])]
+/// ```
+pub fn keyed_nodes<Ms, I, T, K, KF, VF>(items: I, key: KF, view: VF) -> Vec<Node<Ms>>
+where
+    I: IntoIterator<Item = T>,
+    K: ToString,
+    KF: Fn(&T) -> K,
+    VF: Fn(T) -> Node<Ms>,
+{
+    let nodes = items
+        .into_iter()
+        .map(|item| {
+            let item_key = key(&item).to_string();
+            let mut node = view(item);
+            match &mut node {
+                Node::Element(el) => {
+                    el.key = Some(item_key);
+                    #[cfg(debug_assertions)]
+                    {
+                        el.key_source = Some(std::any::type_name::<I::IntoIter>());
+                    }
+                }
+                _ => {
+                    #[cfg(debug_assertions)]
+                    web_sys::console::warn_1(
+                        &format!(
+                            "`keyed_nodes`: the node with the key \"{}\" isn't an element - \
+                             it can't be keyed.",
+                            item_key
+                        )
+                        .into(),
+                    );
+                }
+            }
+            node
+        })
+        .collect::<Vec<_>>();
+
+    #[cfg(debug_assertions)]
+    {
+        if let Some(duplicate) = duplicate_key(keys(nodes.iter())) {
+            web_sys::console::warn_1(
+                &format!(
+                    "`keyed_nodes`: duplicate key \"{}\" - keys have to be unique.",
+                    duplicate
+                )
+                .into(),
+            );
+        }
+    }
+    nodes
+}
+
+#[cfg(debug_assertions)]
+fn keys<'a, Ms: 'static>(nodes: impl Iterator<Item = &'a Node<Ms>>) -> Vec<&'a str> {
+    nodes
+        .filter_map(|node| node.el().and_then(|el| el.key.as_deref()))
+        .collect()
+}
+
+#[cfg(debug_assertions)]
+fn duplicate_key(keys: Vec<&str>) -> Option<&str> {
+    let mut unique_keys = HashSet::new();
+    keys.into_iter().find(|key| !unique_keys.insert(*key))
+}
+
+/// Warning for children of one element rendered by `keyed_nodes` from a hash collection
+/// in another order than its `old_children`. Patching calls it in debug builds.
+#[cfg(debug_assertions)]
+pub(crate) fn reorder_warning<Ms>(
+    old_children: &[Node<Ms>],
+    new_children: &[&mut Node<Ms>],
+) -> Option<String> {
+    let source = new_children
+        .iter()
+        .find_map(|child| child.el().and_then(|el| el.key_source))?;
+    if !is_hash_source(source) {
+        return None;
+    }
+    let new_keys = keys(new_children.iter().map(|child| &**child));
+    if !is_reordered(keys(old_children.iter()), new_keys) {
+        return None;
+    }
+    Some(format!(
+        "`keyed_nodes`: items are iterated from a hash collection and their order \
+         has been changed since the previous render - sort them \
+         or use an ordered collection. Source: `{}`",
+        source
+    ))
+}
+
+#[cfg(debug_assertions)]
+fn is_hash_source(source: &str) -> bool {
+    source.contains("::hash::") || source.starts_with("hashbrown::")
+}
+
+/// Are `keys` the same as `previous` in another order?
+#[cfg(debug_assertions)]
+fn is_reordered(mut previous: Vec<&str>, mut keys: Vec<&str>) -> bool {
+    if previous == keys || previous.len() != keys.len() {
+        return false;
+    }
+    previous.sort();
+    keys.sort();
+    previous == keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::virtual_dom::{El, Tag};
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn keyed_nodes_set_keys() {
+        let nodes = keyed_nodes(
+            &[3, 1, 2],
+            |id| *id,
+            |_| Node::<()>::Element(El::empty(Tag::Li)),
+        );
+        let keys = nodes
+            .iter()
+            .map(|node| node.el().unwrap().key.clone().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["3", "1", "2"]);
+    }
+
+    #[wasm_bindgen_test]
+    fn keys_are_checked() {
+        let hash_source = std::any::type_name::<std::collections::hash_map::Iter<u32, u32>>();
+        let vec_source = std::any::type_name::<std::slice::Iter<u32>>();
+        assert!(is_hash_source(hash_source));
+        assert!(!is_hash_source(vec_source));
+
+        let nodes = |keys: &[&str], source: &'static str| {
+            keys.iter()
+                .map(|key| {
+                    let mut el = El::<()>::empty(Tag::Li);
+                    el.key = Some((*key).to_owned());
+                    el.key_source = Some(source);
+                    Node::Element(el)
+                })
+                .collect::<Vec<_>>()
+        };
+        let warns = |old: &[&str], new: &[&str], source| {
+            let old = nodes(old, source);
+            let mut new = nodes(new, source);
+            reorder_warning(&old, &new.iter_mut().collect::<Vec<_>>()).is_some()
+        };
+
+        // The same order.
+        assert!(!warns(&["1", "2", "3"], &["1", "2", "3"], hash_source));
+        assert!(warns(&["1", "2", "3"], &["3", "1", "2"], hash_source));
+        // Changed items.
+        assert!(!warns(&["3", "1", "2"], &["4", "1", "2"], hash_source));
+        // Sorting a `Vec` is a legit reordering.
+        assert!(!warns(&["1", "2"], &["2", "1"], vec_source));
+
+        assert_eq!(duplicate_key(vec!["1", "2", "1"]), Some("1"));
+        assert_eq!(duplicate_key(vec!["1", "2"]), None);
+    }
+}
@@ -0,0 +1,130 @@
+//! References to rendered DOM elements. See `ElRef`.
+
+use super::{El, Node};
+use std::{cell::RefCell, fmt, marker::PhantomData, rc::Rc};
+use wasm_bindgen::JsCast;
+
+/// Slot for the rendered node shared by all clones of an `ElRef`.
+pub(crate) type SharedNodeWs = Rc<RefCell<Option<web_sys::Node>>>;
+
+/// Reference to a rendered DOM element - e.g. to focus an input or to measure its layout
+/// without querying the DOM.
+///
+/// Attach it to an element with `el_ref` - the reference is set after each render,
+/// updated when the element is recreated and cleared when the element is removed.
+///
+/// # Example
+///
+/// ```rust,no_run
+///struct Model {
+///    input: ElRef<web_sys::HtmlInputElement>,
+///}
+///
+///fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+///    match msg {
+///        Msg::ShowInput => {
+///            let input = model.input.clone();
+///            orders.after_next_render(move |_| {
+///                if let Some(input) = input.get() {
+///                    input.focus().expect("focus input");
+///                }
+///            });
+///        }
+///    }
+///}
+///
+///fn view(model: &Model) -> Node<Msg> {
+///    input![el_ref(&model.input)]
+///}
+/// ```
+pub struct ElRef<E> {
+    pub(crate) shared_node_ws: SharedNodeWs,
+    phantom: PhantomData<E>,
+}
+
+impl<E: JsCast> ElRef<E> {
+    pub fn new() -> Self {
+        Self {
+            shared_node_ws: Rc::new(RefCell::new(None)),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Get the rendered element. Returns `None` when the element isn't rendered
+    /// or when it isn't `E`.
+    pub fn get(&self) -> Option<E> {
+        self.shared_node_ws
+            .borrow()
+            .as_ref()
+            .and_then(|node| node.clone().dyn_into::<E>().ok())
+    }
+}
+
+impl<E: JsCast> Default for ElRef<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> Clone for ElRef<E> {
+    fn clone(&self) -> Self {
+        Self {
+            shared_node_ws: Rc::clone(&self.shared_node_ws),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<E> fmt::Debug for ElRef<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ElRef({:?})", self.shared_node_ws.borrow())
+    }
+}
+
+/// Attach the reference to the element. See `ElRef`.
+pub fn el_ref<E>(reference: &ElRef<E>) -> ElRef<E> {
+    reference.clone()
+}
+
+// ------ Resolving ------
+
+/// Collect references of the current vdom before it's patched.
+pub(crate) fn collect_all<Ms>(el: &El<Ms>) -> Vec<SharedNodeWs> {
+    let mut refs = Vec::new();
+    for_each_ref(el, &mut |_, shared_node_ws| {
+        refs.push(Rc::clone(shared_node_ws));
+    });
+    refs
+}
+
+/// Set references of the patched vdom and clear `old_refs` which aren't in it anymore.
+pub(crate) fn resolve_all<Ms>(el: &El<Ms>, old_refs: Vec<SharedNodeWs>) {
+    let mut new_refs = Vec::new();
+    for_each_ref(el, &mut |el, shared_node_ws| {
+        shared_node_ws.replace(el.node_ws.clone());
+        new_refs.push(Rc::clone(shared_node_ws));
+    });
+    for old_ref in old_refs {
+        if !new_refs.iter().any(|new_ref| Rc::ptr_eq(new_ref, &old_ref)) {
+            old_ref.replace(None);
+        }
+    }
+}
+
+/// Clear references of the removed vdom - see `App::unmount`.
+pub(crate) fn clear_all<Ms>(el: &El<Ms>) {
+    for_each_ref(el, &mut |_, shared_node_ws| {
+        shared_node_ws.replace(None);
+    });
+}
+
+fn for_each_ref<Ms>(el: &El<Ms>, f: &mut impl FnMut(&El<Ms>, &SharedNodeWs)) {
+    if let Some(shared_node_ws) = &el.el_ref {
+        f(el, shared_node_ws);
+    }
+    for child in &el.children {
+        if let Node::Element(child_el) = child {
+            for_each_ref(child_el, f);
+        }
+    }
+}
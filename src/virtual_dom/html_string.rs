@@ -0,0 +1,336 @@
+//! HTML serialization of the vdom - `El` and `Node` implement `Display`, so
+//! `node.to_string()` returns HTML. See also `render_to_string`. It's useful for pre-rendering views on the server
+//! (send the HTML and mount the app with `MountType::Takeover`) and for tests.
+//!
+//! - Void elements (`br`, `img`, `input`, ..) don't have closing tags and their children are ignored.
+//! - Text is HTML-escaped, except inside `script` and `style` - `</` is written as `<\/` there,
+//!   so the text can't close the element.
+//! - HTML set by `inner_html` is written as it is.
+//! - Attributes with `AtValue::None` are rendered without values (`<input disabled>`),
+//!   attributes with invalid names are skipped.
+//! - The `style` attribute is assembled from the element's `Style`.
+//! - Listeners, lifecycle hooks and other client-only parts are skipped.
+//!
+//! The serialization doesn't touch `web_sys`. Text is normalized by `TextNormalization` set
+//! on elements. The app's default (`Builder::text_normalization`) isn't known here - set it
+//! on the root element (`div![TextNormalization::Collapse, ..]`) to get the app's text.
+
+use super::{text_normalization, At, AtValue, El, Node, Tag, TextNormalization};
+use std::fmt;
+
+const VOID_TAGS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+impl<Ms> fmt::Display for Node<Ms> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Node::Element(el) => write!(f, "{}", el),
+            Node::Text(text) => write!(f, "{}", escape(&text.text, false)),
+            Node::Empty => Ok(()),
+        }
+    }
+}
+
+impl<Ms> fmt::Display for El<Ms> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_el(f, self, TextNormalization::Preserve)
+    }
+}
+
+/// Render the output of `view` to HTML - e.g. for server-side rendering.
+/// It's the concatenation of the nodes' `Display` outputs - see the module docs for details.
+///
+/// # Example
+///
+/// ```rust,no_run
+///let html = render_to_string(&view(&model).els());
+/// ```
+pub fn render_to_string<Ms>(nodes: &[Node<Ms>]) -> String {
+    nodes.iter().map(ToString::to_string).collect()
+}
+
+fn write_el<Ms>(
+    f: &mut fmt::Formatter<'_>,
+    el: &El<Ms>,
+    inherited: TextNormalization,
+) -> fmt::Result {
+    let mode = text_normalization::mode(el, inherited);
+    // The app's root - only its children are real nodes.
+    if el.tag == Tag::Placeholder {
+        return write_children(f, el, mode);
+    }
+
+    // HTML tag names are case-insensitive (e.g. `Tag::Script` is "Script"),
+    // SVG tag names aren't (`foreignObject`).
+    let tag = if el.namespace.is_none() {
+        el.tag.as_str().to_ascii_lowercase()
+    } else {
+        el.tag.as_str().to_owned()
+    };
+    write!(f, "<{}", tag)?;
+
+    let mut style = None;
+    for (name, value) in &el.attrs.vals {
+        if name.validate().is_err() {
+            continue;
+        }
+        match value {
+            AtValue::Ignored => (),
+            AtValue::Some(value) if name == &At::Style => style = Some(value.clone()),
+            AtValue::None => write!(f, " {}", name.as_str())?,
+            AtValue::Some(value) => write!(f, " {}=\"{}\"", name.as_str(), escape(value, true))?,
+        }
+    }
+    let style_map = el.style.to_string();
+    if !style_map.is_empty() {
+        style = Some(match style {
+            Some(style) if !style.is_empty() => format!("{};{}", style, style_map),
+            _ => style_map,
+        });
+    }
+    if let Some(style) = style {
+        write!(f, " style=\"{}\"", escape(&style, true))?;
+    }
+    write!(f, ">")?;
+
+    if VOID_TAGS.contains(&tag.as_str()) {
+        return Ok(());
+    }
+    write_children(f, el, mode)?;
+    write!(f, "</{}>", tag)
+}
+
+fn write_children<Ms>(
+    f: &mut fmt::Formatter<'_>,
+    el: &El<Ms>,
+    mode: TextNormalization,
+) -> fmt::Result {
+    // Trusted HTML - see `inner_html`.
+    if let Some(html) = &el.inner_html {
+        return write!(f, "{}", html);
+    }
+    // Raw text elements - their text isn't parsed as HTML, so it mustn't be escaped.
+    let raw_text = el.tag == Tag::Script || el.tag == Tag::Style;
+    for child in &el.children {
+        match child {
+            Node::Element(child_el) => write_el(f, child_el, mode)?,
+            Node::Text(text) => {
+                let text = match mode {
+                    TextNormalization::Collapse => text_normalization::collapse(&text.text),
+                    TextNormalization::Preserve => text.text.as_ref().into(),
+                };
+                if raw_text {
+                    // `</` would end the element (e.g. `"</script>"` in a JS string).
+                    write!(f, "{}", text.replace("</", "<\\/"))?
+                } else {
+                    write!(f, "{}", escape(&text, false))?
+                }
+            }
+            Node::Empty => (),
+        }
+    }
+    Ok(())
+}
+
+/// Escape characters which could start markup. Quotes are escaped in attribute values.
+fn escape(text: &str, attr_value: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if attr_value => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{ev, Ev, St};
+    use crate::virtual_dom::UpdateEl;
+    use indexmap::IndexMap;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[derive(Clone)]
+    enum Msg {
+        Clicked,
+    }
+
+    #[wasm_bindgen_test]
+    fn nested_elements() {
+        let node: Node<Msg> = div![
+            attrs! {At::Class => "list"},
+            ul![li!["First"], li![span!["Second"], " item"]],
+            p![],
+        ];
+        assert_eq!(
+            node.to_string(),
+            r#"<div class="list"><ul><li>First</li><li><span>Second</span> item</li></ul><p></p></div>"#
+        );
+        assert_eq!(Node::<Msg>::Empty.to_string(), "");
+    }
+
+    #[wasm_bindgen_test]
+    fn void_and_boolean_attributes() {
+        let node: Node<Msg> = div![
+            input![attrs! {
+                At::Value => "seed",
+                At::Disabled => AtValue::None,
+                At::Checked => AtValue::Ignored,
+            }],
+            br![],
+            img![attrs! {At::Src => "/logo.png", At::Alt => ""}],
+        ];
+        assert_eq!(
+            node.to_string(),
+            r#"<div><input value="seed" disabled><br><img src="/logo.png" alt=""></div>"#
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn escaping() {
+        let node: Node<Msg> = div![
+            attrs! {
+                At::Title => r#"Say "hi" & <leave>"#,
+                At::Custom("onclick=\"alert(1)\"".to_owned()) => "x",
+            },
+            "1 < 2 && \"quoted\" > 0",
+            Script!["if (1 < 2 && true) {}"],
+        ];
+        assert_eq!(
+            node.to_string(),
+            concat!(
+                r#"<div title="Say &quot;hi&quot; &amp; &lt;leave&gt;">"#,
+                r#"1 &lt; 2 &amp;&amp; "quoted" &gt; 0"#,
+                "<script>if (1 < 2 && true) {}</script></div>"
+            )
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn raw_text_can_not_close_its_element() {
+        let mut style_el = El::empty(Tag::Style);
+        style_el.add_text("/* </style> */");
+        let node: Node<Msg> = div![
+            Script![r#"let html = "</script><script>alert(1)</script>";"#],
+            Node::Element(style_el),
+        ];
+        assert_eq!(
+            node.to_string(),
+            concat!(
+                r#"<div><script>let html = "<\/script><script>alert(1)<\/script>";</script>"#,
+                r#"<style>/* <\/style> */</style></div>"#
+            )
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn text_normalization() {
+        let node: Node<Msg> = div![
+            TextNormalization::Collapse,
+            "Text written
+             across lines",
+            p!["  Hello  ", span!["world"]],
+            pre!["  kept  "],
+        ];
+        assert_eq!(
+            node.to_string(),
+            "<div>Text written across lines<p> Hello <span>world</span></p><pre>  kept  </pre></div>"
+        );
+        // `Preserve` by default.
+        let node: Node<Msg> = p!["a  b"];
+        assert_eq!(node.to_string(), "<p>a  b</p>");
+    }
+
+    #[wasm_bindgen_test]
+    fn style_and_listeners() {
+        let node: Node<Msg> = button![
+            attrs! {At::Style => "color: red"},
+            style! {St::Display => "flex", St::FontSize => "1.5em"},
+            ev(Ev::Click, |_| Msg::Clicked),
+            "Click",
+        ];
+        assert_eq!(
+            node.to_string(),
+            r#"<button style="color:red;display:flex;font-size:1.5em">Click</button>"#
+        );
+
+        let node: Node<Msg> = span![style! {St::Color => r#"url("x")"#}];
+        assert_eq!(
+            node.to_string(),
+            r#"<span style="color:url(&quot;x&quot;)"></span>"#
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn render_nodes_to_string() {
+        let nodes: Vec<Node<Msg>> = vec![
+            p![style! {St::Color => "red"}, "Tom & \"Jerry\" <3"],
+            Node::Empty,
+            div![
+                input![attrs! {At::Required => AtValue::None}],
+                span!["a", br![], "b"]
+            ],
+        ];
+        assert_eq!(
+            render_to_string(&nodes),
+            concat!(
+                r#"<p style="color:red">Tom &amp; "Jerry" &lt;3</p>"#,
+                r#"<div><input required><span>a<br>b</span></div>"#,
+            )
+        );
+        assert_eq!(render_to_string::<Msg>(&[]), "");
+    }
+
+    // The string rendering doesn't touch `web_sys`, so it also works outside of the browser.
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn nested_elements_on_host() {
+        let nodes: Vec<Node<Msg>> =
+            vec![div![ul![li!["First"], li![span!["Second"], " item"]]], p![]];
+        assert_eq!(
+            render_to_string(&nodes),
+            "<div><ul><li>First</li><li><span>Second</span> item</li></ul></div><p></p>"
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn boolean_attributes_on_host() {
+        let node: Node<Msg> = input![attrs! {
+            At::Disabled => AtValue::None,
+            At::Checked => AtValue::Ignored,
+            At::Value => "seed",
+        }];
+        assert_eq!(render_to_string(&[node]), r#"<input disabled value="seed">"#);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn escaping_on_host() {
+        let node: Node<Msg> = div![
+            attrs! {
+                At::Title => r#"<a href="x">&</a>"#,
+                At::Custom("on\"click".to_owned()) => "x",
+            },
+            "1 < 2 & \"3\"",
+        ];
+        assert_eq!(
+            render_to_string(&[node]),
+            r#"<div title="&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;">1 &lt; 2 &amp; "3"</div>"#
+        );
+
+        // Invalid attribute names are skipped without logging.
+        let attrs = attrs! {At::Custom("on\"click".to_owned()) => "x", At::Id => "a&b"};
+        assert_eq!(attrs.to_string(), r#"id="a&amp;b""#);
+    }
+}
@@ -0,0 +1,263 @@
+//! Adoption of server-rendered DOM - see `MountType::Takeover` and `MountType::Hydrate`.
+//!
+//! The children of the mount point are parsed into a vdom whose nodes are the existing DOM nodes,
+//! so the first render only patches the differences between the server HTML and `view` -
+//! matching nodes (and their state, e.g. of media elements) are kept.
+//!
+//! Whitespace-only text nodes containing a line break are formatting of the server HTML
+//! and they are removed (except in `pre` and `textarea`).
+//! Comments and other non-element nodes are left in the DOM, but they aren't part of the vdom.
+
+use super::{lazy_hydration, El, Node, Tag, Text};
+use crate::browser::dom::virtual_dom_bridge;
+use wasm_bindgen::JsCast;
+
+/// Parse children of `parent` into vdom nodes bound to the existing DOM nodes.
+pub(crate) fn adopt_children<Ms>(parent: &web_sys::Node, keep_whitespace: bool) -> Vec<Node<Ms>> {
+    let child_nodes = parent.child_nodes();
+    // Collect first - formatting whitespace is removed while iterating.
+    let child_nodes = (0..child_nodes.length())
+        .filter_map(|index| child_nodes.get(index))
+        .collect::<Vec<_>>();
+    child_nodes
+        .iter()
+        .filter_map(|child| adopt_node(child, parent, keep_whitespace))
+        .collect()
+}
+
+fn adopt_node<Ms>(
+    node: &web_sys::Node,
+    parent: &web_sys::Node,
+    keep_whitespace: bool,
+) -> Option<Node<Ms>> {
+    match node.node_type() {
+        web_sys::Node::ELEMENT_NODE => {
+            let el_ws = node.dyn_ref::<web_sys::Element>()?;
+            let mut el = virtual_dom_bridge::el_from_ws_without_children(el_ws);
+            let keep_whitespace = keep_whitespace || el.tag == Tag::Pre || el.tag == Tag::TextArea;
+            el.children = adopt_children(node, keep_whitespace);
+            el.node_ws = Some(node.clone());
+            Some(Node::Element(el))
+        }
+        web_sys::Node::TEXT_NODE => {
+            let text = node.text_content().unwrap_or_default();
+            let is_formatting =
+                text.contains('\n') && text.chars().all(|c| c.is_ascii_whitespace());
+            if is_formatting && !keep_whitespace {
+                virtual_dom_bridge::remove_node(node, parent);
+                return None;
+            }
+            let mut text = Text::new(text);
+            text.node_ws = Some(node.clone());
+            Some(Node::Text(text))
+        }
+        _ => None,
+    }
+}
+
+/// Sync the adopted DOM with the normalized vdom (see `TextNormalization`) - collapsed text
+/// is written and text nodes removed from the vdom are removed from the DOM.
+pub(crate) fn sync_normalized_text<Ms>(children: &[Node<Ms>], parent: &web_sys::Node) {
+    let child_nodes = parent.child_nodes();
+    let child_nodes = (0..child_nodes.length())
+        .filter_map(|index| child_nodes.get(index))
+        .collect::<Vec<_>>();
+    for child_node in child_nodes {
+        if child_node.node_type() != web_sys::Node::TEXT_NODE {
+            continue;
+        }
+        let is_in_vdom = children.iter().any(|child| match child {
+            Node::Text(text) => text
+                .node_ws
+                .as_ref()
+                .map_or(false, |node_ws| node_ws.is_same_node(Some(&child_node))),
+            _ => false,
+        });
+        if !is_in_vdom {
+            virtual_dom_bridge::remove_node(&child_node, parent);
+        }
+    }
+
+    for child in children {
+        match child {
+            Node::Text(text) => {
+                if let Some(node_ws) = &text.node_ws {
+                    if node_ws.text_content().unwrap_or_default() != text.text {
+                        node_ws.set_text_content(Some(&text.text));
+                    }
+                }
+            }
+            Node::Element(el) => {
+                if let Some(node_ws) = &el.node_ws {
+                    sync_normalized_text(&el.children, node_ws);
+                }
+            }
+            Node::Empty => (),
+        }
+    }
+}
+
+fn describe<Ms>(node: &Node<Ms>) -> String {
+    match node {
+        Node::Element(el) => format!("<{}>", el.tag.as_str().to_lowercase()),
+        Node::Text(text) => format!("text {:?}", text.text),
+        Node::Empty => "nothing".to_owned(),
+    }
+}
+
+fn non_empty<Ms>(nodes: &[Node<Ms>]) -> Vec<&Node<Ms>> {
+    nodes
+        .iter()
+        .filter(|node| match node {
+            Node::Empty => false,
+            _ => true,
+        })
+        .collect()
+}
+
+fn child_path<Ms>(path: &str, child: &Node<Ms>, index: usize) -> String {
+    format!("{} > {}[{}]", path, describe(child), index)
+}
+
+/// Describe the difference in numbers, kinds or tags of `old` and `new` children.
+/// Texts aren't compared.
+fn structural_mismatch<Ms>(path: &str, old: &[Node<Ms>], new: &[&Node<Ms>]) -> Option<String> {
+    if old.len() != new.len() {
+        return Some(format!(
+            "{}: {} server-rendered children, {} rendered children",
+            path,
+            old.len(),
+            new.len()
+        ));
+    }
+    for (index, (old_child, new_child)) in old.iter().zip(new).enumerate() {
+        let is_same_kind = match (old_child, new_child) {
+            // Any element can be adopted as a `hydrate_lazy` placeholder.
+            (Node::Element(_), Node::Element(new_el)) if new_el.lazy_hydration.is_some() => true,
+            (Node::Element(old_el), Node::Element(new_el)) => {
+                old_el.tag == new_el.tag && old_el.namespace == new_el.namespace
+            }
+            (Node::Text(_), Node::Text(_)) => true,
+            _ => false,
+        };
+        if !is_same_kind {
+            return Some(format!(
+                "{}: server rendered {}, rendered {}",
+                child_path(path, old_child, index),
+                describe(old_child),
+                describe(new_child)
+            ));
+        }
+    }
+    None
+}
+
+/// Describe the first structural difference (tags, texts or numbers of children)
+/// between the adopted vdom `old` and the first render `new`.
+pub(crate) fn first_mismatch<Ms>(old: &[Node<Ms>], new: &[Node<Ms>]) -> Option<String> {
+    fn mismatch_in<Ms>(path: &str, old: &[Node<Ms>], new: &[Node<Ms>]) -> Option<String> {
+        let new = non_empty(new);
+        if let Some(mismatch) = structural_mismatch(path, old, &new) {
+            return Some(mismatch);
+        }
+        for (index, (old_child, new_child)) in old.iter().zip(new).enumerate() {
+            let child_path = child_path(path, old_child, index);
+            match (old_child, new_child) {
+                (Node::Element(_), Node::Element(new_el)) if new_el.lazy_hydration.is_some() => (),
+                (Node::Element(old_el), Node::Element(new_el)) => {
+                    if let Some(mismatch) =
+                        mismatch_in(&child_path, &old_el.children, &new_el.children)
+                    {
+                        return Some(mismatch);
+                    }
+                }
+                (Node::Text(old_text), Node::Text(new_text)) if old_text.text != new_text.text => {
+                    return Some(format!(
+                        "{}: server rendered {}, rendered {}",
+                        child_path,
+                        describe(old_child),
+                        describe(new_child)
+                    ))
+                }
+                _ => (),
+            }
+        }
+        None
+    }
+
+    mismatch_in("mount point", old, new)
+}
+
+/// Prepare the adopted vdom `old` for patching by the first render `new` - see `MountType::Hydrate`.
+/// Children of elements with a different structure are removed (with a warning),
+/// so patching recreates them. Other nodes are kept and patching only fixes their differences.
+/// Elements in the place of `hydrate_lazy` placeholders become the placeholders.
+pub(crate) fn hydrate<Ms>(old: &mut El<Ms>, new: &mut El<Ms>, mount_point: &web_sys::Element) {
+    fn hydrate_children<Ms>(
+        path: &str,
+        old: &mut Vec<Node<Ms>>,
+        new: &mut [Node<Ms>],
+        parent: &web_sys::Node,
+    ) {
+        let mut new = new
+            .iter_mut()
+            .filter(|node| match node {
+                Node::Empty => false,
+                _ => true,
+            })
+            .collect::<Vec<_>>();
+        if let Some(mismatch) = structural_mismatch(
+            path,
+            old,
+            &new.iter().map(|node| &**node).collect::<Vec<_>>(),
+        ) {
+            web_sys::console::warn_1(
+                &format!(
+                    "The server-rendered HTML doesn't match the view ({}) - the children will be recreated.",
+                    mismatch
+                )
+                .into(),
+            );
+            for child in old.drain(..) {
+                let node_ws = match child {
+                    Node::Element(el) => el.node_ws,
+                    Node::Text(text) => text.node_ws,
+                    Node::Empty => None,
+                };
+                if let Some(node_ws) = node_ws {
+                    virtual_dom_bridge::remove_node(&node_ws, parent);
+                }
+            }
+            return;
+        }
+        for (index, (old_child, new_child)) in old.iter_mut().zip(new.iter_mut()).enumerate() {
+            let child_path = child_path(path, old_child, index);
+            if let (Node::Element(old_el), Node::Element(new_el)) = (old_child, &mut **new_child) {
+                if new_el.lazy_hydration.is_some() {
+                    lazy_hydration::adopt_server_markup(old_el, new_el);
+                } else if let Some(node_ws) = old_el.node_ws.clone() {
+                    hydrate_children(
+                        &child_path,
+                        &mut old_el.children,
+                        &mut new_el.children,
+                        &node_ws,
+                    );
+                }
+            }
+        }
+    }
+
+    hydrate_children(
+        "mount point",
+        &mut old.children,
+        &mut new.children,
+        mount_point,
+    );
+}
+
+/// Adopt the mount point's children - see the module docs.
+pub(crate) fn adopt_mount_point<Ms>(mount_point: &web_sys::Element) -> El<Ms> {
+    let mut root = El::empty(Tag::Placeholder);
+    root.children = adopt_children(mount_point, false);
+    root
+}
@@ -0,0 +1,39 @@
+//! Trusted HTML content of elements. See `inner_html`.
+
+use super::El;
+
+/// Element content created by `inner_html`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InnerHtml(pub String);
+
+/// Set the element's content to trusted `html` - e.g. sanitized Markdown output.
+///
+/// Unlike `raw!` (which parses HTML into nodes), the content is written by `innerHTML`
+/// and it isn't diffed - it's written again only when `html` changes.
+/// - Children of the element are ignored.
+/// - Listeners aren't attached to the content's elements - listen on the element itself
+///   and check the event's target.
+/// - The HTML isn't sanitized - never pass untrusted input.
+///
+/// # Example
+///
+/// ```rust,no_run
+///article![class!["post"], inner_html(&model.post_html)]
+/// ```
+pub fn inner_html(html: impl Into<String>) -> InnerHtml {
+    InnerHtml(html.into())
+}
+
+/// Remove children of an element with `inner_html` - its content isn't managed by the vdom.
+pub(crate) fn drop_children<Ms>(el: &mut El<Ms>) {
+    if el.inner_html.is_some() && !el.children.is_empty() {
+        web_sys::console::warn_1(
+            &format!(
+                "Children of `{}` with `inner_html` are ignored.",
+                el.tag.as_str()
+            )
+            .into(),
+        );
+        el.children.clear();
+    }
+}
@@ -0,0 +1,187 @@
+//! Subtrees which keep their DOM (and its state - e.g. scroll positions) while they are hidden.
+//!
+//! A hidden subtree is replaced by a lightweight placeholder (an empty `<template>`)
+//! and its DOM is parked in a `DocumentFragment` owned by the app. When the subtree is shown
+//! again, the parked DOM is reinserted and patched against the freshly built vdom.
+
+use super::{
+    patch,
+    renderer::{self, Renderers},
+    El, Node, Tag,
+};
+#[cfg(all(feature = "debug-tools", debug_assertions))]
+use crate::app::memory_growth::NodeCounts;
+use crate::browser::dom::virtual_dom_bridge;
+use std::collections::HashMap;
+use wasm_bindgen::JsCast;
+use web_sys::Document;
+
+/// Keep-alive marker of an element created by `keep_alive`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeepAlive {
+    pub key: String,
+    /// `false` for placeholders of hidden subtrees.
+    pub active: bool,
+}
+
+/// Render `view` only while `cond` is `true`. Unlike `if cond { view() } else { empty![] }`,
+/// DOM nodes created by `view` aren't removed when `cond` becomes `false` - they are parked
+/// and reused once `cond` is `true` again. `key` has to be unique in the app.
+///
+/// Parked DOM is kept until `orders.drop_kept_alive(key)` is called or the app is unmounted.
+///
+/// _Note:_ Only elements are kept alive - text nodes are always rendered from scratch.
+///
+/// # Example
+///
+/// ```rust,no_run
+///div![
+///    keep_alive(model.page == Page::Feed, "feed", || feed::view(&model.feed)),
+///    keep_alive(model.page == Page::Video, "video", || video::view(&model.video)),
+///]
+/// ```
+pub fn keep_alive<Ms>(
+    cond: bool,
+    key: impl Into<String>,
+    view: impl FnOnce() -> Node<Ms>,
+) -> Node<Ms> {
+    let key = key.into();
+    if cond {
+        let mut node = view();
+        if let Node::Element(el) = &mut node {
+            el.keep_alive = Some(KeepAlive { key, active: true });
+        }
+        node
+    } else {
+        let mut placeholder = El::empty(Tag::Template);
+        placeholder.keep_alive = Some(KeepAlive { key, active: false });
+        Node::Element(placeholder)
+    }
+}
+
+// ------ Parking ------
+
+/// Parked subtree with the state which the browser doesn't preserve for detached nodes.
+pub(crate) struct Parked<Ms: 'static> {
+    el: El<Ms>,
+    fragment: web_sys::DocumentFragment,
+    scroll_positions: Vec<(web_sys::Element, i32, i32)>,
+    playing_media: Vec<web_sys::HtmlMediaElement>,
+    /// Counted when parked, so `seed::debug::memory_report` doesn't walk parked subtrees.
+    #[cfg(all(feature = "debug-tools", debug_assertions))]
+    pub(crate) counts: NodeCounts,
+}
+
+impl<Ms> Parked<Ms> {
+    /// The parked element. Its DOM is in the detached fragment.
+    pub(crate) fn el(&self) -> &El<Ms> {
+        &self.el
+    }
+}
+
+pub(crate) type ParkedEls<Ms> = HashMap<String, Parked<Ms>>;
+
+/// Replace `old`'s DOM with `placeholder`'s DOM and park `old`.
+pub(crate) fn park<Ms>(
+    document: &Document,
+    mut old: El<Ms>,
+    placeholder: &mut El<Ms>,
+    parent: &web_sys::Node,
+) -> Parked<Ms> {
+    let old_ws = old.node_ws.take().expect("Missing websys el when parking");
+    // Browsers reset scroll positions and pause media when nodes leave the document.
+    let (scroll_positions, playing_media) = match old_ws.dyn_ref::<web_sys::Element>() {
+        Some(old_el_ws) => (scroll_positions(old_el_ws), playing_media(old_el_ws)),
+        None => (Vec::new(), Vec::new()),
+    };
+
+    placeholder.node_ws = Some(virtual_dom_bridge::make_websys_el(placeholder, document));
+    let placeholder_ws = placeholder.node_ws.as_ref().expect("Missing websys el");
+    virtual_dom_bridge::replace_child(placeholder_ws, &old_ws, parent);
+
+    let fragment = document.create_document_fragment();
+    fragment
+        .append_child(&old_ws)
+        .expect("Problem parking element");
+    old.node_ws = Some(old_ws);
+
+    Parked {
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        counts: NodeCounts::of_el(&old),
+        el: old,
+        fragment,
+        scroll_positions,
+        playing_media,
+    }
+}
+
+/// Replace `placeholder`'s DOM with the parked DOM and return the parked vdom.
+pub(crate) fn unpark<Ms>(
+    parked: Parked<Ms>,
+    placeholder: &El<Ms>,
+    parent: &web_sys::Node,
+) -> El<Ms> {
+    let parked_ws = parked
+        .el
+        .node_ws
+        .as_ref()
+        .expect("Missing parked websys el");
+    let placeholder_ws = placeholder
+        .node_ws
+        .as_ref()
+        .expect("Missing websys el of keep-alive placeholder");
+    virtual_dom_bridge::replace_child(parked_ws, placeholder_ws, parent);
+
+    for (element, top, left) in parked.scroll_positions {
+        element.set_scroll_top(top);
+        element.set_scroll_left(left);
+    }
+    for media in parked.playing_media {
+        // Autoplay policy may reject it; the media just stays paused then.
+        let _ = media.play();
+    }
+    parked.el
+}
+
+/// Remove the parked DOM and invoke `will_unmount` hook.
+pub(crate) fn drop_parked<Ms>(mut parked: Parked<Ms>, renderers: &Renderers<Ms>) {
+    renderer::remove_all(&parked.el, renderers);
+    let parked_ws = parked.el.node_ws.take().expect("Missing parked websys el");
+    patch::remove_node(&parked_ws, &parked.fragment, &mut parked.el);
+}
+
+fn scroll_positions(root: &web_sys::Element) -> Vec<(web_sys::Element, i32, i32)> {
+    let mut elements = vec![root.clone()];
+    let descendants = root
+        .query_selector_all("*")
+        .expect("Problem selecting descendants");
+    elements.extend(
+        (0..descendants.length())
+            .filter_map(|index| descendants.item(index))
+            .filter_map(|node| node.dyn_into::<web_sys::Element>().ok()),
+    );
+    elements
+        .into_iter()
+        .filter(|element| element.scroll_top() != 0 || element.scroll_left() != 0)
+        .map(|element| {
+            let (top, left) = (element.scroll_top(), element.scroll_left());
+            (element, top, left)
+        })
+        .collect()
+}
+
+fn playing_media(root: &web_sys::Element) -> Vec<web_sys::HtmlMediaElement> {
+    let mut media = Vec::new();
+    if let Some(root_media) = root.dyn_ref::<web_sys::HtmlMediaElement>() {
+        media.push(root_media.clone());
+    }
+    let descendants = root
+        .query_selector_all("audio, video")
+        .expect("Problem selecting media elements");
+    media.extend(
+        (0..descendants.length())
+            .filter_map(|index| descendants.item(index))
+            .filter_map(|node| node.dyn_into::<web_sys::HtmlMediaElement>().ok()),
+    );
+    media.into_iter().filter(|media| !media.paused()).collect()
+}
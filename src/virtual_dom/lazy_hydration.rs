@@ -0,0 +1,286 @@
+//! Incremental hydration of below-the-fold content. See `hydrate_lazy`.
+//!
+//! A deferred subtree is rendered as an empty placeholder (`<div data-seed-lazy-hydration="key">`)
+//! until the placeholder approaches the viewport or `Orders::force_hydrate` is called.
+//! Then the key is marked as hydrated and the app is re-rendered - the subtree's `view` is called
+//! and its listeners are attached as usual from then on.
+//!
+//! Placeholders are watched by an `IntersectionObserver` while they are in the vdom.
+//! Browsers without it (see `seed::browser::Capabilities`) hydrate placeholders right after
+//! they are rendered.
+//! Every app has its own hydrated keys and they are never dehydrated.
+//!
+//! When the app hydrates server-rendered HTML (`MountType::Hydrate`), the server markup
+//! in the placeholder's position becomes the placeholder - it stays visible until the key
+//! is hydrated and the subtree is rendered by `view`.
+
+use super::{El, Node, Tag, View};
+use crate::app::App;
+use crate::browser::Capability;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+use wasm_bindgen::{closure::Closure, JsCast};
+use wasm_bindgen_futures::spawn_local;
+
+/// Attribute of placeholders with the key of their deferred subtree.
+pub const LAZY_HYDRATION_ATTR: &str = "data-seed-lazy-hydration";
+
+/// Placeholders are hydrated a bit before they scroll into the viewport.
+const OBSERVER_ROOT_MARGIN: &str = "200px";
+
+type HydratedKeys = Rc<RefCell<HashSet<String>>>;
+
+thread_local! {
+    // Hydrated keys of the app whose `view` is running - see `with_hydrated_keys`.
+    static RENDERING: RefCell<Option<HydratedKeys>> = RefCell::new(None);
+}
+
+/// Render `view` only once its placeholder approaches the viewport or `orders.force_hydrate(key)`
+/// is called. Use it for heavy sections below the fold - e.g. comments or related products -
+/// so they don't delay interactivity of the rest of the page. `key` has to be unique in the app.
+///
+/// Style the placeholder by the attribute selector `[data-seed-lazy-hydration]` (e.g. give it
+/// `min-height`) to prevent layout shifts.
+///
+/// # Example
+///
+/// ```rust,no_run
+///div![
+///    article::view(&model.article),
+///    hydrate_lazy("comments", || comments::view(&model.comments)),
+///]
+/// ```
+pub fn hydrate_lazy<Ms>(key: impl Into<String>, view: impl FnOnce() -> Node<Ms>) -> Node<Ms> {
+    let key = key.into();
+    if is_hydrated(&key) {
+        return view();
+    }
+    let mut placeholder = El::empty(Tag::Div);
+    placeholder.add_attr(LAZY_HYDRATION_ATTR, key.as_str());
+    placeholder.lazy_hydration = Some(key);
+    Node::Element(placeholder)
+}
+
+/// Has the `hydrate_lazy` subtree with `key` been hydrated in the app whose `view` is running?
+/// It's always `false` outside of `view`.
+pub fn is_hydrated(key: &str) -> bool {
+    RENDERING.with(|rendering| {
+        rendering
+            .borrow()
+            .as_ref()
+            .map_or(false, |hydrated| hydrated.borrow().contains(key))
+    })
+}
+
+/// Call `view` with the app's hydrated keys - `hydrate_lazy` reads them.
+pub(crate) fn with_hydrated_keys<Ms, R>(
+    lazy_hydration: &RefCell<LazyHydration<Ms>>,
+    view: impl FnOnce() -> R,
+) -> R {
+    let hydrated = Rc::clone(&lazy_hydration.borrow().hydrated);
+    let previous = RENDERING.with(|rendering| rendering.replace(Some(hydrated)));
+    let output = view();
+    RENDERING.with(|rendering| rendering.replace(previous));
+    output
+}
+
+// ------ Observing ------
+
+struct Observed {
+    element: web_sys::Element,
+    observer: web_sys::IntersectionObserver,
+    _callback: Closure<dyn FnMut(js_sys::Array)>,
+}
+
+impl Drop for Observed {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+    }
+}
+
+pub(crate) struct LazyHydration<Ms> {
+    hydrated: HydratedKeys,
+    /// Observers of rendered placeholders by their keys.
+    observed: HashMap<String, Observed>,
+    pub(crate) handlers: Vec<Box<dyn Fn(String) -> Ms>>,
+}
+
+impl<Ms> Default for LazyHydration<Ms> {
+    fn default() -> Self {
+        Self {
+            hydrated: HydratedKeys::default(),
+            observed: HashMap::new(),
+            handlers: Vec::new(),
+        }
+    }
+}
+
+impl<Ms> LazyHydration<Ms> {
+    /// Disconnect all observers - their callbacks hold the app - and drop handlers.
+    /// See `App::unmount`.
+    pub(crate) fn stop(&mut self) {
+        self.observed.clear();
+        self.handlers.clear();
+    }
+}
+
+// ------ Server markup ------
+
+/// Make the server-rendered element `old` the placeholder `new` (see `hydration::hydrate`).
+/// `new` gets the element's tag and attributes, so patching only adds the placeholder's
+/// attribute, and the element's children are left in the DOM outside of the vdom.
+/// The element is replaced once the key is hydrated - see `patch::patch_el`.
+pub(crate) fn adopt_server_markup<Ms>(old: &mut El<Ms>, new: &mut El<Ms>) {
+    old.children.clear();
+    new.tag = old.tag.clone();
+    new.namespace = old.namespace.clone();
+    let key = new.lazy_hydration.clone().unwrap_or_default();
+    new.attrs = old.attrs.clone();
+    new.style = old.style.clone();
+    new.add_attr(LAZY_HYDRATION_ATTR, key);
+}
+
+/// Start observing all placeholders in a created subtree.
+pub(crate) fn observe_all<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    el: &El<Ms>,
+    app: &App<Ms, Mdl, ElC, GMs>,
+) {
+    for_each_placeholder(el, &mut |key, element| observe(app, key, element));
+}
+
+/// Stop observing all placeholders in a removed subtree.
+pub(crate) fn unobserve_all<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    el: &El<Ms>,
+    app: &App<Ms, Mdl, ElC, GMs>,
+) {
+    for_each_placeholder(el, &mut |key, element| {
+        let mut lazy_hydration = app.data.lazy_hydration.borrow_mut();
+        // A new placeholder with the same key may have been created before the old one's removal.
+        let is_observed = lazy_hydration.observed.get(key).map_or(false, |observed| {
+            observed.element.is_same_node(Some(element))
+        });
+        if is_observed {
+            lazy_hydration.observed.remove(key);
+        }
+    });
+}
+
+fn for_each_placeholder<Ms>(el: &El<Ms>, f: &mut impl FnMut(&str, &web_sys::Element)) {
+    if let Some(key) = &el.lazy_hydration {
+        if let Some(element) = el
+            .node_ws
+            .as_ref()
+            .and_then(|node| node.dyn_ref::<web_sys::Element>())
+        {
+            f(key, element);
+        }
+        return;
+    }
+    for child in &el.children {
+        if let Node::Element(child_el) = child {
+            for_each_placeholder(child_el, f);
+        }
+    }
+}
+
+fn observe<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    key: &str,
+    element: &web_sys::Element,
+) {
+    if !app.cfg.capabilities.has(Capability::IntersectionObserver) {
+        let app = app.clone();
+        let key = key.to_owned();
+        // We are inside of patching - hydrate when it's done.
+        spawn_local(async move {
+            if app.data.unmounted.get() {
+                return;
+            }
+            let (_, msgs) = hydrate(&app, &key);
+            for msg in msgs {
+                app.update(msg);
+            }
+            app.schedule_render();
+        });
+        return;
+    }
+
+    let callback = Closure::wrap(Box::new({
+        let app = app.clone();
+        let key = key.to_owned();
+        move |entries: js_sys::Array| {
+            let is_intersecting = entries
+                .iter()
+                .filter_map(|entry| entry.dyn_into::<web_sys::IntersectionObserverEntry>().ok())
+                .any(|entry| entry.is_intersecting());
+            if !is_intersecting {
+                return;
+            }
+            let (observed, msgs) = hydrate(&app, &key);
+            // We are inside of its callback - drop it later.
+            spawn_local(async move { drop(observed) });
+            for msg in msgs {
+                app.update(msg);
+            }
+            app.schedule_render();
+        }
+    }) as Box<dyn FnMut(js_sys::Array)>);
+
+    let mut observer_init = web_sys::IntersectionObserverInit::new();
+    observer_init.root_margin(OBSERVER_ROOT_MARGIN);
+    let observer = web_sys::IntersectionObserver::new_with_options(
+        callback.as_ref().unchecked_ref(),
+        &observer_init,
+    )
+    .expect("Problem creating IntersectionObserver");
+    observer.observe(element);
+
+    app.data.lazy_hydration.borrow_mut().observed.insert(
+        key.to_owned(),
+        Observed {
+            element: element.clone(),
+            observer,
+            _callback: callback,
+        },
+    );
+}
+
+// ------ Hydration ------
+
+/// Mark `key` as hydrated. The caller has to re-render the app.
+///
+/// Returns the placeholder's observer, so the caller can choose when to drop it,
+/// and messages from hydration handlers - they have to be sent by the caller
+/// because `update` may be running.
+#[must_use]
+fn hydrate<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    key: &str,
+) -> (Option<Observed>, Vec<Ms>) {
+    let mut lazy_hydration = app.data.lazy_hydration.borrow_mut();
+    let newly_hydrated = lazy_hydration.hydrated.borrow_mut().insert(key.to_owned());
+    let observed = lazy_hydration.observed.remove(key);
+    let msgs = if newly_hydrated {
+        lazy_hydration
+            .handlers
+            .iter()
+            .map(|handler| handler(key.to_owned()))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    (observed, msgs)
+}
+
+/// Hydrate `key` from `update`. See `Orders::force_hydrate`.
+pub(crate) fn force_hydrate<Ms: 'static, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    app: &App<Ms, Mdl, ElC, GMs>,
+    key: &str,
+) -> Vec<Ms> {
+    let (observed, msgs) = hydrate(app, key);
+    drop(observed);
+    msgs
+}
@@ -5,7 +5,8 @@ use enclose::enclose;
 use std::{fmt, mem};
 use wasm_bindgen::{closure::Closure, JsCast};
 
-type EventHandler<Ms> = Box<dyn FnMut(web_sys::Event) -> Ms>;
+/// Handler returns `None` when the event should be ignored (e.g. it has an unexpected type).
+type EventHandler<Ms> = Box<dyn FnMut(web_sys::Event) -> Option<Ms>>;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Category {
@@ -16,6 +17,7 @@ pub enum Category {
     Pointer,
     Raw,
     Simple,
+    Typed,
 }
 
 /// Ev-handling for Elements
@@ -107,8 +109,9 @@ impl<Ms> Listener<Ms> {
         let mut handler = self.handler.take().expect("Can't find old handler");
         // This is the closure ran when a DOM element has an user defined callback
         let closure = Closure::new(move |event: web_sys::Event| {
-            let msg = handler(event);
-            mailbox.send(msg);
+            if let Some(msg) = handler(event) {
+                mailbox.send(msg);
+            }
         });
 
         (el_ws.as_ref() as &web_sys::EventTarget)
@@ -123,6 +126,8 @@ impl<Ms> Listener<Ms> {
         if self.closure.replace(closure).is_some() {
             panic!("self.closure already set in attach");
         }
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        crate::debug::memory::listener_attached();
     }
 
     pub fn detach<T>(&mut self, el_ws: &T)
@@ -137,6 +142,8 @@ impl<Ms> Listener<Ms> {
                 closure.as_ref().unchecked_ref(),
             )
             .expect("Problem removing listener from element");
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        crate::debug::memory::listener_detached();
     }
 }
 
@@ -158,7 +165,7 @@ impl<Ms: 'static, OtherMs: 'static> MessageMapper<Ms, OtherMs> for Listener<Ms>
             handler: self.handler.map(enclose!((f) |mut eh| {
                 Box::new(move |event| {
                     let m = (*eh)(event);
-                    (f.clone())(m)
+                    m.map(f.clone())
                 }) as EventHandler<OtherMs>
             })),
             closure: self.closure,
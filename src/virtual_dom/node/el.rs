@@ -1,10 +1,15 @@
-use super::super::{At, AtValue, Attrs, CSSValue, Listener, Node, St, Style, Tag, Text};
+use super::super::{
+    el_ref::SharedNodeWs, ActiveLink, At, AtValue, Attrs, CSSValue, ElRef, KeepAlive, Listener,
+    Node, St, Style, Tag, Text, TextNormalization,
+};
 use crate::app::MessageMapper;
 use crate::browser::{
-    dom::{virtual_dom_bridge, LifecycleHooks, Namespace},
+    dom::{dialog::OpenState, flip::FlipGroup, virtual_dom_bridge, LifecycleHooks, Namespace},
     util,
 };
-use std::borrow::Cow;
+#[cfg(all(feature = "debug-tools", debug_assertions))]
+use crate::debug::Source;
+use std::{borrow::Cow, rc::Rc};
 
 /// A component in our virtual DOM.
 /// [MDN reference](https://developer.mozilla.org/en-US/docs/Web/API/Element)
@@ -23,6 +28,32 @@ pub struct El<Ms: 'static> {
     pub node_ws: Option<web_sys::Node>,
     pub namespace: Option<Namespace>,
     pub hooks: LifecycleHooks<Ms>,
+    pub keep_alive: Option<KeepAlive>,
+    /// Name of the `Renderer` which renders children. See `rendered_by`.
+    pub renderer: Option<&'static str>,
+    /// Key of the placeholder's deferred subtree. See `hydrate_lazy`.
+    pub lazy_hydration: Option<String>,
+    /// Active state of a link. See `active_link`.
+    pub active_link: Option<ActiveLink>,
+    /// Rendered state of a dialog or popover. See `dialog_open`.
+    pub open_state: Option<OpenState>,
+    /// Text normalization of the element's subtree. See `TextNormalization`.
+    pub text_normalization: Option<TextNormalization>,
+    /// Key identifying the element among its siblings. See `el_key`.
+    pub key: Option<String>,
+    /// Reference to the rendered element. See `ElRef`.
+    pub el_ref: Option<SharedNodeWs>,
+    /// FLIP animation group of the element. See `flip_group`.
+    pub flip: Option<FlipGroup>,
+    /// Trusted HTML content which replaces children. See `inner_html`.
+    pub inner_html: Option<String>,
+    /// Where the element was created. See `seed::debug`.
+    #[cfg(all(feature = "debug-tools", debug_assertions))]
+    pub source: Option<Source>,
+    /// Type of the iterator `keyed_nodes` rendered the element from.
+    /// Patching checks it in debug builds - see `keyed_nodes`.
+    #[cfg(debug_assertions)]
+    pub key_source: Option<&'static str>,
 }
 
 impl<Ms: 'static, OtherMs: 'static> MessageMapper<Ms, OtherMs> for El<Ms> {
@@ -53,6 +84,20 @@ impl<Ms: 'static, OtherMs: 'static> MessageMapper<Ms, OtherMs> for El<Ms> {
             node_ws: self.node_ws,
             namespace: self.namespace,
             hooks: self.hooks.map_msg(f),
+            keep_alive: self.keep_alive,
+            renderer: self.renderer,
+            lazy_hydration: self.lazy_hydration,
+            active_link: self.active_link,
+            open_state: self.open_state,
+            text_normalization: self.text_normalization,
+            key: self.key,
+            el_ref: self.el_ref,
+            flip: self.flip,
+            inner_html: self.inner_html,
+            #[cfg(all(feature = "debug-tools", debug_assertions))]
+            source: self.source,
+            #[cfg(debug_assertions)]
+            key_source: self.key_source,
         }
     }
 }
@@ -76,6 +121,44 @@ impl<Ms> El<Ms> {
             node_ws: None,
             namespace: None,
             hooks: LifecycleHooks::new(),
+            keep_alive: None,
+            renderer: None,
+            lazy_hydration: None,
+            active_link: None,
+            open_state: None,
+            text_normalization: None,
+            key: None,
+            el_ref: None,
+            flip: None,
+            inner_html: None,
+            #[cfg(all(feature = "debug-tools", debug_assertions))]
+            source: None,
+            #[cfg(debug_assertions)]
+            key_source: None,
+        }
+    }
+
+    /// Store the place where the element was created. It does nothing without the feature
+    /// `debug-tools` or in release builds. See `set_caller_source`.
+    #[allow(unused_variables)]
+    #[inline]
+    pub fn set_source(&mut self, file: &'static str, line: u32) {
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        {
+            self.source = Some(Source { file, line });
+        }
+    }
+
+    /// Store the caller as the place where the element was created - element macros call it.
+    /// Functions marked with `#[track_caller]` pass their own callers through.
+    /// It does nothing without the feature `debug-tools` or in release builds.
+    #[track_caller]
+    #[inline]
+    pub fn set_caller_source(&mut self) {
+        #[cfg(all(feature = "debug-tools", debug_assertions))]
+        {
+            let location = std::panic::Location::caller();
+            self.set_source(location.file(), location.line());
         }
     }
 
@@ -138,6 +221,18 @@ impl<Ms> El<Ms> {
         self
     }
 
+    /// Set the key identifying the element among its siblings. See `el_key`.
+    pub fn key(&mut self, key: impl Into<String>) -> &mut Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Set the reference to the rendered element. See `ElRef`.
+    pub fn el_ref<E>(&mut self, reference: &ElRef<E>) -> &mut Self {
+        self.el_ref = Some(Rc::clone(&reference.shared_node_ws));
+        self
+    }
+
     /// Add a class. May be cleaner than `add_attr`
     pub fn add_class(&mut self, name: impl Into<Cow<'static, str>>) -> &mut Self {
         let name = name.into();
@@ -216,6 +311,20 @@ impl<Ms> Clone for El<Ms> {
             listeners: Vec::new(),
             namespace: self.namespace.clone(),
             hooks: LifecycleHooks::new(),
+            keep_alive: self.keep_alive.clone(),
+            renderer: self.renderer,
+            lazy_hydration: self.lazy_hydration.clone(),
+            active_link: self.active_link.clone(),
+            open_state: self.open_state,
+            text_normalization: self.text_normalization,
+            key: self.key.clone(),
+            el_ref: self.el_ref.clone(),
+            flip: self.flip.clone(),
+            inner_html: self.inner_html.clone(),
+            #[cfg(all(feature = "debug-tools", debug_assertions))]
+            source: self.source,
+            #[cfg(debug_assertions)]
+            key_source: self.key_source,
         }
     }
 }
@@ -1,9 +1,16 @@
 //! This module contains code related to patching the VDOM. It can be considered
 //! a subset of the `vdom` module.
 
-use super::{At, AtValue, El, Listener, Mailbox, Node, Tag, View};
-use crate::app::App;
-use crate::browser::dom::virtual_dom_bridge;
+use super::{
+    hydration, inner_html, keep_alive, lazy_hydration, renderer, At, AtValue, El, Listener,
+    Mailbox, Node, Tag, View,
+};
+use crate::app::{perf_budget, App};
+use crate::browser::dom::{content_editable, csp, virtual_dom_bridge};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    iter,
+};
 use wasm_bindgen::JsCast;
 use web_sys::{Document, Window};
 
@@ -105,7 +112,7 @@ where
     }
 }
 
-fn patch_el<'a, Ms, Mdl, ElC: View<Ms>, GMs>(
+fn patch_el<'a, Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
     document: &Document,
     mut old: El<Ms>,
     new: &'a mut El<Ms>,
@@ -113,6 +120,17 @@ fn patch_el<'a, Ms, Mdl, ElC: View<Ms>, GMs>(
     mailbox: &Mailbox<Ms>,
     app: &App<Ms, Mdl, ElC, GMs>,
 ) -> Option<&'a web_sys::Node> {
+    // Nodes of unchanged view regions (see `AppBuilder::view_with_slices`) are reused
+    // with their DOM and listeners - `old` is their copy with the same node.
+    if new.node_ws.is_some() && new.node_ws == old.node_ws {
+        return new.node_ws.as_ref();
+    }
+    // `hydrate_lazy` placeholders are kept as they are - they may be adopted server markup.
+    if old.lazy_hydration.is_some() && old.lazy_hydration == new.lazy_hydration {
+        *new = old;
+        return new.node_ws.as_ref();
+    }
+
     if old != *new {
         // At this step, we already assume we have the right element - either
         // by entering this func directly for the top-level, or recursively after
@@ -128,25 +146,34 @@ fn patch_el<'a, Ms, Mdl, ElC: View<Ms>, GMs>(
 
         // Namespaces can't be patched, since they involve create_element_ns instead of create_element.
         // Something about this element itself is different: patch it.
-        if old.tag != new.tag || old.namespace != new.namespace {
+        // Placeholders are replaced by their hydrated subtrees - adopted server markup
+        // in them isn't part of the vdom.
+        if old.tag != new.tag
+            || old.namespace != new.namespace
+            || old.lazy_hydration != new.lazy_hydration
+        {
             let old_el_ws = old.node_ws.as_ref().expect("Missing websys el");
 
             // We don't use assign_nodes directly here, since we only have access to
             // the El, not wrapping node.
-            new.node_ws = Some(virtual_dom_bridge::make_websys_el(new, document));
-            for mut child in &mut new.children {
-                virtual_dom_bridge::assign_ws_nodes(document, &mut child);
-            }
+            virtual_dom_bridge::assign_ws_nodes_to_el(document, new);
+            csp::apply_nonces(new, app.cfg.csp_nonce.as_deref());
             if let Some(unmount_actions) = &mut old.hooks.will_unmount {
                 let old_ws = old.node_ws.as_ref().expect("Missing websys el");
                 (unmount_actions.actions)(old_ws);
             }
+            renderer::remove_all(&old, &app.cfg.renderers);
+            lazy_hydration::unobserve_all(&old, app);
+            app.data.memory_counts.removed(&old);
             virtual_dom_bridge::attach_el_and_children(new, parent);
 
             let new_ws = new.node_ws.as_ref().expect("Missing websys el");
             virtual_dom_bridge::replace_child(new_ws, old_el_ws, parent);
 
             attach_listeners(new, mailbox);
+            renderer::create_all(new, &app.cfg.renderers);
+            lazy_hydration::observe_all(new, app);
+            app.data.memory_counts.inserted(new);
             // We've re-rendered this child and all children; we're done with this recursion.
             return new.node_ws.as_ref();
         } else {
@@ -170,6 +197,36 @@ fn patch_el<'a, Ms, Mdl, ElC: View<Ms>, GMs>(
     for listener in &mut new.listeners {
         listener.attach(&old_el_ws, mailbox.clone());
     }
+    app.data
+        .memory_counts
+        .listeners_replaced(old.listeners.len(), new.listeners.len());
+
+    if old.renderer.is_some() || new.renderer.is_some() {
+        patch_custom_rendered_children(document, mailbox, app, old, new, &old_el_ws);
+        new.node_ws = Some(old_el_ws);
+        return new.node_ws.as_ref();
+    }
+
+    if old.inner_html.is_some() || new.inner_html.is_some() {
+        patch_inner_html(document, mailbox, app, old, new, &old_el_ws);
+        new.node_ws = Some(old_el_ws);
+        return new.node_ws.as_ref();
+    }
+
+    // Edited `contenteditable` elements are controlled like inputs - see `content_editable_ev`.
+    let mut caret_offset = None;
+    if content_editable::is_focused(new, &old_el_ws) {
+        if content_editable::text_equals(&new.children, &old_el_ws) {
+            let adopted = hydration::adopt_children(&old_el_ws, true);
+            app.data
+                .memory_counts
+                .children_replaced(&old.children, &adopted);
+            new.children = adopted;
+            new.node_ws = Some(old_el_ws);
+            return new.node_ws.as_ref();
+        }
+        caret_offset = content_editable::caret_offset(&old_el_ws);
+    }
 
     let old_children_iter = old.children.into_iter();
     let new_children_iter = new.children.iter_mut();
@@ -183,10 +240,112 @@ fn patch_el<'a, Ms, Mdl, ElC: View<Ms>, GMs>(
         new_children_iter,
     );
 
+    if let Some(caret_offset) = caret_offset {
+        content_editable::restore_caret(&old_el_ws, caret_offset);
+    }
+
     new.node_ws = Some(old_el_ws);
     new.node_ws.as_ref()
 }
 
+/// Patch children of hosts (see `rendered_by`) - delegate them to renderers
+/// or switch between a renderer and DOM.
+fn patch_custom_rendered_children<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    document: &Document,
+    mailbox: &Mailbox<Ms>,
+    app: &App<Ms, Mdl, ElC, GMs>,
+    old: El<Ms>,
+    new: &mut El<Ms>,
+    host: &web_sys::Node,
+) {
+    let renderers = &app.cfg.renderers;
+    if old.renderer == new.renderer {
+        app.data
+            .memory_counts
+            .children_replaced(&old.children, &new.children);
+        if let Some(renderer) = renderer::find(new, renderers) {
+            renderer.update(&old, new, host);
+        }
+        return;
+    }
+
+    match old.renderer {
+        Some(_) => {
+            app.data.memory_counts.children_replaced(&old.children, &[]);
+            if let Some(renderer) = renderer::find(&old, renderers) {
+                renderer.remove(&old, host);
+            }
+        }
+        None => patch_els(
+            document,
+            mailbox,
+            app,
+            host,
+            old.children.into_iter(),
+            iter::empty(),
+        ),
+    }
+    match new.renderer {
+        Some(_) => {
+            app.data.memory_counts.children_replaced(&[], &new.children);
+            if let Some(renderer) = renderer::find(new, renderers) {
+                renderer.create(new, host);
+            }
+        }
+        None => patch_els(
+            document,
+            mailbox,
+            app,
+            host,
+            iter::empty(),
+            new.children.iter_mut(),
+        ),
+    }
+}
+
+/// Patch content of elements with `inner_html` - write changed HTML
+/// or switch between HTML and children.
+fn patch_inner_html<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    document: &Document,
+    mailbox: &Mailbox<Ms>,
+    app: &App<Ms, Mdl, ElC, GMs>,
+    mut old: El<Ms>,
+    new: &mut El<Ms>,
+    el_ws: &web_sys::Node,
+) {
+    inner_html::drop_children(new);
+    match (old.inner_html.take(), &new.inner_html) {
+        (Some(old_html), Some(new_html)) => {
+            if old_html != *new_html {
+                virtual_dom_bridge::set_inner_html(el_ws, new_html);
+            }
+        }
+        (Some(_), None) => {
+            virtual_dom_bridge::set_inner_html(el_ws, "");
+            patch_els(
+                document,
+                mailbox,
+                app,
+                el_ws,
+                iter::empty(),
+                new.children.iter_mut(),
+            );
+        }
+        (None, Some(new_html)) => {
+            patch_els(
+                document,
+                mailbox,
+                app,
+                el_ws,
+                old.children.into_iter(),
+                iter::empty(),
+            );
+            virtual_dom_bridge::set_inner_html(el_ws, new_html);
+        }
+        (None, None) => (),
+    }
+}
+
 pub(crate) fn patch_els<'a, Ms, Mdl, ElC, GMs, OI, NI>(
     document: &Document,
     mailbox: &Mailbox<Ms>,
@@ -195,7 +354,52 @@ pub(crate) fn patch_els<'a, Ms, Mdl, ElC, GMs, OI, NI>(
     old_children_iter: OI,
     new_children_iter: NI,
 ) where
-    ElC: View<Ms>,
+    ElC: View<Ms> + 'static,
+    GMs: 'static,
+    OI: ExactSizeIterator<Item = Node<Ms>>,
+    NI: ExactSizeIterator<Item = &'a mut Node<Ms>>,
+{
+    let old_children = old_children_iter.collect::<Vec<_>>();
+    let new_children = new_children_iter.collect::<Vec<_>>();
+
+    #[cfg(debug_assertions)]
+    {
+        if let Some(warning) = super::el_key::reorder_warning(&old_children, &new_children) {
+            web_sys::console::warn_1(&warning.into());
+        }
+    }
+
+    if has_unique_keys(&old_children, &new_children) {
+        patch_keyed_els(
+            document,
+            mailbox,
+            app,
+            old_el_ws,
+            old_children,
+            new_children,
+        );
+    } else {
+        patch_positional_els(
+            document,
+            mailbox,
+            app,
+            old_el_ws,
+            old_children.into_iter(),
+            new_children.into_iter(),
+        );
+    }
+}
+
+fn patch_positional_els<'a, Ms, Mdl, ElC, GMs, OI, NI>(
+    document: &Document,
+    mailbox: &Mailbox<Ms>,
+    app: &App<Ms, Mdl, ElC, GMs>,
+    old_el_ws: &web_sys::Node,
+    old_children_iter: OI,
+    new_children_iter: NI,
+) where
+    ElC: View<Ms> + 'static,
+    GMs: 'static,
     OI: ExactSizeIterator<Item = Node<Ms>>,
     NI: ExactSizeIterator<Item = &'a mut Node<Ms>>,
 {
@@ -242,42 +446,225 @@ pub(crate) fn patch_els<'a, Ms, Mdl, ElC, GMs, OI, NI>(
 
         match child_new {
             Node::Element(child_new_el) => {
+                csp::apply_nonces(child_new_el, app.cfg.csp_nonce.as_deref());
                 virtual_dom_bridge::attach_el_and_children(child_new_el, old_el_ws);
                 attach_listeners(child_new_el, mailbox);
+                renderer::create_all(child_new_el, &app.cfg.renderers);
+                lazy_hydration::observe_all(child_new_el, app);
+                app.data.memory_counts.inserted(child_new_el);
             }
             Node::Text(child_new_text) => {
                 virtual_dom_bridge::attach_text_node(child_new_text, old_el_ws);
+                app.data.memory_counts.text_inserted();
             }
             Node::Empty => (),
         }
     }
 
     // Now purge any existing no-longer-needed children; they're not part of the new vdom.
-    // while let Some(mut child) = old_children_iter.next() {
     for child in old_children_iter {
-        match child {
-            Node::Element(mut child_el) => {
-                let child_ws = child_el.node_ws.take().expect("Missing child el_ws");
-                remove_node(&child_ws, old_el_ws, &mut child_el);
-                child_el.node_ws.replace(child_ws);
+        remove_old_child(child, old_el_ws, app);
+    }
+}
+
+/// Remove a child that isn't part of the new vdom.
+pub(crate) fn remove_old_child<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    child: Node<Ms>,
+    parent: &web_sys::Node,
+    app: &App<Ms, Mdl, ElC, GMs>,
+) {
+    match child {
+        Node::Element(mut child_el) => {
+            renderer::remove_all(&child_el, &app.cfg.renderers);
+            lazy_hydration::unobserve_all(&child_el, app);
+            app.data.memory_counts.removed(&child_el);
+            let child_ws = child_el.node_ws.take().expect("Missing child el_ws");
+            remove_node(&child_ws, parent, &mut child_el);
+            child_el.node_ws.replace(child_ws);
+        }
+        Node::Text(mut child_text) => {
+            app.data.memory_counts.text_removed();
+            let child_ws = child_text.node_ws.take().expect("Missing child node_ws");
+            virtual_dom_bridge::remove_node(&child_ws, parent);
+            child_text.node_ws.replace(child_ws);
+        }
+        Node::Empty => (),
+    }
+}
+
+// ------ Keyed children ------
+
+fn node_key<Ms>(node: &Node<Ms>) -> Option<&str> {
+    match node {
+        Node::Element(el) => el.key.as_deref(),
+        _ => None,
+    }
+}
+
+/// Should children be matched by their keys (see `el_key`)?
+/// Duplicate keys fall back to positional patching (with a warning in debug builds).
+fn has_unique_keys<Ms>(old_children: &[Node<Ms>], new_children: &[&mut Node<Ms>]) -> bool {
+    let mut new_keys = HashSet::new();
+    for key in new_children.iter().filter_map(|child| node_key(child)) {
+        if !new_keys.insert(key) {
+            #[cfg(debug_assertions)]
+            web_sys::console::warn_1(
+                &format!(
+                    "Duplicate element key \"{}\" - the siblings are patched by position.",
+                    key
+                )
+                .into(),
+            );
+            return false;
+        }
+    }
+    if new_keys.is_empty() {
+        return false;
+    }
+    // Old keys can be duplicated only if the previous render fell back to positional patching.
+    let mut old_keys = HashSet::new();
+    old_children
+        .iter()
+        .filter_map(node_key)
+        .all(|key| old_keys.insert(key))
+}
+
+/// Match new keyed children with old children by keys and unkeyed children by their positions
+/// among unkeyed children. Matched DOM nodes are patched and moved to their new positions.
+fn patch_keyed_els<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    document: &Document,
+    mailbox: &Mailbox<Ms>,
+    app: &App<Ms, Mdl, ElC, GMs>,
+    old_el_ws: &web_sys::Node,
+    old_children: Vec<Node<Ms>>,
+    new_children: Vec<&mut Node<Ms>>,
+) {
+    let mut old_keyed = HashMap::new();
+    let mut old_unkeyed = VecDeque::new();
+    for child in old_children {
+        match node_key(&child).map(ToOwned::to_owned) {
+            Some(key) => {
+                old_keyed.insert(key, child);
             }
-            Node::Text(mut child_text) => {
-                let child_ws = child_text.node_ws.take().expect("Missing child node_ws");
-                virtual_dom_bridge::remove_node(&child_ws, old_el_ws);
-                child_text.node_ws.replace(child_ws);
+            None => old_unkeyed.push_back(child),
+        }
+    }
+
+    // Moving a node blurs the focused element inside it.
+    let focused = document.active_element();
+
+    let mut last_visited_node: Option<web_sys::Node> = None;
+    for child_new in new_children {
+        let child_old = match node_key(child_new) {
+            Some(key) => old_keyed.remove(key),
+            None => old_unkeyed.pop_front(),
+        }
+        // There is no matching child - create a new one.
+        .unwrap_or(Node::Empty);
+
+        let next_node = match last_visited_node.as_ref() {
+            Some(node) => node.next_sibling(),
+            None => old_el_ws.first_child(),
+        };
+        if let Some(new_el_ws) = patch(
+            document,
+            child_old,
+            child_new,
+            old_el_ws,
+            next_node.clone(),
+            mailbox,
+            app,
+        ) {
+            // Created nodes are inserted before `next_node`, but patched nodes stay
+            // at the old child's position - move them after the previously patched node.
+            let in_place =
+                next_node.as_ref() == Some(new_el_ws) || new_el_ws.next_sibling() == next_node;
+            if !in_place {
+                virtual_dom_bridge::insert_node(new_el_ws, old_el_ws, next_node);
+            }
+            last_visited_node = Some(new_el_ws.clone());
+        }
+    }
+
+    for (_, child) in old_keyed {
+        remove_old_child(child, old_el_ws, app);
+    }
+    for child in old_unkeyed {
+        remove_old_child(child, old_el_ws, app);
+    }
+
+    if let Some(focused) = focused {
+        if focused.is_connected() && document.active_element().as_ref() != Some(&focused) {
+            if let Some(focused) = focused.dyn_ref::<web_sys::HtmlElement>() {
+                focused
+                    .focus()
+                    .expect("Problem restoring focus after moving keyed nodes");
             }
-            Node::Empty => (),
         }
     }
 }
 
+/// Park or unpark keep-alive elements (see `keep_alive`) and patch the others as usual.
+fn patch_keep_alive_el<'a, Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
+    document: &Document,
+    old: El<Ms>,
+    new: &'a mut El<Ms>,
+    parent: &web_sys::Node,
+    mailbox: &Mailbox<Ms>,
+    app: &App<Ms, Mdl, ElC, GMs>,
+) -> Option<&'a web_sys::Node> {
+    let (old_keep_alive, new_keep_alive) = match (&old.keep_alive, &new.keep_alive) {
+        (Some(old_keep_alive), Some(new_keep_alive))
+            if old_keep_alive.key == new_keep_alive.key =>
+        {
+            (old_keep_alive.active, new_keep_alive.active)
+        }
+        _ => return patch_el(document, old, new, parent, mailbox, app),
+    };
+    match (old_keep_alive, new_keep_alive) {
+        // The subtree has been hidden.
+        (true, false) => {
+            let key = new
+                .keep_alive
+                .as_ref()
+                .expect("Missing keep-alive key")
+                .key
+                .clone();
+            app.data.memory_counts.removed(&old);
+            let parked = keep_alive::park(document, old, new, parent);
+            app.data.memory_counts.inserted(new);
+            if let Some(previous) = app.data.kept_alive.borrow_mut().insert(key, parked) {
+                keep_alive::drop_parked(previous, &app.cfg.renderers);
+            }
+            new.node_ws.as_ref()
+        }
+        // The subtree has been shown again.
+        (false, true) => {
+            let key = &new.keep_alive.as_ref().expect("Missing keep-alive key").key;
+            let parked = app.data.kept_alive.borrow_mut().remove(key);
+            match parked {
+                Some(parked) => {
+                    app.data.memory_counts.removed(&old);
+                    app.data.memory_counts.inserted(parked.el());
+                    let old = keep_alive::unpark(parked, &old, parent);
+                    patch_el(document, old, new, parent, mailbox, app)
+                }
+                None => patch_el(document, old, new, parent, mailbox, app),
+            }
+        }
+        _ => patch_el(document, old, new, parent, mailbox, app),
+    }
+}
+
 // Reduces code repetition
-fn add_el_helper<Ms>(
+fn add_el_helper<Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
     new: &mut El<Ms>,
     parent: &web_sys::Node,
     next_node: Option<web_sys::Node>,
     mailbox: &Mailbox<Ms>,
+    app: &App<Ms, Mdl, ElC, GMs>,
 ) {
+    csp::apply_nonces(new, app.cfg.csp_nonce.as_deref());
     virtual_dom_bridge::attach_children(new);
     let new_ws = new
         .node_ws
@@ -292,11 +679,14 @@ fn add_el_helper<Ms>(
     new.node_ws.replace(new_ws);
     // Make sure to attach after we've replaced node_ws.
     attach_listeners(new, mailbox);
+    renderer::create_all(new, &app.cfg.renderers);
+    lazy_hydration::observe_all(new, app);
+    app.data.memory_counts.inserted(new);
 }
 
 /// Routes patching through different channels, depending on the Node variant
 /// of old and new.
-pub(crate) fn patch<'a, Ms, Mdl, ElC: View<Ms>, GMs>(
+pub(crate) fn patch<'a, Ms, Mdl, ElC: View<Ms> + 'static, GMs: 'static>(
     document: &Document,
     old: Node<Ms>,
     new: &'a mut Node<Ms>,
@@ -315,8 +705,14 @@ pub(crate) fn patch<'a, Ms, Mdl, ElC: View<Ms>, GMs>(
     match old {
         Node::Element(mut old_el) => {
             match new {
-                Node::Element(new_el) => patch_el(document, old_el, new_el, parent, mailbox, app),
+                Node::Element(new_el) => {
+                    patch_keep_alive_el(document, old_el, new_el, parent, mailbox, app)
+                }
                 Node::Text(new_text) => {
+                    renderer::remove_all(&old_el, &app.cfg.renderers);
+                    lazy_hydration::unobserve_all(&old_el, app);
+                    app.data.memory_counts.removed(&old_el);
+                    app.data.memory_counts.text_inserted();
                     // Can't just use assign_ws_nodes; borrow-checker issues.
                     new_text.node_ws = Some(
                         document
@@ -338,6 +734,9 @@ pub(crate) fn patch<'a, Ms, Mdl, ElC: View<Ms>, GMs>(
                     new_text.node_ws.as_ref()
                 }
                 Node::Empty => {
+                    renderer::remove_all(&old_el, &app.cfg.renderers);
+                    lazy_hydration::unobserve_all(&old_el, app);
+                    app.data.memory_counts.removed(&old_el);
                     let old_el_ws = old_el
                         .node_ws
                         .take()
@@ -352,7 +751,7 @@ pub(crate) fn patch<'a, Ms, Mdl, ElC: View<Ms>, GMs>(
             virtual_dom_bridge::assign_ws_nodes(document, new);
             match new {
                 Node::Element(new_el) => {
-                    add_el_helper(new_el, parent, next_node, mailbox);
+                    add_el_helper(new_el, parent, next_node, mailbox, app);
                     new_el.node_ws.as_ref()
                 }
                 Node::Text(new_text) => {
@@ -361,6 +760,7 @@ pub(crate) fn patch<'a, Ms, Mdl, ElC: View<Ms>, GMs>(
                         .as_ref()
                         .expect("new_node_ws missing when patching Empty to Text");
                     virtual_dom_bridge::insert_node(new_node_ws, parent, next_node);
+                    app.data.memory_counts.text_inserted();
                     new_text.node_ws.as_ref()
                 }
                 // If new and old are empty, we don't need to do anything.
@@ -371,12 +771,13 @@ pub(crate) fn patch<'a, Ms, Mdl, ElC: View<Ms>, GMs>(
             virtual_dom_bridge::assign_ws_nodes(document, new);
             match new {
                 Node::Element(new_el) => {
-                    add_el_helper(new_el, parent, next_node, mailbox);
+                    add_el_helper(new_el, parent, next_node, mailbox, app);
 
                     virtual_dom_bridge::remove_node(
                         &old_text.node_ws.expect("Can't find node from Text"),
                         parent,
                     );
+                    app.data.memory_counts.text_removed();
                     new_el.node_ws.as_ref()
                 }
                 Node::Empty => {
@@ -384,6 +785,7 @@ pub(crate) fn patch<'a, Ms, Mdl, ElC: View<Ms>, GMs>(
                         &old_text.node_ws.expect("Can't find old text"),
                         parent,
                     );
+                    app.data.memory_counts.text_removed();
                     None
                 }
                 Node::Text(new_text) => {
@@ -393,6 +795,7 @@ pub(crate) fn patch<'a, Ms, Mdl, ElC: View<Ms>, GMs>(
                         .expect("old_node_ws missing when changing text");
 
                     if new_text != &old_text {
+                        perf_budget::count_dom_mutation();
                         old_node_ws.set_text_content(Some(&new_text.text));
                     }
                     new_text.node_ws.replace(old_node_ws);
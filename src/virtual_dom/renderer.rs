@@ -0,0 +1,92 @@
+//! Custom renderers for vdom subtrees.
+//!
+//! Children of an element marked by `rendered_by(name)` aren't rendered into DOM by Seed -
+//! they are passed to the `Renderer` registered by `AppBuilder::renderer(name, ..)`.
+//! The element itself (the _host_) is still a regular DOM element managed by Seed,
+//! so it can be positioned, styled and can have listeners as usual.
+
+use super::{El, Node};
+use std::{collections::HashMap, rc::Rc};
+
+/// Renders children of host elements marked by `rendered_by`.
+///
+/// All methods receive the whole host element - compare `old` and `new` to find out
+/// what has been changed.
+pub trait Renderer<Ms> {
+    /// The host element has been created - render `el.children` into `host`.
+    fn create(&self, el: &El<Ms>, host: &web_sys::Node);
+
+    /// The host element has been patched.
+    fn update(&self, old: &El<Ms>, new: &El<Ms>, host: &web_sys::Node);
+
+    /// The host element is going to be removed or its children are going to be rendered
+    /// by another renderer - clean up `host`.
+    fn remove(&self, old: &El<Ms>, host: &web_sys::Node);
+}
+
+pub(crate) type Renderers<Ms> = HashMap<&'static str, Rc<dyn Renderer<Ms>>>;
+
+/// Name of the `Renderer` responsible for element's children. See `rendered_by`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RenderedBy(pub &'static str);
+
+/// Delegate rendering of element's children to the `Renderer` registered under `name`.
+///
+/// # Example
+///
+/// ```rust,no_run
+///canvas![
+///    rendered_by("text_layout"),
+///    model.paragraphs.iter().map(|paragraph| p![paragraph]),
+///]
+/// ```
+pub const fn rendered_by(name: &'static str) -> RenderedBy {
+    RenderedBy(name)
+}
+
+// ------ Lifecycle ------
+
+/// Find the renderer of a custom-rendered `el`.
+pub(crate) fn find<'a, Ms>(
+    el: &El<Ms>,
+    renderers: &'a Renderers<Ms>,
+) -> Option<&'a Rc<dyn Renderer<Ms>>> {
+    let name = el.renderer?;
+    let renderer = renderers.get(name);
+    if renderer.is_none() {
+        crate::error(format!("Renderer \"{}\" isn't registered", name));
+    }
+    renderer
+}
+
+/// Invoke `Renderer::create` for all custom-rendered elements in a new subtree.
+pub(crate) fn create_all<Ms>(el: &El<Ms>, renderers: &Renderers<Ms>) {
+    for_each_host(el, &mut |host_el, host| {
+        if let Some(renderer) = find(host_el, renderers) {
+            renderer.create(host_el, host);
+        }
+    });
+}
+
+/// Invoke `Renderer::remove` for all custom-rendered elements in a removed subtree.
+pub(crate) fn remove_all<Ms>(el: &El<Ms>, renderers: &Renderers<Ms>) {
+    for_each_host(el, &mut |host_el, host| {
+        if let Some(renderer) = find(host_el, renderers) {
+            renderer.remove(host_el, host);
+        }
+    });
+}
+
+fn for_each_host<Ms>(el: &El<Ms>, f: &mut impl FnMut(&El<Ms>, &web_sys::Node)) {
+    if el.renderer.is_some() {
+        if let Some(host) = el.node_ws.as_ref() {
+            f(el, host);
+        }
+        return;
+    }
+    for child in &el.children {
+        if let Node::Element(child_el) = child {
+            for_each_host(child_el, f);
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use super::{CSSValue, St};
 use indexmap::IndexMap;
-use std::fmt;
+use std::{fmt, mem};
 
 /// Handle Style separately from Attrs, since it commonly involves multiple parts,
 /// and has a different semantic meaning.
@@ -28,6 +28,157 @@ impl Style {
     pub fn merge(&mut self, other: Self) {
         self.vals.extend(other.vals.into_iter());
     }
+
+    /// Parse a raw style attribute value - e.g. `"color:#333;margin-top:4px !important"`.
+    ///
+    /// Element macros parse `At::Style` values, so raw styles and `style!` are merged
+    /// property by property (the later one wins) and both are patched per property.
+    /// - Semicolons in quotes and in parentheses (e.g. `url(...)`) don't end declarations.
+    /// - Comments are removed and property names (except custom properties) are lowercased.
+    /// - `!important` is kept at the end of the value.
+    /// - Invalid declarations are skipped (with a warning in debug builds).
+    pub fn parse(raw: &str) -> Self {
+        let mut style = Self::empty();
+        for declaration in split_declarations(raw) {
+            if declaration.trim().is_empty() {
+                continue;
+            }
+            match parse_declaration(&declaration) {
+                Some((name, value)) => style.add(St::from_css_name(&name), value),
+                None => {
+                    // Element macros parse styles also on the server - there is no console.
+                    #[cfg(all(debug_assertions, target_arch = "wasm32"))]
+                    web_sys::console::warn_1(
+                        &format!("Invalid style declaration \"{}\" is skipped.", declaration)
+                            .into(),
+                    );
+                }
+            }
+        }
+        style
+    }
+}
+
+// ------ Parsing ------
+
+/// Split `raw` by top-level semicolons and remove comments.
+fn split_declarations(raw: &str) -> Vec<String> {
+    let mut declarations = Vec::new();
+    let mut declaration = String::new();
+    let mut quote = None;
+    let mut depth = 0_usize;
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (_, '\\') => {
+                declaration.push(c);
+                if let Some(escaped) = chars.next() {
+                    declaration.push(escaped);
+                }
+            }
+            (Some(q), _) => {
+                declaration.push(c);
+                if c == q {
+                    quote = None;
+                }
+            }
+            (None, '"') | (None, '\'') => {
+                declaration.push(c);
+                quote = Some(c);
+            }
+            (None, '/') if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = None;
+                for c in chars.by_ref() {
+                    if previous == Some('*') && c == '/' {
+                        break;
+                    }
+                    previous = Some(c);
+                }
+            }
+            (None, '(') => {
+                declaration.push(c);
+                depth += 1;
+            }
+            (None, ')') => {
+                declaration.push(c);
+                depth = depth.saturating_sub(1);
+            }
+            (None, ';') if depth == 0 => {
+                declarations.push(mem::replace(&mut declaration, String::new()))
+            }
+            _ => declaration.push(c),
+        }
+    }
+    declarations.push(declaration);
+    declarations
+}
+
+/// Returns the property name and the value, or `None` when the declaration is invalid.
+fn parse_declaration(declaration: &str) -> Option<(String, String)> {
+    let colon = declaration.find(':')?;
+    let name = declaration[..colon].trim();
+    let value = declaration[colon + 1..].trim();
+
+    let is_custom = name.starts_with("--");
+    let valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        && if is_custom {
+            name.len() > 2
+        } else {
+            !name.starts_with(|c: char| c.is_ascii_digit())
+        };
+    if !valid_name || value.is_empty() || !is_balanced(value) {
+        return None;
+    }
+    let name = if is_custom {
+        name.to_owned()
+    } else {
+        name.to_ascii_lowercase()
+    };
+
+    // `red!important` and `red ! important` are normalized to `red !important`.
+    let lowercase = value.to_ascii_lowercase();
+    if lowercase.ends_with("important") {
+        let before = value[..value.len() - "important".len()].trim_end();
+        if before.ends_with('!') {
+            let base = before[..before.len() - 1].trim_end();
+            if base.is_empty() {
+                return None;
+            }
+            return Some((name, format!("{} !important", base)));
+        }
+    }
+    Some((name, value.to_owned()))
+}
+
+/// Quotes are closed and parentheses are balanced.
+fn is_balanced(value: &str) -> bool {
+    let mut quote = None;
+    let mut depth = 0_usize;
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (_, '\\') => {
+                chars.next();
+            }
+            (Some(q), _) => {
+                if c == q {
+                    quote = None;
+                }
+            }
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => match depth.checked_sub(1) {
+                Some(new_depth) => depth = new_depth,
+                None => return false,
+            },
+            _ => (),
+        }
+    }
+    quote.is_none() && depth == 0
 }
 
 /// Output style as a string, as would be set in the DOM as the attribute value
@@ -49,3 +200,116 @@ impl fmt::Display for Style {
         write!(f, "{}", string)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn declarations(style: &Style) -> Vec<(String, String)> {
+        style
+            .vals
+            .iter()
+            .filter_map(|(name, value)| match value {
+                CSSValue::Some(value) => Some((name.as_str().to_owned(), value.clone())),
+                CSSValue::Ignored => None,
+            })
+            .collect()
+    }
+
+    fn parsed(raw: &str) -> Vec<(String, String)> {
+        declarations(&Style::parse(raw))
+    }
+
+    fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected
+            .iter()
+            .map(|(name, value)| ((*name).to_owned(), (*value).to_owned()))
+            .collect()
+    }
+
+    #[wasm_bindgen_test]
+    fn semicolons_in_quotes_and_parentheses() {
+        assert_eq!(
+            parsed(r#"content: "a;b"; font-family: 'X;Y', serif"#),
+            pairs(&[("content", r#""a;b""#), ("font-family", "'X;Y', serif")])
+        );
+        assert_eq!(
+            parsed("background: url(data:image/png;base64,AAA=) no-repeat;color:red"),
+            pairs(&[
+                ("background", "url(data:image/png;base64,AAA=) no-repeat"),
+                ("color", "red")
+            ])
+        );
+        assert_eq!(
+            parsed(r#"content: "a\";b";"#),
+            pairs(&[("content", r#""a\";b""#)])
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn important_and_comments() {
+        assert_eq!(
+            parsed("color: red!important; margin: 0 ! IMPORTANT; top: 1px /* x; y */;"),
+            pairs(&[
+                ("color", "red !important"),
+                ("margin", "0 !important"),
+                ("top", "1px")
+            ])
+        );
+        assert_eq!(parsed("/* only; a comment */"), pairs(&[]));
+    }
+
+    #[wasm_bindgen_test]
+    fn names_normalized() {
+        assert_eq!(
+            parsed("Margin-Top: 4px; --Main-Color: #333; --x:1"),
+            pairs(&[
+                ("margin-top", "4px"),
+                ("--Main-Color", "#333"),
+                ("--x", "1")
+            ])
+        );
+        assert_eq!(
+            Style::parse("COLOR:red").vals.keys().next(),
+            Some(&St::Color)
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn invalid_declarations_skipped() {
+        assert_eq!(
+            parsed(
+                "color:red; margin; width:; 1x:2; --:3; a b:4; \
+                 background:url(x; top:1px; content:'a; left:2px"
+            ),
+            pairs(&[("color", "red")])
+        );
+        assert_eq!(
+            parsed("height:2px; !important:1; width:!important; margin:0;"),
+            pairs(&[("height", "2px"), ("margin", "0")])
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn raw_style_merged_with_style_macro() {
+        let node: Node<()> = div![
+            attrs! {At::Style => "color:red; margin:0"},
+            style! {St::Color => "blue", St::Padding => px(2)},
+            attrs! {At::Style => "padding:3px", At::Title => "t"},
+        ];
+        let el = match node {
+            Node::Element(el) => el,
+            _ => panic!("element expected"),
+        };
+        assert_eq!(
+            declarations(&el.style),
+            pairs(&[("color", "blue"), ("margin", "0"), ("padding", "3px")])
+        );
+        assert!(el.attrs.vals.get(&At::Style).is_none());
+        assert!(el.attrs.vals.get(&At::Title).is_some());
+    }
+}
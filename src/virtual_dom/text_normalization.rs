@@ -0,0 +1,280 @@
+//! Whitespace handling of text nodes. See `TextNormalization`.
+//!
+//! Text written across multiple lines in element macros contains the line breaks and indentation
+//! of the source code. `TextNormalization::Collapse` removes them with the same rules as JSX:
+//! - Whitespace adjacent to a line break is removed, together with the line break.
+//! - Lines are joined by a single space; lines containing only whitespace are dropped.
+//! - Other runs of whitespace are collapsed to a single space, so a space between inline
+//!   children (`"Hello ", b!["world"]`) is kept.
+//!
+//! Only ASCII whitespace is affected - non-breaking spaces (`\u{a0}`) are always kept.
+//!
+//! The app's default is set by `Builder::text_normalization` and it's applied both to rendered
+//! views and to the nodes taken over from the mount point (see `MountType::Takeover`),
+//! so server-rendered text compares equal to the text rendered by the app.
+//! Use `normalize_el` when rendering a vdom outside of the app - the string rendering
+//! (`node.to_string()`) applies modes set on elements by itself.
+
+use super::{El, Node, Tag};
+use std::borrow::Cow;
+
+/// How whitespace in text nodes is handled. The app's default is `Preserve`
+/// and it can be changed by `Builder::text_normalization`.
+///
+/// The value can be passed also to element macros to override the mode of the element's subtree.
+/// Elements `pre` and `textarea` use `Preserve` unless they are overridden explicitly.
+///
+/// # Example
+///
+/// ```rust,no_run
+///div![
+///    TextNormalization::Collapse,
+///    "Text written
+///     across multiple lines",
+///    p![preserve_whitespace(), model.poem],
+///]
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextNormalization {
+    /// Text is rendered as it is.
+    Preserve,
+    /// Line breaks with their surrounding indentation and runs of whitespace are collapsed.
+    Collapse,
+}
+
+impl Default for TextNormalization {
+    fn default() -> Self {
+        TextNormalization::Preserve
+    }
+}
+
+/// Keep whitespace of the element's text as it is, even if the app collapses it.
+/// Shortcut for `TextNormalization::Preserve`.
+pub fn preserve_whitespace() -> TextNormalization {
+    TextNormalization::Preserve
+}
+
+/// Apply the effective text normalization to `el` and its descendants.
+/// `inherited` is used for elements without their own `TextNormalization`.
+///
+/// Text nodes that become empty are removed.
+pub fn normalize_el<Ms>(el: &mut El<Ms>, inherited: TextNormalization) {
+    let mode = mode(el, inherited);
+
+    if mode == TextNormalization::Collapse {
+        for child in &mut el.children {
+            if let Node::Text(text) = child {
+                let collapsed = match collapse(&text.text) {
+                    Cow::Owned(collapsed) => Some(collapsed),
+                    Cow::Borrowed(_) => None,
+                };
+                if let Some(collapsed) = collapsed {
+                    text.text = Cow::Owned(collapsed);
+                }
+            }
+        }
+        // Text nodes with only formatting whitespace (e.g. between block elements) are empty now.
+        el.children.retain(|child| match child {
+            Node::Text(text) => !text.text.is_empty(),
+            _ => true,
+        });
+    }
+
+    for child in &mut el.children {
+        if let Node::Element(child_el) = child {
+            normalize_el(child_el, mode);
+        }
+    }
+}
+
+/// Mode of `el`'s children - its own, `Preserve` for `pre` and `textarea`, or the `inherited` one.
+pub(crate) fn mode<Ms>(el: &El<Ms>, inherited: TextNormalization) -> TextNormalization {
+    el.text_normalization.unwrap_or_else(|| match el.tag {
+        Tag::Pre | Tag::TextArea => TextNormalization::Preserve,
+        _ => inherited,
+    })
+}
+
+/// Collapse `text` by the `TextNormalization::Collapse` rules.
+/// Returns `Cow::Borrowed` when there is nothing to collapse.
+pub fn collapse(text: &str) -> Cow<str> {
+    let is_whitespace = |c: char| c.is_ascii_whitespace();
+    let collapsed_already = !text.contains('\n')
+        && !text.contains(|c: char| is_whitespace(c) && c != ' ')
+        && !text.contains("  ");
+    if collapsed_already {
+        return Cow::Borrowed(text);
+    }
+
+    let last_index = text.split('\n').count() - 1;
+    let mut collapsed = String::with_capacity(text.len());
+    for (index, line) in text.split('\n').enumerate() {
+        let mut line = line;
+        if index > 0 {
+            line = line.trim_start_matches(is_whitespace);
+        }
+        if index < last_index {
+            line = line.trim_end_matches(is_whitespace);
+        }
+        if line.is_empty() {
+            continue;
+        }
+        if !collapsed.is_empty() {
+            collapsed.push(' ');
+        }
+        let mut previous_whitespace = false;
+        for c in line.chars() {
+            if is_whitespace(c) {
+                if !previous_whitespace {
+                    collapsed.push(' ');
+                }
+                previous_whitespace = true;
+            } else {
+                collapsed.push(c);
+                previous_whitespace = false;
+            }
+        }
+    }
+    Cow::Owned(collapsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::virtual_dom::UpdateEl;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn texts(el: &El<()>) -> Vec<String> {
+        el.children
+            .iter()
+            .map(|child| match child {
+                Node::Text(text) => text.text.to_string(),
+                Node::Element(el) => format!("<{}>", el.tag.as_str()),
+                Node::Empty => String::new(),
+            })
+            .collect()
+    }
+
+    fn normalized(node: Node<()>, mode: TextNormalization) -> El<()> {
+        match node {
+            Node::Element(mut el) => {
+                normalize_el(&mut el, mode);
+                el
+            }
+            _ => panic!("view should be an element"),
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn collapse_text() {
+        let cases = vec![
+            ("Hello", "Hello"),
+            ("Hello world", "Hello world"),
+            // Spaces at the sides without a line break separate inline children.
+            ("Hello ", "Hello "),
+            (" world", " world"),
+            (" ", " "),
+            ("a  \t b", "a b"),
+            // Line breaks with indentation are replaced by a single space.
+            (
+                "Text written\n        across lines",
+                "Text written across lines",
+            ),
+            ("\n    Hello\n", "Hello"),
+            ("\n    \n", ""),
+            ("First\n\n    Second", "First Second"),
+            ("Hello \n world", "Hello world"),
+            // Non-breaking spaces are kept.
+            ("a\u{a0}\u{a0}b", "a\u{a0}\u{a0}b"),
+            ("\n\u{a0}Hello\u{a0}\n", "\u{a0}Hello\u{a0}"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(collapse(text), expected, "text: {:?}", text);
+        }
+        match collapse("Hello world") {
+            Cow::Borrowed(_) => (),
+            Cow::Owned(_) => panic!("collapsed text should be borrowed"),
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn mixed_inline_content() {
+        let view = || {
+            div![
+                "\n    Hello ",
+                b!["dear\n   reader"],
+                ", welcome\n    ",
+                span![],
+                "\n    ",
+                span![" "],
+                "\u{a0}",
+            ]
+        };
+
+        let preserved = normalized(view(), TextNormalization::Preserve);
+        assert_eq!(
+            texts(&preserved),
+            vec![
+                "\n    Hello ",
+                "<b>",
+                ", welcome\n    ",
+                "<span>",
+                "\n    ",
+                "<span>",
+                "\u{a0}"
+            ]
+        );
+        assert_eq!(
+            preserved.children[1].el().unwrap().get_text(),
+            "dear\n   reader"
+        );
+
+        let collapsed = normalized(view(), TextNormalization::Collapse);
+        assert_eq!(
+            texts(&collapsed),
+            vec!["Hello ", "<b>", ", welcome", "<span>", "<span>", "\u{a0}"]
+        );
+        assert_eq!(
+            collapsed.children[1].el().unwrap().get_text(),
+            "dear reader"
+        );
+        assert_eq!(collapsed.children[4].el().unwrap().get_text(), " ");
+    }
+
+    #[wasm_bindgen_test]
+    fn overrides() {
+        let view = || {
+            div![
+                "a\n  b",
+                pre!["a\n  b"],
+                textarea!["a\n  b"],
+                pre![TextNormalization::Collapse, "a\n  b"],
+                div![preserve_whitespace(), "a\n  b", span!["a\n  b"]],
+                div![TextNormalization::Collapse, "a\n  b"],
+            ]
+        };
+        let text_of = |el: &El<()>, index: usize| el.children[index].el().unwrap().get_text();
+
+        let collapsed = normalized(view(), TextNormalization::Collapse);
+        assert_eq!(collapsed.get_text(), "a b");
+        assert_eq!(text_of(&collapsed, 1), "a\n  b");
+        assert_eq!(text_of(&collapsed, 2), "a\n  b");
+        assert_eq!(text_of(&collapsed, 3), "a b");
+        assert_eq!(text_of(&collapsed, 4), "a\n  b");
+        // Descendants inherit the override.
+        assert_eq!(
+            collapsed.children[4].el().unwrap().children[1]
+                .el()
+                .unwrap()
+                .get_text(),
+            "a\n  b"
+        );
+        assert_eq!(text_of(&collapsed, 5), "a b");
+
+        let preserved = normalized(view(), TextNormalization::Preserve);
+        assert_eq!(preserved.get_text(), "a\n  b");
+        assert_eq!(text_of(&preserved, 5), "a b");
+    }
+}
@@ -1,5 +1,12 @@
-use super::{Attrs, El, Listener, Node, Style, Tag, Text};
-use crate::browser::dom::lifecycle_hooks::{DidMount, DidUpdate, WillUnmount};
+use super::{
+    At, AtValue, Attrs, El, ElKey, ElRef, InnerHtml, Listener, Node, RenderedBy, Style, Tag, Text,
+    TextNormalization,
+};
+use crate::browser::dom::{
+    dialog::OpenState,
+    flip::FlipGroup,
+    lifecycle_hooks::{DidMount, DidUpdate, WillUnmount},
+};
 
 /// `UpdateEl` is used to distinguish arguments in element-creation macros, and handle
 /// each type appropriately.
@@ -9,14 +16,18 @@ pub trait UpdateEl<T> {
 }
 
 impl<Ms> UpdateEl<El<Ms>> for Attrs {
-    fn update(self, el: &mut El<Ms>) {
+    fn update(mut self, el: &mut El<Ms>) {
+        // Raw styles are merged with `style!` per property - see `Style::parse`.
+        if let Some(AtValue::Some(style)) = self.vals.shift_remove(&At::Style) {
+            el.style.merge(Style::parse(&style));
+        }
         el.attrs.merge(self);
     }
 }
 
 impl<Ms> UpdateEl<El<Ms>> for &Attrs {
     fn update(self, el: &mut El<Ms>) {
-        el.attrs.merge(self.clone());
+        self.clone().update(el);
     }
 }
 
@@ -62,6 +73,48 @@ impl<Ms> UpdateEl<El<Ms>> for WillUnmount<Ms> {
     }
 }
 
+impl<Ms> UpdateEl<El<Ms>> for RenderedBy {
+    fn update(self, el: &mut El<Ms>) {
+        el.renderer = Some(self.0);
+    }
+}
+
+impl<Ms> UpdateEl<El<Ms>> for OpenState {
+    fn update(self, el: &mut El<Ms>) {
+        el.open_state = Some(self);
+    }
+}
+
+impl<Ms> UpdateEl<El<Ms>> for ElKey {
+    fn update(self, el: &mut El<Ms>) {
+        el.key = Some(self.0);
+    }
+}
+
+impl<Ms> UpdateEl<El<Ms>> for InnerHtml {
+    fn update(self, el: &mut El<Ms>) {
+        el.inner_html = Some(self.0);
+    }
+}
+
+impl<Ms> UpdateEl<El<Ms>> for FlipGroup {
+    fn update(self, el: &mut El<Ms>) {
+        el.flip = Some(self);
+    }
+}
+
+impl<Ms, E> UpdateEl<El<Ms>> for ElRef<E> {
+    fn update(self, el: &mut El<Ms>) {
+        el.el_ref = Some(self.shared_node_ws);
+    }
+}
+
+impl<Ms> UpdateEl<El<Ms>> for TextNormalization {
+    fn update(self, el: &mut El<Ms>) {
+        el.text_normalization = Some(self);
+    }
+}
+
 impl<Ms> UpdateEl<El<Ms>> for &str {
     // This, or some other mechanism seems to work for String too... note sure why.
     fn update(self, el: &mut El<Ms>) {
@@ -0,0 +1,125 @@
+//! Structural equality of vdom trees - the render is skipped when the new vdom equals the old one.
+//!
+//! Unlike `PartialEq` of `El` (which compares only the element itself, for patching),
+//! the comparison is deep. It ignores rendered `web_sys` nodes and event handler closures -
+//! they can't be compared. Handler identity is assumed to be stable within a render:
+//! listeners with the same triggers are equal and the old handlers are kept.
+//! Elements with lifecycle hooks are never equal, so `did_update` runs on each render.
+
+use super::{El, Listener, Node};
+use std::rc::Rc;
+
+/// `new` nodes (before patching) render the same DOM as `old` nodes.
+pub(crate) fn same_nodes<Ms>(old: &[Node<Ms>], new: &[Node<Ms>]) -> bool {
+    old.len() == new.len() && old.iter().zip(new).all(|(old, new)| same_node(old, new))
+}
+
+fn same_node<Ms>(old: &Node<Ms>, new: &Node<Ms>) -> bool {
+    match (old, new) {
+        (Node::Element(old), Node::Element(new)) => same_el(old, new),
+        (Node::Text(old), Node::Text(new)) => old == new,
+        (Node::Empty, Node::Empty) => true,
+        _ => false,
+    }
+}
+
+fn same_el<Ms>(old: &El<Ms>, new: &El<Ms>) -> bool {
+    // Nodes of unchanged view regions (see `AppBuilder::view_with_slices`) are reused -
+    // they are equal only to their copies.
+    if new.node_ws.is_some() {
+        return new.node_ws == old.node_ws;
+    }
+    let has_hooks = |el: &El<Ms>| {
+        el.hooks.did_mount.is_some()
+            || el.hooks.did_update.is_some()
+            || el.hooks.will_unmount.is_some()
+    };
+    #[cfg(all(feature = "debug-tools", debug_assertions))]
+    {
+        if old.source != new.source {
+            return false;
+        }
+    }
+    !has_hooks(old)
+        && !has_hooks(new)
+        && old.tag == new.tag
+        && old.namespace == new.namespace
+        && old.attrs == new.attrs
+        && old.style == new.style
+        && same_listeners(&old.listeners, &new.listeners)
+        && old.keep_alive == new.keep_alive
+        && old.renderer == new.renderer
+        && old.lazy_hydration == new.lazy_hydration
+        // The active state is resolved into attributes.
+        && old.active_link.is_some() == new.active_link.is_some()
+        && old.open_state == new.open_state
+        && old.text_normalization == new.text_normalization
+        && old.key == new.key
+        && match (&old.el_ref, &new.el_ref) {
+            (Some(old), Some(new)) => Rc::ptr_eq(old, new),
+            (None, None) => true,
+            _ => false,
+        }
+        && old.flip == new.flip
+        && old.inner_html == new.inner_html
+        && same_nodes(&old.children, &new.children)
+}
+
+fn same_listeners<Ms>(old: &[Listener<Ms>], new: &[Listener<Ms>]) -> bool {
+    old.len() == new.len()
+        && old.iter().zip(new).all(|(old, new)| {
+            old == new
+                && old.control_val == new.control_val
+                && old.control_checked == new.control_checked
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as seed;
+    use crate::prelude::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[derive(Clone)]
+    enum Msg {
+        Clicked,
+    }
+
+    fn view(title: &str, children: usize) -> Vec<Node<Msg>> {
+        vec![
+            div![
+                class!["list"],
+                attrs! {At::Title => title},
+                (0..children).map(|index| li![index.to_string()]),
+                button![ev(Ev::Click, |_| Msg::Clicked), "Click"],
+            ],
+            seed::empty(),
+            Node::new_text("end"),
+        ]
+    }
+
+    #[wasm_bindgen_test]
+    fn nodes_compared_deeply() {
+        assert!(same_nodes(&view("a", 3), &view("a", 3)));
+        assert!(!same_nodes(&view("a", 3), &view("b", 3)));
+        assert!(!same_nodes(&view("a", 3), &view("a", 4)));
+
+        let mut with_text = view("a", 3);
+        with_text.push(Node::new_text("more"));
+        assert!(!same_nodes(&view("a", 3), &with_text));
+
+        let without_listener: Vec<Node<Msg>> = vec![div![button!["Click"]]];
+        let with_listener: Vec<Node<Msg>> =
+            vec![div![button![ev(Ev::Click, |_| Msg::Clicked), "Click"]]];
+        assert!(!same_nodes(&without_listener, &with_listener));
+    }
+
+    #[wasm_bindgen_test]
+    fn elements_with_hooks_differ() {
+        let view = || -> Vec<Node<Msg>> { vec![div![did_update(|_| {})]] };
+        assert!(!same_nodes(&view(), &view()));
+    }
+}